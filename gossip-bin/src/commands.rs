@@ -1,4 +1,5 @@
-use gossip_lib::{Error, ErrorKind, PersonList, PersonListMetadata, GLOBALS};
+use gossip_lib::kind_registry::RenderHint;
+use gossip_lib::{Error, ErrorKind, PersonList, PersonListMetadata, Profile, Storage, GLOBALS};
 use nostr_types::{
     EncryptedPrivateKey, Event, EventAddr, EventKind, Filter, Id, NostrBech32, NostrUrl, PreEvent,
     PrivateKey, PublicKey, RelayUrl, Tag, UncheckedUrl, Unixtime,
@@ -25,7 +26,7 @@ impl Command {
     }
 }
 
-const COMMANDS: [Command; 34] = [
+const COMMANDS: [Command; 49] = [
     Command {
         cmd: "oneshot",
         usage_params: "{depends}",
@@ -41,6 +42,11 @@ const COMMANDS: [Command; 34] = [
         usage_params: "",
         desc: "backdate last_general_eose_at by 24 hours for every relay",
     },
+    Command {
+        cmd: "backup",
+        usage_params: "<path>",
+        desc: "Write a backup of the whole database to <path>, safe while gossip runs",
+    },
     Command {
         cmd: "bech32_decode",
         usage_params: "<bech32string>",
@@ -86,6 +92,21 @@ const COMMANDS: [Command; 34] = [
         usage_params: "",
         desc: "Export the encrypted private key",
     },
+    Command {
+        cmd: "export_events_jsonl",
+        usage_params: "<path> [<filter_json>]",
+        desc: "Write the stored events matching the filter (default all) to <path>, one JSON event per line",
+    },
+    Command {
+        cmd: "export_identity_bundle",
+        usage_params: "<path>",
+        desc: "Write your key, relays, lists, settings and petnames to an encrypted file",
+    },
+    Command {
+        cmd: "export_thread",
+        usage_params: "<idhex> <path>",
+        desc: "Archive every stored event of the thread containing the event to <path>.json (events as signed, with the relays they were seen on) and <path>.txt (a readable transcript)",
+    },
     Command {
         cmd: "giftwrap_ids",
         usage_params: "",
@@ -106,6 +127,16 @@ const COMMANDS: [Command; 34] = [
         usage_params: "<event_json>",
         desc: "import and process a JSON event",
     },
+    Command {
+        cmd: "import_events_jsonl",
+        usage_params: "<path>",
+        desc: "Store the events in a file of one JSON event per line, skipping ones we have",
+    },
+    Command {
+        cmd: "import_identity_bundle",
+        usage_params: "<path>",
+        desc: "Set up this install from a file written by export_identity_bundle",
+    },
     Command {
         cmd: "login",
         usage_params: "",
@@ -126,10 +157,15 @@ const COMMANDS: [Command; 34] = [
         usage_params: "",
         desc: "print every pubkey that is followed",
     },
+    Command {
+        cmd: "print_kinds",
+        usage_params: "",
+        desc: "print every event kind gossip has a name for",
+    },
     Command {
         cmd: "print_muted",
         usage_params: "",
-        desc: "print every pubkey that is muted",
+        desc: "print every pubkey that is muted, with when and why",
     },
     Command {
         cmd: "print_person_lists",
@@ -146,11 +182,21 @@ const COMMANDS: [Command; 34] = [
         usage_params: "<pubkeyhex>",
         desc: "print all the person-relay records for the given person",
     },
+    Command {
+        cmd: "print_profiles",
+        usage_params: "",
+        desc: "print the named profiles (start gossip with GOSSIP_PROFILE=<name> to use one)",
+    },
     Command {
         cmd: "print_relay",
         usage_params: "<url>",
         desc: "print the relay record",
     },
+    Command {
+        cmd: "print_relay_groups",
+        usage_params: "",
+        desc: "print all relay groups, primary first",
+    },
     Command {
         cmd: "print_relays",
         usage_params: "",
@@ -166,6 +212,16 @@ const COMMANDS: [Command; 34] = [
         usage_params: "",
         desc: "Rebuild all event-related indices",
     },
+    Command {
+        cmd: "register_kind",
+        usage_params: "<kind> <name> [text | longform | summary]",
+        desc: "name an event kind and say how to show its events (default summary)",
+    },
+    Command {
+        cmd: "relay_monitors",
+        usage_params: "[add | remove <pubkeyHexOrBech32>]",
+        desc: "list, add or remove the relay monitors (NIP-66) whose reports we trust",
+    },
     Command {
         cmd: "rename_person_list",
         usage_params: "<number> <newname>",
@@ -176,16 +232,36 @@ const COMMANDS: [Command; 34] = [
         usage_params: "",
         desc: "Reprocess events that came during the last 24 hours",
     },
+    Command {
+        cmd: "restore_backup",
+        usage_params: "<path>",
+        desc: "Restore a backup onto a fresh profile (e.g. set GOSSIP_PROFILE)",
+    },
+    Command {
+        cmd: "set_relay_group",
+        usage_params: "<name> [<url> ...]",
+        desc: "Set a group of relays that fail over to each other, primary first (no urls removes it)",
+    },
     Command {
         cmd: "ungiftwrap",
         usage_params: "<idhex>",
         desc: "Unwrap the giftwrap event with the given ID and print the rumor (in JSON)",
     },
+    Command {
+        cmd: "unregister_kind",
+        usage_params: "<kind>",
+        desc: "forget a kind registered with register_kind",
+    },
     Command {
         cmd: "verify",
         usage_params: "<idhex>",
         desc: "Verify if the given event signature is valid",
     },
+    Command {
+        cmd: "verify_db",
+        usage_params: "[repair]",
+        desc: "Check every table of the database for corrupt records and orphaned index entries, and with 'repair' quarantine or delete them",
+    },
     Command {
         cmd: "verify_json",
         usage_params: "<event_json>",
@@ -218,6 +294,7 @@ pub fn handle_command(mut args: env::Args, runtime: &Runtime) -> Result<bool, Er
         "oneshot" => oneshot(command, args)?,
         "add_person_list" => add_person_list(command, args)?,
         "backdate_eose" => backdate_eose()?,
+        "backup" => backup(command, args)?,
         "bech32_decode" => bech32_decode(command, args)?,
         "bech32_encode_event_addr" => bech32_encode_event_addr(command, args)?,
         "decrypt" => decrypt(command, args)?,
@@ -227,10 +304,15 @@ pub fn handle_command(mut args: env::Args, runtime: &Runtime) -> Result<bool, Er
         "events_of_kind" => events_of_kind(command, args)?,
         "events_of_pubkey_and_kind" => events_of_pubkey_and_kind(command, args)?,
         "export_encrypted_key" => export_encrypted_key()?,
+        "export_events_jsonl" => export_events_jsonl(command, args)?,
+        "export_identity_bundle" => export_identity_bundle(command, args)?,
+        "export_thread" => export_thread(command, args)?,
         "giftwrap_ids" => giftwrap_ids(command)?,
         "help" => help(command, args)?,
         "import_encrypted_private_key" => import_encrypted_private_key(command, args)?,
         "import_event" => import_event(command, args, runtime)?,
+        "import_events_jsonl" => import_events_jsonl(command, args)?,
+        "import_identity_bundle" => import_identity_bundle(command, args)?,
         "login" => {
             login()?;
             return Ok(false);
@@ -241,18 +323,26 @@ pub fn handle_command(mut args: env::Args, runtime: &Runtime) -> Result<bool, Er
         }
         "print_event" => print_event(command, args)?,
         "print_followed" => print_followed(command)?,
+        "print_kinds" => print_kinds()?,
         "print_muted" => print_muted(command)?,
         "print_person_lists" => print_person_lists(command)?,
         "print_person" => print_person(command, args)?,
         "print_person_relays" => print_person_relays(command, args)?,
+        "print_profiles" => print_profiles()?,
         "print_relay" => print_relay(command, args)?,
+        "print_relay_groups" => print_relay_groups()?,
         "print_relays" => print_relays(command)?,
         "print_seen_on" => print_seen_on(command, args)?,
         "rebuild_indices" => rebuild_indices()?,
+        "register_kind" => register_kind(command, args)?,
+        "relay_monitors" => relay_monitors(command, args)?,
         "rename_person_list" => rename_person_list(command, args)?,
         "reprocess_recent" => reprocess_recent(command, runtime)?,
+        "set_relay_group" => set_relay_group(command, args)?,
         "ungiftwrap" => ungiftwrap(command, args)?,
+        "unregister_kind" => unregister_kind(command, args)?,
         "verify" => verify(command, args)?,
+        "verify_db" => verify_db(command, args)?,
         "verify_json" => verify_json(command, args)?,
         "wgpu_renderer" => wgpu_renderer(command, args)?,
         other => println!("Unknown command {}", other),
@@ -318,6 +408,45 @@ pub fn backdate_eose() -> Result<(), Error> {
     Ok(())
 }
 
+pub fn backup(cmd: Command, mut args: env::Args) -> Result<(), Error> {
+    let path = match args.next() {
+        Some(path) => path,
+        None => return cmd.usage("Missing path parameter".to_string()),
+    };
+
+    let info = GLOBALS.storage.backup(std::path::Path::new(&path))?;
+    println!(
+        "Backed up {} bytes at migration level {} to {}",
+        info.size, info.migration_level, path
+    );
+    Ok(())
+}
+
+/// Restoring has to happen before the database is opened, so main calls this
+/// before the lib is initialized.
+pub fn restore_backup(mut args: env::Args) -> Result<(), Error> {
+    let _ = args.next(); // program name
+    let _ = args.next(); // command name
+    let cmd = COMMANDS
+        .iter()
+        .find(|c| c.cmd == "restore_backup")
+        .unwrap()
+        .to_owned();
+    let path = match args.next() {
+        Some(path) => path,
+        None => return cmd.usage("Missing path parameter".to_string()),
+    };
+
+    let lmdb_dir = Profile::current()?.lmdb_dir;
+    let info = Storage::restore(std::path::Path::new(&path), &lmdb_dir)?;
+    println!(
+        "Restored a backup from {} into {}",
+        info.created_at.0,
+        lmdb_dir.display()
+    );
+    Ok(())
+}
+
 pub fn bech32_decode(cmd: Command, mut args: env::Args) -> Result<(), Error> {
     let mut param = match args.next() {
         Some(s) => s,
@@ -444,7 +573,7 @@ pub fn decrypt(cmd: Command, mut args: env::Args) -> Result<(), Error> {
 
     login()?;
 
-    let plaintext = GLOBALS.identity.decrypt(&pubkey, &ciphertext)?;
+    let plaintext = GLOBALS.identity.decrypt_locally(&pubkey, &ciphertext)?;
     println!("{}", plaintext);
 
     Ok(())
@@ -493,7 +622,7 @@ pub fn delete_spam_by_content(
     for event in events {
         let mut matches = false;
         if kind == EventKind::GiftWrap {
-            if let Ok(rumor) = GLOBALS.identity.unwrap_giftwrap(&event) {
+            if let Ok(rumor) = GLOBALS.identity.unwrap_giftwrap_kept(&event) {
                 if rumor.content.contains(&substring) {
                     matches = true;
                 }
@@ -519,7 +648,7 @@ pub fn delete_spam_by_content(
     let mut txn = GLOBALS.storage.get_write_txn()?;
     for id in &target_ids {
         // Delete locally
-        GLOBALS.storage.delete_event(*id, Some(&mut txn))?;
+        GLOBALS.storage.delete_event(*id, Some(&mut *txn))?;
 
         // NOTE: we cannot add a delete relationship; we can't delete
         // other people's events.
@@ -561,7 +690,7 @@ pub fn delete_spam_by_content(
             content: "spam".to_owned(),
         };
         // Should we add a pow? Maybe the relay needs it.
-        GLOBALS.identity.sign_event(pre_event)?
+        GLOBALS.identity.sign_event_locally(pre_event)?
     };
     println!("{}", serde_json::to_string(&event).unwrap());
 
@@ -634,6 +763,44 @@ pub fn import_encrypted_private_key(cmd: Command, mut args: env::Args) -> Result
     Ok(())
 }
 
+pub fn import_events_jsonl(cmd: Command, mut args: env::Args) -> Result<(), Error> {
+    let path = match args.next() {
+        Some(path) => path,
+        None => return cmd.usage("Missing path parameter".to_string()),
+    };
+
+    let file = std::fs::File::open(&path)?;
+    let import = GLOBALS
+        .storage
+        .import_events_jsonl(std::io::BufReader::new(file))?;
+
+    println!(
+        "Imported {} events ({} we already had, {} replaced by newer ones, {} invalid)",
+        import.imported, import.duplicates, import.superseded, import.invalid
+    );
+    Ok(())
+}
+
+pub fn import_identity_bundle(cmd: Command, mut args: env::Args) -> Result<(), Error> {
+    let path = match args.next() {
+        Some(path) => path,
+        None => return cmd.usage("Missing path parameter".to_string()),
+    };
+
+    let text = std::fs::read_to_string(&path)?;
+
+    let mut password = rpassword::prompt_password("Password: ").unwrap();
+    let result = gossip_lib::identity_bundle::import_identity_bundle(&text, &password);
+    password.zeroize();
+    let import = result?;
+
+    println!(
+        "Imported {} relays, {} lists ({} people) and {} settings.",
+        import.relays, import.lists, import.people, import.settings
+    );
+    Ok(())
+}
+
 pub fn import_event(cmd: Command, mut args: env::Args, runtime: &Runtime) -> Result<(), Error> {
     let event = match args.next() {
         Some(json) => {
@@ -675,6 +842,19 @@ pub fn print_event(cmd: Command, mut args: env::Args) -> Result<(), Error> {
     Ok(())
 }
 
+pub fn print_profiles() -> Result<(), Error> {
+    let current = Profile::current()?.name;
+    for name in Profile::list()? {
+        let marker = if current.as_deref() == Some(name.as_str()) {
+            " (current)"
+        } else {
+            ""
+        };
+        println!("{}{}", name, marker);
+    }
+    Ok(())
+}
+
 pub fn print_relay(cmd: Command, mut args: env::Args) -> Result<(), Error> {
     if let Some(url) = args.next() {
         let rurl = RelayUrl::try_from_str(&url)?;
@@ -689,6 +869,14 @@ pub fn print_relay(cmd: Command, mut args: env::Args) -> Result<(), Error> {
     }
 }
 
+pub fn print_relay_groups() -> Result<(), Error> {
+    for group in GLOBALS.storage.read_all_relay_groups()? {
+        let members: Vec<&str> = group.members.iter().map(|u| u.as_str()).collect();
+        println!("{}: {}", group.name, members.join(" "));
+    }
+    Ok(())
+}
+
 pub fn print_relays(_cmd: Command) -> Result<(), Error> {
     let relays = GLOBALS.storage.filter_relays(|_| true)?;
     for relay in &relays {
@@ -731,17 +919,77 @@ pub fn print_followed(_cmd: Command) -> Result<(), Error> {
 }
 
 pub fn print_muted(_cmd: Command) -> Result<(), Error> {
-    let members = GLOBALS.storage.get_people_in_list(PersonList::Muted)?;
-    for (pk, private) in &members {
+    for muted in GLOBALS.people.muted_report()? {
+        let since = match muted.muted_at {
+            Some(at) => format!("since {}", at.0),
+            None => "since unknown".to_owned(),
+        };
+        let until = match muted.until {
+            Some(until) => format!(" until {}", until.0),
+            None => "".to_owned(),
+        };
+        let event = match muted.event {
+            Some(id) => format!(" (event {})", id.as_hex_string()),
+            None => "".to_owned(),
+        };
+        println!(
+            "{} {} {}{}: {}{}",
+            if *muted.private { "prv" } else { "pub" },
+            muted.pubkey.as_hex_string(),
+            since,
+            until,
+            muted.reason.as_deref().unwrap_or("no reason given"),
+            event
+        );
+    }
+    Ok(())
+}
+
+pub fn print_kinds() -> Result<(), Error> {
+    for desc in GLOBALS.kinds.all() {
+        let hint = format!("{:?}", desc.hint).to_lowercase();
         println!(
-            "{} {}",
-            if **private { "prv" } else { "pub" },
-            pk.as_hex_string()
+            "{:>6} {:<12} {:<9} {}{}",
+            desc.kind,
+            desc.category().as_str(),
+            hint,
+            desc.name,
+            if desc.builtin { "" } else { " (registered)" }
         );
     }
     Ok(())
 }
 
+pub fn register_kind(cmd: Command, mut args: env::Args) -> Result<(), Error> {
+    let kind = match args.next() {
+        Some(integer) => integer.parse::<u32>()?,
+        None => return cmd.usage("Missing kind parameter".to_string()),
+    };
+
+    let name = match args.next() {
+        Some(name) => name,
+        None => return cmd.usage("Missing name parameter".to_string()),
+    };
+
+    let hint = match args.next().as_deref() {
+        None | Some("summary") => RenderHint::Summary,
+        Some("text") => RenderHint::Text,
+        Some("longform") => RenderHint::LongForm,
+        Some(other) => return cmd.usage(format!("Unknown rendering hint {}", other)),
+    };
+
+    GLOBALS.kinds.register(kind, &name, hint)
+}
+
+pub fn unregister_kind(cmd: Command, mut args: env::Args) -> Result<(), Error> {
+    let kind = match args.next() {
+        Some(integer) => integer.parse::<u32>()?,
+        None => return cmd.usage("Missing kind parameter".to_string()),
+    };
+
+    GLOBALS.kinds.unregister(kind)
+}
+
 pub fn print_person_lists(_cmd: Command) -> Result<(), Error> {
     let all = GLOBALS.storage.get_all_person_list_metadata()?;
     for (list, metadata) in all.iter() {
@@ -845,6 +1093,94 @@ pub fn export_encrypted_key() -> Result<(), Error> {
     Ok(())
 }
 
+pub fn export_events_jsonl(cmd: Command, mut args: env::Args) -> Result<(), Error> {
+    let path = match args.next() {
+        Some(path) => path,
+        None => return cmd.usage("Missing path parameter".to_string()),
+    };
+    let filter: Filter = match args.next() {
+        Some(json) => serde_json::from_str(&json)?,
+        None => Filter::new(),
+    };
+
+    let file = std::fs::File::create(&path)?;
+    let count = GLOBALS
+        .storage
+        .export_events_jsonl(std::io::BufWriter::new(file), &filter)?;
+
+    println!("Exported {} events to {}", count, path);
+    Ok(())
+}
+
+pub fn export_identity_bundle(cmd: Command, mut args: env::Args) -> Result<(), Error> {
+    let path = match args.next() {
+        Some(path) => path,
+        None => return cmd.usage("Missing path parameter".to_string()),
+    };
+
+    login()?;
+
+    let bundle = gossip_lib::identity_bundle::export_identity_bundle()?;
+    std::fs::write(&path, bundle)?;
+
+    println!("Written to {}", path);
+    Ok(())
+}
+
+pub fn export_thread(cmd: Command, mut args: env::Args) -> Result<(), Error> {
+    let idstr = match args.next() {
+        Some(id) => id,
+        None => return cmd.usage("Missing idhex parameter".to_string()),
+    };
+    let id = Id::try_from_hex_string(&idstr)?;
+    let path = match args.next() {
+        Some(path) => path,
+        None => return cmd.usage("Missing path parameter".to_string()),
+    };
+
+    let archive = gossip_lib::thread_archive::archive_thread(id)?;
+    if archive.events.is_empty() {
+        println!("Event not found");
+        return Ok(());
+    }
+
+    std::fs::write(format!("{}.json", path), archive.to_json()?)?;
+    std::fs::write(format!("{}.txt", path), archive.transcript())?;
+
+    let unverified = archive.events.iter().filter(|a| !a.verified).count();
+    println!(
+        "Archived {} events to {}.json and {}.txt",
+        archive.events.len(),
+        path,
+        path
+    );
+    if unverified > 0 {
+        println!("{} of them do not verify", unverified);
+    }
+    Ok(())
+}
+
+pub fn set_relay_group(cmd: Command, mut args: env::Args) -> Result<(), Error> {
+    let name = match args.next() {
+        Some(name) => name,
+        None => return cmd.usage("Missing name parameter".to_string()),
+    };
+    let mut members: Vec<RelayUrl> = Vec::new();
+    for url in args {
+        members.push(RelayUrl::try_from_str(&url)?);
+    }
+
+    let removed = members.is_empty();
+    gossip_lib::relay_group::set_relay_group(&name, members)?;
+
+    if removed {
+        println!("Relay group {} removed", name);
+    } else {
+        println!("Relay group {} saved", name);
+    }
+    Ok(())
+}
+
 pub fn ungiftwrap(cmd: Command, mut args: env::Args) -> Result<(), Error> {
     let idstr = match args.next() {
         Some(id) => id,
@@ -866,7 +1202,7 @@ pub fn ungiftwrap(cmd: Command, mut args: env::Args) -> Result<(), Error> {
 
     login()?;
 
-    let rumor = GLOBALS.identity.unwrap_giftwrap(&event)?;
+    let rumor = GLOBALS.identity.unwrap_giftwrap_kept(&event)?;
 
     println!("{}", serde_json::to_string(&rumor)?);
 
@@ -942,6 +1278,21 @@ pub fn verify(cmd: Command, mut args: env::Args) -> Result<(), Error> {
     Ok(())
 }
 
+pub fn verify_db(cmd: Command, mut args: env::Args) -> Result<(), Error> {
+    let repair = match args.next().as_deref() {
+        None => false,
+        Some("repair") => true,
+        Some(other) => return cmd.usage(format!("Unknown parameter {}", other)),
+    };
+
+    let report = GLOBALS.storage.verify(repair)?;
+    println!("{}", report);
+    if !repair && report.problems() > 0 {
+        println!("Run 'gossip verify_db repair' to repair them");
+    }
+    Ok(())
+}
+
 pub fn verify_json(cmd: Command, mut args: env::Args) -> Result<(), Error> {
     let json = match args.next() {
         Some(json) => json,
@@ -958,7 +1309,35 @@ pub fn verify_json(cmd: Command, mut args: env::Args) -> Result<(), Error> {
 pub fn rebuild_indices() -> Result<(), Error> {
     println!("Login required in order to reindex DMs and GiftWraps");
     login()?;
-    GLOBALS.storage.rebuild_event_indices(None)?;
+    GLOBALS.storage.rebuild_event_indices()?;
+
+    Ok(())
+}
+
+pub fn relay_monitors(cmd: Command, mut args: env::Args) -> Result<(), Error> {
+    let action = match args.next() {
+        Some(action) => action,
+        None => {
+            for pubkey in gossip_lib::relay_monitor::monitors() {
+                println!("{}", pubkey.as_bech32_string());
+            }
+            return Ok(());
+        }
+    };
+
+    let pubkey = match args.next() {
+        Some(s) => match PublicKey::try_from_hex_string(&s, true) {
+            Ok(pk) => pk,
+            Err(_) => PublicKey::try_from_bech32_string(&s, true)?,
+        },
+        None => return cmd.usage("Missing pubkeyHexOrBech32 parameter".to_string()),
+    };
+
+    match action.as_str() {
+        "add" => gossip_lib::relay_monitor::add_monitor(pubkey)?,
+        "remove" => gossip_lib::relay_monitor::remove_monitor(pubkey)?,
+        _ => return cmd.usage(format!("Unknown action {}", action)),
+    }
 
     Ok(())
 }
@@ -0,0 +1,44 @@
+use gossip_lib::logging::{self, LogLine};
+use nostr_types::Unixtime;
+use std::fmt::Write;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+/// Copies every log event that passes the filter into the lib's log buffer,
+/// for the in-app log view
+pub struct LogBufferLayer;
+
+impl<S: Subscriber> Layer<S> for LogBufferLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let mut visitor = MessageVisitor(String::new());
+        event.record(&mut visitor);
+        logging::push(LogLine {
+            time: Unixtime::now().unwrap(),
+            level: *metadata.level(),
+            target: metadata.target().to_owned(),
+            message: visitor.0,
+        });
+    }
+}
+
+// The message first, then any other fields as name=value
+struct MessageVisitor(String);
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            if self.0.is_empty() {
+                let _ = write!(self.0, "{:?}", value);
+            } else {
+                self.0 = format!("{:?} {}", value, self.0);
+            }
+        } else {
+            if !self.0.is_empty() {
+                self.0.push(' ');
+            }
+            let _ = write!(self.0, "{}={:?}", field.name(), value);
+        }
+    }
+}
@@ -7,6 +7,7 @@
 mod about;
 mod commands;
 mod date_ago;
+mod log_layer;
 mod ui;
 mod unsaved_settings;
 
@@ -14,6 +15,9 @@ use gossip_lib::{Error, RunState, GLOBALS};
 use std::sync::atomic::Ordering;
 use std::{env, thread};
 use tracing_subscriber::filter::{EnvFilter, LevelFilter};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload};
 
 pub const AVATAR_SIZE: u32 = 48; // points, not pixels
 pub const AVATAR_SIZE_F32: f32 = 48.0; // points, not pixels
@@ -30,18 +34,68 @@ fn main() -> Result<(), Error> {
         None => LevelFilter::ERROR,
     };
     let show_debug = cfg!(debug_assertions) || max_level <= LevelFilter::DEBUG;
-    tracing_subscriber::fmt::fmt()
-        .with_target(false)
-        .with_file(show_debug)
-        .with_line_number(show_debug)
-        .with_env_filter(env_filter)
+    // The filter can be changed while running (see gossip_lib::logging); the
+    // directives saved in settings are added to RUST_LOG.
+    let base_filter = env::var("RUST_LOG").unwrap_or_default();
+    let (env_filter, filter_handle) = reload::Layer::new(env_filter);
+    tracing_subscriber::registry()
+        .with(env_filter)
+        .with(
+            fmt::layer()
+                .with_target(false)
+                .with_file(show_debug)
+                .with_line_number(show_debug),
+        )
+        .with(log_layer::LogBufferLayer)
         .init();
 
     let about = about::About::new();
     println!("Gossip {}", about.version);
 
-    // Initialize the lib
-    gossip_lib::init()?;
+    // A backup is restored before the database is opened. Scripts need to know if
+    // it failed, so that exits with an error status.
+    if env::args().nth(1).as_deref() == Some("restore_backup") {
+        if let Err(e) = commands::restore_backup(env::args()) {
+            println!("{}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Initialize the lib, showing the progress of any long storage migration
+    let init_done = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let progress_thread = {
+        let init_done = init_done.clone();
+        thread::spawn(move || {
+            let mut progress = GLOBALS.storage.migration_progress();
+            while !init_done.load(Ordering::Relaxed) {
+                if progress.has_changed().unwrap_or(false) {
+                    if let Some(p) = &*progress.borrow_and_update() {
+                        println!("{}", p);
+                    }
+                }
+                thread::sleep(std::time::Duration::from_millis(500));
+            }
+        })
+    };
+    let init_result = gossip_lib::init();
+    init_done.store(true, Ordering::Relaxed);
+    let _ = progress_thread.join();
+    init_result?;
+
+    // Apply the saved log filter
+    gossip_lib::logging::set_filter_reloader(Box::new(move |directives: &str| {
+        let filter = if directives.is_empty() {
+            EnvFilter::try_new(&base_filter)
+        } else {
+            EnvFilter::try_new(format!("{},{}", base_filter, directives))
+        }
+        .map_err(|e| e.to_string())?;
+        filter_handle.reload(filter).map_err(|e| e.to_string())
+    }));
+    if let Err(e) = gossip_lib::logging::apply_saved_filter() {
+        tracing::error!("{}", e);
+    }
 
     // Setup async
     // We create and enter the runtime on the main thread so that
@@ -0,0 +1,101 @@
+use super::{widgets, GossipUi, Page};
+use eframe::egui;
+use egui::{Context, RichText, Ui};
+use gossip_lib::names::best_name_from_pubkey_lookup;
+use gossip_lib::{FeedKind, GLOBALS};
+use nostr_types::{EventKind, NostrBech32};
+
+pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        ui.heading("Communities");
+    });
+    ui.label("Moderated communities (NIP-72). Anyone may post to one, but only the posts its moderators approve are shown.");
+    ui.add_space(10.0);
+
+    ui.horizontal(|ui| {
+        ui.label("Community:");
+        ui.add(
+            text_edit_line!(app, app.communities_new)
+                .hint_text("naddr1...")
+                .desired_width(400.0),
+        );
+        if ui.button("Join").clicked() {
+            let input = app.communities_new.trim();
+            let input = input.strip_prefix("nostr:").unwrap_or(input);
+            match NostrBech32::try_from_string(input) {
+                Some(NostrBech32::EventAddr(ea)) if ea.kind == EventKind::CommunityDefinition => {
+                    match gossip_lib::communities::set_joined(ea.author, &ea.d, true) {
+                        Ok(()) => {
+                            app.communities_new.clear();
+                            app.set_page(ctx, Page::Feed(FeedKind::Community(ea.author, ea.d)));
+                        }
+                        Err(e) => GLOBALS.status_queue.write().write(format!("{}", e)),
+                    }
+                }
+                _ => GLOBALS
+                    .status_queue
+                    .write()
+                    .write("That is not the naddr of a community.".to_owned()),
+            }
+        }
+    });
+
+    ui.add_space(10.0);
+    ui.separator();
+
+    let communities = match gossip_lib::communities::joined_communities() {
+        Ok(communities) => communities,
+        Err(e) => {
+            tracing::error!("{}", e);
+            vec![]
+        }
+    };
+
+    if communities.is_empty() {
+        ui.add_space(10.0);
+        ui.label("You have not joined any communities.");
+        return;
+    }
+
+    let mut open = None;
+    app.vert_scroll_area()
+        .id_source("communities")
+        .show(ui, |ui| {
+            for community in communities.iter() {
+                widgets::list_entry::make_frame(ui, Some(app.theme.main_content_bgcolor())).show(
+                    ui,
+                    |ui| {
+                        ui.set_min_width(ui.available_width());
+                        ui.horizontal(|ui| {
+                            if ui
+                                .link(RichText::new(community.display_name()).heading())
+                                .clicked()
+                            {
+                                open = Some(Page::Feed(FeedKind::Community(
+                                    community.author,
+                                    community.d.clone(),
+                                )));
+                            }
+                            ui.label(
+                                RichText::new(format!(
+                                    "by {}",
+                                    best_name_from_pubkey_lookup(&community.author)
+                                ))
+                                .small()
+                                .weak(),
+                            );
+                        });
+                        if !community.description.is_empty() {
+                            ui.label(&community.description);
+                        }
+                    },
+                );
+                ui.add_space(4.0);
+            }
+        });
+
+    if let Some(page) = open {
+        app.set_page(ctx, page);
+    }
+}
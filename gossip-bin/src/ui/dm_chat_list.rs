@@ -3,8 +3,9 @@ use eframe::egui;
 use eframe::egui::vec2;
 use eframe::egui::Rect;
 use egui::{Context, Label, RichText, Ui};
-use gossip_lib::FeedKind;
+use gossip_lib::dm_policy;
 use gossip_lib::GLOBALS;
+use gossip_lib::{DmChannelData, FeedKind};
 use gossip_lib::{Error, ErrorKind};
 use std::time::{Duration, Instant};
 
@@ -37,12 +38,44 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
         return;
     }
 
-    let mut channels = app.dm_channel_cache.clone();
+    let (accepted, requests): (Vec<DmChannelData>, Vec<DmChannelData>) = app
+        .dm_channel_cache
+        .iter()
+        .cloned()
+        .partition(dm_policy::is_dm_channel_accepted);
+    let mut channels = if app.dm_show_requests {
+        requests.clone()
+    } else {
+        accepted
+    };
 
     ui.add_space(10.0);
     ui.horizontal(|ui| {
-        ui.heading("Direct Private Message Channels");
+        if app.dm_show_requests {
+            ui.heading("Message Requests");
+        } else {
+            ui.heading("Direct Private Message Channels");
+        }
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            if app.dm_show_requests {
+                if ui.button("Back to channels").clicked() {
+                    app.dm_show_requests = false;
+                }
+            } else if !requests.is_empty()
+                && ui
+                    .button(format!("Message requests ({})", requests.len()))
+                    .clicked()
+            {
+                app.dm_show_requests = true;
+            }
+        });
     });
+    if app.dm_show_requests {
+        ui.label(format!(
+            "These are from people outside of your DM policy ({}). Accept one to move it to your channels.",
+            dm_policy::dm_policy()
+        ));
+    }
     ui.add_space(10.0);
 
     let is_signer_ready = GLOBALS.identity.is_unlocked();
@@ -111,6 +144,9 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
                                     ui.with_layout(
                                         egui::Layout::right_to_left(egui::Align::TOP),
                                         |ui| {
+                                            if app.dm_show_requests {
+                                                accept_button(ui, app, &channeldata);
+                                            }
                                             ui.label(
                                                 RichText::new(format!(
                                                     "{} messages",
@@ -146,3 +182,12 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
             }
         });
 }
+
+fn accept_button(ui: &mut Ui, app: &mut GossipUi, channeldata: &DmChannelData) {
+    if ui.button("Accept").clicked() {
+        match dm_policy::accept_dm_channel(&channeldata.dm_channel) {
+            Ok(()) => app.dm_channel_next_refresh = Instant::now(),
+            Err(e) => GLOBALS.status_queue.write().write(format!("{}", e)),
+        }
+    }
+}
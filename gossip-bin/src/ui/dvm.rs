@@ -0,0 +1,206 @@
+use super::{widgets, GossipUi, Page};
+use eframe::egui;
+use egui::{Context, RichText, Ui};
+use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::dvm::{DvmJobStatus, CONTENT_DISCOVERY_KIND, JOB_KINDS, TRANSLATION_KIND};
+use gossip_lib::names::best_name_from_pubkey_lookup;
+use gossip_lib::{FeedKind, GLOBALS};
+use nostr_types::PublicKey;
+
+pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        ui.heading("Data Vending Machines");
+    });
+    ui.label("Ask data vending machines (NIP-90) to do jobs for you: pick notes for you to read, translate or summarize. Some want to be paid.");
+    ui.add_space(10.0);
+
+    ui.horizontal(|ui| {
+        ui.label("Job:");
+        egui::ComboBox::from_id_source("DvmKindCombo")
+            .selected_text(gossip_lib::dvm::job_kind_name(app.dvm_kind))
+            .show_ui(ui, |ui| {
+                for (kind, name) in JOB_KINDS.iter() {
+                    ui.selectable_value(&mut app.dvm_kind, *kind, *name);
+                }
+            });
+        if app.dvm_kind == TRANSLATION_KIND {
+            ui.label("Into language:");
+            ui.add(text_edit_line!(app, app.dvm_language).desired_width(40.0));
+        }
+    });
+    let hint = if app.dvm_kind == CONTENT_DISCOVERY_KIND {
+        "What to look for (optional)"
+    } else {
+        "Text to work on"
+    };
+    ui.add(
+        text_edit_multiline!(app, app.dvm_input)
+            .hint_text(hint)
+            .desired_rows(3)
+            .desired_width(f32::INFINITY),
+    );
+    ui.horizontal(|ui| {
+        ui.label("Service provider:");
+        ui.add(
+            text_edit_line!(app, app.dvm_provider)
+                .hint_text("npub (optional, otherwise anyone may take it)")
+                .desired_width(400.0),
+        );
+    });
+
+    ui.add_enabled_ui(GLOBALS.identity.is_unlocked(), |ui| {
+        if ui.button("Ask").clicked() {
+            let provider = app.dvm_provider.trim();
+            let provider = if provider.is_empty() {
+                Ok(None)
+            } else {
+                PublicKey::try_from_bech32_string(provider, true)
+                    .or_else(|_| PublicKey::try_from_hex_string(provider, true))
+                    .map(Some)
+            };
+            let input = app.dvm_input.trim().to_owned();
+            match provider {
+                Ok(_) if input.is_empty() && app.dvm_kind != CONTENT_DISCOVERY_KIND => GLOBALS
+                    .status_queue
+                    .write()
+                    .write("Enter the text to work on.".to_owned()),
+                Ok(provider) => {
+                    let mut params: Vec<(String, String)> = Vec::new();
+                    if app.dvm_kind == TRANSLATION_KIND {
+                        params.push(("language".to_owned(), app.dvm_language.trim().to_owned()));
+                    }
+                    let _ = GLOBALS.to_overlord.send(ToOverlordMessage::RequestDvmJob {
+                        kind: app.dvm_kind,
+                        input,
+                        input_type: "text".to_owned(),
+                        params,
+                        provider,
+                    });
+                    app.dvm_input.clear();
+                }
+                Err(_) => GLOBALS
+                    .status_queue
+                    .write()
+                    .write("The service provider is not an npub.".to_owned()),
+            }
+        }
+    });
+
+    ui.add_space(10.0);
+    ui.separator();
+
+    let jobs = match gossip_lib::dvm::jobs() {
+        Ok(jobs) => jobs,
+        Err(e) => {
+            tracing::error!("{}", e);
+            vec![]
+        }
+    };
+
+    if jobs.is_empty() {
+        ui.add_space(10.0);
+        ui.label("You have not asked for any jobs.");
+        return;
+    }
+
+    let mut open = None;
+    let mut forget = None;
+    app.vert_scroll_area().id_source("dvm_jobs").show(ui, |ui| {
+        for job in jobs.iter() {
+            widgets::list_entry::make_frame(ui, Some(app.theme.main_content_bgcolor())).show(
+                ui,
+                |ui| {
+                    ui.set_min_width(ui.available_width());
+                    ui.horizontal_wrapped(|ui| {
+                        ui.label(RichText::new(gossip_lib::dvm::job_kind_name(job.kind)).heading());
+                        ui.label(crate::date_ago::date_ago(job.created_at));
+                        let status = match job.status {
+                            DvmJobStatus::Requested => "waiting for an answer",
+                            DvmJobStatus::Processing => "being worked on",
+                            DvmJobStatus::PaymentRequired => "payment required",
+                            DvmJobStatus::Error => "failed",
+                            DvmJobStatus::Partial => "partly done",
+                            DvmJobStatus::Success => "done",
+                        };
+                        ui.label(RichText::new(status).strong());
+                        if let Some(from) = job.status_from {
+                            ui.label(
+                                RichText::new(format!(
+                                    "by {}",
+                                    best_name_from_pubkey_lookup(&from)
+                                ))
+                                .small()
+                                .weak(),
+                            );
+                        }
+                    });
+                    if !job.input.is_empty() {
+                        ui.label(RichText::new(&job.input).italics());
+                    }
+                    if !job.status_info.is_empty() {
+                        ui.label(&job.status_info);
+                    }
+                    if job.kind != CONTENT_DISCOVERY_KIND && !job.result.is_empty() {
+                        ui.label(&job.result);
+                    }
+
+                    ui.horizontal(|ui| {
+                        if job.kind == CONTENT_DISCOVERY_KIND
+                            && job.result_id.is_some()
+                            && ui.button("Open feed").clicked()
+                        {
+                            open = Some(Page::Feed(FeedKind::Dvm(job.request_id)));
+                        }
+                        if let (Some(msat), Some(_), Some(payee), false) =
+                            (job.amount_msat, &job.bolt11, job.invoice_from, job.paid)
+                        {
+                            let label = format!(
+                                "Pay {} to {}",
+                                format_msats(msat),
+                                best_name_from_pubkey_lookup(&payee)
+                            );
+                            if ui.button(label).clicked() {
+                                let _ = GLOBALS
+                                    .to_overlord
+                                    .send(ToOverlordMessage::PayDvmJob(job.request_id, msat));
+                            }
+                        }
+                        if job.paid {
+                            ui.label("paid");
+                        }
+                        if job.status != DvmJobStatus::Success && ui.button("Check again").clicked()
+                        {
+                            let _ = GLOBALS
+                                .to_overlord
+                                .send(ToOverlordMessage::CheckDvmJob(job.request_id));
+                        }
+                        if ui.button("Forget").clicked() {
+                            forget = Some(job.request_id);
+                        }
+                    });
+                },
+            );
+            ui.add_space(4.0);
+        }
+    });
+
+    if let Some(request_id) = forget {
+        if let Err(e) = GLOBALS.storage.delete_dvm_job(request_id, None) {
+            GLOBALS.status_queue.write().write(format!("{}", e));
+        }
+    }
+
+    if let Some(page) = open {
+        app.set_page(ctx, page);
+    }
+}
+
+// Whole sats, or millisats if it is not a whole number of sats
+fn format_msats(msat: u64) -> String {
+    if msat % 1000 == 0 {
+        format!("{} sats", msat / 1000)
+    } else {
+        format!("{} msats", msat)
+    }
+}
@@ -3,6 +3,7 @@ use super::{widgets, GossipUi, Page};
 use eframe::egui::{self, Align, FontId, Rect};
 use egui::{Context, RichText, Ui, Vec2};
 use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::event_media::MediaFilter;
 use gossip_lib::FeedKind;
 use gossip_lib::GLOBALS;
 use nostr_types::Id;
@@ -94,6 +95,7 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
                     let title_job = super::people::layout_list_title(ui, app, &metadata);
                     ui.label(title_job);
                     recompute_btn(ui);
+                    media_filter_combo(ui);
 
                     if !feed.is_empty() {
                         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
@@ -201,6 +203,7 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
                     ui.heading(gossip_lib::names::best_name_from_pubkey_lookup(&pubkey));
                 }
                 recompute_btn(ui);
+                media_filter_combo(ui);
             });
             ui.add_space(6.0);
 
@@ -238,6 +241,220 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
             let id = channel.unique_id();
             render_a_feed(app, ctx, ui, feed, false, &id, load_more);
         }
+        FeedKind::Starred => {
+            ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                add_left_space(ui);
+                ui.heading("Starred");
+                recompute_btn(ui);
+            });
+            ui.add_space(6.0);
+
+            let feed = GLOBALS.feed.get_starred_feed();
+            if feed.is_empty() {
+                ui.horizontal(|ui| {
+                    add_left_space(ui);
+                    ui.label(
+                        "Star notes from their menu to keep them here. Stars stay on this device.",
+                    );
+                });
+            }
+            render_a_feed(app, ctx, ui, feed, false, "starred", load_more);
+        }
+        FeedKind::Group(relay, id) => {
+            let group = GLOBALS
+                .storage
+                .read_group(&relay, &id)
+                .ok()
+                .flatten()
+                .unwrap_or_else(|| gossip_lib::Group::new(relay.clone(), id.clone()));
+
+            ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                add_left_space(ui);
+                ui.heading(group.display_name());
+                recompute_btn(ui);
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    ui.add_space(16.0);
+                    if group.joined {
+                        if ui.button("Leave").clicked() {
+                            let _ = GLOBALS
+                                .to_overlord
+                                .send(ToOverlordMessage::LeaveGroup(relay.clone(), id.clone()));
+                        }
+                    } else if ui.button("Join").clicked() {
+                        let _ = GLOBALS
+                            .to_overlord
+                            .send(ToOverlordMessage::JoinGroup(relay.clone(), id.clone()));
+                    }
+                });
+            });
+            ui.horizontal_wrapped(|ui| {
+                add_left_space(ui);
+                ui.label(RichText::new(relay.as_str()).small().weak());
+                if !group.members.is_empty() {
+                    ui.label(RichText::new(format!("{} members", group.members.len())).small());
+                }
+                if !group.public {
+                    ui.label(RichText::new("private").small());
+                }
+            });
+            if !group.about.is_empty() {
+                ui.horizontal_wrapped(|ui| {
+                    add_left_space(ui);
+                    ui.label(&group.about);
+                });
+            }
+            ui.add_space(6.0);
+
+            if GLOBALS.identity.is_unlocked() {
+                ui.horizontal(|ui| {
+                    add_left_space(ui);
+                    let response = ui.add(
+                        text_edit_line!(app, app.group_draft)
+                            .hint_text("Write to the group")
+                            .desired_width(ui.available_width() - 80.0),
+                    );
+                    let enter =
+                        response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    if (ui.button("Send").clicked() || enter) && !app.group_draft.trim().is_empty()
+                    {
+                        let _ = GLOBALS.to_overlord.send(ToOverlordMessage::PostToGroup {
+                            relay: relay.clone(),
+                            group: id.clone(),
+                            content: std::mem::take(&mut app.group_draft),
+                            in_reply_to: None,
+                        });
+                    }
+                });
+                ui.add_space(6.0);
+            }
+
+            let feed = GLOBALS.feed.get_group_feed();
+            let scroll_id = format!("group {} {}", relay, id);
+            render_a_feed(app, ctx, ui, feed, false, &scroll_id, load_more);
+        }
+        FeedKind::Community(author, d) => {
+            let community = GLOBALS
+                .storage
+                .read_community(&author, &d)
+                .ok()
+                .flatten()
+                .unwrap_or_else(|| gossip_lib::Community::new(author, d.clone()));
+
+            ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                add_left_space(ui);
+                ui.heading(community.display_name());
+                recompute_btn(ui);
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    ui.add_space(16.0);
+                    let label = if community.joined { "Leave" } else { "Join" };
+                    if ui.button(label).clicked() {
+                        if let Err(e) =
+                            gossip_lib::communities::set_joined(author, &d, !community.joined)
+                        {
+                            GLOBALS.status_queue.write().write(format!("{}", e));
+                        }
+                    }
+                });
+            });
+            ui.horizontal_wrapped(|ui| {
+                add_left_space(ui);
+                ui.label(
+                    RichText::new(format!(
+                        "by {}",
+                        gossip_lib::names::best_name_from_pubkey_lookup(&author)
+                    ))
+                    .small()
+                    .weak(),
+                );
+                if !community.moderators.is_empty() {
+                    ui.label(
+                        RichText::new(format!("{} moderators", community.moderators.len())).small(),
+                    );
+                }
+            });
+            if !community.description.is_empty() {
+                ui.horizontal_wrapped(|ui| {
+                    add_left_space(ui);
+                    ui.label(&community.description);
+                });
+            }
+            if !community.rules.is_empty() {
+                ui.horizontal_wrapped(|ui| {
+                    add_left_space(ui);
+                    ui.label(RichText::new(format!("Rules: {}", community.rules)).small());
+                });
+            }
+            ui.add_space(6.0);
+
+            if GLOBALS.identity.is_unlocked() {
+                ui.horizontal(|ui| {
+                    add_left_space(ui);
+                    ui.add(
+                        text_edit_line!(app, app.community_draft)
+                            .hint_text("Post to the community")
+                            .desired_width(ui.available_width() - 80.0),
+                    );
+                    if ui.button("Post").clicked() && !app.community_draft.trim().is_empty() {
+                        let _ = GLOBALS.to_overlord.send(ToOverlordMessage::Post {
+                            content: std::mem::take(&mut app.community_draft),
+                            tags: vec![gossip_lib::communities::community_tag(&community)],
+                            in_reply_to: None,
+                            dm_channel: None,
+                        });
+                    }
+                });
+                ui.horizontal(|ui| {
+                    add_left_space(ui);
+                    ui.label(
+                        RichText::new("Posts show here once a moderator approves them.")
+                            .small()
+                            .weak(),
+                    );
+                });
+                ui.add_space(6.0);
+            }
+
+            let feed = GLOBALS.feed.get_community_feed();
+            let scroll_id = format!("community {} {}", author.as_hex_string(), d);
+            render_a_feed(app, ctx, ui, feed, false, &scroll_id, load_more);
+        }
+        FeedKind::Dvm(request_id) => {
+            ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                add_left_space(ui);
+                ui.heading(FeedKind::Dvm(request_id).to_string());
+                recompute_btn(ui);
+            });
+            if let Ok(Some(job)) = GLOBALS.storage.read_dvm_job(request_id) {
+                if let Some(provider) = job.result_from {
+                    ui.horizontal(|ui| {
+                        add_left_space(ui);
+                        ui.label(
+                            RichText::new(format!(
+                                "Picked by {}",
+                                gossip_lib::names::best_name_from_pubkey_lookup(&provider)
+                            ))
+                            .small()
+                            .weak(),
+                        );
+                    });
+                }
+            }
+            ui.add_space(6.0);
+
+            let feed = GLOBALS.feed.get_dvm_feed();
+            if feed.is_empty() {
+                ui.horizontal(|ui| {
+                    add_left_space(ui);
+                    ui.label("No results yet.");
+                });
+            }
+            let scroll_id = format!("dvm {}", request_id.as_hex_string());
+            render_a_feed(app, ctx, ui, feed, false, &scroll_id, load_more);
+        }
     }
 
     // Handle any changes due to changes in which notes are visible
@@ -517,6 +734,21 @@ fn add_left_space(ui: &mut Ui) {
     ui.add_space(2.0);
 }
 
+fn media_filter_combo(ui: &mut Ui) {
+    let current = GLOBALS.feed.get_media_filter();
+    let mut selected = current;
+    egui::ComboBox::from_id_source(egui::Id::from("FeedMediaFilterCombo"))
+        .selected_text(current.as_str())
+        .show_ui(ui, |ui| {
+            for filter in MediaFilter::ALL {
+                ui.selectable_value(&mut selected, filter, filter.as_str());
+            }
+        });
+    if selected != current {
+        GLOBALS.feed.set_media_filter(selected);
+    }
+}
+
 fn recompute_btn(ui: &mut Ui) {
     if !read_setting!(recompute_feed_periodically) {
         if ui.link("Refresh").clicked() {
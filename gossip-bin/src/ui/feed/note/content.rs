@@ -5,6 +5,9 @@ use eframe::{
 };
 use egui::{Button, Color32, Pos2, RichText, Stroke, Ui};
 use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::event_media::MediaType;
+use gossip_lib::linkify::{linkify, InlineSegment};
+use gossip_lib::remote_content::{may_fetch, remote_content_policy, RemoteContent};
 use gossip_lib::FeedKind;
 use gossip_lib::GLOBALS;
 use nostr_types::{ContentSegment, EventAddr, Id, IdHex, NostrBech32, PublicKey, Span, Url};
@@ -202,14 +205,14 @@ pub(super) fn render_hyperlink(
     // In DMs, fetching an image allows someone to associate your pubkey with your IP address
     // by controlling the image URL, and since only you see the URL it must have been you
     let privacy_issue = note.direct_message;
+    let policy = remote_content_policy(&note.event.pubkey, note.direct_message);
 
     if let (Ok(url), Some(nurl)) = (url::Url::try_from(link), app.try_check_url(link)) {
-        if is_image_url(&url) {
-            show_image_toggle(app, ui, nurl, privacy_issue);
-        } else if is_video_url(&url) {
-            show_video_toggle(app, ui, nurl, privacy_issue);
-        } else {
-            crate::ui::widgets::break_anywhere_hyperlink_to(ui, link, link);
+        let alt = gossip_lib::event_media::alt_text_of(&note.event, link);
+        match MediaType::of_url(url.as_str()) {
+            Some(MediaType::Image) => show_image_toggle(app, ui, nurl, policy, privacy_issue, alt),
+            Some(MediaType::Video) => show_video_toggle(app, ui, nurl, policy, privacy_issue, alt),
+            _ => crate::ui::widgets::break_anywhere_hyperlink_to(ui, link, link),
         }
     } else {
         crate::ui::widgets::break_anywhere_hyperlink_to(ui, link, link);
@@ -253,7 +256,33 @@ pub(super) fn render_plain(
         if as_deleted {
             ui.label(RichText::new(line).strikethrough());
         } else {
-            ui.label(line);
+            for segment in linkify(line) {
+                match segment {
+                    InlineSegment::Text(text) => {
+                        ui.label(text);
+                    }
+                    InlineSegment::Nostr(bech32, text) => match bech32 {
+                        NostrBech32::EventAddr(ea) => {
+                            render_parameterized_event_link(app, ui, note.event.id, &ea);
+                        }
+                        NostrBech32::EventPointer(ep) => {
+                            render_event_link(app, ui, note.event.id, ep.id);
+                        }
+                        NostrBech32::Id(id) => render_event_link(app, ui, note.event.id, id),
+                        NostrBech32::Profile(prof) => render_profile_link(app, ui, &prof.pubkey),
+                        NostrBech32::Pubkey(pubkey) => render_profile_link(app, ui, &pubkey),
+                        NostrBech32::Relay(_) => {
+                            ui.label(text);
+                        }
+                    },
+                    InlineSegment::LightningInvoice(invoice) => {
+                        render_payment_uri(ui, "⚡ Pay invoice", "lightning", invoice);
+                    }
+                    InlineSegment::CashuToken(token) => {
+                        render_payment_uri(ui, "🥜 Redeem ecash", "cashu", token);
+                    }
+                }
+            }
         }
 
         first = false;
@@ -262,6 +291,25 @@ pub(super) fn render_plain(
     false
 }
 
+// A button that hands a lightning invoice or cashu token to the wallet that handles
+// its URI scheme, with the raw text to copy for wallets that do not
+fn render_payment_uri(ui: &mut Ui, label: &str, scheme: &str, payload: &str) {
+    let response = ui.button(label).on_hover_text(payload);
+    if response.clicked() {
+        ui.ctx().output_mut(|o| {
+            o.open_url = Some(egui::output::OpenUrl {
+                url: format!("{}:{}", scheme, payload),
+                new_tab: false,
+            });
+        });
+    }
+    response.context_menu(|ui| {
+        if ui.button("Copy").clicked() {
+            ui.output_mut(|o| o.copied_text = payload.to_owned());
+        }
+    });
+}
+
 pub(super) fn render_profile_link(app: &mut GossipUi, ui: &mut Ui, pubkey: &PublicKey) {
     let name = gossip_lib::names::best_name_from_pubkey_lookup(pubkey);
     if ui.link(&name).clicked() {
@@ -344,37 +392,29 @@ pub(super) fn render_unknown_reference(ui: &mut Ui, num: usize) {
     }
 }
 
-fn is_image_url(url: &url::Url) -> bool {
-    let lower = url.path().to_lowercase();
-    lower.ends_with(".jpg")
-        || lower.ends_with(".jpeg")
-        || lower.ends_with(".png")
-        || lower.ends_with(".gif")
-        || lower.ends_with(".webp")
-}
-
-fn is_video_url(url: &url::Url) -> bool {
-    let lower = url.path().to_lowercase();
-    lower.ends_with(".mov")
-        || lower.ends_with(".mp4")
-        || lower.ends_with(".mkv")
-        || lower.ends_with(".webm")
-}
-
-fn show_image_toggle(app: &mut GossipUi, ui: &mut Ui, url: Url, privacy_issue: bool) {
+fn show_image_toggle(
+    app: &mut GossipUi,
+    ui: &mut Ui,
+    url: Url,
+    policy: RemoteContent,
+    privacy_issue: bool,
+    alt: Option<String>,
+) {
     let row_height = ui.cursor().height();
     let url_string = url.to_string();
     let mut show_link = true;
 
     // FIXME show/hide lists should persist app restarts
-    let show_image = (read_setting!(show_media)
-        && !app.media_hide_list.contains(&url)
-        && (!privacy_issue || app.media_show_list.contains(&url)))
-        || (!read_setting!(show_media) && app.media_show_list.contains(&url));
+    let show_image = !app.media_hide_list.contains(&url)
+        && may_fetch(&url, policy, app.media_show_list.contains(&url));
 
     if show_image {
         if let Some(response) = try_render_image(app, ui, url.clone()) {
             show_link = false;
+            let response = match &alt {
+                Some(alt) => response.on_hover_text(alt),
+                None => response,
+            };
 
             // full-width toggle
             if response.clicked() {
@@ -393,13 +433,20 @@ fn show_image_toggle(app: &mut GossipUi, ui: &mut Ui, url: Url, privacy_issue: b
             ui.link("[ PRIVACY RISK Image ]").on_hover_text(format!("The sender might be trying to associate your nostr pubkey with your IP address. URL={}", url_string))
         } else {
             // show url on hover
-            ui.link("[ Image ]").on_hover_text(url_string.clone())
+            match &alt {
+                Some(alt) => ui
+                    .link(format!("[ Image: {} ]", alt))
+                    .on_hover_text(url_string.clone()),
+                None => ui.link("[ Image ]").on_hover_text(url_string.clone()),
+            }
         };
 
         if response.clicked() {
             app.media_hide_list.remove(&url);
             app.media_show_list.insert(url.clone());
-            if !read_setting!(load_media) {
+            if policy == RemoteContent::Never {
+                GLOBALS.status_queue.write().write("Media from this author is never loaded (see Content settings). Right-click link to open in browser or copy URL".to_owned());
+            } else if !read_setting!(load_media) {
                 GLOBALS.status_queue.write().write("Fetch Media setting is disabled. Right-click link to open in browser or copy URL".to_owned());
             }
         }
@@ -475,16 +522,21 @@ fn try_render_image(app: &mut GossipUi, ui: &mut Ui, url: Url) -> Option<Respons
     response_return
 }
 
-fn show_video_toggle(app: &mut GossipUi, ui: &mut Ui, url: Url, privacy_issue: bool) {
+fn show_video_toggle(
+    app: &mut GossipUi,
+    ui: &mut Ui,
+    url: Url,
+    policy: RemoteContent,
+    privacy_issue: bool,
+    alt: Option<String>,
+) {
     let row_height = ui.cursor().height();
     let url_string = url.to_string();
     let mut show_link = true;
 
     // FIXME show/hide lists should persist app restarts
-    let show_video = (read_setting!(show_media)
-        && !app.media_hide_list.contains(&url)
-        && (!privacy_issue || app.media_show_list.contains(&url)))
-        || (!read_setting!(show_media) && app.media_show_list.contains(&url));
+    let show_video = !app.media_hide_list.contains(&url)
+        && may_fetch(&url, policy, app.media_show_list.contains(&url));
 
     if show_video {
         if let Some(response) = try_render_video(app, ui, url.clone()) {
@@ -510,13 +562,20 @@ fn show_video_toggle(app: &mut GossipUi, ui: &mut Ui, url: Url, privacy_issue: b
             ))
         } else {
             // show url on hover
-            ui.link("[ Video ]").on_hover_text(url_string.clone())
+            match &alt {
+                Some(alt) => ui
+                    .link(format!("[ Video: {} ]", alt))
+                    .on_hover_text(url_string.clone()),
+                None => ui.link("[ Video ]").on_hover_text(url_string.clone()),
+            }
         };
 
         if response.clicked() {
             app.media_hide_list.remove(&url);
             app.media_show_list.insert(url.clone());
-            if !read_setting!(load_media) {
+            if policy == RemoteContent::Never {
+                GLOBALS.status_queue.write().write("Media from this author is never loaded (see Content settings). Right-click link to open in browser or copy URL".to_owned());
+            } else if !read_setting!(load_media) {
                 GLOBALS.status_queue.write().write("Fetch Media setting is disabled. Right-click link to open in browser or copy URL".to_owned());
             }
         }
@@ -659,11 +718,8 @@ fn add_media_menu(app: &mut GossipUi, ui: &mut Ui, url: Url, response: &Response
                     .on_hover_text("Hide (return to a link)")
                     .clicked()
                 {
-                    if read_setting!(show_media) {
-                        app.media_hide_list.insert(url.clone());
-                    } else {
-                        app.media_show_list.remove(&url);
-                    }
+                    app.media_hide_list.insert(url.clone());
+                    app.media_show_list.remove(&url);
                 }
                 ui.add_space(SPACE);
                 if ui
@@ -693,6 +749,20 @@ fn add_media_menu(app: &mut GossipUi, ui: &mut Ui, url: Url, response: &Response
                 {
                     ui.output_mut(|o| o.copied_text = url.to_string());
                 }
+                let same = GLOBALS.media.same_content(&url);
+                if !same.is_empty() {
+                    ui.add_space(SPACE);
+                    let mut text = "Also posted at:".to_owned();
+                    for other in same.iter() {
+                        text.push('\n');
+                        text.push_str(other.as_str());
+                    }
+                    ui.add_sized(
+                        BTN_SIZE,
+                        egui::Label::new(RichText::new("\u{1F501}").size(TXT_SIZE)),
+                    )
+                    .on_hover_text(text);
+                }
             });
         }
     }
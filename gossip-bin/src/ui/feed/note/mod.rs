@@ -18,8 +18,8 @@ use gossip_lib::DmChannel;
 use gossip_lib::FeedKind;
 use gossip_lib::{Globals, ZapState, GLOBALS};
 use nostr_types::{
-    Event, EventAddr, EventDelegation, EventKind, EventPointer, EventReference, IdHex, NostrUrl,
-    UncheckedUrl,
+    Event, EventAddr, EventDelegation, EventKind, EventPointer, EventReference, Id, IdHex,
+    NostrUrl, UncheckedUrl,
 };
 
 #[derive(Default)]
@@ -302,7 +302,7 @@ pub fn render_note_inner(
                 GossipUi::render_person_name_line(app, ui, &note.author, false);
 
                 ui.horizontal_wrapped(|ui| {
-                    match note.event.replies_to() {
+                    match gossip_lib::nip10::replies_to(&note.event) {
                         Some(EventReference::Id { id: irt, .. }) => {
                             ui.add_space(8.0);
                             ui.style_mut().override_text_style = Some(TextStyle::Small);
@@ -397,6 +397,21 @@ pub fn render_note_inner(
                         );
                     }
 
+                    if let Some(bridge) = &note.bridge {
+                        let color = app.theme.notice_marker_text_color();
+                        let response = ui.label(
+                            RichText::new(format!(
+                                "VIA {}",
+                                bridge.protocol.to_string().to_uppercase()
+                            ))
+                            .color(color)
+                            .text_style(TextStyle::Small),
+                        );
+                        if let Some(url) = &bridge.url {
+                            response.on_hover_text(url);
+                        }
+                    }
+
                     if let Page::Feed(FeedKind::DmChat(_)) = app.page {
                         // don't show ENCRYPTED DM or SECURE in the dm channel itself
                     } else {
@@ -550,6 +565,39 @@ pub fn render_note_inner(
                                 });
                             }),
                         ));
+                        let id = note.event.id;
+                        match GLOBALS.storage.read_star(id).ok().flatten() {
+                            None => {
+                                entries.push(MoreMenuEntry::new(
+                                    "Star",
+                                    Box::new(move |_, _| set_star(id, Some(false))),
+                                ));
+                                entries.push(MoreMenuEntry::new(
+                                    "Pin",
+                                    Box::new(move |_, _| set_star(id, Some(true))),
+                                ));
+                            }
+                            Some((_, pinned)) => {
+                                entries.push(MoreMenuEntry::new(
+                                    "Unstar",
+                                    Box::new(move |_, _| set_star(id, None)),
+                                ));
+                                entries.push(MoreMenuEntry::new(
+                                    if pinned { "Unpin" } else { "Pin" },
+                                    Box::new(move |_, _| set_star(id, Some(!pinned))),
+                                ));
+                                if GLOBALS.identity.is_unlocked() {
+                                    entries.push(MoreMenuEntry::new(
+                                        "Bookmark publicly",
+                                        Box::new(move |_, _| {
+                                            let _ = GLOBALS
+                                                .to_overlord
+                                                .send(ToOverlordMessage::PromoteStarToBookmark(id));
+                                        }),
+                                    ));
+                                }
+                            }
+                        }
                         entries.push(MoreMenuEntry::new(
                             "Dismiss",
                             Box::new(|_, _| {
@@ -605,7 +653,7 @@ pub fn render_note_inner(
                         matches!(feed_kind, FeedKind::Thread { .. })
                     };
 
-                    if is_thread_view && note.event.replies_to().is_some() {
+                    if is_thread_view && gossip_lib::nip10::replies_to(&note.event).is_some() {
                         if collapsed {
                             let color = app.theme.warning_marker_text_color();
                             if widgets::Button::secondary(
@@ -791,8 +839,9 @@ pub fn render_note_inner(
                                         });
                                     } else if note.event.kind == EventKind::EncryptedDirectMessage {
                                         ui.output_mut(|o| {
-                                            if let Ok(m) =
-                                                GLOBALS.identity.decrypt_event_contents(&note.event)
+                                            if let Ok(m) = GLOBALS
+                                                .identity
+                                                .decrypt_event_contents_cached(&note.event)
                                             {
                                                 o.copied_text = m
                                             } else {
@@ -1005,9 +1054,34 @@ pub fn render_note_inner(
                                         .on_disabled_hover_text("Note is not zappable (no lnurl)");
                                     }
 
-                                    // Show the zap total
-                                    ui.add(Label::new(format!("{}", note.zaptotal.0 / 1000)))
+                                    // Show the zap total, and who zapped on hover
+                                    let response = ui
+                                        .add(Label::new(format!("{}", note.zaptotal.0 / 1000)))
                                         .on_hover_cursor(egui::CursorIcon::Default);
+                                    if note.zaptotal.0 > 0 {
+                                        response.on_hover_ui(|ui| {
+                                            let zappers = gossip_lib::zaps::zappers(
+                                                note.event.id,
+                                                note.event.pubkey,
+                                            )
+                                            .unwrap_or_default();
+                                            for (pubkey, msats) in zappers.iter().take(10) {
+                                                ui.label(format!(
+                                                    "{}: {} sats",
+                                                    gossip_lib::names::best_name_from_pubkey_lookup(
+                                                        pubkey
+                                                    ),
+                                                    msats.0 / 1000
+                                                ));
+                                            }
+                                            if zappers.len() > 10 {
+                                                ui.label(format!(
+                                                    "and {} more",
+                                                    zappers.len() - 10
+                                                ));
+                                            }
+                                        });
+                                    }
                                 }
 
                                 ui.add_space(24.0);
@@ -1087,6 +1161,20 @@ pub fn render_note_inner(
     }
 }
 
+// Star (Some(false)), pin (Some(true)) or unstar (None) an event, locally
+fn set_star(id: Id, pinned: Option<bool>) {
+    let result = match pinned {
+        Some(pinned) => GLOBALS.storage.star_event(id, pinned, None),
+        None => GLOBALS.storage.unstar_event(id, None),
+    };
+    if let Err(e) = result {
+        GLOBALS.status_queue.write().write(format!("{}", e));
+    }
+    if matches!(GLOBALS.feed.get_feed_kind(), FeedKind::Starred) {
+        GLOBALS.feed.sync_recompute();
+    }
+}
+
 fn thin_separator(ui: &mut Ui, stroke: Stroke) {
     let style = ui.style_mut();
     style.visuals.widgets.noninteractive.bg_stroke = stroke;
@@ -1148,7 +1236,9 @@ fn render_content(
                         );
                     } else if app.render_qr == Some(event.id) {
                         if note.event.kind == EventKind::EncryptedDirectMessage {
-                            if let Ok(m) = GLOBALS.identity.decrypt_event_contents(&note.event) {
+                            if let Ok(m) =
+                                GLOBALS.identity.decrypt_event_contents_cached(&note.event)
+                            {
                                 app.render_qr(ui, "feedqr", m.trim());
                             }
                         } else {
@@ -1,5 +1,6 @@
+use gossip_lib::kind_registry::RenderHint;
 use gossip_lib::GLOBALS;
-use gossip_lib::{Person, PersonList, Private};
+use gossip_lib::{BridgeSource, Person, PersonList, Private};
 use std::collections::HashMap;
 
 use nostr_types::{
@@ -71,6 +72,9 @@ pub(crate) struct NoteData {
 
     /// Securely delivered via GiftWrap
     pub secure: bool,
+
+    /// Where this note came from, if bridged from another protocol
+    pub bridge: Option<BridgeSource>,
 }
 
 impl NoteData {
@@ -85,7 +89,7 @@ impl NoteData {
             secure = true;
             // Use the rumor for subsequent processing, but swap for the Giftwrap's id
             // since that is the effective event (database-accessible, deletable, etc)
-            if let Ok(rumor) = GLOBALS.identity.unwrap_giftwrap(&event) {
+            if let Ok(rumor) = GLOBALS.identity.unwrap_giftwrap_kept(&event) {
                 let id = event.id;
                 event = rumor.into_event_with_bad_signature();
                 event.id = id; // lie, keep the giftwrap id
@@ -106,10 +110,8 @@ impl NoteData {
             .get_reactions(event.id)
             .unwrap_or((vec![], false));
 
-        let zaptotal = GLOBALS
-            .storage
-            .get_zap_total(event.id)
-            .unwrap_or(MilliSatoshi(0));
+        let zaptotal =
+            gossip_lib::zaps::zap_total(event.id, event.pubkey).unwrap_or(MilliSatoshi(0));
 
         // build a list of all cached mentions and their index
         // only notes that are in the cache will be rendered as reposts
@@ -132,10 +134,10 @@ impl NoteData {
                             (Some(embedded_event), None)
                         } else {
                             (None, {
-                                let kind_number: u32 = embedded_event.kind.into();
+                                let desc = GLOBALS.kinds.describe(embedded_event.kind);
                                 Some(format!(
                                     "ERROR EMBEDDED EVENT UNSUPPORTED KIND : {}",
-                                    kind_number
+                                    desc.describe_event(&embedded_event)
                                 ))
                             })
                         }
@@ -162,7 +164,7 @@ impl NoteData {
             EventKind::Repost => ("".to_owned(), embedded_event_error),
             EventKind::GenericRepost => ("".to_owned(), None),
             EventKind::EncryptedDirectMessage => {
-                match GLOBALS.identity.decrypt_event_contents(&event) {
+                match GLOBALS.identity.decrypt_event_contents_cached(&event) {
                     Ok(m) => (m, None),
                     Err(_) => ("".to_owned(), Some("DECRYPTION FAILED".to_owned())),
                 }
@@ -175,17 +177,16 @@ impl NoteData {
             EventKind::CommunityPost => (event.content.clone(), None),
             EventKind::DraftLongFormContent => (event.content.clone(), None),
             k => {
+                let desc = GLOBALS.kinds.describe(k);
                 if k.is_feed_displayable() {
-                    (event.content.clone(), Some(format!("{:?}", k)))
+                    (event.content.clone(), Some(desc.name))
                 } else {
-                    let mut dc = format!("UNSUPPORTED EVENT KIND {:?}", k);
-                    // support the 'alt' tag of NIP-31:
-                    for tag in &event.tags {
-                        if tag.tagname() == "alt" && tag.value() != "" {
-                            dc = format!("UNSUPPORTED EVENT KIND {:?}, ALT: {}", k, tag.value());
+                    match desc.hint {
+                        RenderHint::Text | RenderHint::LongForm => {
+                            (event.content.clone(), Some(desc.describe_event(&event)))
                         }
+                        RenderHint::Summary => ("".to_owned(), Some(desc.describe_event(&event))),
                     }
-                    ("".to_owned(), Some(dc))
                 }
             }
         };
@@ -261,6 +262,8 @@ impl NoteData {
             .get_event_seen_on_relay(event.id)
             .unwrap_or_default();
 
+        let bridge = gossip_lib::bridge_source(&event);
+
         NoteData {
             event,
             delegation,
@@ -278,6 +281,7 @@ impl NoteData {
             error_content,
             direct_message,
             secure,
+            bridge,
         }
     }
 
@@ -9,12 +9,15 @@ use egui_winit::egui::text::{CCursor, CCursorRange};
 use egui_winit::egui::text_edit::TextEditOutput;
 use egui_winit::egui::{vec2, AboveOrBelow, Id};
 use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::event_media;
+use gossip_lib::posting_policy::PostingPolicy;
 use gossip_lib::DmChannel;
 use gossip_lib::Relay;
 use gossip_lib::GLOBALS;
 use memoize::memoize;
 use nostr_types::{ContentSegment, NostrBech32, NostrUrl, ShatteredContent, Tag};
 use std::collections::HashMap;
+use std::sync::atomic::Ordering;
 
 #[memoize]
 pub fn textarea_highlighter(theme: Theme, text: String, interests: Vec<String>) -> LayoutJob {
@@ -513,6 +516,10 @@ fn real_posting_area(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
                 show_tagging_result(ui, app, &mut output, enter_key);
 
                 app.draft_data.last_textedit_rect = output.response.rect;
+
+                if read_setting!(alt_text_reminder) || read_setting!(alt_text_required) {
+                    alt_text_fields(app, ui);
+                }
             }
 
             ui.add_space(8.0);
@@ -607,6 +614,21 @@ fn real_posting_area(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
                     {
                         send_now = true;
                     }
+
+                    if GLOBALS.posting_in_progress.load(Ordering::Relaxed) {
+                        if ui
+                            .link("Stop sending")
+                            .on_hover_text("Cancel the post being prepared. No part of it is sent.")
+                            .clicked()
+                        {
+                            GLOBALS.cancel_posting.store(true, Ordering::Relaxed);
+                        }
+                    } else if app.draft_data.repost.is_none() {
+                        let parts = PostingPolicy::global().part_count(&app.draft_data.draft);
+                        if parts > 1 {
+                            ui.label(format!("Will post as a thread of {} notes", parts));
+                        }
+                    }
                 });
 
                 ui.add_space(7.0);
@@ -642,10 +664,21 @@ fn real_posting_area(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
         }
     });
 
+    if send_now && read_setting!(alt_text_required) && app.draft_data.repost.is_none() {
+        let replaced = do_replacements(&app.draft_data.draft, &app.draft_data.replacements);
+        if !event_media::media_missing_alt(&replaced, &alt_text_tags(app, &replaced)).is_empty() {
+            GLOBALS
+                .status_queue
+                .write()
+                .write("Add alt text to every image and video before posting.".to_owned());
+            send_now = false;
+        }
+    }
+
     if send_now {
         let replaced = do_replacements(&app.draft_data.draft, &app.draft_data.replacements);
 
-        let mut tags: Vec<Tag> = Vec::new();
+        let mut tags: Vec<Tag> = alt_text_tags(app, &replaced);
         if app.draft_data.include_content_warning {
             tags.push(Tag::new_content_warning(&app.draft_data.content_warning));
         }
@@ -708,6 +741,66 @@ fn real_posting_area(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
     }
 }
 
+// imeta tags with the alt text given for the media in the content
+fn alt_text_tags(app: &GossipUi, content: &str) -> Vec<Tag> {
+    event_media::media_urls_in(content)
+        .iter()
+        .filter_map(|(url, _)| {
+            let alt = app.draft_data.alt_texts.get(url)?;
+            if alt.trim().is_empty() {
+                None
+            } else {
+                Some(event_media::imeta_alt_tag(url, alt))
+            }
+        })
+        .collect()
+}
+
+// An alt text field for each image and video in the draft
+fn alt_text_fields(app: &mut GossipUi, ui: &mut Ui) {
+    let replaced = do_replacements(&app.draft_data.draft, &app.draft_data.replacements);
+    let urls = event_media::media_urls_in(&replaced);
+    if urls.is_empty() {
+        return;
+    }
+
+    ui.add_space(6.0);
+    for (url, media_type) in urls.iter() {
+        let kind = match media_type {
+            event_media::MediaType::Image => "image",
+            event_media::MediaType::Video => "video",
+            event_media::MediaType::Audio => "audio",
+        };
+        let alt = app.draft_data.alt_texts.entry(url.clone()).or_default();
+        ui.horizontal(|ui| {
+            ui.label(format!("Alt text ({}): ", kind))
+                .on_hover_text(url.as_str());
+            widgets::TextEdit::singleline(&app.theme, alt)
+                .hint_text("Describe it for people who can't see it")
+                .desired_width(f32::INFINITY)
+                .show(ui);
+        });
+    }
+    let missing = urls
+        .iter()
+        .filter(|(url, _)| {
+            app.draft_data
+                .alt_texts
+                .get(url)
+                .map(|a| a.trim().is_empty())
+                .unwrap_or(true)
+        })
+        .count();
+    if missing > 0 {
+        let text = if read_setting!(alt_text_required) {
+            format!("Alt text is required: {} missing", missing)
+        } else {
+            format!("{} without alt text", missing)
+        };
+        ui.label(RichText::new(text).color(app.theme.warning_marker_text_color()));
+    }
+}
+
 fn calc_tagging_search(app: &mut GossipUi) {
     // show tagging slector tooltip
     if let Some(search) = &app.draft_data.tagging_search_substring {
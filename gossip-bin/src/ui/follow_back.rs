@@ -0,0 +1,187 @@
+use super::{widgets, GossipUi, Page};
+use eframe::egui;
+use egui::{Context, RichText, Slider, Ui};
+use gossip_lib::follow_back::{FollowBackAction, FollowBackRule};
+use gossip_lib::names::best_name_from_pubkey_lookup;
+use gossip_lib::GLOBALS;
+
+pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        ui.heading("Follow-back Rules");
+    });
+    ui.label("Add new followers to a list when enough of the people you follow follow them too. By default each addition waits for your approval under Notifications.");
+    ui.add_space(10.0);
+
+    let lists = GLOBALS
+        .storage
+        .get_all_person_list_metadata()
+        .unwrap_or_default();
+
+    ui.horizontal(|ui| {
+        ui.label("Name:");
+        ui.add(text_edit_line!(app, app.follow_back_name).desired_width(200.0));
+        ui.label("Add to:");
+        let selected = lists
+            .iter()
+            .find(|(list, _)| *list == app.follow_back_list)
+            .map(|(_, metadata)| metadata.title.clone())
+            .unwrap_or_default();
+        egui::ComboBox::from_id_source("follow_back_list")
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                for (list, metadata) in lists.iter() {
+                    ui.selectable_value(&mut app.follow_back_list, *list, &metadata.title);
+                }
+            });
+    });
+    ui.add(
+        Slider::new(&mut app.follow_back_min_mutual, 0..=20)
+            .text("of the people I follow must follow them"),
+    );
+    ui.add(Slider::new(&mut app.follow_back_per_day, 1..=100).text("at most per day"));
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut app.follow_back_private, "Private");
+        ui.checkbox(&mut app.follow_back_approval, "Ask me first");
+    });
+    if ui.button("Add Rule").clicked() {
+        let name = match app.follow_back_name.trim() {
+            "" => format!("{} mutual follows", app.follow_back_min_mutual),
+            name => name.to_owned(),
+        };
+        let mut rule = FollowBackRule::new(name, app.follow_back_min_mutual, app.follow_back_list);
+        rule.private = app.follow_back_private;
+        rule.require_approval = app.follow_back_approval;
+        rule.max_per_day = app.follow_back_per_day;
+        match gossip_lib::follow_back::save_rule(rule) {
+            Ok(()) => app.follow_back_name.clear(),
+            Err(e) => GLOBALS.status_queue.write().write(format!("{}", e)),
+        }
+    }
+
+    ui.add_space(10.0);
+    ui.separator();
+
+    let rules = match gossip_lib::follow_back::rules() {
+        Ok(rules) => rules,
+        Err(e) => {
+            ui.label(format!("Could not load the follow-back rules: {}", e));
+            return;
+        }
+    };
+    if rules.is_empty() {
+        ui.add_space(10.0);
+        ui.label("You have no follow-back rules.");
+    }
+    let mut changed: Option<FollowBackRule> = None;
+    let mut delete: Option<u64> = None;
+    for rule in rules.iter() {
+        widgets::list_entry::make_frame(ui, Some(app.theme.main_content_bgcolor())).show(
+            ui,
+            |ui| {
+                ui.set_min_width(ui.available_width());
+                ui.horizontal_wrapped(|ui| {
+                    let mut enabled = rule.enabled;
+                    if ui.checkbox(&mut enabled, "").changed() {
+                        changed = Some(FollowBackRule {
+                            enabled,
+                            ..rule.clone()
+                        });
+                    }
+                    ui.label(RichText::new(&rule.name).strong());
+                    let list_name = lists
+                        .iter()
+                        .find(|(list, _)| Some(*list) == rule.person_list())
+                        .map(|(_, metadata)| metadata.title.clone())
+                        .unwrap_or_else(|| "(deleted list)".to_owned());
+                    ui.label(format!(
+                        "followed by {} or more of my follows → {}{}, {} per day{}",
+                        rule.min_mutual_follows,
+                        list_name,
+                        if rule.private { " (private)" } else { "" },
+                        rule.max_per_day,
+                        if rule.require_approval {
+                            ", ask me first"
+                        } else {
+                            ""
+                        }
+                    ));
+                    if ui.button("Delete").clicked() {
+                        delete = Some(rule.id);
+                    }
+                });
+            },
+        );
+        ui.add_space(4.0);
+    }
+
+    if let Some(rule) = changed {
+        if let Err(e) = gossip_lib::follow_back::save_rule(rule) {
+            GLOBALS.status_queue.write().write(format!("{}", e));
+        }
+    }
+    if let Some(id) = delete {
+        if let Err(e) = gossip_lib::follow_back::delete_rule(id) {
+            GLOBALS.status_queue.write().write(format!("{}", e));
+        }
+    }
+
+    ui.add_space(10.0);
+    ui.heading("Log");
+    ui.add_space(4.0);
+
+    let log = match gossip_lib::follow_back::log() {
+        Ok(log) => log,
+        Err(e) => {
+            ui.label(format!("Could not load the log: {}", e));
+            return;
+        }
+    };
+    if log.is_empty() {
+        ui.label("No rule has fired yet.");
+        return;
+    }
+
+    let mut open = None;
+    app.vert_scroll_area()
+        .id_source("follow_back_log")
+        .show(ui, |ui| {
+            for entry in log.iter() {
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(
+                        RichText::new(crate::date_ago::date_ago(entry.at))
+                            .small()
+                            .weak(),
+                    );
+                    if ui
+                        .link(best_name_from_pubkey_lookup(&entry.pubkey))
+                        .clicked()
+                    {
+                        open = Some(Page::Person(entry.pubkey));
+                    }
+                    let action = RichText::new(entry.action.as_str());
+                    ui.label(match entry.action {
+                        FollowBackAction::Throttled | FollowBackAction::Declined => action.weak(),
+                        _ => action.strong(),
+                    });
+                    ui.label(format!("by '{}'", entry.rule_name));
+                    if matches!(
+                        entry.action,
+                        FollowBackAction::Queued
+                            | FollowBackAction::Added
+                            | FollowBackAction::Throttled
+                    ) {
+                        ui.label(
+                            RichText::new(format!("{} mutual follows", entry.mutual_follows))
+                                .small()
+                                .weak(),
+                        );
+                    }
+                });
+            }
+        });
+
+    if let Some(page) = open {
+        app.set_page(ctx, page);
+    }
+}
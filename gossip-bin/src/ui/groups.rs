@@ -0,0 +1,93 @@
+use super::{widgets, GossipUi, Page};
+use eframe::egui;
+use egui::{Context, RichText, Ui};
+use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::{FeedKind, GLOBALS};
+use nostr_types::RelayUrl;
+
+pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        ui.heading("Groups");
+    });
+    ui.label("Relay-based groups (NIP-29). Each group lives on one relay, which decides who may join and post.");
+    ui.add_space(10.0);
+
+    ui.horizontal(|ui| {
+        ui.label("Relay:");
+        ui.add(
+            text_edit_line!(app, app.groups_new_relay)
+                .hint_text("wss://groups.example.com")
+                .desired_width(250.0),
+        );
+        ui.label("Group id:");
+        ui.add(text_edit_line!(app, app.groups_new_id).desired_width(150.0));
+        if ui.button("Join").clicked() {
+            match RelayUrl::try_from_str(app.groups_new_relay.trim()) {
+                Ok(relay) if !app.groups_new_id.trim().is_empty() => {
+                    let id = app.groups_new_id.trim().to_owned();
+                    let _ = GLOBALS
+                        .to_overlord
+                        .send(ToOverlordMessage::JoinGroup(relay.clone(), id.clone()));
+                    app.groups_new_relay.clear();
+                    app.groups_new_id.clear();
+                    app.set_page(ctx, Page::Feed(FeedKind::Group(relay, id)));
+                }
+                Ok(_) => GLOBALS
+                    .status_queue
+                    .write()
+                    .write("Enter the id of the group.".to_owned()),
+                Err(e) => GLOBALS.status_queue.write().write(format!("{}", e)),
+            }
+        }
+    });
+
+    ui.add_space(10.0);
+    ui.separator();
+
+    let groups = match gossip_lib::groups::joined_groups() {
+        Ok(groups) => groups,
+        Err(e) => {
+            tracing::error!("{}", e);
+            vec![]
+        }
+    };
+
+    if groups.is_empty() {
+        ui.add_space(10.0);
+        ui.label("You have not joined any groups.");
+        return;
+    }
+
+    let mut open = None;
+    app.vert_scroll_area().id_source("groups").show(ui, |ui| {
+        for group in groups.iter() {
+            widgets::list_entry::make_frame(ui, Some(app.theme.main_content_bgcolor())).show(
+                ui,
+                |ui| {
+                    ui.set_min_width(ui.available_width());
+                    ui.horizontal(|ui| {
+                        if ui
+                            .link(RichText::new(group.display_name()).heading())
+                            .clicked()
+                        {
+                            open = Some(Page::Feed(FeedKind::Group(
+                                group.relay.clone(),
+                                group.id.clone(),
+                            )));
+                        }
+                        ui.label(RichText::new(group.relay.as_str()).small().weak());
+                    });
+                    if !group.about.is_empty() {
+                        ui.label(&group.about);
+                    }
+                },
+            );
+            ui.add_space(4.0);
+        }
+    });
+
+    if let Some(page) = open {
+        app.set_page(ctx, page);
+    }
+}
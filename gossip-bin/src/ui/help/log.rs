@@ -0,0 +1,78 @@
+use super::GossipUi;
+use crate::ui::widgets;
+use eframe::egui;
+use egui::{Color32, Context, RichText, Ui};
+use gossip_lib::GLOBALS;
+use tracing::Level;
+
+// How many lines to show at once
+const SHOWN_LINES: usize = 1000;
+
+pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
+    ui.add_space(10.0);
+    ui.heading("Log".to_string());
+    ui.add_space(12.0);
+    ui.separator();
+
+    ui.add_space(10.0);
+
+    ui.horizontal(|ui| {
+        ui.label("Filter:");
+        widgets::TextEdit::singleline(&app.theme, &mut app.log_filter)
+            .hint_text("e.g. gossip_lib::overlord::minion=debug")
+            .desired_width(400.0)
+            .show(ui);
+        if ui.button("Apply").clicked() {
+            match gossip_lib::logging::set_filter(&app.log_filter) {
+                Ok(()) => GLOBALS
+                    .status_queue
+                    .write()
+                    .write("Log filter applied".to_owned()),
+                Err(e) => GLOBALS.status_queue.write().write(format!("{}", e)),
+            }
+        }
+    })
+    .response
+    .on_hover_text(
+        "Comma-separated levels for modules, as in RUST_LOG, added to RUST_LOG. Kept across restarts.",
+    );
+
+    ui.horizontal(|ui| {
+        ui.label("Search:");
+        widgets::TextEdit::singleline(&app.theme, &mut app.log_search)
+            .desired_width(400.0)
+            .show(ui);
+        if ui.button("Clear log").clicked() {
+            gossip_lib::logging::clear();
+        }
+    });
+
+    ui.add_space(6.0);
+    ui.separator();
+
+    let lines = gossip_lib::logging::recent(Level::TRACE, &app.log_search, SHOWN_LINES);
+
+    app.vert_scroll_area()
+        .id_source("log_lines")
+        .stick_to_bottom(true)
+        .show(ui, |ui| {
+            for line in lines.iter() {
+                let color = match line.level {
+                    Level::ERROR => Color32::LIGHT_RED,
+                    Level::WARN => Color32::YELLOW,
+                    _ => ui.visuals().text_color(),
+                };
+                let time = time::OffsetDateTime::from_unix_timestamp(line.time.0)
+                    .map(|t| format!("{:02}:{:02}:{:02}", t.hour(), t.minute(), t.second()))
+                    .unwrap_or_default();
+                ui.label(
+                    RichText::new(format!(
+                        "{} {:>5} {}: {}",
+                        time, line.level, line.target, line.message
+                    ))
+                    .monospace()
+                    .color(color),
+                );
+            }
+        });
+}
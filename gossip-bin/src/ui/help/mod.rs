@@ -4,6 +4,7 @@ use egui::{Context, Ui};
 use gossip_lib::PersonList;
 
 mod about;
+mod log;
 mod stats;
 
 pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
@@ -78,6 +79,8 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
         });
     } else if app.page == Page::HelpStats {
         stats::update(app, ctx, _frame, ui);
+    } else if app.page == Page::HelpLog {
+        log::update(app, ctx, _frame, ui);
     } else if app.page == Page::HelpAbout {
         about::update(app, ctx, _frame, ui);
     }
@@ -1,8 +1,9 @@
 use super::GossipUi;
 use eframe::egui;
 use egui::{Context, Ui};
-use gossip_lib::GLOBALS;
+use gossip_lib::{GLOBALS, SLOW_TXN_MILLIS};
 use humansize::{format_size, DECIMAL};
+use nostr_types::Unixtime;
 use std::sync::atomic::Ordering;
 
 pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
@@ -23,6 +24,27 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
 
         ui.add_space(6.0);
 
+        let ck_stats = GLOBALS.identity.conversation_key_cache_stats();
+        ui.label(format!(
+            "NIP-44 conversation keys cached: {} ({:.0}% hit rate over {} lookups)",
+            ck_stats.entries,
+            ck_stats.hit_rate() * 100.0,
+            ck_stats.hits + ck_stats.misses
+        ));
+
+        for stats in GLOBALS.storage.read_cache_stats().iter() {
+            ui.label(format!(
+                "Cached {} records: {} of {} ({:.0}% hit rate over {} reads)",
+                stats.table,
+                stats.entries,
+                stats.capacity,
+                stats.hit_rate() * 100.0,
+                stats.hits + stats.misses
+            ));
+        }
+
+        ui.add_space(6.0);
+
         ui.label(format!(
             "HTTP Requests in flight: {}",
             GLOBALS.fetcher.requests_in_flight()
@@ -33,6 +55,34 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
             GLOBALS.fetcher.requests_queued()
         ));
 
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "HTTP Requests failed or deferred: {}",
+                GLOBALS.fetcher.requests_failed()
+            ));
+            if ui.button("Retry failed fetches").clicked() {
+                GLOBALS.fetcher.retry_all_failed();
+            }
+        });
+
+        let now = Unixtime::now().unwrap();
+        for (host, health) in GLOBALS.fetcher.host_health().iter() {
+            if health.failures == 0 {
+                continue;
+            }
+            let backoff = match health.backoff_until {
+                Some(until) if until > now => format!(", backed off for {}s", until.0 - now.0),
+                _ => "".to_owned(),
+            };
+            ui.label(format!(
+                "  {}: {:.0}% of {} fetches failed{}",
+                host,
+                health.failure_rate() * 100.0,
+                health.successes + health.failures,
+                backoff
+            ));
+        }
+
         ui.add_space(6.0);
         ui.separator();
         ui.add_space(6.0);
@@ -94,6 +144,19 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
         ));
         ui.add_space(6.0);
 
+        ui.label(format!(
+            "Event Media: {} records",
+            GLOBALS.storage.get_event_media_len().unwrap_or(0)
+        ));
+        ui.add_space(6.0);
+
+        ui.label(format!(
+            "Event Media Galleries: {} by author, {} by hashtag",
+            GLOBALS.storage.get_event_media_by_author_len().unwrap_or(0),
+            GLOBALS.storage.get_event_media_by_hashtag_len().unwrap_or(0)
+        ));
+        ui.add_space(6.0);
+
         ui.label(format!(
             "Hashtags: {} records",
             GLOBALS.storage.get_hashtags_len().unwrap_or(0)
@@ -141,5 +204,204 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
             GLOBALS.storage.get_nip46servers_len().unwrap_or(0)
         ));
         ui.add_space(6.0);
+
+        ui.label(format!(
+            "Nip46 Permissions: {} records",
+            GLOBALS.storage.get_nip46_permissions_len().unwrap_or(0)
+        ));
+        ui.add_space(6.0);
+
+        ui.label(format!(
+            "GiftWraps awaiting unlock: {} records",
+            GLOBALS.storage.get_unindexed_giftwraps_len().unwrap_or(0)
+        ));
+        ui.add_space(6.0);
+
+        ui.label(format!(
+            "Unwrapped GiftWraps: {} records",
+            GLOBALS.storage.get_rumors_len().unwrap_or(0)
+        ));
+        ui.add_space(6.0);
+
+        ui.label(format!(
+            "RSS Entries: {} records",
+            GLOBALS.storage.get_rss_entries_len().unwrap_or(0)
+        ));
+        ui.add_space(6.0);
+
+        ui.label(format!(
+            "Corrupt Records: {} records",
+            GLOBALS.storage.get_corrupt_records_len().unwrap_or(0)
+        ));
+        ui.add_space(6.0);
+
+        ui.label(format!(
+            "Reaction Usage: {} records",
+            GLOBALS.storage.get_reaction_usage_len().unwrap_or(0)
+        ));
+        ui.add_space(6.0);
+
+        ui.label(format!(
+            "Interaction Scores: {} records",
+            GLOBALS.storage.get_interaction_scores_len().unwrap_or(0)
+        ));
+        ui.add_space(6.0);
+
+        ui.label(format!(
+            "Media Hashes: {} records",
+            GLOBALS.storage.get_media_hashes_len().unwrap_or(0)
+        ));
+        ui.add_space(6.0);
+
+        ui.label(format!(
+            "Mute Log: {} records",
+            GLOBALS.storage.get_mute_log_len().unwrap_or(0)
+        ));
+        ui.add_space(6.0);
+
+        ui.label(format!(
+            "Starred Events: {} records",
+            GLOBALS.storage.get_stars_len().unwrap_or(0)
+        ));
+        ui.add_space(6.0);
+
+        ui.label(format!(
+            "Relay Authentication Keys: {} records",
+            GLOBALS.storage.get_relay_auth_keys_len().unwrap_or(0)
+        ));
+        ui.add_space(6.0);
+
+        ui.label(format!(
+            "Relay Groups: {} records",
+            GLOBALS.storage.get_relay_groups_len().unwrap_or(0)
+        ));
+        ui.add_space(6.0);
+
+        ui.label(format!(
+            "Zap Receipts: {} records",
+            GLOBALS.storage.get_zap_receipts_len().unwrap_or(0)
+        ));
+        ui.add_space(6.0);
+
+        ui.label(format!(
+            "Live Activities: {} records",
+            GLOBALS.storage.get_live_activities_len().unwrap_or(0)
+        ));
+        ui.add_space(6.0);
+
+        ui.label(format!(
+            "Groups: {} records",
+            GLOBALS.storage.get_groups_len().unwrap_or(0)
+        ));
+        ui.add_space(6.0);
+
+        ui.label(format!(
+            "Communities: {} records",
+            GLOBALS.storage.get_communities_len().unwrap_or(0)
+        ));
+        ui.add_space(6.0);
+
+        ui.label(format!(
+            "DVM Jobs: {} records",
+            GLOBALS.storage.get_dvm_jobs_len().unwrap_or(0)
+        ));
+        ui.add_space(6.0);
+
+        ui.label(format!(
+            "App Data: {} records",
+            GLOBALS.storage.get_app_data_len().unwrap_or(0)
+        ));
+        ui.add_space(6.0);
+
+        ui.separator();
+        ui.add_space(6.0);
+
+        ui.heading("Write Transactions");
+        ui.label(format!(
+            "Where write transactions were started, longest held first. Over {}ms is slow: all other writers wait for it.",
+            SLOW_TXN_MILLIS
+        ));
+        ui.add_space(6.0);
+        for site in GLOBALS.storage.txn_report().iter().take(20) {
+            ui.label(format!(
+                "{}:{}  {} held, mean {}ms, max {}ms, {} slow",
+                site.file,
+                site.line,
+                site.count,
+                site.mean().as_millis(),
+                site.max.as_millis(),
+                site.slow
+            ));
+        }
+
+        let slow = GLOBALS.storage.slow_txns();
+        if !slow.is_empty() {
+            ui.add_space(6.0);
+            ui.label("Recent slow write transactions:");
+            for txn in slow.iter().take(20) {
+                ui.label(format!(
+                    "{}  {}:{}  {}ms{}",
+                    crate::date_ago::date_ago(txn.ended),
+                    txn.file,
+                    txn.line,
+                    txn.held.as_millis(),
+                    if txn.committed { "" } else { " (aborted)" }
+                ));
+            }
+        }
+        ui.add_space(6.0);
+
+        ui.separator();
+        ui.add_space(6.0);
+
+        ui.heading("Database Health");
+        ui.label("The size of each table on disk, largest first.");
+        ui.add_space(6.0);
+        match GLOBALS.storage.stats() {
+            Ok(stats) => {
+                for table in stats.iter() {
+                    let last_write = match table.last_write {
+                        Some(at) => format!(", last written {}", crate::date_ago::date_ago(at)),
+                        None => "".to_owned(),
+                    };
+                    ui.label(format!(
+                        "{}: {} records, {} ({} branch, {} leaf, {} overflow pages){}",
+                        table.name,
+                        table.records,
+                        format_size(table.bytes, DECIMAL),
+                        table.branch_pages,
+                        table.leaf_pages,
+                        table.overflow_pages,
+                        last_write
+                    ));
+                }
+            }
+            Err(e) => {
+                ui.label(format!("Could not read table statistics: {}", e));
+            }
+        }
+        ui.add_space(6.0);
+
+        ui.separator();
+        ui.add_space(6.0);
+
+        ui.heading("Table Reads and Writes");
+        ui.label("Records read and written per table since startup, and how long decoding takes.");
+        ui.add_space(6.0);
+        for (table, metrics) in GLOBALS.storage.table_metrics().iter() {
+            ui.label(format!(
+                "{}: {} reads ({} bytes, mean decode {}µs), {} writes ({} bytes)",
+                table,
+                metrics.reads,
+                metrics.bytes_read,
+                metrics.mean_decode_time().as_micros(),
+                metrics.writes,
+                metrics.bytes_written
+            ));
+        }
+        if ui.button("Reset").clicked() {
+            GLOBALS.storage.reset_table_metrics();
+        }
+        ui.add_space(6.0);
     });
 }
@@ -0,0 +1,118 @@
+use super::{widgets, GossipUi};
+use eframe::egui;
+use egui::{Context, Label, RichText, Ui};
+use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::names::best_name_from_pubkey_lookup;
+use gossip_lib::GLOBALS;
+use std::time::{Duration, Instant};
+
+pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
+    // Possibly refresh the activities and chat (every 2 seconds)
+    if app.live_next_refresh < Instant::now() {
+        app.live_cache = match gossip_lib::live::live_now_from_follows() {
+            Ok(activities) => activities,
+            Err(e) => {
+                tracing::error!("{}", e);
+                vec![]
+            }
+        };
+        app.live_chat_messages = match &app.live_chat {
+            Some(activity) => gossip_lib::live::live_chat(&activity.addr()).unwrap_or_default(),
+            None => vec![],
+        };
+        app.live_next_refresh = Instant::now() + Duration::new(2, 0);
+    }
+
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        ui.heading("Live now");
+    });
+    ui.label(
+        "Live streams and other live activities (NIP-53) hosted by or featuring people you follow.",
+    );
+    ui.add_space(10.0);
+    ui.separator();
+
+    if let Some(activity) = app.live_chat.clone() {
+        ui.horizontal(|ui| {
+            ui.heading(format!("Chat: {}", activity.title));
+            if ui.button("Close chat").clicked() {
+                app.live_chat = None;
+                app.live_chat_messages.clear();
+                let _ = GLOBALS
+                    .to_overlord
+                    .send(ToOverlordMessage::UnsubscribeLiveChat);
+            }
+        });
+        app.vert_scroll_area()
+            .id_source("live_chat")
+            .max_height(300.0)
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                for event in app.live_chat_messages.iter() {
+                    ui.horizontal_wrapped(|ui| {
+                        ui.label(
+                            RichText::new(best_name_from_pubkey_lookup(&event.pubkey)).strong(),
+                        );
+                        ui.label(&event.content);
+                    });
+                }
+            });
+        ui.separator();
+    }
+
+    if app.live_cache.is_empty() {
+        ui.add_space(10.0);
+        ui.label("Nobody you follow is live right now.");
+        return;
+    }
+
+    let mut open_chat = None;
+    app.vert_scroll_area().id_source("live_now").show(ui, |ui| {
+        let color = app.theme.accent_color();
+        for activity in app.live_cache.iter() {
+            widgets::list_entry::make_frame(ui, Some(app.theme.main_content_bgcolor())).show(
+                ui,
+                |ui| {
+                    ui.set_min_width(ui.available_width());
+                    ui.horizontal_wrapped(|ui| {
+                        ui.add(Label::new(
+                            RichText::new(best_name_from_pubkey_lookup(&activity.host))
+                                .small()
+                                .color(color),
+                        ));
+                        if let Some(starts) = activity.starts {
+                            ui.label(format!("started {}", crate::date_ago::date_ago(starts)));
+                        }
+                        if !activity.participants.is_empty() {
+                            ui.label(format!("{} taking part", activity.participants.len()));
+                        }
+                    });
+                    if activity.streaming.is_empty() {
+                        ui.heading(&activity.title);
+                    } else {
+                        ui.hyperlink_to(
+                            RichText::new(&activity.title).heading(),
+                            &activity.streaming,
+                        );
+                    }
+                    if !activity.summary.is_empty() {
+                        ui.label(&activity.summary);
+                    }
+                    if ui.button("Chat").clicked() {
+                        open_chat = Some(activity.clone());
+                    }
+                },
+            );
+            ui.add_space(4.0);
+        }
+    });
+
+    if let Some(activity) = open_chat {
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::SubscribeLiveChat(activity.addr()));
+        app.live_chat = Some(activity);
+        app.live_next_refresh = Instant::now();
+    }
+}
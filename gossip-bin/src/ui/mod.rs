@@ -34,13 +34,19 @@ macro_rules! write_setting {
 }
 
 mod assets;
+mod communities;
 mod components;
 mod dm_chat_list;
+mod dvm;
 mod feed;
+mod follow_back;
+mod groups;
 mod help;
+mod live;
 mod notifications;
 mod people;
 mod relays;
+mod rss;
 mod search;
 mod settings;
 mod theme;
@@ -65,12 +71,14 @@ use egui_winit::egui::Response;
 use egui_winit::egui::ViewportBuilder;
 use gossip_lib::comms::ToOverlordMessage;
 use gossip_lib::{
-    DmChannel, DmChannelData, Error, FeedKind, Person, PersonList, Private, RunState, ZapState,
-    GLOBALS,
+    DmChannel, DmChannelData, Error, FeedKind, LiveActivity, Person, PersonList, Private, RssEntry,
+    RunState, ZapState, GLOBALS,
 };
 use nostr_types::ContentSegment;
 use nostr_types::RelayUrl;
-use nostr_types::{Id, Metadata, MilliSatoshi, Profile, PublicKey, UncheckedUrl, Url};
+use nostr_types::{
+    Event, Id, Metadata, MilliSatoshi, Profile, PublicKey, UncheckedUrl, Unixtime, Url,
+};
 
 use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
@@ -150,19 +158,29 @@ enum Page {
     Notifications,
     PeopleLists,
     PeopleList(PersonList),
+    FollowBack,
     Person(PublicKey),
     YourKeys,
     YourMetadata,
     YourDelegation,
     YourNostrConnect,
+    YourWallet,
     RelaysActivityMonitor,
     RelaysCoverage,
+    RelaysFeedSources,
     RelaysMine,
     RelaysKnownNetwork(Option<RelayUrl>),
+    RelaysQuery,
+    RssFeed,
+    LiveNow,
+    Groups,
+    Communities,
+    Dvm,
     Search,
     Settings,
     HelpHelp,
     HelpStats,
+    HelpLog,
     HelpAbout,
     #[allow(unused)]
     ThemeTest,
@@ -184,9 +202,15 @@ impl Page {
     pub fn to_readable(&self) -> (&'static str /* Category */, String /* Name */) {
         match self {
             Page::DmChatList => (SubMenu::Feeds.as_str(), "Private chats".into()),
+            Page::RssFeed => (SubMenu::Feeds.as_str(), "RSS".into()),
+            Page::LiveNow => (SubMenu::Feeds.as_str(), "Live now".into()),
+            Page::Groups => (SubMenu::Feeds.as_str(), "Groups".into()),
+            Page::Communities => (SubMenu::Feeds.as_str(), "Communities".into()),
+            Page::Dvm => (SubMenu::Feeds.as_str(), "Data Vending Machines".into()),
             Page::Feed(feedkind) => ("Feed", feedkind.to_string()),
             Page::Notifications => ("Notifications", "Notifications".into()),
             Page::PeopleLists => ("Lists", "Lists".into()),
+            Page::FollowBack => ("Lists", "Follow-back Rules".into()),
             Page::PeopleList(list) => {
                 let metadata = GLOBALS
                     .storage
@@ -203,14 +227,18 @@ impl Page {
             Page::YourMetadata => (SubMenu::Account.as_str(), "Profile".into()),
             Page::YourDelegation => (SubMenu::Account.as_str(), "Delegation".into()),
             Page::YourNostrConnect => (SubMenu::Account.as_str(), "Nostr Connect".into()),
+            Page::YourWallet => (SubMenu::Account.as_str(), "Wallet".into()),
             Page::RelaysActivityMonitor => (SubMenu::Relays.as_str(), "Active Relays".into()),
             Page::RelaysCoverage => (SubMenu::Relays.as_str(), "Coverage Report".into()),
+            Page::RelaysFeedSources => (SubMenu::Relays.as_str(), "Feed Sources".into()),
             Page::RelaysMine => (SubMenu::Relays.as_str(), "My Relays".into()),
             Page::RelaysKnownNetwork(_) => (SubMenu::Relays.as_str(), "Known Network".into()),
+            Page::RelaysQuery => (SubMenu::Relays.as_str(), "Relay Query".into()),
             Page::Search => ("Search", "Search".into()),
             Page::Settings => ("Settings", "Settings".into()),
             Page::HelpHelp => (SubMenu::Help.as_str(), "Troubleshooting".into()),
             Page::HelpStats => (SubMenu::Help.as_str(), "Stats".into()),
+            Page::HelpLog => (SubMenu::Help.as_str(), "Log".into()),
             Page::HelpAbout => (SubMenu::Help.as_str(), "About".into()),
             Page::ThemeTest => (SubMenu::Help.as_str(), "Theme Test".into()),
             Page::Wizard(wp) => ("Wizard", wp.as_str().to_string()),
@@ -240,11 +268,13 @@ impl Page {
         match self {
             Page::DmChatList => cat_name(self),
             Page::Feed(_) => name_cat(self),
-            Page::PeopleLists | Page::PeopleList(_) => cat_name(self),
+            Page::PeopleLists | Page::PeopleList(_) | Page::FollowBack => cat_name(self),
             Page::Person(_) => name_cat(self),
-            Page::YourKeys | Page::YourMetadata | Page::YourDelegation | Page::YourNostrConnect => {
-                cat_name(self)
-            }
+            Page::YourKeys
+            | Page::YourMetadata
+            | Page::YourDelegation
+            | Page::YourNostrConnect
+            | Page::YourWallet => cat_name(self),
             Page::Wizard(_) => name_cat(self),
             _ => name(self),
         }
@@ -325,6 +355,9 @@ pub struct DraftData {
     pub include_content_warning: bool,
     pub content_warning: String,
 
+    // alt text of media urls in the draft
+    pub alt_texts: HashMap<String, String>,
+
     // Data for normal draft
     pub repost: Option<Id>,
     pub replying_to: Option<Id>,
@@ -349,6 +382,7 @@ impl Default for DraftData {
             subject: "".to_owned(),
             include_content_warning: false,
             content_warning: "".to_owned(),
+            alt_texts: HashMap::new(),
 
             // The following are ignored for DMs
             repost: None,
@@ -374,6 +408,7 @@ impl DraftData {
         self.subject = "".to_owned();
         self.include_content_warning = false;
         self.content_warning = "".to_owned();
+        self.alt_texts.clear();
         self.repost = None;
         self.replying_to = None;
         self.tagging_search_substring = None;
@@ -470,6 +505,9 @@ struct GossipUi {
     // User entry: delegatee tag (as JSON string)
     delegatee_tag_str: String,
 
+    // User entry: name of a profile to switch to
+    profile_name: String,
+
     // User entry: general
     add_contact: String,
     password: String,
@@ -478,11 +516,15 @@ struct GossipUi {
     delete_confirm: bool,
     new_metadata_fieldname: String,
     import_priv: String,
+    relay_auth_url: String,
+    log_filter: String,
+    log_search: String,
     import_pub: String,
     search: String,
     entering_search_page: bool,
     editing_petname: bool,
     petname: String,
+    mute_reason: String,
     deleting_list: Option<PersonList>,
     creating_list: bool,
     list_name_field_needs_focus: bool,
@@ -493,6 +535,10 @@ struct GossipUi {
     nostr_connect_name: String,
     nostr_connect_relay1: String,
     nostr_connect_relay2: String,
+    nostr_connect_pair_relay: String,
+    bunker_uri: String,
+    nwc_uri: String,
+    nostr_connect_pairing: Option<gossip_lib::NostrConnectUri>,
 
     // Collapsed threads
     collapsed: Vec<Id>,
@@ -521,6 +567,48 @@ struct GossipUi {
     dm_channel_cache: Vec<DmChannelData>,
     dm_channel_next_refresh: Instant,
     dm_channel_error: Option<String>,
+    dm_show_requests: bool,
+
+    // Network settings page
+    blocked_relay_new: String,
+
+    // RSS feed page
+    rss_new_feed: String,
+    rss_cache: Vec<RssEntry>,
+    rss_next_refresh: Instant,
+
+    // Live activities page
+    live_cache: Vec<LiveActivity>,
+    live_next_refresh: Instant,
+    live_chat: Option<LiveActivity>,
+    live_chat_messages: Vec<Event>,
+
+    // Groups page, and the group feed
+    groups_new_relay: String,
+    groups_new_id: String,
+    group_draft: String,
+
+    // Communities page, and the community feed
+    communities_new: String,
+    community_draft: String,
+
+    // Data vending machines page
+    dvm_kind: u32,
+    dvm_input: String,
+    dvm_language: String,
+    dvm_provider: String,
+
+    // Other identities to gather notifications for (content settings)
+    notify_identity_key: String,
+    notify_identity_label: String,
+
+    // Follow-back rules page
+    follow_back_name: String,
+    follow_back_min_mutual: usize,
+    follow_back_list: PersonList,
+    follow_back_private: bool,
+    follow_back_approval: bool,
+    follow_back_per_day: usize,
 }
 
 impl Drop for GossipUi {
@@ -696,6 +784,7 @@ impl GossipUi {
             editing_metadata: false,
             metadata: Metadata::new(),
             delegatee_tag_str: "".to_owned(),
+            profile_name: "".to_owned(),
             add_contact: "".to_owned(),
             password: "".to_owned(),
             password2: "".to_owned(),
@@ -703,11 +792,15 @@ impl GossipUi {
             delete_confirm: false,
             new_metadata_fieldname: String::new(),
             import_priv: "".to_owned(),
+            relay_auth_url: "".to_owned(),
+            log_filter: gossip_lib::logging::filter(),
+            log_search: "".to_owned(),
             import_pub: "".to_owned(),
             search: "".to_owned(),
             entering_search_page: false,
             editing_petname: false,
             petname: "".to_owned(),
+            mute_reason: "".to_owned(),
             deleting_list: None,
             creating_list: false,
             list_name_field_needs_focus: false,
@@ -718,6 +811,10 @@ impl GossipUi {
             nostr_connect_name: "".to_owned(),
             nostr_connect_relay1: "".to_owned(),
             nostr_connect_relay2: "".to_owned(),
+            nostr_connect_pair_relay: "".to_owned(),
+            bunker_uri: "".to_owned(),
+            nwc_uri: "".to_owned(),
+            nostr_connect_pairing: None,
             collapsed: vec![],
             opened: HashSet::new(),
             visible_note_ids: vec![],
@@ -730,6 +827,32 @@ impl GossipUi {
             dm_channel_cache: vec![],
             dm_channel_next_refresh: Instant::now(),
             dm_channel_error: None,
+            dm_show_requests: false,
+            blocked_relay_new: String::new(),
+            rss_new_feed: String::new(),
+            rss_cache: vec![],
+            rss_next_refresh: Instant::now(),
+            live_cache: vec![],
+            live_next_refresh: Instant::now(),
+            live_chat: None,
+            live_chat_messages: vec![],
+            groups_new_relay: String::new(),
+            groups_new_id: String::new(),
+            group_draft: String::new(),
+            communities_new: String::new(),
+            community_draft: String::new(),
+            dvm_kind: gossip_lib::dvm::CONTENT_DISCOVERY_KIND,
+            dvm_input: String::new(),
+            dvm_language: "en".to_owned(),
+            dvm_provider: String::new(),
+            notify_identity_key: String::new(),
+            notify_identity_label: String::new(),
+            follow_back_name: String::new(),
+            follow_back_min_mutual: 3,
+            follow_back_list: PersonList::Followed,
+            follow_back_private: false,
+            follow_back_approval: true,
+            follow_back_per_day: 10,
         }
     }
 
@@ -786,6 +909,14 @@ impl GossipUi {
                 self.set_page_inner(ctx, page);
             }
 
+            // Stop reading a live chat that is no longer shown
+            if self.live_chat.is_some() && self.page != Page::LiveNow {
+                self.live_chat = None;
+                let _ = GLOBALS
+                    .to_overlord
+                    .send(ToOverlordMessage::UnsubscribeLiveChat);
+            }
+
             // Clear QR codes on page switches
             self.qr_codes.clear();
             self.render_qr = None;
@@ -853,22 +984,54 @@ impl GossipUi {
                 feed::enter_feed(self, FeedKind::Person(*pubkey));
                 self.close_all_menus_except_feeds(ctx);
             }
+            Page::Feed(FeedKind::Starred) => {
+                GLOBALS.feed.set_feed_to_starred();
+                feed::enter_feed(self, FeedKind::Starred);
+                self.close_all_menus_except_feeds(ctx);
+            }
+            Page::Feed(FeedKind::Group(relay, id)) => {
+                GLOBALS
+                    .feed
+                    .set_feed_to_group(relay.to_owned(), id.to_owned());
+                feed::enter_feed(self, FeedKind::Group(relay.clone(), id.clone()));
+                self.close_all_menus_except_feeds(ctx);
+            }
+            Page::Feed(FeedKind::Community(author, d)) => {
+                GLOBALS
+                    .feed
+                    .set_feed_to_community(author.to_owned(), d.to_owned());
+                feed::enter_feed(self, FeedKind::Community(*author, d.clone()));
+                self.close_all_menus_except_feeds(ctx);
+            }
+            Page::Feed(FeedKind::Dvm(request_id)) => {
+                GLOBALS.feed.set_feed_to_dvm(*request_id);
+                feed::enter_feed(self, FeedKind::Dvm(*request_id));
+                self.close_all_menus_except_feeds(ctx);
+            }
             Page::PeopleLists => {
                 people::enter_page(self);
                 self.close_all_menus_except_feeds(ctx);
             }
             Page::Person(pubkey) => {
                 self.close_all_menus_except_feeds(ctx);
-                // Fetch metadata for that person at the page switch
+                // Fetch what a profile view asks for at the page switch
                 // (this bypasses checking if it was done recently)
                 let _ = GLOBALS
                     .to_overlord
-                    .send(ToOverlordMessage::UpdateMetadata(*pubkey));
+                    .send(ToOverlordMessage::ViewProfile(*pubkey));
             }
-            Page::YourKeys | Page::YourMetadata | Page::YourDelegation | Page::YourNostrConnect => {
+            Page::YourKeys
+            | Page::YourMetadata
+            | Page::YourDelegation
+            | Page::YourNostrConnect
+            | Page::YourWallet => {
                 self.open_menu(ctx, SubMenu::Account);
             }
-            Page::RelaysActivityMonitor | Page::RelaysCoverage | Page::RelaysMine => {
+            Page::RelaysActivityMonitor
+            | Page::RelaysCoverage
+            | Page::RelaysFeedSources
+            | Page::RelaysMine
+            | Page::RelaysQuery => {
                 self.relays.enter_page(None);
                 self.open_menu(ctx, SubMenu::Relays);
             }
@@ -883,7 +1046,7 @@ impl GossipUi {
             Page::Settings => {
                 self.close_all_menus_except_feeds(ctx);
             }
-            Page::HelpHelp | Page::HelpStats | Page::HelpAbout => {
+            Page::HelpHelp | Page::HelpStats | Page::HelpLog | Page::HelpAbout => {
                 self.open_menu(ctx, SubMenu::Help);
             }
             _ => {
@@ -1025,6 +1188,14 @@ impl GossipUi {
                     }
                 }
 
+                // Local stars
+                if self
+                    .add_selected_label(ui, self.page == Page::Feed(FeedKind::Starred), "Starred")
+                    .clicked()
+                {
+                    self.set_page(ctx, Page::Feed(FeedKind::Starred));
+                }
+
                 // Private chats
                 if GLOBALS.identity.is_unlocked() {
                     if self
@@ -1035,6 +1206,50 @@ impl GossipUi {
                     }
                 }
 
+                // Groups
+                if self
+                    .add_selected_label(ui, self.page == Page::Groups, "Groups")
+                    .clicked()
+                {
+                    self.set_page(ctx, Page::Groups);
+                }
+
+                // Communities
+                if self
+                    .add_selected_label(ui, self.page == Page::Communities, "Communities")
+                    .clicked()
+                {
+                    self.set_page(ctx, Page::Communities);
+                }
+
+                // Data vending machines
+                if self
+                    .add_selected_label(ui, self.page == Page::Dvm, "DVMs")
+                    .clicked()
+                {
+                    self.set_page(ctx, Page::Dvm);
+                }
+
+                // RSS
+                if GLOBALS.storage.read_setting_rss_enabled() {
+                    if self
+                        .add_selected_label(ui, self.page == Page::RssFeed, "RSS")
+                        .clicked()
+                    {
+                        self.set_page(ctx, Page::RssFeed);
+                    }
+                }
+
+                // Live activities
+                if GLOBALS.storage.read_setting_live_activities() {
+                    if self
+                        .add_selected_label(ui, self.page == Page::LiveNow, "Live now")
+                        .clicked()
+                    {
+                        self.set_page(ctx, Page::LiveNow);
+                    }
+                }
+
                 // Search
                 if self
                     .add_selected_label(ui, self.page == Page::Search, "Search")
@@ -1053,6 +1268,12 @@ impl GossipUi {
                     {
                         self.set_page(ctx, Page::PeopleLists);
                     }
+                    if self
+                        .add_selected_label(ui, self.page == Page::FollowBack, "Follow-back Rules")
+                        .clicked()
+                    {
+                        self.set_page(ctx, Page::FollowBack);
+                    }
                 }
 
                 // ---- Relays SubMenu ----
@@ -1063,6 +1284,8 @@ impl GossipUi {
                         self.add_menu_item_page(ui, Page::RelaysActivityMonitor, None, true);
                         self.add_menu_item_page(ui, Page::RelaysMine, None, true);
                         self.add_menu_item_page(ui, Page::RelaysKnownNetwork(None), None, true);
+                        self.add_menu_item_page(ui, Page::RelaysQuery, None, true);
+                        self.add_menu_item_page(ui, Page::RelaysFeedSources, None, true);
                         ui.vertical(|ui| {
                             ui.spacing_mut().button_padding *= 2.0;
                             ui.visuals_mut().widgets.inactive.weak_bg_fill =
@@ -1093,6 +1316,7 @@ impl GossipUi {
                         self.add_menu_item_page(ui, Page::YourKeys, None, true);
                         self.add_menu_item_page(ui, Page::YourDelegation, None, true);
                         self.add_menu_item_page(ui, Page::YourNostrConnect, None, true);
+                        self.add_menu_item_page(ui, Page::YourWallet, None, true);
                     });
                     self.after_openable_menu(ui, &cstate);
                 }
@@ -1112,6 +1336,7 @@ impl GossipUi {
                     cstate.show_body_indented(&header_response, ui, |ui| {
                         self.add_menu_item_page(ui, Page::HelpHelp, None, true);
                         self.add_menu_item_page(ui, Page::HelpStats, None, true);
+                        self.add_menu_item_page(ui, Page::HelpLog, None, true);
                         self.add_menu_item_page(ui, Page::HelpAbout, None, true);
                     });
                     self.after_openable_menu(ui, &cstate);
@@ -1466,6 +1691,7 @@ impl eframe::App for GossipUi {
             ZapState::SeekingAmount(id, _, _, _) => Some(id),
             ZapState::LoadingInvoice(id, _) => Some(id),
             ZapState::ReadyToPay(id, _) => Some(id),
+            ZapState::PayingWithWallet(id) => Some(id),
         };
 
         egui::CentralPanel::default()
@@ -1490,14 +1716,23 @@ impl eframe::App for GossipUi {
                     Page::YourKeys
                     | Page::YourMetadata
                     | Page::YourDelegation
-                    | Page::YourNostrConnect => you::update(self, ctx, frame, ui),
+                    | Page::YourNostrConnect
+                    | Page::YourWallet => you::update(self, ctx, frame, ui),
                     Page::RelaysActivityMonitor
                     | Page::RelaysCoverage
+                    | Page::RelaysFeedSources
                     | Page::RelaysMine
-                    | Page::RelaysKnownNetwork(_) => relays::update(self, ctx, frame, ui),
+                    | Page::RelaysKnownNetwork(_)
+                    | Page::RelaysQuery => relays::update(self, ctx, frame, ui),
+                    Page::RssFeed => rss::update(self, ctx, frame, ui),
+                    Page::LiveNow => live::update(self, ctx, frame, ui),
+                    Page::Groups => groups::update(self, ctx, frame, ui),
+                    Page::Communities => communities::update(self, ctx, frame, ui),
+                    Page::Dvm => dvm::update(self, ctx, frame, ui),
+                    Page::FollowBack => follow_back::update(self, ctx, frame, ui),
                     Page::Search => search::update(self, ctx, frame, ui),
                     Page::Settings => settings::update(self, ctx, frame, ui),
-                    Page::HelpHelp | Page::HelpStats | Page::HelpAbout => {
+                    Page::HelpHelp | Page::HelpStats | Page::HelpLog | Page::HelpAbout => {
                         help::update(self, ctx, frame, ui)
                     }
                     Page::ThemeTest => theme::test_page::update(self, ctx, frame, ui),
@@ -1596,9 +1831,30 @@ impl GossipUi {
 
                 // Do not show 'Mute' if this is yourself
                 if muted || !is_self {
+                    if !muted {
+                        ui.add(
+                            text_edit_line!(app, app.mute_reason)
+                                .hint_text("Reason for muting (optional)"),
+                        );
+                    }
                     let mute_label = if muted { "Unmute" } else { "Mute" };
                     if ui.button(mute_label).clicked() {
-                        let _ = GLOBALS.people.mute(&person.pubkey, !muted, Private(false));
+                        let reason = std::mem::take(&mut app.mute_reason);
+                        let _ = GLOBALS.people.mute_with_reason(
+                            &person.pubkey,
+                            !muted,
+                            Private(false),
+                            Some(reason),
+                            None,
+                        );
+                        app.notes.cache_invalidate_person(&person.pubkey);
+                    }
+                    if !muted && ui.button("Mute for a day").clicked() {
+                        let until =
+                            Unixtime::now().unwrap() + std::time::Duration::from_secs(86400);
+                        let _ = GLOBALS
+                            .people
+                            .mute_until(&person.pubkey, until, Private(false));
                         app.notes.cache_invalidate_person(&person.pubkey);
                     }
                 }
@@ -1713,6 +1969,15 @@ impl GossipUi {
             return Some(th.to_owned());
         }
 
+        // the same image may already be loaded from another url
+        for other in GLOBALS.media.same_content(&url) {
+            if let Some(th) = self.images.get(&other) {
+                let th = th.to_owned();
+                self.images.insert(url, th.clone());
+                return Some(th);
+            }
+        }
+
         if let Some(rgba_image) = GLOBALS.media.get_image(&url) {
             let current_size = [rgba_image.width() as usize, rgba_image.height() as usize];
             let pixels = rgba_image.as_flat_samples();
@@ -2039,10 +2304,18 @@ impl GossipUi {
             ZapState::LoadingInvoice(_id, _pubkey) => {
                 ui.label("Loading zap invoice...");
             }
-            ZapState::ReadyToPay(_id, ref invoice) => {
+            ZapState::ReadyToPay(id, ref invoice) => {
+                if gossip_lib::nwc::is_connected() && ui.button("Pay with Wallet").clicked() {
+                    let _ = GLOBALS
+                        .to_overlord
+                        .send(ToOverlordMessage::PayZapInvoice(id, invoice.to_owned()));
+                }
                 // we have to copy it and get out of the borrow first
                 qr_string = Some(invoice.to_owned());
             }
+            ZapState::PayingWithWallet(_id) => {
+                ui.label("Paying with your wallet...");
+            }
         };
 
         if let Some(qr) = qr_string {
@@ -2281,8 +2554,36 @@ fn wait_for_data_migration(app: &mut GossipUi, ctx: &Context) {
             })
         })
         .show(ctx, |ui| {
-            ui.centered_and_justified(|ui| {
-                ui.heading("Please wait for the data migration to complete...");
-            });
+            let progress = GLOBALS.storage.migration_progress().borrow().clone();
+            match progress {
+                Some(progress) => {
+                    ui.add_space(100.0);
+                    ui.vertical_centered(|ui| {
+                        ui.heading("Please wait for the data migration to complete...");
+                        ui.add_space(20.0);
+                        ui.label(&progress.step);
+                        ui.add(
+                            egui::ProgressBar::new(progress.fraction())
+                                .desired_width(400.0)
+                                .show_percentage(),
+                        );
+                        ui.label(format!("{} of {}", progress.processed, progress.total));
+                        if let Some(eta) = progress.eta() {
+                            let secs = eta.as_secs();
+                            if secs < 90 {
+                                ui.label(format!("About {}s left", secs));
+                            } else {
+                                ui.label(format!("About {}m left", secs / 60));
+                            }
+                        }
+                    });
+                }
+                None => {
+                    ui.centered_and_justified(|ui| {
+                        ui.heading("Please wait for the data migration to complete...");
+                    });
+                }
+            }
         });
+    ctx.request_repaint_after(std::time::Duration::from_secs(1));
 }
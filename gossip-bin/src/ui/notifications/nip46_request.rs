@@ -91,7 +91,8 @@ impl<'a> Notification<'a> for Nip46Request {
                             strip.cell(|ui| {
                                 let text = format!(
                                     "NIP-46 Request from '{}'. Allow {}?",
-                                    self.client_name, self.command.method
+                                    self.client_name,
+                                    gossip_lib::nip46::permission_of(&self.command)
                                 );
                                 widgets::truncated_label(
                                     ui,
@@ -111,7 +112,7 @@ impl<'a> Notification<'a> for Nip46Request {
                                                 ToOverlordMessage::Nip46ServerOpApprovalResponse(
                                                     self.account,
                                                     self.command.clone(),
-                                                    Approval::None,
+                                                    if self.remember { Approval::None } else { Approval::Ask },
                                                 ),
                                             );
                                         }
@@ -133,7 +134,7 @@ impl<'a> Notification<'a> for Nip46Request {
                                     ui.label("Remember");
                                     widgets::Switch::large(theme, &mut self.remember)
                                         .show(ui)
-                                        .on_hover_text("store permission permanently");
+                                        .on_hover_text("store the decision for this client (and kind of event)");
                                 });
                             });
                         });
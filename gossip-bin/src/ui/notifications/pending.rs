@@ -68,6 +68,14 @@ impl<'a> Notification<'a> for Pending {
             PendingItem::PersonListNotPublishedRecently(list) => {
                 self.person_list_not_published_recently(theme, ui, list)
             }
+            PendingItem::ReplaceableConflict { kind, ref d } => {
+                let d = d.clone();
+                self.replaceable_conflict(theme, ui, kind, d)
+            }
+            PendingItem::DuplicatePost { id, previous } => {
+                self.duplicate_post(theme, ui, id, previous)
+            }
+            PendingItem::FollowBack { rule, pubkey } => self.follow_back(theme, ui, rule, pubkey),
         }
     }
 }
@@ -220,6 +228,181 @@ impl Pending {
         self.layout(theme, ui, description, action)
     }
 
+    fn replaceable_conflict(
+        &mut self,
+        theme: &Theme,
+        ui: &mut Ui,
+        kind: u32,
+        d: String,
+    ) -> Option<Page> {
+        let name = GLOBALS.kinds.describe_number(kind).name;
+
+        let description = |_theme: &Theme, ui: &mut Ui| -> Option<Page> {
+            if d.is_empty() {
+                ui.label(format!(
+                    "Your {} was changed by another client since gossip last published it",
+                    name
+                ));
+            } else {
+                ui.label(format!(
+                    "Your {} '{}' was changed by another client since gossip last published it",
+                    name, d
+                ));
+            }
+            None
+        };
+        let action = |theme: &Theme, ui: &mut Ui| -> Option<Page> {
+            let mut new_page = None;
+            let page = match kind {
+                0 => Some(Page::YourMetadata),
+                10002 => Some(Page::RelaysMine),
+                3 | 10000 | 30000 => Some(Page::PeopleLists),
+                _ => None,
+            };
+            if let Some(page) = page {
+                ui.scope(|ui| {
+                    super::manage_style(theme, ui.style_mut());
+                    if ui.button("Review").clicked() {
+                        new_page = Some(page);
+                    }
+                });
+                ui.add_space(10.0);
+            }
+            ui.scope(|ui| {
+                super::approve_style(theme, ui.style_mut());
+                if ui.button("Dismiss").clicked() {
+                    if let Err(e) = gossip_lib::reconcile::acknowledge_conflict(kind, &d) {
+                        GLOBALS.status_queue.write().write(format!("{}", e));
+                    }
+                }
+            });
+            new_page
+        };
+
+        self.layout(theme, ui, description, action)
+    }
+
+    fn duplicate_post(
+        &mut self,
+        theme: &Theme,
+        ui: &mut Ui,
+        id: u64,
+        previous: nostr_types::Id,
+    ) -> Option<Page> {
+        let content = GLOBALS
+            .held_posts
+            .get(&id)
+            .map(|held| held.content.clone())
+            .unwrap_or_default();
+
+        let description = |_theme: &Theme, ui: &mut Ui| -> Option<Page> {
+            ui.label("This post looks like one you made recently, so it was held back:");
+            ui.label(RichText::new(content).italics());
+            None
+        };
+        let action = |theme: &Theme, ui: &mut Ui| -> Option<Page> {
+            let mut new_page = None;
+            ui.scope(|ui| {
+                super::manage_style(theme, ui.style_mut());
+                if ui.button("See Earlier Post").clicked() {
+                    new_page = Some(Page::Feed(crate::ui::FeedKind::Thread {
+                        id: previous,
+                        referenced_by: previous,
+                        author: None,
+                    }));
+                }
+            });
+            ui.add_space(10.0);
+            ui.scope(|ui| {
+                super::decline_style(theme, ui.style_mut());
+                if ui.button("Discard").clicked() {
+                    let _ = GLOBALS
+                        .to_overlord
+                        .send(ToOverlordMessage::ConfirmHeldPost(id, false));
+                }
+            });
+            ui.add_space(10.0);
+            ui.scope(|ui| {
+                super::approve_style(theme, ui.style_mut());
+                if ui.button("Post Anyway").clicked() {
+                    let _ = GLOBALS
+                        .to_overlord
+                        .send(ToOverlordMessage::ConfirmHeldPost(id, true));
+                }
+            });
+            new_page
+        };
+
+        self.layout(theme, ui, description, action)
+    }
+
+    fn follow_back(
+        &mut self,
+        theme: &Theme,
+        ui: &mut Ui,
+        rule_id: u64,
+        pubkey: nostr_types::PublicKey,
+    ) -> Option<Page> {
+        let rule = match gossip_lib::follow_back::rules() {
+            Ok(rules) => rules.into_iter().find(|r| r.id == rule_id),
+            Err(e) => {
+                ui.label(format!("Could not load the follow-back rules: {}", e));
+                return None;
+            }
+        };
+        let (rule_name, list_name) = match &rule {
+            Some(rule) => {
+                let list_name = rule
+                    .person_list()
+                    .and_then(|list| GLOBALS.storage.get_person_list_metadata(list).ok())
+                    .flatten()
+                    .map(|metadata| metadata.title)
+                    .unwrap_or_default();
+                (rule.name.clone(), list_name)
+            }
+            None => ("(deleted)".to_owned(), String::new()),
+        };
+        let name = gossip_lib::names::best_name_from_pubkey_lookup(&pubkey);
+
+        let description = |_theme: &Theme, ui: &mut Ui| -> Option<Page> {
+            ui.label(format!(
+                "{} followed you. Your follow-back rule '{}' would add them to '{}'.",
+                name, rule_name, list_name
+            ));
+            None
+        };
+        let action = |theme: &Theme, ui: &mut Ui| -> Option<Page> {
+            let mut new_page = None;
+            ui.scope(|ui| {
+                super::manage_style(theme, ui.style_mut());
+                if ui.button("View Profile").clicked() {
+                    new_page = Some(Page::Person(pubkey));
+                }
+            });
+            ui.add_space(10.0);
+            ui.scope(|ui| {
+                super::decline_style(theme, ui.style_mut());
+                if ui.button("Decline").clicked() {
+                    if let Err(e) = gossip_lib::follow_back::decline(rule_id, pubkey) {
+                        GLOBALS.status_queue.write().write(format!("{}", e));
+                    }
+                }
+            });
+            ui.add_space(10.0);
+            ui.scope(|ui| {
+                super::approve_style(theme, ui.style_mut());
+                if ui.button("Add").clicked() {
+                    if let Err(e) = gossip_lib::follow_back::approve(rule_id, pubkey) {
+                        GLOBALS.status_queue.write().write(format!("{}", e));
+                    }
+                }
+            });
+            new_page
+        };
+
+        self.layout(theme, ui, description, action)
+    }
+
     fn relay_list_not_advertized_recently(&mut self, theme: &Theme, ui: &mut Ui) -> Option<Page> {
         let description = |_theme: &Theme, ui: &mut Ui| -> Option<Page> {
             ui.label("Your Relay List has not been advertised recently");
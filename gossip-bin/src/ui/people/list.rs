@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
@@ -10,8 +11,11 @@ use egui_winit::egui::text::LayoutJob;
 use egui_winit::egui::text_edit::TextEditOutput;
 use egui_winit::egui::vec2;
 use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::follow_recovery::{self, RecoveryCandidate};
+use gossip_lib::person_list_io::{self, PersonListFormat};
 use gossip_lib::{
-    FeedKind, Freshness, People, Person, PersonList, PersonListMetadata, Private, GLOBALS,
+    FeedKind, Freshness, ListAutoPublish, ListRefresh, People, Person, PersonList,
+    PersonListMetadata, Private, GLOBALS,
 };
 use nostr_types::{Profile, PublicKey, Unixtime};
 
@@ -31,6 +35,17 @@ pub(in crate::ui) struct ListUi {
     add_contact_search_results: Vec<(String, PublicKey)>,
     add_contact_search_selected: Option<usize>,
     add_contact_error: Option<String>,
+    add_contact_recommended: Option<Vec<(String, PublicKey)>>,
+
+    // import
+    importing: bool,
+    import_text: String,
+    import_resolving: bool,
+
+    // follow list recovery
+    follows_look_lost: bool,
+    recovering: bool,
+    recovery: Vec<RecoveryCandidate>,
 
     entering_follow_someone_on_list: bool,
     clear_list_needs_confirm: bool,
@@ -54,6 +69,17 @@ impl ListUi {
             add_contact_search_results: Vec::new(),
             add_contact_search_selected: None,
             add_contact_error: None,
+            add_contact_recommended: None,
+
+            // import
+            importing: false,
+            import_text: String::new(),
+            import_resolving: false,
+
+            // follow list recovery
+            follows_look_lost: false,
+            recovering: false,
+            recovery: Vec::new(),
 
             entering_follow_someone_on_list: false,
             clear_list_needs_confirm: false,
@@ -63,6 +89,8 @@ impl ListUi {
 
 pub(super) fn enter_page(app: &mut GossipUi, list: PersonList) {
     refresh_list_data(app, list);
+    app.people_list.follows_look_lost =
+        list == PersonList::Followed && follow_recovery::follow_list_looks_lost().unwrap_or(false);
 }
 
 pub(super) fn update(
@@ -91,6 +119,10 @@ pub(super) fn update(
         render_clear_list_confirm_popup(ui, app, list);
     } else if app.people_list.entering_follow_someone_on_list {
         render_add_contact_popup(ui, app, list, &metadata);
+    } else if app.people_list.importing {
+        render_import_popup(ui, app, list, &metadata);
+    } else if app.people_list.recovering {
+        render_recovery_popup(ui, app);
     } else if let Some(list) = app.deleting_list {
         super::list::render_delete_list_dialog(ui, app, list);
     } else if app.creating_list {
@@ -110,13 +142,16 @@ pub(super) fn update(
             let len = metadata.len;
             render_more_list_actions(ui, app, list, &metadata, len, true);
 
-            btn_h_space!(ui);
+            // Smart lists are regenerated from local data
+            if metadata.smart.is_none() {
+                btn_h_space!(ui);
 
-            if widgets::Button::primary(&app.theme, "Add contact")
-                .show(ui)
-                .clicked()
-            {
-                app.people_list.entering_follow_someone_on_list = true;
+                if widgets::Button::primary(&app.theme, "Add contact")
+                    .show(ui)
+                    .clicked()
+                {
+                    app.people_list.entering_follow_someone_on_list = true;
+                }
             }
 
             btn_h_space!(ui);
@@ -137,75 +172,98 @@ pub(super) fn update(
 
     ui.add_space(5.0);
 
-    ui.vertical(|ui| {
+    if app.people_list.follows_look_lost {
+        ui.horizontal(|ui| {
+            ui.label(
+                RichText::new("Your follow list looks empty or much shorter than it used to be.")
+                    .color(app.theme.warning_marker_text_color()),
+            );
+            if ui.button("Recover from history...").clicked() {
+                start_recovery(app);
+            }
+        });
+        ui.add_space(5.0);
+    }
 
-        ui.label(RichText::new(&app.people_list.cache_remote_tag))
-            .on_hover_text("This is the data in the latest list event fetched from relays");
+    if let Some(smart) = metadata.smart {
+        ui.label(smart.description());
+        ui.label(
+            RichText::new(
+                "Smart list: gossip keeps it up to date every hour, and never publishes it",
+            )
+            .weak(),
+        );
+    } else {
+        ui.vertical(|ui| {
 
-        ui.add_space(5.0);
+            ui.label(RichText::new(&app.people_list.cache_remote_tag))
+                .on_hover_text("This is the data in the latest list event fetched from relays");
 
-        if app.people_list.cache_local_hash == app.people_list.cache_remote_hash {
-            ui.label("List is synchronized");
-        } else {
-            // remote <-> local buttons
-            ui.horizontal(|ui|{
-                if ui
-                    .button("↓ Overwrite ↓")
-                    .on_hover_text(
-                        "This imports data from the latest event, erasing anything that is already here",
-                    )
-                    .clicked()
-                {
-                    let _ = GLOBALS
-                        .to_overlord
-                        .send(ToOverlordMessage::UpdatePersonList {
-                            person_list: list,
-                            merge: false,
-                        });
-                }
-                if ui
-                    .button("↓ Merge ↓")
-                    .on_hover_text(
-                        "This imports data from the latest event, merging it into what is already here",
-                    )
-                    .clicked()
-                {
-                    let _ = GLOBALS
-                        .to_overlord
-                        .send(ToOverlordMessage::UpdatePersonList {
-                            person_list: list,
-                            merge: true,
-                        });
-                }
+            ui.add_space(5.0);
 
-                if GLOBALS.identity.is_unlocked() {
+            if app.people_list.cache_local_hash == app.people_list.cache_remote_hash {
+                ui.label("List is synchronized");
+            } else {
+                // remote <-> local buttons
+                ui.horizontal(|ui|{
                     if ui
-                        .button("↑ Publish ↑")
-                        .on_hover_text("This publishes the list to your relays")
+                        .button("↓ Overwrite ↓")
+                        .on_hover_text(
+                            "This imports data from the latest event, erasing anything that is already here",
+                        )
                         .clicked()
                     {
                         let _ = GLOBALS
                             .to_overlord
-                            .send(ToOverlordMessage::PushPersonList(list));
+                            .send(ToOverlordMessage::UpdatePersonList {
+                                person_list: list,
+                                merge: false,
+                            });
                     }
-                } else {
-                    ui.horizontal(|ui| {
-                        ui.label("You need to ");
-                        if ui.link("setup your private-key").clicked() {
-                            app.set_page(ctx, Page::YourKeys);
+                    if ui
+                        .button("↓ Merge ↓")
+                        .on_hover_text(
+                            "This imports data from the latest event, merging it into what is already here",
+                        )
+                        .clicked()
+                    {
+                        let _ = GLOBALS
+                            .to_overlord
+                            .send(ToOverlordMessage::UpdatePersonList {
+                                person_list: list,
+                                merge: true,
+                            });
+                    }
+
+                    if GLOBALS.identity.is_unlocked() {
+                        if ui
+                            .button("↑ Publish ↑")
+                            .on_hover_text("This publishes the list to your relays")
+                            .clicked()
+                        {
+                            let _ = GLOBALS
+                                .to_overlord
+                                .send(ToOverlordMessage::PushPersonList(list));
                         }
-                        ui.label(" to push lists.");
-                    });
-                }
-            });
-        }
+                    } else {
+                        ui.horizontal(|ui| {
+                            ui.label("You need to ");
+                            if ui.link("setup your private-key").clicked() {
+                                app.set_page(ctx, Page::YourKeys);
+                            }
+                            ui.label(" to push lists.");
+                        });
+                    }
+                });
+            }
 
-        ui.add_space(5.0);
+            ui.add_space(5.0);
 
-        // local timestamp
-        ui.label(RichText::new(&app.people_list.cache_local_tag))
-            .on_hover_text("This is the local (and effective) list");
-    });
+            // local timestamp
+            ui.label(RichText::new(&app.people_list.cache_local_tag))
+                .on_hover_text("This is the local (and effective) list");
+        });
+    }
 
     ui.add_space(10.0);
 
@@ -293,6 +351,11 @@ pub(super) fn update(
                                 egui::Layout::right_to_left(egui::Align::Min)
                                     .with_cross_align(egui::Align::Center),
                                 |ui| {
+                                    // Smart lists are regenerated from local data
+                                    if metadata.smart.is_some() {
+                                        return;
+                                    }
+
                                     let text = egui::RichText::new("=").size(13.0);
                                     let response = widgets::Button::primary(&app.theme, text)
                                         .small(true)
@@ -342,6 +405,10 @@ pub(super) fn update(
                     .inner
                 },
             );
+            // Only rows actually on screen count as viewed
+            if ui.is_rect_visible(row_response.response.rect) {
+                GLOBALS.people.view_entered(person.pubkey);
+            }
             if row_response
                 .inner
                 .on_hover_cursor(egui::CursorIcon::PointingHand)
@@ -394,6 +461,14 @@ pub(in crate::ui) fn layout_list_title(
                 egui::Align::LEFT,
             );
     }
+    if metadata.smart.is_some() {
+        RichText::new(" (smart)").size(14.5).weak().append_to(
+            &mut layout_job,
+            style,
+            egui::FontSelection::Default,
+            egui::Align::LEFT,
+        );
+    }
     ui.fonts(|fonts| fonts.layout_job(layout_job))
 }
 
@@ -403,7 +478,7 @@ fn render_add_contact_popup(
     list: PersonList,
     metadata: &PersonListMetadata,
 ) {
-    const DLG_SIZE: Vec2 = vec2(400.0, 260.0);
+    const DLG_SIZE: Vec2 = vec2(400.0, 300.0);
     let ret = crate::ui::widgets::modal_popup(ui, DLG_SIZE, DLG_SIZE, true, |ui| {
         let enter_key;
         (app.people_list.add_contact_search_selected, enter_key) =
@@ -463,6 +538,35 @@ fn render_add_contact_popup(
 
         recalc_add_contact_search(app, &mut output);
 
+        // Suggest the people we interact with most who are not on the list yet
+        let recommended = app
+            .people_list
+            .add_contact_recommended
+            .get_or_insert_with(|| {
+                gossip_lib::relationship_strength::recommended(list, 5)
+                    .unwrap_or_default()
+                    .drain(..)
+                    .map(|(pubkey, _)| {
+                        (
+                            gossip_lib::names::best_name_from_pubkey_lookup(&pubkey),
+                            pubkey,
+                        )
+                    })
+                    .collect()
+            })
+            .clone();
+        if !recommended.is_empty() {
+            ui.add_space(8.0);
+            ui.horizontal_wrapped(|ui| {
+                ui.label("You interact most with:");
+                for (name, pubkey) in recommended.iter() {
+                    if ui.link(name).clicked() {
+                        app.add_contact = pubkey.as_bech32_string();
+                    }
+                }
+            });
+        }
+
         ui.add_space(8.0);
 
         ui.label("To add a new contact to this list enter their npub, hex key, nprofile or nip-05 address");
@@ -548,6 +652,7 @@ fn render_add_contact_popup(
                             app.people_list.add_contact_searched = None;
                             app.people_list.add_contact_search_selected = None;
                             app.people_list.add_contact_search_results.clear();
+                            app.people_list.add_contact_recommended = None;
                         }
                         if want_close && can_close {
                             app.people_list.entering_follow_someone_on_list = false;
@@ -564,6 +669,7 @@ fn render_add_contact_popup(
         app.people_list.add_contact_searched = None;
         app.people_list.add_contact_search_selected = None;
         app.people_list.add_contact_search_results.clear();
+        app.people_list.add_contact_recommended = None;
     }
 }
 
@@ -811,7 +917,10 @@ pub(super) fn render_more_list_actions(
         ));
     }
 
-    if on_list {
+    // Smart lists are regenerated from local data and never published
+    let smart = metadata.smart.is_some();
+
+    if on_list && !smart {
         if metadata.private == Private(true) {
             entries.push(MoreMenuEntry::new(
                 "Make Public",
@@ -838,6 +947,94 @@ pub(super) fn render_more_list_actions(
                 }),
             ));
         }
+        for policy in ListAutoPublish::all() {
+            if *policy == metadata.auto_publish {
+                continue;
+            }
+            let label = match policy {
+                ListAutoPublish::Manual => "Publish Manually",
+                ListAutoPublish::OnEdit => "Publish on Edit",
+                ListAutoPublish::Daily => "Publish Daily",
+            };
+            entries.push(MoreMenuEntry::new(
+                label,
+                Box::new(move |_, _| {
+                    let mut metadata = metadata.clone();
+                    metadata.auto_publish = *policy;
+                    let _ = GLOBALS
+                        .storage
+                        .set_person_list_metadata(list, &metadata, None);
+                }),
+            ));
+        }
+    }
+    if on_list {
+        if list.subscribe() {
+            for refresh in [
+                ListRefresh::Live,
+                ListRefresh::EveryMinutes(15),
+                ListRefresh::EveryMinutes(60),
+                ListRefresh::Manual,
+            ] {
+                if refresh == metadata.refresh {
+                    continue;
+                }
+                entries.push(MoreMenuEntry::new(
+                    format!("Refresh {}", refresh.as_string()),
+                    Box::new(move |_, _| {
+                        let mut metadata = metadata.clone();
+                        metadata.refresh = refresh;
+                        let _ = GLOBALS
+                            .storage
+                            .set_person_list_metadata(list, &metadata, None);
+                        // Live subscriptions follow the lists that are live
+                        let _ = GLOBALS
+                            .to_overlord
+                            .send(ToOverlordMessage::RefreshScoresAndPickRelays);
+                    }),
+                ));
+            }
+            if metadata.refresh != ListRefresh::Live {
+                entries.push(MoreMenuEntry::new(
+                    "Refresh Now",
+                    Box::new(|_, _| {
+                        let _ = GLOBALS
+                            .to_overlord
+                            .send(ToOverlordMessage::RefreshPersonList(list));
+                    }),
+                ));
+            }
+        }
+        entries.push(
+            MoreMenuEntry::new(
+                "Export CSV",
+                Box::new(|ui, _| export_to_clipboard(ui, list, PersonListFormat::Csv)),
+            )
+            .enabled(count > 0),
+        );
+        entries.push(
+            MoreMenuEntry::new(
+                "Export JSON",
+                Box::new(|ui, _| export_to_clipboard(ui, list, PersonListFormat::Json)),
+            )
+            .enabled(count > 0),
+        );
+        if list == PersonList::Followed {
+            entries.push(MoreMenuEntry::new(
+                "Recover from history...",
+                Box::new(|_, app| start_recovery(app)),
+            ));
+        }
+    }
+    if on_list && !smart {
+        entries.push(MoreMenuEntry::new(
+            "Import...",
+            Box::new(|_, app| {
+                app.people_list.importing = true;
+                app.people_list.import_resolving = false;
+                app.people_list.import_text.clear();
+            }),
+        ));
         entries.push(
             MoreMenuEntry::new(
                 "Clear All",
@@ -977,17 +1174,18 @@ fn recalc_add_contact_search(app: &mut GossipUi, output: &mut TextEditOutput) {
                 .people
                 .search_people_to_tag(app.people_list.add_contact_search.as_str())
                 .unwrap_or_default();
-            // followed contacts first
+            // followed contacts first, otherwise in the order of the search (which
+            // ranks the people we interact with most higher)
+            let pubkeys: Vec<PublicKey> = pairs.iter().map(|(_, pk)| *pk).collect();
+            let followed: HashSet<PublicKey> = pubkeys
+                .iter()
+                .zip(GLOBALS.people.follow_states(&pubkeys))
+                .filter_map(|(pk, f)| if f { Some(*pk) } else { None })
+                .collect();
             pairs.sort_by(|(_, ak), (_, bk)| {
-                let af = GLOBALS
-                    .storage
-                    .is_person_in_list(ak, gossip_lib::PersonList::Followed)
-                    .unwrap_or(false);
-                let bf = GLOBALS
-                    .storage
-                    .is_person_in_list(bk, gossip_lib::PersonList::Followed)
-                    .unwrap_or(false);
-                bf.cmp(&af).then(std::cmp::Ordering::Greater)
+                let af = followed.contains(ak);
+                let bf = followed.contains(bk);
+                bf.cmp(&af)
             });
             app.people_list.add_contact_searched = Some(app.people_list.add_contact_search.clone());
             app.people_list.add_contact_search_results = pairs.to_owned();
@@ -1034,6 +1232,239 @@ fn render_clear_list_confirm_popup(ui: &mut Ui, app: &mut GossipUi, list: Person
     }
 }
 
+fn export_to_clipboard(ui: &mut Ui, list: PersonList, format: PersonListFormat) {
+    match person_list_io::export_person_list(list, format) {
+        Ok(text) => {
+            ui.output_mut(|o| o.copied_text = text);
+            GLOBALS
+                .status_queue
+                .write()
+                .write("List copied to the clipboard".to_owned());
+        }
+        Err(e) => GLOBALS.status_queue.write().write(format!("{}", e)),
+    }
+}
+
+fn render_import_popup(
+    ui: &mut Ui,
+    app: &mut GossipUi,
+    list: PersonList,
+    metadata: &PersonListMetadata,
+) {
+    const DLG_SIZE: Vec2 = vec2(450.0, 350.0);
+    let ret = widgets::modal_popup(ui, DLG_SIZE, DLG_SIZE, true, |ui| {
+        ui.heading("Import into the list");
+        ui.add_space(8.0);
+
+        if !app.people_list.import_resolving {
+            ui.label("Paste a list exported as CSV (npub,nip05,name,relays) or JSON");
+            ui.add_space(8.0);
+            app.vert_scroll_area()
+                .id_source("person_list_import_text")
+                .max_height(220.0)
+                .show(ui, |ui| {
+                    ui.add(
+                        text_edit_multiline!(app, app.people_list.import_text)
+                            .desired_width(f32::INFINITY)
+                            .desired_rows(10),
+                    );
+                });
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                if widgets::Button::secondary(&app.theme, "Cancel")
+                    .show(ui)
+                    .clicked()
+                {
+                    app.people_list.importing = false;
+                }
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::default()), |ui| {
+                    if widgets::Button::primary(&app.theme, "Resolve")
+                        .show(ui)
+                        .clicked()
+                        && !app.people_list.import_text.trim().is_empty()
+                    {
+                        let _ =
+                            GLOBALS
+                                .to_overlord
+                                .send(ToOverlordMessage::ResolvePersonListImport(
+                                    app.people_list.import_text.clone(),
+                                ));
+                        app.people_list.import_resolving = true;
+                    }
+                });
+            });
+            return;
+        }
+
+        let mut guard = GLOBALS.person_list_import.write();
+        let entries = match guard.as_mut() {
+            Some(entries) => entries,
+            None => {
+                ui.label("Resolving entries...");
+                ui.spinner();
+                return;
+            }
+        };
+
+        ui.label("Review the entries to add:");
+        ui.add_space(8.0);
+        app.vert_scroll_area()
+            .id_source("person_list_import_review")
+            .max_height(220.0)
+            .show(ui, |ui| {
+                for entry in entries.iter_mut() {
+                    ui.horizontal(|ui| {
+                        ui.add_enabled(
+                            entry.pubkey.is_some(),
+                            egui::Checkbox::without_text(&mut entry.selected),
+                        );
+                        let name = if !entry.name.is_empty() {
+                            entry.name.clone()
+                        } else if let Some(pubkey) = entry.pubkey {
+                            gossip_lib::names::best_name_from_pubkey_lookup(&pubkey)
+                        } else {
+                            entry.input.clone()
+                        };
+                        ui.label(name);
+                        if let Some(error) = &entry.error {
+                            ui.label(
+                                RichText::new(error).color(app.theme.warning_marker_text_color()),
+                            );
+                        } else if !entry.nip05.is_empty() {
+                            ui.label(RichText::new(&entry.nip05).weak());
+                        }
+                    });
+                }
+            });
+        ui.add_space(8.0);
+
+        let selected = entries.iter().filter(|e| e.selected).count();
+        ui.horizontal(|ui| {
+            if widgets::Button::secondary(&app.theme, "Cancel")
+                .show(ui)
+                .clicked()
+            {
+                app.people_list.importing = false;
+            }
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::default()), |ui| {
+                if widgets::Button::primary(&app.theme, format!("Import {} selected", selected))
+                    .show(ui)
+                    .clicked()
+                {
+                    match person_list_io::apply_person_list_import(list, metadata.private, entries)
+                    {
+                        Ok(count) => GLOBALS
+                            .status_queue
+                            .write()
+                            .write(format!("Imported {} people", count)),
+                        Err(e) => GLOBALS.status_queue.write().write(format!("{}", e)),
+                    }
+                    app.people_list.importing = false;
+                    mark_refresh(app);
+                }
+            });
+        });
+    });
+
+    if ret.inner.clicked() {
+        app.people_list.importing = false;
+    }
+    if !app.people_list.importing {
+        *GLOBALS.person_list_import.write() = None;
+    }
+}
+
+fn start_recovery(app: &mut GossipUi) {
+    match follow_recovery::propose_recovered_follows() {
+        Ok(candidates) => {
+            app.people_list.recovery = candidates;
+            app.people_list.recovering = true;
+        }
+        Err(e) => GLOBALS.status_queue.write().write(format!("{}", e)),
+    }
+}
+
+fn render_recovery_popup(ui: &mut Ui, app: &mut GossipUi) {
+    const DLG_SIZE: Vec2 = vec2(450.0, 350.0);
+    let ret = widgets::modal_popup(ui, DLG_SIZE, DLG_SIZE, true, |ui| {
+        ui.heading("Recover follows");
+        ui.add_space(8.0);
+
+        if app.people_list.recovery.is_empty() {
+            ui.label("Nobody to propose: there is no usable history.");
+            ui.add_space(8.0);
+            if widgets::Button::secondary(&app.theme, "Close")
+                .show(ui)
+                .clicked()
+            {
+                app.people_list.recovering = false;
+            }
+            return;
+        }
+
+        ui.label("These people were found in your history. Choose who to follow:");
+        ui.add_space(8.0);
+        let mut recovery = std::mem::take(&mut app.people_list.recovery);
+        app.vert_scroll_area()
+            .id_source("follow_recovery_review")
+            .max_height(220.0)
+            .show(ui, |ui| {
+                for candidate in recovery.iter_mut() {
+                    ui.horizontal(|ui| {
+                        ui.checkbox(
+                            &mut candidate.selected,
+                            gossip_lib::names::best_name_from_pubkey_lookup(&candidate.pubkey),
+                        );
+                        let reasons: Vec<String> =
+                            candidate.reasons.iter().map(|r| r.to_string()).collect();
+                        ui.label(RichText::new(reasons.join(", ")).weak());
+                    });
+                }
+            });
+        app.people_list.recovery = recovery;
+        ui.add_space(8.0);
+
+        let selected = app
+            .people_list
+            .recovery
+            .iter()
+            .filter(|c| c.selected)
+            .count();
+        ui.horizontal(|ui| {
+            if widgets::Button::secondary(&app.theme, "Cancel")
+                .show(ui)
+                .clicked()
+            {
+                app.people_list.recovering = false;
+            }
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::default()), |ui| {
+                if widgets::Button::primary(&app.theme, format!("Follow {} selected", selected))
+                    .show(ui)
+                    .clicked()
+                {
+                    match follow_recovery::apply_recovered_follows(&app.people_list.recovery) {
+                        Ok(count) => GLOBALS.status_queue.write().write(format!(
+                            "Followed {} people. Review the list, then publish it.",
+                            count
+                        )),
+                        Err(e) => GLOBALS.status_queue.write().write(format!("{}", e)),
+                    }
+                    app.people_list.recovering = false;
+                    app.people_list.follows_look_lost = false;
+                    mark_refresh(app);
+                }
+            });
+        });
+    });
+
+    if ret.inner.clicked() {
+        app.people_list.recovering = false;
+    }
+    if !app.people_list.recovering {
+        app.people_list.recovery.clear();
+    }
+}
+
 fn mark_refresh(app: &mut GossipUi) {
     app.people_list.cache_next_refresh = Instant::now();
 }
@@ -1053,9 +1484,6 @@ fn refresh_list_data(app: &mut GossipUi, list: PersonList) {
                 let _ = GLOBALS.storage.write_person(&person, None);
                 people.push((person, *private));
             }
-
-            // They are a person of interest (to as to fetch metadata if out of date)
-            GLOBALS.people.person_of_interest(*pk);
         }
         people.sort_by(|a, b| a.0.cmp(&b.0));
         people
@@ -91,6 +91,16 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
                                         .sense(Sense::click()),
                                     );
                                 }
+                                if metadata.smart.is_some() {
+                                    response |= ui.add(
+                                        Label::new(RichText::new("smart").weak())
+                                            .selectable(false)
+                                            .sense(Sense::click()),
+                                    )
+                                    .on_hover_text(
+                                        "Kept up to date by gossip from local data, never published",
+                                    );
+                                }
 
                                 ui.with_layout(
                                     egui::Layout::right_to_left(egui::Align::Center),
@@ -10,6 +10,8 @@ use egui_winit::egui::InnerResponse;
 use egui_winit::egui::Response;
 use egui_winit::egui::Widget;
 use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::event_media::{MediaGalleryItem, MediaType};
+use gossip_lib::remote_content::{may_fetch, remote_content_policy};
 use gossip_lib::{DmChannel, FeedKind, Freshness, People, Person, PersonList, Private, GLOBALS};
 use nostr_types::PublicKey;
 use serde_json::Value;
@@ -19,6 +21,8 @@ const AVATAR_COL_WIDTH: f32 = AVATAR_SIZE_F32 * 3.0;
 const AVATAR_COL_SPACE: f32 = 20.0;
 const AVATAR_COL_WIDTH_SPACE: f32 = AVATAR_COL_WIDTH + AVATAR_COL_SPACE * 2.0;
 const MIN_ITEM_WIDTH: f32 = 200.0;
+const GALLERY_THUMB_SIZE: f32 = 96.0;
+const GALLERY_LEN: usize = 12;
 
 pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
     let (pubkey, person) = match &app.page {
@@ -183,6 +187,31 @@ fn content(app: &mut GossipUi, ctx: &Context, ui: &mut Ui, pubkey: PublicKey, pe
                 }
             }
 
+            if person.is_in_list(PersonList::Muted) {
+                if let Some(entry) = GLOBALS
+                    .storage
+                    .read_mute_log(&pubkey)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .rev()
+                    .find(|entry| entry.muted)
+                {
+                    let mut since = "time unknown".to_owned();
+                    if let Ok(stamp) = time::OffsetDateTime::from_unix_timestamp(entry.at) {
+                        if let Ok(formatted) = stamp.format(time::macros::format_description!(
+                            "[year]-[month repr:short]-[day]"
+                        )) {
+                            since = formatted;
+                        }
+                    }
+                    let text = match entry.reason {
+                        Some(reason) => format!("since {}: {}", since, reason),
+                        None => format!("since {}", since),
+                    };
+                    profile_item(ui, app, width, "muted", text);
+                }
+            }
+
             ui.add_space(10.0);
             ui.horizontal(|ui| {
                 ui.add_space(10.0);
@@ -260,6 +289,8 @@ fn content(app: &mut GossipUi, ctx: &Context, ui: &mut Ui, pubkey: PublicKey, pe
                 }
             });
 
+            media_gallery(app, ctx, ui, pubkey);
+
             ui.add_space(10.0);
             ui.horizontal(|ui| {
                 ui.add_space(10.0);
@@ -559,6 +590,70 @@ fn content(app: &mut GossipUi, ctx: &Context, ui: &mut Ui, pubkey: PublicKey, pe
 }
 
 /// A profile item
+// The person's latest images, from the media index rather than their events
+fn media_gallery(app: &mut GossipUi, ctx: &Context, ui: &mut Ui, pubkey: PublicKey) {
+    let items: Vec<MediaGalleryItem> = GLOBALS
+        .storage
+        .get_author_media(pubkey, None, GALLERY_LEN * 2)
+        .unwrap_or_default()
+        .drain(..)
+        .filter(|item| item.attachment.media_type == MediaType::Image)
+        .take(GALLERY_LEN)
+        .collect();
+    if items.is_empty() {
+        return;
+    }
+
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        ui.add_space(10.0);
+        ui.heading("Media");
+    });
+    ui.separator();
+    ui.add_space(10.0);
+
+    let policy = remote_content_policy(&pubkey, false);
+    make_frame().show(ui, |ui| {
+        ui.horizontal_wrapped(|ui| {
+            for item in items.iter() {
+                let url = match app.try_check_url(&item.attachment.url) {
+                    Some(url) => url,
+                    None => continue,
+                };
+                let show = may_fetch(&url, policy, app.media_show_list.contains(&url));
+                // Don't fetch media the user hasn't asked to see
+                let texture = if show {
+                    app.try_get_media(ctx, url)
+                } else {
+                    None
+                };
+                let response = match texture {
+                    Some(texture) => ui.add(
+                        egui::Image::new(&texture)
+                            .max_size(Vec2::splat(GALLERY_THUMB_SIZE))
+                            .maintain_aspect_ratio(true)
+                            .sense(egui::Sense::click()),
+                    ),
+                    _ => ui.add_sized(
+                        Vec2::splat(GALLERY_THUMB_SIZE),
+                        egui::Button::new("[image]"),
+                    ),
+                };
+                if response.on_hover_text(&item.attachment.url).clicked() {
+                    app.set_page(
+                        ctx,
+                        Page::Feed(FeedKind::Thread {
+                            id: item.id,
+                            referenced_by: item.id,
+                            author: Some(pubkey),
+                        }),
+                    );
+                }
+            }
+        });
+    });
+}
+
 fn profile_item(
     ui: &mut Ui,
     app: &mut GossipUi,
@@ -171,6 +171,10 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
         app.vert_scroll_area().id_source(id_source).show(ui, |ui| {
             for elem in GLOBALS.relay_picker.pubkey_counts_iter() {
                 let pk = elem.key();
+                // Dormant people are only given one relay on purpose
+                if gossip_lib::activity::is_dormant(*pk) {
+                    continue;
+                }
                 let count = elem.value();
                 let name = gossip_lib::names::best_name_from_pubkey_lookup(pk);
                 let relays = find_relays_for_pubkey(pk);
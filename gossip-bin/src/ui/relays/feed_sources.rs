@@ -0,0 +1,91 @@
+use super::GossipUi;
+use crate::ui::widgets;
+use crate::ui::Page;
+use eframe::egui;
+use egui::{Context, RichText, Ui};
+use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::GLOBALS;
+use std::sync::atomic::Ordering;
+
+pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
+    let counting = GLOBALS.feed_sources_counting.load(Ordering::Relaxed);
+
+    widgets::page_header(ui, Page::RelaysFeedSources.name(), |ui| {
+        ui.spacing_mut().button_padding *= 2.0;
+        if counting {
+            ui.label("Counting...");
+        } else if ui
+            .button("Count")
+            .on_hover_cursor(egui::CursorIcon::PointingHand)
+            .clicked()
+        {
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::CountFeedSources);
+        }
+    });
+
+    ui.label(
+        "Which relays delivered the events of each feed over the last week. \
+        The relays marked redundant for a feed could all be dropped together without \
+        losing you any of its events, as everything they delivered also came from the \
+        relays that remain.",
+    );
+    ui.add_space(10.0);
+
+    let attributions = match &*GLOBALS.feed_sources.read() {
+        Some(attributions) => attributions.clone(),
+        None => {
+            if !counting {
+                ui.label("Press Count to go through the last week of your feeds.");
+            }
+            return;
+        }
+    };
+
+    let warning = app.theme.warning_marker_text_color();
+    app.vert_scroll_area()
+        .id_source("feed_sources")
+        .show(ui, |ui| {
+            for attribution in attributions.iter() {
+                let title = match GLOBALS.storage.get_person_list_metadata(attribution.list) {
+                    Ok(Some(md)) => md.title,
+                    _ => "UNKNOWN".to_owned(),
+                };
+                ui.heading(title);
+                ui.label(format!(
+                    "{} events from {} relays ({} not from any relay)",
+                    attribution.events,
+                    attribution.relays.len(),
+                    attribution.unattributed
+                ));
+
+                egui::Grid::new(("feed_sources", attribution.list))
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label(RichText::new("Relay").strong());
+                        ui.label(RichText::new("Events").strong());
+                        ui.label(RichText::new("Only from here").strong());
+                        ui.end_row();
+
+                        for contribution in attribution.relays.iter() {
+                            ui.label(contribution.relay.as_str());
+                            ui.label(format!("{}", contribution.events));
+                            if contribution.redundant {
+                                ui.label(
+                                    RichText::new(format!(
+                                        "{} (redundant)",
+                                        contribution.only_here
+                                    ))
+                                    .color(warning),
+                                );
+                            } else {
+                                ui.label(format!("{}", contribution.only_here));
+                            }
+                            ui.end_row();
+                        }
+                    });
+                ui.add_space(12.0);
+            }
+        });
+}
@@ -29,6 +29,14 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
                 .to_overlord
                 .send(ToOverlordMessage::AdvertiseRelayList);
         }
+        if ui.button("Advertise DM Relays")
+            .on_hover_cursor(egui::CursorIcon::PointingHand)
+            .on_hover_text("Tell others where to send you private messages. Will send 10050 kind listing the relays that have 'DMs' usage enabled")
+            .clicked() {
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::PushDmRelays);
+        }
     });
 
     let relays = if !is_editing {
@@ -9,8 +9,10 @@ use nostr_types::RelayUrl;
 
 mod active;
 mod coverage;
+mod feed_sources;
 mod known;
 mod mine;
+mod query;
 
 pub const RELAY_URL_PREPOPULATE: &str = "wss://";
 
@@ -37,6 +39,12 @@ pub(super) struct RelayUi {
     /// Add Relay dialog
     add_dialog_step: AddRelayDialogStep,
     new_relay_url: String,
+
+    /// Relay Query page
+    query_relay_url: String,
+    query_filter: String,
+    query_ingest: bool,
+    query_cap: usize,
 }
 
 impl RelayUi {
@@ -53,6 +61,10 @@ impl RelayUi {
             edit_needs_scroll: false,
             add_dialog_step: AddRelayDialogStep::Inactive,
             new_relay_url: RELAY_URL_PREPOPULATE.to_string(),
+            query_relay_url: RELAY_URL_PREPOPULATE.to_string(),
+            query_filter: "{\"kinds\":[1],\"limit\":20}".to_string(),
+            query_ingest: false,
+            query_cap: 500,
         }
     }
 
@@ -145,8 +157,10 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, frame: &mut eframe::Fram
     match app.page {
         Page::RelaysActivityMonitor => active::update(app, ctx, frame, ui),
         Page::RelaysCoverage => coverage::update(app, ctx, frame, ui),
+        Page::RelaysFeedSources => feed_sources::update(app, ctx, frame, ui),
         Page::RelaysMine => mine::update(app, ctx, frame, ui),
         Page::RelaysKnownNetwork(_) => known::update(app, ctx, frame, ui),
+        Page::RelaysQuery => query::update(app, ctx, frame, ui),
         _ => {}
     }
 }
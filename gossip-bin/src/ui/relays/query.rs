@@ -0,0 +1,109 @@
+use super::GossipUi;
+use crate::ui::widgets;
+use crate::ui::Page;
+use eframe::egui;
+use egui::widgets::Slider;
+use egui::{Context, RichText, TextStyle, Ui};
+use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::relay_query::MAX_RAW_QUERY_RESULTS;
+use gossip_lib::GLOBALS;
+use nostr_types::{Filter, RelayUrl};
+
+pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
+    widgets::page_header(ui, Page::RelaysQuery.name(), |_ui| ());
+
+    ui.label("Send a filter to one relay and see exactly what it returns.");
+    ui.add_space(10.0);
+
+    ui.horizontal(|ui| {
+        ui.label("Relay:");
+        widgets::TextEdit::singleline(&app.theme, &mut app.relays.query_relay_url)
+            .desired_width(400.0)
+            .show(ui);
+    });
+
+    ui.label("Filter (JSON):");
+    ui.add(
+        egui::TextEdit::multiline(&mut app.relays.query_filter)
+            .font(TextStyle::Monospace)
+            .desired_rows(4)
+            .desired_width(f32::INFINITY),
+    );
+
+    ui.horizontal(|ui| {
+        ui.label("Stop after");
+        ui.add(Slider::new(&mut app.relays.query_cap, 1..=MAX_RAW_QUERY_RESULTS).text("events"));
+        ui.add_space(20.0);
+        ui.checkbox(&mut app.relays.query_ingest, "Also ingest the results")
+            .on_hover_text("If off, nothing returned is stored or shown in any feed.");
+    });
+
+    let running = matches!(&*GLOBALS.raw_query.read(), Some(r) if r.finished.is_none());
+    ui.add_space(6.0);
+    ui.add_enabled_ui(!running, |ui| {
+        if widgets::Button::primary(&app.theme, "Run query")
+            .show(ui)
+            .clicked()
+        {
+            let relay = RelayUrl::try_from_str(app.relays.query_relay_url.trim());
+            let filter = serde_json::from_str::<Filter>(&app.relays.query_filter);
+            match (relay, filter) {
+                (Ok(relay), Ok(filter)) => {
+                    let _ = GLOBALS.to_overlord.send(ToOverlordMessage::RawRelayQuery {
+                        relay,
+                        filter,
+                        ingest: app.relays.query_ingest,
+                        cap: app.relays.query_cap,
+                    });
+                }
+                (Err(e), _) => GLOBALS
+                    .status_queue
+                    .write()
+                    .write(format!("Invalid relay url: {}", e)),
+                (_, Err(e)) => GLOBALS
+                    .status_queue
+                    .write()
+                    .write(format!("Invalid filter: {}", e)),
+            }
+        }
+    });
+
+    ui.add_space(10.0);
+
+    let results = match &*GLOBALS.raw_query.read() {
+        Some(results) => results.clone(),
+        None => return,
+    };
+
+    ui.horizontal(|ui| {
+        let state = match (&results.finished, &results.error) {
+            (None, _) => "running".to_owned(),
+            (Some(_), Some(e)) => format!("failed: {}", e),
+            (Some(_), None) if results.capped => "stopped at the cap".to_owned(),
+            (Some(_), None) => "done".to_owned(),
+        };
+        ui.label(format!(
+            "{}: {} events, {}",
+            results.relay,
+            results.events.len(),
+            state
+        ));
+        if !results.events.is_empty() && ui.button("Copy all").clicked() {
+            ui.output_mut(|o| o.copied_text = results.events.join("\n"));
+        }
+    });
+
+    ui.add_space(6.0);
+
+    app.vert_scroll_area()
+        .id_source("relay_query_results")
+        .show(ui, |ui| {
+            for message in results.messages.iter() {
+                ui.label(RichText::new(message).monospace().italics());
+            }
+            for event in results.events.iter() {
+                ui.label(RichText::new(event).monospace());
+                ui.separator();
+            }
+        });
+}
@@ -0,0 +1,103 @@
+use super::{widgets, GossipUi};
+use eframe::egui;
+use egui::{Context, Label, RichText, Ui};
+use gossip_lib::GLOBALS;
+use std::time::{Duration, Instant};
+use tokio::task;
+
+// We only render this many entries
+const MAX_ENTRIES: usize = 200;
+
+pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
+    // Possibly refresh the entries (every 5 seconds)
+    if app.rss_next_refresh < Instant::now() {
+        app.rss_cache = match gossip_lib::rss::rss_feed() {
+            Ok(mut entries) => {
+                entries.truncate(MAX_ENTRIES);
+                entries
+            }
+            Err(e) => {
+                tracing::error!("{}", e);
+                vec![]
+            }
+        };
+        app.rss_next_refresh = Instant::now() + Duration::new(5, 0);
+    }
+
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        ui.heading("RSS Feeds");
+    });
+    ui.label("Entries from these feeds are kept locally and never published.");
+    ui.add_space(10.0);
+
+    ui.horizontal(|ui| {
+        ui.label("Feed URL:");
+        ui.add(text_edit_line!(app, app.rss_new_feed).hint_text("https://example.com/feed.xml"));
+        if ui.button("Add").clicked() {
+            match gossip_lib::rss::add_feed(&app.rss_new_feed) {
+                Ok(()) => app.rss_new_feed.clear(),
+                Err(e) => GLOBALS.status_queue.write().write(format!("{}", e)),
+            }
+        }
+        if ui.button("Poll now").clicked() {
+            task::spawn(async move {
+                match gossip_lib::rss::poll_feeds().await {
+                    Ok(count) => GLOBALS
+                        .status_queue
+                        .write()
+                        .write(format!("RSS: {} new entries", count)),
+                    Err(e) => tracing::error!("{}", e),
+                }
+            });
+            app.rss_next_refresh = Instant::now();
+        }
+    });
+
+    for feed_url in GLOBALS.storage.read_setting_rss_feeds().iter() {
+        ui.horizontal(|ui| {
+            if ui.button("Remove").clicked() {
+                if let Err(e) = gossip_lib::rss::remove_feed(feed_url) {
+                    GLOBALS.status_queue.write().write(format!("{}", e));
+                }
+                app.rss_next_refresh = Instant::now();
+            }
+            ui.label(feed_url);
+        });
+    }
+
+    if !GLOBALS.storage.read_setting_rss_enabled() {
+        ui.add_space(10.0);
+        ui.label("RSS polling is disabled. You can enable it in Settings > Content.");
+    }
+
+    ui.add_space(10.0);
+    ui.separator();
+
+    app.vert_scroll_area().id_source("rss_feed").show(ui, |ui| {
+        let color = app.theme.accent_color();
+        for entry in app.rss_cache.iter() {
+            widgets::list_entry::make_frame(ui, Some(app.theme.main_content_bgcolor())).show(
+                ui,
+                |ui| {
+                    ui.set_min_width(ui.available_width());
+                    ui.horizontal_wrapped(|ui| {
+                        ui.add(Label::new(
+                            RichText::new(&entry.feed_title).small().color(color),
+                        ));
+                        ui.label(crate::date_ago::date_ago(entry.published));
+                    });
+                    if entry.link.is_empty() {
+                        ui.heading(&entry.title);
+                    } else {
+                        ui.hyperlink_to(RichText::new(&entry.title).heading(), &entry.link);
+                    }
+                    if !entry.summary.is_empty() {
+                        ui.label(&entry.summary);
+                    }
+                },
+            );
+            ui.add_space(4.0);
+        }
+    });
+}
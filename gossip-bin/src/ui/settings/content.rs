@@ -2,6 +2,11 @@ use crate::ui::GossipUi;
 use eframe::egui;
 use egui::widgets::Slider;
 use egui::{Context, Ui};
+use gossip_lib::dm_policy::{self, DmPolicy};
+use gossip_lib::notifications;
+use gossip_lib::remote_content::{RemoteContent, TrustTier};
+use gossip_lib::GLOBALS;
+use nostr_types::PublicKey;
 
 pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
     ui.heading("Content");
@@ -49,6 +54,45 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
         "Initially scroll to the highlighted note when entering a Thread",
     );
 
+    ui.checkbox(
+        &mut app.unsaved_settings.legacy_thread_heuristics,
+        "Understand replies from old clients (positional 'e' tags without root/reply markers)",
+    )
+    .on_hover_text("Off: only 'e' tags marked root or reply place a note in a thread; unmarked ones are mentions.");
+
+    ui.checkbox(
+        &mut app.unsaved_settings.feed_deduplicate,
+        "Deduplicate feeds (show a reposted note only once)",
+    );
+
+    ui.checkbox(
+        &mut app.unsaved_settings.feed_show_bridged,
+        "Show bridged content (notes from ActivityPub, RSS and other bridges)",
+    );
+
+    ui.checkbox(
+        &mut app.unsaved_settings.rss_enabled,
+        "Poll RSS and Atom feeds (shown locally in the RSS page, never published)",
+    );
+
+    ui.horizontal(|ui| {
+        ui.label("RSS poll interval: ");
+        ui.add(
+            Slider::new(&mut app.unsaved_settings.rss_poll_interval_mins, 5..=1440).text("minutes"),
+        );
+    });
+
+    ui.checkbox(
+        &mut app.unsaved_settings.live_activities,
+        "Follow live streams and other live activities (NIP-53) of people I follow",
+    );
+
+    ui.checkbox(
+        &mut app.unsaved_settings.smart_lists,
+        "Keep smart lists (people I talk to, recently followed, mutuals)",
+    )
+    .on_hover_text("Private lists gossip regenerates every hour from what it has stored. They can be viewed as feeds but are never published.");
+
     ui.add_space(10.0);
     ui.heading("Event Selection Settings");
     ui.add_space(10.0);
@@ -83,6 +127,170 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
     )
         .on_hover_text("Unless a relay is marked as SpamSafe, replies and mentions will only be pulled from people you follow. Takes effect fully only on restart.");
 
+    ui.horizontal(|ui| {
+        ui.label("Who can DM me:").on_hover_text("Direct messages from anyone else go to Message Requests, and do not notify you or show in your inbox until you accept them. Kept for each identity; takes effect immediately.");
+        let current = dm_policy::dm_policy();
+        egui::ComboBox::from_id_source("dm_policy")
+            .selected_text(current.to_string())
+            .show_ui(ui, |ui| {
+                for policy in DmPolicy::all() {
+                    if ui
+                        .add(egui::widgets::SelectableLabel::new(
+                            *policy == current,
+                            policy.to_string(),
+                        ))
+                        .clicked()
+                    {
+                        if let Err(e) = dm_policy::set_dm_policy(*policy) {
+                            GLOBALS.status_queue.write().write(format!("{}", e));
+                        }
+                    }
+                }
+            });
+    });
+
+    ui.add_space(10.0);
+    ui.checkbox(
+        &mut app.unsaved_settings.dm_autoreply,
+        "Answer DMs automatically (away message)",
+    )
+    .on_hover_text("Only DMs from people your DM policy accepts are answered, and only while your key is unlocked. Answers start with \"[Automatic reply]\".");
+    if app.unsaved_settings.dm_autoreply {
+        ui.label("Message ({name}, {my_name} and {date} are filled in):");
+        ui.add(
+            text_edit_multiline!(app, app.unsaved_settings.dm_autoreply_message).desired_rows(3),
+        );
+        ui.horizontal(|ui| {
+            ui.label("Answer each person at most once every");
+            ui.add(
+                Slider::new(
+                    &mut app.unsaved_settings.dm_autoreply_interval_hours,
+                    1..=168,
+                )
+                .text("hours"),
+            );
+        });
+        ui.label("Only answer these people (npubs, empty for everyone):");
+        ui.add(
+            text_edit_multiline!(app, app.unsaved_settings.dm_autoreply_allowlist).desired_rows(2),
+        );
+        ui.label("Never answer these people (npubs):");
+        ui.add(
+            text_edit_multiline!(app, app.unsaved_settings.dm_autoreply_denylist).desired_rows(2),
+        );
+    }
+
+    ui.add_space(10.0);
+    ui.heading("Alerts");
+    ui.add_space(10.0);
+
+    ui.checkbox(
+        &mut app.unsaved_settings.alert_dm_from_follow,
+        "Alert on DMs from people I follow",
+    );
+    ui.checkbox(&mut app.unsaved_settings.alert_dm, "Alert on other DMs")
+        .on_hover_text("Only DMs accepted by your DM policy ever alert.");
+    ui.checkbox(
+        &mut app.unsaved_settings.alert_mention,
+        "Alert on mentions and replies",
+    );
+    ui.checkbox(
+        &mut app.unsaved_settings.alert_reaction,
+        "Alert on reactions",
+    );
+    ui.checkbox(
+        &mut app.unsaved_settings.notify_only_my_threads,
+        "Only notify about replies in threads I started or replied in",
+    )
+    .on_hover_text("Otherwise any reply that tags you notifies you. Notes that mention you without replying always do.");
+    ui.horizontal(|ui| {
+        ui.label("Notify about mentions and reactions from:");
+        let current = TrustTier::from_setting(&app.unsaved_settings.notify_min_trust);
+        egui::ComboBox::from_id_source("notify_min_trust")
+            .selected_text(current.to_string())
+            .show_ui(ui, |ui| {
+                for tier in TrustTier::all() {
+                    if ui
+                        .add(egui::widgets::SelectableLabel::new(
+                            *tier == current,
+                            tier.to_string(),
+                        ))
+                        .clicked()
+                    {
+                        app.unsaved_settings.notify_min_trust = tier.as_setting().to_owned();
+                    }
+                }
+            });
+    })
+    .response
+    .on_hover_text("Each choice includes the ones above it.");
+    ui.checkbox(&mut app.unsaved_settings.alert_quiet_hours, "Quiet hours")
+        .on_hover_text("No alerts during these hours (local time).");
+    if app.unsaved_settings.alert_quiet_hours {
+        ui.horizontal(|ui| {
+            ui.label("From");
+            ui.add(Slider::new(&mut app.unsaved_settings.alert_quiet_start_hour, 0..=23).text("h"));
+            ui.label("until");
+            ui.add(Slider::new(&mut app.unsaved_settings.alert_quiet_end_hour, 0..=23).text("h"));
+        });
+        ui.checkbox(
+            &mut app.unsaved_settings.alert_quiet_hours_allow_follow_dms,
+            "Still alert on DMs from people I follow",
+        );
+    }
+
+    ui.add_space(10.0);
+    ui.label("Also gather the notifications of these accounts:")
+        .on_hover_text(
+            "Mentions of them are fetched from their own inbox relays and shown with yours.",
+        );
+    let mine = GLOBALS.identity.public_key();
+    for identity in notifications::identities() {
+        if Some(identity.pubkey) == mine {
+            continue;
+        }
+        ui.horizontal(|ui| {
+            let label = if identity.label.is_empty() {
+                gossip_lib::names::pubkey_short(&identity.pubkey)
+            } else {
+                identity.label.clone()
+            };
+            ui.label(label)
+                .on_hover_text(identity.pubkey.as_bech32_string());
+            if ui.button("Remove").clicked() {
+                if let Err(e) = notifications::remove_identity(identity.pubkey) {
+                    GLOBALS.status_queue.write().write(format!("{}", e));
+                }
+            }
+        });
+    }
+    ui.horizontal(|ui| {
+        ui.label("npub or hex:");
+        ui.text_edit_singleline(&mut app.notify_identity_key);
+        ui.label("label:");
+        ui.text_edit_singleline(&mut app.notify_identity_label);
+        if ui.button("Add").clicked() {
+            let key = app.notify_identity_key.trim();
+            let pubkey = PublicKey::try_from_bech32_string(key, true)
+                .or_else(|_| PublicKey::try_from_hex_string(key, true));
+            match pubkey {
+                Ok(pubkey) => {
+                    match notifications::add_identity(pubkey, &app.notify_identity_label) {
+                        Ok(()) => {
+                            app.notify_identity_key.clear();
+                            app.notify_identity_label.clear();
+                        }
+                        Err(e) => GLOBALS.status_queue.write().write(format!("{}", e)),
+                    }
+                }
+                Err(_) => GLOBALS
+                    .status_queue
+                    .write()
+                    .write("That is not a valid npub or hex public key".to_owned()),
+            }
+        }
+    });
+
     ui.add_space(10.0);
     ui.heading("Event Content Settings");
     ui.add_space(10.0);
@@ -98,6 +306,35 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
     });
 
     ui.checkbox(&mut app.unsaved_settings.show_media, "Render all media inline automatically").on_hover_text("If off, you have to click to (potentially fetch and) render media inline. If on, all media referenced by posts in your feed will be (potentially fetched and) rendered. However, if Fetch Media is disabled, only cached media can be shown as media will not be fetched.");
+
+    ui.label("Media and link previews, by whose post they are in:").on_hover_text("Fetching media tells its host your IP address and that you looked at it. When media isn't loaded you can still open it in a browser.");
+    for tier in TrustTier::all() {
+        let setting = match tier {
+            TrustTier::Followed => &mut app.unsaved_settings.remote_content_followed,
+            TrustTier::SecondDegree => &mut app.unsaved_settings.remote_content_second_degree,
+            TrustTier::Stranger => &mut app.unsaved_settings.remote_content_strangers,
+        };
+        ui.horizontal(|ui| {
+            ui.add_space(20.0);
+            ui.label(format!("{}:", tier));
+            let current = RemoteContent::from_setting(setting);
+            egui::ComboBox::from_id_source(("remote_content", *tier as usize))
+                .selected_text(current.to_string())
+                .show_ui(ui, |ui| {
+                    for policy in RemoteContent::all() {
+                        if ui
+                            .add(egui::widgets::SelectableLabel::new(
+                                *policy == current,
+                                policy.to_string(),
+                            ))
+                            .clicked()
+                        {
+                            *setting = policy.as_setting().to_owned();
+                        }
+                    }
+                });
+        });
+    }
     ui.checkbox(&mut app.unsaved_settings.approve_content_warning, "Approve all content-warning tagged media automatically")
         .on_hover_text("If off, you have to click to show content-warning tagged media. If on, all content-warning tagged media in your feed will be rendered.");
 
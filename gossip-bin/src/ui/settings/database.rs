@@ -4,7 +4,7 @@ use eframe::egui;
 use egui::widgets::Slider;
 use egui::{Context, Ui};
 use gossip_lib::comms::ToOverlordMessage;
-use gossip_lib::GLOBALS;
+use gossip_lib::{Profile, GLOBALS};
 
 pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
     ui.heading("Storage Settings");
@@ -25,19 +25,212 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
         );
     });
 
+    ui.add_space(10.0);
+    ui.checkbox(&mut app.unsaved_settings.auto_prune, "Delete old events automatically")
+        .on_hover_text("Every few hours, delete events by the policies below. Direct messages, your own events, and events you have bookmarked or starred are always kept.");
+
+    ui.add_enabled_ui(app.unsaved_settings.auto_prune, |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Keep the events table under")
+                .on_hover_text("Once events take up more than this, the oldest are deleted until they don't. Zero means no limit.");
+            ui.add(
+                Slider::new(&mut app.unsaved_settings.prune_max_events_mb, 0..=20000)
+                    .text("MB"),
+            );
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Keep events of these kinds for fewer days:")
+                .on_hover_text("Pairs of kind:days separated by spaces, such as \"7:14 6:30\" to keep reactions for 14 days and reposts for 30. Other kinds are kept for as long as set above.");
+            ui.add(
+                text_edit_line!(app, app.unsaved_settings.prune_kind_days).desired_width(200.0),
+            );
+        });
+    });
+
+    ui.add_space(10.0);
+    ui.checkbox(&mut app.unsaved_settings.relaxed_durability, "Relaxed durability")
+        .on_hover_text("Don't wait for the disk after every write, only after writes of keys and your own events, and once a minute otherwise. This is faster, but a power loss or system crash may lose the last minute of changes. Events from relays are always written this way since they can be fetched again.");
+
     // Only let them prune after they have saved
     let stored_settings = UnsavedSettings::load();
     if stored_settings == app.unsaved_settings {
         ui.add_space(20.0);
-        if ui.button("Delete Old Events Now").on_hover_text("This will delete events older than the period specified above. but the LMDB files will continue consuming disk space. To compact them, copy withem with `mdb_copy -c` when gossip is not running.").clicked() {
+        if ui.button("Delete Old Events Now").on_hover_text("This will delete events older than the period specified above, but the LMDB files will continue consuming disk space until they are compacted.").clicked() {
             let _ = GLOBALS.to_overlord.send(ToOverlordMessage::PruneDatabase);
         }
 
+        ui.add_space(20.0);
+        if ui.button("Compact Database").on_hover_text("This shrinks the LMDB files down to what they hold, which is worth doing after deleting many events. Gossip disconnects from relays while it copies the database. The space is only freed on disk once gossip exits.").clicked() {
+            let _ = GLOBALS.to_overlord.send(ToOverlordMessage::CompactDatabase);
+        }
+
         ui.add_space(20.0);
         if ui.button("Delete Old Downloaded Files").on_hover_text("This will delete cache files with modification times older than the period specified above (unfortunately access times are often unavailable and/or unreliable). Note that this will eventually delete everybody's avatar, even if those are in heavy use.").clicked() {
             let _ = GLOBALS.to_overlord.send(ToOverlordMessage::PruneCache);
         }
     }
 
+    ui.add_space(20.0);
+    ui.heading("Profiles");
+    ui.add_space(10.0);
+
+    let current = Profile::current().ok().and_then(|p| p.name);
+    ui.label(format!(
+        "Current profile: {}",
+        current.as_deref().unwrap_or("(default)")
+    ));
+    ui.horizontal_wrapped(|ui| {
+        if current.is_some() && ui.button("(default)").clicked() {
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::SwitchProfile(None));
+        }
+        for name in Profile::list().unwrap_or_default() {
+            if current.as_deref() != Some(name.as_str()) && ui.button(&name).clicked() {
+                let _ = GLOBALS
+                    .to_overlord
+                    .send(ToOverlordMessage::SwitchProfile(Some(name)));
+            }
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("New profile:");
+        ui.add(text_edit_line!(app, app.profile_name));
+        if ui
+            .button("Create and Switch")
+            .on_hover_text("Each profile has a database (and identity) of its own. Switching disconnects from all relays and reconnects as the other profile.")
+            .clicked()
+            && !app.profile_name.trim().is_empty()
+        {
+            let _ = GLOBALS.to_overlord.send(ToOverlordMessage::SwitchProfile(Some(
+                app.profile_name.trim().to_owned(),
+            )));
+            app.profile_name.clear();
+        }
+    });
+
+    ui.add_space(20.0);
+    ui.heading("Encryption at Rest");
+    ui.add_space(10.0);
+
+    let mut encrypt = GLOBALS.storage.read_setting_encrypt_at_rest();
+    if ui
+        .checkbox(&mut encrypt, "Encrypt private data on disk")
+        .on_hover_text("Data that frontends keep in the database (such as drafts) is encrypted with a key derived from your private key, and can only be read while your identity is unlocked. Data already stored is rewritten when you change this (or at your next login if locked). Your private key is always stored encrypted; events are stored as received.")
+        .changed()
+    {
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::SetEncryptAtRest(encrypt));
+    }
+
+    ui.add_space(20.0);
+    ui.heading("Event Audit");
+    ui.add_space(10.0);
+
+    let report = GLOBALS.event_audit.read().clone();
+    match report {
+        Some(report) if report.finished.is_none() => {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label(format!(
+                    "Verifying events: {} checked, {} failed so far",
+                    report.checked,
+                    report.failed.len()
+                ));
+            });
+        }
+        report => {
+            if ui.button("Verify All Events").on_hover_text("This re-checks the id and signature of every stored event in the background. Events that fail (from disk corruption or old bugs) are quarantined: moved out of the events table and kept aside for inspection.").clicked() {
+                let _ = GLOBALS.to_overlord.send(ToOverlordMessage::AuditEvents);
+            }
+            if let Some(report) = report {
+                ui.label(format!(
+                    "Last audit: {} checked, {} failed and quarantined",
+                    report.checked,
+                    report.failed.len()
+                ));
+                for (id, reason) in report.failed.iter().take(50) {
+                    ui.label(
+                        egui::RichText::new(format!("{}: {}", id.as_hex_string(), reason))
+                            .monospace()
+                            .small(),
+                    );
+                }
+            }
+        }
+    }
+
+    ui.add_space(20.0);
+    ui.heading("History Sweep");
+    ui.add_space(10.0);
+
+    ui.horizontal(|ui| {
+        ui.label("Relays to collect your past events from:")
+            .on_hover_text("Separated by spaces or commas");
+        ui.add(
+            egui::TextEdit::singleline(&mut app.unsaved_settings.history_sweep_relays)
+                .hint_text("wss://")
+                .desired_width(400.0),
+        );
+    });
+
+    let report = GLOBALS.history_sweep.read().clone();
+    match report {
+        Some(report) if report.finished.is_none() => {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label(format!(
+                    "Sweeping {}: {} of your events found, {} new",
+                    report
+                        .current
+                        .as_ref()
+                        .map(|r| r.as_str())
+                        .unwrap_or("relays"),
+                    report.found,
+                    report.imported
+                ));
+            });
+        }
+        report => {
+            if stored_settings == app.unsaved_settings {
+                ui.horizontal(|ui| {
+                    if ui.button("Collect My Past Events").on_hover_text("This asks each relay above for every event you ever posted, and stores the ones gossip doesn't have.").clicked() {
+                        let _ = GLOBALS
+                            .to_overlord
+                            .send(ToOverlordMessage::SweepHistory { rebroadcast: false });
+                    }
+                    if ui.button("Collect and Rebroadcast").on_hover_text("As well as collecting them, this sends your past events to each of your write relays that hasn't been seen with them.").clicked() {
+                        let _ = GLOBALS
+                            .to_overlord
+                            .send(ToOverlordMessage::SweepHistory { rebroadcast: true });
+                    }
+                });
+            }
+            if let Some(report) = report {
+                ui.label(format!(
+                    "Last sweep: {} of your events found, {} were new{}",
+                    report.found,
+                    report.imported,
+                    if report.rebroadcast {
+                        ", rebroadcast to your write relays"
+                    } else {
+                        ""
+                    }
+                ));
+                for result in report.relays.iter() {
+                    let text = match &result.error {
+                        Some(e) => {
+                            format!("{}: {} found, stopped: {}", result.relay, result.found, e)
+                        }
+                        None => format!("{}: {} found", result.relay, result.found),
+                    };
+                    ui.label(egui::RichText::new(text).small());
+                }
+            }
+        }
+    }
+
     ui.add_space(20.0);
 }
@@ -2,6 +2,9 @@ use crate::ui::{GossipUi, Page};
 use eframe::egui;
 use egui::widgets::Slider;
 use egui::{Context, Ui};
+use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::GLOBALS;
+use nostr_types::RelayUrl;
 
 pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
     ui.heading("Network Settings");
@@ -29,6 +32,36 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
         "Require user approval before AUTHenticating to a relay for the first time",
     );
 
+    ui.add_space(10.0);
+    ui.heading("Profile View Settings");
+    ui.add_space(10.0);
+
+    ui.label("Viewing someone's profile fetches:")
+        .on_hover_text("On a slow or metered connection, turn off what you don't need. What is not fetched is still shown if it was fetched before.");
+    ui.checkbox(
+        &mut app.unsaved_settings.profile_fetch_metadata,
+        "Their metadata",
+    );
+    ui.checkbox(
+        &mut app.unsaved_settings.profile_fetch_relay_list,
+        "Their relay list",
+    );
+    ui.checkbox(&mut app.unsaved_settings.profile_fetch_notes, "Their notes");
+    if app.unsaved_settings.profile_fetch_notes {
+        ui.horizontal(|ui| {
+            ui.label("At most: ")
+                .on_hover_text("0 fetches all of their notes from the person feed chunk");
+            ui.add(
+                Slider::new(&mut app.unsaved_settings.profile_fetch_notes_limit, 0..=200)
+                    .text("notes"),
+            );
+        });
+    }
+    ui.checkbox(
+        &mut app.unsaved_settings.profile_fetch_augments,
+        "Reactions, zaps and replies of their notes",
+    );
+
     ui.add_space(10.0);
     ui.heading("Relay Settings");
     ui.add_space(10.0);
@@ -46,11 +79,43 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
         ui.add(Slider::new(&mut app.unsaved_settings.num_relays_per_person, 1..=3).text("relays"));
     });
 
+    ui.horizontal(|ui| {
+        ui.label("Treat people as dormant after not posting for: ").on_hover_text("Dormant people are queried on just their best relay, leaving more connections for people who post, and their feed looks further back when connecting. 0 turns this off. Takes effect when relays are next picked.");
+        ui.add(Slider::new(&mut app.unsaved_settings.dormant_after_days, 0..=365).text("days"));
+    });
+
     ui.horizontal(|ui| {
         ui.label("Maximum following feed relays: ").on_hover_text("We will not stay connected to more than this many relays for following feed. Takes affect on restart. During these early days of nostr, I recommend capping this at around 20 to 30.");
         ui.add(Slider::new(&mut app.unsaved_settings.max_relays, 5..=100).text("relays"));
     });
 
+    ui.checkbox(
+        &mut app.unsaved_settings.relay_latency_probing,
+        "Periodically measure the latency of relays you use",
+    )
+    .on_hover_text("Opens a short-lived connection to each relay and times how long it takes to answer a request.");
+
+    ui.horizontal(|ui| {
+        ui.label("Re-measure relay latency every");
+        ui.add(
+            Slider::new(
+                &mut app.unsaved_settings.relay_latency_probe_interval_mins,
+                10..=1440,
+            )
+            .text("minutes"),
+        );
+    });
+
+    ui.checkbox(
+        &mut app.unsaved_settings.relay_prefer_low_latency,
+        "Prefer low-latency relays",
+    )
+    .on_hover_text(
+        "When choosing between relays that would otherwise be equally good, pick the faster one.",
+    );
+
+    blocked_relays(app, ui);
+
     ui.add_space(10.0);
     ui.heading("HTTP Fetch Settings");
     ui.add_space(10.0);
@@ -214,6 +279,14 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
             .text("minutes"),
         );
     });
+    ui.horizontal(|ui| {
+        ui.label("Most people to refresh per minute")
+            .on_hover_text("Stale metadata and relay lists are only refreshed for people currently on screen, and no more than this many per minute.");
+        ui.add(Slider::new(
+            &mut app.unsaved_settings.profile_refresh_max_per_minute,
+            10..=300,
+        ));
+    });
     ui.horizontal(|ui| {
         ui.label("How long before valid nip05 becomes stale and needs rechecking?");
         ui.add(
@@ -249,3 +322,59 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
 
     ui.add_space(20.0);
 }
+
+fn blocked_relays(app: &mut GossipUi, ui: &mut Ui) {
+    ui.add_space(10.0);
+    ui.heading("Blocked Relays");
+    ui.add_space(10.0);
+
+    ui.label("Blocked relays are never connected to, picked, or followed as hints. Changes take effect right away.");
+
+    ui.horizontal(|ui| {
+        ui.label("Relay or pattern:").on_hover_text("A relay URL is added to your (publishable) blocked relays list. A pattern such as *.badhost.com or wss://relay.example.com/* is kept locally.");
+        ui.add(text_edit_line!(app, app.blocked_relay_new).hint_text("wss://relay.example.com"));
+        if ui.button("Block").clicked() {
+            let entry = app.blocked_relay_new.trim();
+            let result = match RelayUrl::try_from_str(entry) {
+                Ok(url) if !entry.contains('*') => gossip_lib::blocked_relays::block_relay(&url),
+                _ => gossip_lib::blocked_relays::add_blocked_relay_pattern(entry),
+            };
+            match result {
+                Ok(()) => app.blocked_relay_new.clear(),
+                Err(e) => GLOBALS.status_queue.write().write(format!("{}", e)),
+            }
+        }
+    });
+
+    for blocked in GLOBALS.storage.read_setting_blocked_relays().iter() {
+        ui.horizontal(|ui| {
+            if ui.button("Unblock").clicked() {
+                if let Ok(url) = RelayUrl::try_from_str(blocked) {
+                    if let Err(e) = gossip_lib::blocked_relays::unblock_relay(&url) {
+                        GLOBALS.status_queue.write().write(format!("{}", e));
+                    }
+                }
+            }
+            ui.label(blocked);
+        });
+    }
+
+    for pattern in GLOBALS.storage.read_setting_blocked_relay_patterns().iter() {
+        ui.horizontal(|ui| {
+            if ui.button("Remove").clicked() {
+                if let Err(e) = gossip_lib::blocked_relays::remove_blocked_relay_pattern(pattern) {
+                    GLOBALS.status_queue.write().write(format!("{}", e));
+                }
+            }
+            ui.label(format!("{} (local pattern)", pattern));
+        });
+    }
+
+    if ui
+        .button("Publish blocked relays list")
+        .on_hover_text("Publish your blocked relays (not the local patterns) so that your other clients can use them")
+        .clicked()
+    {
+        let _ = GLOBALS.to_overlord.send(ToOverlordMessage::PushBlockedRelays);
+    }
+}
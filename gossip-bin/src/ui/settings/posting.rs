@@ -13,6 +13,120 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
         ui.add(Slider::new(&mut app.unsaved_settings.pow, 0..=40).text("leading zero bits"));
     });
 
+    ui.checkbox(
+        &mut app.unsaved_settings.split_long_notes,
+        "Split long notes into a numbered thread",
+    )
+    .on_hover_text("Notes longer than the limit below are posted as a chain of replies (1/n, 2/n, ...). Relays with their own content limit apply theirs too.");
+
+    if app.unsaved_settings.split_long_notes {
+        ui.horizontal(|ui| {
+            ui.label("Split notes longer than: ");
+            ui.add(
+                Slider::new(
+                    &mut app.unsaved_settings.split_long_notes_threshold,
+                    280..=20000,
+                )
+                .text("characters"),
+            );
+        });
+    }
+
+    ui.checkbox(
+        &mut app.unsaved_settings.alt_text_reminder,
+        "Ask for alt text for images and videos in posts",
+    )
+    .on_hover_text(
+        "Alt text describes media for people using screen readers or not loading media.",
+    );
+
+    ui.checkbox(
+        &mut app.unsaved_settings.alt_text_required,
+        "Don't post images or videos without alt text",
+    );
+
+    ui.horizontal(|ui| {
+        ui.label("Hold back posts that repeat one from the last: ")
+            .on_hover_text("A post with the same (or nearly the same) text as one of yours from this window waits in pending notifications until you confirm it. 0 turns this off.");
+        ui.add(
+            Slider::new(&mut app.unsaved_settings.duplicate_post_window_hours, 0..=168)
+                .text("hours"),
+        );
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Each post must reach at least: ")
+            .on_hover_text("If fewer of your write relays accept a post, it is sent to your other write relays and then to the fallback relays below. 0 turns this off.");
+        ui.add(
+            Slider::new(&mut app.unsaved_settings.write_redundancy_target, 0..=10)
+                .text("relays"),
+        );
+    });
+
+    ui.checkbox(
+        &mut app.unsaved_settings.write_relay_rotation,
+        "Send each post to only some of my write relays, taking turns",
+    )
+    .on_hover_text("So that no one write relay sees everything you post. Each post goes to as many as it must reach above (at least 2). Profile, lists and other replaceable events still go to all of them.");
+
+    if app.unsaved_settings.write_relay_rotation {
+        let counts = gossip_lib::write_rotation::rotation_counts();
+        if !counts.is_empty() {
+            egui::CollapsingHeader::new("Posts each write relay got").show(ui, |ui| {
+                for (url, count) in counts.iter() {
+                    ui.label(format!("{}: {}", url, count));
+                }
+            });
+        }
+    }
+
+    if app.unsaved_settings.write_redundancy_target > 0 {
+        ui.horizontal(|ui| {
+            ui.label("Fallback relays: ")
+                .on_hover_text("Separated by spaces or commas");
+            ui.add(
+                egui::TextEdit::singleline(&mut app.unsaved_settings.fallback_write_relays)
+                    .hint_text("wss://")
+                    .desired_width(400.0),
+            );
+        });
+
+        let reports = gossip_lib::write_redundancy::reports();
+        if !reports.is_empty() {
+            egui::CollapsingHeader::new("Recent posts").show(ui, |ui| {
+                for report in reports.iter() {
+                    let state = if report.finished.is_some() {
+                        "done"
+                    } else {
+                        "still trying"
+                    };
+                    ui.label(format!(
+                        "#{}: reached {} of {} relays after {} rounds of alternates, {}",
+                        gossip_lib::names::hex_id_short(&report.id.into()),
+                        report.reached.len(),
+                        report.target,
+                        report.rounds,
+                        state
+                    ))
+                    .on_hover_text(
+                        report
+                            .tried
+                            .iter()
+                            .map(|url| url.as_str())
+                            .collect::<Vec<&str>>()
+                            .join("\n"),
+                    );
+                }
+            });
+        }
+    }
+
+    ui.checkbox(
+        &mut app.unsaved_settings.encrypt_with_nip44,
+        "Encrypt private list entries with NIP-44",
+    )
+    .on_hover_text("NIP-44 is the newer and safer encryption. Turn this off to use NIP-04 for clients that only understand that. Old-style (kind 4) DMs always use NIP-04. Both are always decrypted.");
+
     ui.checkbox(
         &mut app.unsaved_settings.set_client_tag,
         "Add tag [\"client\",\"gossip\"] to posts",
@@ -28,5 +142,28 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
     )
     .on_hover_text("Takes effect on next relay connection.");
 
+    ui.add_space(10.0);
+    ui.heading("Privacy Mode");
+    ui.add_space(10.0);
+
+    ui.checkbox(
+        &mut app.unsaved_settings.privacy_mode,
+        "Reduce client fingerprinting",
+    )
+    .on_hover_text("Omits (or randomizes) the client tag even if set above, pads encrypted JSON payloads, and backdates posts by a random amount within the window below.");
+
+    ui.checkbox(
+        &mut app.unsaved_settings.privacy_randomize_client_tag,
+        "Use a random common client name in the client tag instead of omitting it",
+    );
+
+    ui.horizontal(|ui| {
+        ui.label("Posting time jitter: ");
+        ui.add(
+            Slider::new(&mut app.unsaved_settings.privacy_time_jitter_secs, 0..=900)
+                .text("seconds"),
+        );
+    });
+
     ui.add_space(20.0);
 }
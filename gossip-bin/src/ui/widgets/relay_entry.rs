@@ -3,7 +3,7 @@ use eframe::egui::{self, *};
 use nostr_types::{PublicKeyHex, RelayUrl, Unixtime};
 
 use crate::ui::{widgets, GossipUi};
-use gossip_lib::{comms::ToOverlordMessage, Relay, GLOBALS};
+use gossip_lib::{comms::ToOverlordMessage, Relay, RelayCapabilities, GLOBALS};
 
 use super::{
     list_entry::{
@@ -21,6 +21,8 @@ const DETAIL_VIEW_HEIGHT: f32 = 80.0;
 const EDIT_VIEW_HEIGHT: f32 = 250.0;
 /// Height required for one auth-permission drop-down
 const EDIT_VIEW_AUTH_PERM_HEIGHT: f32 = 25.0;
+/// Height of the posting policy section
+const EDIT_VIEW_POSTING_HEIGHT: f32 = 60.0;
 /// Y-offset for first separator
 const HLINE_1_Y_OFFSET: f32 = LIST_VIEW_HEIGHT - 12.0;
 /// Y-offset for second separator
@@ -74,6 +76,9 @@ const WRITE_HOVER_TEXT: &str =
     "Where you actually write your events to. It is recommended to have a few.";
 const OUTBOX_HOVER_TEXT: &str = "Where you tell others you write to. You should also check Write. It is recommended to have a few.";
 const SPAMSAFE_HOVER_TEXT: &str = "Relay is trusted to filter spam. If not set, replies and mentions from unfollowed people will not be fetched from the relay (when SpamSafe is enabled in settings).";
+const SEARCH_HOVER_TEXT: &str =
+    "Where you send note searches (NIP-50). The relay must support search.";
+const DM_HOVER_TEXT: &str = "Where you tell others to send you private messages (NIP-17). These relays should only serve them to you.";
 
 #[derive(Clone, PartialEq)]
 pub enum RelayEntryView {
@@ -128,6 +133,8 @@ struct UsageBits {
     outbox: bool,
     discover: bool,
     spamsafe: bool,
+    search: bool,
+    dm: bool,
 }
 
 impl UsageBits {
@@ -140,6 +147,8 @@ impl UsageBits {
             outbox: usage_bits & Relay::OUTBOX == Relay::OUTBOX,
             discover: usage_bits & Relay::DISCOVER == Relay::DISCOVER,
             spamsafe: usage_bits & Relay::SPAMSAFE == Relay::SPAMSAFE,
+            search: usage_bits & Relay::SEARCH == Relay::SEARCH,
+            dm: usage_bits & Relay::DM == Relay::DM,
         }
     }
 
@@ -446,6 +455,27 @@ impl RelayEntry {
             ));
         }
 
+        let pos = pos + vec2(150.0, 0.0);
+        let id = self.make_id("test_link");
+        let test_response = draw_link_at(
+            ui,
+            id,
+            pos,
+            "Test Relay".into(),
+            Align::Min,
+            self.enabled,
+            true,
+        );
+        if test_response.clicked() {
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::TestRelay(self.relay.url.to_owned()));
+        }
+        test_response.on_hover_text(match &self.relay.capabilities {
+            Some(report) => capabilities_text(report),
+            None => "Not tested yet".to_owned(),
+        });
+
         // pass the response back so the page knows the edit view should close
         response
     }
@@ -459,7 +489,7 @@ impl RelayEntry {
                 self.relay.success_rate() * 100.0,
                 self.relay.success_count
             ));
-            draw_text_at(
+            let rect = draw_text_at(
                 ui,
                 pos,
                 text.into(),
@@ -467,6 +497,22 @@ impl RelayEntry {
                 Some(ui.visuals().text_color()),
                 None,
             );
+            let mut latency = match (self.relay.latency_p50_ms, self.relay.latency_p90_ms) {
+                (Some(p50), Some(p90)) => format!("Latency: {}ms median, {}ms p90", p50, p90),
+                _ => "Latency: not measured yet".to_owned(),
+            };
+            if let Some(uptime) = self.relay.network_uptime() {
+                latency.push_str(&format!(
+                    "\nRelay monitors: up for {:.0}% of {}",
+                    uptime * 100.0,
+                    self.relay.monitor_reports.len()
+                ));
+                if let Some(ms) = self.relay.network_latency_ms() {
+                    latency.push_str(&format!(", {}ms median", ms));
+                }
+            }
+            ui.interact(rect, self.make_id("latency"), Sense::hover())
+                .on_hover_text(latency);
 
             // ---- Following ----
             let pos = pos + vec2(STATS_COL_2_X, 0.0);
@@ -918,6 +964,36 @@ impl RelayEntry {
                 None,
             );
         }
+        {
+            // ---- dm ----
+            let pos = pos + vec2(2.0 * USAGE_SWITCH_X_SPACING, 0.0);
+            let id = self.make_id("dm_switch");
+            let sw_rect = Rect::from_min_size(pos - vec2(0.0, USAGE_SWITCH_Y_OFFSET), switch_size);
+            let response = widgets::switch_custom_at(
+                ui,
+                true,
+                &mut self.usage.dm,
+                sw_rect,
+                id,
+                knob_fill,
+                on_fill,
+                off_fill,
+            );
+            if response.changed() {
+                modify_relay(&self.relay.url, |relay| {
+                    relay.adjust_usage_bit(Relay::DM, self.usage.dm)
+                });
+            }
+            response.on_hover_text(DM_HOVER_TEXT);
+            draw_text_at(
+                ui,
+                pos + vec2(ui.spacing().item_spacing.x + switch_size.x, 0.0),
+                "DMs".into(),
+                Align::LEFT,
+                Some(ui.visuals().text_color()),
+                None,
+            );
+        }
         let pos = pos + vec2(0.0, USAGE_SWITCH_Y_SPACING);
         {
             // ---- discover ----
@@ -1011,6 +1087,36 @@ impl RelayEntry {
                 None,
             );
         }
+        {
+            // ---- search ----
+            let pos = pos + vec2(2.0 * USAGE_SWITCH_X_SPACING, 0.0);
+            let id = self.make_id("search_switch");
+            let sw_rect = Rect::from_min_size(pos - vec2(0.0, USAGE_SWITCH_Y_OFFSET), switch_size);
+            let response = widgets::switch_custom_at(
+                ui,
+                true,
+                &mut self.usage.search,
+                sw_rect,
+                id,
+                knob_fill,
+                on_fill,
+                off_fill,
+            );
+            if response.changed() {
+                modify_relay(&self.relay.url, |relay| {
+                    relay.adjust_usage_bit(Relay::SEARCH, self.usage.search)
+                });
+            }
+            response.on_hover_text(SEARCH_HOVER_TEXT);
+            draw_text_at(
+                ui,
+                pos + vec2(ui.spacing().item_spacing.x + switch_size.x, 0.0),
+                "Search".into(),
+                Align::LEFT,
+                Some(ui.visuals().text_color()),
+                None,
+            );
+        }
         let pos = pos + vec2(0.0, USAGE_SWITCH_Y_SPACING);
         {
             // ---- rank ----
@@ -1145,6 +1251,93 @@ impl RelayEntry {
         });
     }
 
+    fn paint_posting_policy(&self, ui: &mut Ui, rect: &Rect, top: f32) {
+        let pos = rect.left_top() + vec2(TEXT_LEFT, top + 8.0);
+        let size = vec2(
+            rect.width() - TEXT_LEFT - TEXT_RIGHT,
+            EDIT_VIEW_POSTING_HEIGHT - 10.0,
+        );
+
+        ui.allocate_ui_at_rect(Rect::from_min_size(pos, size), |ui| {
+            ui.horizontal(|ui| {
+                ui.label("When posting here:");
+
+                let mut custom_pow = self.relay.post_pow.is_some();
+                if ui
+                    .checkbox(&mut custom_pow, "Proof of work")
+                    .on_hover_text("Use this difficulty instead of the global setting")
+                    .changed()
+                {
+                    modify_relay(&self.relay.url, |relay| {
+                        relay.post_pow = if custom_pow { Some(0) } else { None };
+                    });
+                }
+                if let Some(mut pow) = self.relay.post_pow {
+                    if ui
+                        .add(egui::DragValue::new(&mut pow).clamp_range(0..=40))
+                        .changed()
+                    {
+                        modify_relay(&self.relay.url, |relay| relay.post_pow = Some(pow));
+                    }
+                }
+
+                ui.add_space(20.0);
+                ui.label("Client tag:");
+                let mut client_tag = self.relay.post_client_tag;
+                let text = |v: Option<bool>| match v {
+                    None => "Default",
+                    Some(true) => "Include",
+                    Some(false) => "Omit",
+                };
+                egui::ComboBox::from_id_source(self.make_id("post_client_tag"))
+                    .width(70.0)
+                    .selected_text(text(client_tag))
+                    .show_ui(ui, |ui| {
+                        for v in [None, Some(true), Some(false)] {
+                            if ui.selectable_value(&mut client_tag, v, text(v)).changed() {
+                                modify_relay(&self.relay.url, |relay| {
+                                    relay.post_client_tag = client_tag
+                                });
+                            }
+                        }
+                    });
+            });
+
+            ui.horizontal(|ui| {
+                let mut limited = self.relay.post_max_content_length.is_some();
+                if ui
+                    .checkbox(&mut limited, "Content limit")
+                    .on_hover_text("The longest note content (in characters) this relay accepts")
+                    .changed()
+                {
+                    modify_relay(&self.relay.url, |relay| {
+                        relay.post_max_content_length = if limited { Some(2000) } else { None };
+                    });
+                }
+                if let Some(mut max) = self.relay.post_max_content_length {
+                    if ui
+                        .add(egui::DragValue::new(&mut max).clamp_range(20..=100000))
+                        .changed()
+                    {
+                        modify_relay(&self.relay.url, |relay| {
+                            relay.post_max_content_length = Some(max)
+                        });
+                    }
+
+                    ui.add_space(20.0);
+                    let mut split = self.relay.post_auto_split;
+                    if ui
+                        .checkbox(&mut split, "Split longer notes into a thread")
+                        .on_hover_text("If off, longer notes are not posted to this relay")
+                        .changed()
+                    {
+                        modify_relay(&self.relay.url, |relay| relay.post_auto_split = split);
+                    }
+                }
+            });
+        });
+    }
+
     fn make_id(&self, str: &str) -> Id {
         (self.relay.url.to_string() + str).into()
     }
@@ -1201,6 +1394,8 @@ impl RelayEntry {
                 (false, false) => (EDIT_VIEW_HEIGHT, HLINE_2_Y_OFFSET),
             };
 
+        let height = height + EDIT_VIEW_POSTING_HEIGHT;
+
         let size = vec2(ui.available_width(), height);
         let rect = Rect::from_min_size(ui.next_widget_position(), size);
 
@@ -1216,6 +1411,8 @@ impl RelayEntry {
             self.paint_usage_settings(ui, &rect);
             self.paint_permissions(ui, &rect);
             paint_hline(ui, &rect, hline2_offset);
+            self.paint_posting_policy(ui, &rect, hline2_offset);
+            paint_hline(ui, &rect, hline2_offset + EDIT_VIEW_POSTING_HEIGHT);
             response |= self.paint_lower_buttons(ui, &rect);
             response |= self.paint_close_btn(ui, &rect);
         }
@@ -1260,6 +1457,53 @@ where
         .send(ToOverlordMessage::UpdateRelay(old, relay));
 }
 
+fn capabilities_text(report: &RelayCapabilities) -> String {
+    fn yes_no(b: Option<bool>) -> &'static str {
+        match b {
+            Some(true) => "yes",
+            Some(false) => "no",
+            None => "?",
+        }
+    }
+    fn millis(ms: Option<u64>) -> String {
+        match ms {
+            Some(ms) => format!("{}ms", ms),
+            None => "-".to_owned(),
+        }
+    }
+
+    let mut text = format!(
+        "Tested {}\nConnect: {}\nNIP-11: {}\nREQ round trip: {}\nWrite accepted: {}",
+        crate::date_ago::date_ago(Unixtime(report.tested_at as i64)),
+        millis(report.connect_ms),
+        yes_no(Some(report.nip11)),
+        millis(report.req_ms),
+        yes_no(report.write_accepted),
+    );
+    if let Some(message) = report.write_message.as_ref().filter(|m| !m.is_empty()) {
+        text.push_str(&format!(" ({})", message));
+    }
+    text.push_str(&format!(
+        "\nAUTH: {}{}, accepted: {}\nSearch: {}",
+        if report.auth_challenged {
+            "challenged"
+        } else {
+            "not challenged"
+        },
+        if report.auth_required {
+            ", required"
+        } else {
+            ""
+        },
+        yes_no(report.auth_accepted),
+        yes_no(report.search),
+    ));
+    if let Some(error) = &report.error {
+        text.push_str(&format!("\nError: {}", error));
+    }
+    text
+}
+
 fn permission_combo(
     ui: &mut Ui,
     permission: &mut Permission,
@@ -5,12 +5,13 @@ use egui::style::Margin;
 use egui::{Color32, Context, Frame, Stroke, Ui};
 use gossip_lib::comms::ToOverlordMessage;
 use gossip_lib::{Globals, GLOBALS};
-use nostr_types::{KeySecurity, PublicKeyHex};
+use nostr_types::{KeySecurity, PublicKeyHex, RelayUrl};
 use zeroize::Zeroize;
 
 mod delegation;
 mod metadata;
 mod nostr_connect;
+mod wallet;
 
 pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
     if app.page == Page::YourKeys {
@@ -55,6 +56,12 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
                     ui.separator();
                     ui.add_space(10.0);
 
+                    offer_relay_auth_keys(app, ui);
+
+                    ui.add_space(10.0);
+                    ui.separator();
+                    ui.add_space(10.0);
+
                     offer_delete(app, ui);
                 } else if GLOBALS.identity.has_private_key() {
                     Frame::none()
@@ -120,6 +127,8 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
         delegation::update(app, ctx, _frame, ui);
     } else if app.page == Page::YourNostrConnect {
         nostr_connect::update(app, ctx, _frame, ui);
+    } else if app.page == Page::YourWallet {
+        wallet::update(app, ctx, _frame, ui);
     }
 }
 
@@ -307,6 +316,67 @@ fn offer_export_priv_key(app: &mut GossipUi, ui: &mut Ui) {
     }
 }
 
+fn offer_relay_auth_keys(app: &mut GossipUi, ui: &mut Ui) {
+    ui.heading("Relay AUTH Keys");
+    ui.label("Authenticate to these relays with a different key than your own (e.g. a paid-membership key). Events are still signed with your own key.");
+
+    ui.add_space(6.0);
+    for (url, pubkey) in GLOBALS.identity.relay_auth_public_keys() {
+        ui.horizontal(|ui| {
+            ui.add_space(10.0);
+            ui.label(format!("{}: {}", url, pubkey.as_bech32_string()));
+            if ui.button("Remove").clicked() {
+                let _ = GLOBALS
+                    .to_overlord
+                    .send(ToOverlordMessage::ClearRelayAuthKey(url.clone()));
+            }
+        });
+    }
+
+    ui.add_space(6.0);
+    ui.horizontal(|ui| {
+        ui.add_space(10.0);
+        ui.label("Relay");
+        ui.add(text_edit_line!(app, app.relay_auth_url).hint_text("wss://"));
+    });
+    ui.horizontal(|ui| {
+        ui.add_space(10.0);
+        ui.label("Private key to AUTH with");
+        ui.add(
+            text_edit_line!(app, app.import_priv)
+                .hint_text("nsec1, or hex")
+                .password(true),
+        );
+    });
+    ui.horizontal(|ui| {
+        ui.add_space(10.0);
+        ui.label("Your passphrase");
+        ui.add(text_edit_line!(app, app.password).password(true));
+    });
+    if ui.button("Use this key for this relay").clicked() {
+        match RelayUrl::try_from_str(app.relay_auth_url.trim()) {
+            Ok(relay) => {
+                let _ = GLOBALS
+                    .to_overlord
+                    .send(ToOverlordMessage::SetRelayAuthKey {
+                        relay,
+                        privkey: app.import_priv.clone(),
+                        password: app.password.clone(),
+                    });
+                app.relay_auth_url = "".to_owned();
+            }
+            Err(e) => GLOBALS
+                .status_queue
+                .write()
+                .write(format!("Invalid relay url: {}", e)),
+        }
+        app.import_priv.zeroize();
+        app.import_priv = "".to_owned();
+        app.password.zeroize();
+        app.password = "".to_owned();
+    }
+}
+
 fn offer_import_priv_key(app: &mut GossipUi, ui: &mut Ui) {
     ui.heading("Import a Private Key");
 
@@ -3,8 +3,10 @@ use crate::ui::widgets::CopyButton;
 use eframe::egui;
 use egui::{Context, Ui};
 use gossip_lib::comms::ToOverlordMessage;
-use gossip_lib::{Nip46UnconnectedServer, GLOBALS};
-use nostr_types::RelayUrl;
+use gossip_lib::nip46::Approval;
+use gossip_lib::{BunkerState, BunkerUri, Nip46UnconnectedServer, PairingState, GLOBALS};
+use nostr_types::{RelayUrl, Unixtime};
+use std::time::Duration;
 
 pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
     ui.add_space(10.0);
@@ -13,7 +15,7 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
     });
 
     ui.add_space(10.0);
-    ui.label("NOTE: Gossip currently only acts as a signing service, using the key you have configured in gossip.");
+    ui.label("Gossip can act as a signing service for other clients, using the key you have configured in gossip, or sign with a remote signer so that your private key is not on this machine.");
 
     // Show status of unconnected server
     if let Ok(Some(unconnected_server)) = GLOBALS.storage.read_nip46_unconnected_server() {
@@ -35,9 +37,11 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
                 ui.label("   ");
                 ui.label(&token);
                 if ui.add(CopyButton::new()).clicked() {
-                    ui.output_mut(|o| o.copied_text = token);
+                    ui.output_mut(|o| o.copied_text = token.clone());
                 }
             });
+            ui.label("or scan it:");
+            app.render_qr(ui, "nip46_bunker_qr", &token);
         }
 
         // Allow delete
@@ -59,13 +63,192 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
         for server in &servers {
             let peer = server.peer_pubkey.as_bech32_string();
             ui.label(format!("name={}, Peer={}", server.name, peer));
+            if let Ok(permissions) = GLOBALS.storage.read_nip46_permissions(server.peer_pubkey) {
+                for (permission, approval) in &permissions {
+                    ui.horizontal(|ui| {
+                        let decision = match approval {
+                            Approval::None => "never".to_owned(),
+                            Approval::Always => "always".to_owned(),
+                            Approval::Until(time) => format!("until {}", time.0),
+                            Approval::Once | Approval::Ask => "ask".to_owned(),
+                        };
+                        ui.label(format!("        {}: {}", permission, decision));
+                        if ui.button("Forget").clicked() {
+                            let _ = GLOBALS.storage.delete_nip46_permission(
+                                server.peer_pubkey,
+                                permission,
+                                None,
+                            );
+                        }
+                    });
+                }
+            }
             if ui.button("Disconnect").clicked() {
                 let _ = GLOBALS.storage.delete_nip46server(server.peer_pubkey, None);
+                let _ = GLOBALS
+                    .storage
+                    .delete_nip46_permissions(server.peer_pubkey, None);
+            }
+        }
+    }
+
+    remote_signer(app, ui);
+
+    pair_with_signer(app, ui);
+
+    ui.separator();
+}
+
+fn remote_signer(app: &mut GossipUi, ui: &mut Ui) {
+    ui.separator();
+    ui.add_space(10.0);
+    ui.heading("Remote Signer (bunker)");
+    ui.add_space(10.0);
+
+    match gossip_lib::bunker::state() {
+        BunkerState::None => {
+            if GLOBALS.identity.has_private_key() {
+                ui.label("To use a remote signer, first delete your private key from gossip.");
+                return;
+            }
+            ui.label("Paste the bunker:// string from your signer:");
+            ui.add(text_edit_line!(app, app.bunker_uri));
+            if let Ok(uri) = BunkerUri::try_from_str(&app.bunker_uri) {
+                if ui.button("Connect").clicked() {
+                    let _ = GLOBALS
+                        .to_overlord
+                        .send(ToOverlordMessage::ConnectBunker(uri));
+                    app.bunker_uri = "".to_owned();
+                }
             }
+            return;
+        }
+        BunkerState::Connecting => {
+            ui.label("Connecting to the remote signer...");
+        }
+        BunkerState::Connected(pubkey) => {
+            ui.label(format!("Signing as {}", pubkey.as_bech32_string()));
+        }
+        BunkerState::Failed(reason) => {
+            ui.label(format!("The remote signer failed: {}", reason));
+        }
+    }
+
+    if let Some(conn) = gossip_lib::bunker::connection() {
+        ui.label(format!(
+            "Signer {} at:",
+            conn.signer_pubkey.as_bech32_string()
+        ));
+        for relay in &conn.relays {
+            ui.label(format!("        {}", relay));
+        }
+    }
+
+    let waiting = gossip_lib::bunker::waiting_requests();
+    if !waiting.is_empty() {
+        ui.add_space(10.0);
+        ui.label("Waiting for the signer:");
+        let now = Unixtime::now().unwrap();
+        for request in &waiting {
+            ui.horizontal_wrapped(|ui| {
+                ui.label(format!(
+                    "        {} ({}s)",
+                    request.method,
+                    now.0 - request.started.0
+                ));
+                if let Some(url) = &request.auth_url {
+                    ui.hyperlink_to("Approve at the signer", url);
+                    if ui.add(CopyButton::new()).clicked() {
+                        ui.output_mut(|o| o.copied_text = url.clone());
+                    }
+                }
+            });
         }
+        ui.ctx().request_repaint_after(Duration::from_secs(1));
+    }
+
+    ui.add_space(10.0);
+    if ui.button("Disconnect").clicked() {
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::DisconnectBunker);
     }
+}
 
+fn pair_with_signer(app: &mut GossipUi, ui: &mut Ui) {
     ui.separator();
+    ui.add_space(10.0);
+    ui.heading("Pair with a Remote Signer");
+
+    if let Some(uri) = app.nostr_connect_pairing.clone() {
+        let state = gossip_lib::nip46::pairing_state(&uri.client_pubkey);
+        match &state {
+            Some(PairingState::Waiting) => {
+                ui.label("Scan this with your signer, or paste the string into it:");
+                let uri_string = uri.as_uri_string();
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(&uri_string);
+                    if ui.add(CopyButton::new()).clicked() {
+                        ui.output_mut(|o| o.copied_text = uri_string.clone());
+                    }
+                });
+                let key = format!("nip46_pairing_qr_{}", uri.client_pubkey.as_hex_string());
+                app.render_qr(ui, &key, &uri_string);
+            }
+            Some(PairingState::Paired(signer)) => {
+                ui.label(format!("Paired with signer {}", signer.as_bech32_string()));
+                if gossip_lib::bunker::connection().is_none()
+                    && !GLOBALS.identity.has_private_key()
+                    && ui.button("Use as Remote Signer").clicked()
+                {
+                    let _ = GLOBALS
+                        .to_overlord
+                        .send(ToOverlordMessage::UsePairedSigner(uri.client_pubkey));
+                    app.nostr_connect_pairing = None;
+                    return;
+                }
+            }
+            Some(PairingState::Failed(reason)) => {
+                ui.label(format!("Pairing failed: {}", reason));
+            }
+            None => {
+                ui.label("This pairing attempt has expired.");
+            }
+        }
+        let label = if state == Some(PairingState::Waiting) {
+            "Cancel"
+        } else {
+            "Done"
+        };
+        if ui.button(label).clicked() {
+            gossip_lib::nip46::cancel_pairing(&uri.client_pubkey);
+            app.nostr_connect_pairing = None;
+        }
+        return;
+    }
+
+    ui.add_space(10.0);
+    ui.label("Enter a relay your signer can reach:");
+    ui.horizontal(|ui| {
+        ui.label("Relay: ");
+        ui.add(text_edit_line!(app, app.nostr_connect_pair_relay));
+    });
+
+    if let Ok(relay) = RelayUrl::try_from_str(&app.nostr_connect_pair_relay) {
+        if ui.button("Show Pairing Code").clicked() {
+            let perms = vec![
+                "sign_event".to_owned(),
+                "nip04_encrypt".to_owned(),
+                "nip04_decrypt".to_owned(),
+                "nip44_encrypt".to_owned(),
+                "nip44_decrypt".to_owned(),
+            ];
+            match gossip_lib::nip46::start_pairing("Gossip", vec![relay], perms) {
+                Ok(uri) => app.nostr_connect_pairing = Some(uri),
+                Err(e) => GLOBALS.status_queue.write().write(format!("{}", e)),
+            }
+        }
+    }
 }
 
 fn setup_unconnected_service(app: &mut GossipUi, ui: &mut Ui) {
@@ -0,0 +1,66 @@
+use super::GossipUi;
+use eframe::egui;
+use egui::{Context, Ui};
+use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::{NwcUri, GLOBALS};
+
+pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
+    ui.add_space(10.0);
+    ui.horizontal_wrapped(|ui| {
+        ui.heading("Wallet");
+    });
+
+    ui.add_space(10.0);
+    ui.label("Connect a lightning wallet with Nostr Wallet Connect (NIP-47) and gossip pays your zaps with it, instead of showing you an invoice to pay.");
+
+    ui.separator();
+    ui.add_space(10.0);
+
+    let conn = match gossip_lib::nwc::connection() {
+        Some(conn) => conn,
+        None => {
+            ui.label("Paste the nostr+walletconnect:// string from your wallet:");
+            ui.add(text_edit_line!(app, app.nwc_uri).password(true));
+            if let Ok(uri) = NwcUri::try_from_str(&app.nwc_uri) {
+                if ui.button("Connect").clicked() {
+                    let _ = GLOBALS
+                        .to_overlord
+                        .send(ToOverlordMessage::ConnectWallet(uri));
+                    app.nwc_uri = "".to_owned();
+                }
+            }
+            return;
+        }
+    };
+
+    ui.label(format!(
+        "Wallet {} at:",
+        conn.wallet_pubkey.as_bech32_string()
+    ));
+    for relay in &conn.relays {
+        ui.label(format!("        {}", relay));
+    }
+    if let Some(lud16) = &conn.lud16 {
+        ui.label(format!("Lightning address: {}", lud16));
+    }
+
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        match gossip_lib::nwc::balance() {
+            Some(msats) => ui.label(format!("Balance: {} sats", msats.0 / 1000)),
+            None => ui.label("Balance: unknown"),
+        };
+        if ui.button("Check").clicked() {
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::CheckWalletBalance);
+        }
+    });
+
+    ui.add_space(10.0);
+    if ui.button("Disconnect").clicked() {
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::DisconnectWallet);
+    }
+}
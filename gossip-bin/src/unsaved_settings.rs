@@ -21,7 +21,7 @@ macro_rules! default_setting {
 macro_rules! save_setting {
     ($field:ident, $slf:ident, $txn:ident) => {
         paste! {
-            GLOBALS.storage.[<write_setting_ $field>](&$slf.$field, Some(&mut $txn))?;
+            GLOBALS.storage.[<write_setting_ $field>](&$slf.$field, Some(&mut *$txn))?;
         }
     };
 }
@@ -49,13 +49,22 @@ pub struct UnsavedSettings {
 
     // Relay settings
     pub num_relays_per_person: u8,
+    pub dormant_after_days: u64,
     pub max_relays: u8,
+    pub relay_latency_probing: bool,
+    pub relay_latency_probe_interval_mins: u64,
+    pub relay_prefer_low_latency: bool,
 
     // Feed Settings
     pub feed_chunk: u64,
     pub replies_chunk: u64,
     pub person_feed_chunk: u64,
     pub overlap: u64,
+    pub feed_deduplicate: bool,
+    pub feed_show_bridged: bool,
+    pub rss_enabled: bool,
+    pub rss_poll_interval_mins: u64,
+    pub live_activities: bool,
 
     // Event Selection
     pub reposts: bool,
@@ -69,21 +78,58 @@ pub struct UnsavedSettings {
     pub reactions: bool,
     pub enable_zap_receipts: bool,
     pub show_media: bool,
+    pub remote_content_followed: String,
+    pub remote_content_second_degree: String,
+    pub remote_content_strangers: String,
     pub approve_content_warning: bool,
     pub show_deleted_events: bool,
     pub avoid_spam_on_unsafe_relays: bool,
+    pub dm_autoreply: bool,
+    pub dm_autoreply_message: String,
+    pub dm_autoreply_interval_hours: u64,
+    pub dm_autoreply_allowlist: String,
+    pub dm_autoreply_denylist: String,
+    pub alert_dm_from_follow: bool,
+    pub alert_dm: bool,
+    pub alert_mention: bool,
+    pub alert_reaction: bool,
+    pub alert_quiet_hours: bool,
+    pub alert_quiet_start_hour: u8,
+    pub alert_quiet_end_hour: u8,
+    pub alert_quiet_hours_allow_follow_dms: bool,
+    pub notify_only_my_threads: bool,
+    pub notify_min_trust: String,
 
     // Posting Settings
     pub pow: u8,
+    pub split_long_notes: bool,
+    pub split_long_notes_threshold: u64,
+    pub alt_text_reminder: bool,
+    pub alt_text_required: bool,
+    pub duplicate_post_window_hours: u64,
+    pub write_redundancy_target: u8,
+    pub fallback_write_relays: String,
+    pub write_relay_rotation: bool,
+    pub smart_lists: bool,
+    pub encrypt_with_nip44: bool,
+    pub profile_fetch_metadata: bool,
+    pub profile_fetch_relay_list: bool,
+    pub profile_fetch_notes: bool,
+    pub profile_fetch_notes_limit: u64,
+    pub profile_fetch_augments: bool,
     pub set_client_tag: bool,
     pub set_user_agent: bool,
     pub delegatee_tag: String,
+    pub privacy_mode: bool,
+    pub privacy_randomize_client_tag: bool,
+    pub privacy_time_jitter_secs: u64,
 
     // UI settings
     pub max_fps: u32,
     pub recompute_feed_periodically: bool,
     pub feed_recompute_interval_ms: u32,
     pub feed_thread_scroll_to_main_event: bool,
+    pub legacy_thread_heuristics: bool,
     pub theme_variant: String,
     pub dark_mode: bool,
     pub follow_os_dark_mode: bool,
@@ -100,6 +146,7 @@ pub struct UnsavedSettings {
     // Staletime settings
     pub relay_list_becomes_stale_minutes: u64,
     pub metadata_becomes_stale_minutes: u64,
+    pub profile_refresh_max_per_minute: u32,
     pub nip05_becomes_stale_if_valid_hours: u64,
     pub nip05_becomes_stale_if_invalid_minutes: u64,
     pub avatar_becomes_stale_hours: u64,
@@ -126,6 +173,11 @@ pub struct UnsavedSettings {
     // Database settings
     pub prune_period_days: u64,
     pub cache_prune_period_days: u64,
+    pub auto_prune: bool,
+    pub prune_max_events_mb: u64,
+    pub prune_kind_days: String,
+    pub relaxed_durability: bool,
+    pub history_sweep_relays: String,
 }
 
 impl Default for UnsavedSettings {
@@ -144,11 +196,20 @@ impl Default for UnsavedSettings {
             ),
             relay_auth_requires_approval: default_setting!(relay_auth_requires_approval),
             num_relays_per_person: default_setting!(num_relays_per_person),
+            dormant_after_days: default_setting!(dormant_after_days),
             max_relays: default_setting!(max_relays),
+            relay_latency_probing: default_setting!(relay_latency_probing),
+            relay_latency_probe_interval_mins: default_setting!(relay_latency_probe_interval_mins),
+            relay_prefer_low_latency: default_setting!(relay_prefer_low_latency),
             feed_chunk: default_setting!(feed_chunk),
             replies_chunk: default_setting!(replies_chunk),
             person_feed_chunk: default_setting!(person_feed_chunk),
             overlap: default_setting!(overlap),
+            feed_deduplicate: default_setting!(feed_deduplicate),
+            feed_show_bridged: default_setting!(feed_show_bridged),
+            rss_enabled: default_setting!(rss_enabled),
+            rss_poll_interval_mins: default_setting!(rss_poll_interval_mins),
+            live_activities: default_setting!(live_activities),
             reposts: default_setting!(reposts),
             show_long_form: default_setting!(show_long_form),
             show_mentions: default_setting!(show_mentions),
@@ -158,17 +219,56 @@ impl Default for UnsavedSettings {
             reactions: default_setting!(reactions),
             enable_zap_receipts: default_setting!(enable_zap_receipts),
             show_media: default_setting!(show_media),
+            remote_content_followed: default_setting!(remote_content_followed),
+            remote_content_second_degree: default_setting!(remote_content_second_degree),
+            remote_content_strangers: default_setting!(remote_content_strangers),
             approve_content_warning: default_setting!(approve_content_warning),
             show_deleted_events: default_setting!(show_deleted_events),
             avoid_spam_on_unsafe_relays: default_setting!(avoid_spam_on_unsafe_relays),
+            dm_autoreply: default_setting!(dm_autoreply),
+            dm_autoreply_message: default_setting!(dm_autoreply_message),
+            dm_autoreply_interval_hours: default_setting!(dm_autoreply_interval_hours),
+            dm_autoreply_allowlist: default_setting!(dm_autoreply_allowlist),
+            dm_autoreply_denylist: default_setting!(dm_autoreply_denylist),
+            alert_dm_from_follow: default_setting!(alert_dm_from_follow),
+            alert_dm: default_setting!(alert_dm),
+            alert_mention: default_setting!(alert_mention),
+            alert_reaction: default_setting!(alert_reaction),
+            alert_quiet_hours: default_setting!(alert_quiet_hours),
+            alert_quiet_start_hour: default_setting!(alert_quiet_start_hour),
+            alert_quiet_end_hour: default_setting!(alert_quiet_end_hour),
+            alert_quiet_hours_allow_follow_dms: default_setting!(
+                alert_quiet_hours_allow_follow_dms
+            ),
+            notify_only_my_threads: default_setting!(notify_only_my_threads),
+            notify_min_trust: default_setting!(notify_min_trust),
             pow: default_setting!(pow),
+            split_long_notes: default_setting!(split_long_notes),
+            split_long_notes_threshold: default_setting!(split_long_notes_threshold),
+            alt_text_reminder: default_setting!(alt_text_reminder),
+            alt_text_required: default_setting!(alt_text_required),
+            duplicate_post_window_hours: default_setting!(duplicate_post_window_hours),
+            write_redundancy_target: default_setting!(write_redundancy_target),
+            fallback_write_relays: default_setting!(fallback_write_relays),
+            write_relay_rotation: default_setting!(write_relay_rotation),
+            smart_lists: default_setting!(smart_lists),
+            encrypt_with_nip44: default_setting!(encrypt_with_nip44),
+            profile_fetch_metadata: default_setting!(profile_fetch_metadata),
+            profile_fetch_relay_list: default_setting!(profile_fetch_relay_list),
+            profile_fetch_notes: default_setting!(profile_fetch_notes),
+            profile_fetch_notes_limit: default_setting!(profile_fetch_notes_limit),
+            profile_fetch_augments: default_setting!(profile_fetch_augments),
             set_client_tag: default_setting!(set_client_tag),
             set_user_agent: default_setting!(set_user_agent),
             delegatee_tag: default_setting!(delegatee_tag),
+            privacy_mode: default_setting!(privacy_mode),
+            privacy_randomize_client_tag: default_setting!(privacy_randomize_client_tag),
+            privacy_time_jitter_secs: default_setting!(privacy_time_jitter_secs),
             max_fps: default_setting!(max_fps),
             recompute_feed_periodically: default_setting!(recompute_feed_periodically),
             feed_recompute_interval_ms: default_setting!(feed_recompute_interval_ms),
             feed_thread_scroll_to_main_event: default_setting!(feed_thread_scroll_to_main_event),
+            legacy_thread_heuristics: default_setting!(legacy_thread_heuristics),
             theme_variant: default_setting!(theme_variant),
             dark_mode: default_setting!(dark_mode),
             follow_os_dark_mode: default_setting!(follow_os_dark_mode),
@@ -183,6 +283,7 @@ impl Default for UnsavedSettings {
             wgpu_renderer: default_setting!(wgpu_renderer),
             relay_list_becomes_stale_minutes: default_setting!(relay_list_becomes_stale_minutes),
             metadata_becomes_stale_minutes: default_setting!(metadata_becomes_stale_minutes),
+            profile_refresh_max_per_minute: default_setting!(profile_refresh_max_per_minute),
             nip05_becomes_stale_if_valid_hours: default_setting!(
                 nip05_becomes_stale_if_valid_hours
             ),
@@ -213,6 +314,11 @@ impl Default for UnsavedSettings {
             nip11_lines_to_output_on_error: default_setting!(nip11_lines_to_output_on_error),
             prune_period_days: default_setting!(prune_period_days),
             cache_prune_period_days: default_setting!(prune_period_days),
+            auto_prune: default_setting!(auto_prune),
+            prune_max_events_mb: default_setting!(prune_max_events_mb),
+            prune_kind_days: default_setting!(prune_kind_days),
+            relaxed_durability: default_setting!(relaxed_durability),
+            history_sweep_relays: default_setting!(history_sweep_relays),
         }
     }
 }
@@ -231,11 +337,20 @@ impl UnsavedSettings {
             relay_connection_requires_approval: load_setting!(relay_connection_requires_approval),
             relay_auth_requires_approval: load_setting!(relay_auth_requires_approval),
             num_relays_per_person: load_setting!(num_relays_per_person),
+            dormant_after_days: load_setting!(dormant_after_days),
             max_relays: load_setting!(max_relays),
+            relay_latency_probing: load_setting!(relay_latency_probing),
+            relay_latency_probe_interval_mins: load_setting!(relay_latency_probe_interval_mins),
+            relay_prefer_low_latency: load_setting!(relay_prefer_low_latency),
             feed_chunk: load_setting!(feed_chunk),
             replies_chunk: load_setting!(replies_chunk),
             person_feed_chunk: load_setting!(person_feed_chunk),
             overlap: load_setting!(overlap),
+            feed_deduplicate: load_setting!(feed_deduplicate),
+            feed_show_bridged: load_setting!(feed_show_bridged),
+            rss_enabled: load_setting!(rss_enabled),
+            rss_poll_interval_mins: load_setting!(rss_poll_interval_mins),
+            live_activities: load_setting!(live_activities),
             reposts: load_setting!(reposts),
             show_long_form: load_setting!(show_long_form),
             show_mentions: load_setting!(show_mentions),
@@ -245,17 +360,54 @@ impl UnsavedSettings {
             reactions: load_setting!(reactions),
             enable_zap_receipts: load_setting!(enable_zap_receipts),
             show_media: load_setting!(show_media),
+            remote_content_followed: load_setting!(remote_content_followed),
+            remote_content_second_degree: load_setting!(remote_content_second_degree),
+            remote_content_strangers: load_setting!(remote_content_strangers),
             approve_content_warning: load_setting!(approve_content_warning),
             show_deleted_events: load_setting!(show_deleted_events),
             avoid_spam_on_unsafe_relays: load_setting!(avoid_spam_on_unsafe_relays),
+            dm_autoreply: load_setting!(dm_autoreply),
+            dm_autoreply_message: load_setting!(dm_autoreply_message),
+            dm_autoreply_interval_hours: load_setting!(dm_autoreply_interval_hours),
+            dm_autoreply_allowlist: load_setting!(dm_autoreply_allowlist),
+            dm_autoreply_denylist: load_setting!(dm_autoreply_denylist),
+            alert_dm_from_follow: load_setting!(alert_dm_from_follow),
+            alert_dm: load_setting!(alert_dm),
+            alert_mention: load_setting!(alert_mention),
+            alert_reaction: load_setting!(alert_reaction),
+            alert_quiet_hours: load_setting!(alert_quiet_hours),
+            alert_quiet_start_hour: load_setting!(alert_quiet_start_hour),
+            alert_quiet_end_hour: load_setting!(alert_quiet_end_hour),
+            alert_quiet_hours_allow_follow_dms: load_setting!(alert_quiet_hours_allow_follow_dms),
+            notify_only_my_threads: load_setting!(notify_only_my_threads),
+            notify_min_trust: load_setting!(notify_min_trust),
             pow: load_setting!(pow),
+            split_long_notes: load_setting!(split_long_notes),
+            split_long_notes_threshold: load_setting!(split_long_notes_threshold),
+            alt_text_reminder: load_setting!(alt_text_reminder),
+            alt_text_required: load_setting!(alt_text_required),
+            duplicate_post_window_hours: load_setting!(duplicate_post_window_hours),
+            write_redundancy_target: load_setting!(write_redundancy_target),
+            fallback_write_relays: load_setting!(fallback_write_relays),
+            write_relay_rotation: load_setting!(write_relay_rotation),
+            smart_lists: load_setting!(smart_lists),
+            encrypt_with_nip44: load_setting!(encrypt_with_nip44),
+            profile_fetch_metadata: load_setting!(profile_fetch_metadata),
+            profile_fetch_relay_list: load_setting!(profile_fetch_relay_list),
+            profile_fetch_notes: load_setting!(profile_fetch_notes),
+            profile_fetch_notes_limit: load_setting!(profile_fetch_notes_limit),
+            profile_fetch_augments: load_setting!(profile_fetch_augments),
             set_client_tag: load_setting!(set_client_tag),
             set_user_agent: load_setting!(set_user_agent),
             delegatee_tag: load_setting!(delegatee_tag),
+            privacy_mode: load_setting!(privacy_mode),
+            privacy_randomize_client_tag: load_setting!(privacy_randomize_client_tag),
+            privacy_time_jitter_secs: load_setting!(privacy_time_jitter_secs),
             max_fps: load_setting!(max_fps),
             recompute_feed_periodically: load_setting!(recompute_feed_periodically),
             feed_recompute_interval_ms: load_setting!(feed_recompute_interval_ms),
             feed_thread_scroll_to_main_event: load_setting!(feed_thread_scroll_to_main_event),
+            legacy_thread_heuristics: load_setting!(legacy_thread_heuristics),
             theme_variant: load_setting!(theme_variant),
             dark_mode: load_setting!(dark_mode),
             follow_os_dark_mode: load_setting!(follow_os_dark_mode),
@@ -270,6 +422,7 @@ impl UnsavedSettings {
             wgpu_renderer: load_setting!(wgpu_renderer),
             relay_list_becomes_stale_minutes: load_setting!(relay_list_becomes_stale_minutes),
             metadata_becomes_stale_minutes: load_setting!(metadata_becomes_stale_minutes),
+            profile_refresh_max_per_minute: load_setting!(profile_refresh_max_per_minute),
             nip05_becomes_stale_if_valid_hours: load_setting!(nip05_becomes_stale_if_valid_hours),
             nip05_becomes_stale_if_invalid_minutes: load_setting!(
                 nip05_becomes_stale_if_invalid_minutes
@@ -298,6 +451,11 @@ impl UnsavedSettings {
             nip11_lines_to_output_on_error: load_setting!(nip11_lines_to_output_on_error),
             prune_period_days: load_setting!(prune_period_days),
             cache_prune_period_days: load_setting!(cache_prune_period_days),
+            auto_prune: load_setting!(auto_prune),
+            prune_max_events_mb: load_setting!(prune_max_events_mb),
+            prune_kind_days: load_setting!(prune_kind_days),
+            relaxed_durability: load_setting!(relaxed_durability),
+            history_sweep_relays: load_setting!(history_sweep_relays),
         }
     }
 
@@ -314,11 +472,20 @@ impl UnsavedSettings {
         save_setting!(relay_connection_requires_approval, self, txn);
         save_setting!(relay_auth_requires_approval, self, txn);
         save_setting!(num_relays_per_person, self, txn);
+        save_setting!(dormant_after_days, self, txn);
         save_setting!(max_relays, self, txn);
+        save_setting!(relay_latency_probing, self, txn);
+        save_setting!(relay_latency_probe_interval_mins, self, txn);
+        save_setting!(relay_prefer_low_latency, self, txn);
         save_setting!(feed_chunk, self, txn);
         save_setting!(replies_chunk, self, txn);
         save_setting!(person_feed_chunk, self, txn);
         save_setting!(overlap, self, txn);
+        save_setting!(feed_deduplicate, self, txn);
+        save_setting!(feed_show_bridged, self, txn);
+        save_setting!(rss_enabled, self, txn);
+        save_setting!(rss_poll_interval_mins, self, txn);
+        save_setting!(live_activities, self, txn);
         save_setting!(reposts, self, txn);
         save_setting!(show_long_form, self, txn);
         save_setting!(show_mentions, self, txn);
@@ -328,17 +495,54 @@ impl UnsavedSettings {
         save_setting!(reactions, self, txn);
         save_setting!(enable_zap_receipts, self, txn);
         save_setting!(show_media, self, txn);
+        save_setting!(remote_content_followed, self, txn);
+        save_setting!(remote_content_second_degree, self, txn);
+        save_setting!(remote_content_strangers, self, txn);
         save_setting!(approve_content_warning, self, txn);
         save_setting!(show_deleted_events, self, txn);
         save_setting!(avoid_spam_on_unsafe_relays, self, txn);
+        save_setting!(dm_autoreply, self, txn);
+        save_setting!(dm_autoreply_message, self, txn);
+        save_setting!(dm_autoreply_interval_hours, self, txn);
+        save_setting!(dm_autoreply_allowlist, self, txn);
+        save_setting!(dm_autoreply_denylist, self, txn);
+        save_setting!(alert_dm_from_follow, self, txn);
+        save_setting!(alert_dm, self, txn);
+        save_setting!(alert_mention, self, txn);
+        save_setting!(alert_reaction, self, txn);
+        save_setting!(alert_quiet_hours, self, txn);
+        save_setting!(alert_quiet_start_hour, self, txn);
+        save_setting!(alert_quiet_end_hour, self, txn);
+        save_setting!(alert_quiet_hours_allow_follow_dms, self, txn);
+        save_setting!(notify_only_my_threads, self, txn);
+        save_setting!(notify_min_trust, self, txn);
         save_setting!(pow, self, txn);
+        save_setting!(split_long_notes, self, txn);
+        save_setting!(split_long_notes_threshold, self, txn);
+        save_setting!(alt_text_reminder, self, txn);
+        save_setting!(alt_text_required, self, txn);
+        save_setting!(duplicate_post_window_hours, self, txn);
+        save_setting!(write_redundancy_target, self, txn);
+        save_setting!(fallback_write_relays, self, txn);
+        save_setting!(write_relay_rotation, self, txn);
+        save_setting!(smart_lists, self, txn);
+        save_setting!(encrypt_with_nip44, self, txn);
+        save_setting!(profile_fetch_metadata, self, txn);
+        save_setting!(profile_fetch_relay_list, self, txn);
+        save_setting!(profile_fetch_notes, self, txn);
+        save_setting!(profile_fetch_notes_limit, self, txn);
+        save_setting!(profile_fetch_augments, self, txn);
         save_setting!(set_client_tag, self, txn);
         save_setting!(set_user_agent, self, txn);
         save_setting!(delegatee_tag, self, txn);
+        save_setting!(privacy_mode, self, txn);
+        save_setting!(privacy_randomize_client_tag, self, txn);
+        save_setting!(privacy_time_jitter_secs, self, txn);
         save_setting!(max_fps, self, txn);
         save_setting!(recompute_feed_periodically, self, txn);
         save_setting!(feed_recompute_interval_ms, self, txn);
         save_setting!(feed_thread_scroll_to_main_event, self, txn);
+        save_setting!(legacy_thread_heuristics, self, txn);
         save_setting!(theme_variant, self, txn);
         save_setting!(dark_mode, self, txn);
         save_setting!(follow_os_dark_mode, self, txn);
@@ -353,6 +557,7 @@ impl UnsavedSettings {
         save_setting!(wgpu_renderer, self, txn);
         save_setting!(relay_list_becomes_stale_minutes, self, txn);
         save_setting!(metadata_becomes_stale_minutes, self, txn);
+        save_setting!(profile_refresh_max_per_minute, self, txn);
         save_setting!(nip05_becomes_stale_if_valid_hours, self, txn);
         save_setting!(nip05_becomes_stale_if_invalid_minutes, self, txn);
         save_setting!(avatar_becomes_stale_hours, self, txn);
@@ -373,6 +578,11 @@ impl UnsavedSettings {
         save_setting!(nip11_lines_to_output_on_error, self, txn);
         save_setting!(prune_period_days, self, txn);
         save_setting!(cache_prune_period_days, self, txn);
+        save_setting!(auto_prune, self, txn);
+        save_setting!(prune_max_events_mb, self, txn);
+        save_setting!(prune_kind_days, self, txn);
+        save_setting!(relaxed_durability, self, txn);
+        save_setting!(history_sweep_relays, self, txn);
         txn.commit()?;
 
         let runstate = *GLOBALS.read_runstate.borrow();
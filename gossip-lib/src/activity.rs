@@ -0,0 +1,154 @@
+use crate::globals::GLOBALS;
+use dashmap::DashMap;
+use nostr_types::{Event, EventKind, Filter, PublicKey, Unixtime};
+use std::time::{Duration, Instant};
+
+// The window over which recent posts are counted
+const RECENT_DAYS: u64 = 30;
+
+// We stop counting recent posts here; it is only a measure of activity
+const MAX_RECENT_POSTS: usize = 50;
+
+// Activity is computed from storage again after this long, so that posts age out of
+// the recent window
+const ACTIVITY_TTL_SECS: u64 = 60 * 60;
+
+// Dormant people's general feed subscriptions look back this many times further,
+// as they are on fewer relays and their rare posts are easier to miss
+pub(crate) const DORMANT_LOOKBACK_FACTOR: u64 = 4;
+
+/// How much somebody posts, from the events we have of theirs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PostingActivity {
+    /// When their newest post was made
+    pub last_post: Option<Unixtime>,
+
+    /// How many posts they made in the last 30 days (counting to 50)
+    pub recent_posts: usize,
+}
+
+struct CachedActivity {
+    activity: PostingActivity,
+
+    // When we started following them, for is_dormant
+    followed_since: Option<i64>,
+
+    loaded: Instant,
+}
+
+lazy_static! {
+    // Computed from storage on first use, then kept up as their events come in
+    static ref ACTIVITY: DashMap<PublicKey, CachedActivity> = DashMap::new();
+}
+
+const POST_KINDS: [EventKind; 4] = [
+    EventKind::TextNote,
+    EventKind::Repost,
+    EventKind::GenericRepost,
+    EventKind::LongFormContent,
+];
+
+fn is_post(kind: EventKind) -> bool {
+    POST_KINDS.contains(&kind)
+}
+
+fn load(pubkey: PublicKey) -> CachedActivity {
+    let mut filter = Filter::new();
+    filter.add_author(&pubkey.into());
+    filter.kinds = POST_KINDS.to_vec();
+    filter.since = Some(Unixtime::now().unwrap() - Duration::from_secs(RECENT_DAYS * 86400));
+    filter.limit = Some(MAX_RECENT_POSTS);
+    let recent = GLOBALS
+        .storage
+        .find_events_by_filter(&filter, |_| true)
+        .unwrap_or_default();
+
+    let last_post = match recent.first() {
+        Some(event) => Some(event.created_at),
+        None => {
+            // Nothing recent. Look back as far as we have, reading only the newest
+            // of each kind.
+            POST_KINDS
+                .iter()
+                .filter_map(|kind| {
+                    GLOBALS
+                        .storage
+                        .latest_events_by_author(pubkey, *kind, 1)
+                        .ok()?
+                        .first()
+                        .map(|e| e.created_at)
+                })
+                .max()
+        }
+    };
+
+    let followed_since = GLOBALS
+        .storage
+        .read_person(&pubkey, None)
+        .ok()
+        .flatten()
+        .and_then(|p| p.followed_since);
+
+    CachedActivity {
+        activity: PostingActivity {
+            last_post,
+            recent_posts: recent.len(),
+        },
+        followed_since,
+        loaded: Instant::now(),
+    }
+}
+
+// Their activity and when we started following them
+fn cached(pubkey: PublicKey) -> (PostingActivity, Option<i64>) {
+    if let Some(cached) = ACTIVITY.get(&pubkey) {
+        if cached.loaded.elapsed() < Duration::from_secs(ACTIVITY_TTL_SECS) {
+            return (cached.activity, cached.followed_since);
+        }
+    }
+    let cached = load(pubkey);
+    let result = (cached.activity, cached.followed_since);
+    ACTIVITY.insert(pubkey, cached);
+    result
+}
+
+/// How much this person posts
+pub fn activity_of(pubkey: PublicKey) -> PostingActivity {
+    cached(pubkey).0
+}
+
+// Keep the activity of people whose posts come in up to date
+pub(crate) fn note_event(event: &Event) {
+    if !is_post(event.kind) {
+        return;
+    }
+    if let Some(mut cached) = ACTIVITY.get_mut(&event.pubkey) {
+        let activity = &mut cached.activity;
+        if activity
+            .last_post
+            .map(|t| t < event.created_at)
+            .unwrap_or(true)
+        {
+            activity.last_post = Some(event.created_at);
+        }
+        let window_start = Unixtime::now().unwrap() - Duration::from_secs(RECENT_DAYS * 86400);
+        if event.created_at >= window_start && activity.recent_posts < MAX_RECENT_POSTS {
+            activity.recent_posts += 1;
+        }
+    }
+}
+
+/// If this person has not posted in `dormant_after_days` (0 turns this off). Somebody
+/// we have never seen post is dormant once we have followed them for that long.
+pub fn is_dormant(pubkey: PublicKey) -> bool {
+    let days = GLOBALS.storage.read_setting_dormant_after_days();
+    if days == 0 {
+        return false;
+    }
+    let cutoff = Unixtime::now().unwrap().0 - (days * 86400) as i64;
+    let (activity, followed_since) = cached(pubkey);
+    match activity.last_post {
+        Some(last) => last.0 < cutoff,
+        None => followed_since.map(|since| since < cutoff).unwrap_or(false),
+    }
+}
@@ -0,0 +1,475 @@
+use crate::globals::GLOBALS;
+use crate::people::PersonList;
+use crate::remote_content::TrustTier;
+use chrono::Timelike;
+use dashmap::DashMap;
+use nostr_types::{Event, EventKind, EventReference, Id, PublicKey, Unixtime};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// Events older than this when they arrive are catching up, not news, so never alert
+const MAX_ALERT_AGE_SECS: i64 = 600;
+
+// How many notes up a reply chain we look for one of ours
+const MAX_THREAD_WALK: usize = 20;
+
+// How long the classification of an event is remembered. Deck columns classify
+// the events they show on every frame.
+const CLASSIFY_CACHE_SECS: u64 = 60;
+const MAX_CLASSIFIED: usize = 20_000;
+
+// How long the web of trust of an identity other than ours is remembered
+const WEB_CACHE_SECS: u64 = 600;
+
+lazy_static! {
+    static ref CLASSIFIED: DashMap<(Id, PublicKey), (Instant, Option<AlertTier>)> = DashMap::new();
+    static ref WEBS: DashMap<PublicKey, (Instant, Arc<Web>)> = DashMap::new();
+}
+
+// Who an identity follows, and who those people follow
+#[derive(Debug, Default)]
+struct Web {
+    followed: HashSet<PublicKey>,
+    second_degree: HashSet<PublicKey>,
+}
+
+impl Web {
+    // From the contact lists that `contact_list` finds
+    fn of<F>(identity: PublicKey, contact_list: F) -> Web
+    where
+        F: Fn(PublicKey) -> Option<Event>,
+    {
+        let people = |pubkey: PublicKey| -> Vec<PublicKey> {
+            contact_list(pubkey)
+                .map(|e| e.people().drain(..).map(|(pk, _, _)| pk).collect())
+                .unwrap_or_default()
+        };
+        let followed: HashSet<PublicKey> = people(identity).drain(..).collect();
+        let mut second_degree: HashSet<PublicKey> = HashSet::new();
+        for pubkey in followed.iter() {
+            second_degree.extend(people(*pubkey));
+        }
+        Web {
+            followed,
+            second_degree,
+        }
+    }
+
+    fn tier(&self, identity: PublicKey, pubkey: &PublicKey) -> TrustTier {
+        if *pubkey == identity || self.followed.contains(pubkey) {
+            TrustTier::Followed
+        } else if self.second_degree.contains(pubkey) {
+            TrustTier::SecondDegree
+        } else {
+            TrustTier::Stranger
+        }
+    }
+}
+
+/// How urgent a notification is, from most to least urgent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum AlertTier {
+    /// A direct message from someone we follow
+    DmFromFollow,
+
+    /// A direct message from anyone else our DM policy accepts
+    Dm,
+
+    /// A note that mentions or replies to us
+    Mention,
+
+    /// A reaction to one of our notes
+    Reaction,
+}
+
+impl AlertTier {
+    pub fn all() -> &'static [AlertTier] {
+        &[
+            AlertTier::DmFromFollow,
+            AlertTier::Dm,
+            AlertTier::Mention,
+            AlertTier::Reaction,
+        ]
+    }
+
+    /// If alerts of this tier are enabled
+    pub fn is_enabled(&self) -> bool {
+        match self {
+            AlertTier::DmFromFollow => GLOBALS.storage.read_setting_alert_dm_from_follow(),
+            AlertTier::Dm => GLOBALS.storage.read_setting_alert_dm(),
+            AlertTier::Mention => GLOBALS.storage.read_setting_alert_mention(),
+            AlertTier::Reaction => GLOBALS.storage.read_setting_alert_reaction(),
+        }
+    }
+}
+
+impl fmt::Display for AlertTier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlertTier::DmFromFollow => write!(f, "DM from someone you follow"),
+            AlertTier::Dm => write!(f, "DM"),
+            AlertTier::Mention => write!(f, "Mention"),
+            AlertTier::Reaction => write!(f, "Reaction"),
+        }
+    }
+}
+
+/// Classify an event by how urgently it should notify us, or None if it is not
+/// something we notify about. GiftWraps should be passed unwrapped (as the rumor).
+pub fn classify(event: &Event) -> Option<AlertTier> {
+    classify_for(event, GLOBALS.identity.public_key()?)
+}
+
+/// Classify an event as a notification to someone other than us, such as one of the
+/// [notified identities](crate::notifications::identities)
+pub fn classify_for(event: &Event, my_pubkey: PublicKey) -> Option<AlertTier> {
+    let key = (event.id, my_pubkey);
+    if let Some(entry) = CLASSIFIED.get(&key) {
+        let (when, tier) = *entry;
+        if when.elapsed() < Duration::from_secs(CLASSIFY_CACHE_SECS) {
+            return tier;
+        }
+    }
+
+    let tier = classify_uncached(event, my_pubkey);
+    if CLASSIFIED.len() >= MAX_CLASSIFIED {
+        CLASSIFIED.clear();
+    }
+    CLASSIFIED.insert(key, (Instant::now(), tier));
+    tier
+}
+
+fn classify_uncached(event: &Event, my_pubkey: PublicKey) -> Option<AlertTier> {
+    if event.pubkey == my_pubkey {
+        return None;
+    }
+
+    // Nothing from those we muted
+    if GLOBALS
+        .people
+        .is_person_in_list(&event.pubkey, PersonList::Muted)
+    {
+        return None;
+    }
+
+    match event.kind {
+        EventKind::EncryptedDirectMessage | EventKind::DmChat => {
+            if !crate::dm_policy::is_dm_sender_accepted(&event.pubkey) {
+                None
+            } else if GLOBALS
+                .people
+                .is_person_in_list(&event.pubkey, PersonList::Followed)
+            {
+                Some(AlertTier::DmFromFollow)
+            } else {
+                Some(AlertTier::Dm)
+            }
+        }
+        EventKind::Reaction => {
+            if event.is_tagged(&my_pubkey) && trusted_enough(&event.pubkey, my_pubkey) {
+                Some(AlertTier::Reaction)
+            } else {
+                None
+            }
+        }
+        kind if kind.is_feed_displayable() => {
+            if event.is_tagged(&my_pubkey)
+                && trusted_enough(&event.pubkey, my_pubkey)
+                && (!GLOBALS.storage.read_setting_notify_only_my_threads()
+                    || in_my_thread(event, my_pubkey))
+            {
+                Some(AlertTier::Mention)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+// Is this person near enough in the web of trust of `my_pubkey` (the
+// `notify_min_trust` setting) for their mentions and reactions to notify it?
+fn trusted_enough(pubkey: &PublicKey, my_pubkey: PublicKey) -> bool {
+    let min = TrustTier::from_setting(&GLOBALS.storage.read_setting_notify_min_trust());
+    if min == TrustTier::Stranger {
+        return true;
+    }
+    let tier = if GLOBALS.identity.public_key() == Some(my_pubkey) {
+        TrustTier::of(pubkey)
+    } else {
+        web_of(my_pubkey).tier(my_pubkey, pubkey)
+    };
+    tier <= min
+}
+
+// The web of trust of an identity other than ours, from the contact lists we have
+fn web_of(identity: PublicKey) -> Arc<Web> {
+    if let Some(entry) = WEBS.get(&identity) {
+        if entry.0.elapsed() < Duration::from_secs(WEB_CACHE_SECS) {
+            return entry.1.clone();
+        }
+    }
+    let web = Arc::new(Web::of(identity, |pubkey| {
+        GLOBALS
+            .storage
+            .get_replaceable_event(EventKind::ContactList, pubkey, "")
+            .ok()
+            .flatten()
+    }));
+    WEBS.insert(identity, (Instant::now(), web.clone()));
+    web
+}
+
+/// Is this note in a thread that `my_pubkey` started or took part in? That is, is
+/// the root ours, or did we reply to the root, or is one of the notes up its reply
+/// chain ours? Notes that are not replies are always in our threads, as they can
+/// only tag us to mention us.
+pub fn in_my_thread(event: &Event, my_pubkey: PublicKey) -> bool {
+    in_thread_of(
+        event,
+        my_pubkey,
+        crate::nip10::replies_to_root,
+        crate::nip10::replies_to,
+        |id| GLOBALS.storage.read_event(id).ok().flatten(),
+        |id| {
+            GLOBALS
+                .storage
+                .get_non_replaceable_replies(id)
+                .unwrap_or_default()
+        },
+    )
+}
+
+// in_my_thread, reading threads with `root_of` and `parent_of`, and events and
+// their replies from `read` and `replies`
+fn in_thread_of<T, P, R, C>(
+    event: &Event,
+    my_pubkey: PublicKey,
+    root_of: T,
+    parent_of: P,
+    read: R,
+    replies: C,
+) -> bool
+where
+    T: Fn(&Event) -> Option<EventReference>,
+    P: Fn(&Event) -> Option<EventReference>,
+    R: Fn(Id) -> Option<Event>,
+    C: Fn(Id) -> Vec<Id>,
+{
+    let root = match root_of(event).or_else(|| parent_of(event)) {
+        Some(root) => root,
+        None => return true,
+    };
+
+    let is_mine = |id| matches!(read(id), Some(e) if e.pubkey == my_pubkey);
+
+    match root {
+        EventReference::Addr(ea) => {
+            if ea.author == my_pubkey {
+                return true;
+            }
+        }
+        EventReference::Id { id, .. } => {
+            if is_mine(id) {
+                return true;
+            }
+            if replies(id).into_iter().any(is_mine) {
+                return true;
+            }
+        }
+    }
+
+    let mut parent = parent_of(event);
+    for _ in 0..MAX_THREAD_WALK {
+        match parent {
+            Some(EventReference::Id { id, .. }) => match read(id) {
+                Some(e) => {
+                    if e.pubkey == my_pubkey {
+                        return true;
+                    }
+                    parent = parent_of(&e);
+                }
+                None => break,
+            },
+            Some(EventReference::Addr(ea)) => return ea.author == my_pubkey,
+            None => break,
+        }
+    }
+
+    false
+}
+
+/// Are we in the configured quiet hours right now (local time)?
+pub fn in_quiet_hours() -> bool {
+    if !GLOBALS.storage.read_setting_alert_quiet_hours() {
+        return false;
+    }
+    let start = GLOBALS.storage.read_setting_alert_quiet_start_hour() as u32;
+    let end = GLOBALS.storage.read_setting_alert_quiet_end_hour() as u32;
+    let hour = chrono::Local::now().hour();
+    hour_in_window(hour, start, end)
+}
+
+// If hour is within [start, end), where the window may wrap past midnight
+fn hour_in_window(hour: u32, start: u32, end: u32) -> bool {
+    if start <= end {
+        start <= hour && hour < end
+    } else {
+        hour >= start || hour < end
+    }
+}
+
+/// Should this (newly arrived) event alert the user right now, and if so at which
+/// tier? This accounts for the tier's enable flag, quiet hours (during which only DMs
+/// from follows get through, if the user allows that) and the event's age.
+pub fn should_alert_now(event: &Event) -> Option<AlertTier> {
+    let age = Unixtime::now().unwrap().0 - event.created_at.0;
+    if age > MAX_ALERT_AGE_SECS {
+        return None;
+    }
+
+    let tier = classify(event)?;
+    if !tier.is_enabled() {
+        return None;
+    }
+    if in_quiet_hours()
+        && !(tier == AlertTier::DmFromFollow
+            && GLOBALS
+                .storage
+                .read_setting_alert_quiet_hours_allow_follow_dms())
+    {
+        return None;
+    }
+    Some(tier)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nostr_types::{PreEvent, PrivateKey, Tag};
+    use std::collections::HashMap;
+
+    fn note(key: &PrivateKey, tags: Vec<Tag>, kind: EventKind) -> Event {
+        let pre_event = PreEvent {
+            pubkey: key.public_key(),
+            created_at: Unixtime::now().unwrap(),
+            kind,
+            tags,
+            content: "".to_owned(),
+        };
+        let id = pre_event.hash().unwrap();
+        Event {
+            id,
+            pubkey: pre_event.pubkey,
+            created_at: pre_event.created_at,
+            kind: pre_event.kind,
+            sig: key.sign_id(id).unwrap(),
+            content: pre_event.content,
+            tags: pre_event.tags,
+        }
+    }
+
+    fn reply(key: &PrivateKey, root: &Event, parent: &Event) -> Event {
+        note(
+            key,
+            vec![
+                Tag::new_event(root.id, None, Some("root".to_string())),
+                Tag::new_event(parent.id, None, Some("reply".to_string())),
+            ],
+            EventKind::TextNote,
+        )
+    }
+
+    #[test]
+    fn test_in_thread_of() {
+        let me = PrivateKey::generate();
+        let alice = PrivateKey::generate();
+        let bob = PrivateKey::generate();
+
+        let alices_root = note(&alice, vec![], EventKind::TextNote);
+        let my_reply = reply(&me, &alices_root, &alices_root);
+        let bobs_reply = reply(&bob, &alices_root, &my_reply);
+        let alices_reply = reply(&alice, &alices_root, &bobs_reply);
+        let other_root = note(&bob, vec![], EventKind::TextNote);
+        let other_reply = reply(&alice, &other_root, &other_root);
+
+        let events: HashMap<Id, Event> = [
+            &alices_root,
+            &my_reply,
+            &bobs_reply,
+            &alices_reply,
+            &other_root,
+            &other_reply,
+        ]
+        .iter()
+        .map(|e| (e.id, (*e).clone()))
+        .collect();
+        let in_thread = |event: &Event, replies_to_root: &[Id]| {
+            in_thread_of(
+                event,
+                me.public_key(),
+                |e| e.replies_to_root(),
+                |e| e.replies_to(),
+                |id| events.get(&id).cloned(),
+                |_| replies_to_root.to_vec(),
+            )
+        };
+
+        // Up the reply chain, or among the replies to the root
+        assert!(in_thread(&alices_reply, &[]));
+        assert!(in_thread(&other_reply, &[my_reply.id]));
+
+        // Not a reply at all, so only a mention
+        assert!(in_thread(&alices_root, &[]));
+
+        // Someone else's thread
+        assert!(!in_thread(&other_reply, &[]));
+    }
+
+    #[test]
+    fn test_web_tier() {
+        let me = PrivateKey::generate().public_key();
+        let friend = PrivateKey::generate();
+        let friend_of_friend = PrivateKey::generate().public_key();
+        let stranger = PrivateKey::generate().public_key();
+
+        let my_contacts = note(
+            &PrivateKey::generate(),
+            vec![Tag::new_pubkey(friend.public_key(), None, None)],
+            EventKind::ContactList,
+        );
+        let friends_contacts = note(
+            &friend,
+            vec![Tag::new_pubkey(friend_of_friend, None, None)],
+            EventKind::ContactList,
+        );
+        let friend_pubkey = friend.public_key();
+        let web = Web::of(me, |pubkey| {
+            if pubkey == me {
+                Some(my_contacts.clone())
+            } else if pubkey == friend_pubkey {
+                Some(friends_contacts.clone())
+            } else {
+                None
+            }
+        });
+
+        assert_eq!(web.tier(me, &me), TrustTier::Followed);
+        assert_eq!(web.tier(me, &friend_pubkey), TrustTier::Followed);
+        assert_eq!(web.tier(me, &friend_of_friend), TrustTier::SecondDegree);
+        assert_eq!(web.tier(me, &stranger), TrustTier::Stranger);
+    }
+
+    #[test]
+    fn test_hour_in_window() {
+        assert!(hour_in_window(23, 22, 7));
+        assert!(hour_in_window(3, 22, 7));
+        assert!(!hour_in_window(7, 22, 7));
+        assert!(!hour_in_window(12, 22, 7));
+        assert!(hour_in_window(13, 12, 14));
+        assert!(!hour_in_window(14, 12, 14));
+    }
+}
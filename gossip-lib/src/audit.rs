@@ -0,0 +1,87 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use nostr_types::{Id, Unixtime};
+use tokio::task;
+
+// How many events we verify per chunk
+const AUDIT_CHUNK_SIZE: usize = 1000;
+
+/// Progress and results of an event audit
+#[derive(Debug, Clone, Default)]
+pub struct AuditReport {
+    pub started: Option<Unixtime>,
+
+    /// None while the audit is still running
+    pub finished: Option<Unixtime>,
+
+    /// Events checked so far
+    pub checked: usize,
+
+    /// Events that failed, and why. These have been quarantined.
+    pub failed: Vec<(Id, String)>,
+}
+
+/// Re-verify the id hash and signature of every stored event, in chunks, quarantining
+/// the ones that fail (their record may be corrupted on disk, or may have been stored
+/// by an older buggy version). Progress is published in `GLOBALS.event_audit`.
+pub async fn audit_events() -> Result<AuditReport, Error> {
+    let mut report = AuditReport {
+        started: Some(Unixtime::now().unwrap()),
+        ..Default::default()
+    };
+    *GLOBALS.event_audit.write() = Some(report.clone());
+
+    let mut after: Option<Id> = None;
+    loop {
+        if GLOBALS.read_runstate.borrow().going_offline() {
+            break;
+        }
+
+        let chunk = GLOBALS.storage.read_events_chunk(after, AUDIT_CHUNK_SIZE)?;
+        let last = match chunk.last() {
+            Some((id, _)) => *id,
+            None => break,
+        };
+        let count = chunk.len();
+
+        // Verifying is CPU work, keep it off the async threads
+        let failures: Vec<(Id, String)> = task::spawn_blocking(move || {
+            let mut failures: Vec<(Id, String)> = Vec::new();
+            for (key, result) in chunk.iter() {
+                match result {
+                    Err(e) => failures.push((*key, format!("Undecodable: {}", e))),
+                    Ok(event) => {
+                        if event.id != *key {
+                            failures.push((*key, "Stored under the wrong id".to_owned()));
+                        } else if let Err(e) = event.verify(None) {
+                            failures.push((*key, format!("{}", e)));
+                        }
+                    }
+                }
+            }
+            failures
+        })
+        .await?;
+
+        for (id, reason) in failures.iter() {
+            tracing::warn!("Event {} failed audit: {}", id.as_hex_string(), reason);
+            GLOBALS.storage.quarantine_event(*id, reason)?;
+        }
+
+        report.checked += count;
+        report.failed.extend(failures);
+        *GLOBALS.event_audit.write() = Some(report.clone());
+
+        after = Some(last);
+    }
+
+    report.finished = Some(Unixtime::now().unwrap());
+    *GLOBALS.event_audit.write() = Some(report.clone());
+
+    if !report.failed.is_empty() {
+        // Get them out of the current feed, which may still hold them
+        GLOBALS.feed.sync_recompute();
+    }
+
+    Ok(report)
+}
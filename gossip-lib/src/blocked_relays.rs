@@ -0,0 +1,193 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use nostr_types::{Event, EventKind, PublicKey, RelayUrl};
+use parking_lot::RwLock;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// How long the blocked relays and patterns read from storage are trusted. They are
+// checked for every relay we consider, and changes made here drop them right away.
+const BLOCKED_CACHE_SECS: u64 = 30;
+
+// The blocked relays, and the wildcard patterns trimmed and lowercased
+struct Blocked {
+    urls: HashSet<String>,
+    patterns: Vec<String>,
+}
+
+lazy_static! {
+    static ref BLOCKED: RwLock<Option<(Instant, Arc<Blocked>)>> = RwLock::new(None);
+}
+
+fn blocked() -> Arc<Blocked> {
+    if let Some((at, blocked)) = &*BLOCKED.read() {
+        if at.elapsed() < Duration::from_secs(BLOCKED_CACHE_SECS) {
+            return blocked.clone();
+        }
+    }
+
+    let blocked = Arc::new(Blocked {
+        urls: GLOBALS
+            .storage
+            .read_setting_blocked_relays()
+            .into_iter()
+            .collect(),
+        patterns: GLOBALS
+            .storage
+            .read_setting_blocked_relay_patterns()
+            .iter()
+            .map(|p| p.trim().to_lowercase())
+            .collect(),
+    });
+    *BLOCKED.write() = Some((Instant::now(), blocked.clone()));
+    blocked
+}
+
+fn forget_blocked() {
+    *BLOCKED.write() = None;
+}
+
+/// Is this relay blocked, either explicitly (NIP-51 kind 10006 list) or by one of the
+/// local wildcard patterns?
+///
+/// Blocked relays are never connected to, are not picked by the relay picker, and are
+/// not followed when they appear as hints.
+pub fn is_relay_blocked(url: &RelayUrl) -> bool {
+    let blocked = blocked();
+    if blocked.urls.contains(url.as_str()) {
+        return true;
+    }
+
+    if blocked.patterns.is_empty() {
+        return false;
+    }
+
+    let host = match url::Url::parse(url.as_str()) {
+        Ok(u) => u.host_str().map(|h| h.to_lowercase()).unwrap_or_default(),
+        Err(_) => String::new(),
+    };
+
+    blocked.patterns.iter().any(|pattern| {
+        if pattern.contains("://") {
+            glob_match(pattern, &url.as_str().to_lowercase())
+        } else {
+            glob_match(pattern, &host)
+        }
+    })
+}
+
+/// Block a relay. This goes in the (publishable) blocked relays list.
+pub fn block_relay(url: &RelayUrl) -> Result<(), Error> {
+    let mut blocked = GLOBALS.storage.read_setting_blocked_relays();
+    if !blocked.iter().any(|b| b == url.as_str()) {
+        blocked.push(url.as_str().to_owned());
+        GLOBALS
+            .storage
+            .write_setting_blocked_relays(&blocked, None)?;
+        forget_blocked();
+    }
+    Ok(())
+}
+
+/// Unblock a relay (this does not affect wildcard patterns)
+pub fn unblock_relay(url: &RelayUrl) -> Result<(), Error> {
+    let mut blocked = GLOBALS.storage.read_setting_blocked_relays();
+    blocked.retain(|b| b != url.as_str());
+    GLOBALS
+        .storage
+        .write_setting_blocked_relays(&blocked, None)?;
+    forget_blocked();
+    Ok(())
+}
+
+/// Add a local wildcard pattern, e.g. `*.badhost.com` (matched against the host) or
+/// `wss://relay.example.com/*` (matched against the whole URL). Patterns are not
+/// published.
+pub fn add_blocked_relay_pattern(pattern: &str) -> Result<(), Error> {
+    let pattern = pattern.trim().to_owned();
+    if pattern.is_empty() {
+        return Ok(());
+    }
+    let mut patterns = GLOBALS.storage.read_setting_blocked_relay_patterns();
+    if !patterns.contains(&pattern) {
+        patterns.push(pattern);
+        GLOBALS
+            .storage
+            .write_setting_blocked_relay_patterns(&patterns, None)?;
+        forget_blocked();
+    }
+    Ok(())
+}
+
+/// Remove a local wildcard pattern
+pub fn remove_blocked_relay_pattern(pattern: &str) -> Result<(), Error> {
+    let mut patterns = GLOBALS.storage.read_setting_blocked_relay_patterns();
+    patterns.retain(|p| p != pattern.trim());
+    GLOBALS
+        .storage
+        .write_setting_blocked_relay_patterns(&patterns, None)?;
+    forget_blocked();
+    Ok(())
+}
+
+/// Take the blocked relays list from our own kind 10006 event (e.g. one published from
+/// another client). Only the newest such event gets this far, so it replaces the local
+/// list. Local wildcard patterns are unaffected.
+pub(crate) fn process_blocked_relays_event(
+    event: &Event,
+    my_pubkey: PublicKey,
+) -> Result<(), Error> {
+    if event.kind != EventKind::BlockedRelaysList || event.pubkey != my_pubkey {
+        return Ok(());
+    }
+
+    let mut blocked: Vec<String> = Vec::new();
+    for tag in event.tags.iter() {
+        if tag.tagname() == "relay" {
+            if let Ok(url) = RelayUrl::try_from_str(tag.value()) {
+                if !blocked.iter().any(|b| b == url.as_str()) {
+                    blocked.push(url.as_str().to_owned());
+                }
+            }
+        }
+    }
+
+    if blocked != GLOBALS.storage.read_setting_blocked_relays() {
+        GLOBALS
+            .storage
+            .write_setting_blocked_relays(&blocked, None)?;
+        forget_blocked();
+    }
+    Ok(())
+}
+
+// Match text against a pattern where '*' matches any run of characters
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<usize> = None;
+    let mut mark = 0;
+
+    while ti < t.len() {
+        if pi < p.len() && p[pi] == t[ti] {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some(pi);
+            mark = ti;
+            pi += 1;
+        } else if let Some(s) = star {
+            pi = s + 1;
+            mark += 1;
+            ti = mark;
+        } else {
+            return false;
+        }
+    }
+    while pi < p.len() && p[pi] == '*' {
+        pi += 1;
+    }
+    pi == p.len()
+}
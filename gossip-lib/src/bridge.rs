@@ -0,0 +1,95 @@
+use crate::globals::GLOBALS;
+use nostr_types::Event;
+use std::fmt;
+
+// Author NIP-05 domains of well known bridges that do not tag their events
+const BRIDGE_DOMAINS: [(&str, BridgeProtocol); 4] = [
+    ("mostr.pub", BridgeProtocol::ActivityPub),
+    ("momostr.pink", BridgeProtocol::ActivityPub),
+    ("rsslay.nostr.moe", BridgeProtocol::Rss),
+    ("rsslay.fiatjaf.com", BridgeProtocol::Rss),
+];
+
+/// The protocol a bridged event originally came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BridgeProtocol {
+    ActivityPub,
+    Atproto,
+    Rss,
+    Web,
+    Other(String),
+}
+
+impl BridgeProtocol {
+    fn from_proxy_tag(protocol: &str) -> BridgeProtocol {
+        match protocol.to_lowercase().as_str() {
+            "activitypub" => BridgeProtocol::ActivityPub,
+            "atproto" => BridgeProtocol::Atproto,
+            "rss" => BridgeProtocol::Rss,
+            "web" => BridgeProtocol::Web,
+            other => BridgeProtocol::Other(other.to_owned()),
+        }
+    }
+}
+
+impl fmt::Display for BridgeProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BridgeProtocol::ActivityPub => write!(f, "ActivityPub"),
+            BridgeProtocol::Atproto => write!(f, "AT Protocol"),
+            BridgeProtocol::Rss => write!(f, "RSS"),
+            BridgeProtocol::Web => write!(f, "Web"),
+            BridgeProtocol::Other(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// Where a bridged event originally came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BridgeSource {
+    pub protocol: BridgeProtocol,
+
+    /// The original URL or id on the source protocol, if the bridge told us
+    pub url: Option<String>,
+}
+
+/// Detect if an event was bridged from another protocol, and if so where from.
+///
+/// This honors NIP-48 `proxy` tags and the older `mostr` tag, and otherwise recognizes
+/// authors whose NIP-05 is on a well known bridge domain.
+pub fn bridge_source(event: &Event) -> Option<BridgeSource> {
+    for tag in event.tags.iter() {
+        if tag.tagname() == "proxy" && !tag.value().is_empty() {
+            return Some(BridgeSource {
+                protocol: BridgeProtocol::from_proxy_tag(tag.get_index(2)),
+                url: Some(tag.value().to_owned()),
+            });
+        }
+        if tag.tagname() == "mostr" && !tag.value().is_empty() {
+            return Some(BridgeSource {
+                protocol: BridgeProtocol::ActivityPub,
+                url: Some(tag.value().to_owned()),
+            });
+        }
+    }
+
+    let person = GLOBALS.storage.read_person(&event.pubkey, None).ok()??;
+    let nip05 = person.metadata.as_ref()?.nip05.as_ref()?.to_lowercase();
+    let domain = nip05.rsplit('@').next()?;
+    for (bridge_domain, protocol) in BRIDGE_DOMAINS.iter() {
+        if domain == *bridge_domain || domain.ends_with(&format!(".{}", bridge_domain)) {
+            return Some(BridgeSource {
+                protocol: protocol.clone(),
+                url: None,
+            });
+        }
+    }
+
+    None
+}
+
+/// If an event was bridged from another protocol
+#[inline]
+pub fn is_bridged(event: &Event) -> bool {
+    bridge_source(event).is_some()
+}
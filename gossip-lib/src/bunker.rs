@@ -0,0 +1,616 @@
+use crate::comms::{ToMinionMessage, ToMinionPayload, ToMinionPayloadDetail, ToOverlordMessage};
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use nostr_types::{
+    ContentEncryptionAlgorithm, Event, EventKind, PreEvent, PrivateKey, PublicKey, RelayUrl, Rumor,
+    Tag, Unixtime,
+};
+use parking_lot::{Mutex, RwLock};
+use serde::Deserialize;
+use speedy::{Readable, Writable};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+/// How long a request waits for the remote signer, including for us to approve it
+/// there
+pub const REQUEST_TIMEOUT_SECS: u64 = 90;
+
+/// A bunker:// URI: where to reach a remote signer (NIP-46) that holds our key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BunkerUri {
+    /// The key the signer talks to us with (not necessarily our own)
+    pub signer_pubkey: PublicKey,
+
+    pub relays: Vec<RelayUrl>,
+
+    /// Passed with `connect`, if the signer wants it
+    pub secret: Option<String>,
+}
+
+impl BunkerUri {
+    pub fn as_uri_string(&self) -> String {
+        let mut query = url::form_urlencoded::Serializer::new(String::new());
+        for relay in self.relays.iter() {
+            query.append_pair("relay", relay.as_str());
+        }
+        if let Some(secret) = &self.secret {
+            query.append_pair("secret", secret);
+        }
+        format!(
+            "bunker://{}?{}",
+            self.signer_pubkey.as_hex_string(),
+            query.finish()
+        )
+    }
+
+    pub fn try_from_str(s: &str) -> Result<BunkerUri, Error> {
+        let url = match url::Url::parse(s.trim()) {
+            Ok(url) if url.scheme() == "bunker" => url,
+            _ => return Err(ErrorKind::BadBunkerString.into()),
+        };
+        let signer_pubkey = match url.host_str() {
+            Some(host) => PublicKey::try_from_hex_string(host, true)?,
+            None => return Err(ErrorKind::BadBunkerString.into()),
+        };
+
+        let mut relays: Vec<RelayUrl> = Vec::new();
+        let mut secret: Option<String> = None;
+        for (key, value) in url.query_pairs() {
+            match &*key {
+                "relay" => relays.push(RelayUrl::try_from_str(&value)?),
+                "secret" if !value.is_empty() => secret = Some(value.into_owned()),
+                _ => (),
+            }
+        }
+
+        if relays.is_empty() {
+            return Err(ErrorKind::BadBunkerString.into());
+        }
+        Ok(BunkerUri {
+            signer_pubkey,
+            relays,
+            secret,
+        })
+    }
+}
+
+/// The remote signer we use instead of a private key, as saved
+#[derive(Debug, Clone, Readable, Writable)]
+pub struct BunkerConnection {
+    pub signer_pubkey: PublicKey,
+    pub relays: Vec<RelayUrl>,
+
+    /// Our public key, once the signer has told us
+    pub user_pubkey: Option<PublicKey>,
+
+    // Our key for talking to the signer, in hex. It is not our identity and the
+    // signer can revoke it.
+    client_key: String,
+}
+
+/// Where our remote signer is at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BunkerState {
+    /// We don't use a remote signer
+    None,
+
+    /// Waiting for the signer to accept us and tell us our public key
+    Connecting,
+
+    /// The signer signs as this key for us
+    Connected(PublicKey),
+
+    Failed(String),
+}
+
+/// A request waiting for the remote signer
+#[derive(Debug, Clone)]
+pub struct BunkerRequest {
+    pub id: String,
+    pub method: String,
+    pub started: Unixtime,
+
+    /// Where the signer asks us to approve this request, if it does
+    pub auth_url: Option<String>,
+}
+
+struct Waiter {
+    request: BunkerRequest,
+    reply: oneshot::Sender<Result<String, String>>,
+}
+
+lazy_static! {
+    static ref CONNECTION: RwLock<Option<(BunkerConnection, PrivateKey)>> = RwLock::new(None);
+    static ref STATE: RwLock<BunkerState> = RwLock::new(BunkerState::None);
+    static ref WAITING: Mutex<HashMap<String, Waiter>> = Mutex::new(HashMap::new());
+}
+
+/// Where our remote signer is at
+pub fn state() -> BunkerState {
+    STATE.read().clone()
+}
+
+/// If a remote signer is signing for us
+pub fn is_connected() -> bool {
+    matches!(*STATE.read(), BunkerState::Connected(_))
+}
+
+/// The requests waiting for the remote signer, oldest first
+pub fn waiting_requests() -> Vec<BunkerRequest> {
+    let mut requests: Vec<BunkerRequest> =
+        WAITING.lock().values().map(|w| w.request.clone()).collect();
+    requests.sort_by_key(|r| r.started);
+    requests
+}
+
+/// The saved remote signer, if we use one
+pub fn connection() -> Option<BunkerConnection> {
+    CONNECTION.read().as_ref().map(|(c, _)| c.clone())
+}
+
+/// The signer's key and ours for talking to it
+pub(crate) fn keys() -> Option<(PublicKey, PublicKey)> {
+    CONNECTION
+        .read()
+        .as_ref()
+        .map(|(c, key)| (c.signer_pubkey, key.public_key()))
+}
+
+/// If the remote signer talks to us through this relay
+pub(crate) fn is_signer_relay(url: &RelayUrl) -> bool {
+    CONNECTION
+        .read()
+        .as_ref()
+        .map(|(c, _)| c.relays.contains(url))
+        .unwrap_or(false)
+}
+
+// GossipIdentity::load calls this
+pub(crate) fn load() -> Result<(), Error> {
+    fail_waiting("The remote signer was unloaded");
+    match GLOBALS.storage.read_bunker_connection()? {
+        Some(conn) => {
+            let key = PrivateKey::try_from_hex_string(&conn.client_key)?;
+            *CONNECTION.write() = Some((conn, key));
+            *STATE.write() = BunkerState::Connecting;
+        }
+        None => {
+            *CONNECTION.write() = None;
+            *STATE.write() = BunkerState::None;
+        }
+    }
+    Ok(())
+}
+
+/// Start using the remote signer of this URI, with a new key to talk to it. The
+/// overlord then connects to it (see [handshake]).
+pub(crate) fn start(uri: &BunkerUri) -> Result<(), Error> {
+    start_with(
+        uri.signer_pubkey,
+        uri.relays.clone(),
+        PrivateKey::generate(),
+    )
+}
+
+/// Start using a remote signer we paired with (it already knows our key for it)
+pub(crate) fn start_with(
+    signer_pubkey: PublicKey,
+    relays: Vec<RelayUrl>,
+    mut client_key: PrivateKey,
+) -> Result<(), Error> {
+    if GLOBALS.identity.has_private_key() {
+        return Err(ErrorKind::General(
+            "Delete the private key from gossip before using a remote signer".to_owned(),
+        )
+        .into());
+    }
+
+    let conn = BunkerConnection {
+        signer_pubkey,
+        relays,
+        user_pubkey: None,
+        client_key: client_key.as_hex_string(),
+    };
+    GLOBALS.storage.write_bunker_connection(&conn, None)?;
+    fail_waiting("The remote signer was replaced");
+    *CONNECTION.write() = Some((conn, client_key));
+    *STATE.write() = BunkerState::Connecting;
+    Ok(())
+}
+
+/// Stop using the remote signer. Our public key stays.
+pub(crate) fn stop() -> Result<(), Error> {
+    GLOBALS.storage.delete_bunker_connection(None)?;
+    fail_waiting("The remote signer was disconnected");
+    *CONNECTION.write() = None;
+    *STATE.write() = BunkerState::None;
+    Ok(())
+}
+
+/// Connect to the remote signer (with `secret` if the bunker URI had one) and learn
+/// our public key from it. The overlord spawns this once it has asked for the
+/// signer's relays, so that it does not wait on it.
+pub(crate) async fn handshake(connect: bool, secret: Option<String>) {
+    let result = async move {
+        wait_for_relays().await?;
+        if connect {
+            let (signer_pubkey, _) = keys().ok_or(ErrorKind::RemoteSignerNotSetup)?;
+            let answer = request(
+                "connect",
+                vec![signer_pubkey.as_hex_string(), secret.unwrap_or_default()],
+            )
+            .await?;
+            if answer != "ack" {
+                tracing::debug!("Remote signer answered connect with {}", answer);
+            }
+        }
+        let pubkey =
+            PublicKey::try_from_hex_string(&request("get_public_key", vec![]).await?, true)?;
+
+        let conn = match CONNECTION.write().as_mut() {
+            Some((conn, _)) if conn.user_pubkey != Some(pubkey) => {
+                conn.user_pubkey = Some(pubkey);
+                Some(conn.clone())
+            }
+            _ => None,
+        };
+        if let Some(conn) = conn {
+            GLOBALS.storage.write_bunker_connection(&conn, None)?;
+        }
+        Ok::<PublicKey, Error>(pubkey)
+    }
+    .await;
+
+    match result {
+        Ok(pubkey) => {
+            *STATE.write() = BunkerState::Connected(pubkey);
+            if let Err(e) = GLOBALS.identity.remote_signer_connected(pubkey) {
+                tracing::error!("{}", e);
+            }
+            tracing::info!("Remote signer connected for {}", pubkey.as_bech32_string());
+        }
+        Err(e) => {
+            tracing::warn!("Remote signer: {}", e);
+            *STATE.write() = BunkerState::Failed(format!("{}", e));
+            GLOBALS
+                .status_queue
+                .write()
+                .write(format!("Remote signer: {}", e));
+        }
+    }
+}
+
+// Wait (for a while) until we are connected to one of the signer's relays
+async fn wait_for_relays() -> Result<(), Error> {
+    for _ in 0..30 {
+        if connected_relays().is_some() {
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+    Err(ErrorKind::RemoteSigner("Could not reach the remote signer's relays".to_owned()).into())
+}
+
+// The signer's relays we are connected to, if any
+fn connected_relays() -> Option<Vec<RelayUrl>> {
+    let binding = CONNECTION.read();
+    let (conn, _) = binding.as_ref()?;
+    let connected: Vec<RelayUrl> = conn
+        .relays
+        .iter()
+        .filter(|url| GLOBALS.connected_relays.contains_key(url))
+        .cloned()
+        .collect();
+    if connected.is_empty() {
+        None
+    } else {
+        Some(connected)
+    }
+}
+
+/// Send a NIP-46 request to the remote signer and wait for the answer, for up to
+/// [REQUEST_TIMEOUT_SECS]. The answer is read by a minion and handed over by
+/// [handle_response], so this never waits on the overlord: if we are not connected
+/// to any of the signer's relays it fails at once (and asks the overlord to
+/// connect).
+pub(crate) async fn request(method: &str, params: Vec<String>) -> Result<String, Error> {
+    let id = format!("{:016x}", rand::random::<u64>());
+
+    let event = {
+        let binding = CONNECTION.read();
+        let (conn, key) = binding.as_ref().ok_or(ErrorKind::RemoteSignerNotSetup)?;
+        let json = serde_json::json!({
+            "id": id,
+            "method": method,
+            "params": params,
+        });
+        let content = key.encrypt(
+            &conn.signer_pubkey,
+            &json.to_string(),
+            ContentEncryptionAlgorithm::Nip44v2,
+        )?;
+        let pre_event = PreEvent {
+            pubkey: key.public_key(),
+            created_at: Unixtime::now().unwrap(),
+            kind: EventKind::NostrConnect,
+            tags: vec![Tag::new_pubkey(conn.signer_pubkey, None, None)],
+            content,
+        };
+        sign_with_key(key, pre_event)?
+    };
+
+    let relays = match connected_relays() {
+        Some(relays) => relays,
+        None => {
+            let _ = GLOBALS.to_overlord.send(ToOverlordMessage::SubscribeBunker);
+            return Err(ErrorKind::RemoteSigner(
+                "Not connected to the remote signer yet, try again shortly".to_owned(),
+            )
+            .into());
+        }
+    };
+
+    let (tx, rx) = oneshot::channel();
+    WAITING.lock().insert(
+        id.clone(),
+        Waiter {
+            request: BunkerRequest {
+                id: id.clone(),
+                method: method.to_owned(),
+                started: Unixtime::now().unwrap(),
+                auth_url: None,
+            },
+            reply: tx,
+        },
+    );
+
+    // Straight to the minions, as the overlord may be the one waiting
+    for url in relays {
+        let _ = GLOBALS.to_minions.send(ToMinionMessage {
+            target: url.as_str().to_owned(),
+            payload: ToMinionPayload {
+                job_id: rand::random::<u64>(),
+                detail: ToMinionPayloadDetail::PostEvents(vec![event.clone()]),
+            },
+        });
+    }
+
+    let answer = tokio::time::timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS), rx).await;
+    WAITING.lock().remove(&id);
+
+    match answer {
+        Ok(Ok(Ok(result))) => Ok(result),
+        Ok(Ok(Err(error))) => Err(ErrorKind::RemoteSigner(error).into()),
+        Ok(Err(_)) => Err(ErrorKind::RemoteSigner("The request was dropped".to_owned()).into()),
+        Err(_) => Err(ErrorKind::RemoteSignerTimeout(method.to_owned()).into()),
+    }
+}
+
+fn fail_waiting(reason: &str) {
+    for (_, waiter) in WAITING.lock().drain() {
+        let _ = waiter.reply.send(Err(reason.to_owned()));
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct BunkerResponse {
+    id: String,
+
+    #[serde(default)]
+    result: String,
+
+    #[serde(default)]
+    error: String,
+}
+
+// If this event is an answer from our remote signer, hand it to the request waiting
+// for it and return true
+pub(crate) fn handle_response(event: &Event) -> Result<bool, Error> {
+    let plaintext = {
+        let binding = CONNECTION.read();
+        let (conn, key) = match binding.as_ref() {
+            Some(found) => found,
+            None => return Ok(false),
+        };
+        let client_pubkey = key.public_key();
+        if event.pubkey != conn.signer_pubkey
+            || !event.people().iter().any(|(pk, _, _)| *pk == client_pubkey)
+        {
+            return Ok(false);
+        }
+        key.decrypt(&event.pubkey, &event.content)?
+    };
+    let response: BunkerResponse = serde_json::from_str(&plaintext)?;
+
+    let mut waiting = WAITING.lock();
+    if response.result == "auth_url" {
+        // The signer wants us to approve this request at a URL; the answer comes later
+        if let Some(waiter) = waiting.get_mut(&response.id) {
+            tracing::info!(
+                "Remote signer asks to approve {} at {}",
+                waiter.request.method,
+                response.error
+            );
+            GLOBALS.status_queue.write().write(format!(
+                "Approve the {} request at your remote signer: {}",
+                waiter.request.method, response.error
+            ));
+            waiter.request.auth_url = Some(response.error);
+        }
+    } else if let Some(waiter) = waiting.remove(&response.id) {
+        let answer = if response.error.is_empty() {
+            Ok(response.result)
+        } else {
+            Err(response.error)
+        };
+        let _ = waiter.reply.send(answer);
+    }
+
+    Ok(true)
+}
+
+// Sign an event with a key of our own (not our identity)
+fn sign_with_key(key: &PrivateKey, input: PreEvent) -> Result<Event, Error> {
+    let id = input.hash()?;
+    let sig = key.sign_id(id)?;
+    Ok(Event {
+        id,
+        pubkey: input.pubkey,
+        created_at: input.created_at,
+        kind: input.kind,
+        sig,
+        content: input.content,
+        tags: input.tags,
+    })
+}
+
+/// Have the remote signer sign this event. What comes back must be exactly this
+/// event, validly signed.
+pub(crate) async fn sign_event(input: PreEvent) -> Result<Event, Error> {
+    let unsigned = serde_json::json!({
+        "pubkey": input.pubkey,
+        "created_at": input.created_at,
+        "kind": input.kind,
+        "tags": input.tags,
+        "content": input.content,
+    });
+    let answer = request("sign_event", vec![unsigned.to_string()]).await?;
+    let event: Event = serde_json::from_str(&answer)?;
+    if event.id != input.hash()? || event.pubkey != input.pubkey {
+        return Err(ErrorKind::RemoteSigner(
+            "The signed event does not match the request".to_owned(),
+        )
+        .into());
+    }
+    if event.verify(None).is_err() {
+        return Err(ErrorKind::RemoteSigner(
+            "The remote signer returned a bad signature".to_owned(),
+        )
+        .into());
+    }
+    Ok(event)
+}
+
+/// Have the remote signer encrypt to this person
+pub(crate) async fn encrypt(
+    other: &PublicKey,
+    plaintext: &str,
+    algo: ContentEncryptionAlgorithm,
+) -> Result<String, Error> {
+    let method = match algo {
+        ContentEncryptionAlgorithm::Nip04 => "nip04_encrypt",
+        _ => "nip44_encrypt",
+    };
+    request(method, vec![other.as_hex_string(), plaintext.to_owned()]).await
+}
+
+/// Have the remote signer decrypt from this person
+pub(crate) async fn decrypt(other: &PublicKey, ciphertext: &str) -> Result<String, Error> {
+    // NIP-04 ciphertexts carry an IV suffix, anything else is NIP-44
+    let method = if ciphertext.contains("?iv=") {
+        "nip04_decrypt"
+    } else {
+        "nip44_decrypt"
+    };
+    request(method, vec![other.as_hex_string(), ciphertext.to_owned()]).await
+}
+
+/// Seal a rumor with the remote signer and giftwrap it (NIP-59) to this person
+pub(crate) async fn giftwrap(input: PreEvent, pubkey: PublicKey) -> Result<Event, Error> {
+    let rumor = serde_json::json!({
+        "id": input.hash()?,
+        "pubkey": input.pubkey,
+        "created_at": input.created_at,
+        "kind": input.kind,
+        "tags": input.tags,
+        "content": input.content,
+    });
+    let content = encrypt(
+        &pubkey,
+        &rumor.to_string(),
+        ContentEncryptionAlgorithm::Nip44v2,
+    )
+    .await?;
+    let seal = sign_event(PreEvent {
+        pubkey: input.pubkey,
+        created_at: tweaked_now(),
+        kind: EventKind::Seal,
+        tags: vec![],
+        content,
+    })
+    .await?;
+
+    let wrap_key = PrivateKey::generate();
+    let content = wrap_key.encrypt(
+        &pubkey,
+        &serde_json::to_string(&seal)?,
+        ContentEncryptionAlgorithm::Nip44v2,
+    )?;
+    sign_with_key(
+        &wrap_key,
+        PreEvent {
+            pubkey: wrap_key.public_key(),
+            created_at: tweaked_now(),
+            kind: EventKind::GiftWrap,
+            tags: vec![Tag::new_pubkey(pubkey, None, None)],
+            content,
+        },
+    )
+}
+
+// Now, moved up to two days into the past, so seals and wraps don't date the rumor
+fn tweaked_now() -> Unixtime {
+    Unixtime::now().unwrap() - Duration::from_secs(rand::random::<u64>() % (2 * 24 * 3600))
+}
+
+/// Unwrap a giftwrap (NIP-59) with the remote signer
+pub(crate) async fn unwrap_giftwrap(event: &Event) -> Result<Rumor, Error> {
+    let seal: Event = serde_json::from_str(&decrypt(&event.pubkey, &event.content).await?)?;
+    if seal.kind != EventKind::Seal {
+        return Err(ErrorKind::General("Giftwrap does not contain a seal".to_owned()).into());
+    }
+    seal.verify(None)?;
+
+    let rumor: Rumor = serde_json::from_str(&decrypt(&seal.pubkey, &seal.content).await?)?;
+    if rumor.pubkey != seal.pubkey {
+        return Err(ErrorKind::General("Rumor was not authored by the sealer".to_owned()).into());
+    }
+    Ok(rumor)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bunker_uri_round_trip() {
+        let uri = BunkerUri {
+            signer_pubkey: PrivateKey::generate().public_key(),
+            relays: vec![
+                RelayUrl::try_from_str("wss://relay.example.com/").unwrap(),
+                RelayUrl::try_from_str("wss://other.example.com/path").unwrap(),
+            ],
+            secret: Some("s3cret".to_owned()),
+        };
+        let s = uri.as_uri_string();
+        assert!(s.starts_with("bunker://"));
+        assert_eq!(BunkerUri::try_from_str(&s).unwrap(), uri);
+
+        let no_secret = BunkerUri {
+            secret: None,
+            ..uri.clone()
+        };
+        assert_eq!(
+            BunkerUri::try_from_str(&no_secret.as_uri_string()).unwrap(),
+            no_secret
+        );
+
+        assert!(BunkerUri::try_from_str("nostrconnect://abc?relay=wss://x.com").is_err());
+        assert!(BunkerUri::try_from_str(&format!(
+            "bunker://{}",
+            uri.signer_pubkey.as_hex_string()
+        ))
+        .is_err());
+    }
+}
@@ -0,0 +1,60 @@
+use crate::alerts::AlertTier;
+use crate::dm_channel::DmChannel;
+use crate::globals::GLOBALS;
+use crate::pending::PendingItem;
+use nostr_types::{Id, PublicKey, RelayUrl};
+use tokio::sync::broadcast;
+
+/// A state change inside of gossip-lib that a front-end (or any other integration)
+/// may wish to react to.
+///
+/// These are broadcast on `GLOBALS.bus`. Instead of polling GLOBALS flags every frame,
+/// subscribe with [subscribe] and react as messages arrive. The older GLOBALS flags
+/// (e.g. `ui_people_to_invalidate`) are still maintained for immediate-mode UIs.
+#[derive(Debug, Clone)]
+pub enum BusMessage {
+    /// A new feed-displayable event was stored
+    NewEventInFeed(Id),
+
+    /// A person record changed (metadata, nip05 validity, list membership)
+    PersonUpdated(PublicKey),
+
+    /// We connected to or disconnected from a relay
+    RelayStatusChanged { url: RelayUrl, connected: bool },
+
+    /// A new pending item (notification) was added
+    NotificationAdded(PendingItem),
+
+    /// A direct message arrived from somebody else
+    DmReceived { channel: DmChannel, id: Id },
+
+    /// A newly arrived event should alert the user now (see [crate::alerts])
+    Alert { id: Id, tier: AlertTier },
+
+    /// A frontend's app data value was set or removed (see [crate::AppData])
+    AppDataChanged { namespace: String, key: String },
+
+    /// A deck, column or column unread counter changed (see [crate::deck])
+    DecksChanged,
+
+    /// The overlord started handling a tracked command
+    JobStarted(u64),
+
+    /// The overlord made progress on a tracked command
+    JobProgress { job_id: u64, detail: String },
+
+    /// The overlord finished handling a tracked command. `error` is None on success.
+    JobCompleted { job_id: u64, error: Option<String> },
+}
+
+/// Subscribe to the bus. Every receiver gets every message sent after it
+/// subscribed. Slow receivers that fall more than the channel capacity behind will
+/// get a `RecvError::Lagged` and then continue with newer messages.
+pub fn subscribe() -> broadcast::Receiver<BusMessage> {
+    GLOBALS.bus.subscribe()
+}
+
+/// Send a message on the bus. It is fine if nobody is listening.
+pub(crate) fn send(message: BusMessage) {
+    let _ = GLOBALS.bus.send(message);
+}
@@ -1,11 +1,14 @@
+use crate::bunker::BunkerUri;
 use crate::dm_channel::DmChannel;
+use crate::globals::GLOBALS;
 use crate::misc::Private;
 use crate::nip46::{Approval, ParsedCommand};
+use crate::nwc::NwcUri;
 use crate::people::PersonList;
 use crate::relay::Relay;
 use nostr_types::{
-    Event, EventAddr, Id, IdHex, Metadata, MilliSatoshi, Profile, PublicKey, RelayUrl, Tag,
-    UncheckedUrl, Unixtime,
+    Event, EventAddr, EventKind, Filter, Id, IdHex, Metadata, MilliSatoshi, Profile, PublicKey,
+    RelayUrl, Tag, UncheckedUrl, Unixtime,
 };
 use std::fmt;
 use std::hash::{Hash, Hasher};
@@ -26,6 +29,9 @@ pub enum ToOverlordMessage {
     /// internal
     AdvertiseRelayListNextChunk(Box<Event>, Vec<RelayUrl>),
 
+    /// Calls [audit_events](crate::Overlord::audit_events)
+    AuditEvents,
+
     /// Calls [auth_approved](crate::Overlord::auth_approved)
     /// pass 'true' as the second parameter for a permanent approval
     AuthApproved(RelayUrl, bool),
@@ -37,17 +43,47 @@ pub enum ToOverlordMessage {
     /// Calls [change_passphrase](crate::Overlord::change_passphrase)
     ChangePassphrase { old: String, new: String },
 
+    /// Calls [check_dvm_job](crate::Overlord::check_dvm_job)
+    CheckDvmJob(Id),
+
+    /// Calls [check_wallet_balance](crate::Overlord::check_wallet_balance)
+    CheckWalletBalance,
+
+    /// internal (the post may need to go to alternate relays)
+    CheckWriteRedundancy(Id),
+
     /// Calls [clear_person_list](crate::Overlord::clear_person_list)
     ClearPersonList(PersonList),
 
+    /// Calls [clear_relay_auth_key](crate::Overlord::clear_relay_auth_key)
+    ClearRelayAuthKey(RelayUrl),
+
+    /// Calls [collect_garbage](crate::Overlord::collect_garbage)
+    CollectGarbage,
+
+    /// Calls [compact_database](crate::Overlord::compact_database)
+    CompactDatabase,
+
+    /// Calls [confirm_held_post](crate::Overlord::confirm_held_post)
+    ConfirmHeldPost(u64, bool),
+
     /// Calls [auth_approved](crate::Overlord::connect_approved)
     /// pass 'true' as the second parameter for a permanent approval
     ConnectApproved(RelayUrl, bool),
 
+    /// Calls [connect_bunker](crate::Overlord::connect_bunker)
+    ConnectBunker(BunkerUri),
+
     /// Calls [auth_approved](crate::Overlord::connect_declined)
     /// pass 'true' as the second parameter for a permanent approval
     ConnectDeclined(RelayUrl, bool),
 
+    /// Calls [connect_wallet](crate::Overlord::connect_wallet)
+    ConnectWallet(NwcUri),
+
+    /// Calls [count_feed_sources](crate::Overlord::count_feed_sources)
+    CountFeedSources,
+
     /// Calls [delegation_reset](crate::Overlord::delegation_reset)
     DelegationReset,
 
@@ -63,15 +99,27 @@ pub enum ToOverlordMessage {
     /// Calls [delete_pub](crate::Overlord::delete_pub)
     DeletePub,
 
+    /// Calls [disconnect_bunker](crate::Overlord::disconnect_bunker)
+    DisconnectBunker,
+
+    /// Calls [disconnect_wallet](crate::Overlord::disconnect_wallet)
+    DisconnectWallet,
+
     /// Calls [drop_relay](crate::Overlord::drop_relay)
     DropRelay(RelayUrl),
 
+    /// Calls [end_feed_preview](crate::Overlord::end_feed_preview)
+    EndFeedPreview(u64),
+
     /// Calls [fetch_event](crate::Overlord::fetch_event)
     FetchEvent(Id, Vec<RelayUrl>),
 
     /// Calls [fetch_event_addr](crate::Overlord::fetch_event_addr)
     FetchEventAddr(EventAddr),
 
+    /// Calls [fetch_zap_signer](crate::Overlord::fetch_zap_signer)
+    FetchZapSigner(PublicKey),
+
     /// Calls [follow_pubkey](crate::Overlord::follow_pubkey)
     FollowPubkey(PublicKey, PersonList, Private),
 
@@ -97,12 +145,21 @@ pub enum ToOverlordMessage {
     /// Calls [import_pub](crate::Overlord::import_pub)
     ImportPub(String),
 
+    /// Calls [join_group](crate::Overlord::join_group)
+    JoinGroup(RelayUrl, String),
+
+    /// Calls [leave_group](crate::Overlord::leave_group)
+    LeaveGroup(RelayUrl, String),
+
     /// Calls [like](crate::Overlord::like)
     Like(Id, PublicKey),
 
     /// Calls [load_more_current_feed](crate::Overlord::load_more_current_feed)
     LoadMoreCurrentFeed,
 
+    /// Calls [load_wallet](crate::Overlord::load_wallet)
+    LoadWallet,
+
     /// internal (minions use this channel too)
     MinionJobComplete(RelayUrl, u64),
 
@@ -112,6 +169,12 @@ pub enum ToOverlordMessage {
     /// Calls [nip46_server_op_approval_response](crate::Overlord::nip46_server_op_approval_response)
     Nip46ServerOpApprovalResponse(PublicKey, ParsedCommand, Approval),
 
+    /// Calls [pay_dvm_job](crate::Overlord::pay_dvm_job)
+    PayDvmJob(Id, u64),
+
+    /// Calls [pay_zap_invoice](crate::Overlord::pay_zap_invoice)
+    PayZapInvoice(Id, String),
+
     /// Calls [post](crate::Overlord::post)
     Post {
         content: String,
@@ -123,45 +186,123 @@ pub enum ToOverlordMessage {
     /// Calls [post_again](crate::Overlord::post_again)
     PostAgain(Event),
 
+    /// Calls [post_nip17_dm](crate::Overlord::post_nip17_dm)
+    PostNip17Dm {
+        dm_channel: DmChannel,
+        content: String,
+        in_reply_to: Option<Id>,
+    },
+
     /// Calls [post_nip46_event](crate::Overlord::post_nip46_event)
     PostNip46Event(Event, Vec<RelayUrl>),
 
+    /// Calls [post_to_group](crate::Overlord::post_to_group)
+    PostToGroup {
+        relay: RelayUrl,
+        group: String,
+        content: String,
+        in_reply_to: Option<Id>,
+    },
+
+    /// Calls [post_wallet_request](crate::Overlord::post_wallet_request)
+    PostWalletRequest(Event, Vec<RelayUrl>),
+
+    /// Calls [promote_star_to_bookmark](crate::Overlord::promote_star_to_bookmark)
+    PromoteStarToBookmark(Id),
+
+    /// Calls [prune_by_policy](crate::Overlord::prune_by_policy)
+    PruneByPolicy,
+
     /// Calls [prune_cache](crate::Overlord::prune_cache)
     PruneCache,
 
     /// Calls [prune_database](crate::Overlord::prune_database)
     PruneDatabase,
 
+    /// Calls [push_blocked_relays](crate::Overlord::push_blocked_relays)
+    PushBlockedRelays,
+
+    /// Calls [push_dm_relays](crate::Overlord::push_dm_relays)
+    PushDmRelays,
+
     /// Calls [push_person_list](crate::Overlord::push_person_list)
     PushPersonList(PersonList),
 
     /// Calls [push_metadata](crate::Overlord::push_metadata)
     PushMetadata(Metadata),
 
+    /// Calls [push_search_relays](crate::Overlord::push_search_relays)
+    PushSearchRelays,
+
     /// Calls [rank_relay](crate::Overlord::rank_relay)
     RankRelay(RelayUrl, u8),
 
+    /// Calls [raw_relay_query](crate::Overlord::raw_relay_query)
+    RawRelayQuery {
+        relay: RelayUrl,
+        filter: Filter,
+        ingest: bool,
+        cap: usize,
+    },
+
+    /// Calls [rebroadcast_events](crate::Overlord::rebroadcast_events)
+    RebroadcastEvents(Vec<Event>),
+
     /// internal (the overlord sends messages to itself sometimes!)
     ReengageMinion(RelayUrl),
 
     /// Calls [refresh_scores_and_pick_relays](crate::Overlord::refresh_scores_and_pick_relays)
     RefreshScoresAndPickRelays,
 
+    /// Calls [refresh_person_list](crate::Overlord::refresh_person_list)
+    RefreshPersonList(PersonList),
+
     /// Calls [reresh_subscribed_metadata](crate::Overlord::refresh_subscribed_metadata)
     RefreshSubscribedMetadata,
 
     /// Calls [repost](crate::Overlord::repost)
     Repost(Id),
 
+    /// Calls [request_dvm_job](crate::Overlord::request_dvm_job)
+    RequestDvmJob {
+        kind: u32,
+        input: String,
+        input_type: String,
+        params: Vec<(String, String)>,
+        provider: Option<PublicKey>,
+    },
+
+    /// Calls [resolve_person_list_import](crate::Overlord::resolve_person_list_import)
+    ResolvePersonListImport(String),
+
     /// Calls [search](crate::Overlord::search)
     Search(String),
 
+    /// Calls [search_relays](crate::Overlord::search_relays)
+    SearchRelays(String),
+
     /// Calls [set_active_person](crate::Overlord::set_active_person)
     SetActivePerson(PublicKey),
 
+    /// Calls [set_encrypt_at_rest](crate::Overlord::set_encrypt_at_rest)
+    SetEncryptAtRest(bool),
+
+    /// Calls [set_relay_auth_key](crate::Overlord::set_relay_auth_key)
+    SetRelayAuthKey {
+        relay: RelayUrl,
+        privkey: String,
+        password: String,
+    },
+
     /// internal
     SetDmChannel(DmChannel),
 
+    /// internal
+    SetGroupFeed(RelayUrl, String),
+
+    /// internal
+    SetCommunityFeed(EventAddr),
+
     /// internal
     SetPersonFeed(PublicKey),
 
@@ -175,6 +316,16 @@ pub enum ToOverlordMessage {
     /// Calls [start_long_lived_subscriptions](crate::Overlord::start_long_lived_subscriptions)
     StartLongLivedSubscriptions,
 
+    /// Calls [start_feed_preview](crate::Overlord::start_feed_preview)
+    StartFeedPreview {
+        id: u64,
+        pubkeys: Vec<PublicKey>,
+        since: Unixtime,
+    },
+
+    /// Calls [subscribe_bunker](crate::Overlord::subscribe_bunker)
+    SubscribeBunker,
+
     /// Calls [subscribe_config](crate::Overlord::subscribe_config)
     SubscribeConfig(Option<Vec<RelayUrl>>),
 
@@ -184,12 +335,36 @@ pub enum ToOverlordMessage {
     /// Calls [subscribe_inbox](crate::Overlord::subscribe_inbox)
     SubscribeInbox(Option<Vec<RelayUrl>>),
 
+    /// Calls [subscribe_other_inboxes](crate::Overlord::subscribe_other_inboxes)
+    SubscribeOtherInboxes,
+
+    /// Calls [subscribe_live_chat](crate::Overlord::subscribe_live_chat)
+    SubscribeLiveChat(EventAddr),
+
     /// Calls [subscribe_nip46](crate::Overlord::subscribe_nip46)
     SubscribeNip46(Vec<RelayUrl>),
 
+    /// Calls [sweep_history](crate::Overlord::sweep_history)
+    SweepHistory { rebroadcast: bool },
+
+    /// Calls [switch_profile](crate::Overlord::switch_profile)
+    SwitchProfile(Option<String>),
+
+    /// Calls [test_relay](crate::Overlord::test_relay)
+    TestRelay(RelayUrl),
+
+    /// internal (see [send_tracked](ToOverlordMessage::send_tracked))
+    Tracked(u64, Box<ToOverlordMessage>),
+
     /// Calls [unlock_key](crate::Overlord::unlock_key)
     UnlockKey(String),
 
+    /// Calls [unsubscribe_group](crate::Overlord::unsubscribe_group)
+    UnsubscribeGroup,
+
+    /// Calls [unsubscribe_live_chat](crate::Overlord::unsubscribe_live_chat)
+    UnsubscribeLiveChat,
+
     /// Calls [update_metadata](crate::Overlord::update_metadata)
     UpdateMetadata(PublicKey),
 
@@ -205,6 +380,12 @@ pub enum ToOverlordMessage {
     /// Calls [update_relay](crate::Overlord::update_relay)
     UpdateRelay(Relay, Relay),
 
+    /// Calls [use_paired_signer](crate::Overlord::use_paired_signer)
+    UsePairedSigner(PublicKey),
+
+    /// Calls [view_profile](crate::Overlord::view_profile)
+    ViewProfile(PublicKey),
+
     /// Calls [visible_notes_changed](crate::Overlord::visible_notes_changed)
     VisibleNotesChanged(Vec<Id>),
 
@@ -215,6 +396,25 @@ pub enum ToOverlordMessage {
     Zap(Id, PublicKey, MilliSatoshi, String),
 }
 
+impl ToOverlordMessage {
+    /// Send this message to the overlord and return a job id for it.
+    ///
+    /// The overlord will send `BusMessage::JobStarted` when it begins handling the
+    /// message, possibly some `BusMessage::JobProgress`, and finally
+    /// `BusMessage::JobCompleted` on the [bus](crate::bus), all keyed by this job id.
+    ///
+    /// Completion means the overlord has finished handling the command. Work that the
+    /// command hands off to minions (e.g. a post being sent to relays) may still be
+    /// in flight.
+    pub fn send_tracked(self) -> u64 {
+        let job_id = rand::random::<u64>();
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::Tracked(job_id, Box::new(self)));
+        job_id
+    }
+}
+
 /// Internal to gossip-lib.
 /// This is a message sent to the minions
 #[derive(Debug, Clone)]
@@ -254,31 +454,65 @@ pub(crate) enum ToMinionPayloadDetail {
     SubscribeDiscover(Vec<PublicKey>),
     SubscribeGeneralFeed(Vec<PublicKey>),
     SubscribeInbox,
+    SubscribeOtherInboxes(Vec<PublicKey>),
     SubscribePersonFeed(PublicKey),
+    SubscribePreviewFeed {
+        id: u64,
+        pubkeys: Vec<PublicKey>,
+        since: Unixtime,
+    },
     SubscribeReplies(IdHex),
     SubscribeRootReplies(IdHex),
     SubscribeDmChannel(DmChannel),
+    SubscribeLiveChat(EventAddr),
+    SubscribeGroup(String),
+    SubscribeCommunity(EventAddr),
+    SubscribeDvmJob(Id, u32),
+    SubscribeBunker,
+    SubscribeWallet,
     SubscribeNip46,
     TempSubscribeGeneralFeedChunk(Unixtime),
-    TempSubscribePersonFeedChunk { pubkey: PublicKey, start: Unixtime },
+    TempSubscribePersonFeedChunk {
+        pubkey: PublicKey,
+        start: Unixtime,
+    },
     TempSubscribeInboxFeedChunk(Unixtime),
+    TempSubscribeListFeed {
+        pubkeys: Vec<PublicKey>,
+        since: Unixtime,
+    },
     TempSubscribeMetadata(Vec<PublicKey>),
+    TempSubscribeOwnReplaceables,
+    TempSubscribeProfile(PublicKey, Vec<EventKind>),
+    TempSubscribeRelayMonitors(Vec<PublicKey>),
+    TempSubscribeSearch(String),
+    UnsubscribeAugments,
+    UnsubscribeCommunity,
+    UnsubscribeGroup,
+    UnsubscribeLiveChat,
+    UnsubscribeOtherInboxes,
     UnsubscribePersonFeed,
+    UnsubscribePreviewFeed(u64),
     UnsubscribeReplies,
 }
 
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 pub enum RelayConnectionReason {
     Advertising,
+    Community,
     Config,
     Discovery,
+    DvmJob,
     FetchAugments,
     FetchDirectMessages,
     FetchContacts,
     FetchEvent,
     FetchInbox,
     FetchMetadata,
+    FetchOtherInboxes,
     Follow,
+    Group,
+    LiveChat,
     NostrConnect,
     PostEvent,
     PostContacts,
@@ -286,8 +520,12 @@ pub enum RelayConnectionReason {
     PostMetadata,
     PostMuteList,
     PostNostrConnect,
+    PreviewFeed,
     ReadThread,
+    RemoteSigner,
+    Search,
     SubscribePerson,
+    Wallet,
 }
 
 impl fmt::Display for RelayConnectionReason {
@@ -302,13 +540,18 @@ impl RelayConnectionReason {
         use RelayConnectionReason::*;
         match *self {
             Discovery => "Searching for other people's Relay Lists",
+            DvmJob => "Asking a data vending machine (NIP-90) to do a job",
+            Community => "Reading a moderated community (NIP-72)",
             Config => "Reading our client configuration",
             FetchInbox => "Searching for inbox of us",
+            FetchOtherInboxes => "Searching for mentions of our other identities",
             Follow => "Following the posts of people in our Contact List",
             FetchAugments => "Fetching events that augment other events (likes, zaps, deletions)",
             FetchDirectMessages => "Fetching direct messages",
             FetchEvent => "Fetching a particular event",
             FetchMetadata => "Fetching metadata for a person",
+            Group => "Reading and posting to a relay-based group (NIP-29)",
+            LiveChat => "Reading the chat of a live activity (NIP-53)",
             NostrConnect => "Nostr connect",
             PostEvent => "Posting an event",
             Advertising => "Advertising our relay list",
@@ -318,8 +561,12 @@ impl RelayConnectionReason {
             PostMuteList => "Posting our mute list",
             PostMetadata => "Posting our metadata",
             PostNostrConnect => "Posting nostrconnect",
+            PreviewFeed => "Previewing the posts of a set of people",
             ReadThread => "Reading ancestors to build a thread",
+            RemoteSigner => "Talking to our remote signer",
+            Search => "Searching for notes (NIP-50)",
             SubscribePerson => "Subscribe to the events of a person",
+            Wallet => "Talking to our wallet (NIP-47)",
         }
     }
 
@@ -327,13 +574,18 @@ impl RelayConnectionReason {
         use RelayConnectionReason::*;
         match *self {
             Discovery => false,
+            DvmJob => true,
+            Community => true,
             Config => false,
             FetchInbox => true,
+            FetchOtherInboxes => true,
             Follow => true,
             FetchAugments => false,
             FetchDirectMessages => true,
             FetchEvent => false,
             FetchMetadata => false,
+            Group => true,
+            LiveChat => true,
             NostrConnect => true,
             PostEvent => false,
             Advertising => false,
@@ -343,8 +595,12 @@ impl RelayConnectionReason {
             PostMuteList => false,
             PostMetadata => false,
             PostNostrConnect => false,
+            PreviewFeed => false,
             ReadThread => true,
+            RemoteSigner => true,
+            Search => false,
             SubscribePerson => false,
+            Wallet => true,
         }
     }
 }
@@ -374,3 +630,38 @@ impl RelayJob {
         self.reason == other.reason && self.payload.detail == other.payload.detail
     }
 }
+
+/// Activity of a relay job, as recorded by the minion doing it
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RelayJobActivity {
+    pub started: Option<Unixtime>,
+    pub subscriptions: Vec<String>,
+    pub filter_summary: String,
+    pub events_received: usize,
+}
+
+/// The status of an active relay job, for display in a network activity view.
+/// See [Globals::jobs](crate::Globals::jobs)
+#[derive(Debug, Clone)]
+pub struct RelayJobStatus {
+    /// The relay doing the job
+    pub relay: RelayUrl,
+
+    /// The job id
+    pub job_id: u64,
+
+    /// Why we are connected to the relay for this job
+    pub reason: RelayConnectionReason,
+
+    /// When the job was handed to the relay
+    pub started: Option<Unixtime>,
+
+    /// Subscriptions serving this job, as "handle:id"
+    pub subscriptions: Vec<String>,
+
+    /// A short human readable summary of the subscription filters
+    pub filter_summary: String,
+
+    /// How many events the relay has sent for this job
+    pub events_received: usize,
+}
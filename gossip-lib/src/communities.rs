@@ -0,0 +1,259 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use nostr_types::{Event, EventAddr, EventKind, Id, PublicKey, RelayUrl, Tag};
+use std::collections::HashSet;
+
+/// Community type, aliased to the latest version
+pub type Community = crate::storage::types::Community1;
+
+/// The value of the 'a' tag that points at a community: `34550:<author hex>:<d>`
+pub fn community_addr_value(author: &PublicKey, d: &str) -> String {
+    format!(
+        "{}:{}:{}",
+        u32::from(EventKind::CommunityDefinition),
+        author.as_hex_string(),
+        d
+    )
+}
+
+/// The 'a' tag that places a post in a community, with a relay hint if the
+/// community names any relays
+pub fn community_tag(community: &Community) -> Tag {
+    let value = community_addr_value(&community.author, &community.d);
+    match community.relays.first() {
+        Some(relay) => Tag::new(&["a", &value, relay.as_str()]),
+        None => Tag::new(&["a", &value]),
+    }
+}
+
+// The communities an event's 'a' tags point at, as (author, d)
+fn communities_tagged(tags: &[Tag]) -> Vec<(PublicKey, String)> {
+    tags.iter()
+        .filter_map(|t| t.parse_address().ok())
+        .filter(|(ea, _)| ea.kind == EventKind::CommunityDefinition)
+        .map(|(ea, _)| (ea.author, ea.d))
+        .collect()
+}
+
+/// Apply a community definition (kind 34550) to what we know of the community.
+/// Returns false if the event is not a definition, or older than what we have.
+pub fn apply_definition(community: &mut Community, event: &Event) -> bool {
+    if event.kind != EventKind::CommunityDefinition || event.created_at <= community.updated_at {
+        return false;
+    }
+
+    community.event_id = Some(event.id);
+    community.updated_at = event.created_at;
+    community.name = String::new();
+    community.description = String::new();
+    community.image = String::new();
+    community.rules = String::new();
+    community.moderators = Vec::new();
+    community.relays = Vec::new();
+
+    for tag in event.tags.iter() {
+        match tag.tagname() {
+            "name" => community.name = tag.value().to_owned(),
+            "description" => community.description = tag.value().to_owned(),
+            "image" => community.image = tag.value().to_owned(),
+            "rules" => community.rules = tag.value().to_owned(),
+            "p" => {
+                if tag.get_index(3) == "moderator" {
+                    if let Ok(pubkey) = PublicKey::try_from_hex_string(tag.value(), true) {
+                        if !community.moderators.contains(&pubkey) {
+                            community.moderators.push(pubkey);
+                        }
+                    }
+                }
+            }
+            "relay" => {
+                if let Ok(url) = RelayUrl::try_from_str(tag.value()) {
+                    if !community.relays.contains(&url) {
+                        community.relays.push(url);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    true
+}
+
+// Process calls this for each community definition
+pub(crate) fn process_community_definition(event: &Event) -> Result<(), Error> {
+    let d = match event.parameter() {
+        Some(d) => d,
+        None => return Ok(()),
+    };
+    GLOBALS.storage.modify_community(
+        &event.pubkey,
+        &d,
+        |community| {
+            apply_definition(community, event);
+        },
+        None,
+    )
+}
+
+/// The relays of the communities these tags place a post in. Posts to a community
+/// go there as well as to our own write relays.
+pub fn relays_for_tags(tags: &[Tag]) -> Result<Vec<RelayUrl>, Error> {
+    let mut relays: Vec<RelayUrl> = Vec::new();
+    for (author, d) in communities_tagged(tags) {
+        if let Some(community) = GLOBALS.storage.read_community(&author, &d)? {
+            relays.extend(community.relays);
+        }
+    }
+    Ok(relays)
+}
+
+/// The community tags of a post, for a reply to it to carry along
+pub fn community_tags_of(event: &Event) -> Vec<Tag> {
+    event
+        .tags
+        .iter()
+        .filter(|t| {
+            t.parse_address()
+                .is_ok_and(|(ea, _)| ea.kind == EventKind::CommunityDefinition)
+        })
+        .cloned()
+        .collect()
+}
+
+/// Join or leave a community. This is only kept locally. Joining one we have no
+/// definition of yet fetches it.
+pub fn set_joined(author: PublicKey, d: &str, joined: bool) -> Result<(), Error> {
+    let mut missing = false;
+    GLOBALS.storage.modify_community(
+        &author,
+        d,
+        |community| {
+            community.joined = joined;
+            missing = community.event_id.is_none();
+        },
+        None,
+    )?;
+    if joined && missing {
+        let _ = GLOBALS
+            .to_overlord
+            .send(crate::comms::ToOverlordMessage::FetchEventAddr(EventAddr {
+                d: d.to_owned(),
+                relays: vec![],
+                kind: EventKind::CommunityDefinition,
+                author,
+            }));
+    }
+    Ok(())
+}
+
+/// The communities we have joined, by name
+pub fn joined_communities() -> Result<Vec<Community>, Error> {
+    let mut communities: Vec<Community> = GLOBALS
+        .storage
+        .read_all_communities()?
+        .drain(..)
+        .filter(|c| c.joined)
+        .collect();
+    communities.sort_by(|a, b| {
+        a.display_name()
+            .to_lowercase()
+            .cmp(&b.display_name().to_lowercase())
+    });
+    Ok(communities)
+}
+
+/// The posts of a community that its moderators approved (kind 4550) and that we
+/// have, newest first
+pub fn approved_posts(community: &Community) -> Result<Vec<Id>, Error> {
+    let addr = community_addr_value(&community.author, &community.d);
+
+    // The tag index matches on prefixes, so check the 'a' tag exactly
+    let approvals = GLOBALS.storage.find_tagged_events(
+        "a",
+        Some(&addr),
+        |e| {
+            e.kind == EventKind::CommunityPostApproval
+                && community.is_moderator(&e.pubkey)
+                && e.tags
+                    .iter()
+                    .any(|t| t.tagname() == "a" && t.value() == addr)
+        },
+        false,
+    )?;
+
+    let mut approved: HashSet<Id> = HashSet::new();
+    for approval in approvals.iter() {
+        for tag in approval.tags.iter() {
+            if let Ok((id, _, _)) = tag.parse_event() {
+                approved.insert(id);
+            }
+        }
+    }
+
+    let mut events: Vec<Event> = Vec::new();
+    for id in approved {
+        if let Some(event) = GLOBALS.storage.read_event(id)? {
+            events.push(event);
+        }
+    }
+    events.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(b.id.cmp(&a.id)));
+    Ok(events.iter().map(|e| e.id).collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nostr_types::{PreEvent, PrivateKey, Unixtime};
+
+    #[test]
+    fn test_apply_definition() {
+        let key = PrivateKey::generate();
+        let moderator = PrivateKey::generate().public_key();
+        let other = PrivateKey::generate().public_key();
+        let pre_event = PreEvent {
+            pubkey: key.public_key(),
+            created_at: Unixtime::now().unwrap(),
+            kind: EventKind::CommunityDefinition,
+            tags: vec![
+                Tag::new(&["d", "rust"]),
+                Tag::new(&["name", "Rustaceans"]),
+                Tag::new(&["description", "All about rust"]),
+                Tag::new(&["p", &moderator.as_hex_string(), "", "moderator"]),
+                Tag::new(&["p", &other.as_hex_string()]),
+                Tag::new(&["relay", "wss://relay.example.com", "requests"]),
+            ],
+            content: "".to_owned(),
+        };
+        let id = pre_event.hash().unwrap();
+        let event = Event {
+            id,
+            pubkey: pre_event.pubkey,
+            created_at: pre_event.created_at,
+            kind: pre_event.kind,
+            sig: key.sign_id(id).unwrap(),
+            content: pre_event.content,
+            tags: pre_event.tags,
+        };
+
+        let mut community = Community::new(key.public_key(), "rust".to_owned());
+        assert!(apply_definition(&mut community, &event));
+        assert_eq!(community.display_name(), "Rustaceans");
+        assert_eq!(community.moderators, vec![moderator]);
+        assert!(community.is_moderator(&moderator));
+        assert!(community.is_moderator(&key.public_key()));
+        assert!(!community.is_moderator(&other));
+        assert_eq!(
+            community.relays,
+            vec![RelayUrl::try_from_str("wss://relay.example.com").unwrap()]
+        );
+
+        // The same definition again changes nothing
+        assert!(!apply_definition(&mut community, &event));
+
+        assert_eq!(
+            communities_tagged(&[community_tag(&community)]),
+            vec![(key.public_key(), "rust".to_owned())]
+        );
+    }
+}
@@ -0,0 +1,304 @@
+use crate::alerts::AlertTier;
+use crate::bus::BusMessage;
+use crate::error::{Error, ErrorKind};
+use crate::feed::FeedKind;
+use crate::globals::GLOBALS;
+use crate::people::PersonList;
+use nostr_types::{Event, EventKind, PublicKey, Unixtime};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+// Decks are kept in the app data store under this namespace and key
+const DECKS_NAMESPACE: &str = crate::storage::LIB_NAMESPACE;
+const DECKS_KEY: &str = "decks";
+
+/// What a deck column shows
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColumnSource {
+    /// Notes by the people in a person list (by list number)
+    List { list: u8, with_replies: bool },
+
+    /// Replies to us (and, if indirect, mentions of us)
+    Inbox { indirect: bool },
+
+    /// Notes by one person
+    Person(PublicKey),
+
+    /// Notifications of the given urgency tiers
+    Notifications(Vec<AlertTier>),
+
+    /// Notes containing some text (case-insensitive)
+    Search(String),
+}
+
+impl ColumnSource {
+    /// The feed this column shows, for sources that are feeds
+    pub fn feed_kind(&self) -> Option<FeedKind> {
+        match self {
+            ColumnSource::List { list, with_replies } => {
+                PersonList::from_number(*list).map(|list| FeedKind::List(list, *with_replies))
+            }
+            ColumnSource::Inbox { indirect } => Some(FeedKind::Inbox(*indirect)),
+            ColumnSource::Person(pubkey) => Some(FeedKind::Person(*pubkey)),
+            ColumnSource::Notifications(_) | ColumnSource::Search(_) => None,
+        }
+    }
+
+    /// Does this event belong in this column?
+    pub fn matches(&self, event: &Event) -> bool {
+        let displayable = event.kind.is_feed_displayable()
+            && event.kind != EventKind::EncryptedDirectMessage
+            && event.kind != EventKind::DmChat;
+
+        match self {
+            ColumnSource::List { list, with_replies } => {
+                displayable
+                    && (*with_replies || crate::nip10::replies_to(event).is_none())
+                    && match PersonList::from_number(*list) {
+                        Some(list) => GLOBALS.people.is_person_in_list(&event.pubkey, list),
+                        None => false,
+                    }
+            }
+            ColumnSource::Inbox { indirect } => {
+                let my_pubkey = match GLOBALS.identity.public_key() {
+                    Some(pk) => pk,
+                    None => return false,
+                };
+                displayable
+                    && event.pubkey != my_pubkey
+                    && event.is_tagged(&my_pubkey)
+                    && (*indirect || crate::nip10::replies_to(event).is_some())
+            }
+            ColumnSource::Person(pubkey) => displayable && event.pubkey == *pubkey,
+            ColumnSource::Notifications(tiers) => match crate::alerts::classify(event) {
+                Some(tier) => tiers.contains(&tier),
+                None => false,
+            },
+            ColumnSource::Search(text) => {
+                displayable
+                    && !text.is_empty()
+                    && event.content.to_lowercase().contains(&text.to_lowercase())
+            }
+        }
+    }
+}
+
+/// A column of a deck
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Column {
+    /// Unique among all columns of all decks
+    pub id: u64,
+
+    pub name: String,
+
+    pub source: ColumnSource,
+
+    /// Events created up to this time have been seen
+    pub last_read: i64,
+
+    /// Matching events that arrived since last_read
+    pub unread: u32,
+}
+
+/// A named set of columns, in display order
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deck {
+    pub name: String,
+    pub columns: Vec<Column>,
+}
+
+/// The decks (multi-column layouts) of the user, persisted in the database.
+///
+/// Unread counters are maintained here as events arrive, so every frontend shows
+/// the same counts. Changes are announced on the bus as [BusMessage::DecksChanged].
+#[derive(Debug, Default)]
+pub struct Decks {
+    // Loaded from storage on first use
+    decks: RwLock<Option<Vec<Deck>>>,
+}
+
+impl Decks {
+    pub(crate) fn new() -> Decks {
+        Decks {
+            decks: RwLock::new(None),
+        }
+    }
+
+    /// All decks
+    pub fn decks(&self) -> Vec<Deck> {
+        self.with_decks(|decks| decks.clone())
+    }
+
+    /// A deck by name
+    pub fn deck(&self, name: &str) -> Option<Deck> {
+        self.with_decks(|decks| decks.iter().find(|d| d.name == name).cloned())
+    }
+
+    /// Add an empty deck
+    pub fn add_deck(&self, name: &str) -> Result<(), Error> {
+        self.modify(|decks| {
+            if decks.iter().any(|d| d.name == name) {
+                return Err(ErrorKind::General(format!("Deck {} already exists", name)).into());
+            }
+            decks.push(Deck {
+                name: name.to_owned(),
+                columns: vec![],
+            });
+            Ok(())
+        })
+    }
+
+    /// Remove a deck and its columns
+    pub fn remove_deck(&self, name: &str) -> Result<(), Error> {
+        self.modify(|decks| {
+            decks.retain(|d| d.name != name);
+            Ok(())
+        })
+    }
+
+    /// Rename a deck
+    pub fn rename_deck(&self, name: &str, new_name: &str) -> Result<(), Error> {
+        self.modify(|decks| {
+            if decks.iter().any(|d| d.name == new_name) {
+                return Err(ErrorKind::General(format!("Deck {} already exists", new_name)).into());
+            }
+            find_deck(decks, name)?.name = new_name.to_owned();
+            Ok(())
+        })
+    }
+
+    /// Add a column to the end of a deck. Returns the new column's id.
+    pub fn add_column(&self, deck: &str, name: &str, source: ColumnSource) -> Result<u64, Error> {
+        let now = Unixtime::now().unwrap().0;
+        self.modify(|decks| {
+            let id = decks
+                .iter()
+                .flat_map(|d| d.columns.iter())
+                .map(|c| c.id)
+                .max()
+                .unwrap_or(0)
+                + 1;
+            find_deck(decks, deck)?.columns.push(Column {
+                id,
+                name: name.to_owned(),
+                source,
+                last_read: now,
+                unread: 0,
+            });
+            Ok(id)
+        })
+    }
+
+    /// Remove a column
+    pub fn remove_column(&self, column_id: u64) -> Result<(), Error> {
+        self.modify(|decks| {
+            for deck in decks.iter_mut() {
+                deck.columns.retain(|c| c.id != column_id);
+            }
+            Ok(())
+        })
+    }
+
+    /// Move a column to a new position within its deck
+    pub fn move_column(&self, column_id: u64, index: usize) -> Result<(), Error> {
+        self.modify(|decks| {
+            for deck in decks.iter_mut() {
+                if let Some(from) = deck.columns.iter().position(|c| c.id == column_id) {
+                    let column = deck.columns.remove(from);
+                    let index = index.min(deck.columns.len());
+                    deck.columns.insert(index, column);
+                    return Ok(());
+                }
+            }
+            Err(ErrorKind::General(format!("No column {}", column_id)).into())
+        })
+    }
+
+    /// Mark everything in a column as read
+    pub fn mark_column_read(&self, column_id: u64) -> Result<(), Error> {
+        let now = Unixtime::now().unwrap().0;
+        self.modify(|decks| {
+            let column = decks
+                .iter_mut()
+                .flat_map(|d| d.columns.iter_mut())
+                .find(|c| c.id == column_id)
+                .ok_or_else(|| -> Error {
+                    ErrorKind::General(format!("No column {}", column_id)).into()
+                })?;
+            column.last_read = now;
+            column.unread = 0;
+            Ok(())
+        })
+    }
+
+    // Process.rs calls this for each newly arrived event. Counters only live in memory
+    // until the next modification (or shutdown) saves them.
+    pub(crate) fn count_new_event(&self, event: &Event) {
+        let mut changed = false;
+        {
+            let mut guard = self.decks.write();
+            let decks = guard.get_or_insert_with(load);
+            for column in decks.iter_mut().flat_map(|d| d.columns.iter_mut()) {
+                if event.created_at.0 > column.last_read && column.source.matches(event) {
+                    column.unread += 1;
+                    changed = true;
+                }
+            }
+        }
+        if changed {
+            crate::bus::send(BusMessage::DecksChanged);
+        }
+    }
+
+    /// Save the decks (including unread counters) to storage
+    pub fn save(&self) -> Result<(), Error> {
+        if let Some(decks) = &*self.decks.read() {
+            GLOBALS
+                .storage
+                .app_data(DECKS_NAMESPACE)?
+                .put(DECKS_KEY, decks)?;
+        }
+        Ok(())
+    }
+
+    fn with_decks<R>(&self, f: impl FnOnce(&Vec<Deck>) -> R) -> R {
+        if let Some(decks) = &*self.decks.read() {
+            return f(decks);
+        }
+        let mut guard = self.decks.write();
+        let decks = guard.get_or_insert_with(load);
+        f(decks)
+    }
+
+    fn modify<R>(&self, f: impl FnOnce(&mut Vec<Deck>) -> Result<R, Error>) -> Result<R, Error> {
+        let r = {
+            let mut guard = self.decks.write();
+            let decks = guard.get_or_insert_with(load);
+            f(decks)?
+        };
+        self.save()?;
+        crate::bus::send(BusMessage::DecksChanged);
+        Ok(r)
+    }
+}
+
+fn find_deck<'a>(decks: &'a mut [Deck], name: &str) -> Result<&'a mut Deck, Error> {
+    decks
+        .iter_mut()
+        .find(|d| d.name == name)
+        .ok_or_else(|| ErrorKind::General(format!("No deck {}", name)).into())
+}
+
+fn load() -> Vec<Deck> {
+    let loaded = GLOBALS
+        .storage
+        .app_data(DECKS_NAMESPACE)
+        .and_then(|store| store.get::<Vec<Deck>>(DECKS_KEY));
+    match loaded {
+        Ok(decks) => decks.unwrap_or_default(),
+        Err(e) => {
+            tracing::error!("Could not load decks: {}", e);
+            vec![]
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use crate::comms::ToOverlordMessage;
+use crate::dm_channel::DmChannel;
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use nostr_types::{Event, EventKind, PublicKey, Unixtime};
+
+/// Every auto-reply starts with this, so that nobody takes it for something we
+/// wrote, and so that two auto-responders never answer each other
+pub const AUTOREPLY_MARKER: &str = "[Automatic reply]";
+
+// DMs older than this when they arrive are catching up, not new, so never answered
+const MAX_DM_AGE_SECS: i64 = 600;
+
+/// Fill in the variables of an auto-reply template: `{name}` (who wrote to us),
+/// `{my_name}` and `{date}` (today, local time)
+pub fn render_template(template: &str, name: &str, my_name: &str, date: &str) -> String {
+    template
+        .replace("{name}", name)
+        .replace("{my_name}", my_name)
+        .replace("{date}", date)
+}
+
+// Read a list of people from a setting: npubs or hex keys, separated by commas or
+// whitespace. Entries that are neither are skipped.
+fn parse_people(s: &str) -> Vec<PublicKey> {
+    s.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| {
+            PublicKey::try_from_bech32_string(entry, true)
+                .or_else(|_| PublicKey::try_from_hex_string(entry, true))
+                .ok()
+        })
+        .collect()
+}
+
+/// Would the auto-responder answer this person, going by its allowlist and
+/// denylist? (It also waits between replies to each person.)
+pub fn is_autoreply_target(pubkey: &PublicKey) -> bool {
+    if parse_people(&GLOBALS.storage.read_setting_dm_autoreply_denylist()).contains(pubkey) {
+        return false;
+    }
+    let allowlist = parse_people(&GLOBALS.storage.read_setting_dm_autoreply_allowlist());
+    allowlist.is_empty() || allowlist.contains(pubkey)
+}
+
+// When we last auto-replied to each person, as saved ("<hex> <unixtime>")
+fn last_sent() -> Vec<(PublicKey, Unixtime)> {
+    GLOBALS
+        .storage
+        .read_setting_dm_autoreply_sent()
+        .iter()
+        .filter_map(|entry| {
+            let (hex, at) = entry.split_once(' ')?;
+            let pubkey = PublicKey::try_from_hex_string(hex, true).ok()?;
+            let at = at.parse::<i64>().ok()?;
+            Some((pubkey, Unixtime(at)))
+        })
+        .collect()
+}
+
+// Remember that we auto-replied to this person now, forgetting replies too old to
+// hold back another one
+fn record_sent(pubkey: PublicKey, now: Unixtime, interval_secs: i64) -> Result<(), Error> {
+    let entries: Vec<String> = last_sent()
+        .drain(..)
+        .filter(|(pk, at)| *pk != pubkey && now.0 - at.0 < interval_secs)
+        .chain(std::iter::once((pubkey, now)))
+        .map(|(pk, at)| format!("{} {}", pk.as_hex_string(), at.0))
+        .collect();
+    GLOBALS
+        .storage
+        .write_setting_dm_autoreply_sent(&entries, None)
+}
+
+/// Process calls this for each DM from an accepted sender. If the auto-responder is
+/// on, and this one-to-one DM is new, not itself an auto-reply, and from someone
+/// we have not auto-replied to within the interval, answer it.
+pub(crate) fn maybe_autoreply(event: &Event, channel: &DmChannel) -> Result<(), Error> {
+    if !GLOBALS.storage.read_setting_dm_autoreply() || !GLOBALS.identity.is_unlocked() {
+        return Ok(());
+    }
+    let now = Unixtime::now().unwrap();
+    if now.0 - event.created_at.0 > MAX_DM_AGE_SECS {
+        return Ok(());
+    }
+    if channel.keys().len() != 1 || channel.keys()[0] != event.pubkey {
+        return Ok(());
+    }
+    let sender = event.pubkey;
+    if !is_autoreply_target(&sender) {
+        return Ok(());
+    }
+
+    let interval_secs = GLOBALS.storage.read_setting_dm_autoreply_interval_hours() as i64 * 3600;
+    if last_sent()
+        .iter()
+        .any(|(pk, at)| *pk == sender && now.0 - at.0 < interval_secs)
+    {
+        return Ok(());
+    }
+
+    let content = match event.kind {
+        EventKind::EncryptedDirectMessage => {
+            GLOBALS.identity.decrypt_event_contents_cached(event)?
+        }
+        _ => event.content.clone(),
+    };
+    if content.trim_start().starts_with(AUTOREPLY_MARKER) {
+        return Ok(());
+    }
+
+    let my_name = match GLOBALS.identity.public_key() {
+        Some(pk) => crate::names::best_name_from_pubkey_lookup(&pk),
+        None => return Ok(()),
+    };
+    let body = render_template(
+        &GLOBALS.storage.read_setting_dm_autoreply_message(),
+        &crate::names::best_name_from_pubkey_lookup(&sender),
+        &my_name,
+        &chrono::Local::now().format("%Y-%m-%d").to_string(),
+    );
+
+    record_sent(sender, now, interval_secs)?;
+    let _ = GLOBALS.to_overlord.send(ToOverlordMessage::Post {
+        content: format!("{} {}", AUTOREPLY_MARKER, body),
+        tags: vec![],
+        in_reply_to: None,
+        dm_channel: Some(channel.clone()),
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nostr_types::PrivateKey;
+
+    #[test]
+    fn test_render_template() {
+        assert_eq!(
+            render_template(
+                "Hi {name}, {my_name} is away until {date}.",
+                "alice",
+                "bob",
+                "2024-05-01"
+            ),
+            "Hi alice, bob is away until 2024-05-01."
+        );
+        assert_eq!(
+            render_template("No variables", "a", "b", "c"),
+            "No variables"
+        );
+    }
+
+    #[test]
+    fn test_parse_people() {
+        let pk1 = PrivateKey::generate().public_key();
+        let pk2 = PrivateKey::generate().public_key();
+        let s = format!(
+            "{},\n{}  nonsense",
+            pk1.as_bech32_string(),
+            pk2.as_hex_string()
+        );
+        assert_eq!(parse_people(&s), vec![pk1, pk2]);
+        assert!(parse_people("").is_empty());
+    }
+}
@@ -74,7 +74,7 @@ impl DmChannel {
                 Some(Self::new(&people))
             }
         } else if event.kind == EventKind::GiftWrap {
-            if let Ok(rumor) = GLOBALS.identity.unwrap_giftwrap(event) {
+            if let Ok(rumor) = GLOBALS.storage.giftwrap_rumor(event) {
                 let rumor_event = rumor.into_event_with_bad_signature();
                 let mut people: Vec<PublicKey> =
                     rumor_event.people().iter().map(|(pk, _, _)| *pk).collect();
@@ -105,4 +105,7 @@ pub struct DmChannelData {
     pub latest_message_content: Option<String>,
     pub message_count: usize,
     pub unread_message_count: usize,
+
+    /// How many of the messages we sent
+    pub sent_message_count: usize,
 }
@@ -0,0 +1,255 @@
+use crate::dm_channel::{DmChannel, DmChannelData};
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use crate::people::PersonList;
+use nostr_types::{Event, EventKind, PublicKey};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+// How long we trust the computed follows-of-follows set
+const FOF_CACHE_SECS: u64 = 600;
+
+// Each identity's policy is kept in the app data store under this namespace, keyed
+// by its public key
+const NAMESPACE: &str = crate::storage::LIB_NAMESPACE;
+const KEY_PREFIX: &str = "dm_policy:";
+
+lazy_static! {
+    static ref FOLLOWS_OF_FOLLOWS: RwLock<Option<(Instant, HashSet<PublicKey>)>> =
+        RwLock::new(None);
+    static ref FOF_REFRESHING: AtomicBool = AtomicBool::new(false);
+}
+
+/// Who may DM us. Messages from anyone else land in "message requests" and do not
+/// notify or show in the inbox until accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DmPolicy {
+    Everyone,
+    Follows,
+    FollowsOfFollows,
+    NobodyNew,
+}
+
+impl DmPolicy {
+    pub fn all() -> &'static [DmPolicy] {
+        &[
+            DmPolicy::Everyone,
+            DmPolicy::FollowsOfFollows,
+            DmPolicy::Follows,
+            DmPolicy::NobodyNew,
+        ]
+    }
+
+    /// The value it is stored as
+    pub fn as_setting(&self) -> &'static str {
+        match self {
+            DmPolicy::Everyone => "everyone",
+            DmPolicy::Follows => "follows",
+            DmPolicy::FollowsOfFollows => "follows_of_follows",
+            DmPolicy::NobodyNew => "nobody_new",
+        }
+    }
+
+    pub fn from_setting(s: &str) -> DmPolicy {
+        match s {
+            "follows" => DmPolicy::Follows,
+            "follows_of_follows" => DmPolicy::FollowsOfFollows,
+            "nobody_new" => DmPolicy::NobodyNew,
+            _ => DmPolicy::Everyone,
+        }
+    }
+}
+
+impl fmt::Display for DmPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DmPolicy::Everyone => write!(f, "Everyone"),
+            DmPolicy::Follows => write!(f, "People I follow"),
+            DmPolicy::FollowsOfFollows => write!(f, "People I follow and people they follow"),
+            DmPolicy::NobodyNew => write!(f, "Nobody new"),
+        }
+    }
+}
+
+// What an identity has chosen: its policy, and who it let through anyway
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct DmPolicySettings {
+    policy: String,
+    accepted: Vec<PublicKey>,
+}
+
+fn load(pubkey: &PublicKey) -> Result<DmPolicySettings, Error> {
+    Ok(GLOBALS
+        .storage
+        .app_data(NAMESPACE)?
+        .get::<DmPolicySettings>(&format!("{}{}", KEY_PREFIX, pubkey.as_hex_string()))?
+        .unwrap_or_default())
+}
+
+fn save(pubkey: &PublicKey, settings: &DmPolicySettings) -> Result<(), Error> {
+    GLOBALS.storage.app_data(NAMESPACE)?.put(
+        &format!("{}{}", KEY_PREFIX, pubkey.as_hex_string()),
+        settings,
+    )?;
+    Ok(())
+}
+
+// The settings of the current identity. Without one (or if they cannot be read)
+// everyone may DM us.
+fn current() -> DmPolicySettings {
+    let pubkey = match GLOBALS.identity.public_key() {
+        Some(pubkey) => pubkey,
+        None => return DmPolicySettings::default(),
+    };
+    match load(&pubkey) {
+        Ok(settings) => settings,
+        Err(e) => {
+            tracing::error!("Could not load the DM policy: {}", e);
+            DmPolicySettings::default()
+        }
+    }
+}
+
+/// The DM acceptance policy of the current identity
+pub fn dm_policy() -> DmPolicy {
+    DmPolicy::from_setting(&current().policy)
+}
+
+/// Set the DM acceptance policy of the current identity
+pub fn set_dm_policy(policy: DmPolicy) -> Result<(), Error> {
+    let pubkey = match GLOBALS.identity.public_key() {
+        Some(pubkey) => pubkey,
+        None => return Err((ErrorKind::NoPublicKey, file!(), line!()).into()),
+    };
+    let mut settings = load(&pubkey)?;
+    settings.policy = policy.as_setting().to_owned();
+    save(&pubkey, &settings)
+}
+
+/// May this person DM us without going through message requests?
+pub fn is_dm_sender_accepted(pubkey: &PublicKey) -> bool {
+    if GLOBALS.identity.public_key().as_ref() == Some(pubkey) {
+        return true;
+    }
+    let settings = current();
+    if settings.accepted.contains(pubkey) {
+        return true;
+    }
+
+    match DmPolicy::from_setting(&settings.policy) {
+        DmPolicy::Everyone => true,
+        DmPolicy::Follows => GLOBALS
+            .people
+            .is_person_in_list(pubkey, PersonList::Followed),
+        DmPolicy::FollowsOfFollows => {
+            GLOBALS
+                .people
+                .is_person_in_list(pubkey, PersonList::Followed)
+                || is_follow_of_follow(pubkey)
+        }
+        DmPolicy::NobodyNew => false,
+    }
+}
+
+/// Is this DM (kind 4, a giftwrap, or an unwrapped rumor) from an accepted sender?
+/// Events that aren't DMs, and giftwraps we cannot unwrap, are not held back.
+pub fn is_dm_event_accepted(event: &Event) -> bool {
+    match event.kind {
+        EventKind::EncryptedDirectMessage | EventKind::DmChat => {
+            is_dm_sender_accepted(&event.pubkey)
+        }
+        EventKind::GiftWrap => match GLOBALS.storage.giftwrap_rumor(event) {
+            Ok(rumor) => is_dm_sender_accepted(&rumor.pubkey),
+            Err(_) => true,
+        },
+        _ => true,
+    }
+}
+
+/// Is this DM channel accepted, or is it a message request? Channels we have written
+/// in are always accepted.
+pub fn is_dm_channel_accepted(data: &DmChannelData) -> bool {
+    data.sent_message_count > 0
+        || data.dm_channel.keys().is_empty()
+        || data.dm_channel.keys().iter().any(is_dm_sender_accepted)
+}
+
+/// Accept a message request, so that these people can DM us from now on
+pub fn accept_dm_channel(channel: &DmChannel) -> Result<(), Error> {
+    let my_pubkey = match GLOBALS.identity.public_key() {
+        Some(pubkey) => pubkey,
+        None => return Ok(()),
+    };
+    let mut settings = load(&my_pubkey)?;
+    let before = settings.accepted.len();
+    for pubkey in channel.keys() {
+        if !settings.accepted.contains(pubkey) {
+            settings.accepted.push(*pubkey);
+        }
+    }
+    if settings.accepted.len() != before {
+        save(&my_pubkey, &settings)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn is_follow_of_follow(pubkey: &PublicKey) -> bool {
+    if let Some((when, set)) = &*FOLLOWS_OF_FOLLOWS.read() {
+        if when.elapsed() < Duration::from_secs(FOF_CACHE_SECS) {
+            return set.contains(pubkey);
+        }
+    }
+
+    let set = match compute_follows_of_follows() {
+        Ok(set) => set,
+        Err(e) => {
+            tracing::error!("{}", e);
+            return false;
+        }
+    };
+    let found = set.contains(pubkey);
+    *FOLLOWS_OF_FOLLOWS.write() = Some((Instant::now(), set));
+    found
+}
+
+/// Like [is_follow_of_follow], but never computes the set on this thread, so it is
+/// fit for the UI thread. If the set is out of date it is recomputed in the
+/// background, and this is None until then.
+pub(crate) fn is_follow_of_follow_quick(pubkey: &PublicKey) -> Option<bool> {
+    if let Some((when, set)) = &*FOLLOWS_OF_FOLLOWS.read() {
+        if when.elapsed() < Duration::from_secs(FOF_CACHE_SECS) {
+            return Some(set.contains(pubkey));
+        }
+    }
+
+    if !FOF_REFRESHING.swap(true, Ordering::SeqCst) {
+        std::thread::spawn(|| {
+            match compute_follows_of_follows() {
+                Ok(set) => *FOLLOWS_OF_FOLLOWS.write() = Some((Instant::now(), set)),
+                Err(e) => tracing::error!("{}", e),
+            }
+            FOF_REFRESHING.store(false, Ordering::SeqCst);
+        });
+    }
+    None
+}
+
+fn compute_follows_of_follows() -> Result<HashSet<PublicKey>, Error> {
+    let mut set: HashSet<PublicKey> = HashSet::new();
+    for (followed, _) in GLOBALS.storage.get_people_in_list(PersonList::Followed)? {
+        if let Some(event) =
+            GLOBALS
+                .storage
+                .get_replaceable_event(EventKind::ContactList, followed, "")?
+        {
+            for (pubkey, _, _) in event.people() {
+                set.insert(pubkey);
+            }
+        }
+    }
+    Ok(set)
+}
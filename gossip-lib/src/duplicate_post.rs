@@ -0,0 +1,123 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::pending::PendingItem;
+use nostr_types::{Event, EventKind, EventReference, Filter, Id, Tag, Unixtime};
+use std::collections::HashSet;
+use std::time::Duration;
+
+// How similar a post must be to an earlier one to count as a duplicate
+const NEAR_DUPLICATE_SIMILARITY: f32 = 0.9;
+
+/// A post held back because it looks like one we posted recently
+#[derive(Debug, Clone)]
+pub struct HeldPost {
+    pub content: String,
+    pub tags: Vec<Tag>,
+    pub in_reply_to: Option<Id>,
+
+    /// The earlier post it looks like
+    pub previous: Id,
+}
+
+// Case and whitespace differences don't make a post different
+fn normalize(content: &str) -> String {
+    content
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+// Dice's coefficient of the character bigrams of two normalized texts
+fn similarity(a: &str, b: &str) -> f32 {
+    if a == b {
+        return 1.0;
+    }
+    let bigrams = |s: &str| -> HashSet<(char, char)> {
+        let chars: Vec<char> = s.chars().collect();
+        chars.windows(2).map(|w| (w[0], w[1])).collect()
+    };
+    let (a, b) = (bigrams(a), bigrams(b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let common = a.intersection(&b).count();
+    (2 * common) as f32 / (a.len() + b.len()) as f32
+}
+
+// The id of the note an event replies to
+fn parent_id(event: &Event) -> Option<Id> {
+    match crate::nip10::replies_to(event) {
+        Some(EventReference::Id { id, .. }) => Some(id),
+        _ => None,
+    }
+}
+
+/// A post of ours from within the last `duplicate_post_window_hours` with the same (or
+/// nearly the same) content, replying to the same note. None if the check is off.
+pub fn find_duplicate(content: &str, in_reply_to: Option<Id>) -> Result<Option<Id>, Error> {
+    let hours = GLOBALS.storage.read_setting_duplicate_post_window_hours();
+    if hours == 0 {
+        return Ok(None);
+    }
+    let pubkey = match GLOBALS.identity.public_key() {
+        Some(pk) => pk,
+        None => return Ok(None),
+    };
+
+    let normalized = normalize(content);
+    if normalized.is_empty() {
+        return Ok(None);
+    }
+
+    let mut filter = Filter::new();
+    filter.add_author(&pubkey.into());
+    filter.add_event_kind(EventKind::TextNote);
+    filter.since = Some(Unixtime::now().unwrap() - Duration::from_secs(hours * 3600));
+
+    let events = GLOBALS.storage.find_events_by_filter(&filter, |event| {
+        parent_id(event) == in_reply_to
+            && similarity(&normalize(&event.content), &normalized) >= NEAR_DUPLICATE_SIMILARITY
+    })?;
+
+    Ok(events.first().map(|e| e.id))
+}
+
+/// Hold a post back until the user confirms it, as a pending item
+pub(crate) fn hold(post: HeldPost) {
+    let id = rand::random::<u64>();
+    let previous = post.previous;
+    GLOBALS.held_posts.insert(id, post);
+    GLOBALS
+        .pending
+        .insert(PendingItem::DuplicatePost { id, previous });
+}
+
+/// Take a held post back, dropping its pending item
+pub(crate) fn take(id: u64) -> Option<HeldPost> {
+    let (_, post) = GLOBALS.held_posts.remove(&id)?;
+    GLOBALS.pending.remove(&PendingItem::DuplicatePost {
+        id,
+        previous: post.previous,
+    });
+    Some(post)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_similarity() {
+        let a = normalize("GM  nostr!\n");
+        assert_eq!(a, "gm nostr!");
+        assert_eq!(similarity(&a, &normalize("gm Nostr!")), 1.0);
+        assert!(
+            similarity(
+                &normalize("Here is my long post about relays and outboxes."),
+                &normalize("Here is my long post about relays and outboxes")
+            ) >= NEAR_DUPLICATE_SIMILARITY
+        );
+        assert!(similarity(&normalize("gm"), &normalize("gn")) < NEAR_DUPLICATE_SIMILARITY);
+    }
+}
@@ -0,0 +1,351 @@
+use crate::comms::ToOverlordMessage;
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use nostr_types::{Event, Id, PublicKey, RelayUrl, Tag};
+
+/// DvmJob type, aliased to the latest version
+pub type DvmJob = crate::storage::types::DvmJob1;
+
+/// DvmJobStatus type, aliased to the latest version
+pub type DvmJobStatus = crate::storage::types::DvmJobStatus1;
+
+/// Asking for a summary of the input
+pub const SUMMARIZATION_KIND: u32 = 5001;
+
+/// Asking for the input in another language (the `language` param)
+pub const TRANSLATION_KIND: u32 = 5002;
+
+/// Asking for events to read: an algorithmic feed
+pub const CONTENT_DISCOVERY_KIND: u32 = 5300;
+
+/// Service providers tell us how a job is going in these
+pub const JOB_FEEDBACK_KIND: u32 = 7000;
+
+/// The jobs we know how to ask for, and what to call them
+pub const JOB_KINDS: &[(u32, &str)] = &[
+    (CONTENT_DISCOVERY_KIND, "Content discovery"),
+    (TRANSLATION_KIND, "Translation"),
+    (SUMMARIZATION_KIND, "Summarization"),
+];
+
+/// What to call a kind of job
+pub fn job_kind_name(kind: u32) -> String {
+    match JOB_KINDS.iter().find(|(k, _)| *k == kind) {
+        Some((_, name)) => (*name).to_owned(),
+        None => format!("Job {}", kind),
+    }
+}
+
+/// Is this a job request kind (5000-5999)?
+pub fn is_job_request_kind(kind: u32) -> bool {
+    (5000..=5999).contains(&kind)
+}
+
+/// Is this a job result kind (6000-6999)? Results are 1000 above their request.
+pub fn is_job_result_kind(kind: u32) -> bool {
+    (6000..=6999).contains(&kind)
+}
+
+/// The tags of a job request. An empty input is left out (content discovery needs
+/// none). Service providers answer on the relays named here.
+pub fn job_request_tags(
+    input: &str,
+    input_type: &str,
+    params: &[(String, String)],
+    provider: Option<PublicKey>,
+    relays: &[RelayUrl],
+) -> Vec<Tag> {
+    let mut tags: Vec<Tag> = Vec::new();
+    if !input.is_empty() {
+        tags.push(Tag::new(&["i", input, input_type]));
+    }
+    for (name, value) in params.iter() {
+        tags.push(Tag::new(&["param", name, value]));
+    }
+    if !relays.is_empty() {
+        let mut relays_tag: Vec<&str> = vec!["relays"];
+        relays_tag.extend(relays.iter().map(|r| r.as_str()));
+        tags.push(Tag::new(&relays_tag));
+    }
+    if let Some(pubkey) = provider {
+        tags.push(Tag::new(&["p", &pubkey.as_hex_string()]));
+    }
+    tags
+}
+
+// The job request a feedback or result event answers
+fn request_id_of(event: &Event) -> Option<Id> {
+    event
+        .tags
+        .iter()
+        .find_map(|t| t.parse_event().ok().map(|(id, _, _)| id))
+}
+
+// The 'amount' tag: millisats, and maybe a bolt11 invoice
+fn amount_of(event: &Event) -> Option<(u64, Option<String>)> {
+    let tag = event.tags.iter().find(|t| t.tagname() == "amount")?;
+    let msat = tag.value().parse::<u64>().ok()?;
+    let bolt11 = match tag.get_index(2) {
+        "" => None,
+        invoice => Some(invoice.to_owned()),
+    };
+    Some((msat, bolt11))
+}
+
+/// Apply a job feedback (kind 7000) or job result (kinds 6000-6999) event to the
+/// job it answers. Returns false if it does not answer this job.
+pub fn apply_job_event(job: &mut DvmJob, event: &Event) -> bool {
+    if request_id_of(event) != Some(job.request_id) {
+        return false;
+    }
+    if let Some(provider) = job.provider {
+        if event.pubkey != provider {
+            return false;
+        }
+    }
+
+    let kind = u32::from(event.kind);
+    if kind == JOB_FEEDBACK_KIND {
+        // A result stands, whatever comes after it
+        if job.status == DvmJobStatus::Success || event.created_at < job.status_at {
+            return false;
+        }
+        let status_tag = match event.tags.iter().find(|t| t.tagname() == "status") {
+            Some(tag) => tag,
+            None => return false,
+        };
+        job.status = match status_tag.value() {
+            "processing" => DvmJobStatus::Processing,
+            "payment-required" => DvmJobStatus::PaymentRequired,
+            "error" => DvmJobStatus::Error,
+            "partial" => DvmJobStatus::Partial,
+            "success" => DvmJobStatus::Success,
+            _ => return false,
+        };
+        job.status_info = match status_tag.get_index(2) {
+            "" => event.content.clone(),
+            info => info.to_owned(),
+        };
+        if job.status == DvmJobStatus::Partial && !event.content.is_empty() {
+            job.result = event.content.clone();
+        }
+    } else if kind == job.kind + 1000 {
+        job.status = DvmJobStatus::Success;
+        job.status_info = String::new();
+        job.result_id = Some(event.id);
+        job.result_from = Some(event.pubkey);
+        job.result = event.content.clone();
+    } else {
+        return false;
+    }
+
+    job.status_from = Some(event.pubkey);
+    job.status_at = event.created_at;
+    if let Some((msat, bolt11)) = amount_of(event) {
+        if !job.paid {
+            job.amount_msat = Some(msat);
+            job.bolt11 = bolt11;
+            job.invoice_from = Some(event.pubkey);
+        }
+    }
+
+    // Whoever we would pay, or whose result we took, is doing the job, so nobody
+    // else can swap in an invoice or a result of theirs
+    if job.invoice_from == Some(event.pubkey) || job.result_from == Some(event.pubkey) {
+        job.provider = Some(event.pubkey);
+    }
+    true
+}
+
+// Process calls this for job feedback and job results
+pub(crate) fn process_job_event(event: &Event) -> Result<(), Error> {
+    let request_id = match request_id_of(event) {
+        Some(id) => id,
+        None => return Ok(()),
+    };
+
+    let mut discovered = false;
+    GLOBALS.storage.modify_dvm_job(
+        request_id,
+        |job| {
+            if apply_job_event(job, event) && job.result_id == Some(event.id) {
+                discovered = job.kind == CONTENT_DISCOVERY_KIND;
+            }
+        },
+        None,
+    )?;
+
+    // Go get the events a content discovery job found for us
+    if discovered {
+        for (id, relays) in result_events(&event.content) {
+            if !GLOBALS.storage.has_event(id)? {
+                let _ = GLOBALS
+                    .to_overlord
+                    .send(ToOverlordMessage::FetchEvent(id, relays));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The events a content discovery result lists, in its order, with relay hints.
+/// The result is a JSON array of tags.
+pub fn result_events(content: &str) -> Vec<(Id, Vec<RelayUrl>)> {
+    let tags: Vec<Vec<String>> = match serde_json::from_str(content) {
+        Ok(tags) => tags,
+        Err(_) => return vec![],
+    };
+    tags.iter()
+        .filter(|t| t.len() >= 2 && t[0] == "e")
+        .filter_map(|t| {
+            let id = Id::try_from_hex_string(&t[1]).ok()?;
+            let relays = t
+                .get(2)
+                .and_then(|r| RelayUrl::try_from_str(r).ok())
+                .into_iter()
+                .collect();
+            Some((id, relays))
+        })
+        .collect()
+}
+
+/// The events a content discovery job found that we have, in the order the
+/// service provider gave them. This is the feed of the job.
+pub fn result_feed(request_id: Id) -> Result<Vec<Id>, Error> {
+    let job = match GLOBALS.storage.read_dvm_job(request_id)? {
+        Some(job) => job,
+        None => return Ok(vec![]),
+    };
+    let mut ids: Vec<Id> = Vec::new();
+    for (id, _) in result_events(&job.result) {
+        if !ids.contains(&id) && GLOBALS.storage.has_event(id)? {
+            ids.push(id);
+        }
+    }
+    Ok(ids)
+}
+
+/// All the jobs we asked for, newest first
+pub fn jobs() -> Result<Vec<DvmJob>, Error> {
+    let mut jobs = GLOBALS.storage.read_all_dvm_jobs()?;
+    jobs.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    Ok(jobs)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nostr_types::{EventKind, PreEvent, PrivateKey, Unixtime};
+
+    fn make_event(key: &PrivateKey, kind: u32, tags: Vec<Tag>, content: &str) -> Event {
+        let pre_event = PreEvent {
+            pubkey: key.public_key(),
+            created_at: Unixtime::now().unwrap(),
+            kind: EventKind::from(kind),
+            tags,
+            content: content.to_owned(),
+        };
+        let id = pre_event.hash().unwrap();
+        Event {
+            id,
+            pubkey: pre_event.pubkey,
+            created_at: pre_event.created_at,
+            kind: pre_event.kind,
+            sig: key.sign_id(id).unwrap(),
+            content: pre_event.content,
+            tags: pre_event.tags,
+        }
+    }
+
+    #[test]
+    fn test_apply_job_event() {
+        let me = PrivateKey::generate();
+        let provider = PrivateKey::generate();
+        let request = make_event(&me, CONTENT_DISCOVERY_KIND, vec![], "");
+        let mut job = DvmJob::new(request.id, CONTENT_DISCOVERY_KIND, request.created_at);
+        let e_tag = Tag::new(&["e", &request.id.as_hex_string()]);
+
+        let feedback = make_event(
+            &provider,
+            JOB_FEEDBACK_KIND,
+            vec![
+                e_tag.clone(),
+                Tag::new(&["status", "payment-required", "Pay up"]),
+                Tag::new(&["amount", "21000", "lnbc210n1example"]),
+            ],
+            "",
+        );
+        assert!(apply_job_event(&mut job, &feedback));
+        assert_eq!(job.status, DvmJobStatus::PaymentRequired);
+        assert_eq!(job.status_info, "Pay up");
+        assert_eq!(job.amount_msat, Some(21000));
+        assert_eq!(job.bolt11.as_deref(), Some("lnbc210n1example"));
+        assert_eq!(job.invoice_from, Some(provider.public_key()));
+
+        assert_eq!(job.provider, Some(provider.public_key()));
+
+        // The job is bound to that service provider, so another cannot replace the
+        // invoice or give the result
+        let other_provider = PrivateKey::generate();
+        let feedback = make_event(
+            &other_provider,
+            JOB_FEEDBACK_KIND,
+            vec![
+                e_tag.clone(),
+                Tag::new(&["status", "payment-required"]),
+                Tag::new(&["amount", "1000", "lnbc10n1other"]),
+            ],
+            "",
+        );
+        assert!(!apply_job_event(&mut job, &feedback));
+        assert_eq!(job.amount_msat, Some(21000));
+        assert_eq!(job.bolt11.as_deref(), Some("lnbc210n1example"));
+        let other_result = make_event(
+            &other_provider,
+            CONTENT_DISCOVERY_KIND + 1000,
+            vec![e_tag.clone()],
+            "[]",
+        );
+        assert!(!apply_job_event(&mut job, &other_result));
+        assert_eq!(job.status, DvmJobStatus::PaymentRequired);
+        assert_eq!(job.result_id, None);
+
+        let found = PrivateKey::generate().public_key();
+        let found_id = make_event(&me, 1, vec![], "hello").id;
+        let content = format!(
+            "[[\"e\",\"{}\",\"wss://relay.example.com\"],[\"p\",\"{}\"]]",
+            found_id.as_hex_string(),
+            found.as_hex_string()
+        );
+        let result = make_event(
+            &provider,
+            CONTENT_DISCOVERY_KIND + 1000,
+            vec![e_tag],
+            &content,
+        );
+        assert!(apply_job_event(&mut job, &result));
+        assert_eq!(job.status, DvmJobStatus::Success);
+        assert_eq!(job.result_id, Some(result.id));
+        assert_eq!(
+            result_events(&job.result),
+            vec![(
+                found_id,
+                vec![RelayUrl::try_from_str("wss://relay.example.com").unwrap()]
+            )]
+        );
+
+        // Events that answer some other job are left alone
+        let other = make_event(
+            &provider,
+            JOB_FEEDBACK_KIND,
+            vec![
+                Tag::new(&["e", &found_id.as_hex_string()]),
+                Tag::new(&["status", "error"]),
+            ],
+            "",
+        );
+        assert!(!apply_job_event(&mut job, &other));
+        assert_eq!(job.status, DvmJobStatus::Success);
+    }
+}
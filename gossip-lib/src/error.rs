@@ -5,7 +5,9 @@ use nostr_types::RelayUrl;
 /// Error kinds that can occur in gossip-lib
 #[derive(Debug)]
 pub enum ErrorKind {
+    BadBunkerString,
     BadNostrConnectString,
+    BadWalletConnectString,
     BroadcastSend(String),
     BroadcastReceive(tokio::sync::broadcast::error::RecvError),
     CannotUpdateRelayUrl,
@@ -22,6 +24,7 @@ pub enum ErrorKind {
     MaxRelaysReached,
     MpscSend(tokio::sync::mpsc::error::SendError<ToOverlordMessage>),
     Nip05KeyNotFound,
+    Nip44(nip44::Error),
     Nip46CommandMissingId,
     Nip46CommandNotJsonObject,
     Nip46Denied,
@@ -48,6 +51,7 @@ pub enum ErrorKind {
     ListAlreadyExists(PersonList),
     ListEventMissingDtag,
     ListIsNotEmpty,
+    ListIsSmart,
     ListIsWellKnown,
     ListNotFound,
     NostrConnectNotSetup,
@@ -57,6 +61,9 @@ pub enum ErrorKind {
     Regex(regex::Error),
     RelayPickerError(gossip_relay_picker::Error),
     RelayRejectedUs,
+    RemoteSigner(String),
+    RemoteSignerNotSetup,
+    RemoteSignerTimeout(String),
     ReqwestHttpError(reqwest::Error),
     SerdeJson(serde_json::Error),
     SliceError(std::array::TryFromSliceError),
@@ -70,6 +77,9 @@ pub enum ErrorKind {
     UrlParse(url::ParseError),
     Usage(String, String), // error, usage line
     Utf8Error(std::str::Utf8Error),
+    Wallet(String),
+    WalletNotSetup,
+    WalletTimeout(String),
     Websocket(tungstenite::Error),
     WrongEventKind,
 }
@@ -93,7 +103,9 @@ impl std::fmt::Display for Error {
             write!(f, "{line}:")?;
         }
         match &self.kind {
+            BadBunkerString => write!(f, "Bad bunker string"),
             BadNostrConnectString => write!(f, "Bad nostrconnect string"),
+            BadWalletConnectString => write!(f, "Bad nostr+walletconnect string"),
             BroadcastSend(s) => write!(f, "Error broadcasting: {s}"),
             BroadcastReceive(e) => write!(f, "Error receiving broadcast: {e}"),
             CannotUpdateRelayUrl => {
@@ -115,6 +127,7 @@ impl std::fmt::Display for Error {
             ),
             MpscSend(e) => write!(f, "Error sending mpsc: {e}"),
             Nip05KeyNotFound => write!(f, "NIP-05 public key not found"),
+            Nip44(e) => write!(f, "NIP-44: {e}"),
             Nip46CommandMissingId => write!(f, "NIP-46 command missing ID"),
             Nip46CommandNotJsonObject => write!(f, "NIP-46 command not a json object"),
             Nip46Denied => write!(f, "NIP-46 command denied"),
@@ -143,6 +156,7 @@ impl std::fmt::Display for Error {
             ListAlreadyExists(_) => write!(f, "List already exists"),
             ListEventMissingDtag => write!(f, "List event missing d-tag"),
             ListIsNotEmpty => write!(f, "List is not empty"),
+            ListIsSmart => write!(f, "List is a smart list and is never published"),
             ListIsWellKnown => write!(f, "List is well known and cannot be deallocated"),
             ListNotFound => write!(f, "List was not found"),
             NostrConnectNotSetup => write!(f, "NostrConnect not setup, cannot connect"),
@@ -152,6 +166,9 @@ impl std::fmt::Display for Error {
             Regex(e) => write!(f, "Regex: {e}"),
             RelayPickerError(e) => write!(f, "Relay Picker error: {e}"),
             RelayRejectedUs => write!(f, "Relay rejected us."),
+            RemoteSigner(s) => write!(f, "Remote signer: {s}"),
+            RemoteSignerNotSetup => write!(f, "No remote signer is set up."),
+            RemoteSignerTimeout(m) => write!(f, "Remote signer did not answer {m} in time."),
             ReqwestHttpError(e) => write!(f, "HTTP (reqwest) error: {e}"),
             SerdeJson(e) => write!(f, "SerdeJson Error: {e}"),
             SliceError(e) => write!(f, "Slice: {e}"),
@@ -165,6 +182,9 @@ impl std::fmt::Display for Error {
             UrlParse(e) => write!(f, "URL parse: {e}"),
             Usage(e, u) => write!(f, "{}\n\nUsage: {}", e, u),
             Utf8Error(e) => write!(f, "UTF-8 error: {e}"),
+            Wallet(s) => write!(f, "Wallet: {s}"),
+            WalletNotSetup => write!(f, "No wallet is connected."),
+            WalletTimeout(m) => write!(f, "Wallet did not answer {m} in time."),
             Websocket(e) => write!(f, "Websocket: {e}"),
             WrongEventKind => write!(f, "Wrong event kind"),
         }
@@ -239,6 +259,12 @@ impl From<tokio::sync::mpsc::error::SendError<ToOverlordMessage>> for ErrorKind
     }
 }
 
+impl From<nip44::Error> for ErrorKind {
+    fn from(e: nip44::Error) -> ErrorKind {
+        ErrorKind::Nip44(e)
+    }
+}
+
 impl From<nostr_types::Error> for ErrorKind {
     fn from(e: nostr_types::Error) -> ErrorKind {
         ErrorKind::Nostr(e)
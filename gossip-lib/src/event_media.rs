@@ -0,0 +1,349 @@
+use nostr_types::{ContentSegment, Event, Id, ShatteredContent, Tag, Unixtime};
+use speedy::{Readable, Writable};
+
+/// The kind of media a URL points at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Readable, Writable)]
+pub enum MediaType {
+    Image,
+    Video,
+    Audio,
+}
+
+impl MediaType {
+    /// Guess the media type from the URL's file extension
+    pub fn of_url(url: &str) -> Option<MediaType> {
+        let path = match url::Url::parse(url) {
+            Ok(url) => url.path().to_lowercase(),
+            Err(_) => return None,
+        };
+        let ext = path.rsplit_once('.').map(|(_, ext)| ext)?;
+        match ext {
+            "jpg" | "jpeg" | "png" | "gif" | "webp" | "avif" => Some(MediaType::Image),
+            "mov" | "mp4" | "mkv" | "webm" | "m4v" => Some(MediaType::Video),
+            "mp3" | "ogg" | "oga" | "wav" | "flac" | "m4a" | "opus" => Some(MediaType::Audio),
+            _ => None,
+        }
+    }
+
+    /// The media type of a MIME type
+    pub fn of_mime(mime: &str) -> Option<MediaType> {
+        match mime.split('/').next() {
+            Some("image") => Some(MediaType::Image),
+            Some("video") => Some(MediaType::Video),
+            Some("audio") => Some(MediaType::Audio),
+            _ => None,
+        }
+    }
+}
+
+/// A media file an event shows
+#[derive(Debug, Clone, PartialEq, Eq, Readable, Writable)]
+pub struct MediaAttachment {
+    pub url: String,
+    pub media_type: MediaType,
+
+    /// The MIME type, if the event gave one (NIP-92 `imeta`)
+    pub mime: Option<String>,
+
+    /// The SHA-256 of the file in hex, if the event gave one
+    pub hash: Option<String>,
+
+    /// Width and height in pixels, if the event gave them
+    pub dim: Option<(u32, u32)>,
+
+    /// A description of the media for people who can't see it, if the event gave one
+    pub alt: Option<String>,
+}
+
+impl MediaAttachment {
+    /// If this is media that can't be understood without seeing or hearing it and
+    /// it has no alt text
+    pub fn missing_alt(&self) -> bool {
+        self.alt
+            .as_deref()
+            .map(|a| a.trim().is_empty())
+            .unwrap_or(true)
+    }
+}
+
+/// The media and links in an event's content, worked out when the event is stored
+#[derive(Debug, Clone, Default, PartialEq, Eq, Readable, Writable)]
+pub struct EventMedia {
+    pub attachments: Vec<MediaAttachment>,
+
+    /// How many links in the content are not media
+    pub links: u32,
+}
+
+impl EventMedia {
+    pub fn of_event(event: &Event) -> EventMedia {
+        let mut media = EventMedia::default();
+
+        // NIP-92 imeta tags, which know more than the URL does
+        for tag in event.tags.iter().filter(|t| t.tagname() == "imeta") {
+            if let Some(attachment) = attachment_from_imeta(tag) {
+                media.attachments.push(attachment);
+            }
+        }
+
+        let shattered = ShatteredContent::new(event.content.clone());
+        for segment in shattered.segments.iter() {
+            if let ContentSegment::Hyperlink(span) = segment {
+                let url = match shattered.slice(span) {
+                    Some(url) => url,
+                    None => continue,
+                };
+                if media.attachments.iter().any(|a| a.url == url) {
+                    continue;
+                }
+                match MediaType::of_url(url) {
+                    Some(media_type) => media.attachments.push(MediaAttachment {
+                        url: url.to_owned(),
+                        media_type,
+                        mime: None,
+                        hash: None,
+                        dim: None,
+                        alt: None,
+                    }),
+                    None => media.links += 1,
+                }
+            }
+        }
+
+        media
+    }
+
+    /// If there is nothing but text
+    pub fn is_empty(&self) -> bool {
+        self.attachments.is_empty() && self.links == 0
+    }
+
+    pub fn has(&self, media_type: MediaType) -> bool {
+        self.attachments.iter().any(|a| a.media_type == media_type)
+    }
+
+    /// The attachments that have no alt text
+    pub fn missing_alt(&self) -> Vec<&MediaAttachment> {
+        self.attachments
+            .iter()
+            .filter(|a| a.missing_alt())
+            .collect()
+    }
+}
+
+/// The alt text an event gives for one of its media URLs
+pub fn alt_text_of(event: &Event, url: &str) -> Option<String> {
+    event
+        .tags
+        .iter()
+        .filter(|t| t.tagname() == "imeta")
+        .filter_map(attachment_from_imeta)
+        .find(|a| a.url == url && !a.missing_alt())
+        .and_then(|a| a.alt)
+}
+
+/// The media URLs in the content of a post being written, in order, without duplicates
+pub fn media_urls_in(content: &str) -> Vec<(String, MediaType)> {
+    let mut output: Vec<(String, MediaType)> = Vec::new();
+    let shattered = ShatteredContent::new(content.to_owned());
+    for segment in shattered.segments.iter() {
+        if let ContentSegment::Hyperlink(span) = segment {
+            let url = match shattered.slice(span) {
+                Some(url) => url,
+                None => continue,
+            };
+            if let Some(media_type) = MediaType::of_url(url) {
+                if !output.iter().any(|(u, _)| u == url) {
+                    output.push((url.to_owned(), media_type));
+                }
+            }
+        }
+    }
+    output
+}
+
+/// An `imeta` tag (NIP-92) giving the alt text of a media URL in a post
+pub fn imeta_alt_tag(url: &str, alt: &str) -> Tag {
+    let url = format!("url {}", url);
+    let alt = format!("alt {}", alt.trim());
+    Tag::new(&["imeta", &url, &alt])
+}
+
+/// The media URLs in a post's content that none of its `imeta` tags give alt text for
+pub fn media_missing_alt(content: &str, tags: &[Tag]) -> Vec<String> {
+    let described: Vec<MediaAttachment> = tags
+        .iter()
+        .filter(|t| t.tagname() == "imeta")
+        .filter_map(attachment_from_imeta)
+        .filter(|a| !a.missing_alt())
+        .collect();
+    media_urls_in(content)
+        .drain(..)
+        .map(|(url, _)| url)
+        .filter(|url| !described.iter().any(|a| &a.url == url))
+        .collect()
+}
+
+// An imeta tag is ["imeta", "url https://...", "m image/jpeg", "x <sha256>", "dim 640x480", ...]
+fn attachment_from_imeta(tag: &nostr_types::Tag) -> Option<MediaAttachment> {
+    let mut url: Option<String> = None;
+    let mut mime: Option<String> = None;
+    let mut hash: Option<String> = None;
+    let mut dim: Option<(u32, u32)> = None;
+    let mut alt: Option<String> = None;
+    let mut index = 1;
+    loop {
+        let field = tag.get_index(index);
+        if field.is_empty() {
+            break;
+        }
+        index += 1;
+        let (name, value) = match field.split_once(' ') {
+            Some(pair) => pair,
+            None => continue,
+        };
+        match name {
+            "url" => url = Some(value.to_owned()),
+            "m" => mime = Some(value.to_lowercase()),
+            "x" => hash = Some(value.to_lowercase()),
+            "dim" => {
+                dim = value
+                    .split_once('x')
+                    .and_then(|(w, h)| Some((w.parse::<u32>().ok()?, h.parse::<u32>().ok()?)))
+            }
+            "alt" => alt = Some(value.to_owned()),
+            _ => (),
+        }
+    }
+
+    let url = url?;
+    let media_type = mime
+        .as_deref()
+        .and_then(MediaType::of_mime)
+        .or_else(|| MediaType::of_url(&url))?;
+    Some(MediaAttachment {
+        url,
+        media_type,
+        mime,
+        hash,
+        dim,
+        alt,
+    })
+}
+
+/// A media file of an event, for media galleries
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MediaGalleryItem {
+    pub id: Id,
+    pub created_at: Unixtime,
+    pub attachment: MediaAttachment,
+}
+
+/// Which events a feed shows, by what their content holds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MediaFilter {
+    #[default]
+    All,
+    HasImage,
+    HasVideo,
+
+    /// Links, but no media
+    LinkOnly,
+
+    /// No links or media at all
+    TextOnly,
+}
+
+impl MediaFilter {
+    pub const ALL: [MediaFilter; 5] = [
+        MediaFilter::All,
+        MediaFilter::HasImage,
+        MediaFilter::HasVideo,
+        MediaFilter::LinkOnly,
+        MediaFilter::TextOnly,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MediaFilter::All => "All",
+            MediaFilter::HasImage => "Has image",
+            MediaFilter::HasVideo => "Has video",
+            MediaFilter::LinkOnly => "Links only",
+            MediaFilter::TextOnly => "Text only",
+        }
+    }
+
+    /// Events with no media record are text only
+    pub fn matches(&self, media: Option<&EventMedia>) -> bool {
+        match (self, media) {
+            (MediaFilter::All, _) => true,
+            (MediaFilter::HasImage, Some(m)) => m.has(MediaType::Image),
+            (MediaFilter::HasVideo, Some(m)) => m.has(MediaType::Video),
+            (MediaFilter::LinkOnly, Some(m)) => m.attachments.is_empty() && m.links > 0,
+            (MediaFilter::TextOnly, m) => m.map(|m| m.is_empty()).unwrap_or(true),
+            (_, None) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_media_type_of_url() {
+        assert_eq!(
+            MediaType::of_url("https://example.com/a/B.JPG?x=1"),
+            Some(MediaType::Image)
+        );
+        assert_eq!(
+            MediaType::of_url("https://example.com/clip.mp4"),
+            Some(MediaType::Video)
+        );
+        assert_eq!(MediaType::of_url("https://example.com/page.html"), None);
+        assert_eq!(MediaType::of_url("https://example.com/"), None);
+    }
+
+    #[test]
+    fn test_media_filter() {
+        let image = EventMedia {
+            attachments: vec![MediaAttachment {
+                url: "https://example.com/a.png".to_owned(),
+                media_type: MediaType::Image,
+                mime: None,
+                hash: None,
+                dim: None,
+                alt: None,
+            }],
+            links: 1,
+        };
+        let links = EventMedia {
+            attachments: vec![],
+            links: 2,
+        };
+        assert!(MediaFilter::HasImage.matches(Some(&image)));
+        assert!(!MediaFilter::HasVideo.matches(Some(&image)));
+        assert!(!MediaFilter::LinkOnly.matches(Some(&image)));
+        assert!(MediaFilter::LinkOnly.matches(Some(&links)));
+        assert!(!MediaFilter::TextOnly.matches(Some(&links)));
+        assert!(MediaFilter::TextOnly.matches(None));
+        assert!(!MediaFilter::HasImage.matches(None));
+    }
+
+    #[test]
+    fn test_alt_text() {
+        let content =
+            "look https://example.com/a.png and https://example.com/b.mp4 https://example.com/";
+        assert_eq!(media_urls_in(content).len(), 2);
+
+        let tags = vec![imeta_alt_tag("https://example.com/a.png", " A cat ")];
+        assert_eq!(
+            media_missing_alt(content, &tags),
+            vec!["https://example.com/b.mp4".to_owned()]
+        );
+
+        let attachment = attachment_from_imeta(&tags[0]).unwrap();
+        assert_eq!(attachment.alt.as_deref(), Some("A cat"));
+        assert!(!attachment.missing_alt());
+    }
+}
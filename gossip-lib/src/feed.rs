@@ -1,10 +1,15 @@
+use crate::bridge::is_bridged;
 use crate::comms::{ToMinionMessage, ToMinionPayload, ToMinionPayloadDetail, ToOverlordMessage};
 use crate::dm_channel::DmChannel;
 use crate::error::Error;
+use crate::event_media::MediaFilter;
 use crate::globals::GLOBALS;
 use crate::people::PersonList;
+use crate::relationship::RelationshipById;
+use crate::storage::EventView;
 use nostr_types::{
-    Event, EventKind, EventReference, Filter, Id, PublicKey, PublicKeyHex, Unixtime,
+    Event, EventAddr, EventKind, EventReference, Filter, Id, PublicKey, PublicKeyHex, RelayUrl,
+    Unixtime,
 };
 use parking_lot::RwLock;
 use std::collections::HashSet;
@@ -24,6 +29,10 @@ pub enum FeedKind {
     },
     Person(PublicKey),
     DmChat(DmChannel),
+    Starred,                      // local stars and pins
+    Group(RelayUrl, String),      // relay-based group (NIP-29)
+    Community(PublicKey, String), // moderated community (NIP-72), by author and d
+    Dvm(Id),                      // results of a content discovery job (NIP-90)
 }
 
 impl std::fmt::Display for FeedKind {
@@ -41,6 +50,19 @@ impl std::fmt::Display for FeedKind {
                 author: _,
             } => write!(f, "Thread {}", crate::names::hex_id_short(&(*id).into())),
             FeedKind::Person(pk) => write!(f, "{}", crate::names::best_name_from_pubkey_lookup(pk)),
+            FeedKind::Starred => write!(f, "Starred"),
+            FeedKind::Group(relay, id) => match GLOBALS.storage.read_group(relay, id) {
+                Ok(Some(group)) => write!(f, "{}", group.display_name()),
+                _ => write!(f, "{}", id),
+            },
+            FeedKind::Community(author, d) => match GLOBALS.storage.read_community(author, d) {
+                Ok(Some(community)) => write!(f, "{}", community.display_name()),
+                _ => write!(f, "{}", d),
+            },
+            FeedKind::Dvm(id) => match GLOBALS.storage.read_dvm_job(*id) {
+                Ok(Some(job)) => write!(f, "{}", crate::dvm::job_kind_name(job.kind)),
+                _ => write!(f, "Job {}", crate::names::hex_id_short(&(*id).into())),
+            },
         }
     }
 }
@@ -52,11 +74,28 @@ impl FeedKind {
             Self::Inbox(_) => true,
             Self::Thread { .. } => false, // always full
             Self::Person(_) => true,
-            Self::DmChat(_) => false, // always full
+            Self::DmChat(_) => false,     // always full
+            Self::Starred => false,       // always full
+            Self::Group(..) => false,     // always full
+            Self::Community(..) => false, // always full
+            Self::Dvm(_) => false,        // always full
         }
     }
 }
 
+/// Where an event in a feed came from
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FeedSource {
+    /// The author is in this person list
+    List(PersonList),
+
+    /// It was reposted by this person
+    RepostedBy(PublicKey),
+
+    /// It was addressed to you
+    Inbox,
+}
+
 /// The system that computes feeds as an ordered list of event Ids.
 pub struct Feed {
     /// Consumers of gossip-lib should only read this, not write to it.
@@ -65,10 +104,17 @@ pub struct Feed {
 
     current_feed_kind: RwLock<FeedKind>,
 
+    // Applies to the list and person feeds
+    media_filter: RwLock<MediaFilter>,
+
     followed_feed: RwLock<Vec<Id>>,
     inbox_feed: RwLock<Vec<Id>>,
     person_feed: RwLock<Vec<Id>>,
     dm_chat_feed: RwLock<Vec<Id>>,
+    starred_feed: RwLock<Vec<Id>>,
+    group_feed: RwLock<Vec<Id>>,
+    community_feed: RwLock<Vec<Id>>,
+    dvm_feed: RwLock<Vec<Id>>,
 
     // When feeds start
     general_feed_start: RwLock<Unixtime>,
@@ -93,10 +139,15 @@ impl Feed {
         Feed {
             recompute_lock: AtomicBool::new(false),
             current_feed_kind: RwLock::new(FeedKind::List(PersonList::Followed, false)),
+            media_filter: RwLock::new(MediaFilter::All),
             followed_feed: RwLock::new(Vec::new()),
             inbox_feed: RwLock::new(Vec::new()),
             person_feed: RwLock::new(Vec::new()),
             dm_chat_feed: RwLock::new(Vec::new()),
+            starred_feed: RwLock::new(Vec::new()),
+            group_feed: RwLock::new(Vec::new()),
+            community_feed: RwLock::new(Vec::new()),
+            dvm_feed: RwLock::new(Vec::new()),
             general_feed_start: RwLock::new(Unixtime::now().unwrap()),
             person_feed_start: RwLock::new(Unixtime::now().unwrap()),
             inbox_feed_start: RwLock::new(Unixtime::now().unwrap()),
@@ -118,6 +169,26 @@ impl Feed {
         *self.inbox_feed_start.write() = inbox_feed_start;
     }
 
+    /// Empty every feed, as when switching profiles. The feed starts are set again
+    /// as at startup.
+    pub(crate) fn clear(&self) {
+        for feed in [
+            &self.followed_feed,
+            &self.inbox_feed,
+            &self.person_feed,
+            &self.dm_chat_feed,
+            &self.starred_feed,
+            &self.group_feed,
+            &self.community_feed,
+            &self.dvm_feed,
+        ] {
+            feed.write().clear();
+        }
+        *self.thread_parent.write() = None;
+        *self.last_computed.write() = None;
+        *self.current_feed_kind.write() = FeedKind::List(PersonList::Followed, false);
+    }
+
     /// This only looks further back in stored events, it doesn't deal with minion subscriptions.
     pub(crate) fn load_more_general_feed(&self) -> Unixtime {
         let mut start = *self.general_feed_start.read();
@@ -168,6 +239,26 @@ impl Feed {
                 },
             });
         }
+
+        // If not in a Group feed
+        if !matches!(feed_kind, FeedKind::Group(..)) {
+            // Stop listening to Group events, and let go of the group's relay
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::UnsubscribeGroup);
+        }
+
+        // If not in a Community feed
+        if !matches!(feed_kind, FeedKind::Community(..)) {
+            // Stop listening to Community events
+            let _ = GLOBALS.to_minions.send(ToMinionMessage {
+                target: "all".to_string(),
+                payload: ToMinionPayload {
+                    job_id: 0,
+                    detail: ToMinionPayloadDetail::UnsubscribeCommunity,
+                },
+            });
+        }
     }
 
     /// Change the feed to the main feed
@@ -178,6 +269,9 @@ impl Feed {
         *self.current_feed_kind.write() = FeedKind::List(list, with_replies);
         *self.thread_parent.write() = None;
 
+        // Lists that are not live get fetched as they are opened
+        crate::list_refresh::on_feed_opened(list);
+
         // Recompute as they switch
         self.sync_recompute();
 
@@ -252,11 +346,82 @@ impl Feed {
             .send(ToOverlordMessage::SetDmChannel(channel));
     }
 
+    /// Change the feed to the locally starred events
+    pub fn set_feed_to_starred(&self) {
+        *self.current_feed_kind.write() = FeedKind::Starred;
+        *self.thread_parent.write() = None;
+
+        // Recompute as they switch
+        self.sync_recompute();
+
+        self.unlisten();
+    }
+
+    /// Change the feed to a relay-based group (NIP-29)
+    pub fn set_feed_to_group(&self, relay: RelayUrl, id: String) {
+        *self.current_feed_kind.write() = FeedKind::Group(relay.clone(), id.clone());
+        *self.thread_parent.write() = None;
+
+        // Recompute as they switch
+        self.sync_recompute();
+
+        self.unlisten();
+
+        // Listen for Group events
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::SetGroupFeed(relay, id));
+    }
+
+    /// Change the feed to a moderated community (NIP-72)
+    pub fn set_feed_to_community(&self, author: PublicKey, d: String) {
+        *self.current_feed_kind.write() = FeedKind::Community(author, d.clone());
+        *self.thread_parent.write() = None;
+
+        // Recompute as they switch
+        self.sync_recompute();
+
+        self.unlisten();
+
+        // Listen for Community events
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::SetCommunityFeed(EventAddr {
+                d,
+                relays: vec![],
+                kind: EventKind::CommunityDefinition,
+                author,
+            }));
+    }
+
+    /// Change the feed to the results of a content discovery job (NIP-90)
+    pub fn set_feed_to_dvm(&self, request_id: Id) {
+        *self.current_feed_kind.write() = FeedKind::Dvm(request_id);
+        *self.thread_parent.write() = None;
+
+        // Recompute as they switch
+        self.sync_recompute();
+
+        self.unlisten();
+    }
+
     /// Get the kind of the current feed
     pub fn get_feed_kind(&self) -> FeedKind {
         self.current_feed_kind.read().to_owned()
     }
 
+    /// Show only events with (or without) media in the list and person feeds
+    pub fn set_media_filter(&self, media_filter: MediaFilter) {
+        *self.media_filter.write() = media_filter;
+
+        // Recompute as they switch
+        self.sync_recompute();
+    }
+
+    pub fn get_media_filter(&self) -> MediaFilter {
+        *self.media_filter.read()
+    }
+
     /// Read the followed feed
     pub fn get_followed(&self) -> Vec<Id> {
         self.sync_maybe_periodic_recompute();
@@ -281,6 +446,31 @@ impl Feed {
         self.dm_chat_feed.read().clone()
     }
 
+    /// Read the starred feed (pinned events first)
+    pub fn get_starred_feed(&self) -> Vec<Id> {
+        self.sync_maybe_periodic_recompute();
+        self.starred_feed.read().clone()
+    }
+
+    /// Read the feed of the current group (NIP-29), newest first
+    pub fn get_group_feed(&self) -> Vec<Id> {
+        self.sync_maybe_periodic_recompute();
+        self.group_feed.read().clone()
+    }
+
+    /// Read the approved posts of the current community (NIP-72), newest first
+    pub fn get_community_feed(&self) -> Vec<Id> {
+        self.sync_maybe_periodic_recompute();
+        self.community_feed.read().clone()
+    }
+
+    /// Read the results of the current content discovery job (NIP-90), in the
+    /// order the service provider gave them
+    pub fn get_dvm_feed(&self) -> Vec<Id> {
+        self.sync_maybe_periodic_recompute();
+        self.dvm_feed.read().clone()
+    }
+
     /// Get the parent of the current thread feed.
     /// The children should be recursively found via `GLOBALS.storage.get_replies(id)`
     pub fn get_thread_parent(&self) -> Option<Id> {
@@ -349,6 +539,8 @@ impl Feed {
         // Filter further for the general feed
         let dismissed = GLOBALS.dismissed.read().await.clone();
         let now = Unixtime::now().unwrap();
+        let show_bridged = GLOBALS.storage.read_setting_feed_show_bridged();
+        let media_filter = *self.media_filter.read();
 
         let current_feed_kind = self.current_feed_kind.read().to_owned();
         match current_feed_kind {
@@ -365,7 +557,7 @@ impl Feed {
                 // FIXME we don't include delegated events. We should look for all events
                 // delegated to people we follow and include those in the feed too.
 
-                let events: Vec<Id> = if pubkeys.is_empty() {
+                let events: Vec<Event> = if pubkeys.is_empty() {
                     Default::default()
                 } else {
                     let mut filter = Filter::new();
@@ -373,25 +565,34 @@ impl Feed {
                     filter.kinds = kinds_without_dms;
                     filter.since = Some(since);
 
+                    // Most events are passed over without decoding them
+                    let prescreen = |v: &EventView<'_>| {
+                        let future = v.created_at().map(|t| t > now).unwrap_or(false);
+                        let dm = matches!(
+                            v.kind(),
+                            Some(EventKind::EncryptedDirectMessage | EventKind::DmChat)
+                        );
+                        let gone = v.id().map(|id| dismissed.contains(&id)).unwrap_or(false);
+                        !future && !dm && !gone
+                    };
+
                     GLOBALS
                         .storage
-                        .find_events_by_filter(&filter, |e| {
-                            e.created_at <= now // no future events
-                                    && e.kind != EventKind::EncryptedDirectMessage // no DMs
-                                    && e.kind != EventKind::DmChat // no DMs
-                                    && !dismissed.contains(&e.id) // not dismissed
-                                    && if !with_replies {
-                                        e.replies_to().is_none() // is not a reply
-                                    } else {
-                                        true
-                                    }
+                        .find_events_by_filter_prescreened(&filter, prescreen, |e| {
+                            (show_bridged || !is_bridged(e)) // not bridged
+                                && if !with_replies {
+                                    crate::nip10::replies_to(e).is_none() // is not a reply
+                                } else {
+                                    true
+                                }
                         })?
-                        .iter()
-                        .map(|e| e.id)
-                        .collect()
                 };
 
-                *self.followed_feed.write() = events;
+                let events = filter_by_media(events, media_filter)?;
+
+                let events = dedup_if_enabled(events);
+
+                *self.followed_feed.write() = events.iter().map(|e| e.id).collect();
             }
             FeedKind::Inbox(indirect) => {
                 if let Some(my_pubkey) = GLOBALS.identity.public_key() {
@@ -414,65 +615,67 @@ impl Feed {
 
                     let my_pubkeyhex: PublicKeyHex = my_pubkey.into();
 
-                    let inbox_events: Vec<Id> = GLOBALS
-                        .storage
-                        .find_tagged_events(
-                            "p",
-                            Some(my_pubkeyhex.as_str()),
-                            |e| {
-                                if e.created_at < since || e.created_at > now {
-                                    return false;
-                                }
-                                if !kinds_with_dms.contains(&e.kind) {
-                                    return false;
-                                }
-                                if dismissed.contains(&e.id) {
-                                    return false;
-                                }
-
-                                // exclude if it's my own note
-                                if e.pubkey == my_pubkey {
-                                    return false;
-                                }
-
-                                if e.kind == EventKind::GiftWrap
-                                    || e.kind == EventKind::EncryptedDirectMessage
-                                {
-                                    return true;
-                                }
-
-                                // Include if it directly replies to one of my events
-                                match e.replies_to() {
-                                    Some(EventReference::Id { id, .. }) => {
-                                        if my_event_ids.contains(&id) {
-                                            return true;
-                                        }
-                                    }
-                                    Some(EventReference::Addr(ea)) => {
-                                        if ea.author == my_pubkey {
-                                            return true;
-                                        }
+                    let inbox_events: Vec<Event> = GLOBALS.storage.find_tagged_events(
+                        "p",
+                        Some(my_pubkeyhex.as_str()),
+                        |e| {
+                            if e.created_at < since || e.created_at > now {
+                                return false;
+                            }
+                            if !kinds_with_dms.contains(&e.kind) {
+                                return false;
+                            }
+                            if dismissed.contains(&e.id) {
+                                return false;
+                            }
+                            if !show_bridged && is_bridged(e) {
+                                return false;
+                            }
+
+                            // exclude if it's my own note
+                            if e.pubkey == my_pubkey {
+                                return false;
+                            }
+
+                            if e.kind == EventKind::GiftWrap
+                                || e.kind == EventKind::EncryptedDirectMessage
+                            {
+                                // Message requests stay out of the inbox
+                                return crate::dm_policy::is_dm_event_accepted(e);
+                            }
+
+                            // Include if it directly replies to one of my events
+                            match crate::nip10::replies_to(e) {
+                                Some(EventReference::Id { id, .. }) => {
+                                    if my_event_ids.contains(&id) {
+                                        return true;
                                     }
-                                    None => (),
                                 }
-
-                                if indirect {
-                                    // Include if it tags me
-                                    e.people().iter().any(|(p, _, _)| *p == my_pubkey)
-                                } else {
-                                    // Include if it directly references me in the content
-                                    e.people_referenced_in_content()
-                                        .iter()
-                                        .any(|p| *p == my_pubkey)
+                                Some(EventReference::Addr(ea)) => {
+                                    if ea.author == my_pubkey {
+                                        return true;
+                                    }
                                 }
-                            },
-                            true,
-                        )?
+                                None => (),
+                            }
+
+                            if indirect {
+                                // Include if it tags me
+                                e.people().iter().any(|(p, _, _)| *p == my_pubkey)
+                            } else {
+                                // Include if it directly references me in the content
+                                e.people_referenced_in_content()
+                                    .iter()
+                                    .any(|p| *p == my_pubkey)
+                            }
+                        },
+                        true,
+                    )?;
+
+                    *self.inbox_feed.write() = dedup_if_enabled(inbox_events)
                         .iter()
                         .map(|e| e.id)
                         .collect();
-
-                    *self.inbox_feed.write() = inbox_events;
                 }
             }
             FeedKind::Thread { .. } => {
@@ -521,7 +724,10 @@ impl Feed {
 
                 events.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(b.id.cmp(&a.id)));
 
-                let events: Vec<Id> = events.iter().map(|e| e.id).collect();
+                let events: Vec<Id> = dedup_if_enabled(filter_by_media(events, media_filter)?)
+                    .iter()
+                    .map(|e| e.id)
+                    .collect();
 
                 *self.person_feed.write() = events;
             }
@@ -529,12 +735,134 @@ impl Feed {
                 let ids = GLOBALS.storage.dm_events(&channel)?;
                 *self.dm_chat_feed.write() = ids;
             }
+            FeedKind::Starred => {
+                // Starred events we don't have (yet) are left out
+                let mut ids: Vec<Id> = Vec::new();
+                for (id, _, _) in GLOBALS.storage.read_all_stars()?.drain(..) {
+                    if GLOBALS.storage.has_event(id)? {
+                        ids.push(id);
+                    }
+                }
+                *self.starred_feed.write() = ids;
+            }
+            FeedKind::Group(relay, id) => {
+                *self.group_feed.write() = crate::groups::group_feed(&relay, &id)?;
+            }
+            FeedKind::Community(author, d) => {
+                *self.community_feed.write() = match GLOBALS.storage.read_community(&author, &d)? {
+                    Some(community) => crate::communities::approved_posts(&community)?,
+                    None => vec![],
+                };
+            }
+            FeedKind::Dvm(request_id) => {
+                *self.dvm_feed.write() = crate::dvm::result_feed(request_id)?;
+            }
         }
 
         self.recompute_lock.store(false, Ordering::Relaxed);
 
         Ok(())
     }
+
+    /// Report the sources through which an event could have entered your feeds:
+    /// the person lists its author is in, who reposted it, and whether it was
+    /// addressed to you.
+    pub fn event_sources(&self, id: Id) -> Result<Vec<FeedSource>, Error> {
+        let mut sources: Vec<FeedSource> = Vec::new();
+
+        let event = match GLOBALS.storage.read_event(id)? {
+            Some(event) => event,
+            None => return Ok(sources),
+        };
+
+        let mut lists: Vec<PersonList> = GLOBALS
+            .storage
+            .read_person_lists(&event.pubkey)?
+            .keys()
+            .copied()
+            .collect();
+        lists.sort();
+        sources.extend(lists.drain(..).map(FeedSource::List));
+
+        for (repost_id, rel) in GLOBALS.storage.find_relationships_by_id(id)? {
+            if rel == RelationshipById::Reposts {
+                if let Some(repost) = GLOBALS.storage.read_event(repost_id)? {
+                    let source = FeedSource::RepostedBy(repost.pubkey);
+                    if !sources.contains(&source) {
+                        sources.push(source);
+                    }
+                }
+            }
+        }
+
+        if let Some(my_pubkey) = GLOBALS.identity.public_key() {
+            if event.pubkey != my_pubkey && event.people().iter().any(|(p, _, _)| *p == my_pubkey) {
+                sources.push(FeedSource::Inbox);
+            }
+        }
+
+        Ok(sources)
+    }
+}
+
+// The events deduplicated, if the feed_deduplicate setting is on
+fn dedup_if_enabled(events: Vec<Event>) -> Vec<Event> {
+    if GLOBALS.storage.read_setting_feed_deduplicate() {
+        dedup_events(events)
+    } else {
+        events
+    }
+}
+
+/// Remove reposts of events that are already in the feed (or that were already
+/// reposted by a newer repost in the feed). Order is preserved.
+fn dedup_events(events: Vec<Event>) -> Vec<Event> {
+    let mut seen: HashSet<Id> = events
+        .iter()
+        .filter(|e| e.kind != EventKind::Repost)
+        .map(|e| e.id)
+        .collect();
+
+    events
+        .into_iter()
+        .filter(|e| {
+            if e.kind != EventKind::Repost {
+                return true;
+            }
+            match reposted_id(e) {
+                Some(id) => seen.insert(id),
+                None => true,
+            }
+        })
+        .collect()
+}
+
+/// The id of the event that a repost reposts
+fn reposted_id(event: &Event) -> Option<Id> {
+    if let Ok(inner_event) = serde_json::from_str::<Event>(&event.content) {
+        return Some(inner_event.id);
+    }
+    for eref in event.mentions().iter() {
+        if let EventReference::Id { id, .. } = eref {
+            return Some(*id);
+        }
+    }
+    None
+}
+
+// Keep the events whose content passes the media filter
+fn filter_by_media(mut events: Vec<Event>, media_filter: MediaFilter) -> Result<Vec<Event>, Error> {
+    if media_filter == MediaFilter::All {
+        return Ok(events);
+    }
+    let mut output: Vec<Event> = Vec::with_capacity(events.len());
+    for event in events.drain(..) {
+        let media = GLOBALS.storage.read_event_media(event.id)?;
+        if media_filter.matches(media.as_ref()) {
+            output.push(event);
+        }
+    }
+    Ok(output)
 }
 
 pub fn enabled_event_kinds() -> Vec<EventKind> {
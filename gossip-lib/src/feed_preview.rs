@@ -0,0 +1,89 @@
+use crate::bridge::is_bridged;
+use crate::comms::ToOverlordMessage;
+use crate::error::Error;
+use crate::feed::feed_displayable_event_kinds;
+use crate::globals::GLOBALS;
+use crate::people::PersonList;
+use nostr_types::{Event, EventKind, Filter, Id, PublicKey, PublicKeyHex, Unixtime};
+
+/// A feed of the posts of an ad-hoc set of people, such as the members of a follow
+/// pack being looked over before following them. Nothing about the set is saved.
+///
+/// While it lives, their posts are subscribed to on the relays they write to. The
+/// subscriptions are closed when it is dropped.
+#[derive(Debug)]
+pub struct FeedPreview {
+    id: u64,
+    pubkeys: Vec<PublicKey>,
+    since: Unixtime,
+}
+
+impl FeedPreview {
+    /// Start previewing the posts of these people from one feed chunk back
+    pub fn start(pubkeys: Vec<PublicKey>) -> FeedPreview {
+        let since =
+            Unixtime(Unixtime::now().unwrap().0 - GLOBALS.storage.read_setting_feed_chunk() as i64);
+        let preview = FeedPreview {
+            id: rand::random::<u64>(),
+            pubkeys,
+            since,
+        };
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::StartFeedPreview {
+                id: preview.id,
+                pubkeys: preview.pubkeys.clone(),
+                since,
+            });
+        preview
+    }
+
+    /// The people being previewed
+    pub fn pubkeys(&self) -> &[PublicKey] {
+        &self.pubkeys
+    }
+
+    /// The posts of these people that we have, newest first. Replies are left out
+    /// unless `with_replies`.
+    pub fn events(&self, with_replies: bool) -> Result<Vec<Id>, Error> {
+        if self.pubkeys.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let now = Unixtime::now().unwrap();
+        let show_bridged = GLOBALS.storage.read_setting_feed_show_bridged();
+
+        let mut filter = Filter::new();
+        filter.authors = self
+            .pubkeys
+            .iter()
+            .filter(|pk| !GLOBALS.people.is_person_in_list(pk, PersonList::Muted))
+            .map(|pk| PublicKeyHex::from(*pk))
+            .collect();
+        if filter.authors.is_empty() {
+            return Ok(Vec::new());
+        }
+        filter.kinds = feed_displayable_event_kinds(false);
+        filter.since = Some(self.since);
+
+        let events = GLOBALS
+            .storage
+            .find_events_by_filter(&filter, |e: &Event| {
+                e.created_at <= now // no future events
+                && e.kind != EventKind::EncryptedDirectMessage // no DMs
+                && e.kind != EventKind::DmChat // no DMs
+                && (show_bridged || !is_bridged(e)) // not bridged
+                && (with_replies || crate::nip10::replies_to(e).is_none())
+            })?;
+
+        Ok(events.iter().map(|e| e.id).collect())
+    }
+}
+
+impl Drop for FeedPreview {
+    fn drop(&mut self) {
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::EndFeedPreview(self.id));
+    }
+}
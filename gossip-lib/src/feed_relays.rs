@@ -0,0 +1,198 @@
+use crate::error::Error;
+use crate::feed::feed_displayable_event_kinds;
+use crate::globals::GLOBALS;
+use crate::people::PersonList;
+use nostr_types::{Filter, PublicKeyHex, RelayUrl, Unixtime};
+use std::collections::{HashMap, HashSet};
+
+/// How far back the attribution looks
+pub const ATTRIBUTION_WINDOW_SECS: i64 = 60 * 60 * 24 * 7;
+
+/// How many events of a feed one relay delivered
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelayContribution {
+    pub relay: RelayUrl,
+
+    /// Events of the feed seen on this relay
+    pub events: usize,
+
+    /// Events of the feed seen on this relay and on no other
+    pub only_here: usize,
+
+    /// This relay could be dropped, along with every other redundant relay of the
+    /// feed, without losing anything from the feed: everything it delivered also
+    /// came from a relay that is not redundant.
+    pub redundant: bool,
+}
+
+/// Which relays the events of a person list feed came from, over the last week
+#[derive(Debug, Clone)]
+pub struct FeedRelayAttribution {
+    pub list: PersonList,
+
+    /// Events in the feed over the last week
+    pub events: usize,
+
+    /// Events we have no relay for (e.g. imported or created locally)
+    pub unattributed: usize,
+
+    /// Most events first
+    pub relays: Vec<RelayContribution>,
+}
+
+/// Count, from the relays each event was seen on, how many events each relay
+/// delivered and how many only it delivered, and which relays could be dropped
+/// together. Also returns how many events were not seen on any relay.
+pub fn attribute(seen_on: &[Vec<RelayUrl>]) -> (Vec<RelayContribution>, usize) {
+    let mut counts: HashMap<RelayUrl, (usize, usize)> = HashMap::new();
+    let mut unattributed = 0;
+    let mut delivered: Vec<Vec<RelayUrl>> = Vec::with_capacity(seen_on.len());
+    for relays in seen_on.iter() {
+        let mut relays = relays.clone();
+        relays.sort();
+        relays.dedup();
+        if relays.is_empty() {
+            unattributed += 1;
+            continue;
+        }
+        let only = relays.len() == 1;
+        for relay in relays.iter() {
+            let entry = counts.entry(relay.to_owned()).or_default();
+            entry.0 += 1;
+            if only {
+                entry.1 += 1;
+            }
+        }
+        delivered.push(relays);
+    }
+
+    let mut contributions: Vec<RelayContribution> = counts
+        .drain()
+        .map(|(relay, (events, only_here))| RelayContribution {
+            relay,
+            events,
+            only_here,
+            redundant: false,
+        })
+        .collect();
+    contributions.sort_by(|a, b| {
+        b.events
+            .cmp(&a.events)
+            .then(b.only_here.cmp(&a.only_here))
+            .then(a.relay.cmp(&b.relay))
+    });
+
+    // Drop relays one at a time, those that delivered least first, as long as
+    // everything they delivered still comes from a relay that remains
+    let mut dropped: HashSet<RelayUrl> = HashSet::new();
+    for contribution in contributions.iter_mut().rev() {
+        let droppable = delivered
+            .iter()
+            .filter(|relays| relays.contains(&contribution.relay))
+            .all(|relays| {
+                relays
+                    .iter()
+                    .any(|r| *r != contribution.relay && !dropped.contains(r))
+            });
+        if droppable {
+            dropped.insert(contribution.relay.clone());
+            contribution.redundant = true;
+        }
+    }
+
+    (contributions, unattributed)
+}
+
+/// Which relays the events of this person list's feed came from over the last week
+pub fn list_feed_attribution(list: PersonList) -> Result<FeedRelayAttribution, Error> {
+    let pubkeys: Vec<PublicKeyHex> = GLOBALS
+        .storage
+        .get_people_in_list(list)?
+        .drain(..)
+        .map(|(pk, _)| pk.into())
+        .collect();
+
+    let mut seen_on: Vec<Vec<RelayUrl>> = Vec::new();
+    if !pubkeys.is_empty() {
+        let mut filter = Filter::new();
+        filter.authors = pubkeys;
+        filter.kinds = feed_displayable_event_kinds(false);
+        filter.since = Some(Unixtime(
+            Unixtime::now().unwrap().0 - ATTRIBUTION_WINDOW_SECS,
+        ));
+        for event in GLOBALS.storage.find_events_by_filter(&filter, |_| true)? {
+            seen_on.push(
+                GLOBALS
+                    .storage
+                    .get_event_seen_on_relay(event.id)?
+                    .drain(..)
+                    .map(|(url, _)| url)
+                    .collect(),
+            );
+        }
+    }
+
+    let (relays, unattributed) = attribute(&seen_on);
+    Ok(FeedRelayAttribution {
+        list,
+        events: seen_on.len(),
+        unattributed,
+        relays,
+    })
+}
+
+/// The relay attribution of every person list feed
+pub fn all_feed_attribution() -> Result<Vec<FeedRelayAttribution>, Error> {
+    let mut output: Vec<FeedRelayAttribution> = Vec::new();
+    for (list, _) in GLOBALS.storage.get_all_person_list_metadata()? {
+        output.push(list_feed_attribution(list)?);
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_attribute() {
+        let a = RelayUrl::try_from_str("wss://a.example.com").unwrap();
+        let b = RelayUrl::try_from_str("wss://b.example.com").unwrap();
+        let c = RelayUrl::try_from_str("wss://c.example.com").unwrap();
+
+        let seen_on = vec![
+            vec![a.clone(), b.clone()],
+            vec![a.clone()],
+            vec![a.clone(), c.clone(), a.clone()],
+            vec![b.clone(), c.clone()],
+            vec![],
+        ];
+        let (contributions, unattributed) = attribute(&seen_on);
+        assert_eq!(unattributed, 1);
+        assert_eq!(
+            contributions,
+            vec![
+                RelayContribution {
+                    relay: a,
+                    events: 3,
+                    only_here: 1,
+                    redundant: false,
+                },
+                // Nothing came only from b or only from c, but without c, b is
+                // the only relay left for the last event
+                RelayContribution {
+                    relay: b,
+                    events: 2,
+                    only_here: 0,
+                    redundant: false,
+                },
+                RelayContribution {
+                    relay: c,
+                    events: 2,
+                    only_here: 0,
+                    redundant: true,
+                },
+            ]
+        );
+    }
+}
@@ -8,7 +8,7 @@ use reqwest::header::ETAG;
 use reqwest::Client;
 use reqwest::StatusCode;
 use sha2::Digest;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::PathBuf;
 use std::sync::atomic::Ordering;
@@ -16,13 +16,47 @@ use std::sync::RwLock;
 use std::time::{Duration, SystemTime};
 use tokio::time::Instant;
 
+// A URL is only tracked until it is fetched: if it succeeds, it is removed entirely.
 #[derive(Copy, Clone, Debug)]
 enum FetchState {
     Queued,
     QueuedStale, // Queued, only fetching because cache is stale
     InFlight,
-    Failed,
-    // If it succeeds, it is removed entirely.
+    Failed,             // Trying again won't help (unless asked to)
+    Deferred(Unixtime), // Failed transiently too often, try again after this
+}
+
+/// How many times a URL is tried for transient failures before it is deferred
+const MAX_TRANSIENT_ATTEMPTS: u32 = 4;
+
+/// How long a deferred URL waits before it is tried again
+const DEFERRED_RETRY_SECS: u64 = 60 * 30;
+
+/// The longest a host is backed off for
+const MAX_HOST_BACKOFF_SECS: u64 = 60 * 60;
+
+/// How fetches from a host have been going
+#[derive(Debug, Clone, Default)]
+pub struct HostHealth {
+    pub successes: u64,
+    pub failures: u64,
+
+    /// Transient failures since the last success. Each doubles the backoff.
+    pub consecutive_failures: u32,
+
+    /// Nothing is fetched from the host until then
+    pub backoff_until: Option<Unixtime>,
+}
+
+impl HostHealth {
+    pub fn failure_rate(&self) -> f32 {
+        let total = self.successes + self.failures;
+        if total == 0 {
+            0.0
+        } else {
+            self.failures as f32 / total as f32
+        }
+    }
 }
 
 /// System that fetches HTTP resources
@@ -34,11 +68,17 @@ pub struct Fetcher {
     // Here is where we store the current state of each URL being fetched
     urls: RwLock<HashMap<Url, FetchState>>,
 
+    // Transient failures of each URL being retried
+    attempts: RwLock<HashMap<Url, u32>>,
+
     // Load currently applied to a host
     host_load: RwLock<HashMap<String, usize>>,
 
-    // Here is where we put hosts into a penalty box to time them out
-    penalty_box: RwLock<HashMap<String, Unixtime>>,
+    // Successes, failures and backoff of each host
+    host_health: RwLock<HashMap<String, HostHealth>>,
+
+    // Remote content that its author's remote content policy does not let us fetch
+    withheld: RwLock<HashSet<Url>>,
 }
 
 impl Fetcher {
@@ -50,7 +90,9 @@ impl Fetcher {
 
     pub(crate) fn init() -> Result<(), Error> {
         // Setup the cache directory
-        *GLOBALS.fetcher.cache_dir.write().unwrap() = Profile::current()?.cache_dir;
+        let cache_dir = Profile::current()?.cache_dir;
+        fs::create_dir_all(cache_dir.join("blobs"))?;
+        *GLOBALS.fetcher.cache_dir.write().unwrap() = cache_dir;
 
         // Create client
         let connect_timeout = std::time::Duration::new(
@@ -137,6 +179,58 @@ impl Fetcher {
             .count()
     }
 
+    /// Count of HTTP requests that failed or were deferred
+    pub fn requests_failed(&self) -> usize {
+        self.urls
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(_u, r)| matches!(r, FetchState::Failed | FetchState::Deferred(_)))
+            .count()
+    }
+
+    /// How fetches from each host have been going
+    pub fn host_health(&self) -> Vec<(String, HostHealth)> {
+        let mut output: Vec<(String, HostHealth)> = self
+            .host_health
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(h, hh)| (h.clone(), hh.clone()))
+            .collect();
+        output.sort_by(|a, b| b.1.failures.cmp(&a.1.failures).then(a.0.cmp(&b.0)));
+        output
+    }
+
+    /// Try a failed or deferred URL again the next time it is asked for, and lift
+    /// the backoff on its host
+    pub fn retry_now(&self, url: &Url) {
+        self.urls.write().unwrap().remove(url);
+        self.attempts.write().unwrap().remove(url);
+        if let Some(host) = self.host(url) {
+            if let Some(hh) = self.host_health.write().unwrap().get_mut(&host) {
+                hh.consecutive_failures = 0;
+                hh.backoff_until = None;
+            }
+        }
+    }
+
+    /// Try every failed or deferred URL again, including the media and avatars
+    /// that gave up on them. Not for async callers.
+    pub fn retry_all_failed(&self) {
+        self.urls
+            .write()
+            .unwrap()
+            .retain(|_, state| !matches!(state, FetchState::Failed | FetchState::Deferred(_)));
+        self.attempts.write().unwrap().clear();
+        for hh in self.host_health.write().unwrap().values_mut() {
+            hh.consecutive_failures = 0;
+            hh.backoff_until = None;
+        }
+        GLOBALS.media.retry_all_failed();
+        GLOBALS.failed_avatars.blocking_write().clear();
+    }
+
     pub(crate) async fn process_queue(&self) {
         if GLOBALS.storage.read_setting_offline() {
             return;
@@ -157,16 +251,8 @@ impl Fetcher {
         for (url, state) in self.urls.read().unwrap().iter() {
             if matches!(state, FetchState::Queued) || matches!(state, FetchState::QueuedStale) {
                 if let Some(host) = self.host(url) {
-                    {
-                        let mut penalty_box = self.penalty_box.write().unwrap();
-                        if let Some(time) = penalty_box.get(&*host) {
-                            if time < &now {
-                                // Remove from penalty box
-                                penalty_box.remove(&*host);
-                            } else {
-                                continue; // We cannot dequeue this one
-                            }
-                        }
+                    if self.host_backed_off(&host, now) {
+                        continue; // We cannot dequeue this one
                     }
 
                     let load = self.fetch_host_load(&host);
@@ -193,6 +279,20 @@ impl Fetcher {
         }
     }
 
+    // Don't fetch this URL, see [may_fetch](crate::remote_content::may_fetch)
+    pub(crate) fn withhold(&self, url: &Url) {
+        if !self.withheld.read().unwrap().contains(url) {
+            self.withheld.write().unwrap().insert(url.to_owned());
+        }
+    }
+
+    // Fetch this URL again if asked
+    pub(crate) fn release(&self, url: &Url) {
+        if self.withheld.read().unwrap().contains(url) {
+            self.withheld.write().unwrap().remove(url);
+        }
+    }
+
     /// This is where other parts of the library attempt to get the bytes of a file.
     ///
     /// If it is missing:  You'll get an `Ok(None)` response, but the fetcher will then
@@ -222,6 +322,14 @@ impl Fetcher {
                 )
                 .into());
             }
+            Some(FetchState::Deferred(until)) => {
+                if Unixtime::now().unwrap() < until {
+                    tracing::trace!("FETCH {url}: Deferred.");
+                    return Ok(None);
+                }
+                // Try again
+                self.attempts.write().unwrap().remove(url);
+            }
             Some(FetchState::Queued) | Some(FetchState::QueuedStale) => {
                 tracing::trace!("FETCH {url}: Already queued.");
                 return Ok(None);
@@ -270,6 +378,12 @@ impl Fetcher {
             }
         }
 
+        // What we have cached is fine to show, but fetching it is not up to us
+        if self.withheld.read().unwrap().contains(url) {
+            tracing::trace!("FETCH {url}: Withheld by remote content policy");
+            return Ok(None);
+        }
+
         // We can't fetch as we are not async and we don't want to block the caller.
         // So we queue this request for now.
         let state = if stale {
@@ -285,10 +399,21 @@ impl Fetcher {
     }
 
     async fn fetch(&self, url: Url) {
+        // Withheld since it was queued
+        if self.withheld.read().unwrap().contains(&url) {
+            tracing::debug!("FETCH {url}: Dropped: withheld by remote content policy");
+            self.urls.write().unwrap().remove(&url);
+            return;
+        }
+
         // Do not fetch if offline
         if GLOBALS.storage.read_setting_offline() {
-            tracing::debug!("FETCH {url}: Failed: offline mode");
-            self.urls.write().unwrap().insert(url, FetchState::Failed);
+            tracing::debug!("FETCH {url}: Deferred: offline mode");
+            let now = Unixtime::now().unwrap();
+            self.urls
+                .write()
+                .unwrap()
+                .insert(url, FetchState::Deferred(now));
             return;
         }
 
@@ -343,7 +468,8 @@ impl Fetcher {
         }
 
         // closure to run when finished (if we didn't succeed)
-        let finish = |outcome, message, err: Option<Error>, sinbin_secs| {
+        // Requeued (transient) failures back the host off, starting at backoff_secs
+        let finish = |outcome, message, err: Option<Error>, backoff_secs| {
             match outcome {
                 FailOutcome::Fail => {
                     if stale {
@@ -377,21 +503,39 @@ impl Fetcher {
                     let _ =
                         filetime::set_file_mtime(cache_file.as_path(), filetime::FileTime::now());
                     self.urls.write().unwrap().remove(&url);
+                    self.attempts.write().unwrap().remove(&url);
                 }
                 FailOutcome::Requeue => {
+                    let attempts = {
+                        let mut attempts = self.attempts.write().unwrap();
+                        let a = attempts.entry(url.clone()).or_insert(0);
+                        *a += 1;
+                        *a
+                    };
+                    let state = if attempts >= MAX_TRANSIENT_ATTEMPTS {
+                        FetchState::Deferred(
+                            Unixtime::now().unwrap() + Duration::from_secs(DEFERRED_RETRY_SECS),
+                        )
+                    } else {
+                        FetchState::Queued
+                    };
+                    let action = if attempts >= MAX_TRANSIENT_ATTEMPTS {
+                        "Deferred"
+                    } else {
+                        "Re-Queued"
+                    };
                     if let Some(e) = err {
-                        tracing::info!("FETCH {url}: Re-Queued: {message}: {e}");
+                        tracing::info!("FETCH {url}: {action}: {message}: {e}");
                     } else {
-                        tracing::info!("FETCH {url}: Re-Queued: {message}");
+                        tracing::info!("FETCH {url}: {action}: {message}");
                     }
-                    self.urls
-                        .write()
-                        .unwrap()
-                        .insert(url.clone(), FetchState::Queued);
+                    self.urls.write().unwrap().insert(url.clone(), state);
                 }
             }
-            if sinbin_secs > 0 {
-                self.sinbin(&url, Duration::from_secs(sinbin_secs));
+            match outcome {
+                FailOutcome::NotModified => self.host_succeeded(&host),
+                FailOutcome::Requeue => self.host_failed(&host, backoff_secs),
+                FailOutcome::Fail => self.host_failed(&host, 0),
             }
             self.decrement_host_load(&host);
         };
@@ -434,9 +578,19 @@ impl Fetcher {
                 } else if e.is_request() {
                     finish(FailOutcome::Fail, "request error", Some(e.into()), 0);
                 } else if e.is_connect() {
-                    finish(FailOutcome::Fail, "connect error", Some(e.into()), 0);
+                    finish(
+                        FailOutcome::Requeue,
+                        "connect error",
+                        Some(e.into()),
+                        med_exclusion,
+                    );
                 } else if e.is_body() {
-                    finish(FailOutcome::Fail, "body error", Some(e.into()), 0);
+                    finish(
+                        FailOutcome::Requeue,
+                        "body error",
+                        Some(e.into()),
+                        low_exclusion,
+                    );
                 } else if e.is_decode() {
                     finish(FailOutcome::Fail, "decode error", Some(e.into()), 0);
                 } else {
@@ -502,34 +656,46 @@ impl Fetcher {
         let bytes = match maybe_bytes {
             Ok(bytes) => bytes,
             Err(e) => {
-                finish(FailOutcome::Fail, "response bytes", Some(e.into()), 0);
+                finish(
+                    FailOutcome::Requeue,
+                    "response bytes",
+                    Some(e.into()),
+                    low_exclusion,
+                );
                 return;
             }
         };
 
         // Do not accept zero-length files, and don't try again
         if bytes.is_empty() {
-            finish(FailOutcome::Fail, "zero length file", None, 10);
+            finish(FailOutcome::Fail, "zero length file", None, 0);
             return;
         }
 
         GLOBALS.bytes_read.fetch_add(bytes.len(), Ordering::Relaxed);
 
-        // Write to the file
-        if let Err(e) = tokio::fs::write(cache_file.as_path(), bytes).await {
-            finish(
-                FailOutcome::Fail,
-                "writing to cache file",
-                Some(e.into()),
-                0,
-            );
+        // Write to the file, keeping identical content only once
+        let content_hash: [u8; 32] = sha2::Sha256::digest(&bytes).into();
+        if let Err(e) = self
+            .write_blob(cache_file.as_path(), content_hash, &bytes)
+            .await
+        {
+            finish(FailOutcome::Fail, "writing to cache file", Some(e), 0);
             return;
         }
+        if let Err(e) = GLOBALS
+            .storage
+            .write_media_hash(url.as_str(), content_hash, None)
+        {
+            tracing::warn!("FETCH {url}: Could not record content hash: {e}");
+        }
+        GLOBALS.media.forget_same_content();
 
         tracing::debug!("FETCH {url}: Cached");
 
         // Remove from host load
         self.decrement_host_load(&host);
+        self.host_succeeded(&host);
 
         // If there was an etag, save it
         if let Some(etag) = maybe_etag {
@@ -537,6 +703,42 @@ impl Fetcher {
         }
 
         self.urls.write().unwrap().remove(&url);
+        self.attempts.write().unwrap().remove(&url);
+    }
+
+    // Where content with this sha256 hash is kept, however many urls served it
+    fn blob_file(&self, content_hash: [u8; 32]) -> PathBuf {
+        let mut blob_file = self.cache_dir.read().unwrap().clone();
+        blob_file.push("blobs");
+        blob_file.push(hex::encode(content_hash));
+        blob_file
+    }
+
+    // Write the content once as a blob and make the url's cache file a hard link
+    // to it (or a copy, where the filesystem cannot link). The cache file is touched,
+    // as freshness goes by its mtime and an older blob would have made it stale.
+    async fn write_blob(
+        &self,
+        cache_file: &std::path::Path,
+        content_hash: [u8; 32],
+        bytes: &[u8],
+    ) -> Result<(), Error> {
+        let blob_file = self.blob_file(content_hash);
+        if !matches!(tokio::fs::try_exists(blob_file.as_path()).await, Ok(true)) {
+            let tmp_file = blob_file.with_extension("tmp");
+            tokio::fs::write(tmp_file.as_path(), bytes).await?;
+            tokio::fs::rename(tmp_file.as_path(), blob_file.as_path()).await?;
+        }
+
+        let _ = tokio::fs::remove_file(cache_file).await;
+        if tokio::fs::hard_link(blob_file.as_path(), cache_file)
+            .await
+            .is_err()
+        {
+            tokio::fs::copy(blob_file.as_path(), cache_file).await?;
+        }
+        filetime::set_file_mtime(cache_file, filetime::FileTime::now())?;
+        Ok(())
     }
 
     fn cache_file(&self, url: &Url) -> PathBuf {
@@ -553,24 +755,42 @@ impl Fetcher {
         cache_file
     }
 
-    fn sinbin(&self, url: &Url, duration: Duration) {
-        let now = Unixtime::now().unwrap();
-        let later = now + duration;
-        let host = match self.host(url) {
-            Some(h) => h,
-            None => return,
-        };
+    fn host_backed_off(&self, host: &str, now: Unixtime) -> bool {
+        match self.host_health.read().unwrap().get(host) {
+            Some(HostHealth {
+                backoff_until: Some(until),
+                ..
+            }) => *until > now,
+            _ => false,
+        }
+    }
 
-        // lock penalty box
-        let mut penalty_box = self.penalty_box.write().unwrap();
+    fn host_succeeded(&self, host: &str) {
+        let mut host_health = self.host_health.write().unwrap();
+        let hh = host_health.entry(host.to_owned()).or_default();
+        hh.successes += 1;
+        hh.consecutive_failures = 0;
+        hh.backoff_until = None;
+    }
 
-        if let Some(time) = penalty_box.get_mut(&*host) {
-            if *time < later {
-                *time = later;
-            }
-        } else {
-            penalty_box.insert(host, later);
+    // Transient failures (with a backoff) back the host off exponentially
+    fn host_failed(&self, host: &str, backoff_secs: u64) {
+        let mut host_health = self.host_health.write().unwrap();
+        let hh = host_health.entry(host.to_owned()).or_default();
+        hh.failures += 1;
+        if backoff_secs == 0 {
+            return;
         }
+        hh.consecutive_failures += 1;
+        let doublings = (hh.consecutive_failures - 1).min(16);
+        let secs = backoff_secs
+            .saturating_mul(1 << doublings)
+            .min(MAX_HOST_BACKOFF_SECS);
+        let later = Unixtime::now().unwrap() + Duration::from_secs(secs);
+        if hh.backoff_until.map(|until| until < later).unwrap_or(true) {
+            hh.backoff_until = Some(later);
+        }
+        tracing::debug!("FETCH: backing off {host} for {secs}s");
     }
 
     fn host(&self, url: &Url) -> Option<String> {
@@ -614,6 +834,13 @@ impl Fetcher {
         }
     }
 
+    // The sha256 hash a cache file or blob is named by
+    fn hash_of_file_name(path: &std::path::Path) -> Option<[u8; 32]> {
+        let name = path.file_name()?.to_str()?;
+        let bytes = hex::decode(name).ok()?;
+        bytes.try_into().ok()
+    }
+
     pub(crate) async fn prune(&self, age: Duration) -> Result<usize, Error> {
         let mut count: usize = 0;
         let cache_path = self.cache_dir.read().unwrap().to_owned();
@@ -640,10 +867,29 @@ impl Fetcher {
                 if file_age > age {
                     tokio::fs::remove_file(entry.path().as_path()).await?;
                     count += 1;
+
+                    // Forget what content this url served
+                    if let Some(url_key) = Self::hash_of_file_name(&entry.path()) {
+                        GLOBALS.storage.delete_media_hash(url_key, None)?;
+                    }
                 }
             }
         }
 
+        // Remove blobs no url refers to anymore
+        let mut blob_path = cache_path;
+        blob_path.push("blobs");
+        let mut entries = tokio::fs::read_dir(blob_path.as_path()).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let in_use = match Self::hash_of_file_name(&entry.path()) {
+                Some(content_hash) => GLOBALS.storage.media_hash_in_use(content_hash)?,
+                None => false, // an unfinished write
+            };
+            if !in_use {
+                tokio::fs::remove_file(entry.path().as_path()).await?;
+            }
+        }
+
         Ok(count)
     }
 }
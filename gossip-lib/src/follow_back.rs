@@ -0,0 +1,489 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::misc::Private;
+use crate::pending::PendingItem;
+use crate::people::PersonList;
+use nostr_types::{Event, EventKind, PublicKey, Unixtime};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+// The rules and their log are kept in the app data store under this namespace
+const NAMESPACE: &str = crate::storage::LIB_NAMESPACE;
+const RULES_KEY: &str = "follow_back_rules";
+const LOG_KEY: &str = "follow_back_log";
+
+// The log keeps this many of the latest firings
+const MAX_LOG_ENTRIES: usize = 500;
+
+const DAY_SECS: i64 = 60 * 60 * 24;
+
+// With no earlier contact list of theirs to compare against, someone following us
+// counts as new only if their contact list is at most this old
+const NEW_FOLLOW_SECS: i64 = DAY_SECS;
+
+// How long we trust the computed mutual follow counts
+const MUTUALS_CACHE_SECS: u64 = 600;
+
+/// A rule for following back new followers, e.g. "add new followers who are followed
+/// by at least 3 of the people I follow to list X"
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FollowBackRule {
+    pub id: u64,
+    pub name: String,
+    pub enabled: bool,
+
+    /// How many of the people we follow must follow the new follower
+    pub min_mutual_follows: usize,
+
+    /// The number of the person list to add them to
+    pub list: u8,
+    pub private: bool,
+
+    /// Queue each addition for our approval (the default), rather than make it
+    pub require_approval: bool,
+
+    /// At most this many people are queued or added by this rule per day. Those
+    /// over the limit are looked at again when their contact list next arrives.
+    pub max_per_day: usize,
+}
+
+impl FollowBackRule {
+    pub fn new(name: String, min_mutual_follows: usize, list: PersonList) -> FollowBackRule {
+        FollowBackRule {
+            id: rand::random::<u64>(),
+            name,
+            enabled: true,
+            min_mutual_follows,
+            list: list.into(),
+            private: false,
+            require_approval: true,
+            max_per_day: 10,
+        }
+    }
+
+    /// The list this rule adds people to, if it still exists
+    pub fn person_list(&self) -> Option<PersonList> {
+        PersonList::from_number(self.list)
+    }
+}
+
+/// What a rule did when it fired
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FollowBackAction {
+    /// Waiting for our approval
+    Queued,
+
+    /// Added to the list without asking
+    Added,
+
+    /// We approved it, and they were added
+    Approved,
+
+    /// We declined it
+    Declined,
+
+    /// The rule matched, but had done all it may do today
+    Throttled,
+}
+
+impl FollowBackAction {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            FollowBackAction::Queued => "queued for approval",
+            FollowBackAction::Added => "added",
+            FollowBackAction::Approved => "approved",
+            FollowBackAction::Declined => "declined",
+            FollowBackAction::Throttled => "throttled",
+        }
+    }
+}
+
+/// A firing of a follow-back rule
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FollowBackLogEntry {
+    pub at: Unixtime,
+    pub rule: u64,
+    pub rule_name: String,
+    pub pubkey: PublicKey,
+    pub mutual_follows: usize,
+    pub action: FollowBackAction,
+}
+
+// Loaded on first use
+static RULES: RwLock<Option<Vec<FollowBackRule>>> = RwLock::new(None);
+
+// How many of the people we follow follow each person
+static MUTUALS: RwLock<Option<(Instant, HashMap<PublicKey, usize>)>> = RwLock::new(None);
+
+fn load<T: for<'de> Deserialize<'de>>(key: &str) -> Result<Vec<T>, Error> {
+    Ok(GLOBALS
+        .storage
+        .app_data(NAMESPACE)?
+        .get::<Vec<T>>(key)?
+        .unwrap_or_default())
+}
+
+/// Is any follow-back rule enabled?
+pub fn any_enabled() -> bool {
+    rules().is_ok_and(|rules| rules.iter().any(|r| r.enabled))
+}
+
+/// The follow-back rules
+pub fn rules() -> Result<Vec<FollowBackRule>, Error> {
+    if let Some(rules) = &*RULES.read() {
+        return Ok(rules.clone());
+    }
+    let mut guard = RULES.write();
+    if guard.is_none() {
+        *guard = Some(load(RULES_KEY)?);
+    }
+    Ok(guard.as_ref().unwrap().clone())
+}
+
+fn modify_rules(f: impl FnOnce(&mut Vec<FollowBackRule>)) -> Result<(), Error> {
+    let mut guard = RULES.write();
+    let mut rules = match guard.as_ref() {
+        Some(rules) => rules.clone(),
+        None => load(RULES_KEY)?,
+    };
+    f(&mut rules);
+    GLOBALS
+        .storage
+        .app_data(NAMESPACE)?
+        .put(RULES_KEY, &rules)?;
+    *guard = Some(rules);
+    Ok(())
+}
+
+/// Add a rule, or replace the one with the same id
+pub fn save_rule(rule: FollowBackRule) -> Result<(), Error> {
+    modify_rules(|rules| match rules.iter_mut().find(|r| r.id == rule.id) {
+        Some(existing) => *existing = rule,
+        None => rules.push(rule),
+    })
+}
+
+/// Remove a rule. Its firings stay in the log.
+pub fn delete_rule(id: u64) -> Result<(), Error> {
+    modify_rules(|rules| rules.retain(|r| r.id != id))
+}
+
+/// The log of rule firings, newest first
+pub fn log() -> Result<Vec<FollowBackLogEntry>, Error> {
+    let mut log: Vec<FollowBackLogEntry> = load(LOG_KEY)?;
+    log.reverse();
+    Ok(log)
+}
+
+fn append_log(entry: FollowBackLogEntry) -> Result<(), Error> {
+    GLOBALS
+        .storage
+        .app_data(NAMESPACE)?
+        .modify(LOG_KEY, |log: &mut Vec<FollowBackLogEntry>| {
+            push_log(log, entry)
+        })
+}
+
+fn push_log(log: &mut Vec<FollowBackLogEntry>, entry: FollowBackLogEntry) {
+    log.push(entry);
+    if log.len() > MAX_LOG_ENTRIES {
+        let excess = log.len() - MAX_LOG_ENTRIES;
+        log.drain(..excess);
+    }
+}
+
+// Has this rule already decided about this person? Throttled firings don't count,
+// those get another chance.
+fn already_fired(log: &[FollowBackLogEntry], rule: u64, pubkey: &PublicKey) -> bool {
+    log.iter()
+        .any(|e| e.rule == rule && e.pubkey == *pubkey && e.action != FollowBackAction::Throttled)
+}
+
+// Is a throttled firing of this rule for this person already logged?
+fn already_throttled(log: &[FollowBackLogEntry], rule: u64, pubkey: &PublicKey) -> bool {
+    log.iter()
+        .any(|e| e.rule == rule && e.pubkey == *pubkey && e.action == FollowBackAction::Throttled)
+}
+
+// How many people this rule queued or added in the day before now
+fn fired_today(log: &[FollowBackLogEntry], rule: u64, now: Unixtime) -> usize {
+    log.iter()
+        .filter(|e| {
+            e.rule == rule
+                && now.0 - e.at.0 < DAY_SECS
+                && matches!(e.action, FollowBackAction::Queued | FollowBackAction::Added)
+        })
+        .count()
+}
+
+/// How many of the people we follow follow this person, going by the contact
+/// lists of theirs that we have. The counts are worked out for everybody at once
+/// and kept for a while.
+pub fn mutual_follows(pubkey: &PublicKey) -> Result<usize, Error> {
+    if let Some((when, counts)) = &*MUTUALS.read() {
+        if when.elapsed() < Duration::from_secs(MUTUALS_CACHE_SECS) {
+            return Ok(counts.get(pubkey).copied().unwrap_or(0));
+        }
+    }
+
+    let mut counts: HashMap<PublicKey, usize> = HashMap::new();
+    for (followed, _) in GLOBALS.storage.get_people_in_list(PersonList::Followed)? {
+        if let Some(event) =
+            GLOBALS
+                .storage
+                .get_replaceable_event(EventKind::ContactList, followed, "")?
+        {
+            let theirs: HashSet<PublicKey> = event.people().iter().map(|(pk, _, _)| *pk).collect();
+            for pk in theirs {
+                *counts.entry(pk).or_default() += 1;
+            }
+        }
+    }
+    let count = counts.get(pubkey).copied().unwrap_or(0);
+    *MUTUALS.write() = Some((Instant::now(), counts));
+    Ok(count)
+}
+
+// Process calls this before a contact list of somebody else replaces the one we
+// have of theirs: whether that one followed us, or None if we had none.
+pub(crate) fn followed_us_before(event: &Event) -> Result<Option<bool>, Error> {
+    let my_pubkey = match GLOBALS.identity.public_key() {
+        Some(pk) => pk,
+        None => return Ok(None),
+    };
+    if event.pubkey == my_pubkey || !any_enabled() {
+        return Ok(None);
+    }
+    Ok(GLOBALS
+        .storage
+        .get_replaceable_event(EventKind::ContactList, event.pubkey, "")?
+        .map(|old| old.people().iter().any(|(pk, _, _)| *pk == my_pubkey)))
+}
+
+// Process calls this for the contact lists of others, with what
+// [followed_us_before] said before it was stored. If its author has just followed
+// us, the enabled rules are evaluated for them.
+pub(crate) fn on_contact_list(
+    event: &Event,
+    followed_us_before: Option<bool>,
+) -> Result<(), Error> {
+    let rules: Vec<FollowBackRule> = rules()?.into_iter().filter(|r| r.enabled).collect();
+    if rules.is_empty() {
+        return Ok(());
+    }
+    let my_pubkey = match GLOBALS.identity.public_key() {
+        Some(pk) => pk,
+        None => return Ok(()),
+    };
+    let follower = event.pubkey;
+    if follower == my_pubkey || !event.people().iter().any(|(pk, _, _)| *pk == my_pubkey) {
+        return Ok(());
+    }
+    let now = Unixtime::now().unwrap();
+    let new_follow = match followed_us_before {
+        Some(before) => !before,
+        None => now.0 - event.created_at.0 <= NEW_FOLLOW_SECS,
+    };
+    // Those a rule was throttled for get another chance
+    if !new_follow
+        && !load::<FollowBackLogEntry>(LOG_KEY)?
+            .iter()
+            .any(|e| e.pubkey == follower && e.action == FollowBackAction::Throttled)
+    {
+        return Ok(());
+    }
+    if GLOBALS
+        .people
+        .is_person_in_list(&follower, PersonList::Muted)
+    {
+        return Ok(());
+    }
+
+    let count = mutual_follows(&follower)?;
+    let mut candidates: Vec<(FollowBackRule, PersonList)> = Vec::new();
+    for rule in rules {
+        if let Some(list) = rule.person_list() {
+            if count >= rule.min_mutual_follows
+                && !GLOBALS.people.is_person_in_list(&follower, list)
+            {
+                candidates.push((rule, list));
+            }
+        }
+    }
+    if candidates.is_empty() {
+        return Ok(());
+    }
+
+    // Decide and log in one transaction, so that contact lists processed at the
+    // same time can't both fire a rule, or fire it more than max_per_day times
+    let fired: Vec<(FollowBackRule, PersonList, FollowBackAction)> = GLOBALS
+        .storage
+        .app_data(NAMESPACE)?
+        .modify(LOG_KEY, |log: &mut Vec<FollowBackLogEntry>| {
+            let mut fired = Vec::new();
+            for (rule, list) in candidates {
+                if already_fired(log, rule.id, &follower) {
+                    continue;
+                }
+
+                let action = if fired_today(log, rule.id, now) >= rule.max_per_day {
+                    // Their contact list may arrive again; one entry is enough
+                    if already_throttled(log, rule.id, &follower) {
+                        continue;
+                    }
+                    FollowBackAction::Throttled
+                } else if rule.require_approval {
+                    FollowBackAction::Queued
+                } else {
+                    FollowBackAction::Added
+                };
+
+                push_log(
+                    log,
+                    FollowBackLogEntry {
+                        at: now,
+                        rule: rule.id,
+                        rule_name: rule.name.clone(),
+                        pubkey: follower,
+                        mutual_follows: count,
+                        action,
+                    },
+                );
+                fired.push((rule, list, action));
+            }
+            fired
+        })?;
+
+    for (rule, list, action) in fired {
+        match action {
+            FollowBackAction::Queued => {
+                GLOBALS.pending.insert(PendingItem::FollowBack {
+                    rule: rule.id,
+                    pubkey: follower,
+                });
+            }
+            FollowBackAction::Added => {
+                GLOBALS
+                    .people
+                    .follow(&follower, true, list, Private(rule.private))?;
+            }
+            _ => (),
+        }
+    }
+
+    Ok(())
+}
+
+// The queued firings we have not yet decided on. The pending queue is not saved,
+// so these are put back into it from the log.
+pub(crate) fn pending_items() -> Result<Vec<PendingItem>, Error> {
+    let log: Vec<FollowBackLogEntry> = load(LOG_KEY)?;
+    let mut decided: HashSet<(u64, PublicKey)> = HashSet::new();
+    let mut items: Vec<PendingItem> = Vec::new();
+    for entry in log.iter().rev() {
+        if entry.action == FollowBackAction::Throttled {
+            continue;
+        }
+        // Only the latest firing for this rule and person counts
+        if decided.insert((entry.rule, entry.pubkey)) && entry.action == FollowBackAction::Queued {
+            items.push(PendingItem::FollowBack {
+                rule: entry.rule,
+                pubkey: entry.pubkey,
+            });
+        }
+    }
+    Ok(items)
+}
+
+/// Approve a queued follow-back: add them to the rule's list
+pub fn approve(rule_id: u64, pubkey: PublicKey) -> Result<(), Error> {
+    decide(rule_id, pubkey, true)
+}
+
+/// Decline a queued follow-back
+pub fn decline(rule_id: u64, pubkey: PublicKey) -> Result<(), Error> {
+    decide(rule_id, pubkey, false)
+}
+
+fn decide(rule_id: u64, pubkey: PublicKey, approved: bool) -> Result<(), Error> {
+    GLOBALS.pending.remove(&PendingItem::FollowBack {
+        rule: rule_id,
+        pubkey,
+    });
+
+    let rule = rules()?.into_iter().find(|r| r.id == rule_id);
+    let rule_name = rule.as_ref().map(|r| r.name.clone()).unwrap_or_default();
+    if approved {
+        match rule
+            .as_ref()
+            .and_then(|r| r.person_list().map(|l| (l, r.private)))
+        {
+            Some((list, private)) => {
+                GLOBALS
+                    .people
+                    .follow(&pubkey, true, list, Private(private))?;
+            }
+            None => {
+                GLOBALS
+                    .status_queue
+                    .write()
+                    .write("The rule or its list no longer exists.".to_owned());
+                return Ok(());
+            }
+        }
+    }
+
+    append_log(FollowBackLogEntry {
+        at: Unixtime::now().unwrap(),
+        rule: rule_id,
+        rule_name,
+        pubkey,
+        mutual_follows: 0,
+        action: if approved {
+            FollowBackAction::Approved
+        } else {
+            FollowBackAction::Declined
+        },
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nostr_types::PrivateKey;
+
+    #[test]
+    fn test_firings() {
+        let alice = PrivateKey::generate().public_key();
+        let bob = PrivateKey::generate().public_key();
+        let now = Unixtime::now().unwrap();
+        let entry = |pubkey: PublicKey, ago: i64, action: FollowBackAction| FollowBackLogEntry {
+            at: Unixtime(now.0 - ago),
+            rule: 1,
+            rule_name: "mutuals".to_owned(),
+            pubkey,
+            mutual_follows: 3,
+            action,
+        };
+
+        let log = vec![
+            entry(alice, DAY_SECS * 2, FollowBackAction::Added),
+            entry(bob, 60, FollowBackAction::Throttled),
+        ];
+        assert!(already_fired(&log, 1, &alice));
+        assert!(!already_fired(&log, 2, &alice));
+        assert!(!already_fired(&log, 1, &bob));
+        assert!(already_throttled(&log, 1, &bob));
+        assert!(!already_throttled(&log, 1, &alice));
+        assert_eq!(fired_today(&log, 1, now), 0);
+
+        let log = vec![
+            entry(alice, 60, FollowBackAction::Queued),
+            entry(bob, 120, FollowBackAction::Added),
+        ];
+        assert_eq!(fired_today(&log, 1, now), 2);
+        assert!(already_fired(&log, 1, &bob));
+    }
+}
@@ -0,0 +1,200 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::misc::Private;
+use crate::people::PersonList;
+use nostr_types::{Event, EventKind, Filter, PublicKey, PublicKeyHex};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Why a person is proposed for the recovered follow list
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RecoveryReason {
+    /// They were in a contact list (kind 3) of ours that we still have
+    InOldContactList,
+
+    /// They are in another of our person lists
+    InOtherList,
+
+    /// We have DMed them
+    DirectMessaged,
+
+    /// We have zapped them
+    Zapped,
+
+    /// We have replied to them
+    RepliedTo,
+}
+
+impl fmt::Display for RecoveryReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecoveryReason::InOldContactList => write!(f, "in an older follow list"),
+            RecoveryReason::InOtherList => write!(f, "in another list"),
+            RecoveryReason::DirectMessaged => write!(f, "DMed"),
+            RecoveryReason::Zapped => write!(f, "zapped"),
+            RecoveryReason::RepliedTo => write!(f, "replied to"),
+        }
+    }
+}
+
+/// A person proposed for the recovered follow list
+#[derive(Debug, Clone)]
+pub struct RecoveryCandidate {
+    pub pubkey: PublicKey,
+
+    /// All the reasons, strongest first
+    pub reasons: Vec<RecoveryReason>,
+
+    /// If the user wants this person followed. Defaults to true unless the only
+    /// evidence is a reply.
+    pub selected: bool,
+}
+
+/// Does our follow list look lost or clobbered? This is the case if it is empty, or
+/// if it is much shorter than a contact list of ours that we still have.
+pub fn follow_list_looks_lost() -> Result<bool, Error> {
+    let my_pubkey = match GLOBALS.identity.public_key() {
+        Some(pk) => pk,
+        None => return Ok(false),
+    };
+
+    let followed = GLOBALS
+        .storage
+        .get_people_in_list(PersonList::Followed)?
+        .len();
+    if followed == 0 {
+        return Ok(true);
+    }
+
+    let largest = my_events(my_pubkey, vec![EventKind::ContactList])?
+        .iter()
+        .map(|e| e.people().len())
+        .max()
+        .unwrap_or(0);
+    Ok(followed * 2 < largest)
+}
+
+/// Reconstruct a follow list from local history: people in our older contact lists
+/// or other person lists, and people we DMed, zapped, or replied to. People we
+/// already follow, muted people and ourself are left out.
+///
+/// Nothing is changed; pass the (reviewed) result to [apply_recovered_follows].
+pub fn propose_recovered_follows() -> Result<Vec<RecoveryCandidate>, Error> {
+    let my_pubkey = match GLOBALS.identity.public_key() {
+        Some(pk) => pk,
+        None => return Ok(vec![]),
+    };
+
+    let mut found: HashMap<PublicKey, Vec<RecoveryReason>> = HashMap::new();
+    let mut add = |pubkey: PublicKey, reason: RecoveryReason| {
+        let reasons = found.entry(pubkey).or_default();
+        if !reasons.contains(&reason) {
+            reasons.push(reason);
+        }
+    };
+
+    for event in my_events(my_pubkey, vec![EventKind::ContactList])?.iter() {
+        for (pubkey, _, _) in event.people() {
+            add(pubkey, RecoveryReason::InOldContactList);
+        }
+    }
+
+    for (list, _) in GLOBALS.storage.get_all_person_list_metadata()? {
+        if matches!(list, PersonList::Custom(_)) {
+            for (pubkey, _) in GLOBALS.storage.get_people_in_list(list)? {
+                add(pubkey, RecoveryReason::InOtherList);
+            }
+        }
+    }
+
+    for channel_data in GLOBALS.storage.dm_channels()? {
+        if channel_data.sent_message_count > 0 {
+            for pubkey in channel_data.dm_channel.keys() {
+                add(*pubkey, RecoveryReason::DirectMessaged);
+            }
+        }
+    }
+
+    // Zap receipts carry the zapper in an uppercase 'P' tag
+    let my_hex = my_pubkey.as_hex_string();
+    let mut filter = Filter::new();
+    filter.add_event_kind(EventKind::Zap);
+    for event in GLOBALS
+        .storage
+        .find_events_by_filter(&filter, |e| {
+            e.tags
+                .iter()
+                .any(|t| t.tagname() == "P" && t.get_index(1) == my_hex)
+        })?
+        .iter()
+    {
+        for tag in event.tags.iter() {
+            if tag.tagname() == "p" {
+                if let Ok(pubkey) = PublicKey::try_from_hex_string(tag.get_index(1), true) {
+                    add(pubkey, RecoveryReason::Zapped);
+                }
+            }
+        }
+    }
+
+    // Replies tag the people up the thread
+    for event in my_events(my_pubkey, vec![EventKind::TextNote])?.iter() {
+        if crate::nip10::replies_to(event).is_some() {
+            for (pubkey, _, _) in event.people() {
+                add(pubkey, RecoveryReason::RepliedTo);
+            }
+        }
+    }
+
+    let mut candidates: Vec<RecoveryCandidate> = Vec::new();
+    for (pubkey, mut reasons) in found.drain() {
+        if pubkey == my_pubkey
+            || GLOBALS
+                .people
+                .is_person_in_list(&pubkey, PersonList::Followed)
+            || GLOBALS.people.is_person_in_list(&pubkey, PersonList::Muted)
+        {
+            continue;
+        }
+        reasons.sort();
+        let selected = reasons.iter().any(|r| *r != RecoveryReason::RepliedTo);
+        candidates.push(RecoveryCandidate {
+            pubkey,
+            reasons,
+            selected,
+        });
+    }
+
+    // Strongest evidence first
+    candidates.sort_by(|a, b| {
+        b.reasons
+            .len()
+            .cmp(&a.reasons.len())
+            .then(a.reasons.cmp(&b.reasons))
+    });
+
+    Ok(candidates)
+}
+
+/// Follow the selected candidates (publicly). The follow list is not published;
+/// the user should review it and then publish it.
+pub fn apply_recovered_follows(candidates: &[RecoveryCandidate]) -> Result<usize, Error> {
+    let mut count = 0;
+    for candidate in candidates.iter().filter(|c| c.selected) {
+        GLOBALS.people.follow(
+            &candidate.pubkey,
+            true,
+            PersonList::Followed,
+            Private(false),
+        )?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+fn my_events(my_pubkey: PublicKey, kinds: Vec<EventKind>) -> Result<Vec<Event>, Error> {
+    let mut filter = Filter::new();
+    filter.authors = vec![PublicKeyHex::from(my_pubkey)];
+    filter.kinds = kinds;
+    GLOBALS.storage.find_events_by_filter(&filter, |_| true)
+}
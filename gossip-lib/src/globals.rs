@@ -1,18 +1,31 @@
-use crate::comms::{RelayJob, ToMinionMessage, ToOverlordMessage};
+use crate::audit::AuditReport;
+use crate::bus::BusMessage;
+use crate::comms::{
+    RelayJob, RelayJobActivity, RelayJobStatus, ToMinionMessage, ToOverlordMessage,
+};
+use crate::deck::Decks;
 use crate::delegation::Delegation;
+use crate::duplicate_post::HeldPost;
 use crate::error::Error;
 use crate::feed::Feed;
+use crate::feed_relays::FeedRelayAttribution;
 use crate::fetcher::Fetcher;
 use crate::gossip_identity::GossipIdentity;
+use crate::history_sweep::SweepReport;
+use crate::kind_registry::KindRegistry;
 use crate::media::Media;
 use crate::misc::ZapState;
+use crate::nip46::Nip46Pairing;
 use crate::pending::Pending;
 use crate::people::{People, Person};
+use crate::person_list_io::PersonListImportEntry;
 use crate::relay::Relay;
 use crate::relay_picker_hooks::Hooks;
+use crate::relay_query::RawQueryResults;
 use crate::seeker::Seeker;
 use crate::status::StatusQueue;
-use crate::storage::Storage;
+use crate::storage::{Storage, StorageHandle};
+use crate::write_redundancy::RedundancyReport;
 use crate::RunState;
 use dashmap::{DashMap, DashSet};
 use gossip_relay_picker::RelayPicker;
@@ -53,6 +66,10 @@ pub struct Globals {
     /// is created.
     pub tmp_overlord_receiver: Mutex<Option<mpsc::UnboundedReceiver<ToOverlordMessage>>>,
 
+    /// This is a broadcast channel of state changes for front-ends and integrations.
+    /// To create a receiver, just run .subscribe() on it (or use `bus::subscribe()`).
+    pub bus: broadcast::Sender<BusMessage>,
+
     /// All nostr people records currently loaded into memory, keyed by pubkey
     pub people: People,
 
@@ -65,6 +82,10 @@ pub struct Globals {
     /// time passes, but which we still have jobs for
     pub penalty_box_relays: DashMap<RelayUrl, Vec<RelayJob>>,
 
+    /// Activity of relay jobs (subscriptions, counts), keyed by relay and job id.
+    /// Use `Globals::jobs()` to read this.
+    pub(crate) relay_job_activity: DashMap<(RelayUrl, u64), RelayJobActivity>,
+
     /// The relay picker, used to pick the next relay
     pub relay_picker: RelayPicker<Hooks>,
 
@@ -111,6 +132,43 @@ pub struct Globals {
     pub people_search_results: PRwLock<Vec<Person>>,
     pub note_search_results: PRwLock<Vec<Event>>,
 
+    /// Resolved entries of a person list import, awaiting review
+    pub person_list_import: PRwLock<Option<Vec<PersonListImportEntry>>>,
+
+    /// The user's decks (multi-column layouts)
+    pub decks: Decks,
+
+    /// What we know of event kinds
+    pub kinds: KindRegistry,
+
+    /// Progress or result of the last event audit
+    pub event_audit: PRwLock<Option<AuditReport>>,
+
+    /// Results of the latest raw relay query
+    pub raw_query: PRwLock<Option<RawQueryResults>>,
+
+    /// Progress or result of the last sweep for our own past events
+    pub history_sweep: PRwLock<Option<SweepReport>>,
+
+    /// Which relays delivered the events of each person list feed, as last counted
+    pub feed_sources: PRwLock<Option<Vec<FeedRelayAttribution>>>,
+
+    /// Whether the feed sources are being counted
+    pub feed_sources_counting: AtomicBool,
+
+    /// Our attempts to pair with remote signers, by client key
+    pub nip46_pairings: DashMap<PublicKey, Nip46Pairing>,
+
+    /// Posts held back as likely duplicates, until confirmed
+    pub held_posts: DashMap<u64, HeldPost>,
+
+    /// How many relays our recent posts reached, newest first
+    pub write_redundancy: PRwLock<Vec<RedundancyReport>>,
+
+    /// How many rotated events each write relay got (saved across restarts), see
+    /// [crate::write_rotation]
+    pub write_rotation: DashMap<RelayUrl, u64>,
+
     /// UI note cache invalidation per note
     // when we update an augment (deletion/reaction/zap) the UI must recompute
     pub ui_notes_to_invalidate: PRwLock<Vec<Id>>,
@@ -131,8 +189,8 @@ pub struct Globals {
     /// Tagging regex
     pub tagging_regex: Regex,
 
-    /// LMDB storage
-    pub storage: Storage,
+    /// LMDB storage, of the current profile
+    pub storage: StorageHandle,
 
     /// Events Processed
     pub events_processed: AtomicU32,
@@ -148,6 +206,13 @@ pub struct Globals {
     // Wait for data migration
     pub wait_for_data_migration: AtomicBool,
 
+    /// A post is being prepared (signed, with proof of work)
+    pub posting_in_progress: AtomicBool,
+
+    /// Set to cancel the post being prepared. Nothing of it (no part of a split
+    /// note) is sent.
+    pub cancel_posting: AtomicBool,
+
     // Active advertise jobs
     pub active_advertise_jobs: DashSet<u64>,
 
@@ -169,6 +234,9 @@ lazy_static! {
         // We start in the Offline state
         let (write_runstate, read_runstate) = tokio::sync::watch::channel(RunState::Initializing);
 
+        // Setup a broadcast channel for state changes
+        let (bus, _) = broadcast::channel(1024);
+
         let storage = match Storage::new() {
             Ok(s) => s,
             Err(e) => panic!("{e}")
@@ -183,9 +251,11 @@ lazy_static! {
             write_runstate,
             read_runstate,
             tmp_overlord_receiver: Mutex::new(Some(tmp_overlord_receiver)),
+            bus,
             people: People::new(),
             connected_relays: DashMap::new(),
             penalty_box_relays: DashMap::new(),
+            relay_job_activity: DashMap::new(),
             relay_picker: Default::default(),
             identity: GossipIdentity::default(),
             dismissed: RwLock::new(Vec::new()),
@@ -205,19 +275,33 @@ lazy_static! {
             //event_addrs_being_searched_for: PRwLock::new(Vec::new()),
             people_search_results: PRwLock::new(Vec::new()),
             note_search_results: PRwLock::new(Vec::new()),
+            person_list_import: PRwLock::new(None),
+            decks: Decks::new(),
+            kinds: KindRegistry::new(),
+            event_audit: PRwLock::new(None),
+            raw_query: PRwLock::new(None),
+            history_sweep: PRwLock::new(None),
+            feed_sources: PRwLock::new(None),
+            feed_sources_counting: AtomicBool::new(false),
+            nip46_pairings: DashMap::new(),
+            held_posts: DashMap::new(),
+            write_redundancy: PRwLock::new(Vec::new()),
+            write_rotation: DashMap::new(),
             ui_notes_to_invalidate: PRwLock::new(Vec::new()),
             ui_people_to_invalidate: PRwLock::new(Vec::new()),
             ui_invalidate_all: AtomicBool::new(false),
             current_zap: PRwLock::new(ZapState::None),
             hashtag_regex: Regex::new(r"(?:^|\W)(#[\w\p{Extended_Pictographic}]+)(?:$|\W)").unwrap(),
             tagging_regex: Regex::new(r"(?:^|\s+)@([\w\p{Extended_Pictographic}]+)(?:$|\W)").unwrap(),
-            storage,
+            storage: StorageHandle::new(storage),
             events_processed: AtomicU32::new(0),
             filter_engine,
             filter,
             wait_for_login: AtomicBool::new(false),
             wait_for_login_notify: Notify::new(),
             wait_for_data_migration: AtomicBool::new(false),
+            posting_in_progress: AtomicBool::new(false),
+            cancel_posting: AtomicBool::new(false),
             active_advertise_jobs: DashSet::new(),
             pending: Pending::new(),
         }
@@ -254,20 +338,48 @@ impl Globals {
         Some(profile)
     }
 
+    /// All the jobs that connected relays are currently doing, with their
+    /// subscriptions and activity
+    pub fn jobs() -> Vec<RelayJobStatus> {
+        let mut output: Vec<RelayJobStatus> = Vec::new();
+        for elem in GLOBALS.connected_relays.iter() {
+            let relay = elem.key();
+            for job in elem.value().iter() {
+                let job_id = job.payload.job_id;
+                let activity = GLOBALS
+                    .relay_job_activity
+                    .get(&(relay.clone(), job_id))
+                    .map(|a| a.value().clone())
+                    .unwrap_or_default();
+                output.push(RelayJobStatus {
+                    relay: relay.clone(),
+                    job_id,
+                    reason: job.reason,
+                    started: activity.started,
+                    subscriptions: activity.subscriptions,
+                    filter_summary: activity.filter_summary,
+                    events_received: activity.events_received,
+                });
+            }
+        }
+        output.sort_by(|a, b| a.relay.cmp(&b.relay).then(a.started.cmp(&b.started)));
+        output
+    }
+
     // Which relays should an event be posted to (that it hasn't already been
     // seen on)?
     pub fn relays_for_event(event: &Event) -> Result<Vec<RelayUrl>, Error> {
         let num_relays_per_person = GLOBALS.storage.read_setting_num_relays_per_person();
         let mut relay_urls: Vec<RelayUrl> = Vec::new();
 
-        // Get all of the relays that we write to
+        // Get all of the relays that we write to (or some, if they rotate)
         let write_relay_urls: Vec<RelayUrl> = GLOBALS
             .storage
             .filter_relays(|r| r.has_usage_bits(Relay::WRITE) && r.rank != 0)?
             .iter()
             .map(|relay| relay.url.clone())
             .collect();
-        relay_urls.extend(write_relay_urls);
+        relay_urls.extend(crate::write_rotation::rotate(event.kind, write_relay_urls));
 
         // Get 'read' relays for everybody tagged in the event.
         let mut tagged_pubkeys: Vec<PublicKey> = event
@@ -1,26 +1,73 @@
-use crate::error::Error;
+use crate::comms::ToOverlordMessage;
+use crate::error::{Error, ErrorKind};
 use crate::globals::GLOBALS;
 use nostr_types::{
     ContentEncryptionAlgorithm, DelegationConditions, EncryptedPrivateKey, Event, EventKind,
     EventV1, EventV2, Filter, Id, Identity, KeySecurity, Metadata, PreEvent, PrivateKey, PublicKey,
-    Rumor, RumorV1, RumorV2, Signature,
+    RelayUrl, Rumor, RumorV1, RumorV2, Signature, Tag, Unixtime,
 };
 use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::mpsc::Sender;
 use tokio::task;
+use zeroize::Zeroize;
 
 pub struct GossipIdentity {
     pub inner: RwLock<Identity>,
+
+    // NIP-44 conversation keys by peer, so we don't redo ECDH+HKDF for every decrypt.
+    // These are as sensitive as the private key and are zeroized whenever the key
+    // changes or goes away.
+    conversation_keys: RwLock<HashMap<PublicKey, [u8; 32]>>,
+    // Bumped each time they are cleared, so that a key derived from the previous
+    // private key is not cached after it
+    conversation_key_generation: AtomicU64,
+    conversation_key_hits: AtomicU64,
+    conversation_key_misses: AtomicU64,
+
+    // Keys to authenticate (NIP-42) to particular relays with, instead of the main key.
+    // They are encrypted under the same passphrase and unlock along with it.
+    relay_auth: RwLock<HashMap<RelayUrl, Identity>>,
+
+    // Kind 4 DMs the remote signer decrypted for us, so the UI can show them without
+    // waiting on it
+    remote_decrypted: RwLock<HashMap<Id, String>>,
 }
 
 impl Default for GossipIdentity {
     fn default() -> GossipIdentity {
         GossipIdentity {
             inner: RwLock::new(Identity::default()),
+            conversation_keys: RwLock::new(HashMap::new()),
+            conversation_key_generation: AtomicU64::new(0),
+            conversation_key_hits: AtomicU64::new(0),
+            conversation_key_misses: AtomicU64::new(0),
+            relay_auth: RwLock::new(HashMap::new()),
+            remote_decrypted: RwLock::new(HashMap::new()),
         }
     }
 }
 
+/// Statistics about the NIP-44 conversation key cache
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConversationKeyCacheStats {
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl ConversationKeyCacheStats {
+    /// The fraction of lookups served from the cache
+    pub fn hit_rate(&self) -> f32 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            return 0.0;
+        }
+        self.hits as f32 / lookups as f32
+    }
+}
+
 impl GossipIdentity {
     pub(crate) fn load(&self) -> Result<(), Error> {
         let pk = GLOBALS.storage.read_setting_public_key();
@@ -30,6 +77,15 @@ impl GossipIdentity {
             (Some(pk), None) => *self.inner.write() = Identity::Public(pk),
             (None, _) => *self.inner.write() = Identity::None,
         }
+
+        let mut relay_auth = self.relay_auth.write();
+        relay_auth.clear();
+        for (url, pk, epk) in GLOBALS.storage.read_all_relay_auth_keys()?.drain(..) {
+            relay_auth.insert(url, Identity::from_locked_parts(pk, epk));
+        }
+        drop(relay_auth);
+
+        crate::bunker::load()?;
         Ok(())
     }
 
@@ -48,6 +104,8 @@ impl GossipIdentity {
 
     // Any function that changes GossipIdentity and changes the key should run this instead
     fn on_keychange(&self) -> Result<(), Error> {
+        self.clear_conversation_keys();
+        self.remote_decrypted.write().clear();
         self.on_change()?;
         if !matches!(*self.inner.read(), Identity::None) {
             // Rebuild the event tag index if the identity changes
@@ -77,8 +135,40 @@ impl GossipIdentity {
 
         GLOBALS.ui_notes_to_invalidate.write().extend(dms);
 
-        // Index any waiting GiftWraps
-        GLOBALS.storage.index_unindexed_giftwraps()?;
+        // Index any waiting GiftWraps, then process them fully now that they can be
+        // unwrapped (they were only stored when they arrived)
+        let waiting = GLOBALS.storage.index_unindexed_giftwraps()?;
+        if !waiting.is_empty() {
+            task::spawn(async move {
+                tracing::info!(
+                    "Processing {} giftwraps that arrived while locked",
+                    waiting.len()
+                );
+                for id in waiting {
+                    if let Ok(Some(event)) = GLOBALS.storage.read_event(id) {
+                        if let Err(e) =
+                            crate::process::process_new_event(&event, None, None, false, true).await
+                        {
+                            tracing::warn!("Giftwrap {}: {}", id.as_hex_string(), e);
+                        }
+                    }
+                }
+            });
+        }
+
+        // Seal what was written in the clear while we were locked
+        if GLOBALS.storage.get_flag_reseal_needed() && self.storage_key().is_some() {
+            task::spawn_blocking(|| {
+                if let Err(e) = GLOBALS.storage.reseal_private_records() {
+                    tracing::error!("{}", e);
+                }
+            });
+        }
+
+        // The saved wallet is sealed with our identity, or clear with a remote signer
+        if !crate::nwc::is_connected() {
+            let _ = GLOBALS.to_overlord.send(ToOverlordMessage::LoadWallet);
+        }
 
         // Update wait for login condition
         GLOBALS
@@ -95,6 +185,21 @@ impl GossipIdentity {
         Ok(())
     }
 
+    // The remote signer calls this once it is signing for us as this key
+    pub(crate) fn remote_signer_connected(&self, public_key: PublicKey) -> Result<(), Error> {
+        if self.public_key() != Some(public_key) {
+            self.set_public_key(public_key)?;
+        }
+        self.on_unlock()?;
+        Ok(())
+    }
+
+    /// If a remote signer (NIP-46 bunker) signs and decrypts for us, as we have no
+    /// private key
+    pub fn is_remote(&self) -> bool {
+        !self.inner.read().has_private_key() && crate::bunker::is_connected()
+    }
+
     pub(crate) fn clear_public_key(&self) -> Result<(), Error> {
         *self.inner.write() = Identity::None;
         self.on_keychange()?;
@@ -113,7 +218,37 @@ impl GossipIdentity {
 
     pub(crate) async fn change_passphrase(&self, old: &str, new: &str) -> Result<(), Error> {
         let log_n = GLOBALS.storage.read_setting_log_n();
-        self.inner.write().change_passphrase(old, new, log_n)?;
+        let epk = match self.encrypted_private_key() {
+            Some(epk) => epk,
+            None => return Err((ErrorKind::NoPrivateKey, file!(), line!()).into()),
+        };
+
+        // Re-encrypt every key before any is saved, so that a failure leaves them
+        // all under the old passphrase. Relay authentication keys share it.
+        let mut identity = Identity::from_encrypted_private_key(epk, old)?;
+        identity.change_passphrase(old, new, log_n)?;
+        let mut relay_auth: HashMap<RelayUrl, Identity> = HashMap::new();
+        for (url, _, epk) in GLOBALS.storage.read_all_relay_auth_keys()?.drain(..) {
+            let mut auth = Identity::from_encrypted_private_key(epk, old)?;
+            auth.change_passphrase(old, new, log_n)?;
+            relay_auth.insert(url, auth);
+        }
+
+        let mut txn = GLOBALS.storage.get_write_txn()?;
+        GLOBALS
+            .storage
+            .write_encrypted_private_key(identity.encrypted_private_key(), Some(&mut *txn))?;
+        for (url, auth) in relay_auth.iter() {
+            if let (Some(pubkey), Some(epk)) = (auth.public_key(), auth.encrypted_private_key()) {
+                GLOBALS
+                    .storage
+                    .write_relay_auth_key(url, pubkey, epk, Some(&mut *txn))?;
+            }
+        }
+        txn.commit()?;
+
+        *self.inner.write() = identity;
+        *self.relay_auth.write() = relay_auth;
         self.on_keychange()?;
         Ok(())
     }
@@ -138,6 +273,15 @@ impl GossipIdentity {
             }
         }
 
+        // Relay authentication keys share the passphrase
+        for (url, identity) in self.relay_auth.write().iter_mut() {
+            if !identity.is_unlocked() {
+                if let Err(e) = identity.unlock(pass) {
+                    tracing::warn!("Could not unlock the authentication key for {}: {}", url, e);
+                }
+            }
+        }
+
         self.on_unlock()?;
 
         Ok(())
@@ -151,6 +295,17 @@ impl GossipIdentity {
     }
 
     pub(crate) fn delete_identity(&self) -> Result<(), Error> {
+        // Relay authentication keys go with it
+        let relay_auth_keys = GLOBALS.storage.read_all_relay_auth_keys()?;
+        let mut txn = GLOBALS.storage.get_write_txn()?;
+        for (url, _, _) in relay_auth_keys.iter() {
+            GLOBALS
+                .storage
+                .delete_relay_auth_key(url, Some(&mut *txn))?;
+        }
+        txn.commit()?;
+        self.relay_auth.write().clear();
+
         *self.inner.write() = Identity::None;
         self.on_keychange()?;
         Ok(())
@@ -161,7 +316,7 @@ impl GossipIdentity {
     }
 
     pub fn is_unlocked(&self) -> bool {
-        self.inner.read().is_unlocked()
+        self.inner.read().is_unlocked() || self.is_remote()
     }
 
     pub fn public_key(&self) -> Option<PublicKey> {
@@ -176,20 +331,115 @@ impl GossipIdentity {
         Ok(self.inner.read().key_security()?)
     }
 
-    pub fn sign_event(&self, input: PreEvent) -> Result<Event, Error> {
-        Ok(self.inner.read().sign_event(input)?)
+    /// Sign with our private key, or else have the remote signer sign
+    pub async fn sign_event(&self, input: PreEvent) -> Result<Event, Error> {
+        if !self.is_remote() {
+            return self.sign_event_locally(input);
+        }
+        let event = crate::bunker::sign_event(input).await?;
+        crate::reconcile::record_signed(&event);
+        Ok(event)
+    }
+
+    /// Sign with our private key, never waiting on a remote signer
+    pub fn sign_event_locally(&self, input: PreEvent) -> Result<Event, Error> {
+        let event = self.inner.read().sign_event(input)?;
+        crate::reconcile::record_signed(&event);
+        Ok(event)
     }
 
-    pub fn sign_event_with_pow(
+    /// Mine proof of work into the event here, then sign it as
+    /// [sign_event](Self::sign_event) does. Mining stops (with an error) when the
+    /// post being prepared is cancelled.
+    pub async fn sign_event_with_pow(
         &self,
         input: PreEvent,
         zero_bits: u8,
         work_sender: Option<Sender<u8>>,
     ) -> Result<Event, Error> {
-        Ok(self
-            .inner
+        let mined = task::spawn_blocking(move || {
+            crate::posting_policy::mine(input, zero_bits, work_sender, &GLOBALS.cancel_posting)
+        })
+        .await??;
+        self.sign_event(mined).await
+    }
+
+    /// The key we authenticate to this relay with, if it is not our identity
+    pub fn relay_auth_public_key(&self, url: &RelayUrl) -> Option<PublicKey> {
+        self.relay_auth.read().get(url).and_then(|i| i.public_key())
+    }
+
+    /// Every relay we authenticate to with a key other than our identity, with that key
+    pub fn relay_auth_public_keys(&self) -> Vec<(RelayUrl, PublicKey)> {
+        let mut output: Vec<(RelayUrl, PublicKey)> = self
+            .relay_auth
             .read()
-            .sign_event_with_pow(input, zero_bits, work_sender)?)
+            .iter()
+            .filter_map(|(url, i)| i.public_key().map(|pk| (url.clone(), pk)))
+            .collect();
+        output.sort_by(|a, b| a.0.cmp(&b.0));
+        output
+    }
+
+    /// Can we sign an authentication for this relay right now
+    pub fn is_unlocked_for_auth(&self, url: &RelayUrl) -> bool {
+        match self.relay_auth.read().get(url) {
+            Some(identity) => identity.is_unlocked(),
+            None => self.is_unlocked(),
+        }
+    }
+
+    /// Sign an authentication event (NIP-42) for this relay, with the relay's own key
+    /// if it has one, or else our identity. The pubkey of the PreEvent is set to match.
+    pub(crate) async fn sign_auth_event(
+        &self,
+        url: &RelayUrl,
+        mut input: PreEvent,
+    ) -> Result<Event, Error> {
+        if let Some(identity) = self.relay_auth.read().get(url) {
+            input.pubkey = identity
+                .public_key()
+                .ok_or_else(|| -> Error { ErrorKind::NoPrivateKeyForAuth(url.clone()).into() })?;
+            return Ok(identity.sign_event(input)?);
+        }
+        self.sign_event(input).await
+    }
+
+    /// Authenticate to this relay with a key of its own from now on. The key is
+    /// encrypted under `pass`, which must be the passphrase of our identity so that it
+    /// unlocks along with it.
+    pub(crate) fn set_relay_auth_key(
+        &self,
+        url: &RelayUrl,
+        pk: PrivateKey,
+        pass: &str,
+    ) -> Result<PublicKey, Error> {
+        match self.encrypted_private_key() {
+            Some(epk) => {
+                // Only to check the passphrase
+                let _ = Identity::from_encrypted_private_key(epk, pass)?;
+            }
+            None => return Err((ErrorKind::NoPrivateKey, file!(), line!()).into()),
+        }
+
+        let log_n = GLOBALS.storage.read_setting_log_n();
+        let identity = Identity::from_private_key(pk, pass, log_n)?;
+        let (pubkey, epk) = match (identity.public_key(), identity.encrypted_private_key()) {
+            (Some(pubkey), Some(epk)) => (pubkey, epk.clone()),
+            _ => return Err((ErrorKind::NoPrivateKey, file!(), line!()).into()),
+        };
+        GLOBALS
+            .storage
+            .write_relay_auth_key(url, pubkey, &epk, None)?;
+        self.relay_auth.write().insert(url.clone(), identity);
+        Ok(pubkey)
+    }
+
+    /// Authenticate to this relay with our identity again
+    pub(crate) fn clear_relay_auth_key(&self, url: &RelayUrl) -> Result<(), Error> {
+        GLOBALS.storage.delete_relay_auth_key(url, None)?;
+        self.relay_auth.write().remove(url);
+        Ok(())
     }
 
     pub fn export_private_key_bech32(&self, pass: &str) -> Result<(String, bool), Error> {
@@ -205,10 +455,54 @@ impl GossipIdentity {
         Ok(self.inner.write().export_private_key_in_hex(pass, log_n)?)
     }
 
-    pub fn unwrap_giftwrap(&self, event: &Event) -> Result<Rumor, Error> {
+    /// Unwrap with our private key, or else have the remote signer unwrap
+    pub async fn unwrap_giftwrap(&self, event: &Event) -> Result<Rumor, Error> {
+        if self.is_remote() {
+            return crate::bunker::unwrap_giftwrap(event).await;
+        }
+        self.unwrap_giftwrap_locally(event)
+    }
+
+    /// The rumor of a giftwrap as kept in storage once it was first unwrapped, or
+    /// else unwrapped with our private key. This never waits on a remote signer,
+    /// so it is what the UI uses.
+    pub fn unwrap_giftwrap_kept(&self, event: &Event) -> Result<Rumor, Error> {
+        if let Some(rumor) = GLOBALS.storage.read_rumor(event.id)? {
+            return Ok(rumor);
+        }
+        self.unwrap_giftwrap_locally(event)
+    }
+
+    /// Unwrap with our private key, never waiting on a remote signer
+    pub fn unwrap_giftwrap_locally(&self, event: &Event) -> Result<Rumor, Error> {
+        // The outer layer is encrypted by a throwaway key so caching doesn't help there,
+        // but the seal is encrypted by the sender, whose conversation key we cache.
+        if event.kind == EventKind::GiftWrap {
+            if let Ok(rumor) = self.unwrap_giftwrap_cached(event) {
+                return Ok(rumor);
+            }
+        }
         Ok(self.inner.read().unwrap_giftwrap(event)?)
     }
 
+    fn unwrap_giftwrap_cached(&self, event: &Event) -> Result<Rumor, Error> {
+        let seal_key = self.inner.read().nip44_conversation_key(&event.pubkey)?;
+        let seal: Event = serde_json::from_str(&nip44::decrypt(&seal_key, &event.content)?)?;
+        if seal.kind != EventKind::Seal {
+            return Err(ErrorKind::General("Giftwrap does not contain a seal".to_owned()).into());
+        }
+        seal.verify(None)?;
+
+        let rumor_key = self.conversation_key(&seal.pubkey)?;
+        let rumor: Rumor = serde_json::from_str(&nip44::decrypt(&rumor_key, &seal.content)?)?;
+        if rumor.pubkey != seal.pubkey {
+            return Err(
+                ErrorKind::General("Rumor was not authored by the sealer".to_owned()).into(),
+            );
+        }
+        Ok(rumor)
+    }
+
     /// @deprecated for migrations only
     pub fn unwrap_giftwrap1(&self, event: &EventV1) -> Result<RumorV1, Error> {
         Ok(self.inner.read().unwrap_giftwrap1(event)?)
@@ -219,36 +513,204 @@ impl GossipIdentity {
         Ok(self.inner.read().unwrap_giftwrap2(event)?)
     }
 
-    pub fn decrypt_event_contents(&self, event: &Event) -> Result<String, Error> {
+    // Who the other side of a kind 4 DM is
+    fn dm_peer(&self, event: &Event) -> PublicKey {
+        if Some(event.pubkey) == self.public_key() {
+            match event.people().first() {
+                Some((pubkey, _, _)) => *pubkey,
+                None => event.pubkey,
+            }
+        } else {
+            event.pubkey
+        }
+    }
+
+    /// Decrypt with our private key, or else have the remote signer decrypt
+    pub async fn decrypt_event_contents(&self, event: &Event) -> Result<String, Error> {
+        if event.kind == EventKind::EncryptedDirectMessage && self.is_remote() {
+            let plaintext = self.decrypt(&self.dm_peer(event), &event.content).await?;
+            self.remote_decrypted
+                .write()
+                .insert(event.id, plaintext.clone());
+            return Ok(plaintext);
+        }
+        self.decrypt_event_contents_locally(event)
+    }
+
+    /// Decrypt with our private key, or else from what the remote signer decrypted
+    /// for us before. On a miss the remote signer is asked in the background and
+    /// the note rerenders once it answers. This never waits, so it is what the UI
+    /// uses.
+    pub fn decrypt_event_contents_cached(&self, event: &Event) -> Result<String, Error> {
+        if event.kind != EventKind::EncryptedDirectMessage || !self.is_remote() {
+            return self.decrypt_event_contents_locally(event);
+        }
+        if let Some(plaintext) = self.remote_decrypted.read().get(&event.id) {
+            return Ok(plaintext.clone());
+        }
+        let event = event.clone();
+        task::spawn(async move {
+            match GLOBALS.identity.decrypt_event_contents(&event).await {
+                Ok(_) => GLOBALS.ui_notes_to_invalidate.write().push(event.id),
+                Err(e) => tracing::warn!("Remote signer: {}", e),
+            }
+        });
+        Err(ErrorKind::RemoteSigner("Waiting for the remote signer".to_owned()).into())
+    }
+
+    /// Decrypt with our private key, never waiting on a remote signer
+    pub fn decrypt_event_contents_locally(&self, event: &Event) -> Result<String, Error> {
+        // Kind 4 DMs from other clients may be NIP-44 encrypted
+        if event.kind == EventKind::EncryptedDirectMessage && !event.content.contains("?iv=") {
+            return self.decrypt_locally(&self.dm_peer(event), &event.content);
+        }
         Ok(self.inner.read().decrypt_event_contents(event)?)
     }
 
-    pub fn decrypt(&self, other: &PublicKey, ciphertext: &str) -> Result<String, Error> {
+    /// Decrypt with our private key, or else have the remote signer decrypt
+    pub async fn decrypt(&self, other: &PublicKey, ciphertext: &str) -> Result<String, Error> {
+        if self.is_remote() {
+            return crate::bunker::decrypt(other, ciphertext).await;
+        }
+        self.decrypt_locally(other, ciphertext)
+    }
+
+    /// Decrypt with our private key, never waiting on a remote signer
+    pub fn decrypt_locally(&self, other: &PublicKey, ciphertext: &str) -> Result<String, Error> {
+        // NIP-04 ciphertexts carry an IV suffix, anything else is NIP-44
+        if !ciphertext.contains("?iv=") {
+            let key = self.conversation_key(other)?;
+            if let Ok(plaintext) = nip44::decrypt(&key, ciphertext) {
+                return Ok(plaintext);
+            }
+        }
         Ok(self.inner.read().decrypt(other, ciphertext)?)
     }
 
     pub fn nip44_conversation_key(&self, other: &PublicKey) -> Result<[u8; 32], Error> {
-        Ok(self.inner.read().nip44_conversation_key(other)?)
+        self.conversation_key(other)
+    }
+
+    /// The key our private records are encrypted at rest with. It is derived from our
+    /// private key (our conversation key with ourselves), so it is only available
+    /// while unlocked, and it is never sent anywhere.
+    pub(crate) fn storage_key(&self) -> Option<[u8; 32]> {
+        use sha2::Digest;
+
+        if !self.is_unlocked() {
+            return None;
+        }
+        let pubkey = self.public_key()?;
+        let mut conversation_key = self.conversation_key(&pubkey).ok()?;
+        let mut hasher = sha2::Sha256::new();
+        hasher.update(b"gossip storage key");
+        hasher.update(conversation_key);
+        conversation_key.zeroize();
+        Some(hasher.finalize().into())
+    }
+
+    // Get a NIP-44 conversation key from the cache, deriving it on a miss
+    fn conversation_key(&self, other: &PublicKey) -> Result<[u8; 32], Error> {
+        if let Some(key) = self.conversation_keys.read().get(other) {
+            self.conversation_key_hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(*key);
+        }
+        self.conversation_key_misses.fetch_add(1, Ordering::Relaxed);
+        let generation = self.conversation_key_generation.load(Ordering::SeqCst);
+        let key = self.inner.read().nip44_conversation_key(other)?;
+        let mut keys = self.conversation_keys.write();
+        if self.conversation_key_generation.load(Ordering::SeqCst) == generation {
+            keys.insert(*other, key);
+        }
+        Ok(key)
+    }
+
+    /// Zeroize and forget all cached NIP-44 conversation keys
+    pub fn clear_conversation_keys(&self) {
+        let mut keys = self.conversation_keys.write();
+        self.conversation_key_generation
+            .fetch_add(1, Ordering::SeqCst);
+        for key in keys.values_mut() {
+            key.zeroize();
+        }
+        keys.clear();
+    }
+
+    /// Statistics about the NIP-44 conversation key cache
+    pub fn conversation_key_cache_stats(&self) -> ConversationKeyCacheStats {
+        ConversationKeyCacheStats {
+            entries: self.conversation_keys.read().len(),
+            hits: self.conversation_key_hits.load(Ordering::Relaxed),
+            misses: self.conversation_key_misses.load(Ordering::Relaxed),
+        }
     }
 
-    pub fn encrypt(
+    /// How the private entries of our lists are encrypted, per the `encrypt_with_nip44`
+    /// setting. Lists too long for NIP-44 fall back to NIP-04. Kind 4 DMs are always
+    /// NIP-04. Content encrypted either way is always decrypted.
+    pub fn outgoing_encryption(&self, plaintext: &str) -> ContentEncryptionAlgorithm {
+        if GLOBALS.storage.read_setting_encrypt_with_nip44()
+            && plaintext.len() <= crate::privacy::NIP44_MAX_PLAINTEXT
+        {
+            ContentEncryptionAlgorithm::Nip44v2
+        } else {
+            ContentEncryptionAlgorithm::Nip04
+        }
+    }
+
+    /// Encrypt with our private key, or else have the remote signer encrypt
+    pub async fn encrypt(
         &self,
         other: &PublicKey,
         plaintext: &str,
         algo: ContentEncryptionAlgorithm,
     ) -> Result<String, Error> {
+        if self.is_remote() {
+            let padded = match algo {
+                ContentEncryptionAlgorithm::Nip44v2 => {
+                    crate::privacy::pad_json_plaintext(plaintext)
+                }
+                _ => None,
+            };
+            return crate::bunker::encrypt(other, padded.as_deref().unwrap_or(plaintext), algo)
+                .await;
+        }
+        self.encrypt_locally(other, plaintext, algo)
+    }
+
+    /// Encrypt with our private key, never waiting on a remote signer
+    pub fn encrypt_locally(
+        &self,
+        other: &PublicKey,
+        plaintext: &str,
+        algo: ContentEncryptionAlgorithm,
+    ) -> Result<String, Error> {
+        if matches!(algo, ContentEncryptionAlgorithm::Nip44v2) {
+            let padded = crate::privacy::pad_json_plaintext(plaintext);
+            let plaintext = padded.as_deref().unwrap_or(plaintext);
+
+            // Use the cached conversation key if we can derive one
+            if let Ok(key) = self.conversation_key(other) {
+                return Ok(nip44::encrypt(&key, plaintext)?);
+            }
+            return Ok(self.inner.read().encrypt(other, plaintext, algo)?);
+        }
         Ok(self.inner.read().encrypt(other, plaintext, algo)?)
     }
 
-    pub fn create_metadata_event(
+    pub async fn create_metadata_event(
         &self,
-        input: PreEvent,
+        mut input: PreEvent,
         metadata: Metadata,
     ) -> Result<Event, Error> {
+        if self.is_remote() {
+            input.content = serde_json::to_string(&metadata)?;
+            return self.sign_event(input).await;
+        }
         Ok(self.inner.read().create_metadata_event(input, metadata)?)
     }
 
-    pub fn create_zap_request_event(
+    pub async fn create_zap_request_event(
         &self,
         recipient_pubkey: PublicKey,
         zapped_event: Option<Id>,
@@ -256,6 +718,29 @@ impl GossipIdentity {
         relays: Vec<String>,
         content: String,
     ) -> Result<Event, Error> {
+        if self.is_remote() {
+            let pubkey = self.public_key().ok_or(ErrorKind::NoPublicKey)?;
+            let mut relays_tag: Vec<&str> = vec!["relays"];
+            relays_tag.extend(relays.iter().map(|r| r.as_str()));
+            let amount = millisatoshis.to_string();
+            let mut tags = vec![
+                Tag::new(&relays_tag),
+                Tag::new(&["amount", &amount]),
+                Tag::new_pubkey(recipient_pubkey, None, None),
+            ];
+            if let Some(id) = zapped_event {
+                tags.push(Tag::new(&["e", &id.as_hex_string()]));
+            }
+            return self
+                .sign_event(PreEvent {
+                    pubkey,
+                    created_at: Unixtime::now().unwrap(),
+                    kind: EventKind::ZapRequest,
+                    tags,
+                    content,
+                })
+                .await;
+        }
         Ok(self.inner.read().create_zap_request_event(
             recipient_pubkey,
             zapped_event,
@@ -270,13 +755,22 @@ impl GossipIdentity {
         delegated_pubkey: PublicKey,
         delegation_conditions: &DelegationConditions,
     ) -> Result<Signature, Error> {
+        if self.is_remote() {
+            return Err(ErrorKind::Delegation(
+                "Delegation is not available with a remote signer".to_owned(),
+            )
+            .into());
+        }
         Ok(self
             .inner
             .read()
             .generate_delegation_signature(delegated_pubkey, delegation_conditions)?)
     }
 
-    pub fn giftwrap(&self, input: PreEvent, pubkey: PublicKey) -> Result<Event, Error> {
+    pub async fn giftwrap(&self, input: PreEvent, pubkey: PublicKey) -> Result<Event, Error> {
+        if self.is_remote() {
+            return crate::bunker::giftwrap(input, pubkey).await;
+        }
         Ok(self.inner.read().giftwrap(input, pubkey)?)
     }
 
@@ -0,0 +1,301 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::people::PersonList;
+use dashmap::DashMap;
+use nostr_types::{Event, EventKind, Id, PublicKey, RelayUrl, Tag};
+
+/// Group type, aliased to the latest version
+pub type Group = crate::storage::types::Group1;
+
+/// Chat messages in a group
+pub const GROUP_CHAT_KIND: u32 = 9;
+
+/// Threads in a group
+pub const GROUP_THREAD_KIND: u32 = 11;
+
+/// Asking to join a group
+pub const GROUP_JOIN_REQUEST_KIND: u32 = 9021;
+
+/// Asking to leave a group
+pub const GROUP_LEAVE_REQUEST_KIND: u32 = 9022;
+
+/// The relay's record of a group's metadata
+pub const GROUP_METADATA_KIND: u32 = 39000;
+
+/// The relay's record of a group's admins
+pub const GROUP_ADMINS_KIND: u32 = 39001;
+
+/// The relay's record of a group's members
+pub const GROUP_MEMBERS_KIND: u32 = 39002;
+
+lazy_static! {
+    // Our join requests that the relay hasn't answered yet, by event id
+    static ref PENDING_JOINS: DashMap<Id, (RelayUrl, String)> = DashMap::new();
+}
+
+/// The kinds of events that make up a group's feed
+pub fn group_content_kinds() -> Vec<EventKind> {
+    vec![
+        EventKind::from(GROUP_CHAT_KIND),
+        EventKind::from(GROUP_THREAD_KIND),
+        EventKind::TextNote,
+    ]
+}
+
+/// The kinds of events in which the relay describes a group
+pub fn group_record_kinds() -> Vec<EventKind> {
+    vec![
+        EventKind::from(GROUP_METADATA_KIND),
+        EventKind::from(GROUP_ADMINS_KIND),
+        EventKind::from(GROUP_MEMBERS_KIND),
+    ]
+}
+
+/// The group an event was posted in: the value of its 'h' tag
+pub fn group_id_of(event: &Event) -> Option<String> {
+    event
+        .tags
+        .iter()
+        .find(|t| t.tagname() == "h" && !t.value().is_empty())
+        .map(|t| t.value().to_owned())
+}
+
+/// The 'h' tag that places an event in a group. Every event sent to a group needs
+/// one, or the relay will reject it.
+pub fn group_tag(id: &str) -> Tag {
+    Tag::new(&["h", id])
+}
+
+// The key a relay signs its records of groups with: the pubkey of its NIP-11
+// document
+fn relay_pubkey(relay: &RelayUrl) -> Result<Option<PublicKey>, Error> {
+    Ok(GLOBALS
+        .storage
+        .read_relay(relay, None)?
+        .and_then(|r| r.nip11)
+        .and_then(|doc| doc.pubkey)
+        .and_then(|pk| PublicKey::try_from_hex_string(pk.as_str(), true).ok()))
+}
+
+// Process calls this for the relay's records of a group. Groups belong to their
+// relay, so these only count as seen on the relay they describe a group of, and
+// signed by it.
+pub(crate) fn process_group_record(event: &Event, seen_on: Option<&RelayUrl>) -> Result<(), Error> {
+    let relay = match seen_on {
+        Some(relay) => relay,
+        None => return Ok(()),
+    };
+    if relay_pubkey(relay)? != Some(event.pubkey) {
+        return Ok(());
+    }
+    let id = match event.parameter() {
+        Some(id) if !id.is_empty() => id,
+        _ => return Ok(()),
+    };
+    let kind = u32::from(event.kind);
+
+    // A member list with us on it answers a join request the relay left pending
+    let mut joined = false;
+    if kind == GROUP_MEMBERS_KIND {
+        if let Some(me) = GLOBALS.identity.public_key() {
+            let listed = event
+                .tags
+                .iter()
+                .any(|t| t.tagname() == "p" && t.value() == me.as_hex_string());
+            if listed {
+                joined = !take_pending_joins(relay, &id).is_empty();
+            }
+        }
+    }
+
+    GLOBALS.storage.modify_group(
+        relay,
+        &id,
+        |group| match kind {
+            GROUP_METADATA_KIND if event.created_at > group.metadata_at => {
+                apply_metadata(group, event);
+                group.metadata_at = event.created_at;
+            }
+            GROUP_ADMINS_KIND if event.created_at > group.admins_at => {
+                group.admins = event
+                    .tags
+                    .iter()
+                    .filter(|t| t.tagname() == "p")
+                    .filter_map(|t| {
+                        let pubkey = PublicKey::try_from_hex_string(t.value(), true).ok()?;
+                        let roles: Vec<String> = (2..)
+                            .map(|i| t.get_index(i))
+                            .take_while(|r| !r.is_empty())
+                            .map(|r| r.to_owned())
+                            .collect();
+                        Some((pubkey, roles))
+                    })
+                    .collect();
+                group.admins_at = event.created_at;
+            }
+            GROUP_MEMBERS_KIND if event.created_at > group.members_at => {
+                group.members = event
+                    .tags
+                    .iter()
+                    .filter(|t| t.tagname() == "p")
+                    .filter_map(|t| PublicKey::try_from_hex_string(t.value(), true).ok())
+                    .collect();
+                group.members_at = event.created_at;
+                if joined {
+                    group.joined = true;
+                }
+            }
+            _ => (),
+        },
+        None,
+    )
+}
+
+// Remember that we asked the relay to let us into a group with `event_id`
+pub(crate) fn join_requested(event_id: Id, relay: RelayUrl, id: String) {
+    PENDING_JOINS.insert(event_id, (relay, id));
+}
+
+// Forget the join requests we have pending for a group, returning them
+fn take_pending_joins(relay: &RelayUrl, id: &str) -> Vec<Id> {
+    let ids: Vec<Id> = PENDING_JOINS
+        .iter()
+        .filter(|e| &e.value().0 == relay && e.value().1 == id)
+        .map(|e| *e.key())
+        .collect();
+    for event_id in ids.iter() {
+        PENDING_JOINS.remove(event_id);
+    }
+    ids
+}
+
+// Minions call this when a relay answers an event we sent it. If it was a join
+// request, the group is joined once the relay accepts it (or says we are already
+// in). Closed groups may only take us in later, which a member list with us on it
+// then says.
+pub(crate) fn join_answered(event_id: Id, ok: bool, message: &str) -> Result<(), Error> {
+    let (relay, id) = match PENDING_JOINS.get(&event_id) {
+        Some(pending) => pending.value().to_owned(),
+        None => return Ok(()),
+    };
+    if ok || message.starts_with("duplicate:") {
+        take_pending_joins(&relay, &id);
+        GLOBALS
+            .storage
+            .modify_group(&relay, &id, |group| group.joined = true, None)?;
+    } else {
+        GLOBALS.status_queue.write().write(format!(
+            "{} did not let you into the group: {}",
+            relay, message
+        ));
+    }
+    Ok(())
+}
+
+fn apply_metadata(group: &mut Group, event: &Event) {
+    group.name = String::new();
+    group.about = String::new();
+    group.picture = String::new();
+    group.public = true;
+    group.open = true;
+    for tag in event.tags.iter() {
+        match tag.tagname() {
+            "name" => group.name = tag.value().to_owned(),
+            "about" => group.about = tag.value().to_owned(),
+            "picture" => group.picture = tag.value().to_owned(),
+            "private" => group.public = false,
+            "closed" => group.open = false,
+            _ => (),
+        }
+    }
+}
+
+/// The groups we have joined, by name
+pub fn joined_groups() -> Result<Vec<Group>, Error> {
+    let mut groups: Vec<Group> = GLOBALS
+        .storage
+        .read_all_groups()?
+        .drain(..)
+        .filter(|g| g.joined)
+        .collect();
+    groups.sort_by(|a, b| {
+        a.display_name()
+            .to_lowercase()
+            .cmp(&b.display_name().to_lowercase())
+    });
+    Ok(groups)
+}
+
+/// The events of a group that we have, newest first. Group ids are only unique
+/// per relay, so events with the same 'h' tag on other relays are left out, as are
+/// those of people we mute.
+pub fn group_feed(relay: &RelayUrl, id: &str) -> Result<Vec<Id>, Error> {
+    let kinds = group_content_kinds();
+    let mut events: Vec<Event> = Vec::new();
+    for event in GLOBALS
+        .storage
+        .find_tagged_events("h", Some(id), |e| kinds.contains(&e.kind), false)?
+        .drain(..)
+    {
+        if GLOBALS
+            .people
+            .is_person_in_list(&event.pubkey, PersonList::Muted)
+        {
+            continue;
+        }
+        if GLOBALS
+            .storage
+            .get_event_seen_on_relay(event.id)?
+            .iter()
+            .any(|(url, _)| url == relay)
+        {
+            events.push(event);
+        }
+    }
+    events.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(b.id.cmp(&a.id)));
+    Ok(events.iter().map(|e| e.id).collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nostr_types::{PreEvent, PrivateKey, Unixtime};
+
+    #[test]
+    fn test_apply_metadata() {
+        let key = PrivateKey::generate();
+        let pre_event = PreEvent {
+            pubkey: key.public_key(),
+            created_at: Unixtime::now().unwrap(),
+            kind: EventKind::from(GROUP_METADATA_KIND),
+            tags: vec![
+                Tag::new(&["d", "pizza"]),
+                Tag::new(&["name", "Pizza lovers"]),
+                Tag::new(&["about", "All about pizza"]),
+                Tag::new(&["private"]),
+                Tag::new(&["open"]),
+            ],
+            content: "".to_owned(),
+        };
+        let id = pre_event.hash().unwrap();
+        let event = Event {
+            id,
+            pubkey: pre_event.pubkey,
+            created_at: pre_event.created_at,
+            kind: pre_event.kind,
+            sig: key.sign_id(id).unwrap(),
+            content: pre_event.content,
+            tags: pre_event.tags,
+        };
+
+        let mut group = Group::new(
+            RelayUrl::try_from_str("wss://groups.example.com").unwrap(),
+            "pizza".to_owned(),
+        );
+        apply_metadata(&mut group, &event);
+        assert_eq!(group.display_name(), "Pizza lovers");
+        assert_eq!(group.about, "All about pizza");
+        assert!(!group.public);
+        assert!(group.open);
+    }
+}
@@ -0,0 +1,259 @@
+use crate::comms::ToOverlordMessage;
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use crate::relay::Relay;
+use crate::relay_connect::RelayWebSocket;
+use futures_util::sink::SinkExt;
+use futures_util::stream::StreamExt;
+use nostr_types::{ClientMessage, Event, Filter, Id, RelayUrl, SubscriptionId, Unixtime};
+use std::collections::HashSet;
+use std::time::Duration;
+use tungstenite::protocol::Message as WsMessage;
+
+// Events asked for at a time. Relays often cap this lower, which is fine, we page
+// until they run out.
+const SWEEP_PAGE_SIZE: usize = 500;
+
+// Stop paging one relay after this many pages
+const MAX_SWEEP_PAGES: usize = 200;
+
+// Give up connecting after this long, or when the relay goes quiet this long
+const SWEEP_TIMEOUT_SECS: u64 = 30;
+
+/// How the sweep of one relay went
+#[derive(Debug, Clone)]
+pub struct SweepRelayResult {
+    pub relay: RelayUrl,
+
+    /// Our events the relay returned
+    pub found: usize,
+
+    /// Why we stopped early, if we did
+    pub error: Option<String>,
+}
+
+/// Progress and results of a history sweep
+#[derive(Debug, Clone, Default)]
+pub struct SweepReport {
+    pub started: Option<Unixtime>,
+
+    /// None while the sweep is still running
+    pub finished: Option<Unixtime>,
+
+    /// The relay being swept now
+    pub current: Option<RelayUrl>,
+
+    /// The relays swept so far
+    pub relays: Vec<SweepRelayResult>,
+
+    /// Distinct events of ours found on any relay, validly signed
+    pub found: usize,
+
+    /// Of those, the ones we did not have and stored
+    pub imported: usize,
+
+    /// If the events found are being sent to our write relays
+    pub rebroadcast: bool,
+}
+
+/// The relays of the `history_sweep_relays` setting
+pub fn sweep_relays() -> Vec<RelayUrl> {
+    GLOBALS
+        .storage
+        .read_setting_history_sweep_relays()
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|s| !s.is_empty())
+        .filter_map(|s| RelayUrl::try_from_str(s).ok())
+        .collect()
+}
+
+/// Ask each of the sweep relays (that we may connect to) for every event we ever
+/// authored, paging back through time, and import the ones we don't have. Events that
+/// are not ours or not validly signed are dropped. If `rebroadcast` is set, what was
+/// found and not since deleted is then sent to those of our write relays that haven't
+/// been seen with it. Progress is published in `GLOBALS.history_sweep`.
+pub async fn sweep_history(rebroadcast: bool) -> Result<SweepReport, Error> {
+    let pubkey = match GLOBALS.identity.public_key() {
+        Some(pk) => pk,
+        None => return Err(ErrorKind::NoPublicKey.into()),
+    };
+
+    let mut report = SweepReport {
+        started: Some(Unixtime::now().unwrap()),
+        rebroadcast,
+        ..Default::default()
+    };
+    *GLOBALS.history_sweep.write() = Some(report.clone());
+
+    let mut seen: HashSet<Id> = HashSet::new();
+    let mut events: Vec<Event> = Vec::new();
+
+    for relay in sweep_relays() {
+        if GLOBALS.read_runstate.borrow().going_offline() {
+            break;
+        }
+        let may_connect = match GLOBALS.storage.read_relay(&relay, None)? {
+            Some(r) => crate::relay_connect::may_connect(&r),
+            None => crate::relay_connect::may_connect(&Relay::new(relay.clone())),
+        };
+        if !may_connect {
+            continue;
+        }
+
+        report.current = Some(relay.clone());
+        *GLOBALS.history_sweep.write() = Some(report.clone());
+
+        let mut result = SweepRelayResult {
+            relay: relay.clone(),
+            found: 0,
+            error: None,
+        };
+
+        let mut filter = Filter::new();
+        filter.add_author(&pubkey.into());
+        if let Err(e) = sweep_relay(&relay, filter, &mut result, |event| {
+            if event.pubkey != pubkey || event.verify(None).is_err() {
+                return;
+            }
+            if seen.insert(event.id) {
+                events.push(event);
+            }
+        })
+        .await
+        {
+            tracing::warn!("History sweep of {} stopped: {}", relay, e);
+            result.error = Some(format!("{}", e));
+        }
+
+        // Import what is new to us
+        for event in events[report.found..].iter() {
+            if GLOBALS.storage.has_event(event.id)? {
+                continue;
+            }
+            crate::process::process_new_event(event, Some(relay.clone()), None, false, false)
+                .await?;
+            // It may have been refused (e.g. deleted or expired)
+            if GLOBALS.storage.has_event(event.id)? {
+                report.imported += 1;
+            }
+        }
+        report.found = events.len();
+        report.relays.push(result);
+        report.current = None;
+        *GLOBALS.history_sweep.write() = Some(report.clone());
+    }
+
+    // Never put back what we deleted
+    if rebroadcast {
+        let mut deleted: HashSet<Id> = HashSet::new();
+        for event in events.iter() {
+            if !GLOBALS.storage.get_deletions(event)?.is_empty() {
+                deleted.insert(event.id);
+            }
+        }
+        events.retain(|e| !deleted.contains(&e.id));
+    }
+
+    if rebroadcast && !events.is_empty() {
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::RebroadcastEvents(events));
+    }
+
+    report.finished = Some(Unixtime::now().unwrap());
+    *GLOBALS.history_sweep.write() = Some(report.clone());
+
+    Ok(report)
+}
+
+// Page back through the events the filter matches on one relay
+async fn sweep_relay<F: FnMut(Event)>(
+    relay: &RelayUrl,
+    mut filter: Filter,
+    result: &mut SweepRelayResult,
+    mut found: F,
+) -> Result<(), Error> {
+    let timeout = Duration::from_secs(SWEEP_TIMEOUT_SECS);
+    let mut websocket = crate::relay_connect::connect(relay, timeout).await?;
+
+    let mut seen: HashSet<Id> = HashSet::new();
+    for page in 0..MAX_SWEEP_PAGES {
+        if GLOBALS.read_runstate.borrow().going_offline() {
+            break;
+        }
+
+        filter.limit = Some(SWEEP_PAGE_SIZE);
+        let subid = SubscriptionId(format!("sweep{}", page));
+        let events = fetch_page(&mut websocket, subid, filter.clone(), timeout).await?;
+
+        // Relays return the events at 'until' too, so a page can repeat some
+        let mut oldest: Option<Unixtime> = None;
+        let mut new = 0;
+        for event in events {
+            if oldest.map(|o| event.created_at < o).unwrap_or(true) {
+                oldest = Some(event.created_at);
+            }
+            if seen.insert(event.id) {
+                new += 1;
+                found(event);
+            }
+        }
+        result.found += new;
+
+        match oldest {
+            Some(oldest) if new > 0 => filter.until = Some(oldest),
+            _ => break,
+        }
+    }
+
+    let _ = websocket.close(None).await;
+
+    Ok(())
+}
+
+// Send one REQ and collect its events, until EOSE, CLOSED or the relay going quiet
+async fn fetch_page(
+    websocket: &mut RelayWebSocket,
+    subid: SubscriptionId,
+    filter: Filter,
+    timeout: Duration,
+) -> Result<Vec<Event>, Error> {
+    let wire = serde_json::to_string(&ClientMessage::Req(subid.clone(), vec![filter]))?;
+    websocket.send(WsMessage::Text(wire)).await?;
+
+    let mut events: Vec<Event> = Vec::new();
+    loop {
+        let message = match tokio::time::timeout(timeout, websocket.next()).await {
+            Err(_) => break, // quiet
+            Ok(None) => break,
+            Ok(Some(message)) => message?,
+        };
+        let text = match message {
+            WsMessage::Text(text) => text,
+            WsMessage::Close(_) => break,
+            _ => continue,
+        };
+
+        let value: serde_json::Value = serde_json::from_str(&text)?;
+        match value.get(0).and_then(|v| v.as_str()).unwrap_or("") {
+            "EVENT" => {
+                let raw = value.get(2).cloned().unwrap_or(serde_json::Value::Null);
+                match serde_json::from_value::<Event>(raw) {
+                    Ok(event) => events.push(event),
+                    Err(e) => tracing::warn!("History sweep event did not parse: {}", e),
+                }
+            }
+            "EOSE" => break,
+            "CLOSED" => {
+                return Err(ErrorKind::General(format!("Relay closed the sweep: {}", text)).into())
+            }
+            _ => (),
+        }
+    }
+
+    if let Ok(wire) = serde_json::to_string(&ClientMessage::Close(subid)) {
+        let _ = websocket.send(WsMessage::Text(wire)).await;
+    }
+
+    Ok(events)
+}
@@ -0,0 +1,305 @@
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use crate::misc::Private;
+use crate::people::{PersonList, PersonListMetadata};
+use crate::relay::Relay;
+use crate::storage::Storage;
+use base64::Engine;
+use nostr_types::{EncryptedPrivateKey, PublicKey, RelayUrl, Unixtime};
+use serde::{Deserialize, Serialize};
+
+// What the first field of a bundle says it is
+const BUNDLE_FORMAT: &str = "gossip-identity-bundle";
+
+// The version written. Bundles of a newer version are refused.
+const BUNDLE_VERSION: u32 = 1;
+
+// NIP-44 plaintexts are limited to 64KiB, so the payload is encrypted in pieces
+const CHUNK_SIZE: usize = 32768;
+
+// The relay usages carried over (not whether we advertise them)
+const BUNDLED_USAGE_BITS: u64 = Relay::READ
+    | Relay::WRITE
+    | Relay::INBOX
+    | Relay::OUTBOX
+    | Relay::DISCOVER
+    | Relay::SPAMSAFE
+    | Relay::SEARCH
+    | Relay::DM;
+
+// The file. Only the key (as ncryptsec) and the npub are readable without the
+// passphrase; the rest is NIP-44 encrypted to ourselves.
+#[derive(Debug, Serialize, Deserialize)]
+struct BundleFile {
+    format: String,
+    version: u32,
+    created_at: i64,
+    npub: String,
+    ncryptsec: String,
+    payload: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct BundlePayload {
+    #[serde(default)]
+    relays: Vec<BundleRelay>,
+    #[serde(default)]
+    lists: Vec<BundleList>,
+
+    /// Setting key and its stored bytes (base64)
+    #[serde(default)]
+    settings: Vec<(String, String)>,
+
+    /// npub and petname
+    #[serde(default)]
+    petnames: Vec<(String, String)>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BundleRelay {
+    url: String,
+    usage_bits: u64,
+    rank: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BundleList {
+    dtag: String,
+    title: String,
+    private: bool,
+    favorite: bool,
+
+    /// npub and if they are in the list privately
+    members: Vec<(String, bool)>,
+}
+
+/// What an import brought in
+#[derive(Debug, Clone, Default)]
+pub struct BundleImport {
+    pub public_key: Option<PublicKey>,
+    pub relays: usize,
+    pub lists: usize,
+    pub people: usize,
+    pub settings: usize,
+}
+
+/// Export our identity as a single self-contained bundle: the private key (as
+/// ncryptsec), our relays, person lists, settings and petnames. Everything but the
+/// key and npub is encrypted to ourselves, so the identity must be unlocked.
+pub fn export_identity_bundle() -> Result<String, Error> {
+    let pubkey = match GLOBALS.identity.public_key() {
+        Some(pk) => pk,
+        None => return Err(ErrorKind::NoPublicKey.into()),
+    };
+    let epk = match GLOBALS.identity.encrypted_private_key() {
+        Some(epk) => epk,
+        None => return Err(ErrorKind::NoPrivateKey.into()),
+    };
+    if !GLOBALS.identity.is_unlocked() {
+        return Err(ErrorKind::General("Unlock your identity to export it".to_owned()).into());
+    }
+
+    let mut payload = BundlePayload::default();
+
+    for relay in GLOBALS
+        .storage
+        .filter_relays(|r| r.has_any_usage_bit() || r.rank != 3)?
+    {
+        payload.relays.push(BundleRelay {
+            url: relay.url.as_str().to_owned(),
+            usage_bits: relay.get_usage_bits() & BUNDLED_USAGE_BITS,
+            rank: relay.rank,
+        });
+    }
+
+    for (list, metadata) in GLOBALS.storage.get_all_person_list_metadata()? {
+        // Smart lists are regenerated from local data
+        if metadata.smart.is_some() {
+            continue;
+        }
+        let members = GLOBALS
+            .storage
+            .get_people_in_list(list)?
+            .iter()
+            .map(|(pk, private)| (pk.as_bech32_string(), private.0))
+            .collect();
+        payload.lists.push(BundleList {
+            dtag: metadata.dtag,
+            title: metadata.title,
+            private: metadata.private.0,
+            favorite: metadata.favorite,
+            members,
+        });
+    }
+
+    let engine = base64::engine::general_purpose::STANDARD;
+    for (key, bytes) in GLOBALS.storage.read_raw_settings()? {
+        // The public key comes with the identity
+        if key != "public_key" {
+            payload.settings.push((key, engine.encode(bytes)));
+        }
+    }
+
+    for person in GLOBALS.storage.filter_people(|p| p.petname.is_some())? {
+        if let Some(petname) = person.petname {
+            payload
+                .petnames
+                .push((person.pubkey.as_bech32_string(), petname));
+        }
+    }
+
+    // Pieces are encrypted as they are (not padded), so they join back exactly
+    let key = GLOBALS.identity.nip44_conversation_key(&pubkey)?;
+    let plaintext = serde_json::to_string(&payload)?;
+    let mut chunks: Vec<String> = Vec::new();
+    let mut rest: &str = &plaintext;
+    while !rest.is_empty() {
+        let mut end = rest.len().min(CHUNK_SIZE);
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        chunks.push(nip44::encrypt(&key, &rest[..end])?);
+        rest = &rest[end..];
+    }
+
+    let file = BundleFile {
+        format: BUNDLE_FORMAT.to_owned(),
+        version: BUNDLE_VERSION,
+        created_at: Unixtime::now().unwrap().0,
+        npub: pubkey.as_bech32_string(),
+        ncryptsec: epk.0,
+        payload: chunks,
+    };
+
+    Ok(serde_json::to_string_pretty(&file)?)
+}
+
+/// Reconstruct an account from an identity bundle. This is meant for a fresh install:
+/// it refuses if a different identity is already set up. The key is imported and
+/// unlocked with the passphrase, then relays, lists, settings and petnames are
+/// written (lists are merged into any that exist).
+pub fn import_identity_bundle(text: &str, password: &str) -> Result<BundleImport, Error> {
+    let file: BundleFile = serde_json::from_str(text)?;
+    if file.format != BUNDLE_FORMAT {
+        return Err(ErrorKind::General("This is not an identity bundle".to_owned()).into());
+    }
+    if file.version > BUNDLE_VERSION {
+        return Err(ErrorKind::General(format!(
+            "This identity bundle is version {}, which needs a newer gossip",
+            file.version
+        ))
+        .into());
+    }
+
+    let pubkey = PublicKey::try_from_bech32_string(&file.npub, true)?;
+    if let Some(existing) = GLOBALS.identity.public_key() {
+        if existing != pubkey {
+            return Err(ErrorKind::General(
+                "A different identity is already set up here".to_owned(),
+            )
+            .into());
+        }
+    }
+
+    // The key first: it decrypts the rest
+    let epk = EncryptedPrivateKey(file.ncryptsec);
+    GLOBALS.identity.set_encrypted_private_key(epk, password)?;
+    if !GLOBALS.identity.is_unlocked() {
+        GLOBALS.identity.unlock(password)?;
+    }
+
+    let key = GLOBALS.identity.nip44_conversation_key(&pubkey)?;
+    let mut plaintext = String::new();
+    for chunk in file.payload.iter() {
+        plaintext.push_str(&nip44::decrypt(&key, chunk)?);
+    }
+    let payload: BundlePayload = serde_json::from_str(&plaintext)?;
+
+    let mut import = BundleImport {
+        public_key: Some(pubkey),
+        ..Default::default()
+    };
+
+    let mut txn = GLOBALS.storage.get_write_txn()?;
+
+    let engine = base64::engine::general_purpose::STANDARD;
+    for (key, value) in payload.settings.iter() {
+        let bytes = match engine.decode(value) {
+            Ok(bytes) => bytes,
+            Err(_) => continue,
+        };
+        // Settings this version doesn't have are skipped
+        if !Storage::is_setting_key(key) {
+            continue;
+        }
+        GLOBALS
+            .storage
+            .write_raw_setting(key, &bytes, Some(&mut *txn))?;
+        import.settings += 1;
+    }
+
+    for bundled in payload.relays.iter() {
+        let url = match RelayUrl::try_from_str(&bundled.url) {
+            Ok(url) => url,
+            Err(_) => continue,
+        };
+        GLOBALS.storage.modify_relay(
+            &url,
+            |relay| {
+                relay.clear_usage_bits(BUNDLED_USAGE_BITS);
+                relay.set_usage_bits(bundled.usage_bits & BUNDLED_USAGE_BITS);
+                relay.rank = bundled.rank;
+            },
+            Some(&mut *txn),
+        )?;
+        import.relays += 1;
+    }
+
+    for bundled in payload.lists.iter() {
+        let list = match bundled.dtag.as_str() {
+            "muted" => PersonList::Muted,
+            "followed" => PersonList::Followed,
+            dtag => match GLOBALS.storage.find_person_list_by_dtag(dtag)? {
+                Some((list, _)) => list,
+                None => {
+                    let metadata = PersonListMetadata {
+                        dtag: bundled.dtag.clone(),
+                        title: bundled.title.clone(),
+                        private: Private(bundled.private),
+                        favorite: bundled.favorite,
+                        ..Default::default()
+                    };
+                    GLOBALS
+                        .storage
+                        .allocate_person_list(&metadata, Some(&mut *txn))?
+                }
+            },
+        };
+        for (npub, private) in bundled.members.iter() {
+            if let Ok(pk) = PublicKey::try_from_bech32_string(npub, true) {
+                GLOBALS.storage.add_person_to_list(
+                    &pk,
+                    list,
+                    Private(*private),
+                    Some(&mut *txn),
+                )?;
+                import.people += 1;
+            }
+        }
+        import.lists += 1;
+    }
+
+    for (npub, petname) in payload.petnames.iter() {
+        if let Ok(pk) = PublicKey::try_from_bech32_string(npub, true) {
+            GLOBALS.storage.modify_person(
+                pk,
+                |p| p.petname = Some(petname.clone()),
+                Some(&mut *txn),
+            )?;
+        }
+    }
+
+    txn.commit()?;
+
+    Ok(import)
+}
@@ -0,0 +1,297 @@
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use nostr_types::{Event, EventKind};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+// User-defined kinds are kept in the app data store under this namespace and key
+const KINDS_NAMESPACE: &str = crate::storage::LIB_NAMESPACE;
+const KINDS_KEY: &str = "kind_registry";
+
+/// How relays store a kind (NIP-01), which follows from the kind number
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum KindCategory {
+    Regular,
+    Replaceable,
+    Ephemeral,
+    Addressable,
+}
+
+impl KindCategory {
+    pub fn of(kind: u32) -> KindCategory {
+        match kind {
+            0 | 3 | 10000..=19999 => KindCategory::Replaceable,
+            20000..=29999 => KindCategory::Ephemeral,
+            30000..=39999 => KindCategory::Addressable,
+            _ => KindCategory::Regular,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KindCategory::Regular => "regular",
+            KindCategory::Replaceable => "replaceable",
+            KindCategory::Ephemeral => "ephemeral",
+            KindCategory::Addressable => "addressable",
+        }
+    }
+}
+
+/// How to show an event of a kind we have no dedicated rendering for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RenderHint {
+    /// The content is text meant for people
+    Text,
+
+    /// The content is long-form markdown
+    LongForm,
+
+    /// The content is not for people; show the 'alt' tag (NIP-31) or a summary
+    Summary,
+}
+
+/// What gossip knows about an event kind
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KindDescriptor {
+    pub kind: u32,
+
+    /// A human-readable name
+    pub name: String,
+
+    pub hint: RenderHint,
+
+    /// False for kinds the user registered
+    #[serde(skip)]
+    pub builtin: bool,
+}
+
+impl KindDescriptor {
+    pub fn category(&self) -> KindCategory {
+        KindCategory::of(self.kind)
+    }
+
+    /// A one-line description of an event of this kind, for when it can't be rendered:
+    /// the kind's name and its 'alt' tag (NIP-31) if it has one
+    pub fn describe_event(&self, event: &Event) -> String {
+        let alt = event
+            .tags
+            .iter()
+            .find(|t| t.tagname() == "alt" && !t.value().is_empty())
+            .map(|t| t.value().to_owned());
+        match alt {
+            Some(alt) => format!("{} (kind {}): {}", self.name, self.kind, alt),
+            None => format!(
+                "{} (kind {}, {})",
+                self.name,
+                self.kind,
+                self.category().as_str()
+            ),
+        }
+    }
+}
+
+// The kinds we know of out of the box
+const BUILTIN_KINDS: &[(u32, &str, RenderHint)] = &[
+    (0, "Profile", RenderHint::Summary),
+    (1, "Text note", RenderHint::Text),
+    (3, "Follow list", RenderHint::Summary),
+    (4, "Encrypted direct message", RenderHint::Summary),
+    (5, "Deletion", RenderHint::Summary),
+    (6, "Repost", RenderHint::Summary),
+    (7, "Reaction", RenderHint::Summary),
+    (8, "Badge award", RenderHint::Summary),
+    (9, "Group chat message", RenderHint::Text),
+    (11, "Group thread", RenderHint::Text),
+    (13, "Seal", RenderHint::Summary),
+    (14, "Chat message", RenderHint::Text),
+    (16, "Generic repost", RenderHint::Summary),
+    (40, "Channel creation", RenderHint::Summary),
+    (41, "Channel metadata", RenderHint::Summary),
+    (42, "Channel message", RenderHint::Text),
+    (43, "Channel hide message", RenderHint::Summary),
+    (44, "Channel mute user", RenderHint::Summary),
+    (1059, "Gift wrap", RenderHint::Summary),
+    (1063, "File metadata", RenderHint::Summary),
+    (1311, "Live chat message", RenderHint::Text),
+    (1984, "Report", RenderHint::Summary),
+    (1985, "Label", RenderHint::Summary),
+    (4550, "Community post approval", RenderHint::Summary),
+    (5001, "Summarization job request", RenderHint::Summary),
+    (5002, "Translation job request", RenderHint::Summary),
+    (5300, "Content discovery job request", RenderHint::Summary),
+    (6001, "Summarization job result", RenderHint::Summary),
+    (6002, "Translation job result", RenderHint::Summary),
+    (6300, "Content discovery job result", RenderHint::Summary),
+    (7000, "Job feedback", RenderHint::Summary),
+    (9021, "Group join request", RenderHint::Summary),
+    (9022, "Group leave request", RenderHint::Summary),
+    (9734, "Zap request", RenderHint::Summary),
+    (9735, "Zap receipt", RenderHint::Summary),
+    (9802, "Highlight", RenderHint::Text),
+    (10000, "Mute list", RenderHint::Summary),
+    (10001, "Pin list", RenderHint::Summary),
+    (10002, "Relay list", RenderHint::Summary),
+    (10003, "Bookmark list", RenderHint::Summary),
+    (10004, "Communities list", RenderHint::Summary),
+    (10005, "Public chats list", RenderHint::Summary),
+    (10006, "Blocked relays list", RenderHint::Summary),
+    (10007, "Search relays list", RenderHint::Summary),
+    (10015, "Interests list", RenderHint::Summary),
+    (10030, "Emoji list", RenderHint::Summary),
+    (10050, "DM relays list", RenderHint::Summary),
+    (13194, "Wallet info", RenderHint::Summary),
+    (22242, "Relay authentication", RenderHint::Summary),
+    (23194, "Wallet request", RenderHint::Summary),
+    (23195, "Wallet response", RenderHint::Summary),
+    (24133, "Nostr Connect", RenderHint::Summary),
+    (27235, "HTTP auth", RenderHint::Summary),
+    (30000, "Follow set", RenderHint::Summary),
+    (30001, "Generic list", RenderHint::Summary),
+    (30002, "Relay set", RenderHint::Summary),
+    (30003, "Bookmark set", RenderHint::Summary),
+    (30008, "Profile badges", RenderHint::Summary),
+    (30009, "Badge definition", RenderHint::Summary),
+    (30017, "Marketplace stall", RenderHint::Summary),
+    (30018, "Marketplace product", RenderHint::Summary),
+    (30023, "Long-form content", RenderHint::LongForm),
+    (30024, "Draft long-form content", RenderHint::LongForm),
+    (30030, "Emoji set", RenderHint::Summary),
+    (30078, "Application data", RenderHint::Summary),
+    (30311, "Live event", RenderHint::Summary),
+    (30315, "User status", RenderHint::Text),
+    (30402, "Classified listing", RenderHint::LongForm),
+    (31922, "Date-based calendar event", RenderHint::Summary),
+    (31923, "Time-based calendar event", RenderHint::Summary),
+    (31989, "Handler recommendation", RenderHint::Summary),
+    (31990, "Handler information", RenderHint::Summary),
+    (34550, "Community definition", RenderHint::Summary),
+    (39000, "Group metadata", RenderHint::Summary),
+    (39001, "Group admins", RenderHint::Summary),
+    (39002, "Group members", RenderHint::Summary),
+];
+
+/// Names, categories and rendering hints for event kinds. User-registered kinds
+/// are persisted in the database and take precedence over the built-in ones.
+#[derive(Debug, Default)]
+pub struct KindRegistry {
+    // Loaded from storage on first use
+    custom: RwLock<Option<Vec<KindDescriptor>>>,
+}
+
+impl KindRegistry {
+    pub(crate) fn new() -> KindRegistry {
+        KindRegistry {
+            custom: RwLock::new(None),
+        }
+    }
+
+    /// What we know of this kind. Kinds nobody registered get a generic name.
+    pub fn describe(&self, kind: EventKind) -> KindDescriptor {
+        self.describe_number(u32::from(kind))
+    }
+
+    /// What we know of this kind number
+    pub fn describe_number(&self, kind: u32) -> KindDescriptor {
+        if let Some(desc) = self.with_custom(|c| c.iter().find(|d| d.kind == kind).cloned()) {
+            return desc;
+        }
+        if let Some((_, name, hint)) = BUILTIN_KINDS.iter().find(|(k, _, _)| *k == kind) {
+            return KindDescriptor {
+                kind,
+                name: (*name).to_owned(),
+                hint: *hint,
+                builtin: true,
+            };
+        }
+        KindDescriptor {
+            kind,
+            name: format!("Kind {}", kind),
+            hint: RenderHint::Summary,
+            builtin: true,
+        }
+    }
+
+    /// Every kind with a name, built-in or registered, in kind order
+    pub fn all(&self) -> Vec<KindDescriptor> {
+        let mut output: Vec<KindDescriptor> = self.with_custom(|c| c.clone());
+        for (kind, _, _) in BUILTIN_KINDS.iter() {
+            if !output.iter().any(|d| d.kind == *kind) {
+                output.push(self.describe_number(*kind));
+            }
+        }
+        output.sort_by_key(|d| d.kind);
+        output
+    }
+
+    /// Register (or replace) the name and rendering hint of a kind
+    pub fn register(&self, kind: u32, name: &str, hint: RenderHint) -> Result<(), Error> {
+        if name.trim().is_empty() {
+            return Err(ErrorKind::General("A kind needs a name".to_owned()).into());
+        }
+        self.modify(|custom| {
+            custom.retain(|d| d.kind != kind);
+            custom.push(KindDescriptor {
+                kind,
+                name: name.trim().to_owned(),
+                hint,
+                builtin: false,
+            });
+        })
+    }
+
+    /// Forget a registered kind (built-in kinds revert to their built-in description)
+    pub fn unregister(&self, kind: u32) -> Result<(), Error> {
+        self.modify(|custom| custom.retain(|d| d.kind != kind))
+    }
+
+    fn with_custom<R>(&self, f: impl FnOnce(&Vec<KindDescriptor>) -> R) -> R {
+        if let Some(custom) = &*self.custom.read() {
+            return f(custom);
+        }
+        let mut guard = self.custom.write();
+        let custom = guard.get_or_insert_with(load);
+        f(custom)
+    }
+
+    fn modify(&self, f: impl FnOnce(&mut Vec<KindDescriptor>)) -> Result<(), Error> {
+        let mut guard = self.custom.write();
+        let custom = guard.get_or_insert_with(load);
+        f(custom);
+        custom.sort_by_key(|d| d.kind);
+        GLOBALS
+            .storage
+            .app_data(KINDS_NAMESPACE)?
+            .put(KINDS_KEY, &*custom)?;
+        Ok(())
+    }
+}
+
+fn load() -> Vec<KindDescriptor> {
+    let loaded = GLOBALS
+        .storage
+        .app_data(KINDS_NAMESPACE)
+        .and_then(|store| store.get::<Vec<KindDescriptor>>(KINDS_KEY));
+    match loaded {
+        Ok(custom) => custom.unwrap_or_default(),
+        Err(e) => {
+            tracing::error!("Could not load registered kinds: {}", e);
+            vec![]
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_kind_category() {
+        assert_eq!(KindCategory::of(1), KindCategory::Regular);
+        assert_eq!(KindCategory::of(0), KindCategory::Replaceable);
+        assert_eq!(KindCategory::of(3), KindCategory::Replaceable);
+        assert_eq!(KindCategory::of(10002), KindCategory::Replaceable);
+        assert_eq!(KindCategory::of(22242), KindCategory::Ephemeral);
+        assert_eq!(KindCategory::of(30023), KindCategory::Addressable);
+        assert_eq!(KindCategory::of(40000), KindCategory::Regular);
+    }
+}
@@ -0,0 +1,162 @@
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use crate::relay::Relay;
+use crate::relay_connect::{connect, may_connect};
+use futures_util::sink::SinkExt;
+use futures_util::stream::StreamExt;
+use nostr_types::{
+    ClientMessage, EventKind, Filter, RelayMessage, RelayUrl, SubscriptionId, Unixtime,
+};
+use std::time::{Duration, Instant};
+use tokio::task;
+use tungstenite::protocol::Message as WsMessage;
+
+// How many relays we probe in each round
+const RELAYS_PER_ROUND: usize = 20;
+
+// Give up on a probe after this long
+const PROBE_TIMEOUT_SECS: u64 = 15;
+
+// Scores are multiplied by this so that the latency bonus (which is always less than
+// this) only breaks ties between relays that would otherwise score the same
+pub(crate) const LATENCY_TIE_BREAK_SCALE: u64 = 16;
+
+/// Measure the round-trip time to a relay: from sending a REQ on a freshly opened
+/// connection until the relay sends EOSE (or anything else in reply).
+///
+/// Connection setup is not included, so this approximates the latency of every
+/// later request.
+pub async fn probe_latency(url: &RelayUrl) -> Result<Duration, Error> {
+    let timeout = Duration::from_secs(PROBE_TIMEOUT_SECS);
+    let mut websocket = connect(url, timeout).await?;
+
+    // Ask for nothing, we only care about how fast the relay answers
+    let mut filter = Filter::new();
+    filter.add_event_kind(EventKind::Metadata);
+    filter.limit = Some(0);
+    let subid = SubscriptionId("latency".to_owned());
+    let wire = serde_json::to_string(&ClientMessage::Req(subid.clone(), vec![filter]))?;
+
+    let start = Instant::now();
+    websocket.send(WsMessage::Text(wire)).await?;
+
+    let result = tokio::time::timeout(timeout, async {
+        while let Some(message) = websocket.next().await {
+            if let WsMessage::Text(s) = message? {
+                match serde_json::from_str::<RelayMessage>(&s) {
+                    // Relays may send an AUTH challenge first; that doesn't answer us
+                    Ok(RelayMessage::Auth(_)) => continue,
+                    _ => return Ok(start.elapsed()),
+                }
+            }
+        }
+        Err::<Duration, Error>(ErrorKind::General("Relay closed the connection".to_owned()).into())
+    })
+    .await?;
+
+    if let Ok(wire) = serde_json::to_string(&ClientMessage::Close(subid)) {
+        let _ = websocket.send(WsMessage::Text(wire)).await;
+    }
+    let _ = websocket.close(None).await;
+
+    result
+}
+
+/// Probe a relay and record the sample (and updated percentiles) in its relay record
+pub async fn probe_and_record(url: &RelayUrl) -> Result<(), Error> {
+    let elapsed = probe_latency(url).await;
+    let now = Unixtime::now().unwrap().0 as u64;
+    GLOBALS.storage.modify_relay(
+        url,
+        |relay| {
+            relay.last_latency_probe_at = Some(now);
+            if let Ok(duration) = &elapsed {
+                relay.add_latency_sample(duration.as_millis() as u64);
+            }
+        },
+        None,
+    )?;
+    elapsed.map(|_| ())
+}
+
+/// A bonus in `0..LATENCY_TIE_BREAK_SCALE` for the relay picker, larger for faster
+/// relays. Relays neither we nor the relay monitors have measured get a middling
+/// bonus.
+pub(crate) fn latency_bonus(relay: &Relay) -> u64 {
+    match relay.effective_latency_ms() {
+        None => LATENCY_TIE_BREAK_SCALE / 2,
+        Some(ms) => (LATENCY_TIE_BREAK_SCALE - 1).saturating_sub(ms / 100),
+    }
+}
+
+// Relays worth probing are the ones we actually use (and may connect to), probed
+// longest-ago first. None while offline.
+fn relays_to_probe() -> Result<Vec<RelayUrl>, Error> {
+    if GLOBALS.storage.read_setting_offline() {
+        return Ok(vec![]);
+    }
+    let interval = GLOBALS
+        .storage
+        .read_setting_relay_latency_probe_interval_mins()
+        * 60;
+    let now = Unixtime::now().unwrap().0 as u64;
+
+    let mut relays = GLOBALS.storage.filter_relays(|r| {
+        r.has_any_usage_bit()
+            && may_connect(r)
+            && r.last_latency_probe_at.unwrap_or(0) + interval <= now
+    })?;
+    relays.sort_by_key(|r| r.last_latency_probe_at.unwrap_or(0));
+    Ok(relays
+        .drain(..)
+        .take(RELAYS_PER_ROUND)
+        .map(|r| r.url)
+        .collect())
+}
+
+pub(crate) fn start() {
+    tracing::info!("Relay latency prober startup");
+
+    task::spawn(async {
+        let mut read_runstate = GLOBALS.read_runstate.clone();
+        read_runstate.mark_unchanged();
+        if read_runstate.borrow().going_offline() {
+            return;
+        }
+
+        // Wait until startup traffic has settled
+        let sleep = tokio::time::sleep(Duration::from_secs(120));
+        tokio::pin!(sleep);
+
+        loop {
+            tokio::select! {
+                _ = &mut sleep => {
+                    sleep.as_mut().reset(tokio::time::Instant::now() + Duration::from_secs(300));
+                },
+                _ = read_runstate.wait_for(|runstate| runstate.going_offline()) => break,
+            }
+
+            if !GLOBALS.storage.read_setting_relay_latency_probing() {
+                continue;
+            }
+
+            let urls = match relays_to_probe() {
+                Ok(urls) => urls,
+                Err(e) => {
+                    tracing::error!("{}", e);
+                    continue;
+                }
+            };
+            for url in urls.iter() {
+                if read_runstate.borrow().going_offline() {
+                    break;
+                }
+                if let Err(e) = probe_and_record(url).await {
+                    tracing::debug!("Latency probe of {} failed: {}", url, e);
+                }
+            }
+        }
+
+        tracing::info!("Relay latency prober shutdown");
+    });
+}
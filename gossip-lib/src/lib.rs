@@ -57,44 +57,139 @@
 //! but will return an `Error` if that happens.  The result instead appears as a side-effect
 //! either in GLOBALS data or in the database.
 //!
+//! Rather than polling GLOBALS for those side-effects, a UI can subscribe to the
+//! [bus](crate::bus) and react to `BusMessage`s such as new feed events, person
+//! updates, relay connection changes, notifications and incoming DMs.
+//!
 //! # Storage
 //!
 //! Besides talking to the `Overlord`, the most common thing a front-end needs to do is interact
 //! with the storage engine. In some cases, the `Overlord` has more complex code for doing this,
 //! but in many cases, you can interact with `GLOBALS.storage` directly.
 
+/// How much the people we follow post, and who has gone dormant
+pub mod activity;
+
+/// Urgency tiers for notifications and the "should alert now" decision
+pub mod alerts;
+
+/// Re-verification of stored events, and quarantine of those that fail
+pub mod audit;
+
+/// Blocked relays (NIP-51 kind 10006 list and local wildcard patterns)
+pub mod blocked_relays;
+
+mod bridge;
+pub use bridge::{bridge_source, is_bridged, BridgeProtocol, BridgeSource};
+
+/// Using a remote signer (NIP-46 bunker) instead of a private key
+pub mod bunker;
+pub use bunker::{BunkerState, BunkerUri};
+
+/// Broadcast bus of state changes for front-ends
+pub mod bus;
+pub use bus::BusMessage;
+
 /// Defines messages sent to the overlord
 pub mod comms;
 
+/// Moderated communities (NIP-72)
+pub mod communities;
+pub use communities::Community;
+
+/// Decks: named multi-column layouts with per-column unread counters
+pub mod deck;
+
 mod delegation;
 pub use delegation::Delegation;
 
+/// Answering DMs automatically while we are away
+pub mod dm_autoreply;
+
 mod dm_channel;
 pub use dm_channel::{DmChannel, DmChannelData};
 
+/// Who may DM us, and message requests from everyone else
+pub mod dm_policy;
+
 // direct quick-temporary communication with relays, without overlord/minion involvement
 pub mod direct;
 
+/// Holding back posts that look like ones we just posted
+pub mod duplicate_post;
+
+/// Asking data vending machines (NIP-90) to do jobs for us
+pub mod dvm;
+pub use dvm::DvmJob;
+
 mod error;
 pub use error::{Error, ErrorKind};
 
+/// Media and links in event content, for filtering feeds
+pub mod event_media;
+
 mod feed;
-pub use feed::{Feed, FeedKind};
+pub use feed::{Feed, FeedKind, FeedSource};
+
+/// Previewing the posts of people without following them
+pub mod feed_preview;
+
+/// Which relays the events of each feed came from
+pub mod feed_relays;
 
 mod fetcher;
-pub use fetcher::Fetcher;
+pub use fetcher::{Fetcher, HostHealth};
 
 mod filter;
 
+/// Rules for following back new followers, with an approval queue and a log
+pub mod follow_back;
+
+/// Reconstructing a lost follow list from local history
+pub mod follow_recovery;
+
 mod globals;
 pub use globals::{Globals, GLOBALS};
 
+/// Relay-based groups (NIP-29)
+pub mod groups;
+pub use groups::Group;
+
+/// Collecting all of our own past events from large relays
+pub mod history_sweep;
+
 mod gossip_identity;
-pub use gossip_identity::GossipIdentity;
+
+/// Moving a whole account (key, relays, lists, settings) to another install
+pub mod identity_bundle;
+pub use gossip_identity::{ConversationKeyCacheStats, GossipIdentity};
+
+/// Names, categories and rendering hints of event kinds
+pub mod kind_registry;
+
+/// Relay latency probing
+pub mod latency;
+
+/// Picking bare nostr links, lightning invoices and cashu tokens out of plain text
+pub mod linkify;
+
+mod list_autopublish;
+
+/// How often the people of each person list are fetched
+pub mod list_refresh;
+
+/// Live activities such as live streams (NIP-53)
+pub mod live;
+pub use live::LiveActivity;
 
 mod media;
 pub use media::Media;
 
+/// An in-memory log buffer and a filter changeable while running
+pub mod logging;
+
+mod maintenance;
+
 mod misc;
 pub use misc::{Freshness, Private, ZapState};
 
@@ -104,9 +199,22 @@ pub mod names;
 /// nip05 handling
 pub mod nip05;
 
+/// NIP-10 thread roles of 'e' tags
+pub mod nip10;
+
+/// NIP-17 private direct messages
+pub mod nip17;
+
 #[allow(dead_code)]
 pub mod nip46;
-pub use nip46::{Nip46Server, Nip46UnconnectedServer};
+pub use nip46::{Nip46Server, Nip46UnconnectedServer, NostrConnectUri, PairingState};
+
+/// Paying zaps with a wallet over Nostr Wallet Connect (NIP-47)
+pub mod nwc;
+pub use nwc::NwcUri;
+
+/// Notifications of our identity and of other accounts we look after, together
+pub mod notifications;
 
 mod overlord;
 pub use overlord::Overlord;
@@ -116,37 +224,111 @@ pub use pending::Pending;
 pub use pending::PendingItem;
 
 mod people;
-pub use people::{hash_person_list_event, People, Person, PersonList, PersonListMetadata};
+pub use people::{
+    hash_person_list_event, ListAutoPublish, ListRefresh, MuteLogEntry, MutedPerson, People,
+    Person, PersonList, PersonListMetadata, SmartList,
+};
+
+/// Exporting person lists to CSV/JSON and importing them back
+pub mod person_list_io;
 
 mod person_relay;
 pub use person_relay::PersonRelay;
 
+/// Posting policies (proof of work, client tag, content length and note splitting)
+pub mod posting_policy;
+
+/// Privacy mode (fingerprint reduction) helpers
+pub mod privacy;
+
 /// Processing incoming events
 pub mod process;
 
 mod profile;
 pub use profile::Profile;
 
+/// What viewing a profile requests from relays
+pub mod profile_fetch;
+
+/// Automatic pruning of old events by age, size and kind
+pub mod pruning;
+
+/// Reaction picker data: our most used reactions and custom emoji
+pub mod reactions;
+
+/// Noticing edits to our replaceable events made by other clients
+pub mod reconcile;
+
 mod relationship;
 
+/// How strong our relationship with each person is, from our interactions
+pub mod relationship_strength;
+
 mod relay;
-pub use relay::Relay;
+pub use relay::{Relay, RelayCapabilities, RelayMonitorReport};
+
+// Short-lived connections of our own to relays, outside of any minion
+mod relay_connect;
+
+/// Named groups of relays that fail over to each other
+pub mod relay_group;
 
 mod relay_picker_hooks;
 pub use relay_picker_hooks::Hooks;
 
+/// Raw queries against a single relay, for inspecting what it holds
+pub mod relay_query;
+
+/// Relay monitor (NIP-66) reports on relays
+pub mod relay_monitor;
+
+/// Capability tests of relays
+pub mod relay_test;
+
+/// Whose media and link previews are loaded without asking
+pub mod remote_content;
+
+/// RSS and Atom feeds, kept as local-only entries
+pub mod rss;
+pub use rss::RssEntry;
+
 mod seeker;
+
+/// Person lists gossip keeps up to date from local data (never published)
+pub mod smart_lists;
 pub use seeker::Seeker;
 
 mod status;
 pub use status::StatusQueue;
 
 mod storage;
+pub use storage::byte_rep;
 pub use storage::types::*;
-pub use storage::Storage;
+pub use storage::{
+    AppData, BackupInfo, CompactionReport, CorruptRecord, EventView, GarbageReport, GlobalContext,
+    JsonlImport, MigrationProgress, NoIdentity, PageCursor, ReadCacheStats, RecordPage, ScanOrder,
+    SlowTxn, Storage, StorageContext, StorageHandle, TableCheck, TableMetrics, TableStats,
+    TimedTxn, TxnSiteStats, VerifyReport, WriteClass, BATCH_MAX_MILLIS, SLOW_TXN_MILLIS,
+};
 
 mod tags;
 
+mod thread;
+pub use thread::{thread_view, ThreadEntry, ThreadLayout};
+
+/// Archiving whole threads with where each event was seen
+pub mod thread_archive;
+
+/// Getting each post to enough distinct relays
+pub mod write_redundancy;
+
+/// Taking turns between our write relays, so no one of them sees every post
+pub mod write_rotation;
+
+/// Checking and totalling zap receipts (NIP-57)
+pub mod zaps;
+pub use zaps::ZapReceipt;
+
 #[macro_use]
 extern crate lazy_static;
 
@@ -206,6 +388,7 @@ pub fn init() -> Result<(), Error> {
         // If we need to rebuild relationships
         if GLOBALS.storage.get_flag_rebuild_relationships_needed()
             || GLOBALS.storage.get_flag_rebuild_indexes_needed()
+            || GLOBALS.storage.get_flag_reseal_needed()
         {
             GLOBALS
                 .wait_for_login
@@ -258,6 +441,24 @@ pub async fn run() {
                     // Start periodic tasks in pending
                     crate::pending::start();
 
+                    // Start the person list auto-publisher
+                    crate::list_autopublish::start();
+
+                    // Start the person list refresher
+                    crate::list_refresh::start();
+
+                    // Start the maintenance scheduler
+                    crate::maintenance::start();
+
+                    // Start the RSS poller
+                    crate::rss::start();
+
+                    // Start the automatic pruner
+                    crate::pruning::start();
+
+                    // Start the relay latency prober
+                    crate::latency::start();
+
                     // Start long-lived subscriptions
                     // (this also does a relay_picker init)
                     let _ = GLOBALS
@@ -279,7 +480,18 @@ pub async fn run() {
     let mut overlord = Overlord::new(overlord_receiver);
     overlord.run().await;
 
-    // Sync storage
+    // Save deck unread counters
+    if let Err(e) = GLOBALS.decks.save() {
+        tracing::error!("{}", e);
+    }
+
+    // Don't leave derived key material in memory
+    GLOBALS.identity.clear_conversation_keys();
+
+    // Write out queued events and sync storage
+    if let Err(e) = GLOBALS.storage.flush_event_batch() {
+        tracing::error!("{}", e);
+    }
     if let Err(e) = GLOBALS.storage.sync() {
         tracing::error!("{}", e);
     } else {
@@ -0,0 +1,129 @@
+use nostr_types::NostrBech32;
+
+/// A piece of text that `ShatteredContent` left as plain, picked apart further
+#[derive(Debug, Clone, PartialEq)]
+pub enum InlineSegment<'a> {
+    /// Just text
+    Text(&'a str),
+
+    /// A bare npub, nprofile, note, nevent or naddr (without the `nostr:` prefix),
+    /// and the text it was read from
+    Nostr(NostrBech32, &'a str),
+
+    /// A lightning invoice (BOLT-11), without any `lightning:` prefix
+    LightningInvoice(&'a str),
+
+    /// A cashu ecash token
+    CashuToken(&'a str),
+}
+
+// Punctuation that may surround a word without being part of it
+const LEADING: &[char] = &['(', '[', '<', '"', '\''];
+const TRAILING: &[char] = &['.', ',', ';', ':', '!', '?', ')', ']', '>', '"', '\''];
+
+// Shorter than this and it is not a real invoice or token, just a word that starts
+// the same way
+const MIN_PAYLOAD_LEN: usize = 20;
+
+/// Find bare nostr bech32 strings, lightning invoices and cashu tokens in plain
+/// text, so they can be shown as profiles, events, pay buttons and redeem buttons.
+/// The text segments of the output, in order, with the others, make up the input.
+pub fn linkify(text: &str) -> Vec<InlineSegment<'_>> {
+    let mut output: Vec<InlineSegment<'_>> = Vec::new();
+    let mut text_start = 0;
+
+    for word in text.split_whitespace() {
+        let word_start = word.as_ptr() as usize - text.as_ptr() as usize;
+        let core = word.trim_start_matches(LEADING);
+        let start = word_start + (word.len() - core.len());
+        let core = core.trim_end_matches(TRAILING);
+
+        if let Some(segment) = classify(core) {
+            if start > text_start {
+                output.push(InlineSegment::Text(&text[text_start..start]));
+            }
+            output.push(segment);
+            text_start = start + core.len();
+        }
+    }
+
+    if text_start < text.len() {
+        output.push(InlineSegment::Text(&text[text_start..]));
+    }
+
+    output
+}
+
+fn classify(word: &str) -> Option<InlineSegment<'_>> {
+    let lower = word.to_ascii_lowercase();
+
+    let invoice = match lower.strip_prefix("lightning:") {
+        Some(_) => &word["lightning:".len()..],
+        None => word,
+    };
+    let invoice_lower = invoice.to_ascii_lowercase();
+    if (invoice_lower.starts_with("lnbc") || invoice_lower.starts_with("lntb"))
+        && invoice.len() >= MIN_PAYLOAD_LEN
+        && invoice.chars().all(|c| c.is_ascii_alphanumeric())
+    {
+        return Some(InlineSegment::LightningInvoice(invoice));
+    }
+
+    if (word.starts_with("cashuA") || word.starts_with("cashuB"))
+        && word.len() >= MIN_PAYLOAD_LEN
+        && word
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '+' | '/' | '='))
+    {
+        return Some(InlineSegment::CashuToken(word));
+    }
+
+    if ["npub1", "nprofile1", "note1", "nevent1", "naddr1"]
+        .iter()
+        .any(|prefix| lower.starts_with(prefix))
+    {
+        if let Some(bech32) = NostrBech32::try_from_string(word) {
+            return Some(InlineSegment::Nostr(bech32, word));
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nostr_types::PrivateKey;
+
+    #[test]
+    fn test_linkify() {
+        let pubkey = PrivateKey::generate().public_key();
+        let npub = pubkey.as_bech32_string();
+        let invoice = "lnbc10u1pjexampleinvoicedata0123456789";
+        let token = "cashuAeyJ0b2tlbiI6W3sibWludCI6Imh0dHBzOi8vbWludCJ9XX0=";
+
+        let text = format!(
+            "Thanks ({})! Pay lightning:{}, or take {}. Not lnbc or npub1nope.",
+            npub, invoice, token
+        );
+        let segments = linkify(&text);
+        assert_eq!(
+            segments,
+            vec![
+                InlineSegment::Text("Thanks ("),
+                InlineSegment::Nostr(NostrBech32::Pubkey(pubkey), &npub),
+                InlineSegment::Text(")! Pay "),
+                InlineSegment::LightningInvoice(invoice),
+                InlineSegment::Text(", or take "),
+                InlineSegment::CashuToken(token),
+                InlineSegment::Text(". Not lnbc or npub1nope."),
+            ]
+        );
+
+        assert_eq!(
+            linkify("just words"),
+            vec![InlineSegment::Text("just words")]
+        );
+        assert!(linkify("").is_empty());
+    }
+}
@@ -0,0 +1,96 @@
+use crate::comms::ToOverlordMessage;
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::people::{ListAutoPublish, PersonList};
+use nostr_types::Unixtime;
+use std::time::Duration;
+use tokio::task;
+use tokio::time::Instant;
+
+// How often we look for lists that are due
+const LOOP_SECS: u64 = 15;
+
+// Lists that publish on edit wait this long after the last edit, so a burst of
+// edits becomes a single event
+const ON_EDIT_SETTLE_SECS: i64 = 30;
+
+// Lists that publish daily wait this long after the last time they were published
+const DAILY_SECS: i64 = 60 * 60 * 24;
+
+// The person lists whose auto-publish policy says they should be published now
+fn lists_due() -> Result<Vec<PersonList>, Error> {
+    // Publishing needs a signature
+    if !GLOBALS.identity.is_unlocked() {
+        return Ok(vec![]);
+    }
+
+    let now = Unixtime::now().unwrap();
+    let mut due: Vec<PersonList> = Vec::new();
+    for (list, metadata) in GLOBALS.storage.get_all_person_list_metadata()?.drain(..) {
+        // Smart lists are never published
+        if metadata.smart.is_some() {
+            continue;
+        }
+
+        // Only lists edited here since they were last published
+        if metadata.last_edit_time <= metadata.event_created_at {
+            continue;
+        }
+
+        let ready = match metadata.auto_publish {
+            ListAutoPublish::Manual => false,
+            ListAutoPublish::OnEdit => now.0 - metadata.last_edit_time.0 >= ON_EDIT_SETTLE_SECS,
+            ListAutoPublish::Daily => now.0 - metadata.event_created_at.0 >= DAILY_SECS,
+        };
+        if !ready {
+            continue;
+        }
+
+        // Nothing to publish if the last event already has these people
+        if GLOBALS.storage.hash_person_list(list)? == crate::people::hash_person_list_event(list)? {
+            continue;
+        }
+
+        due.push(list);
+    }
+
+    Ok(due)
+}
+
+pub(crate) fn start() {
+    tracing::info!("List auto-publisher startup");
+
+    task::spawn(async {
+        let mut read_runstate = GLOBALS.read_runstate.clone();
+        read_runstate.mark_unchanged();
+        if read_runstate.borrow().going_offline() {
+            return;
+        }
+
+        let sleep = tokio::time::sleep(Duration::from_secs(LOOP_SECS));
+        tokio::pin!(sleep);
+
+        loop {
+            tokio::select! {
+                _ = &mut sleep => {
+                    sleep.as_mut().reset(Instant::now() + Duration::from_secs(LOOP_SECS));
+                },
+                _ = read_runstate.wait_for(|runstate| runstate.going_offline()) => break,
+            }
+
+            match lists_due() {
+                Ok(lists) => {
+                    for list in lists {
+                        tracing::info!("Auto-publishing person list {:?}", list);
+                        let _ = GLOBALS
+                            .to_overlord
+                            .send(ToOverlordMessage::PushPersonList(list));
+                    }
+                }
+                Err(e) => tracing::error!("{}", e),
+            }
+        }
+
+        tracing::info!("List auto-publisher shutdown");
+    });
+}
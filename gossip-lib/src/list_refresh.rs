@@ -0,0 +1,110 @@
+use crate::comms::ToOverlordMessage;
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::people::{ListRefresh, PersonList};
+use nostr_types::Unixtime;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::task;
+use tokio::time::Instant;
+
+// How often we look for lists that are due
+const LOOP_SECS: u64 = 60;
+
+// Opening the feed of a list that isn't live refreshes it, unless that was done
+// this recently
+const MIN_REFRESH_SECS: i64 = 60;
+
+lazy_static! {
+    // When each list was last fetched (in memory, so every list is due at startup)
+    static ref LAST_REFRESHED: Mutex<HashMap<PersonList, Unixtime>> = Mutex::new(HashMap::new());
+}
+
+/// When the content of this list's people was last fetched (lists that are not live)
+pub fn last_refreshed(list: PersonList) -> Option<Unixtime> {
+    LAST_REFRESHED.lock().get(&list).copied()
+}
+
+// The overlord calls this as it fetches a list. Returns when it was last fetched.
+pub(crate) fn mark_refreshed(list: PersonList) -> Option<Unixtime> {
+    LAST_REFRESHED.lock().insert(list, Unixtime::now().unwrap())
+}
+
+// Feed calls this when the user opens a list's feed
+pub(crate) fn on_feed_opened(list: PersonList) {
+    let refresh = match GLOBALS.storage.get_person_list_metadata(list) {
+        Ok(Some(metadata)) => metadata.refresh,
+        _ => return,
+    };
+    if refresh == ListRefresh::Live {
+        return;
+    }
+    let now = Unixtime::now().unwrap();
+    if let Some(last) = last_refreshed(list) {
+        if now.0 - last.0 < MIN_REFRESH_SECS {
+            return;
+        }
+    }
+    let _ = GLOBALS
+        .to_overlord
+        .send(ToOverlordMessage::RefreshPersonList(list));
+}
+
+// The lists fetched every so often that are due
+fn lists_due() -> Result<Vec<PersonList>, Error> {
+    let now = Unixtime::now().unwrap();
+    let mut due: Vec<PersonList> = Vec::new();
+    for (list, metadata) in GLOBALS.storage.get_all_person_list_metadata()?.drain(..) {
+        if !list.subscribe() {
+            continue;
+        }
+        if let ListRefresh::EveryMinutes(minutes) = metadata.refresh {
+            let is_due = match last_refreshed(list) {
+                Some(last) => now.0 - last.0 >= minutes.max(1) as i64 * 60,
+                None => true,
+            };
+            if is_due {
+                due.push(list);
+            }
+        }
+    }
+    Ok(due)
+}
+
+pub(crate) fn start() {
+    tracing::info!("List refresher startup");
+
+    task::spawn(async {
+        let mut read_runstate = GLOBALS.read_runstate.clone();
+        read_runstate.mark_unchanged();
+        if read_runstate.borrow().going_offline() {
+            return;
+        }
+
+        let sleep = tokio::time::sleep(Duration::from_secs(LOOP_SECS));
+        tokio::pin!(sleep);
+
+        loop {
+            tokio::select! {
+                _ = &mut sleep => {
+                    sleep.as_mut().reset(Instant::now() + Duration::from_secs(LOOP_SECS));
+                },
+                _ = read_runstate.wait_for(|runstate| runstate.going_offline()) => break,
+            }
+
+            match lists_due() {
+                Ok(lists) => {
+                    for list in lists {
+                        let _ = GLOBALS
+                            .to_overlord
+                            .send(ToOverlordMessage::RefreshPersonList(list));
+                    }
+                }
+                Err(e) => tracing::error!("{}", e),
+            }
+        }
+
+        tracing::info!("List refresher shutdown");
+    });
+}
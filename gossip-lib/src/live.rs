@@ -0,0 +1,282 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::people::PersonList;
+use crate::relationship::RelationshipByAddr;
+use nostr_types::{Event, EventAddr, EventKind, PublicKey, RelayUrl, Signature, Unixtime};
+
+/// LiveActivity type, aliased to the latest version
+pub type LiveActivity = crate::storage::types::LiveActivity1;
+
+/// LiveStatus type, aliased to the latest version
+pub type LiveStatus = crate::storage::types::LiveStatus1;
+
+// NIP-53 lets clients treat an activity that has been live for this long without
+// an update as ended
+const STALE_AFTER_SECS: i64 = 3600;
+
+/// Read a live activity from its announcement (kind 30311). None if the event is
+/// not one, or has no 'd' tag.
+pub fn parse_live_event(event: &Event) -> Option<LiveActivity> {
+    if event.kind != EventKind::LiveEvent {
+        return None;
+    }
+
+    let mut activity = LiveActivity {
+        host: event.pubkey,
+        d: event.parameter()?,
+        event_id: event.id,
+        updated_at: event.created_at,
+        title: String::new(),
+        summary: String::new(),
+        image: String::new(),
+        streaming: String::new(),
+        recording: String::new(),
+        status: LiveStatus::Planned,
+        starts: None,
+        ends: None,
+        participants: Vec::new(),
+        relays: Vec::new(),
+    };
+
+    for tag in event.tags.iter() {
+        match tag.tagname() {
+            "title" => activity.title = tag.value().to_owned(),
+            "summary" => activity.summary = tag.value().to_owned(),
+            "image" => activity.image = tag.value().to_owned(),
+            "streaming" => activity.streaming = tag.value().to_owned(),
+            "recording" => activity.recording = tag.value().to_owned(),
+            "status" => {
+                activity.status = match tag.value() {
+                    "live" => LiveStatus::Live,
+                    "ended" => LiveStatus::Ended,
+                    _ => LiveStatus::Planned,
+                }
+            }
+            "starts" => activity.starts = tag.value().parse::<i64>().ok().map(Unixtime),
+            "ends" => activity.ends = tag.value().parse::<i64>().ok().map(Unixtime),
+            "p" => {
+                if let Ok(pubkey) = PublicKey::try_from_hex_string(tag.value(), true) {
+                    if !activity.participants.iter().any(|(pk, _)| *pk == pubkey) {
+                        activity
+                            .participants
+                            .push((pubkey, tag.get_index(3).to_owned()));
+                    }
+                }
+            }
+            "relays" => {
+                let mut i = 1;
+                while !tag.get_index(i).is_empty() {
+                    if let Ok(url) = RelayUrl::try_from_str(tag.get_index(i)) {
+                        if !activity.relays.contains(&url) {
+                            activity.relays.push(url);
+                        }
+                    }
+                    i += 1;
+                }
+            }
+            _ => (),
+        }
+    }
+
+    Some(activity)
+}
+
+// Process calls this for each live activity announcement
+pub(crate) fn process_live_event(event: &Event) -> Result<(), Error> {
+    if let Some(activity) = parse_live_event(event) {
+        GLOBALS.storage.write_live_activity(&activity, None)?;
+    }
+    Ok(())
+}
+
+/// Is this activity live right now? Activities that say they are live but have not
+/// been updated in an hour are taken to have ended.
+pub fn is_live_now(activity: &LiveActivity) -> bool {
+    activity.status == LiveStatus::Live
+        && Unixtime::now().unwrap().0 - activity.updated_at.0 < STALE_AFTER_SECS
+}
+
+/// The participants of a live activity announcement (kind 30311) that proved they
+/// take part: their p tag carries their signature of the activity's address
+/// (`kind:host:d`), as NIP-53 describes. Anyone can tag anyone, so only these are
+/// known to be in it.
+pub fn proven_participants(event: &Event) -> Vec<PublicKey> {
+    let d = match event.parameter() {
+        Some(d) => d,
+        None => return vec![],
+    };
+    let address = format!(
+        "{}:{}:{}",
+        u32::from(event.kind),
+        event.pubkey.as_hex_string(),
+        d
+    );
+
+    let mut output: Vec<PublicKey> = Vec::new();
+    for tag in event.tags.iter() {
+        if tag.tagname() != "p" {
+            continue;
+        }
+        let pubkey = match PublicKey::try_from_hex_string(tag.value(), true) {
+            Ok(pubkey) => pubkey,
+            Err(_) => continue,
+        };
+        let proof = match Signature::try_from_hex_string(tag.get_index(4)) {
+            Ok(proof) => proof,
+            Err(_) => continue,
+        };
+        if pubkey.verify(address.as_bytes(), &proof).is_ok() && !output.contains(&pubkey) {
+            output.push(pubkey);
+        }
+    }
+    output
+}
+
+/// Activities that are live right now, hosted by or (with proof) featuring someone
+/// we follow, most recently updated first
+pub fn live_now_from_follows() -> Result<Vec<LiveActivity>, Error> {
+    let followed = |pubkey: &PublicKey| {
+        GLOBALS
+            .people
+            .is_person_in_list(pubkey, PersonList::Followed)
+    };
+    let features_followed = |activity: &LiveActivity| -> bool {
+        if !activity.participants.iter().any(|(pk, _)| followed(pk)) {
+            return false;
+        }
+        // Check their proof in the announcement itself
+        match GLOBALS.storage.read_event(activity.event_id) {
+            Ok(Some(event)) => proven_participants(&event).iter().any(followed),
+            _ => false,
+        }
+    };
+    let mut output: Vec<LiveActivity> = GLOBALS
+        .storage
+        .read_all_live_activities()?
+        .drain(..)
+        .filter(|a| is_live_now(a) && (followed(&a.host) || features_followed(a)))
+        .collect();
+    output.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+    Ok(output)
+}
+
+/// The chat messages (kind 1311) of an activity that we have, oldest first
+pub fn live_chat(addr: &EventAddr) -> Result<Vec<Event>, Error> {
+    let mut output: Vec<Event> = Vec::new();
+    for (id, rel) in GLOBALS.storage.find_relationships_by_addr(addr)? {
+        if rel == RelationshipByAddr::ChatsWithin {
+            if let Some(event) = GLOBALS.storage.read_event(id)? {
+                output.push(event);
+            }
+        }
+    }
+    output.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+    Ok(output)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nostr_types::{PreEvent, PrivateKey, Tag};
+
+    #[test]
+    fn test_parse_live_event() {
+        let key = PrivateKey::generate();
+        let speaker = PrivateKey::generate().public_key();
+        let pre_event = PreEvent {
+            pubkey: key.public_key(),
+            created_at: Unixtime::now().unwrap(),
+            kind: EventKind::LiveEvent,
+            tags: vec![
+                Tag::new(&["d", "stream1"]),
+                Tag::new(&["title", "Building gossip"]),
+                Tag::new(&["streaming", "https://example.com/live.m3u8"]),
+                Tag::new(&["status", "live"]),
+                Tag::new(&["starts", "1700000000"]),
+                Tag::new(&["p", &speaker.as_hex_string(), "", "Speaker"]),
+                Tag::new(&["relays", "wss://relay.example.com", "not a relay"]),
+            ],
+            content: "".to_owned(),
+        };
+        let id = pre_event.hash().unwrap();
+        let event = Event {
+            id,
+            pubkey: pre_event.pubkey,
+            created_at: pre_event.created_at,
+            kind: pre_event.kind,
+            sig: key.sign_id(id).unwrap(),
+            content: pre_event.content,
+            tags: pre_event.tags,
+        };
+
+        let activity = parse_live_event(&event).unwrap();
+        assert_eq!(activity.d, "stream1");
+        assert_eq!(activity.title, "Building gossip");
+        assert_eq!(activity.streaming, "https://example.com/live.m3u8");
+        assert_eq!(activity.status, LiveStatus::Live);
+        assert_eq!(activity.starts, Some(Unixtime(1700000000)));
+        assert_eq!(activity.participants, vec![(speaker, "Speaker".to_owned())]);
+        assert_eq!(
+            activity.relays,
+            vec![RelayUrl::try_from_str("wss://relay.example.com").unwrap()]
+        );
+        assert!(is_live_now(&activity));
+
+        // Nobody proved they take part
+        assert!(proven_participants(&event).is_empty());
+    }
+
+    #[test]
+    fn test_proven_participants() {
+        let host = PrivateKey::generate();
+        let speaker = PrivateKey::generate();
+        let stranger = PrivateKey::generate();
+        let address = format!("30311:{}:stream1", host.public_key().as_hex_string());
+        let proof = speaker.sign(address.as_bytes()).unwrap().as_hex_string();
+        let wrong = stranger.sign(address.as_bytes()).unwrap().as_hex_string();
+
+        let pre_event = PreEvent {
+            pubkey: host.public_key(),
+            created_at: Unixtime::now().unwrap(),
+            kind: EventKind::LiveEvent,
+            tags: vec![
+                Tag::new(&["d", "stream1"]),
+                Tag::new(&[
+                    "p",
+                    &speaker.public_key().as_hex_string(),
+                    "",
+                    "Speaker",
+                    &proof,
+                ]),
+                // A proof made by someone else doesn't count
+                Tag::new(&[
+                    "p",
+                    &stranger.public_key().as_hex_string(),
+                    "",
+                    "Host",
+                    &proof,
+                ]),
+                Tag::new(&[
+                    "p",
+                    &PrivateKey::generate().public_key().as_hex_string(),
+                    "",
+                    "Guest",
+                    &wrong,
+                ]),
+            ],
+            content: "".to_owned(),
+        };
+        let id = pre_event.hash().unwrap();
+        let event = Event {
+            id,
+            pubkey: pre_event.pubkey,
+            created_at: pre_event.created_at,
+            kind: pre_event.kind,
+            sig: host.sign_id(id).unwrap(),
+            content: pre_event.content,
+            tags: pre_event.tags,
+        };
+
+        assert_eq!(proven_participants(&event), vec![speaker.public_key()]);
+    }
+}
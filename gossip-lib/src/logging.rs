@@ -0,0 +1,105 @@
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use nostr_types::Unixtime;
+use parking_lot::{Mutex, RwLock};
+use std::collections::VecDeque;
+use tracing::Level;
+
+/// How many log lines are kept for in-app log views
+pub const LOG_BUFFER_LINES: usize = 5000;
+
+/// A line of the log
+#[derive(Debug, Clone)]
+pub struct LogLine {
+    pub time: Unixtime,
+    pub level: Level,
+
+    /// Where it was logged from (usually the module path)
+    pub target: String,
+
+    pub message: String,
+}
+
+type FilterReloader = Box<dyn Fn(&str) -> Result<(), String> + Send + Sync>;
+
+lazy_static! {
+    static ref BUFFER: Mutex<VecDeque<LogLine>> =
+        Mutex::new(VecDeque::with_capacity(LOG_BUFFER_LINES));
+    static ref RELOADER: RwLock<Option<FilterReloader>> = RwLock::new(None);
+}
+
+/// Add a line to the log buffer. The frontend's log subscriber calls this for every
+/// event it lets through.
+pub fn push(line: LogLine) {
+    let mut buffer = BUFFER.lock();
+    if buffer.len() >= LOG_BUFFER_LINES {
+        buffer.pop_front();
+    }
+    buffer.push_back(line);
+}
+
+/// The most recent `limit` lines at `level` or more severe, whose target or message
+/// contains `search` (case insensitive), oldest first
+pub fn recent(level: Level, search: &str, limit: usize) -> Vec<LogLine> {
+    let search = search.to_lowercase();
+    let buffer = BUFFER.lock();
+    let mut output: Vec<LogLine> = buffer
+        .iter()
+        .rev()
+        .filter(|line| line.level <= level)
+        .filter(|line| {
+            search.is_empty()
+                || line.target.to_lowercase().contains(&search)
+                || line.message.to_lowercase().contains(&search)
+        })
+        .take(limit)
+        .cloned()
+        .collect();
+    output.reverse();
+    output
+}
+
+/// Empty the log buffer
+pub fn clear() {
+    BUFFER.lock().clear();
+}
+
+/// The frontend owns the log subscriber. It hands us this to change its filter
+/// while running: given directives (as in RUST_LOG, e.g.
+/// `gossip_lib::overlord::minion=debug`) it rebuilds the filter, or says why not.
+pub fn set_filter_reloader(reloader: FilterReloader) {
+    *RELOADER.write() = Some(reloader);
+}
+
+/// The saved filter directives, which apply on top of RUST_LOG
+pub fn filter() -> String {
+    GLOBALS.storage.read_setting_log_filter()
+}
+
+/// Change the log filter now and save it for next time. Empty directives go back
+/// to RUST_LOG alone.
+pub fn set_filter(directives: &str) -> Result<(), Error> {
+    let directives = directives.trim();
+    apply(directives)?;
+    GLOBALS
+        .storage
+        .write_setting_log_filter(&directives.to_owned(), None)?;
+    Ok(())
+}
+
+/// Apply the saved filter (at startup, once the reloader is set)
+pub fn apply_saved_filter() -> Result<(), Error> {
+    let directives = filter();
+    if directives.is_empty() {
+        return Ok(());
+    }
+    apply(&directives)
+}
+
+fn apply(directives: &str) -> Result<(), Error> {
+    match &*RELOADER.read() {
+        Some(reloader) => reloader(directives)
+            .map_err(|e| ErrorKind::General(format!("Invalid log filter: {}", e)).into()),
+        None => Err(ErrorKind::General("The log filter cannot be changed".to_owned()).into()),
+    }
+}
@@ -0,0 +1,73 @@
+use crate::globals::GLOBALS;
+use crate::storage::BATCH_MAX_MILLIS;
+use std::time::Duration;
+use tokio::task;
+use tokio::time::Instant;
+
+// How often the maintenance jobs run
+const LOOP_SECS: u64 = 60;
+
+// One pass of the jobs that keep records up to date while we run
+fn run_jobs() {
+    match GLOBALS.people.unmute_expired() {
+        Ok(0) => (),
+        Ok(count) => tracing::info!("Unmuted {} people whose mute expired", count),
+        Err(e) => tracing::error!("{}", e),
+    }
+
+    if let Err(e) = GLOBALS.storage.sweep_expired() {
+        tracing::error!("{}", e);
+    }
+
+    if let Err(e) = crate::smart_lists::regenerate_if_due() {
+        tracing::error!("{}", e);
+    }
+
+    // Bring relaxed commits to disk
+    if let Err(e) = GLOBALS.storage.sync_if_needed() {
+        tracing::error!("{}", e);
+    }
+}
+
+pub(crate) fn start() {
+    tracing::info!("Maintenance scheduler startup");
+
+    task::spawn(async {
+        let mut read_runstate = GLOBALS.read_runstate.clone();
+        read_runstate.mark_unchanged();
+        if read_runstate.borrow().going_offline() {
+            return;
+        }
+
+        // Mutes and records that expired while we were not running end now
+        run_jobs();
+
+        let sleep = tokio::time::sleep(Duration::from_secs(LOOP_SECS));
+        tokio::pin!(sleep);
+        let mut batch_interval = tokio::time::interval(Duration::from_millis(BATCH_MAX_MILLIS));
+
+        loop {
+            tokio::select! {
+                _ = &mut sleep => {
+                    sleep.as_mut().reset(Instant::now() + Duration::from_secs(LOOP_SECS));
+                },
+                _ = batch_interval.tick() => {
+                    // Queued events are not left waiting for more to come
+                    if let Err(e) = GLOBALS.storage.flush_event_batch_if_due() {
+                        tracing::error!("{}", e);
+                    }
+                    continue;
+                },
+                _ = read_runstate.wait_for(|runstate| runstate.going_offline()) => break,
+            }
+
+            run_jobs();
+        }
+
+        if let Err(e) = GLOBALS.storage.flush_event_batch() {
+            tracing::error!("{}", e);
+        }
+
+        tracing::info!("Maintenance scheduler shutdown");
+    });
+}
@@ -21,6 +21,9 @@ pub struct Media {
     data_temp: DashMap<Url, Vec<u8>>,
     media_pending_processing: DashSet<Url>,
     failed_media: RwLock<HashSet<UncheckedUrl>>,
+
+    // What same_content found, as the UI asks every frame
+    same_content: DashMap<Url, Vec<Url>>,
 }
 
 impl Default for Media {
@@ -36,9 +39,19 @@ impl Media {
             data_temp: DashMap::new(),
             media_pending_processing: DashSet::new(),
             failed_media: RwLock::new(HashSet::new()),
+            same_content: DashMap::new(),
         }
     }
 
+    /// Forget all media held or being fetched, as when switching profiles
+    pub(crate) async fn clear(&self) {
+        self.image_temp.clear();
+        self.data_temp.clear();
+        self.media_pending_processing.clear();
+        self.failed_media.write().await.clear();
+        self.forget_same_content();
+    }
+
     /// Check if a Url is a valid HTTP Url
     pub fn check_url(&self, unchecked_url: UncheckedUrl) -> Option<Url> {
         // Fail permanently if the URL is bad
@@ -61,6 +74,14 @@ impl Media {
     /// Retry a failed Url
     pub fn retry_failed(&self, unchecked_url: &UncheckedUrl) {
         self.failed_media.blocking_write().remove(unchecked_url);
+        if let Ok(url) = Url::try_from_unchecked_url(unchecked_url) {
+            GLOBALS.fetcher.retry_now(&url);
+        }
+    }
+
+    /// Retry every failed Url (see [Fetcher::retry_all_failed](crate::Fetcher::retry_all_failed))
+    pub(crate) fn retry_all_failed(&self) {
+        self.failed_media.blocking_write().clear();
     }
 
     /// Get an image by Url
@@ -162,6 +183,35 @@ impl Media {
             }
         }
     }
+
+    /// Other Urls that served exactly the same content as this one (the same image
+    /// posted via different hosts). Empty until this Url has been fetched.
+    pub fn same_content(&self, url: &Url) -> Vec<Url> {
+        if let Some(same) = self.same_content.get(url) {
+            return same.clone();
+        }
+        // Not fetched yet is remembered too, until something is
+        let urls = match GLOBALS.storage.read_media_hash(url.as_str()) {
+            Ok(Some(hash)) => match GLOBALS.storage.read_media_hash_urls(hash) {
+                Ok(urls) => urls,
+                Err(_) => return vec![],
+            },
+            Ok(None) => vec![],
+            Err(_) => return vec![],
+        };
+        let same: Vec<Url> = urls
+            .iter()
+            .filter(|other| other.as_str() != url.as_str())
+            .filter_map(|other| Url::try_from_unchecked_url(&UncheckedUrl(other.to_owned())).ok())
+            .collect();
+        self.same_content.insert(url.clone(), same.clone());
+        same
+    }
+
+    // Fetching more media may find more of the same content
+    pub(crate) fn forget_same_content(&self) {
+        self.same_content.clear();
+    }
 }
 
 // Note: size is required for SVG which has no inherent size, even if we don't resize
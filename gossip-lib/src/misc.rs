@@ -3,7 +3,7 @@ use crate::globals::GLOBALS;
 use nostr_types::{Event, EventReference, Id, PayRequestData, PublicKey, UncheckedUrl};
 use std::ops::Deref;
 
-/// The state that a Zap is in (it moves through 5 states before it is complete)
+/// The state that a Zap is in (it moves through 5 or 6 states before it is complete)
 #[derive(Debug, Clone)]
 pub enum ZapState {
     None,
@@ -11,6 +11,7 @@ pub enum ZapState {
     SeekingAmount(Id, PublicKey, PayRequestData, UncheckedUrl),
     LoadingInvoice(Id, PublicKey),
     ReadyToPay(Id, String), // String is the Zap Invoice as a string, to be shown as a QR code
+    PayingWithWallet(Id),   // Our wallet (NIP-47) is paying the invoice
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -51,10 +52,10 @@ pub(crate) fn get_event_ancestors(main: EventReference) -> Result<EventAncestors
             if let Some(event) = GLOBALS.storage.read_event_reference(remote)? {
                 ancestors.highest_connected_local = Some(event.clone());
                 ancestors.highest_connected_remote = None;
-                if let Some(parent) = event.replies_to() {
+                if let Some(parent) = crate::nip10::replies_to(&event) {
                     ancestors.highest_connected_remote = Some(parent);
                 }
-                if let Some(root) = event.replies_to_root() {
+                if let Some(root) = crate::nip10::replies_to_root(&event) {
                     ancestors.root_is_local =
                         GLOBALS.storage.read_event_reference(&root)?.is_some();
                     ancestors.root = Some(root);
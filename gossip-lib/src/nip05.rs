@@ -1,3 +1,4 @@
+use crate::bus::BusMessage;
 use crate::error::{Error, ErrorKind};
 use crate::globals::GLOBALS;
 use crate::misc::Private;
@@ -83,6 +84,7 @@ pub async fn validate_nip05(person: Person) -> Result<(), Error> {
 
     // UI cache invalidation (so notes of the person get rerendered)
     GLOBALS.ui_people_to_invalidate.write().push(person.pubkey);
+    crate::bus::send(BusMessage::PersonUpdated(person.pubkey));
 
     if valid {
         update_relays(&nip05, nip05file, &person.pubkey).await?;
@@ -91,6 +93,16 @@ pub async fn validate_nip05(person: Person) -> Result<(), Error> {
     Ok(())
 }
 
+/// Look up the pubkey a NIP-05 identifier points to
+pub async fn resolve_nip05(nip05: &str) -> Result<PublicKey, Error> {
+    let (user, domain) = parse_nip05(nip05)?;
+    let nip05file = fetch_nip05(&user, &domain).await?;
+    match nip05file.names.get(&user) {
+        Some(pk) => Ok(PublicKey::try_from_hex_string(pk, true)?),
+        None => Err((ErrorKind::Nip05KeyNotFound, file!(), line!()).into()),
+    }
+}
+
 pub async fn get_and_follow_nip05(
     nip05: String,
     list: PersonList,
@@ -0,0 +1,377 @@
+use crate::globals::GLOBALS;
+use nostr_types::{Event, EventKind, EventReference, Id, PublicKey, RelayUrl, Tag};
+
+/// The part an 'e' tag plays in placing a note in a thread (NIP-10)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ETagRole {
+    /// The root of the thread. If there is no reply tag, also the note replied to.
+    Root,
+
+    /// The note directly replied to
+    Reply,
+
+    /// Only referenced, does not place the note in a thread
+    Mention,
+}
+
+/// How the role of an 'e' tag was decided
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoleSource {
+    /// From its marker
+    Marker,
+
+    /// From its position among the 'e' tags (the deprecated NIP-10 scheme)
+    Positional,
+
+    /// It repeats a root or reply marker already taken by an earlier tag
+    Duplicate,
+
+    /// It has no marker, and markers are in use (or positional reading is off)
+    Unmarked,
+}
+
+/// An 'e' tag of an event, with the role it was given
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedETag {
+    /// Index of the tag among all the event's tags
+    pub index: usize,
+
+    pub id: Id,
+
+    pub relay: Option<RelayUrl>,
+
+    /// The author pubkey some clients put after the marker
+    pub author: Option<PublicKey>,
+
+    /// The marker as understood (lowercase), if the tag had one
+    pub marker: Option<String>,
+
+    pub role: ETagRole,
+
+    pub source: RoleSource,
+}
+
+/// Parse the 'e' tags of an event and give each a thread role. Markers are read
+/// leniently (any case, or misplaced into the relay slot). When no tag is marked
+/// root or reply and `legacy` is set, unmarked tags are read by position: a lone tag
+/// is the root (and the note replied to), otherwise the first is the root and the
+/// last the reply. Tags whose id does not parse are left out.
+pub fn parse_e_tags(tags: &[Tag], legacy: bool) -> Vec<ParsedETag> {
+    let mut parsed: Vec<ParsedETag> = Vec::new();
+    for (index, tag) in tags.iter().enumerate() {
+        if tag.tagname() != "e" {
+            continue;
+        }
+        let id = match Id::try_from_hex_string(tag.get_index(1).trim()) {
+            Ok(id) => id,
+            Err(_) => continue,
+        };
+        let slot2 = tag.get_index(2).trim();
+        let (relay, marker) = match as_marker(tag.get_index(3)) {
+            Some(marker) => (slot2, Some(marker)),
+            None => match as_marker(slot2) {
+                Some(marker) => ("", Some(marker)), // ["e", <id>, "reply"]
+                None => (slot2, None),
+            },
+        };
+        parsed.push(ParsedETag {
+            index,
+            id,
+            relay: RelayUrl::try_from_str(relay).ok(),
+            author: PublicKey::try_from_hex_string(tag.get_index(4).trim(), true).ok(),
+            marker,
+            role: ETagRole::Mention,
+            source: RoleSource::Unmarked,
+        });
+    }
+
+    let uses_markers = parsed
+        .iter()
+        .any(|p| matches!(p.marker.as_deref(), Some("root") | Some("reply")));
+
+    if uses_markers {
+        let mut have_root = false;
+        let mut have_reply = false;
+        for p in parsed.iter_mut() {
+            let (role, source) = match p.marker.as_deref() {
+                Some("root") if !have_root => {
+                    have_root = true;
+                    (ETagRole::Root, RoleSource::Marker)
+                }
+                Some("reply") if !have_reply => {
+                    have_reply = true;
+                    (ETagRole::Reply, RoleSource::Marker)
+                }
+                Some("mention") => (ETagRole::Mention, RoleSource::Marker),
+                Some(_) => (ETagRole::Mention, RoleSource::Duplicate),
+                None => (ETagRole::Mention, RoleSource::Unmarked),
+            };
+            p.role = role;
+            p.source = source;
+        }
+        return parsed;
+    }
+
+    // Tags explicitly marked as mentions stay mentions either way
+    for p in parsed.iter_mut().filter(|p| p.marker.is_some()) {
+        p.source = RoleSource::Marker;
+    }
+
+    if legacy {
+        let positional: Vec<usize> = parsed
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| p.marker.is_none())
+            .map(|(i, _)| i)
+            .collect();
+        for (n, i) in positional.iter().enumerate() {
+            parsed[*i].source = RoleSource::Positional;
+            parsed[*i].role = if n == 0 {
+                ETagRole::Root
+            } else if n == positional.len() - 1 {
+                ETagRole::Reply
+            } else {
+                ETagRole::Mention
+            };
+        }
+    }
+
+    parsed
+}
+
+/// The root of the thread among parsed 'e' tags
+pub fn root(parsed: &[ParsedETag]) -> Option<&ParsedETag> {
+    parsed.iter().find(|p| p.role == ETagRole::Root)
+}
+
+/// The note replied to among parsed 'e' tags: the reply tag, or else the root
+pub fn reply_target(parsed: &[ParsedETag]) -> Option<&ParsedETag> {
+    parsed
+        .iter()
+        .find(|p| p.role == ETagRole::Reply)
+        .or_else(|| root(parsed))
+}
+
+/// The 'e' tags of an event with their thread roles, as gossip currently reads them
+/// (per the legacy_thread_heuristics setting). Other kinds than text notes have no
+/// NIP-10 roles; their 'e' tags are all given as mentions.
+pub fn e_tag_roles(event: &Event) -> Vec<ParsedETag> {
+    let mut parsed = parse_e_tags(&event.tags, legacy());
+    if event.kind != EventKind::TextNote {
+        for p in parsed.iter_mut() {
+            p.role = ETagRole::Mention;
+            p.source = RoleSource::Unmarked;
+        }
+    }
+    parsed
+}
+
+/// What this event replies to. Text notes are read per NIP-10 (see [parse_e_tags])
+/// with 'a' tags as a fallback; other kinds as nostr-types reads them.
+pub fn replies_to(event: &Event) -> Option<EventReference> {
+    if event.kind != EventKind::TextNote {
+        return event.replies_to();
+    }
+    let parsed = parse_e_tags(&event.tags, legacy());
+    match reply_target(&parsed) {
+        Some(p) => Some(as_reference(p)),
+        None => only_addr(event.replies_to()),
+    }
+}
+
+/// The root of the thread this event is in, read as in [replies_to]
+pub fn replies_to_root(event: &Event) -> Option<EventReference> {
+    if event.kind != EventKind::TextNote {
+        return event.replies_to_root();
+    }
+    let parsed = parse_e_tags(&event.tags, legacy());
+    match root(&parsed) {
+        Some(p) => Some(as_reference(p)),
+        None => only_addr(event.replies_to_root()),
+    }
+}
+
+/// Put the marked root 'e' tag first and the marked reply 'e' tag last among the
+/// 'e' tags, so that clients still reading them by position agree with the markers.
+/// Other tags keep their places.
+pub fn order_e_tags_positionally(tags: &mut [Tag]) {
+    let slots: Vec<usize> = tags
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| t.tagname() == "e")
+        .map(|(i, _)| i)
+        .collect();
+    let mut etags: Vec<Tag> = slots.iter().map(|i| tags[*i].clone()).collect();
+    etags.sort_by_key(|t| match as_marker(t.get_index(3)).as_deref() {
+        Some("root") => 0,
+        Some("reply") => 2,
+        _ => 1,
+    });
+    for (slot, tag) in slots.into_iter().zip(etags) {
+        tags[slot] = tag;
+    }
+}
+
+fn legacy() -> bool {
+    GLOBALS.storage.read_setting_legacy_thread_heuristics()
+}
+
+fn as_marker(s: &str) -> Option<String> {
+    let s = s.trim().to_lowercase();
+    match s.as_str() {
+        "root" | "reply" | "mention" => Some(s),
+        _ => None,
+    }
+}
+
+fn as_reference(p: &ParsedETag) -> EventReference {
+    EventReference::Id {
+        id: p.id,
+        author: p.author,
+        relays: p.relay.iter().cloned().collect(),
+        marker: p.marker.clone(),
+    }
+}
+
+fn only_addr(eref: Option<EventReference>) -> Option<EventReference> {
+    match eref {
+        Some(EventReference::Addr(ea)) => Some(EventReference::Addr(ea)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const A: &str = "1111111111111111111111111111111111111111111111111111111111111111";
+    const B: &str = "2222222222222222222222222222222222222222222222222222222222222222";
+    const C: &str = "3333333333333333333333333333333333333333333333333333333333333333";
+    const PK: &str = "3bf0c63fcb93463407af97a5e5ee64fa883d107ef9e558472c4eb9aaaefa459d";
+
+    fn id(hex: &str) -> Id {
+        Id::try_from_hex_string(hex).unwrap()
+    }
+
+    // (root, reply target) as read
+    fn thread(tags: &[Tag], legacy: bool) -> (Option<Id>, Option<Id>) {
+        let parsed = parse_e_tags(tags, legacy);
+        (
+            root(&parsed).map(|p| p.id),
+            reply_target(&parsed).map(|p| p.id),
+        )
+    }
+
+    #[test]
+    fn test_marked_threads() {
+        // Direct reply to the root: only a root marker
+        let tags = vec![Tag::new(&["e", A, "", "root"])];
+        assert_eq!(thread(&tags, true), (Some(id(A)), Some(id(A))));
+
+        // Nested reply, in either order
+        let tags = vec![
+            Tag::new(&["e", B, "wss://relay.example.com", "reply"]),
+            Tag::new(&["e", A, "", "root"]),
+        ];
+        assert_eq!(thread(&tags, false), (Some(id(A)), Some(id(B))));
+        let parsed = parse_e_tags(&tags, false);
+        assert!(parsed[0].relay.is_some());
+        assert_eq!(parsed[0].source, RoleSource::Marker);
+
+        // Unmarked tags in a marked event are mentions, even with legacy reading
+        let tags = vec![
+            Tag::new(&["e", A, "", "root"]),
+            Tag::new(&["e", C]),
+            Tag::new(&["e", B, "", "reply"]),
+        ];
+        assert_eq!(thread(&tags, true), (Some(id(A)), Some(id(B))));
+        let parsed = parse_e_tags(&tags, true);
+        assert_eq!(parsed[1].role, ETagRole::Mention);
+        assert_eq!(parsed[1].source, RoleSource::Unmarked);
+
+        // A reply marker without a root
+        let tags = vec![Tag::new(&["e", B, "", "reply"])];
+        assert_eq!(thread(&tags, true), (None, Some(id(B))));
+    }
+
+    #[test]
+    fn test_malformed_threads() {
+        // Marker in the relay slot, and in capitals
+        let tags = vec![Tag::new(&["e", A, "Root"]), Tag::new(&["e", B, "REPLY"])];
+        assert_eq!(thread(&tags, false), (Some(id(A)), Some(id(B))));
+
+        // Two roots: the first wins, the second is a duplicate
+        let tags = vec![
+            Tag::new(&["e", A, "", "root"]),
+            Tag::new(&["e", C, "", "root"]),
+            Tag::new(&["e", B, "", "reply"]),
+        ];
+        assert_eq!(thread(&tags, true), (Some(id(A)), Some(id(B))));
+        assert_eq!(parse_e_tags(&tags, true)[1].source, RoleSource::Duplicate);
+
+        // A pubkey where the marker should be is not a marker
+        let tags = vec![Tag::new(&["e", A, "wss://relay.example.com", PK])];
+        assert_eq!(thread(&tags, false), (None, None));
+        assert_eq!(thread(&tags, true), (Some(id(A)), Some(id(A))));
+
+        // Unparseable ids are skipped and don't shift positions
+        let tags = vec![
+            Tag::new(&["e", "not-an-id"]),
+            Tag::new(&["e", A]),
+            Tag::new(&["e", B]),
+        ];
+        assert_eq!(thread(&tags, true), (Some(id(A)), Some(id(B))));
+        assert_eq!(parse_e_tags(&tags, true)[0].index, 1);
+
+        // Author pubkey after the marker
+        let tags = vec![Tag::new(&["e", A, "", "root", PK])];
+        assert!(parse_e_tags(&tags, true)[0].author.is_some());
+    }
+
+    #[test]
+    fn test_positional_threads() {
+        // A lone unmarked tag is the root and the note replied to
+        let tags = vec![Tag::new(&["e", A])];
+        assert_eq!(thread(&tags, true), (Some(id(A)), Some(id(A))));
+        assert_eq!(thread(&tags, false), (None, None));
+
+        // First is root, last is reply, the rest are mentions
+        let tags = vec![
+            Tag::new(&["e", A]),
+            Tag::new(&["e", C]),
+            Tag::new(&["e", B]),
+        ];
+        assert_eq!(thread(&tags, true), (Some(id(A)), Some(id(B))));
+        let parsed = parse_e_tags(&tags, true);
+        assert_eq!(parsed[1].role, ETagRole::Mention);
+        assert_eq!(parsed[1].source, RoleSource::Positional);
+
+        // Mention markers alone don't switch off positional reading
+        let tags = vec![Tag::new(&["e", C, "", "mention"]), Tag::new(&["e", A])];
+        assert_eq!(thread(&tags, true), (Some(id(A)), Some(id(A))));
+        assert_eq!(parse_e_tags(&tags, true)[0].source, RoleSource::Marker);
+    }
+
+    #[test]
+    fn test_order_e_tags_positionally() {
+        let mut tags = vec![
+            Tag::new(&["p", PK]),
+            Tag::new(&["e", B, "", "reply"]),
+            Tag::new(&["e", C, "", "mention"]),
+            Tag::new(&["e", A, "", "root"]),
+        ];
+        order_e_tags_positionally(&mut tags);
+        assert_eq!(tags[0].tagname(), "p");
+        assert_eq!(tags[1].get_index(1), A);
+        assert_eq!(tags[2].get_index(1), C);
+        assert_eq!(tags[3].get_index(1), B);
+
+        // A client ignoring the markers reads the same thread
+        let unmarked: Vec<Tag> = tags
+            .iter()
+            .filter(|t| t.tagname() == "e")
+            .map(|t| Tag::new(&["e", t.get_index(1)]))
+            .collect();
+        assert_eq!(thread(&unmarked, true), (Some(id(A)), Some(id(B))));
+    }
+}
@@ -0,0 +1,113 @@
+use crate::blocked_relays::is_relay_blocked;
+use crate::dm_channel::DmChannel;
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use crate::relay::Relay;
+use nostr_types::{Event, EventKind, Id, PreEvent, PublicKey, RelayUrl, Tag, Unixtime};
+
+/// The relays of a DM relays list (kind 10050)
+pub fn relays_of_list(event: &Event) -> Vec<RelayUrl> {
+    event
+        .tags
+        .iter()
+        .filter(|t| t.tagname() == "relay")
+        .filter_map(|t| RelayUrl::try_from_str(t.value()).ok())
+        .collect()
+}
+
+/// Where this person takes private messages (NIP-17). Ours are the relays with the
+/// DM usage bit, anybody else's those of the latest DM relays list we have of theirs.
+/// Empty if there are none, in which case they should not be sent NIP-17 messages.
+pub fn dm_relays(pubkey: PublicKey) -> Result<Vec<RelayUrl>, Error> {
+    let urls: Vec<RelayUrl> = if Some(pubkey) == GLOBALS.identity.public_key() {
+        GLOBALS
+            .storage
+            .filter_relays(|r| r.has_usage_bits(Relay::DM))?
+            .drain(..)
+            .map(|r| r.url)
+            .collect()
+    } else {
+        match GLOBALS
+            .storage
+            .get_replaceable_event(EventKind::DmRelayList, pubkey, "")?
+        {
+            Some(event) => relays_of_list(&event),
+            None => vec![],
+        }
+    };
+    Ok(urls.into_iter().filter(|u| !is_relay_blocked(u)).collect())
+}
+
+/// If everybody in the channel takes NIP-17 messages
+pub fn can_send(channel: &DmChannel) -> Result<bool, Error> {
+    for pubkey in channel.keys() {
+        if dm_relays(*pubkey)?.is_empty() {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// A message to a channel (a kind 14 rumor), sealed and giftwrapped to each of its
+/// members and to ourselves, along with the relays each wrap goes to. Our own copy
+/// goes to our DM relays, or to our write relays if we have none.
+pub(crate) async fn wrap_message(
+    channel: &DmChannel,
+    content: String,
+    reply_to: Option<Id>,
+) -> Result<Vec<(PublicKey, Event, Vec<RelayUrl>)>, Error> {
+    let my_pubkey = match GLOBALS.identity.public_key() {
+        Some(pk) => pk,
+        None => return Err(ErrorKind::NoPublicKey.into()),
+    };
+
+    // A note to ourselves is tagged to ourselves
+    let mut tags: Vec<Tag> = if channel.keys().is_empty() {
+        vec![Tag::new_pubkey(my_pubkey, None, None)]
+    } else {
+        channel
+            .keys()
+            .iter()
+            .map(|pk| Tag::new_pubkey(*pk, None, None))
+            .collect()
+    };
+    if let Some(id) = reply_to {
+        tags.push(Tag::new(&["e", &id.as_hex_string()]));
+    }
+
+    let rumor = PreEvent {
+        pubkey: my_pubkey,
+        created_at: Unixtime::now().unwrap(),
+        kind: EventKind::DmChat,
+        tags,
+        content,
+    };
+
+    let mut wraps: Vec<(PublicKey, Event, Vec<RelayUrl>)> = Vec::new();
+    for pubkey in channel.keys() {
+        let urls = dm_relays(*pubkey)?;
+        if urls.is_empty() {
+            return Err(ErrorKind::General(format!(
+                "{} has no DM relays",
+                crate::names::best_name_from_pubkey_lookup(pubkey)
+            ))
+            .into());
+        }
+        let wrap = GLOBALS.identity.giftwrap(rumor.clone(), *pubkey).await?;
+        wraps.push((*pubkey, wrap, urls));
+    }
+
+    let mut urls = dm_relays(my_pubkey)?;
+    if urls.is_empty() {
+        urls = GLOBALS
+            .storage
+            .filter_relays(|r| r.has_usage_bits(Relay::WRITE) && r.rank != 0)?
+            .drain(..)
+            .map(|r| r.url)
+            .collect();
+    }
+    let wrap = GLOBALS.identity.giftwrap(rumor, my_pubkey).await?;
+    wraps.push((my_pubkey, wrap, urls));
+
+    Ok(wraps)
+}
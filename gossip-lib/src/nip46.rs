@@ -2,7 +2,8 @@ use crate::comms::ToOverlordMessage;
 use crate::globals::GLOBALS;
 use crate::{Error, ErrorKind};
 use nostr_types::{
-    ContentEncryptionAlgorithm, Event, EventKind, PreEvent, PublicKey, RelayUrl, Tag, Unixtime,
+    ContentEncryptionAlgorithm, Event, EventKind, PreEvent, PrivateKey, PublicKey, RelayUrl, Tag,
+    Unixtime,
 };
 use serde::Deserialize;
 use speedy::{Readable, Writable};
@@ -17,17 +18,8 @@ pub struct Nip46UnconnectedServer {
 
 impl Nip46UnconnectedServer {
     pub fn new(name: String, relays: Vec<RelayUrl>) -> Nip46UnconnectedServer {
-        let mut connect_secret = textnonce::TextNonce::sized_urlsafe(32)
-            .unwrap()
-            .into_string();
-
-        // Map - and _ back into other characters.  We don't care if the result is
-        // uniformly random or not.
-        connect_secret = connect_secret.replace('-', "0");
-        connect_secret = connect_secret.replace('_', "1");
-
         Nip46UnconnectedServer {
-            connect_secret,
+            connect_secret: new_secret(),
             name,
             relays,
         }
@@ -57,35 +49,307 @@ impl Nip46UnconnectedServer {
     }
 }
 
+fn new_secret() -> String {
+    let secret = textnonce::TextNonce::sized_urlsafe(32)
+        .unwrap()
+        .into_string();
+
+    // Map - and _ back into other characters.  We don't care if the result is
+    // uniformly random or not.
+    secret.replace('-', "0").replace('_', "1")
+}
+
+/// A nostrconnect:// URI: a client's offer to pair with a remote signer, usually
+/// shown as a QR code for the signer to scan
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NostrConnectUri {
+    /// The key the client talks to the signer with
+    pub client_pubkey: PublicKey,
+
+    pub relays: Vec<RelayUrl>,
+
+    /// The signer proves it has this URI by answering `connect` with it
+    pub secret: String,
+
+    /// Permissions requested, e.g. `sign_event:1` or `nip44_encrypt`
+    pub perms: Vec<String>,
+
+    /// The client's name, for the signer to show
+    pub name: Option<String>,
+}
+
+impl NostrConnectUri {
+    pub fn as_uri_string(&self) -> String {
+        let mut query = url::form_urlencoded::Serializer::new(String::new());
+        for relay in self.relays.iter() {
+            query.append_pair("relay", relay.as_str());
+        }
+        query.append_pair("secret", &self.secret);
+        if !self.perms.is_empty() {
+            query.append_pair("perms", &self.perms.join(","));
+        }
+        if let Some(name) = &self.name {
+            query.append_pair("name", name);
+        }
+        format!(
+            "nostrconnect://{}?{}",
+            self.client_pubkey.as_hex_string(),
+            query.finish()
+        )
+    }
+
+    pub fn try_from_str(s: &str) -> Result<NostrConnectUri, Error> {
+        let url = match url::Url::parse(s.trim()) {
+            Ok(url) if url.scheme() == "nostrconnect" => url,
+            _ => return Err(ErrorKind::BadNostrConnectString.into()),
+        };
+        let client_pubkey = match url.host_str() {
+            Some(host) => PublicKey::try_from_hex_string(host, true)?,
+            None => return Err(ErrorKind::BadNostrConnectString.into()),
+        };
+
+        let mut relays: Vec<RelayUrl> = Vec::new();
+        let mut secret: Option<String> = None;
+        let mut perms: Vec<String> = Vec::new();
+        let mut name: Option<String> = None;
+        for (key, value) in url.query_pairs() {
+            match &*key {
+                "relay" => relays.push(RelayUrl::try_from_str(&value)?),
+                "secret" => secret = Some(value.into_owned()),
+                "perms" => {
+                    perms = value
+                        .split(',')
+                        .filter(|p| !p.is_empty())
+                        .map(|p| p.to_owned())
+                        .collect()
+                }
+                "name" => name = Some(value.into_owned()),
+                _ => (),
+            }
+        }
+
+        match secret {
+            Some(secret) if !relays.is_empty() => Ok(NostrConnectUri {
+                client_pubkey,
+                relays,
+                secret,
+                perms,
+                name,
+            }),
+            _ => Err(ErrorKind::BadNostrConnectString.into()),
+        }
+    }
+}
+
+/// How long a pairing attempt waits for the signer
+pub const PAIRING_TIMEOUT_SECS: i64 = 600;
+
+/// Where a pairing attempt is at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PairingState {
+    Waiting,
+
+    /// The signer with this key answered with our secret
+    Paired(PublicKey),
+
+    /// The signer refused, or nothing answered in time
+    Failed(String),
+}
+
+/// An attempt to pair with a remote signer by showing it a [NostrConnectUri]
+pub struct Nip46Pairing {
+    pub uri: NostrConnectUri,
+    pub started: Unixtime,
+    pub state: PairingState,
+
+    // Made for this pairing; the signer's answers are encrypted to it
+    client_key: PrivateKey,
+}
+
+/// Start pairing with a remote signer. Show the returned URI to the signer (as a QR
+/// code or text) and watch [pairing_state] for the answer.
+pub fn start_pairing(
+    name: &str,
+    relays: Vec<RelayUrl>,
+    perms: Vec<String>,
+) -> Result<NostrConnectUri, Error> {
+    if relays.is_empty() {
+        return Err(ErrorKind::Nip46RelayNeeded.into());
+    }
+
+    expire_pairings();
+
+    let client_key = PrivateKey::generate();
+    let uri = NostrConnectUri {
+        client_pubkey: client_key.public_key(),
+        relays: relays.clone(),
+        secret: new_secret(),
+        perms,
+        name: if name.is_empty() {
+            None
+        } else {
+            Some(name.to_owned())
+        },
+    };
+    GLOBALS.nip46_pairings.insert(
+        uri.client_pubkey,
+        Nip46Pairing {
+            uri: uri.clone(),
+            started: Unixtime::now().unwrap(),
+            state: PairingState::Waiting,
+            client_key,
+        },
+    );
+
+    // Listen for the answer
+    GLOBALS
+        .to_overlord
+        .send(ToOverlordMessage::SubscribeNip46(relays))?;
+
+    Ok(uri)
+}
+
+/// Where the pairing started with this URI is at, if it is still known
+pub fn pairing_state(client_pubkey: &PublicKey) -> Option<PairingState> {
+    expire_pairings();
+    GLOBALS
+        .nip46_pairings
+        .get(client_pubkey)
+        .map(|pairing| pairing.state.clone())
+}
+
+/// Give up on a pairing attempt (or forget a finished one)
+pub fn cancel_pairing(client_pubkey: &PublicKey) {
+    GLOBALS.nip46_pairings.remove(client_pubkey);
+}
+
+/// Take a finished pairing to use the signer as our remote signer: the signer's key,
+/// the relays and our key for talking to it
+pub(crate) fn take_paired(
+    client_pubkey: &PublicKey,
+) -> Option<(PublicKey, Vec<RelayUrl>, PrivateKey)> {
+    let signer_pubkey = match pairing_state(client_pubkey) {
+        Some(PairingState::Paired(pk)) => pk,
+        _ => return None,
+    };
+    GLOBALS
+        .nip46_pairings
+        .remove(client_pubkey)
+        .map(|(_, pairing)| (signer_pubkey, pairing.uri.relays, pairing.client_key))
+}
+
+/// The client keys of pairings still waiting for an answer
+pub(crate) fn waiting_pairing_pubkeys() -> Vec<PublicKey> {
+    GLOBALS
+        .nip46_pairings
+        .iter()
+        .filter(|pairing| pairing.state == PairingState::Waiting)
+        .map(|pairing| *pairing.key())
+        .collect()
+}
+
+fn expire_pairings() {
+    let now = Unixtime::now().unwrap();
+    for mut pairing in GLOBALS.nip46_pairings.iter_mut() {
+        if pairing.state == PairingState::Waiting
+            && now.0 - pairing.started.0 > PAIRING_TIMEOUT_SECS
+        {
+            pairing.state = PairingState::Failed("No signer answered in time".to_owned());
+        }
+    }
+
+    // Forget about them after a while
+    GLOBALS
+        .nip46_pairings
+        .retain(|_, pairing| now.0 - pairing.started.0 <= PAIRING_TIMEOUT_SECS * 6);
+}
+
+// If this event answers a pairing attempt, settle the attempt and return true
+fn handle_pairing_response(event: &Event) -> Result<bool, Error> {
+    let client_pubkey = match event.tags.iter().find_map(|tag| match tag.parse_pubkey() {
+        Ok((pubkey, _, _)) if GLOBALS.nip46_pairings.contains_key(&pubkey) => Some(pubkey),
+        _ => None,
+    }) {
+        Some(pk) => pk,
+        None => return Ok(false),
+    };
+
+    let mut pairing = match GLOBALS.nip46_pairings.get_mut(&client_pubkey) {
+        Some(pairing) => pairing,
+        None => return Ok(false),
+    };
+    if pairing.state != PairingState::Waiting {
+        return Ok(true);
+    }
+
+    let plaintext = pairing.client_key.decrypt(&event.pubkey, &event.content)?;
+    let response: Nip46Response = serde_json::from_str(&plaintext)?;
+
+    if !response.error.is_empty() {
+        tracing::info!("NIP-46 pairing refused: {}", response.error);
+        pairing.state = PairingState::Failed(response.error);
+    } else if response.result == pairing.uri.secret {
+        tracing::info!(
+            "NIP-46 paired with signer {}",
+            event.pubkey.as_bech32_string()
+        );
+        pairing.state = PairingState::Paired(event.pubkey);
+    } else {
+        // Not the secret, so not from a signer that has our URI
+        tracing::warn!(
+            "Ignoring a NIP-46 pairing answer without our secret from {}",
+            event.pubkey.as_bech32_string()
+        );
+    }
+
+    Ok(true)
+}
+
+#[derive(Debug, Deserialize)]
+struct Nip46Response {
+    #[serde(default)]
+    result: String,
+
+    #[serde(default)]
+    error: String,
+}
+
+/// What the user decided a NIP-46 client may do
 #[derive(Debug, Default, Copy, Clone, Readable, Writable, PartialEq, Eq)]
 pub enum Approval {
+    /// Never (it is refused without asking)
     None,
+
+    /// This once (never stored)
     Once,
+
     Until(Unixtime),
     Always,
+
+    /// Not decided, so ask
     #[default]
     Ask,
 }
 
-impl Approval {
-    fn is_approved(&mut self) -> bool {
-        match self {
-            Approval::None => false,
-            Approval::Once => {
-                *self = Approval::None;
-                true
-            }
-            Approval::Until(time) => {
-                let approved = Unixtime::now().unwrap() < *time;
-                if !approved {
-                    *self = Approval::None;
-                }
-                approved
-            }
-            Approval::Always => true,
-            Approval::Ask => false,
+/// The permission a command needs: its method, or `sign_event:<kind>` to sign an
+/// event of some kind
+pub fn permission_of(cmd: &ParsedCommand) -> String {
+    if cmd.method == "sign_event" {
+        if let Some(kind) = cmd
+            .params
+            .first()
+            .and_then(|p| serde_json::from_str::<Nip46PreEvent>(p).ok())
+            .map(|pre| pre.kind)
+        {
+            return format!("sign_event:{}", u32::from(kind));
         }
     }
+    cmd.method.clone()
+}
+
+// Commands that are answered without asking
+fn needs_permission(method: &str) -> bool {
+    !matches!(method, "connect" | "get_public_key" | "get_relays" | "ping")
 }
 
 #[derive(Debug, Clone, Readable, Writable)]
@@ -93,105 +357,92 @@ pub struct Nip46Server {
     pub name: String,
     pub peer_pubkey: PublicKey,
     pub relays: Vec<RelayUrl>,
+
+    // Superseded by the per-permission decisions of the nip46_permissions table
+    // (see [permission_of]); kept as they are part of the stored record
     pub sign_approval: Approval,
     pub encrypt_approval: Approval,
     pub decrypt_approval: Approval,
 }
 
 impl Nip46Server {
-    pub fn handle(&mut self, cmd: &ParsedCommand) -> Result<(), Error> {
-        let ParsedCommand {
-            ref id,
-            ref method,
-            ref params,
-        } = cmd;
-
-        let result: Result<String, Error> = match method.as_str() {
-            "connect" => Ok("ack".into()),
-            "get_public_key" => self.get_public_key(),
-            "sign_event" => {
-                if self.sign_approval.is_approved() {
-                    self.sign_event(params)
-                } else if self.sign_approval == Approval::Ask {
-                    Err(ErrorKind::Nip46NeedApproval.into())
-                } else {
-                    Err(ErrorKind::Nip46Denied.into())
-                }
-            }
-            "get_relays" => self.get_relays(),
-            "nip04_encrypt" => {
-                if self.encrypt_approval.is_approved() {
-                    self.nip04_encrypt(params)
-                } else if self.encrypt_approval == Approval::Ask {
-                    Err(ErrorKind::Nip46NeedApproval.into())
-                } else {
-                    Err(ErrorKind::Nip46Denied.into())
-                }
+    /// Answer a command, or return [ErrorKind::Nip46NeedApproval] if the user has not
+    /// decided whether this client may do it
+    pub fn handle(&self, cmd: &ParsedCommand) -> Result<(), Error> {
+        if needs_permission(&cmd.method) {
+            match self.approval_for(cmd)? {
+                Approval::Ask => return Err(ErrorKind::Nip46NeedApproval.into()),
+                Approval::None => return self.respond(cmd, Err(ErrorKind::Nip46Denied.into())),
+                _ => (),
             }
-            "nip04_decrypt" => {
-                if self.decrypt_approval.is_approved() {
-                    self.nip04_decrypt(params)
-                } else if self.decrypt_approval == Approval::Ask {
-                    Err(ErrorKind::Nip46NeedApproval.into())
-                } else {
-                    Err(ErrorKind::Nip46Denied.into())
-                }
-            }
-            "nip44_get_key" => {
-                if self.encrypt_approval.is_approved() || self.decrypt_approval.is_approved() {
-                    self.nip44_get_key(params)
-                } else if self.encrypt_approval == Approval::Ask {
-                    Err(ErrorKind::Nip46NeedApproval.into())
-                } else {
-                    Err(ErrorKind::Nip46Denied.into())
-                }
-            }
-            "nip44_encrypt" => {
-                if self.encrypt_approval.is_approved() {
-                    self.nip44_encrypt(params)
-                } else if self.encrypt_approval == Approval::Ask {
-                    Err(ErrorKind::Nip46NeedApproval.into())
-                } else {
-                    Err(ErrorKind::Nip46Denied.into())
-                }
-            }
-            "nip44_decrypt" => {
-                if self.decrypt_approval.is_approved() {
-                    self.nip44_decrypt(params)
-                } else if self.decrypt_approval == Approval::Ask {
-                    Err(ErrorKind::Nip46NeedApproval.into())
-                } else {
-                    Err(ErrorKind::Nip46Denied.into())
-                }
-            }
-            "ping" => self.ping(),
-            _ => Err("unrecognized command".into()),
+        }
+        let result = self.run(cmd);
+        self.respond(cmd, result)
+    }
+
+    /// Answer a command the user was just asked about
+    pub fn handle_decided(&self, cmd: &ParsedCommand, approved: bool) -> Result<(), Error> {
+        let result = if approved {
+            self.run(cmd)
+        } else {
+            Err(ErrorKind::Nip46Denied.into())
         };
+        self.respond(cmd, result)
+    }
 
-        match result {
-            Ok(answer) => send_response(
-                id.to_owned(),
-                answer,
-                "".to_owned(),
-                self.peer_pubkey,
-                self.relays.clone(),
-            )?,
-            Err(e) => {
-                if matches!(e.kind, ErrorKind::Nip46NeedApproval) {
-                    return Err(e);
-                } else {
-                    send_response(
-                        id.to_owned(),
-                        "".to_owned(),
-                        format!("{}", e),
-                        self.peer_pubkey,
-                        self.relays.clone(),
-                    )?;
+    // What the user decided for this command. A decision for the kind of event to
+    // sign wins over one for all signing.
+    fn approval_for(&self, cmd: &ParsedCommand) -> Result<Approval, Error> {
+        let mut permissions = vec![permission_of(cmd)];
+        if permissions[0] != cmd.method {
+            permissions.push(cmd.method.clone());
+        }
+        for permission in permissions.iter() {
+            match GLOBALS
+                .storage
+                .read_nip46_permission(self.peer_pubkey, permission)?
+            {
+                Some(Approval::Until(time)) if Unixtime::now().unwrap() >= time => {
+                    GLOBALS
+                        .storage
+                        .delete_nip46_permission(self.peer_pubkey, permission, None)?;
                 }
+                Some(Approval::Ask) | None => (),
+                Some(approval) => return Ok(approval),
             }
         }
+        Ok(Approval::Ask)
+    }
 
-        Ok(())
+    fn run(&self, cmd: &ParsedCommand) -> Result<String, Error> {
+        let params = &cmd.params;
+        match cmd.method.as_str() {
+            "connect" => Ok("ack".into()),
+            "get_public_key" => self.get_public_key(),
+            "sign_event" => self.sign_event(params),
+            "get_relays" => self.get_relays(),
+            "nip04_encrypt" => self.nip04_encrypt(params),
+            "nip04_decrypt" => self.nip04_decrypt(params),
+            "nip44_get_key" => self.nip44_get_key(params),
+            "nip44_encrypt" => self.nip44_encrypt(params),
+            "nip44_decrypt" => self.nip44_decrypt(params),
+            "ping" => self.ping(),
+            _ => Err("unrecognized command".into()),
+        }
+    }
+
+    fn respond(&self, cmd: &ParsedCommand, result: Result<String, Error>) -> Result<(), Error> {
+        let (answer, error) = match result {
+            Ok(answer) => (answer, "".to_owned()),
+            Err(e) => ("".to_owned(), format!("{}", e)),
+        };
+        send_response(
+            cmd.id.to_owned(),
+            answer,
+            error,
+            self.peer_pubkey,
+            self.relays.clone(),
+        )
     }
 
     fn get_public_key(&self) -> Result<String, Error> {
@@ -227,7 +478,7 @@ impl Nip46Server {
             content,
         };
 
-        let event = GLOBALS.identity.sign_event(pre_event)?;
+        let event = GLOBALS.identity.sign_event_locally(pre_event)?;
 
         let event_str = serde_json::to_string(&event)?;
 
@@ -244,7 +495,7 @@ impl Nip46Server {
             return Err("nip04_encrypt: requires two parameters".into());
         }
         let other_pubkey = PublicKey::try_from_hex_string(&params[0], true)?;
-        let ciphertext = GLOBALS.identity.encrypt(
+        let ciphertext = GLOBALS.identity.encrypt_locally(
             &other_pubkey,
             &params[1],
             ContentEncryptionAlgorithm::Nip04,
@@ -257,7 +508,7 @@ impl Nip46Server {
             return Err("nip04_decrypt: requires two parameters".into());
         }
         let other_pubkey = PublicKey::try_from_hex_string(&params[0], true)?;
-        GLOBALS.identity.decrypt(&other_pubkey, &params[1])
+        GLOBALS.identity.decrypt_locally(&other_pubkey, &params[1])
     }
 
     fn nip44_get_key(&self, params: &[String]) -> Result<String, Error> {
@@ -275,7 +526,7 @@ impl Nip46Server {
             return Err("nip44_encrypt: requires two parameters".into());
         }
         let other_pubkey = PublicKey::try_from_hex_string(&params[0], true)?;
-        let ciphertext = GLOBALS.identity.encrypt(
+        let ciphertext = GLOBALS.identity.encrypt_locally(
             &other_pubkey,
             &params[1],
             ContentEncryptionAlgorithm::Nip44v2,
@@ -288,7 +539,9 @@ impl Nip46Server {
             return Err("nip44_decrypt: requires two parameters".into());
         }
         let other_pubkey = PublicKey::try_from_hex_string(&params[0], true)?;
-        let plaintext = GLOBALS.identity.decrypt(&other_pubkey, &params[1])?;
+        let plaintext = GLOBALS
+            .identity
+            .decrypt_locally(&other_pubkey, &params[1])?;
         Ok(plaintext)
     }
 
@@ -321,7 +574,7 @@ pub struct ParsedCommand {
 }
 
 fn parse_command(peer_pubkey: PublicKey, contents: &str) -> Result<ParsedCommand, Error> {
-    let bytes = GLOBALS.identity.decrypt(&peer_pubkey, contents)?;
+    let bytes = GLOBALS.identity.decrypt_locally(&peer_pubkey, contents)?;
 
     let json: serde_json::Value = serde_json::from_str(&bytes)?;
 
@@ -399,9 +652,10 @@ fn send_response(
     });
     let s = output.to_string();
 
-    let e = GLOBALS
-        .identity
-        .encrypt(&peer_pubkey, &s, ContentEncryptionAlgorithm::Nip04)?;
+    let e =
+        GLOBALS
+            .identity
+            .encrypt_locally(&peer_pubkey, &s, ContentEncryptionAlgorithm::Nip04)?;
 
     let pre_event = PreEvent {
         pubkey: public_key,
@@ -411,7 +665,7 @@ fn send_response(
         content: e,
     };
 
-    let event = GLOBALS.identity.sign_event(pre_event)?;
+    let event = GLOBALS.identity.sign_event_locally(pre_event)?;
 
     GLOBALS
         .to_overlord
@@ -421,8 +675,18 @@ fn send_response(
 }
 
 pub fn handle_command(event: &Event, seen_on: Option<RelayUrl>) -> Result<(), Error> {
+    // Answers from our remote signer
+    if crate::bunker::handle_response(event)? {
+        return Ok(());
+    }
+
+    // Answers to our own pairing attempts
+    if handle_pairing_response(event)? {
+        return Ok(());
+    }
+
     // If we have a server for that pubkey
-    if let Some(mut server) = GLOBALS.storage.read_nip46server(event.pubkey)? {
+    if let Some(server) = GLOBALS.storage.read_nip46server(event.pubkey)? {
         // Parse the command
         let parsed_command = match parse_command(event.pubkey, &event.content) {
             Ok(pc) => pc,
@@ -584,10 +848,12 @@ pub fn handle_command(event: &Event, seen_on: Option<RelayUrl>) -> Result<(), Er
 
     // Save the server, and delete the unconnected server
     let mut txn = GLOBALS.storage.get_write_txn()?;
-    GLOBALS.storage.write_nip46server(&server, Some(&mut txn))?;
     GLOBALS
         .storage
-        .delete_nip46_unconnected_server(Some(&mut txn))?;
+        .write_nip46server(&server, Some(&mut *txn))?;
+    GLOBALS
+        .storage
+        .delete_nip46_unconnected_server(Some(&mut *txn))?;
     txn.commit()?;
 
     // Acknowledge
@@ -601,3 +867,51 @@ pub fn handle_command(event: &Event, seen_on: Option<RelayUrl>) -> Result<(), Er
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_nostrconnect_uri_round_trip() {
+        let uri = NostrConnectUri {
+            client_pubkey: PrivateKey::generate().public_key(),
+            relays: vec![
+                RelayUrl::try_from_str("wss://relay.example.com/").unwrap(),
+                RelayUrl::try_from_str("wss://other.example.com/path").unwrap(),
+            ],
+            secret: new_secret(),
+            perms: vec!["sign_event:1".to_owned(), "nip44_encrypt".to_owned()],
+            name: Some("Gossip & friends".to_owned()),
+        };
+        let s = uri.as_uri_string();
+        assert!(s.starts_with("nostrconnect://"));
+        assert_eq!(NostrConnectUri::try_from_str(&s).unwrap(), uri);
+
+        assert!(NostrConnectUri::try_from_str("bunker://abc?relay=wss://x.com").is_err());
+    }
+
+    #[test]
+    fn test_permission_of() {
+        let sign = ParsedCommand {
+            id: "1".to_owned(),
+            method: "sign_event".to_owned(),
+            params: vec![r#"{"kind":7,"tags":[],"content":"+"}"#.to_owned()],
+        };
+        assert_eq!(permission_of(&sign), "sign_event:7");
+
+        let unparsable = ParsedCommand {
+            params: vec!["nope".to_owned()],
+            ..sign.clone()
+        };
+        assert_eq!(permission_of(&unparsable), "sign_event");
+
+        let encrypt = ParsedCommand {
+            method: "nip44_encrypt".to_owned(),
+            ..sign
+        };
+        assert_eq!(permission_of(&encrypt), "nip44_encrypt");
+        assert!(needs_permission("nip44_encrypt"));
+        assert!(!needs_permission("get_public_key"));
+    }
+}
@@ -0,0 +1,179 @@
+use crate::alerts::{classify_for, AlertTier};
+use crate::comms::ToOverlordMessage;
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use crate::profile::Profile;
+use nostr_types::{Event, Id, PublicKey, PublicKeyHex, Unixtime};
+use std::collections::HashMap;
+
+/// An identity whose notifications we gather: our own, or another account of ours
+/// (say a project account) that this profile watches for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotifiedIdentity {
+    pub pubkey: PublicKey,
+
+    /// How it is labelled where notifications of several identities are shown
+    pub label: String,
+}
+
+/// A notification to one or more of the [identities]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub id: Id,
+    pub created_at: Unixtime,
+    pub tier: AlertTier,
+
+    /// The identities it is a notification to, in the order of [identities]
+    pub identities: Vec<PublicKey>,
+}
+
+// The `notify_identities` setting holds "<pubkey hex> <label>" entries
+fn format_entry(pubkey: PublicKey, label: &str) -> String {
+    format!("{} {}", pubkey.as_hex_string(), label.trim())
+}
+
+fn parse_entry(entry: &str) -> Option<NotifiedIdentity> {
+    let (hex, label) = entry.split_once(' ').unwrap_or((entry, ""));
+    let pubkey = PublicKey::try_from_hex_string(hex, true).ok()?;
+    Some(NotifiedIdentity {
+        pubkey,
+        label: label.to_owned(),
+    })
+}
+
+/// The identities we gather notifications for. Ours comes first, labelled with the
+/// profile name, followed by the other accounts added with [add_identity].
+pub fn identities() -> Vec<NotifiedIdentity> {
+    let mut output: Vec<NotifiedIdentity> = Vec::new();
+    if let Some(pubkey) = GLOBALS.identity.public_key() {
+        let label = Profile::current()
+            .ok()
+            .and_then(|p| p.name)
+            .unwrap_or_else(|| "Me".to_owned());
+        output.push(NotifiedIdentity { pubkey, label });
+    }
+    for entry in GLOBALS.storage.read_setting_notify_identities().iter() {
+        if let Some(identity) = parse_entry(entry) {
+            if !output.iter().any(|i| i.pubkey == identity.pubkey) {
+                output.push(identity);
+            }
+        }
+    }
+    output
+}
+
+/// Also gather the notifications of this account, under this label. Mentions of it
+/// are subscribed to on its own inbox relays from then on.
+pub fn add_identity(pubkey: PublicKey, label: &str) -> Result<(), Error> {
+    if GLOBALS.identity.public_key() == Some(pubkey) {
+        return Err(ErrorKind::General("That is already our identity".to_owned()).into());
+    }
+    let mut entries = GLOBALS.storage.read_setting_notify_identities();
+    entries.retain(|e| parse_entry(e).map(|i| i.pubkey) != Some(pubkey));
+    entries.push(format_entry(pubkey, label));
+    GLOBALS
+        .storage
+        .write_setting_notify_identities(&entries, None)?;
+    resubscribe();
+    Ok(())
+}
+
+/// Stop gathering the notifications of this account
+pub fn remove_identity(pubkey: PublicKey) -> Result<(), Error> {
+    let mut entries = GLOBALS.storage.read_setting_notify_identities();
+    entries.retain(|e| parse_entry(e).map(|i| i.pubkey) != Some(pubkey));
+    GLOBALS
+        .storage
+        .write_setting_notify_identities(&entries, None)?;
+    resubscribe();
+    Ok(())
+}
+
+fn resubscribe() {
+    let _ = GLOBALS
+        .to_overlord
+        .send(ToOverlordMessage::SubscribeOtherInboxes);
+}
+
+/// The notifications since `since` to all of our [identities], or just to `only`,
+/// newest first. A notification to several of them is listed once.
+pub fn notifications(since: Unixtime, only: Option<PublicKey>) -> Result<Vec<Notification>, Error> {
+    let now = Unixtime::now().unwrap();
+    let identities: Vec<PublicKey> = identities()
+        .iter()
+        .map(|i| i.pubkey)
+        .filter(|pk| only.is_none() || only == Some(*pk))
+        .collect();
+
+    let mut found: HashMap<Id, Notification> = HashMap::new();
+    for pubkey in identities.iter() {
+        let hex: PublicKeyHex = (*pubkey).into();
+        let events: Vec<Event> = GLOBALS.storage.find_tagged_events(
+            "p",
+            Some(hex.as_str()),
+            |e| e.created_at >= since && e.created_at <= now,
+            false,
+        )?;
+        for event in events.iter() {
+            let tier = match classify_for(event, *pubkey) {
+                Some(tier) => tier,
+                None => continue,
+            };
+            let notification = found.entry(event.id).or_insert_with(|| Notification {
+                id: event.id,
+                created_at: event.created_at,
+                tier,
+                identities: Vec::new(),
+            });
+            notification.tier = notification.tier.min(tier);
+            notification.identities.push(*pubkey);
+        }
+    }
+
+    let mut output: Vec<Notification> = found.into_values().collect();
+    output.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(b.id.cmp(&a.id)));
+    Ok(output)
+}
+
+/// The pubkeys of the identities other than ours, whose mentions are subscribed to
+/// along with ours
+pub(crate) fn other_identity_pubkeys() -> Vec<PublicKey> {
+    let mine = GLOBALS.identity.public_key();
+    identities()
+        .drain(..)
+        .map(|i| i.pubkey)
+        .filter(|pk| Some(*pk) != mine)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nostr_types::PrivateKey;
+
+    #[test]
+    fn test_entries() {
+        let pubkey = PrivateKey::generate().public_key();
+
+        let entry = format_entry(pubkey, "  Project account ");
+        assert_eq!(
+            parse_entry(&entry),
+            Some(NotifiedIdentity {
+                pubkey,
+                label: "Project account".to_owned(),
+            })
+        );
+
+        // The label may be left out
+        assert_eq!(
+            parse_entry(&pubkey.as_hex_string()),
+            Some(NotifiedIdentity {
+                pubkey,
+                label: String::new(),
+            })
+        );
+
+        assert_eq!(parse_entry("not a pubkey"), None);
+        assert_eq!(parse_entry(""), None);
+    }
+}
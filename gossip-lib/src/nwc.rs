@@ -0,0 +1,417 @@
+use crate::comms::{ToMinionMessage, ToMinionPayload, ToMinionPayloadDetail, ToOverlordMessage};
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use nostr_types::{
+    ContentEncryptionAlgorithm, Event, EventKind, Id, MilliSatoshi, PreEvent, PrivateKey,
+    PublicKey, RelayUrl, Tag, Unixtime,
+};
+use parking_lot::{Mutex, RwLock};
+use serde::Deserialize;
+use serde_json::Value;
+use speedy::{Readable, Writable};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+/// How long a request waits for the wallet to answer
+pub const REQUEST_TIMEOUT_SECS: u64 = 60;
+
+/// A nostr+walletconnect:// URI: where to reach a wallet (NIP-47) and the key it
+/// gave us to pay with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NwcUri {
+    /// The key the wallet service answers with
+    pub wallet_pubkey: PublicKey,
+
+    pub relays: Vec<RelayUrl>,
+
+    /// Our key for talking to the wallet, in hex. Whoever has it can spend what the
+    /// wallet allows it to.
+    pub secret: String,
+
+    /// The lightning address of the wallet, if it has one
+    pub lud16: Option<String>,
+}
+
+impl NwcUri {
+    pub fn try_from_str(s: &str) -> Result<NwcUri, Error> {
+        let url = match url::Url::parse(s.trim()) {
+            Ok(url) if url.scheme() == "nostr+walletconnect" => url,
+            _ => return Err(ErrorKind::BadWalletConnectString.into()),
+        };
+        let wallet_pubkey = match url.host_str() {
+            Some(host) => PublicKey::try_from_hex_string(host, true)?,
+            None => return Err(ErrorKind::BadWalletConnectString.into()),
+        };
+
+        let mut relays: Vec<RelayUrl> = Vec::new();
+        let mut secret: Option<String> = None;
+        let mut lud16: Option<String> = None;
+        for (key, value) in url.query_pairs() {
+            match &*key {
+                "relay" => relays.push(RelayUrl::try_from_str(&value)?),
+                "secret" => {
+                    // Make sure it is a key
+                    let _ = PrivateKey::try_from_hex_string(&value)?;
+                    secret = Some(value.into_owned());
+                }
+                "lud16" if !value.is_empty() => lud16 = Some(value.into_owned()),
+                _ => (),
+            }
+        }
+
+        match secret {
+            Some(secret) if !relays.is_empty() => Ok(NwcUri {
+                wallet_pubkey,
+                relays,
+                secret,
+                lud16,
+            }),
+            _ => Err(ErrorKind::BadWalletConnectString.into()),
+        }
+    }
+}
+
+/// The wallet we pay zaps with, as saved
+#[derive(Debug, Clone, Readable, Writable)]
+pub struct NwcConnection {
+    pub wallet_pubkey: PublicKey,
+    pub relays: Vec<RelayUrl>,
+    pub lud16: Option<String>,
+
+    // Our key for talking to the wallet, in hex
+    secret: String,
+}
+
+impl From<NwcUri> for NwcConnection {
+    fn from(uri: NwcUri) -> NwcConnection {
+        NwcConnection {
+            wallet_pubkey: uri.wallet_pubkey,
+            relays: uri.relays,
+            lud16: uri.lud16,
+            secret: uri.secret,
+        }
+    }
+}
+
+lazy_static! {
+    static ref CONNECTION: RwLock<Option<(NwcConnection, PrivateKey)>> = RwLock::new(None);
+    static ref BALANCE: RwLock<Option<MilliSatoshi>> = RwLock::new(None);
+    static ref WAITING: Mutex<HashMap<Id, oneshot::Sender<Result<Value, String>>>> =
+        Mutex::new(HashMap::new());
+}
+
+/// If we have a wallet to pay with
+pub fn is_connected() -> bool {
+    CONNECTION.read().is_some()
+}
+
+/// The saved wallet, if we have one
+pub fn connection() -> Option<NwcConnection> {
+    CONNECTION.read().as_ref().map(|(c, _)| c.clone())
+}
+
+/// The balance of the wallet when we last asked it
+pub fn balance() -> Option<MilliSatoshi> {
+    *BALANCE.read()
+}
+
+/// The wallet's key and ours for talking to it
+pub(crate) fn keys() -> Option<(PublicKey, PublicKey)> {
+    CONNECTION
+        .read()
+        .as_ref()
+        .map(|(c, key)| (c.wallet_pubkey, key.public_key()))
+}
+
+// The wallet is sealed with our identity when we hold its private key. With a
+// remote signer (NIP-46) there is no key to seal it with, so it is saved in the clear.
+fn sealable() -> Result<bool, Error> {
+    if GLOBALS.identity.storage_key().is_some() {
+        Ok(true)
+    } else if GLOBALS.identity.is_remote() {
+        Ok(false)
+    } else {
+        Err(ErrorKind::General(
+            "Unlock your identity (or connect your remote signer) to use a wallet".to_owned(),
+        )
+        .into())
+    }
+}
+
+// The overlord calls this at startup, and again once the identity is unlocked or
+// the remote signer is connected, as the saved wallet may be sealed with it
+pub(crate) fn load() -> Result<(), Error> {
+    let sealed = match sealable() {
+        Ok(sealed) => sealed,
+        Err(_) => return Ok(()),
+    };
+    match GLOBALS.storage.read_nwc_connection()? {
+        Some(conn) => {
+            let key = PrivateKey::try_from_hex_string(&conn.secret)?;
+            // Wallets saved before they were sealed are sealed now
+            if sealed {
+                GLOBALS.storage.write_nwc_connection(&conn, true, None)?;
+            }
+            *CONNECTION.write() = Some((conn, key));
+        }
+        None => *CONNECTION.write() = None,
+    }
+    Ok(())
+}
+
+/// Start paying with the wallet of this URI
+pub(crate) fn start(uri: NwcUri) -> Result<(), Error> {
+    let sealed = sealable()?;
+    let key = PrivateKey::try_from_hex_string(&uri.secret)?;
+    let conn: NwcConnection = uri.into();
+    GLOBALS.storage.write_nwc_connection(&conn, sealed, None)?;
+    fail_waiting("The wallet was replaced");
+    *CONNECTION.write() = Some((conn, key));
+    *BALANCE.write() = None;
+    Ok(())
+}
+
+/// Stop paying with the wallet
+pub(crate) fn stop() -> Result<(), Error> {
+    GLOBALS.storage.delete_nwc_connection(None)?;
+    fail_waiting("The wallet was disconnected");
+    *CONNECTION.write() = None;
+    *BALANCE.write() = None;
+    Ok(())
+}
+
+/// The amount a lightning invoice (BOLT-11) is for, in millisats, as given in its
+/// human readable part. None if it is not an invoice, or is for any amount.
+pub fn invoice_amount_msat(invoice: &str) -> Option<u64> {
+    let invoice = invoice.trim().to_lowercase();
+    let invoice = invoice.strip_prefix("lightning:").unwrap_or(&invoice);
+    let hrp = &invoice[..invoice.rfind('1')?];
+    let rest = hrp.strip_prefix("ln")?;
+
+    // The currency (bc, tb, bcrt, ...) has no digits, the amount follows it
+    let start = rest.find(|c: char| c.is_ascii_digit())?;
+    let amount = &rest[start..];
+    let (digits, multiplier) = match amount.chars().last()? {
+        c if c.is_ascii_digit() => (amount, None),
+        c => (&amount[..amount.len() - c.len_utf8()], Some(c)),
+    };
+    let value = digits.parse::<u64>().ok()?;
+
+    // In millisats, per unit of the multiplier (1 BTC is 10^11 millisats)
+    match multiplier {
+        None => value.checked_mul(100_000_000_000),
+        Some('m') => value.checked_mul(100_000_000),
+        Some('u') => value.checked_mul(100_000),
+        Some('n') => value.checked_mul(100),
+        Some('p') if value % 10 == 0 => Some(value / 10),
+        _ => None,
+    }
+}
+
+/// Pay a lightning invoice, returning the preimage
+pub(crate) async fn pay_invoice(invoice: &str) -> Result<String, Error> {
+    let result = request("pay_invoice", serde_json::json!({ "invoice": invoice })).await?;
+    match result.get("preimage") {
+        Some(Value::String(preimage)) => Ok(preimage.to_owned()),
+        _ => Ok("".to_owned()),
+    }
+}
+
+/// Ask the wallet its balance, and remember it (see [balance])
+pub(crate) async fn get_balance() -> Result<MilliSatoshi, Error> {
+    let result = request("get_balance", serde_json::json!({})).await?;
+    match result.get("balance").and_then(|b| b.as_u64()) {
+        Some(msats) => {
+            *BALANCE.write() = Some(MilliSatoshi(msats));
+            Ok(MilliSatoshi(msats))
+        }
+        None => Err(ErrorKind::Wallet("No balance in the answer".to_owned()).into()),
+    }
+}
+
+// Send a NIP-47 request to the wallet and wait for its result, for up to
+// [REQUEST_TIMEOUT_SECS]
+async fn request(method: &str, params: Value) -> Result<Value, Error> {
+    let (event, relays) = {
+        let binding = CONNECTION.read();
+        let (conn, key) = binding.as_ref().ok_or(ErrorKind::WalletNotSetup)?;
+        let json = serde_json::json!({
+            "method": method,
+            "params": params,
+        });
+        let content = key.encrypt(
+            &conn.wallet_pubkey,
+            &json.to_string(),
+            ContentEncryptionAlgorithm::Nip04,
+        )?;
+        let pre_event = PreEvent {
+            pubkey: key.public_key(),
+            created_at: Unixtime::now().unwrap(),
+            kind: EventKind::WalletRequest,
+            tags: vec![Tag::new_pubkey(conn.wallet_pubkey, None, None)],
+            content,
+        };
+        (sign_with_key(key, pre_event)?, conn.relays.clone())
+    };
+
+    let (tx, rx) = oneshot::channel();
+    WAITING.lock().insert(event.id, tx);
+
+    for url in relays {
+        if GLOBALS.connected_relays.contains_key(&url) {
+            let _ = GLOBALS.to_minions.send(ToMinionMessage {
+                target: url.as_str().to_owned(),
+                payload: ToMinionPayload {
+                    job_id: rand::random::<u64>(),
+                    detail: ToMinionPayloadDetail::PostEvents(vec![event.clone()]),
+                },
+            });
+        } else {
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::PostWalletRequest(
+                    event.clone(),
+                    vec![url],
+                ));
+        }
+    }
+
+    let answer = tokio::time::timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS), rx).await;
+    WAITING.lock().remove(&event.id);
+
+    match answer {
+        Ok(Ok(Ok(result))) => Ok(result),
+        Ok(Ok(Err(error))) => Err(ErrorKind::Wallet(error).into()),
+        Ok(Err(_)) => Err(ErrorKind::Wallet("The request was dropped".to_owned()).into()),
+        Err(_) => Err(ErrorKind::WalletTimeout(method.to_owned()).into()),
+    }
+}
+
+fn fail_waiting(reason: &str) {
+    for (_, reply) in WAITING.lock().drain() {
+        let _ = reply.send(Err(reason.to_owned()));
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct NwcError {
+    #[serde(default)]
+    code: String,
+
+    #[serde(default)]
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct NwcResponse {
+    #[serde(default)]
+    error: Option<NwcError>,
+
+    #[serde(default)]
+    result: Option<Value>,
+}
+
+// Process calls this for each wallet response. If it answers one of our requests,
+// hand it to the request waiting for it.
+pub(crate) fn handle_response(event: &Event) -> Result<(), Error> {
+    let plaintext = {
+        let binding = CONNECTION.read();
+        let (conn, key) = match binding.as_ref() {
+            Some(found) => found,
+            None => return Ok(()),
+        };
+        let client_pubkey = key.public_key();
+        if event.pubkey != conn.wallet_pubkey
+            || !event.people().iter().any(|(pk, _, _)| *pk == client_pubkey)
+        {
+            return Ok(());
+        }
+        key.decrypt(&event.pubkey, &event.content)?
+    };
+
+    let request_id = event
+        .tags
+        .iter()
+        .find(|t| t.tagname() == "e")
+        .and_then(|t| Id::try_from_hex_string(t.value()).ok());
+    let request_id = match request_id {
+        Some(id) => id,
+        None => return Ok(()),
+    };
+
+    let response: NwcResponse = serde_json::from_str(&plaintext)?;
+    let answer = match response.error {
+        Some(error) => Err(format!("{} {}", error.code, error.message)),
+        None => Ok(response.result.unwrap_or(Value::Null)),
+    };
+    if let Some(reply) = WAITING.lock().remove(&request_id) {
+        let _ = reply.send(answer);
+    }
+
+    Ok(())
+}
+
+// Sign an event with a key of our own (not our identity)
+pub(crate) fn sign_with_key(key: &PrivateKey, input: PreEvent) -> Result<Event, Error> {
+    let id = input.hash()?;
+    let sig = key.sign_id(id)?;
+    Ok(Event {
+        id,
+        pubkey: input.pubkey,
+        created_at: input.created_at,
+        kind: input.kind,
+        sig,
+        content: input.content,
+        tags: input.tags,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_nwc_uri() {
+        let wallet = PrivateKey::generate().public_key();
+        let mut key = PrivateKey::generate();
+        let secret = key.as_hex_string();
+        let s = format!(
+            "nostr+walletconnect://{}?relay=wss%3A%2F%2Frelay.example.com%2F&secret={}&lud16=me%40example.com",
+            wallet.as_hex_string(),
+            secret
+        );
+        let uri = NwcUri::try_from_str(&s).unwrap();
+        assert_eq!(uri.wallet_pubkey, wallet);
+        assert_eq!(
+            uri.relays,
+            vec![RelayUrl::try_from_str("wss://relay.example.com/").unwrap()]
+        );
+        assert_eq!(uri.secret, secret);
+        assert_eq!(uri.lud16.as_deref(), Some("me@example.com"));
+
+        // A secret is required
+        let s = format!(
+            "nostr+walletconnect://{}?relay=wss://relay.example.com",
+            wallet.as_hex_string()
+        );
+        assert!(NwcUri::try_from_str(&s).is_err());
+        assert!(NwcUri::try_from_str("bunker://abc?relay=wss://x.com").is_err());
+    }
+
+    #[test]
+    fn test_invoice_amount_msat() {
+        assert_eq!(invoice_amount_msat("lnbc210n1pjexample"), Some(21_000));
+        assert_eq!(invoice_amount_msat("LNBC2500U1PVJLUEZ"), Some(250_000_000));
+        assert_eq!(invoice_amount_msat("lntb20m1pvjluez"), Some(2_000_000_000));
+        assert_eq!(invoice_amount_msat("lnbc10p1pexample"), Some(1));
+        assert_eq!(
+            invoice_amount_msat("lightning:lnbc1u1pexample"),
+            Some(100_000)
+        );
+        // Any amount, a bad multiplier, and not an invoice
+        assert_eq!(invoice_amount_msat("lnbc1pvjluezpp5"), None);
+        assert_eq!(invoice_amount_msat("lnbc15p1pexample"), None);
+        assert_eq!(invoice_amount_msat("npub1example"), None);
+    }
+}
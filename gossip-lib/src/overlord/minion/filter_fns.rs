@@ -1,6 +1,8 @@
 use crate::dm_channel::DmChannel;
 use crate::globals::GLOBALS;
-use nostr_types::{EventKind, Filter, IdHex, PublicKey, PublicKeyHex, Unixtime};
+use crate::relay::Relay;
+use crate::relay_monitor::RELAY_DISCOVERY_KIND;
+use nostr_types::{EventAddr, EventKind, Filter, Id, IdHex, PublicKey, PublicKeyHex, Unixtime};
 
 pub enum FeedRange {
     // Long-term subscription for anything after the given time
@@ -45,6 +47,18 @@ pub fn general_feed(authors: &[PublicKey], range: FeedRange) -> Vec<Filter> {
 
     let (since, until, limit) = range.since_until_limit();
 
+    // Live activities (NIP-53) of people followed, which are not feed events
+    if GLOBALS.storage.read_setting_live_activities() {
+        filters.push(Filter {
+            authors: pkp.clone(),
+            kinds: vec![EventKind::LiveEvent],
+            since,
+            until,
+            limit,
+            ..Default::default()
+        });
+    }
+
     // feed related by people followed
     filters.push(Filter {
         authors: pkp,
@@ -58,6 +72,54 @@ pub fn general_feed(authors: &[PublicKey], range: FeedRange) -> Vec<Filter> {
     filters
 }
 
+// Mentions of these pubkeys (hex), but only from followers if the relay is not spam
+// safe and we avoid spam on those
+fn mentions_filter(
+    pubkeys: Vec<String>,
+    kinds: Vec<EventKind>,
+    spamsafe: bool,
+    range: &FeedRange,
+) -> Filter {
+    let (since, until, limit) = range.since_until_limit();
+    let mut filter = Filter {
+        kinds,
+        since,
+        until,
+        limit,
+        ..Default::default()
+    };
+    filter.set_tag_values('p', pubkeys);
+
+    // Spam prevention:
+    if !spamsafe && GLOBALS.storage.read_setting_avoid_spam_on_unsafe_relays() {
+        // As the relay is not spam safe, only take mentions from followers
+        filter.authors = GLOBALS
+            .people
+            .get_subscribed_pubkeys()
+            .drain(..)
+            .map(|pk| pk.into())
+            .collect();
+    }
+
+    filter
+}
+
+/// Mentions of the other identities we gather notifications for, on a relay that
+/// is an inbox relay of theirs. DMs to them are not ours to read, so not those.
+pub fn other_inboxes(pubkeys: &[PublicKey], spamsafe: bool, range: FeedRange) -> Vec<Filter> {
+    if pubkeys.is_empty() {
+        return Vec::new();
+    }
+    let mut event_kinds = crate::feed::feed_related_event_kinds(false);
+    event_kinds.retain(|f| *f != EventKind::GiftWrap);
+    vec![mentions_filter(
+        pubkeys.iter().map(|pk| pk.as_hex_string()).collect(),
+        event_kinds,
+        spamsafe,
+        &range,
+    )]
+}
+
 pub fn inbox_feed(spamsafe: bool, range: FeedRange) -> Vec<Filter> {
     let mut filters: Vec<Filter> = Vec::new();
 
@@ -73,31 +135,12 @@ pub fn inbox_feed(spamsafe: bool, range: FeedRange) -> Vec<Filter> {
 
         let pkh: PublicKeyHex = pubkey.into();
 
-        let filter = {
-            let mut filter = Filter {
-                kinds: event_kinds,
-                since,
-                until,
-                limit,
-                ..Default::default()
-            };
-            let values = vec![pkh.to_string()];
-            filter.set_tag_values('p', values);
-
-            // Spam prevention:
-            if !spamsafe && GLOBALS.storage.read_setting_avoid_spam_on_unsafe_relays() {
-                // As the relay is not spam safe, only take mentions from followers
-                filter.authors = GLOBALS
-                    .people
-                    .get_subscribed_pubkeys()
-                    .drain(..)
-                    .map(|pk| pk.into())
-                    .collect();
-            }
-
-            filter
-        };
-        filters.push(filter);
+        filters.push(mentions_filter(
+            vec![pkh.to_string()],
+            event_kinds,
+            spamsafe,
+            &range,
+        ));
 
         if GLOBALS.identity.is_unlocked() {
             // Giftwraps cannot be filtered by author so we have to take them regardless
@@ -141,19 +184,26 @@ pub fn person_feed(pubkey: PublicKey, range: FeedRange) -> Vec<Filter> {
     }]
 }
 
-pub fn augments(ids: &[IdHex]) -> Vec<Filter> {
-    let event_kinds = crate::feed::feed_augment_event_kinds();
+// Ids per augments filter, to keep each filter within what relays accept
+const AUGMENT_IDS_PER_FILTER: usize = 100;
 
-    let filter = {
-        let mut filter = Filter {
-            kinds: event_kinds,
-            ..Default::default()
-        };
-        filter.set_tag_values('e', ids.iter().map(|id| id.to_string()).collect());
-        filter
-    };
+/// Reactions, zaps, deletions and replies referring to these events
+pub fn augments(ids: &[IdHex]) -> Vec<Filter> {
+    let mut event_kinds = crate::feed::feed_augment_event_kinds();
+    if !event_kinds.contains(&EventKind::TextNote) {
+        event_kinds.push(EventKind::TextNote);
+    }
 
-    vec![filter]
+    ids.chunks(AUGMENT_IDS_PER_FILTER)
+        .map(|chunk| {
+            let mut filter = Filter {
+                kinds: event_kinds.clone(),
+                ..Default::default()
+            };
+            filter.set_tag_values('e', chunk.iter().map(|id| id.to_string()).collect());
+            filter
+        })
+        .collect()
 }
 
 pub fn config(since: Unixtime) -> Vec<Filter> {
@@ -177,6 +227,17 @@ pub fn config(since: Unixtime) -> Vec<Filter> {
             filters.push(giftwrap_filter);
         }
 
+        // Contact lists of people who follow me, for the follow-back rules
+        if crate::follow_back::any_enabled() {
+            let mut filter = Filter {
+                kinds: vec![EventKind::ContactList],
+                since: Some(since),
+                ..Default::default()
+            };
+            filter.set_tag_values('p', vec![pkh.to_string()]);
+            filters.push(filter);
+        }
+
         // Actual config stuff
         filters.push(Filter {
             authors: vec![pkh.clone()],
@@ -187,6 +248,10 @@ pub fn config(since: Unixtime) -> Vec<Filter> {
                 EventKind::MuteList,
                 EventKind::FollowSets,
                 EventKind::RelayList,
+                EventKind::BlockedRelaysList,
+                EventKind::SearchRelaysList,
+                EventKind::DmRelayList,
+                EventKind::UserEmojiList,
             ],
             // these are all replaceable, no since required
             ..Default::default()
@@ -211,7 +276,7 @@ pub fn discover(pubkeys: &[PublicKey]) -> Vec<Filter> {
     let pkp: Vec<PublicKeyHex> = pubkeys.iter().map(|pk| pk.into()).collect();
     vec![Filter {
         authors: pkp,
-        kinds: vec![EventKind::RelayList],
+        kinds: vec![EventKind::RelayList, EventKind::DmRelayList],
         // these are all replaceable, no since required
         ..Default::default()
     }]
@@ -274,18 +339,134 @@ pub fn dm_channel(dmchannel: DmChannel) -> Vec<Filter> {
     vec![filter]
 }
 
-pub fn nip46() -> Vec<Filter> {
-    let pubkey = match GLOBALS.identity.public_key() {
-        Some(pk) => pk,
+pub fn bunker() -> Vec<Filter> {
+    // Answers from our remote signer, to our key for talking to it
+    let (signer_pubkey, client_pubkey) = match crate::bunker::keys() {
+        Some(keys) => keys,
         None => return vec![],
     };
-    let pkh: PublicKeyHex = pubkey.into();
 
     let mut filter = Filter {
         kinds: vec![EventKind::NostrConnect],
         ..Default::default()
     };
-    filter.set_tag_values('p', vec![pkh.to_string()]);
+    filter.add_author(&signer_pubkey.into());
+    filter.set_tag_values('p', vec![PublicKeyHex::from(client_pubkey).to_string()]);
+
+    vec![filter]
+}
+
+pub fn wallet() -> Vec<Filter> {
+    // Answers from our wallet, to our key for talking to it
+    let (wallet_pubkey, client_pubkey) = match crate::nwc::keys() {
+        Some(keys) => keys,
+        None => return vec![],
+    };
+
+    let mut filter = Filter {
+        kinds: vec![EventKind::WalletResponse],
+        ..Default::default()
+    };
+    filter.add_author(&wallet_pubkey.into());
+    filter.set_tag_values('p', vec![PublicKeyHex::from(client_pubkey).to_string()]);
+
+    vec![filter]
+}
+
+pub fn live_chat(addr: &EventAddr) -> Vec<Filter> {
+    // Chat messages within the activity
+    let mut filter = Filter {
+        kinds: vec![EventKind::LiveChatMessage],
+        limit: Some(500),
+        ..Default::default()
+    };
+    filter.set_tag_values(
+        'a',
+        vec![format!(
+            "{}:{}:{}",
+            u32::from(addr.kind),
+            addr.author.as_hex_string(),
+            addr.d
+        )],
+    );
+
+    vec![filter]
+}
+
+pub fn group(id: &str) -> Vec<Filter> {
+    // The relay's records of the group
+    let mut records = Filter {
+        kinds: crate::groups::group_record_kinds(),
+        ..Default::default()
+    };
+    records.set_tag_values('d', vec![id.to_owned()]);
+
+    // What was posted in the group
+    let mut content = Filter {
+        kinds: crate::groups::group_content_kinds(),
+        limit: Some(500),
+        ..Default::default()
+    };
+    content.set_tag_values('h', vec![id.to_owned()]);
+
+    vec![records, content]
+}
+
+pub fn dvm_job(request_id: Id, result_kind: u32) -> Vec<Filter> {
+    // Feedback on the job, and its result
+    let mut filter = Filter {
+        kinds: vec![
+            EventKind::from(result_kind),
+            EventKind::from(crate::dvm::JOB_FEEDBACK_KIND),
+        ],
+        ..Default::default()
+    };
+    filter.set_tag_values('e', vec![request_id.as_hex_string()]);
+
+    vec![filter]
+}
+
+pub fn community(addr: &EventAddr) -> Vec<Filter> {
+    let value = crate::communities::community_addr_value(&addr.author, &addr.d);
+
+    // Its definition
+    let mut definition = Filter {
+        kinds: vec![EventKind::CommunityDefinition],
+        ..Default::default()
+    };
+    definition.add_author(&addr.author.into());
+    definition.set_tag_values('d', vec![addr.d.clone()]);
+
+    // Approvals by its moderators, and the posts themselves
+    let mut posts = Filter {
+        kinds: vec![EventKind::CommunityPostApproval, EventKind::TextNote],
+        limit: Some(500),
+        ..Default::default()
+    };
+    posts.set_tag_values('a', vec![value]);
+
+    vec![definition, posts]
+}
+
+pub fn nip46() -> Vec<Filter> {
+    // Ourself as a signer, and the keys of our pairing attempts with signers
+    let mut pubkeys = crate::nip46::waiting_pairing_pubkeys();
+    if let Some(pk) = GLOBALS.identity.public_key() {
+        pubkeys.push(pk);
+    }
+    if pubkeys.is_empty() {
+        return vec![];
+    }
+    let pkhs: Vec<String> = pubkeys
+        .iter()
+        .map(|pk| PublicKeyHex::from(*pk).to_string())
+        .collect();
+
+    let mut filter = Filter {
+        kinds: vec![EventKind::NostrConnect],
+        ..Default::default()
+    };
+    filter.set_tag_values('p', pkhs);
 
     vec![filter]
 }
@@ -301,3 +482,77 @@ pub fn metadata(pubkeys: &[PublicKey]) -> Vec<Filter> {
         ..Default::default()
     }]
 }
+
+/// The replaceable events of these kinds of a person whose profile is viewed
+pub fn profile(pubkey: PublicKey, kinds: Vec<EventKind>) -> Vec<Filter> {
+    vec![Filter {
+        authors: vec![pubkey.into()],
+        kinds,
+        ..Default::default()
+    }]
+}
+
+/// Our own replaceable events, to compare with what we last published
+pub fn own_replaceables() -> Vec<Filter> {
+    match GLOBALS.identity.public_key() {
+        Some(pubkey) => vec![Filter {
+            authors: vec![pubkey.into()],
+            kinds: crate::reconcile::RECONCILED_KINDS.to_vec(),
+            ..Default::default()
+        }],
+        None => vec![],
+    }
+}
+
+/// The recent reports of these relay monitors (NIP-66)
+pub fn relay_monitors(monitors: &[PublicKey]) -> Vec<Filter> {
+    let since = Unixtime::now().unwrap().0 - Relay::MONITOR_REPORT_FRESH_SECS as i64;
+    vec![Filter {
+        authors: monitors.iter().map(|pk| pk.into()).collect(),
+        kinds: vec![EventKind::from(RELAY_DISCOVERY_KIND)],
+        since: Some(Unixtime(since)),
+        ..Default::default()
+    }]
+}
+
+/// Filters for a NIP-50 search. The search string itself is added by the subscription.
+pub fn search() -> Vec<Filter> {
+    vec![Filter {
+        kinds: crate::feed::feed_displayable_event_kinds(false),
+        limit: Some(100),
+        ..Default::default()
+    }]
+}
+
+/// A short human readable summary of a set of filters
+pub fn summarize(filters: &[Filter]) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    for filter in filters {
+        let mut part: Vec<String> = Vec::new();
+        if !filter.kinds.is_empty() {
+            let kinds: Vec<String> = filter
+                .kinds
+                .iter()
+                .map(|k| u32::from(*k).to_string())
+                .collect();
+            part.push(format!("kinds={}", kinds.join(",")));
+        }
+        if !filter.authors.is_empty() {
+            part.push(format!("authors={}", filter.authors.len()));
+        }
+        if !filter.ids.is_empty() {
+            part.push(format!("ids={}", filter.ids.len()));
+        }
+        if let Some(since) = filter.since {
+            part.push(format!("since={}", since.0));
+        }
+        if let Some(until) = filter.until {
+            part.push(format!("until={}", until.0));
+        }
+        if let Some(limit) = filter.limit {
+            part.push(format!("limit={}", limit));
+        }
+        parts.push(format!("[{}]", part.join(" ")));
+    }
+    parts.join(" ")
+}
@@ -30,11 +30,19 @@ impl Minion {
                     .unwrap_or_else(|| "_".to_owned());
 
                 if let Some(sub) = self.subscription_map.get_mut_by_id(&subid.0) {
+                    // Count it against the job
+                    if let Some(mut activity) = GLOBALS
+                        .relay_job_activity
+                        .get_mut(&(self.url.clone(), sub.get_job_id()))
+                    {
+                        activity.events_received += 1;
+                    }
+
                     // Check if the event matches one of our filters
                     //
-                    // exclude temp_ feeds that get rewritten as we are likely to get data
-                    // from the previous version of said filter.
-                    if !handle.starts_with("temp_") {
+                    // exclude temp_ feeds and augments that get rewritten as we are likely
+                    // to get data from the previous version of said filter.
+                    if !handle.starts_with("temp_") && handle != "augments" {
                         let mut it_matches = false;
                         for filter in sub.get_filters().iter() {
                             if filter.event_matches(&event) {
@@ -189,6 +197,8 @@ impl Minion {
                     self.postings.remove(&id);
                 }
 
+                crate::groups::join_answered(id, ok, &ok_message)?;
+
                 match ok {
                     true => tracing::info!("{relay_response}"),
                     false => tracing::warn!("{relay_response}"),
@@ -201,7 +211,12 @@ impl Minion {
                         Some(true) => self.authenticate().await?,
                         Some(false) => (),
                         None => {
-                            if let Some(pubkey) = GLOBALS.identity.public_key() {
+                            // Ask about the key that would actually be revealed
+                            if let Some(pubkey) = GLOBALS
+                                .identity
+                                .relay_auth_public_key(&self.url)
+                                .or_else(|| GLOBALS.identity.public_key())
+                            {
                                 GLOBALS.pending.insert(
                                     crate::pending::PendingItem::RelayAuthenticationRequest {
                                         account: pubkey,
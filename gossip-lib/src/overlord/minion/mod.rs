@@ -493,7 +493,11 @@ impl Minion {
             ToMinionPayloadDetail::AuthApproved => {
                 self.dbrelay.allow_auth = Some(true); // save in our memory copy of the relay
                 self.authenticate().await?;
-                if let Some(pubkey) = GLOBALS.identity.public_key() {
+                if let Some(pubkey) = GLOBALS
+                    .identity
+                    .relay_auth_public_key(&self.url)
+                    .or_else(|| GLOBALS.identity.public_key())
+                {
                     GLOBALS.pending.remove(
                         &crate::pending::PendingItem::RelayAuthenticationRequest {
                             account: pubkey,
@@ -504,7 +508,11 @@ impl Minion {
             }
             ToMinionPayloadDetail::AuthDeclined => {
                 self.dbrelay.allow_auth = Some(false); // save in our memory copy of the relay
-                if let Some(pubkey) = GLOBALS.identity.public_key() {
+                if let Some(pubkey) = GLOBALS
+                    .identity
+                    .relay_auth_public_key(&self.url)
+                    .or_else(|| GLOBALS.identity.public_key())
+                {
                     GLOBALS.pending.remove(
                         &crate::pending::PendingItem::RelayAuthenticationRequest {
                             account: pubkey,
@@ -564,6 +572,10 @@ impl Minion {
             ToMinionPayloadDetail::SubscribeInbox => {
                 self.subscribe_inbox(message.job_id).await?;
             }
+            ToMinionPayloadDetail::SubscribeOtherInboxes(pubkeys) => {
+                self.subscribe_other_inboxes(message.job_id, pubkeys)
+                    .await?;
+            }
             ToMinionPayloadDetail::SubscribeConfig => {
                 self.subscribe_config(message.job_id).await?;
             }
@@ -573,6 +585,10 @@ impl Minion {
             ToMinionPayloadDetail::SubscribePersonFeed(pubkey) => {
                 self.subscribe_person_feed(message.job_id, pubkey).await?;
             }
+            ToMinionPayloadDetail::SubscribePreviewFeed { id, pubkeys, since } => {
+                self.subscribe_preview_feed(message.job_id, id, pubkeys, since)
+                    .await?;
+            }
             ToMinionPayloadDetail::SubscribeReplies(main) => {
                 self.subscribe_replies(message.job_id, main).await?;
             }
@@ -582,6 +598,25 @@ impl Minion {
             ToMinionPayloadDetail::SubscribeDmChannel(dmchannel) => {
                 self.subscribe_dm_channel(message.job_id, dmchannel).await?;
             }
+            ToMinionPayloadDetail::SubscribeLiveChat(addr) => {
+                self.subscribe_live_chat(message.job_id, addr).await?;
+            }
+            ToMinionPayloadDetail::SubscribeGroup(id) => {
+                self.subscribe_group(message.job_id, id).await?;
+            }
+            ToMinionPayloadDetail::SubscribeCommunity(addr) => {
+                self.subscribe_community(message.job_id, addr).await?;
+            }
+            ToMinionPayloadDetail::SubscribeDvmJob(request_id, result_kind) => {
+                self.subscribe_dvm_job(message.job_id, request_id, result_kind)
+                    .await?;
+            }
+            ToMinionPayloadDetail::SubscribeBunker => {
+                self.subscribe_bunker(message.job_id).await?;
+            }
+            ToMinionPayloadDetail::SubscribeWallet => {
+                self.subscribe_wallet(message.job_id).await?;
+            }
             ToMinionPayloadDetail::SubscribeNip46 => {
                 self.subscribe_nip46(message.job_id).await?;
             }
@@ -593,6 +628,10 @@ impl Minion {
                 self.temp_subscribe_person_feed_chunk(message.job_id, pubkey, start)
                     .await?;
             }
+            ToMinionPayloadDetail::TempSubscribeListFeed { pubkeys, since } => {
+                self.temp_subscribe_list_feed(message.job_id, pubkeys, since)
+                    .await?;
+            }
             ToMinionPayloadDetail::TempSubscribeInboxFeedChunk(start) => {
                 self.temp_subscribe_inbox_feed_chunk(message.job_id, start)
                     .await?;
@@ -601,9 +640,41 @@ impl Minion {
                 self.temp_subscribe_metadata(message.job_id, pubkeys)
                     .await?;
             }
+            ToMinionPayloadDetail::TempSubscribeOwnReplaceables => {
+                self.temp_subscribe_own_replaceables(message.job_id).await?;
+            }
+            ToMinionPayloadDetail::TempSubscribeProfile(pubkey, kinds) => {
+                self.temp_subscribe_profile(message.job_id, pubkey, kinds)
+                    .await?;
+            }
+            ToMinionPayloadDetail::TempSubscribeRelayMonitors(monitors) => {
+                self.temp_subscribe_relay_monitors(message.job_id, monitors)
+                    .await?;
+            }
+            ToMinionPayloadDetail::TempSubscribeSearch(text) => {
+                self.temp_subscribe_search(message.job_id, text).await?;
+            }
+            ToMinionPayloadDetail::UnsubscribeAugments => {
+                self.unsubscribe("augments").await?;
+            }
+            ToMinionPayloadDetail::UnsubscribeCommunity => {
+                self.unsubscribe("community").await?;
+            }
+            ToMinionPayloadDetail::UnsubscribeGroup => {
+                self.unsubscribe("group").await?;
+            }
+            ToMinionPayloadDetail::UnsubscribeLiveChat => {
+                self.unsubscribe("live_chat").await?;
+            }
+            ToMinionPayloadDetail::UnsubscribeOtherInboxes => {
+                self.unsubscribe("other_inboxes").await?;
+            }
             ToMinionPayloadDetail::UnsubscribePersonFeed => {
                 self.unsubscribe("person_feed").await?;
             }
+            ToMinionPayloadDetail::UnsubscribePreviewFeed(id) => {
+                self.unsubscribe(&format!("preview_feed_{}", id)).await?;
+            }
             ToMinionPayloadDetail::UnsubscribeReplies => {
                 self.unsubscribe("replies").await?;
                 self.unsubscribe("root_replies").await?;
@@ -613,12 +684,14 @@ impl Minion {
         Ok(())
     }
 
+    // This replaces the augments subscription of the notes previously in view. It
+    // stays open for augments that arrive while they remain in view.
     async fn subscribe_augments(&mut self, job_id: u64, ids: Vec<IdHex>) -> Result<(), Error> {
         let filters = filter_fns::augments(&ids);
 
-        self.subscribe(filters, "temp_augments", job_id).await?;
+        self.subscribe(filters, "augments", job_id).await?;
 
-        if let Some(sub) = self.subscription_map.get_mut("temp_augments") {
+        if let Some(sub) = self.subscription_map.get_mut("augments") {
             if let Some(nip11) = &self.nip11 {
                 if !nip11.supports_nip(15) {
                     // Does not support EOSE.  Set subscription to EOSE now.
@@ -642,6 +715,7 @@ impl Minion {
         );
 
         // Compute how far to look back
+        let fresh = self.general_feed_start.is_none();
         let since = {
             if self.general_feed_start.is_some() {
                 // We already have a general subscription.
@@ -657,7 +731,28 @@ impl Minion {
             }
         };
 
-        let filters = filter_fns::general_feed(&self.general_feed_keys, FeedRange::After { since });
+        // Dormant people look back further, as they are on fewer relays
+        let (dormant, active): (Vec<PublicKey>, Vec<PublicKey>) = self
+            .general_feed_keys
+            .iter()
+            .partition(|pk| crate::activity::is_dormant(**pk));
+        let mut filters = filter_fns::general_feed(&active, FeedRange::After { since });
+        if !dormant.is_empty() {
+            let dormant_since = if fresh {
+                self.compute_since(
+                    GLOBALS.storage.read_setting_feed_chunk()
+                        * crate::activity::DORMANT_LOOKBACK_FACTOR,
+                )
+            } else {
+                since
+            };
+            filters.extend(filter_fns::general_feed(
+                &dormant,
+                FeedRange::After {
+                    since: dormant_since,
+                },
+            ));
+        }
 
         if filters.is_empty() {
             self.unsubscribe("general_feed").await?;
@@ -792,7 +887,15 @@ impl Minion {
         let since = self.compute_since(GLOBALS.storage.read_setting_person_feed_chunk());
         self.person_feed_start = Some(since);
 
-        let filters = filter_fns::person_feed(pubkey, FeedRange::After { since });
+        let mut filters = filter_fns::person_feed(pubkey, FeedRange::After { since });
+
+        // Profile views may ask for only their latest notes
+        let limit = crate::profile_fetch::ProfileFetch::current().notes_limit;
+        if limit > 0 {
+            for filter in filters.iter_mut() {
+                filter.limit = Some(limit);
+            }
+        }
 
         if filters.is_empty() {
             self.unsubscribe_person_feed().await?;
@@ -835,6 +938,27 @@ impl Minion {
         Ok(())
     }
 
+    // Subscribe to mentions of our other identities, on a relay that is an inbox
+    // relay of theirs
+    async fn subscribe_other_inboxes(
+        &mut self,
+        job_id: u64,
+        pubkeys: Vec<PublicKey>,
+    ) -> Result<(), Error> {
+        let since = self.compute_since(GLOBALS.storage.read_setting_replies_chunk());
+        let spamsafe = self.dbrelay.has_usage_bits(Relay::SPAMSAFE);
+
+        let filters = filter_fns::other_inboxes(&pubkeys, spamsafe, FeedRange::After { since });
+
+        if filters.is_empty() {
+            return Ok(());
+        }
+
+        self.subscribe(filters, "other_inboxes", job_id).await?;
+
+        Ok(())
+    }
+
     async fn temp_subscribe_inbox_feed_chunk(
         &mut self,
         job_id: u64,
@@ -917,6 +1041,60 @@ impl Minion {
         Ok(())
     }
 
+    // This replaces the chat subscription of any other live activity
+    async fn subscribe_live_chat(&mut self, job_id: u64, addr: EventAddr) -> Result<(), Error> {
+        let filters = filter_fns::live_chat(&addr);
+        self.subscribe(filters, "live_chat", job_id).await?;
+        Ok(())
+    }
+
+    // This replaces the subscription of any other group on this relay
+    async fn subscribe_group(&mut self, job_id: u64, id: String) -> Result<(), Error> {
+        let filters = filter_fns::group(&id);
+        self.subscribe(filters, "group", job_id).await?;
+        Ok(())
+    }
+
+    // This replaces the subscription of any other community on this relay
+    async fn subscribe_community(&mut self, job_id: u64, addr: EventAddr) -> Result<(), Error> {
+        let filters = filter_fns::community(&addr);
+        self.subscribe(filters, "community", job_id).await?;
+        Ok(())
+    }
+
+    // Each job gets its own subscription, so several can be waited on at once
+    async fn subscribe_dvm_job(
+        &mut self,
+        job_id: u64,
+        request_id: Id,
+        result_kind: u32,
+    ) -> Result<(), Error> {
+        let filters = filter_fns::dvm_job(request_id, result_kind);
+        let handle = format!("dvm_job_{}", request_id.as_hex_string());
+        self.subscribe(filters, &handle, job_id).await?;
+        Ok(())
+    }
+
+    async fn subscribe_bunker(&mut self, job_id: u64) -> Result<(), Error> {
+        let filters = filter_fns::bunker();
+
+        if !filters.is_empty() {
+            self.subscribe(filters, "bunker", job_id).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn subscribe_wallet(&mut self, job_id: u64) -> Result<(), Error> {
+        let filters = filter_fns::wallet();
+
+        if !filters.is_empty() {
+            self.subscribe(filters, "wallet", job_id).await?;
+        }
+
+        Ok(())
+    }
+
     async fn subscribe_nip46(&mut self, job_id: u64) -> Result<(), Error> {
         let filters = filter_fns::nip46();
 
@@ -1103,11 +1281,110 @@ impl Minion {
         self.subscribe(filters, &handle, job_id).await
     }
 
+    // What viewing someone's profile asks for
+    async fn temp_subscribe_profile(
+        &mut self,
+        job_id: u64,
+        pubkey: PublicKey,
+        kinds: Vec<EventKind>,
+    ) -> Result<(), Error> {
+        let handle = format!("temp_subscribe_profile_{}", job_id);
+        let filters = filter_fns::profile(pubkey, kinds);
+        self.subscribe(filters, &handle, job_id).await
+    }
+
+    // A one-off fetch of what these people posted since then (for a person list
+    // that is not live)
+    async fn temp_subscribe_list_feed(
+        &mut self,
+        job_id: u64,
+        pubkeys: Vec<PublicKey>,
+        since: Unixtime,
+    ) -> Result<(), Error> {
+        let filters = filter_fns::general_feed(&pubkeys, FeedRange::After { since });
+        if filters.is_empty() {
+            return Ok(());
+        }
+
+        // Each list gets its own subscription so they do not replace each other
+        let handle = format!("temp_list_feed_{}", job_id);
+        self.subscribe(filters, &handle, job_id).await
+    }
+
+    // This stays open, for posts that come in while they are being previewed
+    async fn subscribe_preview_feed(
+        &mut self,
+        job_id: u64,
+        id: u64,
+        pubkeys: Vec<PublicKey>,
+        since: Unixtime,
+    ) -> Result<(), Error> {
+        let filters = filter_fns::general_feed(&pubkeys, FeedRange::After { since });
+        if filters.is_empty() {
+            return Ok(());
+        }
+
+        let handle = format!("preview_feed_{}", id);
+        self.subscribe(filters, &handle, job_id).await
+    }
+
+    async fn temp_subscribe_own_replaceables(&mut self, job_id: u64) -> Result<(), Error> {
+        let filters = filter_fns::own_replaceables();
+        if filters.is_empty() {
+            return Ok(());
+        }
+
+        tracing::trace!("Fetching our replaceable events from {}", &self.url);
+
+        self.subscribe(filters, "temp_own_replaceables", job_id)
+            .await
+    }
+
+    async fn temp_subscribe_relay_monitors(
+        &mut self,
+        job_id: u64,
+        monitors: Vec<PublicKey>,
+    ) -> Result<(), Error> {
+        if monitors.is_empty() {
+            return Ok(());
+        }
+
+        tracing::trace!("Fetching relay monitor reports from {}", &self.url);
+
+        let filters = filter_fns::relay_monitors(&monitors);
+        self.subscribe(filters, "temp_relay_monitors", job_id).await
+    }
+
+    async fn temp_subscribe_search(&mut self, job_id: u64, text: String) -> Result<(), Error> {
+        let handle = "temp_search".to_string();
+
+        // A new search replaces any search still in progress
+        self.unsubscribe(&handle).await?;
+
+        tracing::trace!("Searching for {:?} on {}", &text, &self.url);
+
+        let filters = filter_fns::search();
+
+        self.subscribe_with_search(filters, &handle, job_id, Some(text))
+            .await
+    }
+
     async fn subscribe(
         &mut self,
         filters: Vec<Filter>,
         handle: &str,
         job_id: u64,
+    ) -> Result<(), Error> {
+        self.subscribe_with_search(filters, handle, job_id, None)
+            .await
+    }
+
+    async fn subscribe_with_search(
+        &mut self,
+        filters: Vec<Filter>,
+        handle: &str,
+        job_id: u64,
+        search: Option<String>,
     ) -> Result<(), Error> {
         if filters.is_empty() {
             tracing::warn!("EMPTY FILTERS handle={} jobid={}", handle, job_id);
@@ -1136,9 +1413,22 @@ impl Minion {
                 None => now.0 as u64,
             });
 
+            let summary = filter_fns::summarize(&filters);
             sub.set_filters(filters);
             let old_job_id = sub.change_job_id(job_id);
             let id = sub.get_id();
+
+            // Carry the job activity over to the new job id
+            let mut activity = GLOBALS
+                .relay_job_activity
+                .remove(&(self.url.clone(), old_job_id))
+                .map(|(_, a)| a)
+                .unwrap_or_default();
+            activity.filter_summary = summary;
+            GLOBALS
+                .relay_job_activity
+                .insert((self.url.clone(), job_id), activity);
+
             tracing::debug!(
                 "UPDATED SUBSCRIPTION on {} handle={}, id={}",
                 &self.url,
@@ -1151,7 +1441,15 @@ impl Minion {
                 job_id,
             ))?;
         } else {
+            let summary = filter_fns::summarize(&filters);
             let id = self.subscription_map.add(handle, job_id, filters);
+            let mut activity = GLOBALS
+                .relay_job_activity
+                .entry((self.url.clone(), job_id))
+                .or_default();
+            activity.subscriptions.push(format!("{}:{}", handle, id));
+            activity.filter_summary = summary;
+            drop(activity);
             tracing::debug!(
                 "NEW SUBSCRIPTION on {} handle={}, id={}",
                 &self.url,
@@ -1160,6 +1458,10 @@ impl Minion {
             );
         }
 
+        if let Some(sub) = self.subscription_map.get_mut(handle) {
+            sub.set_search(search);
+        }
+
         if matches!(self.auth_state, AuthState::Waiting(_)) {
             // Save this, subscribe after AUTH completes
             self.subscriptions_waiting_for_auth
@@ -1172,11 +1474,10 @@ impl Minion {
     }
 
     async fn send_subscription(&mut self, handle: &str) -> Result<(), Error> {
-        let req_message = match self.subscription_map.get(handle) {
-            Some(sub) => sub.req_message(),
+        let wire = match self.subscription_map.get(handle) {
+            Some(sub) => sub.req_wire()?,
             None => return Ok(()), // Not much we can do. It is not there.
         };
-        let wire = serde_json::to_string(&req_message)?;
         let websocket_stream = self.stream.as_mut().unwrap();
         tracing::trace!("{}: Sending {}", &self.url, &wire);
         self.last_message_sent = wire.clone();
@@ -1223,10 +1524,15 @@ impl Minion {
             _ => (),
         }
 
-        if !GLOBALS.identity.is_unlocked() {
+        if !GLOBALS.identity.is_unlocked_for_auth(&self.url) {
             return Err(ErrorKind::NoPrivateKeyForAuth(self.url.clone()).into());
         }
-        let pubkey = match GLOBALS.identity.public_key() {
+        // This relay may have a key of its own to authenticate with
+        let pubkey = match GLOBALS
+            .identity
+            .relay_auth_public_key(&self.url)
+            .or_else(|| GLOBALS.identity.public_key())
+        {
             Some(pk) => pk,
             None => {
                 return Err(ErrorKind::NoPrivateKeyForAuth(self.url.clone()).into());
@@ -1242,14 +1548,29 @@ impl Minion {
             ],
             content: "".to_string(),
         };
-        let event = GLOBALS.identity.sign_event(pre_event)?;
+        // The remote signer answers through the relays we are connected to, and
+        // this minion can't read its answer while it waits for it
+        if GLOBALS.identity.is_remote()
+            && GLOBALS.identity.relay_auth_public_key(&self.url).is_none()
+            && crate::bunker::is_signer_relay(&self.url)
+        {
+            return Err(ErrorKind::NoPrivateKeyForAuth(self.url.clone()).into());
+        }
+        let event = GLOBALS
+            .identity
+            .sign_auth_event(&self.url, pre_event)
+            .await?;
         let id = event.id;
         let msg = ClientMessage::Auth(Box::new(event));
         let wire = serde_json::to_string(&msg)?;
         self.last_message_sent = wire.clone();
         let ws_stream = self.stream.as_mut().unwrap();
         ws_stream.send(WsMessage::Text(wire)).await?;
-        tracing::info!("Authenticated to {}", &self.url);
+        tracing::info!(
+            "Authenticated to {} as {}",
+            &self.url,
+            pubkey.as_hex_string()
+        );
 
         self.auth_state = AuthState::Waiting(id);
 
@@ -9,6 +9,7 @@ pub struct Subscription {
     filters: Vec<Filter>,
     eose: bool,
     clone: bool,
+    search: Option<String>,
 }
 
 impl Subscription {
@@ -20,6 +21,7 @@ impl Subscription {
             filters: vec![],
             eose: false,
             clone: false,
+            search: None,
         }
     }
 
@@ -56,10 +58,38 @@ impl Subscription {
         self.eose
     }
 
+    /// Set a NIP-50 search string, added to every filter when the REQ is sent
+    pub fn set_search(&mut self, search: Option<String>) {
+        self.search = search;
+    }
+
     pub fn req_message(&self) -> ClientMessage {
         ClientMessage::Req(SubscriptionId(self.get_id()), self.filters.clone())
     }
 
+    /// The REQ message as it goes on the wire
+    pub fn req_wire(&self) -> Result<String, serde_json::Error> {
+        let req_message = self.req_message();
+        let search = match &self.search {
+            Some(s) => s,
+            None => return serde_json::to_string(&req_message),
+        };
+
+        // Filter does not carry a search field, so add it to the JSON
+        let mut value = serde_json::to_value(&req_message)?;
+        if let Some(array) = value.as_array_mut() {
+            for filter in array.iter_mut().skip(2) {
+                if let Some(object) = filter.as_object_mut() {
+                    object.insert(
+                        "search".to_owned(),
+                        serde_json::Value::String(search.to_owned()),
+                    );
+                }
+            }
+        }
+        serde_json::to_string(&value)
+    }
+
     pub fn close_message(&self) -> ClientMessage {
         ClientMessage::Close(SubscriptionId(self.get_id()))
     }
@@ -73,6 +103,7 @@ impl Clone for Subscription {
             filters: self.filters.clone(),
             eose: self.eose,
             clone: true,
+            search: self.search.clone(),
         }
     }
 }
@@ -1,5 +1,7 @@
 mod minion;
 
+use crate::bunker::BunkerUri;
+use crate::bus::BusMessage;
 use crate::comms::{
     RelayConnectionReason, RelayJob, ToMinionMessage, ToMinionPayload, ToMinionPayloadDetail,
     ToOverlordMessage,
@@ -10,11 +12,16 @@ use crate::feed::FeedKind;
 use crate::globals::{Globals, GLOBALS};
 use crate::misc::{Private, ZapState};
 use crate::nip46::{Approval, ParsedCommand};
+use crate::nwc::NwcUri;
 use crate::pending::PendingItem;
 use crate::people::{Person, PersonList};
+use crate::posting_policy::PostingPolicy;
+use crate::profile_fetch::ProfileFetch;
 use crate::relay::Relay;
+use crate::storage::{GlobalContext, Storage};
 use crate::tags::{
     add_addr_to_tags, add_event_to_tags, add_pubkey_to_tags, add_subject_to_tags_if_missing,
+    repair_relay_hints,
 };
 use crate::RunState;
 use gossip_relay_picker::RelayAssignment;
@@ -46,6 +53,9 @@ type MinionResult = Result<MinionExitReason, Error>;
 /// 2. Send it a message using `GLOBALS.to_overlord`. This works from a synchronous
 ///    context, but does not wait for or deliver a result. This is how the canonical
 ///    immediate-mode renderer (egui) engages the Overlord.
+/// 3. Send it a message using [ToOverlordMessage::send_tracked]. This works from a
+///    synchronous context and returns a job id which is reported on the
+///    [bus](crate::bus) as the command progresses and completes.
 pub struct Overlord {
     to_minions: Sender<ToMinionMessage>,
     inbox: UnboundedReceiver<ToOverlordMessage>,
@@ -57,6 +67,12 @@ pub struct Overlord {
 
     // Map from minion task::Id to Url
     minions_task_url: HashMap<task::Id, RelayUrl>,
+
+    // The tracked job currently being handled, if any
+    current_job: Option<u64>,
+
+    // The notes in view that each relay is subscribed for augments of
+    augment_subs: HashMap<RelayUrl, Vec<Id>>,
 }
 
 impl Overlord {
@@ -105,6 +121,8 @@ impl Overlord {
             read_runstate: GLOBALS.read_runstate.clone(),
             minions: task::JoinSet::new(),
             minions_task_url: HashMap::new(),
+            current_job: None,
+            augment_subs: HashMap::new(),
         }
     }
 
@@ -152,42 +170,8 @@ impl Overlord {
             return Ok(());
         }
 
-        {
-            // If we need to rebuild relationships, do so now
-            if GLOBALS.storage.get_flag_rebuild_relationships_needed() {
-                tracing::info!("Rebuilding relationships...");
-                GLOBALS.storage.rebuild_relationships(None)?;
-            }
-
-            // If we need to rebuild indexes, do so now
-            if GLOBALS.storage.get_flag_rebuild_indexes_needed() {
-                tracing::info!("Rebuilding event indices...");
-                GLOBALS.storage.rebuild_event_indices(None)?;
-            }
-
-            // If we need to reapply relay lists, do so now
-            if GLOBALS.storage.get_flag_reprocess_relay_lists_needed() {
-                tracing::info!("Reprocessing relay lists...");
-                GLOBALS.storage.reprocess_relay_lists()?;
-            }
-
-            // Data migrations complete
-            GLOBALS
-                .wait_for_data_migration
-                .store(false, Ordering::Relaxed);
-        }
-
-        // Init some feed variables
-        let now = Unixtime::now().unwrap();
-        let general_feed_start =
-            now - Duration::from_secs(GLOBALS.storage.read_setting_feed_chunk());
-        let person_feed_start =
-            now - Duration::from_secs(GLOBALS.storage.read_setting_person_feed_chunk());
-        let inbox_feed_start =
-            now - Duration::from_secs(GLOBALS.storage.read_setting_replies_chunk());
-        GLOBALS
-            .feed
-            .set_feed_starts(general_feed_start, person_feed_start, inbox_feed_start);
+        Self::run_data_migrations()?;
+        Self::init_feed_starts();
 
         // Switch out of initializing RunState
         if GLOBALS.storage.read_setting_offline() {
@@ -223,8 +207,10 @@ impl Overlord {
                         // Minions will shut themselves down. Forget about all the jobs.
                         // When we go back online we start fresh.
                         RunState::Offline => {
+                            self.augment_subs.clear();
                             GLOBALS.relay_picker.init().await?;
                             GLOBALS.connected_relays.clear();
+                            GLOBALS.relay_job_activity.clear();
                         },
                         _ => { }
                     }
@@ -238,6 +224,54 @@ impl Overlord {
         Ok(())
     }
 
+    // Start the feeds as far back as the settings say
+    fn init_feed_starts() {
+        let now = Unixtime::now().unwrap();
+        let general_feed_start =
+            now - Duration::from_secs(GLOBALS.storage.read_setting_feed_chunk());
+        let person_feed_start =
+            now - Duration::from_secs(GLOBALS.storage.read_setting_person_feed_chunk());
+        let inbox_feed_start =
+            now - Duration::from_secs(GLOBALS.storage.read_setting_replies_chunk());
+        GLOBALS
+            .feed
+            .set_feed_starts(general_feed_start, person_feed_start, inbox_feed_start);
+    }
+
+    // Rebuild whatever storage has flagged as needing it
+    fn run_data_migrations() -> Result<(), Error> {
+        // If we need to rebuild relationships, do so now
+        if GLOBALS.storage.get_flag_rebuild_relationships_needed() {
+            tracing::info!("Rebuilding relationships...");
+            GLOBALS.storage.rebuild_relationships()?;
+        }
+
+        // If we need to rebuild indexes, do so now
+        if GLOBALS.storage.get_flag_rebuild_indexes_needed() {
+            tracing::info!("Rebuilding event indices...");
+            GLOBALS.storage.rebuild_event_indices()?;
+        }
+
+        // If private records need resealing, do so now
+        if GLOBALS.storage.get_flag_reseal_needed() && GLOBALS.identity.is_unlocked() {
+            tracing::info!("Resealing private records...");
+            Self::reseal_private_records();
+        }
+
+        // If we need to reapply relay lists, do so now
+        if GLOBALS.storage.get_flag_reprocess_relay_lists_needed() {
+            tracing::info!("Reprocessing relay lists...");
+            GLOBALS.storage.reprocess_relay_lists()?;
+        }
+
+        // Data migrations complete
+        GLOBALS
+            .wait_for_data_migration
+            .store(false, Ordering::Relaxed);
+
+        Ok(())
+    }
+
     async fn pick_relays(&mut self) {
         // Garbage collect
         match GLOBALS.relay_picker.garbage_collect().await {
@@ -323,7 +357,21 @@ impl Overlord {
         Ok(())
     }
 
-    async fn engage_minion(&mut self, url: RelayUrl, jobs: Vec<RelayJob>) -> Result<(), Error> {
+    async fn engage_minion(&mut self, mut url: RelayUrl, jobs: Vec<RelayJob>) -> Result<(), Error> {
+        // A relay that is down hands its jobs to a member of its group that is up
+        // (but not those of a NIP-29 group, which lives on that relay alone)
+        let group_jobs = jobs
+            .iter()
+            .any(|j| j.reason == RelayConnectionReason::Group);
+        if !group_jobs && crate::relay_group::is_relay_down(&url) {
+            if let Some(stand_in) = crate::relay_group::stand_in_for(&url) {
+                tracing::debug!("{} is down, failing over to {}", url, stand_in);
+                url = stand_in;
+            }
+        }
+
+        self.job_progress(format!("Engaging relay {}", url));
+
         let relay = GLOBALS.storage.read_or_create_relay(&url, None)?;
 
         if GLOBALS
@@ -367,6 +415,22 @@ impl Overlord {
             return Ok(());
         }
 
+        // don't connect to blocked relays
+        if crate::blocked_relays::is_relay_blocked(&url) {
+            tracing::debug!("Not connecting to blocked relay {}", url);
+            return Ok(());
+        }
+
+        // Record when these jobs started
+        let now = Unixtime::now().unwrap();
+        for job in jobs.iter() {
+            GLOBALS
+                .relay_job_activity
+                .entry((url.clone(), job.payload.job_id))
+                .or_default()
+                .started = Some(now);
+        }
+
         if let Some(mut refmut) = GLOBALS.connected_relays.get_mut(&url) {
             // We are already connected. Send it the jobs
             for job in jobs.drain(..) {
@@ -407,7 +471,11 @@ impl Overlord {
             self.minions_task_url.insert(id, url.clone());
 
             // And record it
-            GLOBALS.connected_relays.insert(url, jobs);
+            GLOBALS.connected_relays.insert(url.clone(), jobs);
+            crate::bus::send(BusMessage::RelayStatusChanged {
+                url,
+                connected: true,
+            });
         }
 
         Ok(())
@@ -439,6 +507,13 @@ impl Overlord {
             Some(jobs) => jobs,
             None => vec![],
         };
+        crate::bus::send(BusMessage::RelayStatusChanged {
+            url: url.clone(),
+            connected: false,
+        });
+        GLOBALS
+            .relay_job_activity
+            .retain(|(activity_url, _), _| *activity_url != url);
 
         // Exclusion will be non-zero if there was a failure.  It will be zero if we
         // succeeded
@@ -553,13 +628,31 @@ impl Overlord {
         // it after the exclusion completes
         let exclusion = exclusion.max(10); // safety catch, minimum exclusion is 10s
 
-        GLOBALS.penalty_box_relays.insert(url.clone(), jobs);
+        // If it is in a relay group, a member that is up takes over its jobs now.
+        // It still waits out the exclusion before it is used again.
+        match crate::relay_group::stand_in_for(&url) {
+            Some(stand_in) => {
+                tracing::info!(
+                    "Minion {} failed over to {} for {} seconds",
+                    &url,
+                    &stand_in,
+                    exclusion
+                );
+                GLOBALS.penalty_box_relays.insert(url.clone(), vec![]);
+                if let Err(e) = self.engage_minion(stand_in, jobs).await {
+                    tracing::error!("{}", e);
+                }
+            }
+            None => {
+                GLOBALS.penalty_box_relays.insert(url.clone(), jobs);
 
-        tracing::info!(
-            "Minion {} will restart in {} seconds to continue persistent jobs",
-            &url,
-            exclusion
-        );
+                tracing::info!(
+                    "Minion {} will restart in {} seconds to continue persistent jobs",
+                    &url,
+                    exclusion
+                );
+            }
+        }
 
         if exclusion != u64::MAX {
             // Re-engage after the delay
@@ -598,6 +691,31 @@ impl Overlord {
     }
 
     async fn handle_message(&mut self, message: ToOverlordMessage) -> Result<(), Error> {
+        let (job_id, message) = match message {
+            ToOverlordMessage::Tracked(job_id, inner) => (job_id, *inner),
+            other => return self.handle_untracked_message(other).await,
+        };
+
+        crate::bus::send(BusMessage::JobStarted(job_id));
+        self.current_job = Some(job_id);
+        let result = self.handle_untracked_message(message).await;
+        self.current_job = None;
+        crate::bus::send(BusMessage::JobCompleted {
+            job_id,
+            error: result.as_ref().err().map(|e| format!("{}", e)),
+        });
+
+        result
+    }
+
+    /// Report progress on the tracked job currently being handled, if any
+    fn job_progress(&self, detail: String) {
+        if let Some(job_id) = self.current_job {
+            crate::bus::send(BusMessage::JobProgress { job_id, detail });
+        }
+    }
+
+    async fn handle_untracked_message(&mut self, message: ToOverlordMessage) -> Result<(), Error> {
         match message {
             ToOverlordMessage::AddRelay(relay_url) => {
                 self.add_relay(relay_url).await?;
@@ -608,6 +726,9 @@ impl Overlord {
             ToOverlordMessage::AdvertiseRelayListNextChunk(event, relays) => {
                 self.advertise_relay_list_next_chunk(event, relays).await?;
             }
+            ToOverlordMessage::AuditEvents => {
+                Self::audit_events()?;
+            }
             ToOverlordMessage::AuthApproved(relay_url, permanent) => {
                 self.auth_approved(relay_url, permanent)?;
             }
@@ -617,15 +738,45 @@ impl Overlord {
             ToOverlordMessage::ChangePassphrase { old, new } => {
                 Self::change_passphrase(old, new).await?;
             }
+            ToOverlordMessage::CheckDvmJob(request_id) => {
+                self.check_dvm_job(request_id).await?;
+            }
+            ToOverlordMessage::CheckWalletBalance => {
+                Self::check_wallet_balance();
+            }
+            ToOverlordMessage::CheckWriteRedundancy(id) => {
+                self.check_write_redundancy(id).await?;
+            }
             ToOverlordMessage::ClearPersonList(list) => {
                 self.clear_person_list(list)?;
             }
+            ToOverlordMessage::ClearRelayAuthKey(relay_url) => {
+                Self::clear_relay_auth_key(relay_url)?;
+            }
+            ToOverlordMessage::CollectGarbage => {
+                Self::collect_garbage();
+            }
+            ToOverlordMessage::CompactDatabase => {
+                self.compact_database().await?;
+            }
+            ToOverlordMessage::ConfirmHeldPost(id, post) => {
+                self.confirm_held_post(id, post).await?;
+            }
             ToOverlordMessage::ConnectApproved(relay_url, permanent) => {
                 self.connect_approved(relay_url, permanent).await?;
             }
+            ToOverlordMessage::ConnectBunker(uri) => {
+                self.connect_bunker(uri).await?;
+            }
             ToOverlordMessage::ConnectDeclined(relay_url, permanent) => {
                 self.connect_declined(relay_url, permanent).await?;
             }
+            ToOverlordMessage::ConnectWallet(uri) => {
+                self.connect_wallet(uri).await?;
+            }
+            ToOverlordMessage::CountFeedSources => {
+                Self::count_feed_sources()?;
+            }
             ToOverlordMessage::DelegationReset => {
                 Self::delegation_reset().await?;
             }
@@ -641,15 +792,27 @@ impl Overlord {
             ToOverlordMessage::DeletePub => {
                 Self::delete_pub().await?;
             }
+            ToOverlordMessage::DisconnectBunker => {
+                Self::disconnect_bunker()?;
+            }
+            ToOverlordMessage::DisconnectWallet => {
+                Self::disconnect_wallet()?;
+            }
             ToOverlordMessage::DropRelay(relay_url) => {
                 self.drop_relay(relay_url)?;
             }
+            ToOverlordMessage::EndFeedPreview(id) => {
+                self.end_feed_preview(id)?;
+            }
             ToOverlordMessage::FetchEvent(id, relay_urls) => {
                 self.fetch_event(id, relay_urls).await?;
             }
             ToOverlordMessage::FetchEventAddr(ea) => {
                 self.fetch_event_addr(ea).await?;
             }
+            ToOverlordMessage::FetchZapSigner(pubkey) => {
+                Self::fetch_zap_signer(pubkey)?;
+            }
             ToOverlordMessage::FollowPubkey(pubkey, list, private) => {
                 self.follow_pubkey(pubkey, list, private).await?;
             }
@@ -671,6 +834,12 @@ impl Overlord {
             ToOverlordMessage::ImportPub(pubstr) => {
                 Self::import_pub(pubstr).await?;
             }
+            ToOverlordMessage::JoinGroup(relay, id) => {
+                self.join_group(relay, id).await?;
+            }
+            ToOverlordMessage::LeaveGroup(relay, id) => {
+                self.leave_group(relay, id).await?;
+            }
             ToOverlordMessage::Like(id, pubkey) => {
                 self.like(id, pubkey).await?;
             }
@@ -680,9 +849,16 @@ impl Overlord {
                     FeedKind::Inbox(_) => self.load_more_inbox_feed().await?,
                     FeedKind::Person(pubkey) => self.load_more_person_feed(pubkey).await?,
                     FeedKind::DmChat(_) => (), // DmChat is complete, not chunked
+                    FeedKind::Starred => (),   // Starred is complete, not chunked
+                    FeedKind::Group(..) => (), // Group is complete, not chunked
+                    FeedKind::Community(..) => (), // Community is complete, not chunked
+                    FeedKind::Dvm(_) => (),    // Dvm is complete, not chunked
                     FeedKind::Thread { .. } => (), // Thread is complete, not chunked
                 }
             }
+            ToOverlordMessage::LoadWallet => {
+                self.load_wallet().await?;
+            }
             ToOverlordMessage::MinionJobComplete(url, job_id) => {
                 self.finish_job(url, Some(job_id), None)?;
             }
@@ -708,6 +884,12 @@ impl Overlord {
                 self.nip46_server_op_approval_response(pubkey, parsed_command, approval)
                     .await?;
             }
+            ToOverlordMessage::PayDvmJob(request_id, amount_msat) => {
+                Self::pay_dvm_job(request_id, amount_msat)?;
+            }
+            ToOverlordMessage::PayZapInvoice(id, invoice) => {
+                Self::pay_zap_invoice(id, invoice);
+            }
             ToOverlordMessage::RefreshScoresAndPickRelays => {
                 self.refresh_scores_and_pick_relays().await?;
             }
@@ -722,42 +904,122 @@ impl Overlord {
             ToOverlordMessage::PostAgain(event) => {
                 self.post_again(event).await?;
             }
+            ToOverlordMessage::PostNip17Dm {
+                dm_channel,
+                content,
+                in_reply_to,
+            } => {
+                self.post_nip17_dm(dm_channel, content, in_reply_to).await?;
+            }
             ToOverlordMessage::PostNip46Event(event, relays) => {
                 self.post_nip46_event(event, relays).await?;
             }
+            ToOverlordMessage::PostToGroup {
+                relay,
+                group,
+                content,
+                in_reply_to,
+            } => {
+                self.post_to_group(relay, group, content, in_reply_to)
+                    .await?;
+            }
+            ToOverlordMessage::PostWalletRequest(event, relays) => {
+                self.post_wallet_request(event, relays).await?;
+            }
+            ToOverlordMessage::PromoteStarToBookmark(id) => {
+                self.promote_star_to_bookmark(id).await?;
+            }
+            ToOverlordMessage::PruneByPolicy => {
+                Self::prune_by_policy();
+            }
             ToOverlordMessage::PruneCache => {
                 Self::prune_cache().await?;
             }
             ToOverlordMessage::PruneDatabase => {
                 Self::prune_database()?;
             }
+            ToOverlordMessage::PushBlockedRelays => {
+                self.push_blocked_relays().await?;
+            }
+            ToOverlordMessage::PushDmRelays => {
+                self.push_dm_relays().await?;
+            }
             ToOverlordMessage::PushPersonList(person_list) => {
                 self.push_person_list(person_list).await?;
             }
             ToOverlordMessage::PushMetadata(metadata) => {
                 self.push_metadata(metadata).await?;
             }
+            ToOverlordMessage::PushSearchRelays => {
+                self.push_search_relays().await?;
+            }
             ToOverlordMessage::RankRelay(relay_url, rank) => {
                 Self::rank_relay(relay_url, rank)?;
             }
+            ToOverlordMessage::RawRelayQuery {
+                relay,
+                filter,
+                ingest,
+                cap,
+            } => {
+                Self::raw_relay_query(relay, filter, ingest, cap)?;
+            }
+            ToOverlordMessage::RebroadcastEvents(events) => {
+                self.rebroadcast_events(events).await?;
+            }
             ToOverlordMessage::ReengageMinion(url) => {
                 self.reengage_minion(url).await?;
             }
+            ToOverlordMessage::RefreshPersonList(list) => {
+                self.refresh_person_list(list).await?;
+            }
             ToOverlordMessage::RefreshSubscribedMetadata => {
                 self.refresh_subscribed_metadata().await?;
             }
             ToOverlordMessage::Repost(id) => {
                 self.repost(id).await?;
             }
+            ToOverlordMessage::RequestDvmJob {
+                kind,
+                input,
+                input_type,
+                params,
+                provider,
+            } => {
+                self.request_dvm_job(kind, input, input_type, params, provider)
+                    .await?;
+            }
+            ToOverlordMessage::ResolvePersonListImport(text) => {
+                Self::resolve_person_list_import(text).await?;
+            }
             ToOverlordMessage::Search(text) => {
                 Self::search(text).await?;
             }
+            ToOverlordMessage::SearchRelays(text) => {
+                self.search_relays(text).await?;
+            }
             ToOverlordMessage::SetActivePerson(pubkey) => {
                 Self::set_active_person(pubkey).await?;
             }
+            ToOverlordMessage::SetEncryptAtRest(on) => {
+                Self::set_encrypt_at_rest(on)?;
+            }
+            ToOverlordMessage::SetRelayAuthKey {
+                relay,
+                privkey,
+                password,
+            } => {
+                Self::set_relay_auth_key(relay, privkey, password)?;
+            }
+            ToOverlordMessage::SetCommunityFeed(addr) => {
+                self.set_community_feed(addr).await?;
+            }
             ToOverlordMessage::SetDmChannel(dmchannel) => {
                 self.set_dm_channel(dmchannel).await?;
             }
+            ToOverlordMessage::SetGroupFeed(relay, id) => {
+                self.set_group_feed(relay, id).await?;
+            }
             ToOverlordMessage::SetPersonFeed(pubkey) => {
                 self.set_person_feed(pubkey).await?;
             }
@@ -768,9 +1030,15 @@ impl Overlord {
             } => {
                 self.set_thread_feed(id, referenced_by, author).await?;
             }
+            ToOverlordMessage::StartFeedPreview { id, pubkeys, since } => {
+                self.start_feed_preview(id, pubkeys, since).await?;
+            }
             ToOverlordMessage::StartLongLivedSubscriptions => {
                 self.start_long_lived_subscriptions().await?;
             }
+            ToOverlordMessage::SubscribeBunker => {
+                self.subscribe_bunker().await?;
+            }
             ToOverlordMessage::SubscribeConfig(opt_relays) => {
                 self.subscribe_config(opt_relays).await?;
             }
@@ -780,12 +1048,36 @@ impl Overlord {
             ToOverlordMessage::SubscribeInbox(opt_relays) => {
                 self.subscribe_inbox(opt_relays).await?;
             }
+            ToOverlordMessage::SubscribeOtherInboxes => {
+                self.subscribe_other_inboxes().await?;
+            }
+            ToOverlordMessage::SubscribeLiveChat(addr) => {
+                self.subscribe_live_chat(addr).await?;
+            }
             ToOverlordMessage::SubscribeNip46(relays) => {
                 self.subscribe_nip46(relays).await?;
             }
+            ToOverlordMessage::SweepHistory { rebroadcast } => {
+                Self::sweep_history(rebroadcast)?;
+            }
+            ToOverlordMessage::SwitchProfile(name) => {
+                self.switch_profile(name).await?;
+            }
+            ToOverlordMessage::TestRelay(url) => {
+                Self::test_relay(url)?;
+            }
+            ToOverlordMessage::Tracked(job_id, _) => {
+                tracing::warn!("Ignoring nested tracked message for job {}", job_id);
+            }
             ToOverlordMessage::UnlockKey(password) => {
                 Self::unlock_key(password)?;
             }
+            ToOverlordMessage::UnsubscribeGroup => {
+                self.unsubscribe_group()?;
+            }
+            ToOverlordMessage::UnsubscribeLiveChat => {
+                self.unsubscribe_live_chat();
+            }
             ToOverlordMessage::UpdateMetadata(pubkey) => {
                 self.update_metadata(pubkey).await?;
             }
@@ -798,6 +1090,12 @@ impl Overlord {
             ToOverlordMessage::UpdateRelay(old, new) => {
                 self.update_relay(old, new).await?;
             }
+            ToOverlordMessage::UsePairedSigner(client_pubkey) => {
+                self.use_paired_signer(client_pubkey).await?;
+            }
+            ToOverlordMessage::ViewProfile(pubkey) => {
+                self.view_profile(pubkey).await?;
+            }
             ToOverlordMessage::VisibleNotesChanged(visible) => {
                 self.visible_notes_changed(visible).await?;
             }
@@ -861,7 +1159,7 @@ impl Overlord {
             content: "".to_string(),
         };
 
-        let event = GLOBALS.identity.sign_event(pre_event)?;
+        let event = GLOBALS.identity.sign_event(pre_event).await?;
 
         let relays: Vec<RelayUrl> = GLOBALS
             .storage
@@ -1019,6 +1317,33 @@ impl Overlord {
         Ok(())
     }
 
+    /// If a post has not reached enough relays yet, send it to alternate ones
+    /// (see [write_redundancy](crate::write_redundancy))
+    pub async fn check_write_redundancy(&mut self, id: Id) -> Result<(), Error> {
+        let (events, urls) = match crate::write_redundancy::next_relays(id)? {
+            Some(next) => next,
+            None => return Ok(()),
+        };
+
+        for url in urls {
+            tracing::debug!("Asking {} to post (for redundancy)", &url);
+            self.engage_minion(
+                url.clone(),
+                vec![RelayJob {
+                    reason: RelayConnectionReason::PostEvent,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::PostEvents(events.clone()),
+                    },
+                }],
+            )
+            .await?;
+        }
+
+        crate::write_redundancy::schedule_check(id);
+        Ok(())
+    }
+
     /// Clear the specified person lit. This wipes everybody. But it doesn't publish
     /// the empty list. You should probably double-check that the user is certain.
     pub fn clear_person_list(&mut self, list: PersonList) -> Result<(), Error> {
@@ -1026,6 +1351,17 @@ impl Overlord {
         Ok(())
     }
 
+    /// Stop using a separate key to AUTH to this relay. The posting identity is
+    /// used again from the next AUTH challenge on.
+    pub fn clear_relay_auth_key(relay_url: RelayUrl) -> Result<(), Error> {
+        GLOBALS.identity.clear_relay_auth_key(&relay_url)?;
+        GLOBALS
+            .status_queue
+            .write()
+            .write(format!("{} will get our own key on AUTH", relay_url));
+        Ok(())
+    }
+
     /// User has approved connection to this relay. Save this result for later
     /// and inform the minion.
     pub async fn connect_approved(
@@ -1077,6 +1413,38 @@ impl Overlord {
         Ok(())
     }
 
+    /// Use the remote signer (NIP-46 bunker) of this URI instead of a private key.
+    /// See [bunker::state](crate::bunker::state) for how it goes.
+    pub async fn connect_bunker(&mut self, uri: BunkerUri) -> Result<(), Error> {
+        crate::bunker::start(&uri)?;
+        self.subscribe_bunker().await?;
+        let secret = uri.secret;
+        std::mem::drop(task::spawn(crate::bunker::handshake(true, secret)));
+        Ok(())
+    }
+
+    /// Pay zaps with the wallet (NIP-47) of this URI from now on
+    pub async fn connect_wallet(&mut self, uri: NwcUri) -> Result<(), Error> {
+        crate::nwc::start(uri)?;
+        self.subscribe_wallet().await?;
+        GLOBALS
+            .status_queue
+            .write()
+            .write("Wallet connected.".to_string());
+        Self::check_wallet_balance();
+        Ok(())
+    }
+
+    /// Ask our wallet (NIP-47) its balance. See [nwc::balance](crate::nwc::balance).
+    pub fn check_wallet_balance() {
+        std::mem::drop(tokio::spawn(async {
+            if let Err(e) = crate::nwc::get_balance().await {
+                tracing::warn!("{}", e);
+                GLOBALS.status_queue.write().write(format!("{}", e));
+            }
+        }));
+    }
+
     /// Remove any key delegation setup
     pub async fn delegation_reset() -> Result<(), Error> {
         if GLOBALS.delegation.reset() {
@@ -1100,10 +1468,10 @@ impl Overlord {
 
         // Delete the list locally
         let mut txn = GLOBALS.storage.get_write_txn()?;
-        GLOBALS.storage.clear_person_list(list, Some(&mut txn))?;
+        GLOBALS.storage.clear_person_list(list, Some(&mut *txn))?;
         GLOBALS
             .storage
-            .deallocate_person_list(list, Some(&mut txn))?;
+            .deallocate_person_list(list, Some(&mut *txn))?;
         txn.commit()?;
 
         // If we are only following, nothing else needed
@@ -1172,7 +1540,7 @@ impl Overlord {
             };
 
             // Should we add a pow? Maybe the relay needs it.
-            GLOBALS.identity.sign_event(pre_event)?
+            GLOBALS.identity.sign_event(pre_event).await?
         };
 
         // Process this event locally
@@ -1255,7 +1623,7 @@ impl Overlord {
             };
 
             // Should we add a pow? Maybe the relay needs it.
-            GLOBALS.identity.sign_event(pre_event)?
+            GLOBALS.identity.sign_event(pre_event).await?
         };
 
         // Process this event locally
@@ -1325,6 +1693,26 @@ impl Overlord {
         Ok(())
     }
 
+    /// Stop using the remote signer (NIP-46 bunker). Our public key stays.
+    pub fn disconnect_bunker() -> Result<(), Error> {
+        crate::bunker::stop()?;
+        GLOBALS
+            .status_queue
+            .write()
+            .write("Remote signer disconnected.".to_string());
+        Ok(())
+    }
+
+    /// Stop paying zaps with our wallet (NIP-47)
+    pub fn disconnect_wallet() -> Result<(), Error> {
+        crate::nwc::stop()?;
+        GLOBALS
+            .status_queue
+            .write()
+            .write("Wallet disconnected.".to_string());
+        Ok(())
+    }
+
     /// Disconnect from the specified relay. This may not happen immediately if the minion
     /// handling that relay is stuck waiting for a timeout.
     pub fn drop_relay(&mut self, relay_url: RelayUrl) -> Result<(), Error> {
@@ -1399,6 +1787,17 @@ impl Overlord {
         Ok(())
     }
 
+    /// Look up the key that signs zap receipts for `recipient` in the background.
+    /// See [crate::zaps::zap_signer].
+    pub fn fetch_zap_signer(recipient: PublicKey) -> Result<(), Error> {
+        std::mem::drop(tokio::spawn(async move {
+            if let Err(e) = crate::zaps::fetch_zap_signer(recipient).await {
+                tracing::warn!("Zap signer of {}: {}", recipient.as_hex_string(), e);
+            }
+        }));
+        Ok(())
+    }
+
     /// Follow a person by `PublicKey`
     pub async fn follow_pubkey(
         &mut self,
@@ -1535,62 +1934,294 @@ impl Overlord {
         Ok(())
     }
 
-    fn maybe_disconnect_relay(&mut self, url: &RelayUrl) -> Result<(), Error> {
-        if let Some(refmut) = GLOBALS.connected_relays.get_mut(url) {
-            // If no job remains, disconnect the relay
-            let mut disconnect = refmut.value().is_empty();
-
-            // If only one 'augments' job remains, disconnect the relay
-            if refmut.value().len() == 1
-                && refmut.value()[0].reason == RelayConnectionReason::FetchAugments
-            {
-                disconnect = true;
+    /// Ask data vending machines (NIP-90) to do a job, or just the one `provider` if
+    /// given. Service providers answer on our read relays, where we wait for them.
+    pub async fn request_dvm_job(
+        &mut self,
+        kind: u32,
+        input: String,
+        input_type: String,
+        params: Vec<(String, String)>,
+        provider: Option<PublicKey>,
+    ) -> Result<(), Error> {
+        let public_key = match GLOBALS.identity.public_key() {
+            Some(pk) => pk,
+            None => {
+                tracing::warn!("No public key! Not posting");
+                return Ok(());
             }
+        };
 
-            if disconnect {
-                let _ = self.to_minions.send(ToMinionMessage {
-                    target: url.as_str().to_owned(),
-                    payload: ToMinionPayload {
-                        job_id: 0,
-                        detail: ToMinionPayloadDetail::Shutdown,
-                    },
-                });
-            }
+        if !crate::dvm::is_job_request_kind(kind) {
+            return Err(
+                ErrorKind::General(format!("Kind {} is not a job request kind", kind)).into(),
+            );
         }
 
-        Ok(())
-    }
+        // Where service providers should answer
+        let relays: Vec<RelayUrl> = GLOBALS
+            .storage
+            .filter_relays(|r| r.has_usage_bits(Relay::READ) && r.rank != 0)?
+            .iter()
+            .map(|relay| relay.url.clone())
+            .collect();
 
-    /// Like a post. The backend doesn't read the event, so you have to supply the
-    /// pubkey author too.
-    pub async fn like(&mut self, id: Id, pubkey: PublicKey) -> Result<(), Error> {
-        let event = {
-            let public_key = match GLOBALS.identity.public_key() {
-                Some(pk) => pk,
-                None => {
-                    tracing::warn!("No public key! Not posting");
-                    return Ok(());
-                }
-            };
+        let pre_event = PreEvent {
+            pubkey: public_key,
+            created_at: Unixtime::now().unwrap(),
+            kind: EventKind::from(kind),
+            tags: crate::dvm::job_request_tags(&input, &input_type, &params, provider, &relays),
+            content: "".to_owned(),
+        };
+        let event = GLOBALS.identity.sign_event(pre_event).await?;
 
-            let mut tags: Vec<Tag> = vec![
-                Tag::new_event(
-                    id,
-                    Relay::recommended_relay_for_reply(id)
-                        .await?
-                        .map(|rr| rr.to_unchecked_url()),
+        let mut job = crate::dvm::DvmJob::new(event.id, kind, event.created_at);
+        job.input = input;
+        job.provider = provider;
+        job.relays = relays;
+        GLOBALS.storage.write_dvm_job(&job, None)?;
+        crate::process::process_new_event(&event, None, None, false, false).await?;
+
+        // Send it to our write relays, and to the inbox of the provider we asked
+        let mut post_relays: Vec<RelayUrl> = GLOBALS
+            .storage
+            .filter_relays(|r| r.has_usage_bits(Relay::WRITE) && r.rank != 0)?
+            .iter()
+            .map(|relay| relay.url.clone())
+            .collect();
+        if let Some(pubkey) = provider {
+            post_relays.extend(
+                GLOBALS
+                    .storage
+                    .get_best_relays(pubkey, RelayUsage::Inbox)?
+                    .drain(..)
+                    .map(|(url, _)| url),
+            );
+        }
+        post_relays.sort();
+        post_relays.dedup();
+
+        for url in post_relays {
+            self.engage_minion(
+                url,
+                vec![RelayJob {
+                    reason: RelayConnectionReason::DvmJob,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::PostEvents(vec![event.clone()]),
+                    },
+                }],
+            )
+            .await?;
+        }
+
+        self.check_dvm_job(event.id).await
+    }
+
+    /// Wait for answers to a job we asked data vending machines (NIP-90) to do, on
+    /// the relays we asked them to answer on
+    pub async fn check_dvm_job(&mut self, request_id: Id) -> Result<(), Error> {
+        let job = match GLOBALS.storage.read_dvm_job(request_id)? {
+            Some(job) => job,
+            None => return Ok(()),
+        };
+
+        for url in job.relays {
+            self.engage_minion(
+                url,
+                vec![RelayJob {
+                    reason: RelayConnectionReason::DvmJob,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::SubscribeDvmJob(request_id, job.kind + 1000),
+                    },
+                }],
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Pay the invoice a service provider sent for a job we asked data vending
+    /// machines (NIP-90) to do, with our wallet (NIP-47). `amount_msat` is what the
+    /// user was shown; the invoice must be for exactly that.
+    pub fn pay_dvm_job(request_id: Id, amount_msat: u64) -> Result<(), Error> {
+        let invoice = match GLOBALS.storage.read_dvm_job(request_id)? {
+            Some(job) if !job.paid && job.amount_msat == Some(amount_msat) => job.bolt11,
+            _ => None,
+        };
+        let invoice = match invoice {
+            Some(invoice) => invoice,
+            None => {
+                GLOBALS
+                    .status_queue
+                    .write()
+                    .write("There is no invoice to pay for this job.".to_string());
+                return Ok(());
+            }
+        };
+        if crate::nwc::invoice_amount_msat(&invoice) != Some(amount_msat) {
+            GLOBALS.status_queue.write().write(
+                "The invoice is not for the amount the service provider asked. Not paying it."
+                    .to_string(),
+            );
+            return Ok(());
+        }
+        if !crate::nwc::is_connected() {
+            GLOBALS
+                .status_queue
+                .write()
+                .write("Connect a wallet to pay for jobs.".to_string());
+            return Ok(());
+        }
+
+        std::mem::drop(tokio::spawn(async move {
+            match crate::nwc::pay_invoice(&invoice).await {
+                Ok(_) => {
+                    if let Err(e) =
+                        GLOBALS
+                            .storage
+                            .modify_dvm_job(request_id, |job| job.paid = true, None)
+                    {
+                        tracing::error!("{}", e);
+                    }
+                    GLOBALS
+                        .status_queue
+                        .write()
+                        .write("Job paid with your wallet.".to_string());
+                    let _ = crate::nwc::get_balance().await;
+                }
+                Err(e) => {
+                    tracing::warn!("{}", e);
+                    GLOBALS
+                        .status_queue
+                        .write()
+                        .write(format!("Your wallet did not pay for the job: {}", e));
+                }
+            }
+        }));
+
+        Ok(())
+    }
+
+    /// Ask the relay of a group (NIP-29) to let us in. Open groups take us right
+    /// away, closed ones leave it to their admins. The group is joined once the
+    /// relay says so (see [crate::groups]).
+    pub async fn join_group(&mut self, relay: RelayUrl, id: String) -> Result<(), Error> {
+        let event = match Self::group_request(&id, crate::groups::GROUP_JOIN_REQUEST_KIND).await? {
+            Some(event) => event,
+            None => return Ok(()),
+        };
+        crate::groups::join_requested(event.id, relay.clone(), id);
+        self.send_to_group_relay(relay, event).await
+    }
+
+    /// Ask the relay of a group (NIP-29) to take us out of it
+    pub async fn leave_group(&mut self, relay: RelayUrl, id: String) -> Result<(), Error> {
+        if let Some(event) =
+            Self::group_request(&id, crate::groups::GROUP_LEAVE_REQUEST_KIND).await?
+        {
+            self.send_to_group_relay(relay.clone(), event).await?;
+        }
+        GLOBALS
+            .storage
+            .modify_group(&relay, &id, |group| group.joined = false, None)?;
+        Ok(())
+    }
+
+    // Join and leave requests carry nothing but the group's 'h' tag
+    async fn group_request(id: &str, kind: u32) -> Result<Option<Event>, Error> {
+        let public_key = match GLOBALS.identity.public_key() {
+            Some(pk) => pk,
+            None => {
+                tracing::warn!("No public key! Not posting");
+                return Ok(None);
+            }
+        };
+
+        let pre_event = PreEvent {
+            pubkey: public_key,
+            created_at: Unixtime::now().unwrap(),
+            kind: EventKind::from(kind),
+            tags: vec![crate::groups::group_tag(id)],
+            content: "".to_owned(),
+        };
+        Ok(Some(GLOBALS.identity.sign_event(pre_event).await?))
+    }
+
+    // Events of a group go to its relay, and nowhere else
+    async fn send_to_group_relay(&mut self, relay: RelayUrl, event: Event) -> Result<(), Error> {
+        self.engage_minion(
+            relay,
+            vec![RelayJob {
+                reason: RelayConnectionReason::Group,
+                payload: ToMinionPayload {
+                    job_id: rand::random::<u64>(),
+                    detail: ToMinionPayloadDetail::PostEvents(vec![event]),
+                },
+            }],
+        )
+        .await
+    }
+
+    fn maybe_disconnect_relay(&mut self, url: &RelayUrl) -> Result<(), Error> {
+        if let Some(refmut) = GLOBALS.connected_relays.get_mut(url) {
+            // If no job remains, disconnect the relay
+            let mut disconnect = refmut.value().is_empty();
+
+            // If only one 'augments' job remains, disconnect the relay
+            if refmut.value().len() == 1
+                && refmut.value()[0].reason == RelayConnectionReason::FetchAugments
+            {
+                disconnect = true;
+            }
+
+            if disconnect {
+                let _ = self.to_minions.send(ToMinionMessage {
+                    target: url.as_str().to_owned(),
+                    payload: ToMinionPayload {
+                        job_id: 0,
+                        detail: ToMinionPayloadDetail::Shutdown,
+                    },
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Like a post. The backend doesn't read the event, so you have to supply the
+    /// pubkey author too.
+    pub async fn like(&mut self, id: Id, pubkey: PublicKey) -> Result<(), Error> {
+        let event = {
+            let public_key = match GLOBALS.identity.public_key() {
+                Some(pk) => pk,
+                None => {
+                    tracing::warn!("No public key! Not posting");
+                    return Ok(());
+                }
+            };
+
+            let mut tags: Vec<Tag> = vec![
+                Tag::new_event(
+                    id,
+                    Relay::recommended_relay_for_reply(id)
+                        .await?
+                        .map(|rr| rr.to_unchecked_url()),
                     None,
                 ),
                 Tag::new_pubkey(pubkey, None, None),
             ];
+            repair_relay_hints(&mut tags).await;
 
-            if GLOBALS.storage.read_setting_set_client_tag() {
-                tags.push(Tag::new(&["client", "gossip"]));
+            if let Some(tag) = crate::privacy::client_tag() {
+                tags.push(tag);
             }
 
             let pre_event = PreEvent {
                 pubkey: public_key,
-                created_at: Unixtime::now().unwrap(),
+                created_at: crate::privacy::created_at(),
                 kind: EventKind::Reaction,
                 tags,
                 content: "+".to_owned(),
@@ -1604,23 +2235,28 @@ impl Overlord {
                 });
                 GLOBALS
                     .identity
-                    .sign_event_with_pow(pre_event, powint, Some(work_sender))?
+                    .sign_event_with_pow(pre_event, powint, Some(work_sender))
+                    .await?
             } else {
-                GLOBALS.identity.sign_event(pre_event)?
+                GLOBALS.identity.sign_event(pre_event).await?
             }
         };
 
-        let relays: Vec<Relay> = GLOBALS
+        let relay_urls: Vec<RelayUrl> = GLOBALS
             .storage
-            .filter_relays(|r| r.has_usage_bits(Relay::WRITE) && r.rank != 0)?;
+            .filter_relays(|r| r.has_usage_bits(Relay::WRITE) && r.rank != 0)?
+            .drain(..)
+            .map(|r| r.url)
+            .collect();
+        let relay_urls = crate::write_rotation::rotate(event.kind, relay_urls);
         // FIXME - post it to relays we have seen it on.
 
-        for relay in relays {
+        for url in relay_urls {
             // Send it the event to post
-            tracing::debug!("Asking {} to post", &relay.url);
+            tracing::debug!("Asking {} to post", &url);
 
             self.engage_minion(
-                relay.url.clone(),
+                url.clone(),
                 vec![RelayJob {
                     reason: RelayConnectionReason::PostLike,
                     payload: ToMinionPayload {
@@ -1724,7 +2360,9 @@ impl Overlord {
         Ok(())
     }
 
-    /// Process approved nip46 server operation
+    /// Answer a NIP-46 request the user was asked about. `Approval::Once` and
+    /// `Approval::Ask` decide this request only (approving and declining it);
+    /// the others are remembered for the client and the kind of event to sign.
     pub async fn nip46_server_op_approval_response(
         &mut self,
         pubkey: PublicKey,
@@ -1735,23 +2373,19 @@ impl Overlord {
         GLOBALS.pending.take_nip46_request(&pubkey, &parsed_command);
 
         // Handle the request
-        if let Some(mut server) = GLOBALS.storage.read_nip46server(pubkey)? {
-            match parsed_command.method.as_str() {
-                "sign_event" => server.sign_approval = approval,
-                "nip04_encrypt" | "nip44_encrypt" => server.encrypt_approval = approval,
-                "nip04_decrypt" | "nip44_decrypt" => server.decrypt_approval = approval,
-                "nip44_get_key" => {
-                    server.encrypt_approval = approval;
-                    server.decrypt_approval = approval;
-                }
-                _ => {}
+        if let Some(server) = GLOBALS.storage.read_nip46server(pubkey)? {
+            if matches!(
+                approval,
+                Approval::Always | Approval::Until(_) | Approval::None
+            ) {
+                let permission = crate::nip46::permission_of(&parsed_command);
+                GLOBALS
+                    .storage
+                    .write_nip46_permission(pubkey, &permission, approval, None)?;
             }
 
-            // Save back
-            GLOBALS.storage.write_nip46server(&server, None)?;
-
-            // Handle it
-            server.handle(&parsed_command)?;
+            let approved = !matches!(approval, Approval::None | Approval::Ask);
+            server.handle_decided(&parsed_command, approved)?;
         }
 
         Ok(())
@@ -1789,10 +2423,22 @@ impl Overlord {
                     .value_mut()
                     .retain(|job| job.payload.job_id != job_id);
             }
+            GLOBALS
+                .relay_job_activity
+                .remove(&(relay_url.clone(), job_id));
         } else if let Some(reason) = reason {
             if let Some(mut refmut) = GLOBALS.connected_relays.get_mut(&relay_url) {
                 // Remove job by reason
-                refmut.value_mut().retain(|job| job.reason != reason);
+                refmut.value_mut().retain(|job| {
+                    if job.reason == reason {
+                        GLOBALS
+                            .relay_job_activity
+                            .remove(&(relay_url.clone(), job.payload.job_id));
+                        false
+                    } else {
+                        true
+                    }
+                });
             }
         }
 
@@ -1802,8 +2448,49 @@ impl Overlord {
         Ok(())
     }
 
-    /// Post a TextNote (kind 1) event
+    /// Post a TextNote (kind 1) event. If it looks like one we posted recently it
+    /// is held back as a pending item until confirmed with
+    /// [confirm_held_post](Self::confirm_held_post).
     pub async fn post(
+        &mut self,
+        content: String,
+        tags: Vec<Tag>,
+        reply_to: Option<Id>,
+        dm_channel: Option<DmChannel>,
+    ) -> Result<(), Error> {
+        if dm_channel.is_none() {
+            if let Some(previous) = crate::duplicate_post::find_duplicate(&content, reply_to)? {
+                crate::duplicate_post::hold(crate::duplicate_post::HeldPost {
+                    content,
+                    tags,
+                    in_reply_to: reply_to,
+                    previous,
+                });
+                GLOBALS.status_queue.write().write(
+                    "This looks like something you just posted. Confirm it under pending notifications to post it anyway.".to_owned(),
+                );
+                return Ok(());
+            }
+        }
+
+        self.post_unchecked(content, tags, reply_to, dm_channel)
+            .await
+    }
+
+    /// Post (or with `post` false, discard) a post held back as a likely duplicate
+    pub async fn confirm_held_post(&mut self, id: u64, post: bool) -> Result<(), Error> {
+        let held = match crate::duplicate_post::take(id) {
+            Some(held) => held,
+            None => return Ok(()),
+        };
+        if post {
+            self.post_unchecked(held.content, held.tags, held.in_reply_to, None)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn post_unchecked(
         &mut self,
         content: String,
         mut tags: Vec<Tag>,
@@ -1818,28 +2505,52 @@ impl Overlord {
             }
         };
 
+        // DMs go to the relays of their recipients, and nowhere else
+        let is_dm = dm_channel.is_some();
+
+        if !is_dm && GLOBALS.storage.read_setting_alt_text_required() {
+            let missing = crate::event_media::media_missing_alt(&content, &tags);
+            if !missing.is_empty() {
+                return Err(ErrorKind::General(format!(
+                    "Alt text is required but missing for {}",
+                    missing.join(", ")
+                ))
+                .into());
+            }
+        }
+
+        // NIP-17 if everybody in the channel takes it, otherwise NIP-04 (one to one)
+        if let Some(dmc) = &dm_channel {
+            if crate::nip17::can_send(dmc)? {
+                return self.post_nip17_dm(dmc.clone(), content, reply_to).await;
+            }
+        }
+
         let pre_event = match dm_channel {
             Some(dmc) => {
                 if dmc.keys().len() > 1 {
                     return Err((ErrorKind::GroupDmsNotYetSupported, file!(), line!()).into());
                 }
 
+                // Writing to someone accepts their message request
+                crate::dm_policy::accept_dm_channel(&dmc)?;
+
                 let recipient = if dmc.keys().is_empty() {
                     public_key // must be to yourself
                 } else {
                     dmc.keys()[0]
                 };
 
-                // On a DM, we ignore tags and reply_to
-                let enc_content = GLOBALS.identity.encrypt(
-                    &recipient,
-                    &content,
-                    ContentEncryptionAlgorithm::Nip04,
-                )?;
+                // On a DM, we ignore tags and reply_to. Kind 4 stays NIP-04, which
+                // is what other clients expect of it.
+                let enc_content = GLOBALS
+                    .identity
+                    .encrypt(&recipient, &content, ContentEncryptionAlgorithm::Nip04)
+                    .await?;
 
                 PreEvent {
                     pubkey: public_key,
-                    created_at: Unixtime::now().unwrap(),
+                    created_at: crate::privacy::created_at(),
                     kind: EventKind::EncryptedDirectMessage,
                     tags: vec![Tag::new_pubkey(
                         recipient, None, // FIXME
@@ -1849,9 +2560,7 @@ impl Overlord {
                 }
             }
             _ => {
-                if GLOBALS.storage.read_setting_set_client_tag() {
-                    tags.push(Tag::new(&["client", "gossip"]));
-                }
+                // (The client tag is added per relay, see PostingPolicy)
 
                 // Add Tags based on references in the content
                 //
@@ -1927,7 +2636,7 @@ impl Overlord {
 
                     // Possibly add a tag to the 'root'
                     let mut parent_is_root = true;
-                    match parent.replies_to_root() {
+                    match crate::nip10::replies_to_root(&parent) {
                         Some(EventReference::Id {
                             id: root,
                             author: _,
@@ -1952,7 +2661,7 @@ impl Overlord {
                         None => {
                             // double check in case replies_to_root() isn't sufficient
                             // (it might be but this code doesn't hurt)
-                            let ancestor = parent.replies_to();
+                            let ancestor = crate::nip10::replies_to(&parent);
                             if ancestor.is_none() {
                                 // parent is the root
                                 add_event_to_tags(&mut tags, parent_id, None, "root").await;
@@ -1992,11 +2701,26 @@ impl Overlord {
                             add_subject_to_tags_if_missing(&mut tags, subject);
                         }
                     }
+
+                    // Keep replies in the community the parent was posted in
+                    for tag in crate::communities::community_tags_of(&parent) {
+                        if !tags.contains(&tag) {
+                            tags.push(tag);
+                        }
+                    }
+                }
+
+                // Replace dead or private relay hints
+                repair_relay_hints(&mut tags).await;
+
+                // Keep the markers and the positions in agreement for old clients
+                if GLOBALS.storage.read_setting_legacy_thread_heuristics() {
+                    crate::nip10::order_e_tags_positionally(&mut tags);
                 }
 
                 PreEvent {
                     pubkey: public_key,
-                    created_at: Unixtime::now().unwrap(),
+                    created_at: crate::privacy::created_at(),
                     kind: EventKind::TextNote,
                     tags,
                     content,
@@ -2017,27 +2741,6 @@ impl Overlord {
             })
             .collect();
 
-        let event = {
-            let powint = GLOBALS.storage.read_setting_pow();
-            if powint > 0 {
-                let (work_sender, work_receiver) = mpsc::channel();
-                std::thread::spawn(move || {
-                    work_logger(work_receiver, powint);
-                });
-                GLOBALS
-                    .identity
-                    .sign_event_with_pow(pre_event, powint, Some(work_sender))?
-            } else {
-                GLOBALS.identity.sign_event(pre_event)?
-            }
-        };
-
-        // Process this event locally
-        crate::process::process_new_event(&event, None, None, false, false).await?;
-
-        // Maybe include parent to retransmit it
-        let events = vec![event];
-
         // Determine which relays to post this to
         let mut relay_urls: Vec<RelayUrl> = Vec::new();
         {
@@ -2053,39 +2756,170 @@ impl Overlord {
                 relay_urls.extend(best_relays);
             }
 
-            // Get all of the relays that we write to
+            // Get all of the relays that we write to (or some, if they rotate)
             let write_relay_urls: Vec<RelayUrl> = GLOBALS
                 .storage
                 .filter_relays(|r| r.has_usage_bits(Relay::WRITE) && r.rank != 0)?
                 .iter()
                 .map(|relay| relay.url.clone())
                 .collect();
-            relay_urls.extend(write_relay_urls);
+            relay_urls.extend(crate::write_rotation::rotate(
+                pre_event.kind,
+                write_relay_urls,
+            ));
+
+            // And the relays of any community it is posted in
+            relay_urls.extend(crate::communities::relays_for_tags(&pre_event.tags)?);
 
             relay_urls.sort();
             relay_urls.dedup();
         }
 
+        // Group the relays by posting policy. The post is signed once, under a policy
+        // that satisfies them all, and each group gets that same event.
+        let mut groups: Vec<(PostingPolicy, Vec<RelayUrl>)> = Vec::new();
         for url in relay_urls {
-            // Send it the event to post
-            tracing::debug!("Asking {} to post", &url);
-
-            self.engage_minion(
-                url.clone(),
-                vec![RelayJob {
-                    reason: RelayConnectionReason::PostEvent,
-                    payload: ToMinionPayload {
-                        job_id: rand::random::<u64>(),
-                        detail: ToMinionPayloadDetail::PostEvents(events.clone()),
-                    },
-                }],
-            )
-            .await?;
+            let policy = match GLOBALS.storage.read_relay(&url, None)? {
+                Some(relay) => PostingPolicy::for_relay(&relay),
+                None => PostingPolicy::global(),
+            };
+            match groups.iter_mut().find(|(p, _)| *p == policy) {
+                Some((_, urls)) => urls.push(url),
+                None => groups.push((policy, vec![url])),
+            }
+        }
+        // With no relays we still sign and keep it locally
+        if groups.is_empty() {
+            groups.push((PostingPolicy::global(), vec![]));
         }
 
-        Ok(())
-    }
-
+        // Sign every part of a split note before sending any of it, so a
+        // cancellation or failure leaves nothing half-posted
+        let sign = |pre_event: PreEvent, powint: u8| async move {
+            if GLOBALS.cancel_posting.load(Ordering::Relaxed) {
+                return Err(ErrorKind::General("Posting cancelled".to_owned()).into());
+            }
+            if powint > 0 {
+                let (work_sender, work_receiver) = mpsc::channel();
+                std::thread::spawn(move || {
+                    work_logger(work_receiver, powint);
+                });
+                GLOBALS
+                    .identity
+                    .sign_event_with_pow(pre_event, powint, Some(work_sender))
+                    .await
+            } else {
+                GLOBALS.identity.sign_event(pre_event).await
+            }
+        };
+        GLOBALS.cancel_posting.store(false, Ordering::Relaxed);
+        GLOBALS.posting_in_progress.store(true, Ordering::Relaxed);
+        let policies: Vec<PostingPolicy> = groups.iter().map(|(p, _)| *p).collect();
+        let signed = PostingPolicy::combine(&policies)
+            .sign_events(&pre_event, sign)
+            .await;
+        GLOBALS.posting_in_progress.store(false, Ordering::Relaxed);
+        if GLOBALS.cancel_posting.swap(false, Ordering::Relaxed) {
+            GLOBALS
+                .status_queue
+                .write()
+                .write("Post cancelled. Nothing was sent.".to_owned());
+            return Ok(());
+        }
+        let events = signed?;
+
+        // Relays that don't split and have a tighter limit can't take it
+        let (groups, refused): (Vec<_>, Vec<_>) = groups
+            .into_iter()
+            .partition(|(policy, urls)| urls.is_empty() || policy.accepts(&events));
+        if !refused.is_empty() {
+            let names: Vec<&str> = refused
+                .iter()
+                .flat_map(|(_, urls)| urls.iter().map(|u| u.as_str()))
+                .collect();
+            GLOBALS.status_queue.write().write(format!(
+                "Not posted to {} (too long for their content limit)",
+                names.join(", ")
+            ));
+            if groups.is_empty() {
+                return Ok(());
+            }
+        }
+
+        // Process it locally
+        for event in events.iter() {
+            crate::process::process_new_event(event, None, None, false, false).await?;
+        }
+
+        let mut tried: Vec<RelayUrl> = Vec::new();
+        for (_, urls) in groups {
+            tried.extend(urls.iter().cloned());
+
+            // A split note goes to each relay as a single job, in order
+            for url in urls {
+                // Send it the event to post
+                tracing::debug!("Asking {} to post", &url);
+
+                self.engage_minion(
+                    url.clone(),
+                    vec![RelayJob {
+                        reason: RelayConnectionReason::PostEvent,
+                        payload: ToMinionPayload {
+                            job_id: rand::random::<u64>(),
+                            detail: ToMinionPayloadDetail::PostEvents(events.clone()),
+                        },
+                    }],
+                )
+                .await?;
+            }
+        }
+
+        if !is_dm {
+            crate::write_redundancy::track(events, tried);
+        }
+
+        Ok(())
+    }
+
+    /// Send a private message (NIP-17) to a DM channel. It is giftwrapped to each
+    /// member, to go to their DM relays, and to ourselves so that we keep a copy.
+    pub async fn post_nip17_dm(
+        &mut self,
+        dm_channel: DmChannel,
+        content: String,
+        reply_to: Option<Id>,
+    ) -> Result<(), Error> {
+        // Writing to someone accepts their message request
+        crate::dm_policy::accept_dm_channel(&dm_channel)?;
+
+        let my_pubkey = GLOBALS.identity.public_key();
+        for (pubkey, wrap, urls) in
+            crate::nip17::wrap_message(&dm_channel, content, reply_to).await?
+        {
+            if Some(pubkey) == my_pubkey {
+                crate::process::process_new_event(&wrap, None, None, false, false).await?;
+            }
+
+            for url in urls {
+                tracing::debug!("Asking {} to post a giftwrap", &url);
+
+                self.engage_minion(
+                    url.clone(),
+                    vec![RelayJob {
+                        reason: RelayConnectionReason::PostEvent,
+                        payload: ToMinionPayload {
+                            job_id: rand::random::<u64>(),
+                            detail: ToMinionPayloadDetail::PostEvents(vec![wrap.clone()]),
+                        },
+                    }],
+                )
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+
     pub async fn post_again(&mut self, event: Event) -> Result<(), Error> {
         let relay_urls = Globals::relays_for_event(&event)?;
 
@@ -2119,9 +2953,469 @@ impl Overlord {
             tracing::debug!("Asking {} to post nostrconnect", &url);
 
             self.engage_minion(
-                url.clone(),
+                url.clone(),
+                vec![RelayJob {
+                    reason: RelayConnectionReason::PostNostrConnect,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::PostEvents(vec![event.clone()]),
+                    },
+                }],
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Post a chat message to a group (NIP-29), with `in_reply_to` quoting the message
+    /// it answers. It goes only to the group's relay, tagged with the group's 'h' tag.
+    pub async fn post_to_group(
+        &mut self,
+        relay: RelayUrl,
+        group: String,
+        content: String,
+        in_reply_to: Option<Id>,
+    ) -> Result<(), Error> {
+        let public_key = match GLOBALS.identity.public_key() {
+            Some(pk) => pk,
+            None => {
+                tracing::warn!("No public key! Not posting");
+                return Ok(());
+            }
+        };
+
+        let mut tags: Vec<Tag> = vec![crate::groups::group_tag(&group)];
+        if let Some(parent_id) = in_reply_to {
+            let parent = match GLOBALS.storage.read_event(parent_id)? {
+                Some(e) => e,
+                None => return Err("Cannot find event we are replying to.".into()),
+            };
+            tags.push(Tag::new(&[
+                "q",
+                &parent.id.as_hex_string(),
+                relay.as_str(),
+                &parent.pubkey.as_hex_string(),
+            ]));
+            if parent.pubkey != public_key {
+                tags.push(Tag::new_pubkey(parent.pubkey, None, None));
+            }
+        }
+
+        let pre_event = PreEvent {
+            pubkey: public_key,
+            created_at: crate::privacy::created_at(),
+            kind: EventKind::from(crate::groups::GROUP_CHAT_KIND),
+            tags,
+            content,
+        };
+        let event = GLOBALS.identity.sign_event(pre_event).await?;
+
+        // Process it as seen on the group's relay, so it shows in the group feed now
+        crate::process::process_new_event(&event, Some(relay.clone()), None, false, false).await?;
+
+        self.send_to_group_relay(relay, event).await
+    }
+
+    /// Post a request to our wallet (NIP-47) to its relays
+    pub async fn post_wallet_request(
+        &mut self,
+        event: Event,
+        relays: Vec<RelayUrl>,
+    ) -> Result<(), Error> {
+        for url in relays {
+            self.engage_minion(
+                url.clone(),
+                vec![RelayJob {
+                    reason: RelayConnectionReason::Wallet,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::PostEvents(vec![event.clone()]),
+                    },
+                }],
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Prune the cache (downloaded files)
+    pub async fn prune_cache() -> Result<(), Error> {
+        GLOBALS
+            .status_queue
+            .write()
+            .write("Pruning cache, please be patient..".to_owned());
+
+        let age = Duration::new(
+            GLOBALS.storage.read_setting_cache_prune_period_days() * 60 * 60 * 24,
+            0,
+        );
+
+        let count = GLOBALS.fetcher.prune(age).await?;
+
+        GLOBALS
+            .status_queue
+            .write()
+            .write(format!("Cache has been pruned. {} files removed.", count));
+
+        Ok(())
+    }
+
+    /// Send a filter to one relay and collect the raw results (at most `cap`) in
+    /// `GLOBALS.raw_query`, without ingesting them unless `ingest` is set. A new
+    /// query replaces the previous results.
+    pub fn raw_relay_query(
+        relay: RelayUrl,
+        filter: Filter,
+        ingest: bool,
+        cap: usize,
+    ) -> Result<(), Error> {
+        if matches!(&*GLOBALS.raw_query.read(), Some(results) if results.finished.is_none()) {
+            GLOBALS
+                .status_queue
+                .write()
+                .write("A relay query is already running.".to_owned());
+            return Ok(());
+        }
+
+        std::mem::drop(tokio::spawn(async move {
+            if let Err(e) = crate::relay_query::raw_query(relay, filter, ingest, cap).await {
+                tracing::warn!("Relay query failed: {}", e);
+                GLOBALS
+                    .status_queue
+                    .write()
+                    .write(format!("Relay query failed: {}", e));
+            }
+        }));
+
+        Ok(())
+    }
+
+    /// Send our events to those of our write relays they haven't been seen on
+    pub async fn rebroadcast_events(&mut self, events: Vec<Event>) -> Result<(), Error> {
+        let write_relays: Vec<RelayUrl> = GLOBALS
+            .storage
+            .filter_relays(|r| r.has_usage_bits(Relay::WRITE) && r.rank != 0)?
+            .drain(..)
+            .map(|r| r.url)
+            .collect();
+
+        let mut seen_on: HashMap<Id, Vec<RelayUrl>> = HashMap::new();
+        for event in events.iter() {
+            let relays = GLOBALS
+                .storage
+                .get_event_seen_on_relay(event.id)?
+                .drain(..)
+                .map(|(url, _)| url)
+                .collect();
+            seen_on.insert(event.id, relays);
+        }
+
+        for url in write_relays {
+            let missing: Vec<Event> = events
+                .iter()
+                .filter(|e| {
+                    !seen_on
+                        .get(&e.id)
+                        .map(|r| r.contains(&url))
+                        .unwrap_or(false)
+                })
+                .cloned()
+                .collect();
+            if missing.is_empty() {
+                continue;
+            }
+
+            tracing::info!("Rebroadcasting {} events to {}", missing.len(), &url);
+
+            self.engage_minion(
+                url.clone(),
+                vec![RelayJob {
+                    reason: RelayConnectionReason::PostEvent,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::PostEvents(missing),
+                    },
+                }],
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Collect our past events from the `history_sweep_relays` in the background,
+    /// importing those we are missing and (if `rebroadcast`) sending them on to our
+    /// write relays. Progress and the final report are in `GLOBALS.history_sweep`.
+    pub fn sweep_history(rebroadcast: bool) -> Result<(), Error> {
+        if matches!(&*GLOBALS.history_sweep.read(), Some(report) if report.finished.is_none()) {
+            return Ok(()); // Already running
+        }
+
+        std::mem::drop(tokio::spawn(async move {
+            match crate::history_sweep::sweep_history(rebroadcast).await {
+                Ok(report) => GLOBALS.status_queue.write().write(format!(
+                    "History sweep complete: {} of your events found on {} relays, {} were new.",
+                    report.found,
+                    report.relays.len(),
+                    report.imported
+                )),
+                Err(e) => {
+                    tracing::error!("{}", e);
+                    GLOBALS
+                        .status_queue
+                        .write()
+                        .write(format!("History sweep failed: {}", e));
+                }
+            }
+        }));
+        Ok(())
+    }
+
+    /// Count which relays delivered the events of each person list feed over the
+    /// last week, in the background. The result is in `GLOBALS.feed_sources`.
+    pub fn count_feed_sources() -> Result<(), Error> {
+        if GLOBALS.feed_sources_counting.swap(true, Ordering::SeqCst) {
+            return Ok(()); // Already running
+        }
+
+        std::mem::drop(tokio::spawn(async move {
+            let result =
+                tokio::task::spawn_blocking(crate::feed_relays::all_feed_attribution).await;
+            match result {
+                Ok(Ok(attributions)) => *GLOBALS.feed_sources.write() = Some(attributions),
+                Ok(Err(e)) => {
+                    tracing::error!("{}", e);
+                    GLOBALS.status_queue.write().write(format!("{}", e));
+                }
+                Err(e) => tracing::error!("{}", e),
+            }
+            GLOBALS.feed_sources_counting.store(false, Ordering::SeqCst);
+        }));
+        Ok(())
+    }
+
+    /// Re-verify all stored events in the background, quarantining those that fail.
+    /// Progress and the final report are in `GLOBALS.event_audit`.
+    pub fn audit_events() -> Result<(), Error> {
+        if matches!(&*GLOBALS.event_audit.read(), Some(report) if report.finished.is_none()) {
+            return Ok(()); // Already running
+        }
+
+        std::mem::drop(tokio::spawn(async move {
+            match crate::audit::audit_events().await {
+                Ok(report) => GLOBALS.status_queue.write().write(format!(
+                    "Event audit complete: {} checked, {} failed and quarantined.",
+                    report.checked,
+                    report.failed.len()
+                )),
+                Err(e) => {
+                    tracing::error!("{}", e);
+                    GLOBALS
+                        .status_queue
+                        .write()
+                        .write(format!("Event audit failed: {}", e));
+                }
+            }
+        }));
+        Ok(())
+    }
+
+    /// Prune the database (events and more)
+    pub fn prune_database() -> Result<(), Error> {
+        GLOBALS
+            .status_queue
+            .write()
+            .write("Pruning database, please be patient..".to_owned());
+
+        let now = Unixtime::now().unwrap();
+        let then = now
+            - Duration::new(
+                GLOBALS.storage.read_setting_prune_period_days() * 60 * 60 * 24,
+                0,
+            );
+        let count = GLOBALS.storage.prune(then)?;
+
+        GLOBALS.status_queue.write().write(format!(
+            "Database has been pruned. {} events removed.",
+            count
+        ));
+
+        crate::pruning::collect_garbage();
+
+        Ok(())
+    }
+
+    /// Delete old events by the pruning policies in settings, in the background
+    pub fn prune_by_policy() {
+        std::mem::drop(task::spawn_blocking(
+            || match crate::pruning::prune_by_policy() {
+                Ok(0) => crate::pruning::collect_garbage(),
+                Ok(count) => {
+                    tracing::info!("Pruning policies removed {} events", count);
+                    GLOBALS.status_queue.write().write(format!(
+                        "Database has been pruned. {} events removed.",
+                        count
+                    ));
+                    crate::pruning::collect_garbage();
+                }
+                Err(e) => tracing::error!("{}", e),
+            },
+        ));
+    }
+
+    /// Remove what the auxiliary tables hold about events we don't have, in the
+    /// background
+    pub fn collect_garbage() {
+        std::mem::drop(task::spawn_blocking(crate::pruning::collect_garbage));
+    }
+
+    /// Publish the user's specified PersonList
+    pub async fn push_person_list(&mut self, list: PersonList) -> Result<(), Error> {
+        let metadata = match GLOBALS.storage.get_person_list_metadata(list)? {
+            Some(m) => m,
+            None => return Ok(()),
+        };
+        if metadata.smart.is_some() {
+            return Err(ErrorKind::ListIsSmart.into());
+        }
+
+        let event = GLOBALS.people.generate_person_list_event(list).await?;
+
+        // process event locally
+        crate::process::process_new_event(&event, None, None, false, false).await?;
+
+        // Push to all of the relays we post to
+        let relays: Vec<Relay> = GLOBALS
+            .storage
+            .filter_relays(|r| r.has_usage_bits(Relay::WRITE) && r.rank != 0)?;
+
+        for relay in relays {
+            // Send it the event to pull our followers
+            tracing::debug!("Pushing PersonList={} to {}", metadata.title, &relay.url);
+
+            self.engage_minion(
+                relay.url.clone(),
+                vec![RelayJob {
+                    reason: RelayConnectionReason::PostContacts,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::PostEvents(vec![event.clone()]),
+                    },
+                }],
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Publish the user's blocked relays list (NIP-51 kind 10006). Local wildcard
+    /// patterns are not included.
+    pub async fn push_blocked_relays(&mut self) -> Result<(), Error> {
+        let public_key = match GLOBALS.identity.public_key() {
+            Some(pk) => pk,
+            None => return Err((ErrorKind::NoPrivateKey, file!(), line!()).into()), // not even a public key
+        };
+
+        let mut tags: Vec<Tag> = Vec::new();
+        for url in GLOBALS.storage.read_setting_blocked_relays().iter() {
+            if let Ok(relay_url) = RelayUrl::try_from_str(url) {
+                tags.push(Tag::new_relay(relay_url.to_unchecked_url(), None));
+            }
+        }
+
+        let pre_event = PreEvent {
+            pubkey: public_key,
+            created_at: Unixtime::now().unwrap(),
+            kind: EventKind::BlockedRelaysList,
+            tags,
+            content: "".to_string(),
+        };
+
+        let event = GLOBALS.identity.sign_event(pre_event).await?;
+
+        // process event locally
+        crate::process::process_new_event(&event, None, None, false, false).await?;
+
+        // Push to all of the relays we post to
+        let relays: Vec<Relay> = GLOBALS
+            .storage
+            .filter_relays(|r| r.has_usage_bits(Relay::WRITE) && r.rank != 0)?;
+
+        for relay in relays {
+            tracing::debug!("Pushing blocked relays list to {}", &relay.url);
+
+            self.engage_minion(
+                relay.url.clone(),
+                vec![RelayJob {
+                    reason: RelayConnectionReason::PostEvent,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::PostEvents(vec![event.clone()]),
+                    },
+                }],
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Publish a locally starred event as a bookmark, by adding it to the user's
+    /// bookmark list (NIP-51 kind 10003) and republishing that. The local star is
+    /// removed once the list has been published. Private bookmarks in the list
+    /// content are carried over unchanged.
+    pub async fn promote_star_to_bookmark(&mut self, id: Id) -> Result<(), Error> {
+        let public_key = match GLOBALS.identity.public_key() {
+            Some(pk) => pk,
+            None => return Err((ErrorKind::NoPrivateKey, file!(), line!()).into()), // not even a public key
+        };
+
+        let (mut tags, content) =
+            match GLOBALS
+                .storage
+                .get_replaceable_event(EventKind::BookmarkList, public_key, "")?
+            {
+                Some(list) => (list.tags, list.content),
+                None => (vec![], "".to_owned()),
+            };
+
+        let already = tags
+            .iter()
+            .any(|tag| matches!(tag.parse_event(), Ok((tid, _, _)) if tid == id));
+        if !already {
+            let mut tag = [Tag::new_event(id, None, None)];
+            repair_relay_hints(&mut tag).await; // fills in a relay hint
+            tags.extend(tag);
+        }
+
+        let pre_event = PreEvent {
+            pubkey: public_key,
+            created_at: Unixtime::now().unwrap(),
+            kind: EventKind::BookmarkList,
+            tags,
+            content,
+        };
+
+        let event = GLOBALS.identity.sign_event(pre_event).await?;
+
+        // process event locally
+        crate::process::process_new_event(&event, None, None, false, false).await?;
+
+        // Push to all of the relays we post to
+        let relays: Vec<Relay> = GLOBALS
+            .storage
+            .filter_relays(|r| r.has_usage_bits(Relay::WRITE) && r.rank != 0)?;
+
+        for relay in relays {
+            tracing::debug!("Pushing bookmark list to {}", &relay.url);
+
+            self.engage_minion(
+                relay.url.clone(),
                 vec![RelayJob {
-                    reason: RelayConnectionReason::PostNostrConnect,
+                    reason: RelayConnectionReason::PostEvent,
                     payload: ToMinionPayload {
                         job_id: rand::random::<u64>(),
                         detail: ToMinionPayloadDetail::PostEvents(vec![event.clone()]),
@@ -2131,62 +3425,88 @@ impl Overlord {
             .await?;
         }
 
+        GLOBALS.storage.unstar_event(id, None)?;
+        GLOBALS.feed.sync_recompute();
+
         Ok(())
     }
 
-    /// Prune the cache (downloaded files)
-    pub async fn prune_cache() -> Result<(), Error> {
-        GLOBALS
-            .status_queue
-            .write()
-            .write("Pruning cache, please be patient..".to_owned());
+    /// Publish the user's DM relays list (NIP-17 kind 10050), being the relays
+    /// marked with the DM usage bit
+    pub async fn push_dm_relays(&mut self) -> Result<(), Error> {
+        let public_key = match GLOBALS.identity.public_key() {
+            Some(pk) => pk,
+            None => return Err((ErrorKind::NoPrivateKey, file!(), line!()).into()), // not even a public key
+        };
 
-        let age = Duration::new(
-            GLOBALS.storage.read_setting_cache_prune_period_days() * 60 * 60 * 24,
-            0,
-        );
+        let tags: Vec<Tag> = GLOBALS
+            .storage
+            .filter_relays(|r| r.has_usage_bits(Relay::DM))?
+            .iter()
+            .map(|relay| Tag::new_relay(relay.url.to_unchecked_url(), None))
+            .collect();
 
-        let count = GLOBALS.fetcher.prune(age).await?;
+        let pre_event = PreEvent {
+            pubkey: public_key,
+            created_at: Unixtime::now().unwrap(),
+            kind: EventKind::DmRelayList,
+            tags,
+            content: "".to_string(),
+        };
 
-        GLOBALS
-            .status_queue
-            .write()
-            .write(format!("Cache has been pruned. {} files removed.", count));
+        let event = GLOBALS.identity.sign_event(pre_event).await?;
 
-        Ok(())
-    }
+        // process event locally
+        crate::process::process_new_event(&event, None, None, false, false).await?;
 
-    /// Prune the database (events and more)
-    pub fn prune_database() -> Result<(), Error> {
-        GLOBALS
-            .status_queue
-            .write()
-            .write("Pruning database, please be patient..".to_owned());
+        // Push to all of the relays we post to
+        let relays: Vec<Relay> = GLOBALS
+            .storage
+            .filter_relays(|r| r.has_usage_bits(Relay::WRITE) && r.rank != 0)?;
 
-        let now = Unixtime::now().unwrap();
-        let then = now
-            - Duration::new(
-                GLOBALS.storage.read_setting_prune_period_days() * 60 * 60 * 24,
-                0,
-            );
-        let count = GLOBALS.storage.prune(then)?;
+        for relay in relays {
+            tracing::debug!("Pushing DM relays list to {}", &relay.url);
 
-        GLOBALS.status_queue.write().write(format!(
-            "Database has been pruned. {} events removed.",
-            count
-        ));
+            self.engage_minion(
+                relay.url.clone(),
+                vec![RelayJob {
+                    reason: RelayConnectionReason::PostEvent,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::PostEvents(vec![event.clone()]),
+                    },
+                }],
+            )
+            .await?;
+        }
 
         Ok(())
     }
 
-    /// Publish the user's specified PersonList
-    pub async fn push_person_list(&mut self, list: PersonList) -> Result<(), Error> {
-        let metadata = match GLOBALS.storage.get_person_list_metadata(list)? {
-            Some(m) => m,
-            None => return Ok(()),
+    /// Publish the user's search relays list (NIP-51 kind 10007), being the relays
+    /// marked with the SEARCH usage bit
+    pub async fn push_search_relays(&mut self) -> Result<(), Error> {
+        let public_key = match GLOBALS.identity.public_key() {
+            Some(pk) => pk,
+            None => return Err((ErrorKind::NoPrivateKey, file!(), line!()).into()), // not even a public key
         };
 
-        let event = GLOBALS.people.generate_person_list_event(list).await?;
+        let tags: Vec<Tag> = GLOBALS
+            .storage
+            .filter_relays(|r| r.has_usage_bits(Relay::SEARCH))?
+            .iter()
+            .map(|relay| Tag::new_relay(relay.url.to_unchecked_url(), None))
+            .collect();
+
+        let pre_event = PreEvent {
+            pubkey: public_key,
+            created_at: Unixtime::now().unwrap(),
+            kind: EventKind::SearchRelaysList,
+            tags,
+            content: "".to_string(),
+        };
+
+        let event = GLOBALS.identity.sign_event(pre_event).await?;
 
         // process event locally
         crate::process::process_new_event(&event, None, None, false, false).await?;
@@ -2197,13 +3517,12 @@ impl Overlord {
             .filter_relays(|r| r.has_usage_bits(Relay::WRITE) && r.rank != 0)?;
 
         for relay in relays {
-            // Send it the event to pull our followers
-            tracing::debug!("Pushing PersonList={} to {}", metadata.title, &relay.url);
+            tracing::debug!("Pushing search relays list to {}", &relay.url);
 
             self.engage_minion(
                 relay.url.clone(),
                 vec![RelayJob {
-                    reason: RelayConnectionReason::PostContacts,
+                    reason: RelayConnectionReason::PostEvent,
                     payload: ToMinionPayload {
                         job_id: rand::random::<u64>(),
                         detail: ToMinionPayloadDetail::PostEvents(vec![event.clone()]),
@@ -2231,7 +3550,7 @@ impl Overlord {
             content: serde_json::to_string(&metadata)?,
         };
 
-        let event = GLOBALS.identity.sign_event(pre_event)?;
+        let event = GLOBALS.identity.sign_event(pre_event).await?;
 
         // Push to all of the relays we post to
         let relays: Vec<Relay> = GLOBALS
@@ -2269,6 +3588,131 @@ impl Overlord {
         Ok(())
     }
 
+    /// Fetch what the people in this list posted since it was last fetched, with
+    /// one-off subscriptions on their relays. This is how lists that are not live
+    /// (see [ListRefresh](crate::ListRefresh)) get their content.
+    pub async fn refresh_person_list(&mut self, list: PersonList) -> Result<(), Error> {
+        let since = match crate::list_refresh::mark_refreshed(list) {
+            Some(last) => Unixtime(last.0 - GLOBALS.storage.read_setting_overlap() as i64),
+            None => Unixtime(
+                Unixtime::now().unwrap().0 - GLOBALS.storage.read_setting_feed_chunk() as i64,
+            ),
+        };
+
+        let num_relays_per_person = GLOBALS.storage.read_setting_num_relays_per_person();
+
+        // Sort the people into the relays they write to
+        let mut map: HashMap<RelayUrl, Vec<PublicKey>> = HashMap::new();
+        for (pubkey, _) in GLOBALS.storage.get_people_in_list(list)?.drain(..) {
+            for (relay, _) in GLOBALS
+                .storage
+                .get_best_relays(pubkey, RelayUsage::Outbox)?
+                .drain(..)
+                .take(num_relays_per_person as usize)
+            {
+                map.entry(relay).or_default().push(pubkey);
+            }
+        }
+
+        for (relay_url, pubkeys) in map.drain() {
+            self.engage_minion(
+                relay_url,
+                vec![RelayJob {
+                    reason: RelayConnectionReason::Follow,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::TempSubscribeListFeed { pubkeys, since },
+                    },
+                }],
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Subscribe to the posts of these people since `since` on the relays they write
+    /// to, for a [FeedPreview](crate::feed_preview::FeedPreview) with this id
+    pub async fn start_feed_preview(
+        &mut self,
+        id: u64,
+        pubkeys: Vec<PublicKey>,
+        since: Unixtime,
+    ) -> Result<(), Error> {
+        let num_relays_per_person = GLOBALS.storage.read_setting_num_relays_per_person();
+
+        // Sort the people (but not the muted ones) into the relays they write to,
+        // leaving out blocked relays so they don't use up a person's share
+        let mut map: HashMap<RelayUrl, Vec<PublicKey>> = HashMap::new();
+        for pubkey in pubkeys {
+            if GLOBALS.people.is_person_in_list(&pubkey, PersonList::Muted) {
+                continue;
+            }
+            for (relay, _) in GLOBALS
+                .storage
+                .get_best_relays(pubkey, RelayUsage::Outbox)?
+                .drain(..)
+                .filter(|(relay, _)| !crate::blocked_relays::is_relay_blocked(relay))
+                .take(num_relays_per_person as usize)
+            {
+                map.entry(relay).or_default().push(pubkey);
+            }
+        }
+
+        for (relay_url, pubkeys) in map.drain() {
+            self.engage_minion(
+                relay_url,
+                vec![RelayJob {
+                    reason: RelayConnectionReason::PreviewFeed,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::SubscribePreviewFeed { id, pubkeys, since },
+                    },
+                }],
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Close the subscriptions of the [FeedPreview](crate::feed_preview::FeedPreview)
+    /// with this id, and drop the relays that were connected only for it
+    pub fn end_feed_preview(&mut self, id: u64) -> Result<(), Error> {
+        let _ = GLOBALS.to_minions.send(ToMinionMessage {
+            target: "all".to_string(),
+            payload: ToMinionPayload {
+                job_id: 0,
+                detail: ToMinionPayloadDetail::UnsubscribePreviewFeed(id),
+            },
+        });
+
+        let jobs: Vec<(RelayUrl, u64)> = GLOBALS
+            .connected_relays
+            .iter()
+            .flat_map(|refmulti| {
+                let relay_url = refmulti.key().clone();
+                refmulti
+                    .value()
+                    .iter()
+                    .filter(|job| {
+                        matches!(
+                            job.payload.detail,
+                            ToMinionPayloadDetail::SubscribePreviewFeed { id: job_id, .. }
+                                if job_id == id
+                        )
+                    })
+                    .map(|job| (relay_url.clone(), job.payload.job_id))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        for (relay_url, job_id) in jobs {
+            self.finish_job(relay_url, Some(job_id), None)?;
+        }
+
+        Ok(())
+    }
+
     /// Refresh metadata for everybody who is followed
     /// This gets it whether we had it or not. Because it might have changed.
     pub async fn refresh_subscribed_metadata(&mut self) -> Result<(), Error> {
@@ -2314,6 +3758,25 @@ impl Overlord {
         Ok(())
     }
 
+    /// Resolve the entries of an exported person list (CSV or JSON) to pubkeys, for
+    /// review. The result goes to `GLOBALS.person_list_import`.
+    pub async fn resolve_person_list_import(text: String) -> Result<(), Error> {
+        *GLOBALS.person_list_import.write() = None;
+        std::mem::drop(tokio::spawn(async move {
+            match crate::person_list_io::resolve_person_list_import(&text).await {
+                Ok(entries) => *GLOBALS.person_list_import.write() = Some(entries),
+                Err(e) => {
+                    GLOBALS
+                        .status_queue
+                        .write()
+                        .write(format!("Could not read the person list: {}", e));
+                    *GLOBALS.person_list_import.write() = Some(vec![]);
+                }
+            }
+        }));
+        Ok(())
+    }
+
     /// Repost a post by `Id`
     pub async fn repost(&mut self, id: Id) -> Result<(), Error> {
         let reposted_event = match GLOBALS.storage.read_event(id)? {
@@ -2343,6 +3806,7 @@ impl Overlord {
             Tag::new_pubkey(reposted_event.pubkey, None, None),
             Tag::new_event(id, relay_url.clone(), None),
         ];
+        repair_relay_hints(&mut tags).await;
 
         if reposted_event.kind != EventKind::TextNote {
             kind = EventKind::GenericRepost;
@@ -2376,13 +3840,13 @@ impl Overlord {
                 }
             };
 
-            if GLOBALS.storage.read_setting_set_client_tag() {
-                tags.push(Tag::new(&["client", "gossip"]));
+            if let Some(tag) = crate::privacy::client_tag() {
+                tags.push(tag);
             }
 
             let pre_event = PreEvent {
                 pubkey: public_key,
-                created_at: Unixtime::now().unwrap(),
+                created_at: crate::privacy::created_at(),
                 kind,
                 tags,
                 content: serde_json::to_string(&reposted_event)?,
@@ -2396,9 +3860,10 @@ impl Overlord {
                 });
                 GLOBALS
                     .identity
-                    .sign_event_with_pow(pre_event, powint, Some(work_sender))?
+                    .sign_event_with_pow(pre_event, powint, Some(work_sender))
+                    .await?
             } else {
-                GLOBALS.identity.sign_event(pre_event)?
+                GLOBALS.identity.sign_event(pre_event).await?
             }
         };
 
@@ -2408,14 +3873,14 @@ impl Overlord {
         // Determine which relays to post this to
         let mut relay_urls: Vec<RelayUrl> = Vec::new();
         {
-            // Get all of the relays that we write to
+            // Get all of the relays that we write to (or some, if they rotate)
             let write_relay_urls: Vec<RelayUrl> = GLOBALS
                 .storage
                 .filter_relays(|r| r.has_usage_bits(Relay::WRITE) && r.rank != 0)?
                 .iter()
                 .map(|relay| relay.url.clone())
                 .collect();
-            relay_urls.extend(write_relay_urls);
+            relay_urls.extend(crate::write_rotation::rotate(event.kind, write_relay_urls));
             relay_urls.sort();
             relay_urls.dedup();
         }
@@ -2440,7 +3905,7 @@ impl Overlord {
         Ok(())
     }
 
-    /// Search people and notes in the local database.
+    /// Search people and notes in the local database, and notes on search relays.
     /// Search results eventually arrive in `GLOBALS.people_search_results` and `GLOBALS.note_search_results`
     pub async fn search(mut text: String) -> Result<(), Error> {
         if text.len() < 2 {
@@ -2536,6 +4001,11 @@ impl Overlord {
                 }
                 NostrBech32::Relay(_relay) => (),
             }
+        } else {
+            // Also ask search relays (NIP-50). Results arrive after the local ones.
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::SearchRelays(text.clone()));
         }
 
         people_search_results.extend(GLOBALS.storage.filter_people(|p| {
@@ -2553,21 +4023,181 @@ impl Overlord {
                 }
             }
 
-            false
-        })?);
-
-        note_search_results.extend(GLOBALS.storage.search_events(&text)?);
-
-        *GLOBALS.people_search_results.write() = people_search_results;
-        *GLOBALS.note_search_results.write() = note_search_results;
-
+            false
+        })?);
+
+        note_search_results.extend(GLOBALS.storage.search_events(&text)?);
+
+        *GLOBALS.people_search_results.write() = people_search_results;
+        *GLOBALS.note_search_results.write() = note_search_results;
+
+        Ok(())
+    }
+
+    /// Search for notes on relays (NIP-50). Only the user's search relays (kind 10007)
+    /// are asked; if there are none, read relays that advertise NIP-50 support are asked
+    /// instead. Results are added to `GLOBALS.note_search_results` as they arrive.
+    pub async fn search_relays(&mut self, text: String) -> Result<(), Error> {
+        let mut relays: Vec<RelayUrl> = GLOBALS
+            .storage
+            .filter_relays(|r| r.has_usage_bits(Relay::SEARCH) && r.rank != 0)?
+            .iter()
+            .map(|relay| relay.url.clone())
+            .collect();
+
+        if relays.is_empty() {
+            relays = GLOBALS
+                .storage
+                .filter_relays(|r| {
+                    r.has_usage_bits(Relay::READ)
+                        && r.rank != 0
+                        && r.nip11
+                            .as_ref()
+                            .map(|doc| doc.supported_nips.contains(&50))
+                            .unwrap_or(false)
+                })?
+                .iter()
+                .map(|relay| relay.url.clone())
+                .collect();
+        }
+
+        if relays.is_empty() {
+            tracing::debug!("No search relays, searched locally only");
+            return Ok(());
+        }
+
+        for relay_url in relays {
+            self.engage_minion(
+                relay_url,
+                vec![RelayJob {
+                    reason: RelayConnectionReason::Search,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::TempSubscribeSearch(text.clone()),
+                    },
+                }],
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Set a particular person as active in the `People` structure. This affects the results of
+    /// some functions of that structure
+    pub async fn set_active_person(pubkey: PublicKey) -> Result<(), Error> {
+        GLOBALS.people.set_active_person(pubkey).await?;
+        Ok(())
+    }
+
+    /// Turn encryption at rest of our private records on or off, and reseal the
+    /// records already stored to match. With the identity locked, that waits until
+    /// it is next unlocked at startup.
+    pub fn set_encrypt_at_rest(on: bool) -> Result<(), Error> {
+        let mut txn = GLOBALS.storage.get_write_txn()?;
+        GLOBALS
+            .storage
+            .write_setting_encrypt_at_rest(&on, Some(&mut *txn))?;
+        GLOBALS
+            .storage
+            .set_flag_reseal_needed(true, Some(&mut *txn))?;
+        txn.commit()?;
+
+        if GLOBALS.identity.is_unlocked() {
+            std::mem::drop(tokio::task::spawn_blocking(Self::reseal_private_records));
+        }
+        Ok(())
+    }
+
+    fn reseal_private_records() {
+        match GLOBALS.storage.reseal_private_records() {
+            Ok(count) => GLOBALS.status_queue.write().write(format!(
+                "Private data is now {}. {} records rewritten.",
+                if GLOBALS.storage.read_setting_encrypt_at_rest() {
+                    "encrypted"
+                } else {
+                    "unencrypted"
+                },
+                count
+            )),
+            Err(e) => tracing::error!("{}", e),
+        }
+    }
+
+    /// Authenticate to this relay with a separate key (e.g. a paid-membership key)
+    /// instead of the posting identity. `privkey` is an nsec or hex, and `password`
+    /// must be the passphrase of our identity.
+    pub fn set_relay_auth_key(
+        relay_url: RelayUrl,
+        mut privkey: String,
+        mut password: String,
+    ) -> Result<(), Error> {
+        let maybe_pk1 = PrivateKey::try_from_bech32_string(privkey.trim());
+        let maybe_pk2 = PrivateKey::try_from_hex_string(privkey.trim());
+        privkey.zeroize();
+        let privkey = match (maybe_pk1, maybe_pk2) {
+            (Ok(pk), _) | (_, Ok(pk)) => pk,
+            _ => {
+                password.zeroize();
+                GLOBALS
+                    .status_queue
+                    .write()
+                    .write("Private key not recognized.".to_owned());
+                return Ok(());
+            }
+        };
+
+        let result = GLOBALS
+            .identity
+            .set_relay_auth_key(&relay_url, privkey, &password);
+        password.zeroize();
+        match result {
+            Ok(pubkey) => GLOBALS.status_queue.write().write(format!(
+                "{} will get {} on AUTH",
+                relay_url,
+                pubkey.as_bech32_string()
+            )),
+            Err(e) => GLOBALS.status_queue.write().write(format!(
+                "Could not set the AUTH key for {}: {}",
+                relay_url, e
+            )),
+        }
         Ok(())
     }
 
-    /// Set a particular person as active in the `People` structure. This affects the results of
-    /// some functions of that structure
-    pub async fn set_active_person(pubkey: PublicKey) -> Result<(), Error> {
-        GLOBALS.people.set_active_person(pubkey).await?;
+    async fn set_community_feed(&mut self, addr: EventAddr) -> Result<(), Error> {
+        // Read the community's own relays, or if we don't know of any yet, its
+        // author's outbox relays
+        let mut relays: Vec<RelayUrl> =
+            match GLOBALS.storage.read_community(&addr.author, &addr.d)? {
+                Some(community) => community.relays,
+                None => vec![],
+            };
+        if relays.is_empty() {
+            let num_relays_per_person = GLOBALS.storage.read_setting_num_relays_per_person();
+            relays = GLOBALS
+                .storage
+                .get_best_relays(addr.author, RelayUsage::Outbox)?
+                .drain(..)
+                .take(num_relays_per_person as usize + 1)
+                .map(|(relay, _rank)| relay)
+                .collect();
+        }
+
+        for relay in relays.iter() {
+            self.engage_minion(
+                relay.to_owned(),
+                vec![RelayJob {
+                    reason: RelayConnectionReason::Community,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::SubscribeCommunity(addr.clone()),
+                    },
+                }],
+            )
+            .await?;
+        }
+
         Ok(())
     }
 
@@ -2597,7 +4227,39 @@ impl Overlord {
         Ok(())
     }
 
+    async fn set_group_feed(&mut self, relay: RelayUrl, id: String) -> Result<(), Error> {
+        // The group we were reading may be on another relay
+        self.unsubscribe_group()?;
+
+        self.engage_minion(
+            relay,
+            vec![RelayJob {
+                reason: RelayConnectionReason::Group,
+                payload: ToMinionPayload {
+                    job_id: rand::random::<u64>(),
+                    detail: ToMinionPayloadDetail::SubscribeGroup(id),
+                },
+            }],
+        )
+        .await?;
+
+        Ok(())
+    }
+
     async fn set_person_feed(&mut self, pubkey: PublicKey) -> Result<(), Error> {
+        // Profile views may not ask for notes. Then stop listening to the notes of
+        // whoever we viewed before, as no new subscription replaces theirs.
+        if !ProfileFetch::current().notes {
+            let _ = self.to_minions.send(ToMinionMessage {
+                target: "all".to_string(),
+                payload: ToMinionPayload {
+                    job_id: 0,
+                    detail: ToMinionPayloadDetail::UnsubscribePersonFeed,
+                },
+            });
+            return Ok(());
+        }
+
         let num_relays_per_person = GLOBALS.storage.read_setting_num_relays_per_person();
 
         let relays: Vec<RelayUrl> = GLOBALS
@@ -2876,11 +4538,20 @@ impl Overlord {
         // Separately subscribe to our outbox events on our write relays
         self.subscribe_config(None).await?;
 
+        // Check our replaceable events on our read relays for edits made elsewhere
+        self.reconcile_replaceables().await?;
+
+        // Take in what the relay monitors we trust observed about relays
+        self.fetch_relay_monitor_reports().await?;
+
         // Separately subscribe to our inbox on our read relays
         // NOTE: we also do this on all dynamically connected relays since NIP-65 is
         //       not in widespread usage.
         self.subscribe_inbox(None).await?;
 
+        // And to mentions of our other identities, on their own inbox relays
+        self.subscribe_other_inboxes().await?;
+
         // Separately subscribe to nostr-connect channels
         let mut relays: Vec<RelayUrl> = Vec::new();
         let servers = GLOBALS.storage.read_all_nip46servers()?;
@@ -2891,32 +4562,280 @@ impl Overlord {
         if let Some(nip46unconnected) = GLOBALS.storage.read_nip46_unconnected_server()? {
             relays.extend(nip46unconnected.relays);
         }
-        relays.sort();
-        relays.dedup();
-        self.subscribe_nip46(relays).await?;
+        relays.sort();
+        relays.dedup();
+        self.subscribe_nip46(relays).await?;
+
+        // And to our remote signer, and check in with it
+        if crate::bunker::connection().is_some() {
+            self.subscribe_bunker().await?;
+            std::mem::drop(task::spawn(crate::bunker::handshake(false, None)));
+        }
+
+        // And to our wallet
+        self.load_wallet().await?;
+
+        Ok(())
+    }
+
+    /// Fetch the recent reports of the relay monitors we trust (NIP-66) from our
+    /// discover relays. They fill in for our own relay measurements while those are
+    /// sparse (see [relay_monitor](crate::relay_monitor)).
+    pub async fn fetch_relay_monitor_reports(&mut self) -> Result<(), Error> {
+        let monitors = crate::relay_monitor::monitors();
+        if monitors.is_empty() {
+            return Ok(());
+        }
+
+        let discover_relay_urls: Vec<RelayUrl> = GLOBALS
+            .storage
+            .filter_relays(|r| r.has_usage_bits(Relay::DISCOVER) && r.rank != 0)?
+            .iter()
+            .map(|relay| relay.url.clone())
+            .collect();
+        for relay_url in discover_relay_urls.iter() {
+            self.engage_minion(
+                relay_url.to_owned(),
+                vec![RelayJob {
+                    reason: RelayConnectionReason::Discovery,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::TempSubscribeRelayMonitors(monitors.clone()),
+                    },
+                }],
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Fetch the latest versions of our replaceable events (profile, lists, relay
+    /// list) from our read relays. Any that is newer than what we last published
+    /// raises a pending conflict (see [reconcile](crate::reconcile)).
+    pub async fn reconcile_replaceables(&mut self) -> Result<(), Error> {
+        if GLOBALS.identity.public_key().is_none() {
+            return Ok(());
+        }
+
+        let read_relay_urls: Vec<RelayUrl> = GLOBALS
+            .storage
+            .filter_relays(|r| r.has_usage_bits(Relay::READ) && r.rank != 0)?
+            .iter()
+            .map(|relay| relay.url.clone())
+            .collect();
+        for relay_url in read_relay_urls.iter() {
+            self.engage_minion(
+                relay_url.to_owned(),
+                vec![RelayJob {
+                    reason: RelayConnectionReason::Config,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::TempSubscribeOwnReplaceables,
+                    },
+                }],
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Subscribe to the user's configuration events from the given relay
+    pub async fn subscribe_config(&mut self, relays: Option<Vec<RelayUrl>>) -> Result<(), Error> {
+        let config_relays: Vec<RelayUrl> = match relays {
+            Some(r) => r,
+            None => {
+                let mut urls: Vec<RelayUrl> = GLOBALS
+                    .storage
+                    .filter_relays(|r| r.has_usage_bits(Relay::WRITE) && r.rank != 0)?
+                    .iter()
+                    .map(|relay| relay.url.clone())
+                    .collect();
+
+                // Our giftwraps come to our DM relays
+                if let Some(pubkey) = GLOBALS.identity.public_key() {
+                    for url in crate::nip17::dm_relays(pubkey)? {
+                        if !urls.contains(&url) {
+                            urls.push(url);
+                        }
+                    }
+                }
+                urls
+            }
+        };
+        for relay_url in config_relays.iter() {
+            self.engage_minion(
+                relay_url.to_owned(),
+                vec![RelayJob {
+                    reason: RelayConnectionReason::Config,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::SubscribeConfig,
+                    },
+                }],
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Subscribe to the multiple user's relay lists (optionally on the given relays, otherwise using
+    /// theconfigured discover relays)
+    ///
+    /// Caller should probably check Person.relay_list_last_sought first to make sure we don't
+    /// already have an in-flight request doing this.  This can be done with:
+    ///    GLOBALS.people.person_needs_relay_list()
+    ///    GLOBALS.people.get_subscribed_pubkeys_needing_relay_lists()
+    pub async fn subscribe_discover(
+        &mut self,
+        pubkeys: Vec<PublicKey>,
+        relays: Option<Vec<RelayUrl>>,
+    ) -> Result<(), Error> {
+        // Mark for each person that we are seeking their relay list
+        // so that we don't repeat this for a while
+        let now = Unixtime::now().unwrap();
+        let mut txn = GLOBALS.storage.get_write_txn()?;
+        for pk in pubkeys.iter() {
+            let mut person = GLOBALS.storage.read_or_create_person(pk, Some(&mut *txn))?;
+            person.relay_list_last_sought = now.0;
+            GLOBALS.storage.write_person(&person, Some(&mut *txn))?;
+        }
+        txn.commit()?;
+
+        // Discover their relays
+        let discover_relay_urls: Vec<RelayUrl> = match relays {
+            Some(r) => r,
+            None => GLOBALS
+                .storage
+                .filter_relays(|r| r.has_usage_bits(Relay::DISCOVER) && r.rank != 0)?
+                .iter()
+                .map(|relay| relay.url.clone())
+                .collect(),
+        };
+        for relay_url in discover_relay_urls.iter() {
+            self.engage_minion(
+                relay_url.to_owned(),
+                vec![RelayJob {
+                    reason: RelayConnectionReason::Discovery,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::SubscribeDiscover(pubkeys.clone()),
+                    },
+                }],
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Subscribe to the user's configuration events from the given relay
+    pub async fn subscribe_inbox(&mut self, relays: Option<Vec<RelayUrl>>) -> Result<(), Error> {
+        let mention_relays: Vec<RelayUrl> = match relays {
+            Some(r) => r,
+            None => GLOBALS
+                .storage
+                .filter_relays(|r| r.has_usage_bits(Relay::READ) && r.rank != 0)?
+                .iter()
+                .map(|relay| relay.url.clone())
+                .collect(),
+        };
+        for relay_url in mention_relays.iter() {
+            self.engage_minion(
+                relay_url.to_owned(),
+                vec![RelayJob {
+                    reason: RelayConnectionReason::FetchInbox,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::SubscribeInbox,
+                    },
+                }],
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Subscribe to mentions of the other identities we gather notifications for
+    /// (see [notifications](crate::notifications)), each on its own inbox relays.
+    /// The subscriptions made for them before are closed first, so this also drops
+    /// the identities no longer gathered for.
+    pub async fn subscribe_other_inboxes(&mut self) -> Result<(), Error> {
+        let _ = GLOBALS.to_minions.send(ToMinionMessage {
+            target: "all".to_string(),
+            payload: ToMinionPayload {
+                job_id: 0,
+                detail: ToMinionPayloadDetail::UnsubscribeOtherInboxes,
+            },
+        });
+        let relay_urls: Vec<RelayUrl> = GLOBALS
+            .connected_relays
+            .iter()
+            .filter(|refmulti| {
+                refmulti
+                    .value()
+                    .iter()
+                    .any(|job| job.reason == RelayConnectionReason::FetchOtherInboxes)
+            })
+            .map(|refmulti| refmulti.key().clone())
+            .collect();
+        for relay_url in relay_urls {
+            self.finish_job(
+                relay_url,
+                None,
+                Some(RelayConnectionReason::FetchOtherInboxes),
+            )?;
+        }
+
+        let num_relays_per_person = GLOBALS.storage.read_setting_num_relays_per_person();
+
+        // Sort the identities into their inbox relays
+        let mut map: HashMap<RelayUrl, Vec<PublicKey>> = HashMap::new();
+        for pubkey in crate::notifications::other_identity_pubkeys() {
+            for (relay, _) in GLOBALS
+                .storage
+                .get_best_relays(pubkey, RelayUsage::Inbox)?
+                .drain(..)
+                .take(num_relays_per_person as usize)
+            {
+                map.entry(relay).or_default().push(pubkey);
+            }
+        }
+
+        for (relay_url, pubkeys) in map.drain() {
+            self.engage_minion(
+                relay_url,
+                vec![RelayJob {
+                    reason: RelayConnectionReason::FetchOtherInboxes,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::SubscribeOtherInboxes(pubkeys),
+                    },
+                }],
+            )
+            .await?;
+        }
 
         Ok(())
     }
 
-    /// Subscribe to the user's configuration events from the given relay
-    pub async fn subscribe_config(&mut self, relays: Option<Vec<RelayUrl>>) -> Result<(), Error> {
-        let config_relays: Vec<RelayUrl> = match relays {
-            Some(r) => r,
-            None => GLOBALS
-                .storage
-                .filter_relays(|r| r.has_usage_bits(Relay::WRITE) && r.rank != 0)?
-                .iter()
-                .map(|relay| relay.url.clone())
-                .collect(),
+    /// Subscribe to the answers of our remote signer (NIP-46 bunker) on its relays
+    pub async fn subscribe_bunker(&mut self) -> Result<(), Error> {
+        let relays = match crate::bunker::connection() {
+            Some(conn) => conn.relays,
+            None => return Ok(()),
         };
-        for relay_url in config_relays.iter() {
+        for relay_url in relays.iter() {
             self.engage_minion(
                 relay_url.to_owned(),
                 vec![RelayJob {
-                    reason: RelayConnectionReason::Config,
+                    reason: RelayConnectionReason::RemoteSigner,
                     payload: ToMinionPayload {
                         job_id: rand::random::<u64>(),
-                        detail: ToMinionPayloadDetail::SubscribeConfig,
+                        detail: ToMinionPayloadDetail::SubscribeBunker,
                     },
                 }],
             )
@@ -2926,47 +4845,27 @@ impl Overlord {
         Ok(())
     }
 
-    /// Subscribe to the multiple user's relay lists (optionally on the given relays, otherwise using
-    /// theconfigured discover relays)
-    ///
-    /// Caller should probably check Person.relay_list_last_sought first to make sure we don't
-    /// already have an in-flight request doing this.  This can be done with:
-    ///    GLOBALS.people.person_needs_relay_list()
-    ///    GLOBALS.people.get_subscribed_pubkeys_needing_relay_lists()
-    pub async fn subscribe_discover(
-        &mut self,
-        pubkeys: Vec<PublicKey>,
-        relays: Option<Vec<RelayUrl>>,
-    ) -> Result<(), Error> {
-        // Mark for each person that we are seeking their relay list
-        // so that we don't repeat this for a while
-        let now = Unixtime::now().unwrap();
-        let mut txn = GLOBALS.storage.get_write_txn()?;
-        for pk in pubkeys.iter() {
-            let mut person = GLOBALS.storage.read_or_create_person(pk, Some(&mut txn))?;
-            person.relay_list_last_sought = now.0;
-            GLOBALS.storage.write_person(&person, Some(&mut txn))?;
-        }
-        txn.commit()?;
+    /// Load the saved wallet (NIP-47) and subscribe to its answers. Its key is
+    /// encrypted with our identity, so this does nothing until that is unlocked.
+    pub async fn load_wallet(&mut self) -> Result<(), Error> {
+        crate::nwc::load()?;
+        self.subscribe_wallet().await
+    }
 
-        // Discover their relays
-        let discover_relay_urls: Vec<RelayUrl> = match relays {
-            Some(r) => r,
-            None => GLOBALS
-                .storage
-                .filter_relays(|r| r.has_usage_bits(Relay::DISCOVER) && r.rank != 0)?
-                .iter()
-                .map(|relay| relay.url.clone())
-                .collect(),
+    /// Subscribe to the answers of our wallet (NIP-47) on its relays
+    pub async fn subscribe_wallet(&mut self) -> Result<(), Error> {
+        let relays = match crate::nwc::connection() {
+            Some(conn) => conn.relays,
+            None => return Ok(()),
         };
-        for relay_url in discover_relay_urls.iter() {
+        for relay_url in relays.iter() {
             self.engage_minion(
                 relay_url.to_owned(),
                 vec![RelayJob {
-                    reason: RelayConnectionReason::Discovery,
+                    reason: RelayConnectionReason::Wallet,
                     payload: ToMinionPayload {
                         job_id: rand::random::<u64>(),
-                        detail: ToMinionPayloadDetail::SubscribeDiscover(pubkeys.clone()),
+                        detail: ToMinionPayloadDetail::SubscribeWallet,
                     },
                 }],
             )
@@ -2976,25 +4875,41 @@ impl Overlord {
         Ok(())
     }
 
-    /// Subscribe to the user's configuration events from the given relay
-    pub async fn subscribe_inbox(&mut self, relays: Option<Vec<RelayUrl>>) -> Result<(), Error> {
-        let mention_relays: Vec<RelayUrl> = match relays {
-            Some(r) => r,
-            None => GLOBALS
-                .storage
-                .filter_relays(|r| r.has_usage_bits(Relay::READ) && r.rank != 0)?
+    /// Subscribe to the chat of a live activity (NIP-53), on the relays it names or
+    /// else on the outbox relays of its host. This replaces the chat subscription of
+    /// any other live activity.
+    pub async fn subscribe_live_chat(&mut self, addr: EventAddr) -> Result<(), Error> {
+        self.unsubscribe_live_chat();
+
+        let mut relays: Vec<RelayUrl> =
+            match GLOBALS.storage.read_live_activity(&addr.author, &addr.d)? {
+                Some(activity) => activity.relays,
+                None => vec![],
+            };
+        relays.extend(
+            addr.relays
                 .iter()
-                .map(|relay| relay.url.clone())
-                .collect(),
-        };
-        for relay_url in mention_relays.iter() {
+                .filter_map(|u| RelayUrl::try_from_unchecked_url(u).ok()),
+        );
+        if relays.is_empty() {
+            relays = GLOBALS
+                .storage
+                .get_best_relays(addr.author, RelayUsage::Outbox)?
+                .drain(..)
+                .map(|(relay, _rank)| relay)
+                .collect();
+        }
+        relays.sort();
+        relays.dedup();
+
+        for relay_url in relays.iter() {
             self.engage_minion(
                 relay_url.to_owned(),
                 vec![RelayJob {
-                    reason: RelayConnectionReason::FetchInbox,
+                    reason: RelayConnectionReason::LiveChat,
                     payload: ToMinionPayload {
                         job_id: rand::random::<u64>(),
-                        detail: ToMinionPayloadDetail::SubscribeInbox,
+                        detail: ToMinionPayloadDetail::SubscribeLiveChat(addr.clone()),
                     },
                 }],
             )
@@ -3023,6 +4938,186 @@ impl Overlord {
         Ok(())
     }
 
+    // Disconnect from all relays, letting the minions finish (for a while), and
+    // forget about the connections. Returns false if they did not finish in time
+    // and were aborted.
+    async fn go_offline_and_wait(&mut self) -> Result<bool, Error> {
+        let _ = GLOBALS.write_runstate.send(RunState::Offline);
+        let drained = tokio::time::timeout(Duration::from_secs(10), async {
+            while !self.minions.is_empty() {
+                let joined = self.minions.join_next_with_id().await;
+                self.handle_task_nextjoined(joined).await;
+            }
+        })
+        .await;
+        if drained.is_err() {
+            tracing::warn!("Minions did not finish in time, aborting them");
+            self.minions.abort_all();
+            self.minions_task_url.clear();
+        }
+        self.augment_subs.clear();
+        GLOBALS.relay_picker.init().await?;
+        GLOBALS.connected_relays.clear();
+        GLOBALS.relay_job_activity.clear();
+        GLOBALS.penalty_box_relays.clear();
+
+        Ok(drained.is_ok())
+    }
+
+    /// Shrink the database file down to what it holds. We go offline and let the
+    /// minions finish so that nothing is written meanwhile, copy the database into a
+    /// fresh compacted file and swap it in, then go back online unless set to stay
+    /// offline. If the minions or other writers don't finish in time, the compaction
+    /// is abandoned.
+    pub async fn compact_database(&mut self) -> Result<(), Error> {
+        GLOBALS
+            .status_queue
+            .write()
+            .write("Compacting database, please be patient..".to_owned());
+
+        let result = if self.go_offline_and_wait().await? {
+            task::spawn_blocking(|| {
+                GLOBALS.storage.compact(
+                    Box::new(GlobalContext),
+                    Duration::from_secs(crate::storage::DRAIN_WRITERS_SECS),
+                )
+            })
+            .await?
+        } else {
+            Err(ErrorKind::General(
+                "Relay connections did not finish in time, so the database was not compacted"
+                    .to_owned(),
+            )
+            .into())
+        };
+        match result {
+            Ok(report) => {
+                tracing::info!(
+                    "Compacted the database from {} to {} bytes",
+                    report.before,
+                    report.after
+                );
+                GLOBALS.status_queue.write().write(format!(
+                    "Database has been compacted from {} MB to {} MB.",
+                    report.before / 1048576,
+                    report.after / 1048576
+                ));
+            }
+            Err(e) => {
+                tracing::error!("Compacting the database failed: {}", e);
+                GLOBALS
+                    .status_queue
+                    .write()
+                    .write(format!("Compacting the database failed: {}", e));
+            }
+        }
+
+        if !GLOBALS.storage.read_setting_offline() {
+            let _ = GLOBALS.write_runstate.send(RunState::Online);
+        }
+
+        Ok(())
+    }
+
+    /// Move the running gossip over to another profile (None for the default one),
+    /// creating it if it does not exist yet. We go offline and let the minions
+    /// finish (or give up if they don't), swap in the storage of that profile, forget
+    /// what we held from the old one and load its identity (which starts out locked),
+    /// then go back online unless that profile is set to stay offline.
+    pub async fn switch_profile(&mut self, name: Option<String>) -> Result<(), Error> {
+        if crate::profile::Profile::current()?.name == name {
+            return Ok(());
+        }
+
+        // Nothing may write into the old storage once it is swapped out
+        if !self.go_offline_and_wait().await? {
+            GLOBALS.status_queue.write().write(
+                "Relay connections did not finish in time, so the profile was not switched"
+                    .to_owned(),
+            );
+            if !GLOBALS.storage.read_setting_offline() {
+                let _ = GLOBALS.write_runstate.send(RunState::Online);
+            }
+            return Ok(());
+        }
+
+        // Swap in the storage of the other profile
+        let previous = crate::profile::Profile::current()?.name;
+        let profile = crate::profile::Profile::switch_to(name.as_deref())?;
+        let swapped = Storage::open(&profile.lmdb_dir, Box::new(GlobalContext)).and_then(|s| {
+            GLOBALS
+                .storage
+                .replace(s, Duration::from_secs(crate::storage::DRAIN_WRITERS_SECS))
+        });
+        if let Err(e) = swapped {
+            let _ = crate::profile::Profile::switch_to(previous.as_deref());
+            GLOBALS
+                .status_queue
+                .write()
+                .write(format!("Switching profiles failed: {}", e));
+            if !GLOBALS.storage.read_setting_offline() {
+                let _ = GLOBALS.write_runstate.send(RunState::Online);
+            }
+            return Err(e);
+        }
+
+        // Forget what we held from the old one
+        GLOBALS.people.clear().await;
+        GLOBALS.feed.clear();
+        GLOBALS.dismissed.write().await.clear();
+        GLOBALS.media.clear().await;
+        GLOBALS.seeker.clear();
+        GLOBALS.identity.clear_conversation_keys();
+
+        // Load what we keep in memory from the new one
+        GLOBALS.identity.load()?;
+        GLOBALS.delegation.load()?;
+        Self::run_data_migrations()?;
+        Self::init_feed_starts();
+        GLOBALS.relay_picker.init().await?;
+        GLOBALS.feed.sync_recompute();
+        GLOBALS.ui_invalidate_all.store(true, Ordering::Relaxed);
+
+        GLOBALS.status_queue.write().write(format!(
+            "Switched to profile {}",
+            name.as_deref().unwrap_or("(default)")
+        ));
+        tracing::info!("Switched to profile {}", profile.profile_dir.display());
+
+        if !GLOBALS.storage.read_setting_offline() {
+            let _ = GLOBALS.write_runstate.send(RunState::Online);
+        }
+
+        Ok(())
+    }
+
+    /// Run a capability test of a relay (see [test_relay](crate::relay_test::test_relay))
+    /// and keep the report on its relay record
+    pub fn test_relay(url: RelayUrl) -> Result<(), Error> {
+        std::mem::drop(tokio::spawn(async move {
+            let message = match crate::relay_test::test_relay(&url).await {
+                Ok(report) => {
+                    let message = match &report.error {
+                        Some(e) => format!("Tested {}: {}", url, e),
+                        None => format!("Tested {}", url),
+                    };
+                    if let Err(e) = GLOBALS.storage.modify_relay(
+                        &url,
+                        |relay| relay.capabilities = Some(report.clone()),
+                        None,
+                    ) {
+                        tracing::error!("{}", e);
+                    }
+                    message
+                }
+                Err(e) => format!("Relay test of {} failed: {}", url, e),
+            };
+            GLOBALS.status_queue.write().write(message);
+        }));
+
+        Ok(())
+    }
+
     /// Unlock the private key with the given passphrase so that gossip can use it.
     /// This is akin to logging in.
     pub fn unlock_key(mut password: String) -> Result<(), Error> {
@@ -3044,6 +5139,50 @@ impl Overlord {
         Ok(())
     }
 
+    /// Stop reading the group (NIP-29) of the group feed, letting its relay go if
+    /// nothing else needs it
+    pub fn unsubscribe_group(&mut self) -> Result<(), Error> {
+        let _ = self.to_minions.send(ToMinionMessage {
+            target: "all".to_string(),
+            payload: ToMinionPayload {
+                job_id: 0,
+                detail: ToMinionPayloadDetail::UnsubscribeGroup,
+            },
+        });
+
+        let jobs: Vec<(RelayUrl, u64)> = GLOBALS
+            .connected_relays
+            .iter()
+            .flat_map(|refmulti| {
+                let relay_url = refmulti.key().clone();
+                refmulti
+                    .value()
+                    .iter()
+                    .filter(|job| {
+                        matches!(job.payload.detail, ToMinionPayloadDetail::SubscribeGroup(_))
+                    })
+                    .map(|job| (relay_url.clone(), job.payload.job_id))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        for (relay_url, job_id) in jobs {
+            self.finish_job(relay_url, Some(job_id), None)?;
+        }
+
+        Ok(())
+    }
+
+    /// Stop reading the chat of a live activity
+    pub fn unsubscribe_live_chat(&mut self) {
+        let _ = self.to_minions.send(ToMinionMessage {
+            target: "all".to_string(),
+            payload: ToMinionPayload {
+                job_id: 0,
+                detail: ToMinionPayloadDetail::UnsubscribeLiveChat,
+            },
+        });
+    }
+
     /// Subscribe, fetch, and update metadata for the person
     pub async fn update_metadata(&mut self, pubkey: PublicKey) -> Result<(), Error> {
         // Indicate that we are doing this, as the People manager wants to know
@@ -3079,6 +5218,62 @@ impl Overlord {
         Ok(())
     }
 
+    /// Use the signer of a finished pairing (see
+    /// [start_pairing](crate::nip46::start_pairing)) as our remote signer
+    pub async fn use_paired_signer(&mut self, client_pubkey: PublicKey) -> Result<(), Error> {
+        let (signer_pubkey, relays, client_key) = match crate::nip46::take_paired(&client_pubkey) {
+            Some(paired) => paired,
+            None => {
+                return Err(ErrorKind::General("That pairing is not finished".to_owned()).into())
+            }
+        };
+        crate::bunker::start_with(signer_pubkey, relays, client_key)?;
+        self.subscribe_bunker().await?;
+
+        // The signer already connected to us when pairing
+        std::mem::drop(task::spawn(crate::bunker::handshake(false, None)));
+        Ok(())
+    }
+
+    /// Fetch what viewing this person's profile asks for, per the
+    /// [ProfileFetch](crate::profile_fetch::ProfileFetch) settings. Their notes are
+    /// fetched by the person feed.
+    pub async fn view_profile(&mut self, pubkey: PublicKey) -> Result<(), Error> {
+        let fetch = ProfileFetch::current();
+        let kinds = fetch.kinds();
+        if kinds.is_empty() {
+            return Ok(());
+        }
+
+        if fetch.metadata {
+            GLOBALS.people.metadata_fetch_initiated(&[pubkey]);
+        }
+
+        let best_relays = GLOBALS
+            .storage
+            .get_best_relays(pubkey, RelayUsage::Outbox)?;
+        let num_relays_per_person = GLOBALS.storage.read_setting_num_relays_per_person();
+        for (relay_url, _score) in best_relays.iter().take(num_relays_per_person as usize + 1) {
+            self.engage_minion(
+                relay_url.to_owned(),
+                vec![RelayJob {
+                    reason: RelayConnectionReason::FetchMetadata,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::TempSubscribeProfile(pubkey, kinds.clone()),
+                    },
+                }],
+            )
+            .await?;
+        }
+
+        if fetch.metadata {
+            GLOBALS.people.recheck_nip05_on_update_metadata(&pubkey);
+        }
+
+        Ok(())
+    }
+
     /// Subscribe, fetch, and update metadata for the people
     pub async fn update_metadata_in_bulk(
         &mut self,
@@ -3185,7 +5380,8 @@ impl Overlord {
         if list != PersonList::Followed && !event.content.is_empty() {
             if GLOBALS.identity.is_unlocked() {
                 // Private entries
-                let decrypted_content = GLOBALS.identity.decrypt(&my_pubkey, &event.content)?;
+                let decrypted_content =
+                    GLOBALS.identity.decrypt(&my_pubkey, &event.content).await?;
 
                 let tags: Vec<Tag> = serde_json::from_str(&decrypted_content)?;
 
@@ -3207,14 +5403,15 @@ impl Overlord {
         }
 
         if !merge {
-            GLOBALS.storage.clear_person_list(list, Some(&mut txn))?;
+            GLOBALS.storage.clear_person_list(list, Some(&mut *txn))?;
         }
 
         for (pubkey, private) in &entries {
             GLOBALS
                 .storage
-                .add_person_to_list(pubkey, list, *private, Some(&mut txn))?;
+                .add_person_to_list(pubkey, list, *private, Some(&mut *txn))?;
             GLOBALS.ui_people_to_invalidate.write().push(*pubkey);
+            crate::bus::send(BusMessage::PersonUpdated(*pubkey));
         }
 
         let last_edit = if merge { now } else { event.created_at };
@@ -3228,7 +5425,7 @@ impl Overlord {
 
         GLOBALS
             .storage
-            .set_person_list_metadata(list, &metadata, Some(&mut txn))?;
+            .set_person_list_metadata(list, &metadata, Some(&mut *txn))?;
 
         txn.commit()?;
 
@@ -3418,11 +5615,22 @@ impl Overlord {
     }
 
     /// Set which notes are currently visible to the user. This is used to modify subscriptions
-    /// that query for likes, zaps, and deletions. Such subscriptions only query for that data
-    /// for events currently in view, to keep them small.
+    /// that query for likes, zaps, deletions and replies. Such subscriptions only query for
+    /// that data for events currently in view, to keep them small.
+    ///
+    /// Each relay gets one subscription covering the visible notes it is likely to have
+    /// augments for. It is only replaced when that set of notes changes, and is closed
+    /// once none of them are in view anymore.
     ///
     /// WARNING: DO NOT CALL TOO OFTEN or relays will hate you.
     pub async fn visible_notes_changed(&mut self, mut visible: Vec<Id>) -> Result<(), Error> {
+        // Profile views may not ask for augments
+        if matches!(GLOBALS.feed.get_feed_kind(), FeedKind::Person(_))
+            && !ProfileFetch::current().augments
+        {
+            visible.clear();
+        }
+
         let num_relays_per_person = GLOBALS.storage.read_setting_num_relays_per_person();
 
         // Work out which relays to use to find augments for which ids
@@ -3462,9 +5670,48 @@ impl Overlord {
             }
         }
 
+        // Close the subscriptions of relays with nothing in view anymore
+        let gone: Vec<RelayUrl> = self
+            .augment_subs
+            .keys()
+            .filter(|url| !augment_subs.contains_key(*url))
+            .cloned()
+            .collect();
+        for relay_url in gone {
+            self.augment_subs.remove(&relay_url);
+            let _ = self.to_minions.send(ToMinionMessage {
+                target: relay_url.as_str().to_owned(),
+                payload: ToMinionPayload {
+                    job_id: 0,
+                    detail: ToMinionPayloadDetail::UnsubscribeAugments,
+                },
+            });
+            if let Some(mut refmut) = GLOBALS.connected_relays.get_mut(&relay_url) {
+                refmut
+                    .value_mut()
+                    .retain(|job| job.reason != RelayConnectionReason::FetchAugments);
+            }
+            self.maybe_disconnect_relay(&relay_url)?;
+        }
+
         // Create jobs for minions
-        for (relay_url, ids) in augment_subs.drain() {
+        for (relay_url, mut ids) in augment_subs.drain() {
+            ids.sort();
+
+            // Leave it be if it is already subscribed for exactly these
+            let subscribed = GLOBALS
+                .connected_relays
+                .get(&relay_url)
+                .is_some_and(|jobs| {
+                    jobs.iter()
+                        .any(|job| job.reason == RelayConnectionReason::FetchAugments)
+                });
+            if subscribed && self.augment_subs.get(&relay_url) == Some(&ids) {
+                continue;
+            }
+
             let ids_hex: Vec<IdHex> = ids.iter().map(|i| (*i).into()).collect();
+            self.augment_subs.insert(relay_url.clone(), ids);
 
             self.engage_minion(
                 relay_url,
@@ -3516,6 +5763,9 @@ impl Overlord {
         // Read the PayRequestData from the lnurl
         let response = client.get(url.as_str()).send().await?;
         let text = response.text().await?;
+        if let Err(e) = crate::zaps::remember_zap_signer_from(target_pubkey, &text) {
+            tracing::error!("{}", e);
+        }
         let prd: PayRequestData = match serde_json::from_str(&text) {
             Ok(prd) => prd,
             Err(e) => {
@@ -3680,7 +5930,7 @@ impl Overlord {
             content: comment,
         };
 
-        let event = GLOBALS.identity.sign_event(pre_event)?;
+        let event = GLOBALS.identity.sign_event(pre_event).await?;
         let serialized_event = serde_json::to_string(&event)?;
 
         let client = reqwest::Client::builder()
@@ -3711,7 +5961,21 @@ impl Overlord {
         if let Value::Object(map) = value {
             if let Some(Value::String(s)) = map.get("pr") {
                 tracing::debug!("Zap Invoice = {}", s);
-                *GLOBALS.current_zap.write() = ZapState::ReadyToPay(id, s.to_owned());
+                if crate::nwc::is_connected() {
+                    // The wallet pays whatever the invoice says, so it must be what we asked for
+                    if crate::nwc::invoice_amount_msat(s) != Some(msats.0) {
+                        *GLOBALS.current_zap.write() = ZapState::None;
+                        tracing::warn!("Zap invoice is not for {} msats: {}", msats.0, s);
+                        GLOBALS.status_queue.write().write(
+                            "The zap invoice is not for the amount asked, not paying it."
+                                .to_string(),
+                        );
+                        return Ok(());
+                    }
+                    Self::pay_zap_invoice(id, s.to_owned());
+                } else {
+                    *GLOBALS.current_zap.write() = ZapState::ReadyToPay(id, s.to_owned());
+                }
                 return Ok(());
             }
         }
@@ -3725,6 +5989,43 @@ impl Overlord {
 
         Ok(())
     }
+
+    /// Pay the invoice of the zap on the note with Id with our wallet (NIP-47). If the
+    /// wallet does not pay, the invoice is offered again to pay some other way.
+    pub fn pay_zap_invoice(id: Id, invoice: String) {
+        *GLOBALS.current_zap.write() = ZapState::PayingWithWallet(id);
+        std::mem::drop(tokio::spawn(async move {
+            let result = crate::nwc::pay_invoice(&invoice).await;
+
+            // Leave the zap state alone if the user moved on
+            {
+                let mut current_zap = GLOBALS.current_zap.write();
+                if matches!(*current_zap, ZapState::PayingWithWallet(paying) if paying == id) {
+                    *current_zap = match result {
+                        Ok(_) => ZapState::None,
+                        Err(_) => ZapState::ReadyToPay(id, invoice),
+                    };
+                }
+            }
+
+            match result {
+                Ok(_) => {
+                    GLOBALS
+                        .status_queue
+                        .write()
+                        .write("Zap paid with your wallet.".to_string());
+                    let _ = crate::nwc::get_balance().await;
+                }
+                Err(e) => {
+                    tracing::warn!("{}", e);
+                    GLOBALS
+                        .status_queue
+                        .write()
+                        .write(format!("Your wallet did not pay the zap: {}", e));
+                }
+            }
+        }));
+    }
 }
 
 fn work_logger(work_receiver: mpsc::Receiver<u8>, powint: u8) {
@@ -1,9 +1,10 @@
+use crate::bus::BusMessage;
 use crate::comms::RelayJob;
 use crate::error::{Error, ErrorKind};
 use crate::globals::GLOBALS;
 use crate::nip46::ParsedCommand;
 use crate::people::PersonList;
-use nostr_types::{EventKind, Filter, PublicKey, PublicKeyHex, RelayList, RelayUrl, Unixtime};
+use nostr_types::{EventKind, Filter, Id, PublicKey, PublicKeyHex, RelayList, RelayUrl, Unixtime};
 use parking_lot::RwLock as PRwLock;
 use parking_lot::RwLockReadGuard as PRwLockReadGuard;
 use std::collections::hash_map::DefaultHasher;
@@ -44,6 +45,24 @@ pub enum PendingItem {
     PersonListNeverPublished(PersonList),
     PersonListOutOfSync(PersonList),
     PersonListNotPublishedRecently(PersonList),
+    // Another client published a newer version of one of our replaceable events
+    // (kind, and 'd' tag value or "") than we last did
+    ReplaceableConflict {
+        kind: u32,
+        d: String,
+    },
+    // A post was held back because it looks like one we posted recently. The post
+    // is in GLOBALS.held_posts under the id.
+    DuplicatePost {
+        id: u64,
+        previous: Id,
+    },
+    // A follow-back rule wants to add a new follower to its list. The firing is
+    // in the follow-back log.
+    FollowBack {
+        rule: u64,
+        pubkey: PublicKey,
+    },
     // A posted event didn't make it to all the relays it should go to.
     // PROBLEM: Often there is a dead relay on somebody's list and so these events pile
     //          up far too much.
@@ -134,12 +153,13 @@ impl Pending {
         });
 
         if !existing {
-            self.pending.write().push((item, now));
+            self.pending.write().push((item.clone(), now));
             {
                 let mut list = self.pending.write();
                 list.sort_by(|a, b| b.1.cmp(&a.1));
                 *self.pending_hash.write() = calculate_pending_hash(&list);
             }
+            crate::bus::send(BusMessage::NotificationAdded(item));
             true
         } else {
             false
@@ -281,6 +301,11 @@ impl Pending {
             }
         }
 
+        // Follow-back firings still waiting for approval
+        for item in crate::follow_back::pending_items()? {
+            self.insert(item);
+        }
+
         {
             let pending = self.pending.read();
             *self.pending_hash.write() = calculate_pending_hash(&pending);
@@ -1,3 +1,4 @@
+use crate::bus::BusMessage;
 use crate::comms::ToOverlordMessage;
 use crate::error::{Error, ErrorKind};
 use crate::globals::GLOBALS;
@@ -5,11 +6,11 @@ use crate::misc::{Freshness, Private};
 use dashmap::{DashMap, DashSet};
 use image::RgbaImage;
 use nostr_types::{
-    ContentEncryptionAlgorithm, Event, EventKind, Metadata, PreEvent, PublicKey, RelayUrl,
-    RelayUsage, Tag, UncheckedUrl, Unixtime, Url,
+    Event, EventKind, Id, Metadata, PreEvent, PublicKey, RelayUrl, RelayUsage, Tag, UncheckedUrl,
+    Unixtime, Url,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
 use std::hash::{Hash, Hasher};
 use std::sync::atomic::Ordering;
 use std::time::Duration;
@@ -17,14 +18,51 @@ use tokio::sync::RwLock;
 use tokio::task;
 use tokio::time::Instant;
 
+// A person the UI has not reported as displayed for this long is no longer in view
+const VIEW_EXPIRY_SECS: u64 = 10;
+
 /// Person type, aliased to the latest version
-pub type Person = crate::storage::types::Person2;
+pub type Person = crate::storage::types::Person3;
+
+/// MuteLogEntry type, aliased to the latest version
+pub type MuteLogEntry = crate::storage::types::MuteLogEntry1;
 
 /// PersonList type, aliased to the latest version
 pub type PersonList = crate::storage::types::PersonList1;
 
 /// PersonListMetadata type, aliased to the latest version
-pub type PersonListMetadata = crate::storage::types::PersonListMetadata3;
+pub type PersonListMetadata = crate::storage::types::PersonListMetadata6;
+
+/// ListAutoPublish type, aliased to the latest version
+pub type ListAutoPublish = crate::storage::types::ListAutoPublish1;
+
+/// ListRefresh type, aliased to the latest version
+pub type ListRefresh = crate::storage::types::ListRefresh1;
+
+/// SmartList type, aliased to the latest version
+pub type SmartList = crate::storage::types::SmartList1;
+
+/// Someone who is muted, with when and why (from the mute log)
+#[derive(Debug, Clone)]
+pub struct MutedPerson {
+    pub pubkey: PublicKey,
+
+    /// If they are on the private part of the mute list
+    pub private: Private,
+
+    /// When they were muted (None if before the mute log was kept, or if they were
+    /// muted on another client)
+    pub muted_at: Option<Unixtime>,
+
+    /// Why, if a reason was given
+    pub reason: Option<String>,
+
+    /// The event that prompted it, if any
+    pub event: Option<Id>,
+
+    /// When the mute expires, if it does
+    pub until: Option<Unixtime>,
+}
 
 /// Handles people and remembers what needs to be done for each, such as fetching
 /// metadata or avatars.
@@ -44,9 +82,17 @@ pub struct People {
     // the person's NIP-05 when that metadata come in. We remember this here.
     recheck_nip05: DashSet<PublicKey>,
 
-    // People of interest that the UI is showing, whose metadata should
-    // be updated if it is stale.
-    people_of_interest: DashSet<PublicKey>,
+    // People the UI is currently displaying, with when it last told us so.
+    // Stale metadata and relay lists are refreshed for exactly these people.
+    in_view: DashMap<PublicKey, Instant>,
+
+    // Start of the current refresh rate-limit window, and how many people
+    // we have refreshed within it
+    refresh_window: parking_lot::Mutex<(Instant, u32)>,
+
+    // Everybody in the Followed list, loaded on first use and then kept in sync
+    // by storage as list memberships are written
+    followed: parking_lot::RwLock<Option<HashSet<PublicKey>>>,
 
     // Metadata fetches in progress. Once these get too old we can remove them
     // and consider them to have failed.
@@ -69,7 +115,9 @@ impl People {
             avatars_temp: DashMap::new(),
             avatars_pending_processing: DashSet::new(),
             recheck_nip05: DashSet::new(),
-            people_of_interest: DashSet::new(),
+            in_view: DashMap::new(),
+            refresh_window: parking_lot::Mutex::new((Instant::now(), 0)),
+            followed: parking_lot::RwLock::new(None),
             fetching_metadata: DashMap::new(),
         }
     }
@@ -101,8 +149,8 @@ impl People {
                     _ = read_runstate.wait_for(|runstate| runstate.going_offline()) => break,
                 }
 
-                // We fetch needed metadata
-                GLOBALS.people.maybe_fetch_metadata().await;
+                // We refresh people in view who need it
+                GLOBALS.people.refresh_viewed_people().await;
             }
 
             tracing::info!("People task manager shutdown");
@@ -130,15 +178,108 @@ impl People {
         }
     }
 
+    /// The people we keep live subscriptions for: those in a subscribed list whose
+    /// refresh cadence is live. People only in lists fetched every so often (or
+    /// manually) are left out. (We also force the current user into this list)
+    pub fn get_live_subscribed_pubkeys(&self) -> Vec<PublicKey> {
+        let metadata = match GLOBALS.storage.get_all_person_list_metadata() {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                tracing::error!("{}", e);
+                return vec![];
+            }
+        };
+        if metadata
+            .iter()
+            .all(|(list, md)| !list.subscribe() || md.refresh == ListRefresh::Live)
+        {
+            return self.get_subscribed_pubkeys();
+        }
+
+        let mut people: HashSet<PublicKey> = HashSet::new();
+        for (list, _) in metadata
+            .iter()
+            .filter(|(list, md)| list.subscribe() && md.refresh == ListRefresh::Live)
+        {
+            match GLOBALS.storage.get_people_in_list(*list) {
+                Ok(members) => people.extend(members.iter().map(|(pk, _)| *pk)),
+                Err(e) => tracing::error!("{}", e),
+            }
+        }
+        if let Some(pk) = GLOBALS.identity.public_key() {
+            people.insert(pk);
+        }
+        people.into_iter().collect()
+    }
+
     /// Is the person in the list? (returns false on error)
     #[inline]
     pub fn is_person_in_list(&self, pubkey: &PublicKey, list: PersonList) -> bool {
+        if list == PersonList::Followed {
+            return self.with_followed(|followed| followed.contains(pubkey));
+        }
         GLOBALS
             .storage
             .is_person_in_list(pubkey, list)
             .unwrap_or(false)
     }
 
+    /// Which of these people do we follow? The answers are in the same order as the
+    /// pubkeys. This is answered from memory, so it stays cheap for large batches
+    /// such as everybody in a long thread.
+    pub fn follow_states(&self, pubkeys: &[PublicKey]) -> Vec<bool> {
+        self.with_followed(|followed| pubkeys.iter().map(|pk| followed.contains(pk)).collect())
+    }
+
+    fn with_followed<R>(&self, f: impl FnOnce(&HashSet<PublicKey>) -> R) -> R {
+        if let Some(followed) = &*self.followed.read() {
+            return f(followed);
+        }
+
+        let mut guard = self.followed.write();
+        if guard.is_none() {
+            match GLOBALS.storage.get_people_in_list(PersonList::Followed) {
+                Ok(people) => *guard = Some(people.iter().map(|(pk, _)| *pk).collect()),
+                Err(e) => {
+                    tracing::error!("{}", e);
+                    return f(&HashSet::new());
+                }
+            }
+        }
+        match &*guard {
+            Some(followed) => f(followed),
+            None => f(&HashSet::new()),
+        }
+    }
+
+    // Storage calls this whenever a person's list memberships are written
+    pub(crate) fn update_follow_state(&self, pubkey: &PublicKey, followed: bool) {
+        if let Some(set) = &mut *self.followed.write() {
+            if followed {
+                set.insert(*pubkey);
+            } else {
+                set.remove(pubkey);
+            }
+        }
+    }
+
+    // Storage calls this when the Followed list is cleared; we reload on next use
+    pub(crate) fn invalidate_follow_states(&self) {
+        *self.followed.write() = None;
+    }
+
+    /// Forget everything held about people, as when switching profiles
+    pub(crate) async fn clear(&self) {
+        *self.active_person.write().await = None;
+        self.active_persons_write_relays.write().await.clear();
+        self.avatars_temp.clear();
+        self.avatars_pending_processing.clear();
+        self.recheck_nip05.clear();
+        self.in_view.clear();
+        self.fetching_metadata.clear();
+        self.invalidate_follow_states();
+    }
+
     /// Get all the pubkeys that need relay lists (from the given set)
     pub fn get_subscribed_pubkeys_needing_relay_lists(&self) -> Vec<PublicKey> {
         let stale = Unixtime::now().unwrap().0
@@ -195,14 +336,31 @@ impl People {
     }
 
     /// Mark this person as a person who the UI wants fresh metadata for.
-    /// maybe_fetch_metadata() will do the processing later on.
+    /// This is the same as [view_entered](Self::view_entered).
     pub fn person_of_interest(&self, pubkey: PublicKey) {
-        // Don't set if metadata if disabled
-        if !GLOBALS.storage.read_setting_automatically_fetch_metadata() {
-            return;
-        }
+        self.view_entered(pubkey);
+    }
+
+    /// The UI is displaying this person. Immediate-mode UIs may simply call this
+    /// every frame; people not reported again within a few seconds are treated as
+    /// having left the view.
+    pub fn view_entered(&self, pubkey: PublicKey) {
+        self.in_view.insert(pubkey, Instant::now());
+    }
 
-        self.people_of_interest.insert(pubkey);
+    /// The UI is no longer displaying this person
+    pub fn view_exited(&self, pubkey: &PublicKey) {
+        self.in_view.remove(pubkey);
+    }
+
+    /// The people the UI is currently displaying
+    pub fn people_in_view(&self) -> Vec<PublicKey> {
+        let expiry = Duration::from_secs(VIEW_EXPIRY_SECS);
+        self.in_view
+            .iter()
+            .filter(|r| r.value().elapsed() < expiry)
+            .map(|r| *r.key())
+            .collect()
     }
 
     /// The overlord calls this to indicate that it is fetching metadata
@@ -214,34 +372,58 @@ impl People {
         }
     }
 
-    /// This is run periodically. It checks the database first, only then does it
-    /// ask the overlord to update the metadata from the relays.
-    async fn maybe_fetch_metadata(&self) {
-        // Take everybody out of self.people_of_interest, into a local var
-        let mut people_of_interest: Vec<PublicKey> = self
-            .people_of_interest
+    /// This is run periodically. It checks the database for the people in view, and
+    /// only for those with stale metadata or relay lists does it ask the overlord to
+    /// fetch them from the relays, no more than `profile_refresh_max_per_minute`
+    /// people per minute.
+    async fn refresh_viewed_people(&self) {
+        let expiry = Duration::from_secs(VIEW_EXPIRY_SECS);
+        self.in_view.retain(|_, seen| seen.elapsed() < expiry);
+
+        if !GLOBALS.storage.read_setting_automatically_fetch_metadata() {
+            return;
+        }
+
+        // Most recently reported first, so that when we are over budget the people
+        // on screen right now win
+        let mut viewed: Vec<(PublicKey, Instant)> = self
+            .in_view
             .iter()
-            .map(|refmulti| refmulti.key().to_owned())
+            .map(|r| (*r.key(), *r.value()))
             .collect();
-        self.people_of_interest.clear();
+        viewed.sort_by(|a, b| b.1.cmp(&a.1));
 
-        if !people_of_interest.is_empty() {
-            tracing::trace!(
-                "Periodic metadata check against {} people",
-                people_of_interest.len()
-            );
-        }
+        let mut budget = {
+            let max = GLOBALS
+                .storage
+                .read_setting_profile_refresh_max_per_minute();
+            let mut window = self.refresh_window.lock();
+            if window.0.elapsed() >= Duration::from_secs(60) {
+                *window = (Instant::now(), 0);
+            }
+            max.saturating_sub(window.1)
+        };
+        let budget_start = budget;
 
         let now = Unixtime::now().unwrap();
-        let stale = Duration::from_secs(
-            60 * GLOBALS
+        let metadata_stale = now.0
+            - 60 * GLOBALS
+                .storage
+                .read_setting_metadata_becomes_stale_minutes() as i64;
+        let relay_list_stale = now.0
+            - 60 * GLOBALS
                 .storage
-                .read_setting_metadata_becomes_stale_minutes(),
-        );
+                .read_setting_relay_list_becomes_stale_minutes() as i64;
 
-        let mut verified_need: Vec<PublicKey> = Vec::new();
+        let mut need_metadata: Vec<PublicKey> = Vec::new();
+        let mut need_relay_list: Vec<PublicKey> = Vec::new();
+
+        for (pubkey, _) in viewed.drain(..) {
+            if budget == 0 {
+                tracing::trace!("Profile refresh budget exhausted for this minute");
+                break;
+            }
 
-        for pubkey in people_of_interest.drain(..) {
             // If we already tried fetching_metadata (within the stale period)
             // skip them
             // NOTE: if we tried and it never came in, odds are low that trying
@@ -250,29 +432,41 @@ impl People {
             // in this circumstance isn't such a great idea.
             let opt_fetching_asof: Option<Unixtime> =
                 self.fetching_metadata.get(&pubkey).map(|r| *r.value());
-            if let Some(fetching_asof) = opt_fetching_asof {
-                if fetching_asof.0 >= (now - stale).0 {
-                    continue;
-                } else {
+            let fetching = match opt_fetching_asof {
+                Some(fetching_asof) if fetching_asof.0 >= metadata_stale => true,
+                Some(_) => {
                     // remove stale entry
                     self.fetching_metadata.remove(&pubkey);
+                    false
                 }
-            }
+                None => false,
+            };
 
             match GLOBALS.storage.read_person(&pubkey, None) {
                 Ok(Some(person)) => {
+                    let mut refreshed = false;
+
                     // We need metadata if it is missing or old
-                    let need = {
-                        // Metadata refresh interval
-                        person.metadata_created_at.is_none()
-                            || person.metadata_last_received < (now - stale).0
-                    };
-                    if !need {
-                        continue;
+                    if !fetching
+                        && (person.metadata_created_at.is_none()
+                            || person.metadata_last_received < metadata_stale)
+                    {
+                        tracing::debug!("Seeking metadata for {}", pubkey.as_hex_string());
+                        need_metadata.push(pubkey);
+                        refreshed = true;
+                    }
+
+                    // Relay lists of people we subscribe to are kept fresh elsewhere
+                    if !person.is_subscribed_to()
+                        && person.relay_list_last_sought < relay_list_stale
+                    {
+                        need_relay_list.push(pubkey);
+                        refreshed = true;
                     }
 
-                    tracing::debug!("Seeking metadata for {}", pubkey.as_hex_string());
-                    verified_need.push(pubkey);
+                    if refreshed {
+                        budget -= 1;
+                    }
                 }
                 _ => {
                     // Trigger a future create and load
@@ -283,12 +477,21 @@ impl People {
             }
         }
 
+        self.refresh_window.lock().1 += budget_start - budget;
+
         // This fires off the minions to fetch metadata events
         // When they come in, process.rs handles it by calling
         // GLOBALS.people.update_metadata() [down below]
-        let _ = GLOBALS
-            .to_overlord
-            .send(ToOverlordMessage::UpdateMetadataInBulk(verified_need));
+        if !need_metadata.is_empty() {
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::UpdateMetadataInBulk(need_metadata));
+        }
+        if !need_relay_list.is_empty() {
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::SubscribeDiscover(need_relay_list, None));
+        }
     }
 
     pub(crate) fn recheck_nip05_on_update_metadata(&self, pubkey: &PublicKey) {
@@ -341,6 +544,7 @@ impl People {
             }
             GLOBALS.storage.write_person(&person, None)?;
             GLOBALS.ui_people_to_invalidate.write().push(*pubkey);
+            crate::bus::send(BusMessage::PersonUpdated(*pubkey));
         }
 
         // Remove from failed avatars list so the UI will try to fetch the avatar again if missing
@@ -554,6 +758,10 @@ impl People {
                     // bigger names have a higher match chance, but they should be scored lower
                     score -= result_name.len() as u16;
 
+                    // people we interact with more come first among similar matches
+                    let strength = crate::relationship_strength::strength(person.pubkey);
+                    score += (strength * 5.0).min(99.0) as u16;
+
                     return Some((score, result_name, person.pubkey));
                 }
 
@@ -622,7 +830,8 @@ impl People {
         let old_tags = {
             if let Some(ref event) = existing_event {
                 if !event.content.is_empty() && kind != EventKind::ContactList {
-                    let decrypted_content = GLOBALS.identity.decrypt(&my_pubkey, &event.content)?;
+                    let decrypted_content =
+                        GLOBALS.identity.decrypt(&my_pubkey, &event.content).await?;
                     let mut tags: Vec<Tag> = serde_json::from_str(&decrypted_content)?;
                     tags.extend(event.tags.clone());
                     tags
@@ -735,11 +944,14 @@ impl People {
                 }
             } else {
                 let private_tags_string = serde_json::to_string(&private_tags)?;
-                GLOBALS.identity.encrypt(
-                    &my_pubkey,
-                    &private_tags_string,
-                    ContentEncryptionAlgorithm::Nip04,
-                )?
+                GLOBALS
+                    .identity
+                    .encrypt(
+                        &my_pubkey,
+                        &private_tags_string,
+                        GLOBALS.identity.outgoing_encryption(&private_tags_string),
+                    )
+                    .await?
             }
         };
 
@@ -751,7 +963,7 @@ impl People {
             content,
         };
 
-        GLOBALS.identity.sign_event(pre_event)
+        GLOBALS.identity.sign_event(pre_event).await
     }
 
     /// Follow (or unfollow) the public key
@@ -791,6 +1003,7 @@ impl People {
         }
 
         GLOBALS.ui_people_to_invalidate.write().push(*pubkey);
+        crate::bus::send(BusMessage::PersonUpdated(*pubkey));
 
         let _ = GLOBALS
             .to_overlord
@@ -808,6 +1021,21 @@ impl People {
 
     /// Mute (or unmute) a public key
     pub fn mute(&self, pubkey: &PublicKey, mute: bool, private: Private) -> Result<(), Error> {
+        self.mute_with_reason(pubkey, mute, private, None, None)
+    }
+
+    /// Mute (or unmute) a public key, noting why and the event that prompted it in
+    /// the mute log. The log is kept locally and is never published, and is skipped
+    /// if it can't be written (it is sealed, so needs the identity unlocked).
+    pub fn mute_with_reason(
+        &self,
+        pubkey: &PublicKey,
+        mute: bool,
+        private: Private,
+        reason: Option<String>,
+        event: Option<Id>,
+    ) -> Result<(), Error> {
+        let now = Unixtime::now().unwrap();
         let mut txn = GLOBALS.storage.get_write_txn()?;
 
         if mute {
@@ -821,33 +1049,156 @@ impl People {
                 pubkey,
                 PersonList::Muted,
                 private,
-                Some(&mut txn),
+                Some(&mut *txn),
             )?;
+
+            // A plain mute does not expire
+            GLOBALS
+                .storage
+                .modify_person(*pubkey, |p| p.muted_until = None, Some(&mut *txn))?;
         } else {
             GLOBALS
                 .storage
-                .remove_person_from_list(pubkey, PersonList::Muted, Some(&mut txn))?;
+                .remove_person_from_list(pubkey, PersonList::Muted, Some(&mut *txn))?;
         }
 
         if let Some(mut metadata) = GLOBALS
             .storage
             .get_person_list_metadata(PersonList::Muted)?
         {
-            metadata.last_edit_time = Unixtime::now().unwrap();
+            metadata.last_edit_time = now;
             GLOBALS.storage.set_person_list_metadata(
                 PersonList::Muted,
                 &metadata,
-                Some(&mut txn),
+                Some(&mut *txn),
             )?;
         }
 
         txn.commit()?;
 
+        // The log entry is sealed, which fails while the identity is locked. That
+        // must not undo the mute, so the entry is only best-effort.
+        let entry = MuteLogEntry {
+            at: now.0,
+            muted: mute,
+            reason: reason.filter(|r| !r.trim().is_empty()),
+            event,
+        };
+        if let Err(e) = GLOBALS.storage.write_mute_log_entry(pubkey, &entry, None) {
+            tracing::warn!(
+                "Mute log entry for {} not written: {}",
+                pubkey.as_hex_string(),
+                e
+            );
+        }
+
         GLOBALS.ui_people_to_invalidate.write().push(*pubkey);
+        crate::bus::send(BusMessage::PersonUpdated(*pubkey));
 
         Ok(())
     }
 
+    /// Mute a public key until a time, when the maintenance scheduler unmutes them
+    pub fn mute_until(
+        &self,
+        pubkey: &PublicKey,
+        until: Unixtime,
+        private: Private,
+    ) -> Result<(), Error> {
+        self.mute(pubkey, true, private)?;
+        GLOBALS
+            .storage
+            .modify_person(*pubkey, |p| p.muted_until = Some(until.0), None)?;
+        GLOBALS.ui_people_to_invalidate.write().push(*pubkey);
+        Ok(())
+    }
+
+    /// Unmute everybody whose mute has expired, returning how many there were
+    pub fn unmute_expired(&self) -> Result<usize, Error> {
+        let now = Unixtime::now().unwrap().0;
+        let mut count = 0;
+        for (pubkey, _) in GLOBALS.storage.get_people_in_list(PersonList::Muted)? {
+            let expired = GLOBALS
+                .storage
+                .read_person(&pubkey, None)?
+                .map(|p| p.mute_expired(now))
+                .unwrap_or(false);
+            if expired {
+                self.mute(&pubkey, false, Private(false))?;
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Everybody who is muted, with when and why, most recently muted first
+    pub fn muted_report(&self) -> Result<Vec<MutedPerson>, Error> {
+        let mut report: Vec<MutedPerson> = Vec::new();
+        for (pubkey, private) in GLOBALS.storage.get_people_in_list(PersonList::Muted)? {
+            let last_mute = GLOBALS
+                .storage
+                .read_mute_log(&pubkey)?
+                .into_iter()
+                .rev()
+                .find(|entry| entry.muted);
+            let until = GLOBALS
+                .storage
+                .read_person(&pubkey, None)?
+                .and_then(|p| p.muted_until)
+                .map(Unixtime);
+            report.push(MutedPerson {
+                pubkey,
+                private,
+                muted_at: last_mute.as_ref().map(|entry| Unixtime(entry.at)),
+                reason: last_mute.as_ref().and_then(|entry| entry.reason.clone()),
+                event: last_mute.as_ref().and_then(|entry| entry.event),
+                until,
+            });
+        }
+        report.sort_by(|a, b| b.muted_at.cmp(&a.muted_at));
+        Ok(report)
+    }
+
+    /// People we started following since a time, most recent first
+    pub fn followed_since(&self, since: Unixtime) -> Result<Vec<Person>, Error> {
+        let mut people = GLOBALS
+            .storage
+            .filter_people(|p| p.followed_since.map(|t| t >= since.0).unwrap_or(false))?;
+        people.sort_by(|a, b| b.followed_since.cmp(&a.followed_since));
+        Ok(people)
+    }
+
+    /// People we interacted with since a time, most recent first
+    pub fn interacted_since(&self, since: Unixtime) -> Result<Vec<Person>, Error> {
+        let mut people = GLOBALS
+            .storage
+            .filter_people(|p| p.last_interacted.map(|t| t >= since.0).unwrap_or(false))?;
+        people.sort_by(|a, b| b.last_interacted.cmp(&a.last_interacted));
+        Ok(people)
+    }
+
+    // Our event reached these people: note when we last interacted with them
+    pub(crate) fn note_interaction(
+        &self,
+        pubkeys: &[PublicKey],
+        at: Unixtime,
+    ) -> Result<(), Error> {
+        let mut txn = GLOBALS.storage.get_write_txn()?;
+        for pubkey in pubkeys {
+            GLOBALS.storage.modify_person(
+                *pubkey,
+                |p| {
+                    if p.last_interacted.map(|t| t < at.0).unwrap_or(true) {
+                        p.last_interacted = Some(at.0);
+                    }
+                },
+                Some(&mut *txn),
+            )?;
+        }
+        txn.commit()?;
+        Ok(())
+    }
+
     // Returns true if the date passed in is newer than what we already had
     pub(crate) async fn update_relay_list_stamps(
         &self,
@@ -908,6 +1259,7 @@ impl People {
 
             GLOBALS.storage.write_person(&person, None)?;
             GLOBALS.ui_people_to_invalidate.write().push(*pubkey);
+            crate::bus::send(BusMessage::PersonUpdated(*pubkey));
         }
 
         Ok(())
@@ -1029,7 +1381,9 @@ pub fn hash_person_list_event(list: PersonList) -> Result<u64, Error> {
         // Collect private entries
         if event.kind != EventKind::ContactList && !event.content.is_empty() {
             if GLOBALS.identity.is_unlocked() {
-                let decrypted_content = GLOBALS.identity.decrypt(&my_pubkey, &event.content)?;
+                let decrypted_content = GLOBALS
+                    .identity
+                    .decrypt_locally(&my_pubkey, &event.content)?;
                 let tags: Vec<Tag> = serde_json::from_str(&decrypted_content)?;
                 for tag in &tags {
                     if let Ok((pubkey, _, _)) = tag.parse_pubkey() {
@@ -0,0 +1,286 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::misc::Private;
+use crate::people::PersonList;
+use crate::relay::RelayUsage;
+use nostr_types::{PublicKey, RelayUrl, Unixtime};
+use serde::{Deserialize, Serialize};
+
+// Include at most this many relay hints per person in an export
+const MAX_RELAY_HINTS: usize = 3;
+
+/// Formats a person list can be exported to and imported from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PersonListFormat {
+    Csv,
+    Json,
+}
+
+// One exported person. This is also what the JSON format looks like.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct PersonListRow {
+    npub: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    nip05: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    name: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    relays: Vec<String>,
+}
+
+/// Export the members of a person list, with their npub, nip05, display name and
+/// relay hints, so the list can be shared or audited outside of nostr.
+pub fn export_person_list(list: PersonList, format: PersonListFormat) -> Result<String, Error> {
+    let mut rows: Vec<PersonListRow> = Vec::new();
+    for (pubkey, _) in GLOBALS.storage.get_people_in_list(list)? {
+        let mut row = PersonListRow {
+            npub: pubkey.as_bech32_string(),
+            ..Default::default()
+        };
+        if let Some(person) = GLOBALS.storage.read_person(&pubkey, None)? {
+            row.nip05 = person.nip05().unwrap_or_default().to_owned();
+            row.name = person.display_name().unwrap_or_default().to_owned();
+        }
+        row.relays = GLOBALS
+            .storage
+            .get_best_relays(pubkey, RelayUsage::Outbox)?
+            .iter()
+            .take(MAX_RELAY_HINTS)
+            .map(|(url, _)| url.as_str().to_owned())
+            .collect();
+        rows.push(row);
+    }
+
+    match format {
+        PersonListFormat::Json => Ok(serde_json::to_string_pretty(&rows)?),
+        PersonListFormat::Csv => {
+            let mut output = "npub,nip05,name,relays\n".to_owned();
+            for row in rows.iter() {
+                output.push_str(&format!(
+                    "{},{},{},{}\n",
+                    csv_quote(&row.npub),
+                    csv_quote(&row.nip05),
+                    csv_quote(&row.name),
+                    csv_quote(&row.relays.join(" "))
+                ));
+            }
+            Ok(output)
+        }
+    }
+}
+
+/// An entry read from an imported person list, resolved (or not) to a pubkey, awaiting
+/// review by the user before anyone is added.
+#[derive(Debug, Clone)]
+pub struct PersonListImportEntry {
+    /// What identified this person in the imported text (npub, hex or nip05)
+    pub input: String,
+
+    /// The resolved pubkey, if resolution succeeded
+    pub pubkey: Option<PublicKey>,
+
+    pub nip05: String,
+    pub name: String,
+    pub relays: Vec<RelayUrl>,
+
+    /// Why this entry could not be resolved
+    pub error: Option<String>,
+
+    /// If the user wants this entry imported (defaults to true for resolved entries)
+    pub selected: bool,
+}
+
+/// Parse an exported person list (CSV or JSON, detected automatically) and resolve
+/// every entry to a pubkey, falling back to a NIP-05 lookup when there is no usable key.
+///
+/// Nothing is changed; pass the (reviewed) result to [apply_person_list_import].
+pub async fn resolve_person_list_import(text: &str) -> Result<Vec<PersonListImportEntry>, Error> {
+    let rows = if text.trim_start().starts_with('[') {
+        serde_json::from_str::<Vec<PersonListRow>>(text)?
+    } else {
+        parse_csv(text)
+    };
+
+    let mut entries: Vec<PersonListImportEntry> = Vec::new();
+    for row in rows.into_iter() {
+        let mut entry = PersonListImportEntry {
+            input: if row.npub.is_empty() {
+                row.nip05.clone()
+            } else {
+                row.npub.clone()
+            },
+            pubkey: None,
+            nip05: row.nip05.clone(),
+            name: row.name.clone(),
+            relays: row
+                .relays
+                .iter()
+                .filter_map(|r| RelayUrl::try_from_str(r).ok())
+                .collect(),
+            error: None,
+            selected: false,
+        };
+
+        let key = row.npub.trim();
+        if let Ok(pubkey) = PublicKey::try_from_bech32_string(key, true) {
+            entry.pubkey = Some(pubkey);
+        } else if let Ok(pubkey) = PublicKey::try_from_hex_string(key, true) {
+            entry.pubkey = Some(pubkey);
+        } else if !row.nip05.trim().is_empty() {
+            match crate::nip05::resolve_nip05(row.nip05.trim()).await {
+                Ok(pubkey) => entry.pubkey = Some(pubkey),
+                Err(e) => entry.error = Some(format!("{}", e)),
+            }
+        } else {
+            entry.error = Some("No npub or nip05".to_owned());
+        }
+
+        entry.selected = entry.pubkey.is_some();
+        entries.push(entry);
+    }
+
+    Ok(entries)
+}
+
+/// Add the selected, resolved entries of a reviewed import to a person list, saving
+/// their relay hints. Returns how many people were added.
+pub fn apply_person_list_import(
+    list: PersonList,
+    private: Private,
+    entries: &[PersonListImportEntry],
+) -> Result<usize, Error> {
+    let now = Unixtime::now().unwrap().0 as u64;
+    let mut count = 0;
+    for entry in entries.iter().filter(|e| e.selected) {
+        let pubkey = match entry.pubkey {
+            Some(pk) => pk,
+            None => continue,
+        };
+        for relay_url in entry.relays.iter() {
+            GLOBALS.storage.write_relay_if_missing(relay_url, None)?;
+            GLOBALS.storage.modify_person_relay(
+                pubkey,
+                relay_url,
+                |pr| pr.last_suggested = Some(now),
+                None,
+            )?;
+        }
+        GLOBALS.people.follow(&pubkey, true, list, private)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+fn csv_quote(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+// Split CSV text into records of fields, honoring quotes
+fn csv_records(text: &str) -> Vec<Vec<String>> {
+    let mut records: Vec<Vec<String>> = Vec::new();
+    let mut record: Vec<String> = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if quoted {
+            if c == '"' {
+                if chars.peek() == Some(&'"') {
+                    field.push('"');
+                    chars.next();
+                } else {
+                    quoted = false;
+                }
+            } else {
+                field.push(c);
+            }
+            continue;
+        }
+        match c {
+            '"' => quoted = true,
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    records
+}
+
+fn parse_csv(text: &str) -> Vec<PersonListRow> {
+    let mut records = csv_records(text).into_iter();
+
+    // Use the header to find columns, if there is one. Otherwise assume our own order.
+    let mut columns: [Option<usize>; 4] = [Some(0), Some(1), Some(2), Some(3)];
+    let mut first: Option<Vec<String>> = records.next();
+    if let Some(header) = &first {
+        let find = |name: &str| {
+            header
+                .iter()
+                .position(|h| h.trim().eq_ignore_ascii_case(name))
+        };
+        if find("npub").is_some() || find("nip05").is_some() {
+            columns = [find("npub"), find("nip05"), find("name"), find("relays")];
+            first = None;
+        }
+    }
+
+    let get = |record: &Vec<String>, column: Option<usize>| {
+        column
+            .and_then(|i| record.get(i))
+            .map(|s| s.trim().to_owned())
+            .unwrap_or_default()
+    };
+
+    first
+        .into_iter()
+        .chain(records)
+        .filter(|record| record.iter().any(|f| !f.trim().is_empty()))
+        .map(|record| PersonListRow {
+            npub: get(&record, columns[0]),
+            nip05: get(&record, columns[1]),
+            name: get(&record, columns[2]),
+            relays: get(&record, columns[3])
+                .split_whitespace()
+                .map(|s| s.to_owned())
+                .collect(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_csv_quoting() {
+        let line = format!("{},{}\n", csv_quote("a,b"), csv_quote("say \"hi\""));
+        let records = csv_records(&line);
+        assert_eq!(
+            records,
+            vec![vec!["a,b".to_owned(), "say \"hi\"".to_owned()]]
+        );
+    }
+
+    #[test]
+    fn test_parse_csv_header() {
+        let rows = parse_csv("name,nip05\nBob,bob@example.com\n");
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].name, "Bob");
+        assert_eq!(rows[0].nip05, "bob@example.com");
+        assert!(rows[0].npub.is_empty());
+    }
+}
@@ -0,0 +1,394 @@
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use crate::relay::Relay;
+use nostr_types::{Event, EventKind, Id, PreEvent, Tag, Unixtime};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+
+// Room kept at the end of each part of a split note for the " (k/n)" numbering
+const SPLIT_NUMBERING_RESERVE: usize = 10;
+
+// How many nonces are tried between looks at the cancel flag while mining
+const MINE_CANCEL_CHECK: u64 = 4096;
+
+/// How a post is prepared for one relay, from that relay's posting settings with
+/// the global settings filling in what the relay leaves unset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PostingPolicy {
+    /// Proof-of-work difficulty to compute
+    pub pow: u8,
+
+    /// Include the client tag. None: as the global (and privacy) settings say
+    pub client_tag: Option<bool>,
+
+    /// Maximum content length in characters
+    pub max_content_length: Option<usize>,
+
+    /// Split over-long notes into a thread instead of not posting them
+    pub auto_split: bool,
+}
+
+impl PostingPolicy {
+    /// The policy from the global settings alone
+    pub fn global() -> PostingPolicy {
+        let split = GLOBALS.storage.read_setting_split_long_notes();
+        PostingPolicy {
+            pow: GLOBALS.storage.read_setting_pow(),
+            client_tag: None,
+            max_content_length: if split {
+                Some(GLOBALS.storage.read_setting_split_long_notes_threshold() as usize)
+            } else {
+                None
+            },
+            auto_split: split,
+        }
+    }
+
+    /// The policy that applies when posting to this relay. The tighter of the
+    /// relay's content limit and the global split threshold applies.
+    pub fn for_relay(relay: &Relay) -> PostingPolicy {
+        let global = Self::global();
+        let (max_content_length, auto_split) =
+            match (relay.post_max_content_length, global.max_content_length) {
+                (Some(r), Some(g)) if r < g => (Some(r), relay.post_auto_split),
+                (Some(r), None) => (Some(r), relay.post_auto_split),
+                (_, g) => (g, global.auto_split),
+            };
+        PostingPolicy {
+            pow: relay.post_pow.unwrap_or(global.pow),
+            client_tag: relay.post_client_tag,
+            max_content_length,
+            auto_split,
+        }
+    }
+
+    /// One policy that satisfies all of these, so that a post is signed once and
+    /// the same event(s) go to every relay: the highest proof of work, no client tag
+    /// if any of them declines it, and splitting at the tightest limit of those that
+    /// split. Relays that don't split may still be unable to take it (see
+    /// [accepts](Self::accepts)).
+    pub fn combine(policies: &[PostingPolicy]) -> PostingPolicy {
+        if policies.is_empty() {
+            return Self::global();
+        }
+        let client_tag = if policies.iter().any(|p| p.client_tag == Some(false)) {
+            Some(false)
+        } else if policies.iter().any(|p| p.client_tag == Some(true)) {
+            Some(true)
+        } else {
+            None
+        };
+        let max_content_length = policies
+            .iter()
+            .filter(|p| p.auto_split)
+            .filter_map(|p| p.max_content_length)
+            .min();
+        PostingPolicy {
+            pow: policies.iter().map(|p| p.pow).max().unwrap_or(0),
+            client_tag,
+            max_content_length,
+            auto_split: max_content_length.is_some(),
+        }
+    }
+
+    /// If relays with this policy can take these events, which may have been
+    /// prepared under another (combined) policy
+    pub fn accepts(&self, events: &[Event]) -> bool {
+        match self.max_content_length {
+            Some(max) => events
+                .iter()
+                .all(|e| e.kind != EventKind::TextNote || e.content.chars().count() <= max),
+            None => true,
+        }
+    }
+
+    /// How many parts a text note with this content would be posted as (0 if it
+    /// would not be posted at all)
+    pub fn part_count(&self, content: &str) -> usize {
+        match self.max_content_length {
+            Some(max) if content.chars().count() > max => {
+                if self.auto_split {
+                    split_content(content, max).len()
+                } else {
+                    0
+                }
+            }
+            _ => 1,
+        }
+    }
+
+    /// The client tag to include, if any. Privacy mode still decides which
+    /// client name (if any) is used when the relay asks for the tag.
+    pub fn client_tag(&self) -> Option<Tag> {
+        match self.client_tag {
+            None => crate::privacy::client_tag(),
+            Some(false) => None,
+            Some(true) => {
+                if crate::privacy::privacy_mode() {
+                    crate::privacy::client_tag()
+                } else {
+                    Some(Tag::new(&["client", "gossip"]))
+                }
+            }
+        }
+    }
+
+    /// Build and sign the event(s) to send to relays with this policy. Other kinds
+    /// are only signed. Text notes get the client tag, and content over the maximum
+    /// length is split into a numbered thread if the policy allows; otherwise nothing
+    /// is returned.
+    ///
+    /// `sign` signs a PreEvent with the given proof-of-work difficulty.
+    pub(crate) async fn sign_events<S, F>(
+        &self,
+        pre_event: &PreEvent,
+        sign: S,
+    ) -> Result<Vec<Event>, Error>
+    where
+        S: Fn(PreEvent, u8) -> F,
+        F: Future<Output = Result<Event, Error>>,
+    {
+        let is_note = pre_event.kind == EventKind::TextNote;
+
+        if !is_note {
+            return Ok(vec![sign(pre_event.clone(), self.pow).await?]);
+        }
+
+        let mut first = pre_event.clone();
+        if let Some(tag) = self.client_tag() {
+            first.tags.push(tag);
+        }
+
+        let max = match self.max_content_length {
+            Some(max) if pre_event.content.chars().count() > max => max,
+            _ => return Ok(vec![sign(first, self.pow).await?]),
+        };
+
+        if !self.auto_split {
+            return Ok(vec![]);
+        }
+
+        let parts = split_content(&pre_event.content, max);
+        let count = parts.len();
+        let mut events: Vec<Event> = Vec::with_capacity(count);
+        for (i, part) in parts.into_iter().enumerate() {
+            let content = format!("{} ({}/{})", part, i + 1, count);
+            let pre = if i == 0 {
+                PreEvent {
+                    content,
+                    ..first.clone()
+                }
+            } else {
+                PreEvent {
+                    pubkey: pre_event.pubkey,
+                    created_at: Unixtime(pre_event.created_at.0 + i as i64),
+                    kind: EventKind::TextNote,
+                    tags: continuation_tags(&first, events[0].id, events[i - 1].id),
+                    content,
+                }
+            };
+            events.push(sign(pre, self.pow).await?);
+        }
+        Ok(events)
+    }
+}
+
+// Later parts of a split note keep the original's root (or make the first part the
+// root), reply to the previous part, and keep the original's people, hashtags,
+// quotes and subject
+fn continuation_tags(original: &PreEvent, first: Id, previous: Id) -> Vec<Tag> {
+    let mut tags: Vec<Tag> = Vec::new();
+
+    let mut has_root = false;
+    for tag in original.tags.iter() {
+        let tagname = tag.tagname();
+        if (tagname == "e" || tagname == "a") && tag.get_index(3) == "root" {
+            tags.push(tag.clone());
+            has_root = true;
+        }
+    }
+    if !has_root {
+        tags.push(Tag::new_event(first, None, Some("root".to_string())));
+    }
+    tags.push(Tag::new_event(previous, None, Some("reply".to_string())));
+
+    for tag in original.tags.iter() {
+        if matches!(tag.tagname(), "p" | "t" | "q" | "subject" | "client") {
+            tags.push(tag.clone());
+        }
+    }
+
+    tags
+}
+
+/// Split content into parts of at most `max` characters (less room for the part
+/// numbering), preferring to break at whitespace
+pub fn split_content(content: &str, max: usize) -> Vec<String> {
+    let budget = max.saturating_sub(SPLIT_NUMBERING_RESERVE).max(1);
+
+    let mut parts: Vec<String> = Vec::new();
+    let mut rest: Vec<char> = content.trim().chars().collect();
+    while rest.len() > budget {
+        let cut = match rest[..=budget].iter().rposition(|c| c.is_whitespace()) {
+            Some(pos) if pos > 0 => pos,
+            _ => budget,
+        };
+        let part: String = rest[..cut].iter().collect();
+        parts.push(part.trim_end().to_owned());
+        rest = rest[cut..]
+            .iter()
+            .collect::<String>()
+            .trim_start()
+            .chars()
+            .collect();
+    }
+    if !rest.is_empty() {
+        parts.push(rest.into_iter().collect());
+    }
+    parts
+}
+
+/// Mine proof of work (NIP-13) into this event. Each new best difficulty is sent
+/// to `work_sender`. Mining stops with an error once `cancel` is set.
+pub(crate) fn mine(
+    mut input: PreEvent,
+    zero_bits: u8,
+    work_sender: Option<mpsc::Sender<u8>>,
+    cancel: &AtomicBool,
+) -> Result<PreEvent, Error> {
+    let target = zero_bits.to_string();
+    let nonce_at = input.tags.len();
+    input.tags.push(Tag::new(&["nonce", "0", &target]));
+
+    let mut best: u8 = 0;
+    let mut nonce: u64 = 0;
+    loop {
+        if nonce % MINE_CANCEL_CHECK == 0 && cancel.load(Ordering::Relaxed) {
+            return Err(ErrorKind::General("Proof of work cancelled".to_owned()).into());
+        }
+        input.tags[nonce_at] = Tag::new(&["nonce", &nonce.to_string(), &target]);
+        let bits = leading_zero_bits(&input.hash()?.0);
+        if bits > best {
+            best = bits;
+            if let Some(sender) = &work_sender {
+                let _ = sender.send(best);
+            }
+        }
+        if bits >= zero_bits {
+            break;
+        }
+        nonce += 1;
+    }
+
+    Ok(input)
+}
+
+fn leading_zero_bits(bytes: &[u8]) -> u8 {
+    let mut bits: u8 = 0;
+    for byte in bytes {
+        if *byte == 0 {
+            bits += 8;
+        } else {
+            bits += byte.leading_zeros() as u8;
+            break;
+        }
+    }
+    bits
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_split_content() {
+        let content = "one two three four five six seven eight nine ten";
+        let parts = split_content(content, 25);
+        assert!(parts.iter().all(|p| p.chars().count() <= 15));
+        assert_eq!(parts.join(" "), content);
+
+        assert_eq!(split_content("short", 100), vec!["short".to_owned()]);
+
+        let long_word = "x".repeat(40);
+        let parts = split_content(&long_word, 20);
+        assert_eq!(parts.concat(), long_word);
+        assert!(parts.iter().all(|p| p.chars().count() <= 10));
+    }
+
+    #[test]
+    fn test_combine() {
+        let a = PostingPolicy {
+            pow: 8,
+            client_tag: Some(true),
+            max_content_length: Some(500),
+            auto_split: true,
+        };
+        let b = PostingPolicy {
+            pow: 16,
+            client_tag: None,
+            max_content_length: Some(200),
+            auto_split: false,
+        };
+        let c = PostingPolicy {
+            pow: 0,
+            client_tag: Some(false),
+            max_content_length: Some(300),
+            auto_split: true,
+        };
+
+        let ab = PostingPolicy::combine(&[a, b]);
+        assert_eq!(ab.pow, 16);
+        assert_eq!(ab.client_tag, Some(true));
+        assert_eq!(ab.max_content_length, Some(500));
+        assert!(ab.auto_split);
+
+        let abc = PostingPolicy::combine(&[a, b, c]);
+        assert_eq!(abc.client_tag, Some(false));
+        assert_eq!(abc.max_content_length, Some(300));
+
+        let only_b = PostingPolicy::combine(&[b]);
+        assert_eq!(only_b.max_content_length, None);
+        assert!(!only_b.auto_split);
+    }
+
+    #[test]
+    fn test_continuation_tags() {
+        let original = PreEvent {
+            pubkey: nostr_types::PrivateKey::generate().public_key(),
+            created_at: Unixtime(1_700_000_000),
+            kind: EventKind::TextNote,
+            tags: vec![
+                Tag::new(&["t", "nostr"]),
+                Tag::new(&["subject", "A long one"]),
+                Tag::new(&["q", &Id([3; 32]).as_hex_string()]),
+                Tag::new(&["nonsense", "dropped"]),
+            ],
+            content: "".to_owned(),
+        };
+        let tags = continuation_tags(&original, Id([1; 32]), Id([2; 32]));
+        let names: Vec<&str> = tags.iter().map(|t| t.tagname()).collect();
+        assert_eq!(names, vec!["e", "e", "t", "subject", "q"]);
+    }
+
+    #[test]
+    fn test_leading_zero_bits() {
+        assert_eq!(leading_zero_bits(&[0, 0, 0x0f, 0xff]), 20);
+        assert_eq!(leading_zero_bits(&[0x80]), 0);
+        assert_eq!(leading_zero_bits(&[0, 1]), 15);
+    }
+
+    #[test]
+    fn test_mine_cancelled() {
+        let pre_event = PreEvent {
+            pubkey: nostr_types::PrivateKey::generate().public_key(),
+            created_at: Unixtime(1_700_000_000),
+            kind: EventKind::TextNote,
+            tags: vec![],
+            content: "hello".to_owned(),
+        };
+        let mined = mine(pre_event.clone(), 8, None, &AtomicBool::new(false)).unwrap();
+        assert!(leading_zero_bits(&mined.hash().unwrap().0) >= 8);
+        assert!(mine(pre_event, 200, None, &AtomicBool::new(true)).is_err());
+    }
+}
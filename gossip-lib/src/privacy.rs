@@ -0,0 +1,83 @@
+use crate::globals::GLOBALS;
+use nostr_types::{Tag, Unixtime};
+use rand::Rng;
+
+// Client names we may pretend to be when randomizing the client tag
+const CLIENT_NAMES: [&str; 6] = [
+    "gossip",
+    "amethyst",
+    "damus",
+    "coracle",
+    "nostrudel",
+    "primal",
+];
+
+// Pad NIP-44 JSON plaintexts to a multiple of this many bytes in privacy mode
+const NIP44_PAD_BLOCK: usize = 1024;
+
+// The longest plaintext NIP-44 can encrypt
+pub(crate) const NIP44_MAX_PLAINTEXT: usize = 65535;
+
+/// If privacy mode (fingerprint reduction) is on
+#[inline]
+pub fn privacy_mode() -> bool {
+    GLOBALS.storage.read_setting_privacy_mode()
+}
+
+/// The created_at to use for an event we are about to create.
+///
+/// In privacy mode this is backdated by a random number of whole seconds within the
+/// configured jitter window, so publish times don't correlate with created_at.
+/// (It is never moved forward, as relays reject events from the future.)
+pub fn created_at() -> Unixtime {
+    let mut now = Unixtime::now().unwrap();
+    if privacy_mode() {
+        let window = GLOBALS.storage.read_setting_privacy_time_jitter_secs();
+        if window > 0 {
+            now.0 -= rand::thread_rng().gen_range(0..=window) as i64;
+        }
+    }
+    now
+}
+
+/// The client tag to add to an event, if any.
+///
+/// Outside of privacy mode this honors the `set_client_tag` setting. In privacy mode
+/// the tag is either omitted or set to a random common client name.
+pub fn client_tag() -> Option<Tag> {
+    if privacy_mode() {
+        if GLOBALS.storage.read_setting_privacy_randomize_client_tag() {
+            let index = rand::thread_rng().gen_range(0..CLIENT_NAMES.len());
+            Some(Tag::new(&["client", CLIENT_NAMES[index]]))
+        } else {
+            None
+        }
+    } else if GLOBALS.storage.read_setting_set_client_tag() {
+        Some(Tag::new(&["client", "gossip"]))
+    } else {
+        None
+    }
+}
+
+/// In privacy mode, pad a JSON plaintext with trailing whitespace (which JSON parsers
+/// ignore) before NIP-44 encryption, so the ciphertext length reveals less than
+/// NIP-44's own padding does. Padding stops at the longest plaintext NIP-44 can
+/// encrypt.
+pub(crate) fn pad_json_plaintext(plaintext: &str) -> Option<String> {
+    if !privacy_mode() {
+        return None;
+    }
+    let trimmed = plaintext.trim_start();
+    if !trimmed.starts_with('{') && !trimmed.starts_with('[') {
+        return None;
+    }
+    let len = plaintext.len();
+    if len >= NIP44_MAX_PLAINTEXT {
+        return None;
+    }
+    let padded_len =
+        (len.div_ceil(NIP44_PAD_BLOCK).max(1) * NIP44_PAD_BLOCK).min(NIP44_MAX_PLAINTEXT);
+    let mut padded = plaintext.to_owned();
+    padded.extend(std::iter::repeat(' ').take(padded_len - len));
+    Some(padded)
+}
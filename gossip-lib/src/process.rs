@@ -1,10 +1,14 @@
+use crate::blocked_relays::is_relay_blocked;
+use crate::bus::BusMessage;
 use crate::comms::ToOverlordMessage;
+use crate::dm_channel::DmChannel;
 use crate::error::Error;
 use crate::filter::EventFilterAction;
 use crate::globals::GLOBALS;
 use crate::misc::{Freshness, Private};
 use crate::people::{People, PersonList, PersonListMetadata};
 use crate::relationship::{RelationshipByAddr, RelationshipById};
+use crate::relay::Relay;
 use async_recursion::async_recursion;
 use heed::RwTxn;
 use nostr_types::{
@@ -74,7 +78,7 @@ pub async fn process_new_event(
     {
         let filter_result = {
             if event.kind == EventKind::GiftWrap {
-                if let Ok(rumor) = GLOBALS.identity.unwrap_giftwrap(event) {
+                if let Ok(rumor) = GLOBALS.storage.giftwrap_rumor(event) {
                     let author = GLOBALS.storage.read_person(&rumor.pubkey, None)?;
                     Some(crate::filter::filter_rumor(rumor, author, event.id))
                 } else {
@@ -97,12 +101,26 @@ pub async fn process_new_event(
                 return Ok(());
             }
             Some(EventFilterAction::MuteAuthor) => {
-                GLOBALS.people.mute(&event.pubkey, true, Private(false))?;
+                GLOBALS.people.mute_with_reason(
+                    &event.pubkey,
+                    true,
+                    Private(false),
+                    Some("Spam filter".to_owned()),
+                    Some(event.id),
+                )?;
                 return Ok(());
             }
         }
     }
 
+    // If it came from a relay search (NIP-50), add it to the search results
+    if subscription.as_deref() == Some("temp_search") {
+        let mut results = GLOBALS.note_search_results.write();
+        if !results.iter().any(|e| e.id == event.id) {
+            results.push(event.clone());
+        }
+    }
+
     // Invalidate the note itself (due to seen_on probably changing)
     GLOBALS.ui_notes_to_invalidate.write().push(event.id);
 
@@ -158,6 +176,14 @@ pub async fn process_new_event(
         }
     }
 
+    // Whether the author of a contact list followed us in the one it is about to
+    // replace, so that follow-back rules only fire for new followers
+    let followed_us_before = if event.kind == EventKind::ContactList {
+        crate::follow_back::followed_us_before(event)?
+    } else {
+        None
+    };
+
     // Save event
     // Bail if the event is an already-replaced replaceable event
     if event.kind.is_replaceable() {
@@ -171,6 +197,12 @@ pub async fn process_new_event(
             );
             return Ok(()); // This did not replace anything.
         }
+        if seen_on.is_some() {
+            crate::reconcile::check_remote_version(event)?;
+        }
+    } else if seen_on.is_some() {
+        // Written with others that come in at about the same time
+        GLOBALS.storage.queue_event(event)?;
     } else {
         // This will ignore if it is already there
         GLOBALS.storage.write_event(event, None)?;
@@ -200,7 +232,38 @@ pub async fn process_new_event(
     let mut event: &Event = event; // take ownership of this reference
     let mut rumor_event: Event;
     if event.kind == EventKind::GiftWrap {
-        let rumor = GLOBALS.identity.unwrap_giftwrap(event)?;
+        let kept = GLOBALS.storage.read_rumor(event.id).ok().flatten();
+        let rumor = match kept {
+            Some(rumor) => Ok(rumor),
+            None if GLOBALS.identity.is_remote() => {
+                // Don't hold up this minion waiting on the remote signer
+                unwrap_remotely(event.clone());
+                return Ok(());
+            }
+            None => GLOBALS
+                .identity
+                .unwrap_giftwrap_locally(event)
+                .and_then(|rumor| {
+                    // Keep it unwrapped, so it need not be unwrapped again
+                    GLOBALS.storage.write_rumor(event.id, &rumor, None)?;
+                    Ok(rumor)
+                }),
+        };
+        let rumor = match rumor {
+            Ok(rumor) => rumor,
+            Err(e) => {
+                if GLOBALS.identity.is_unlocked() {
+                    return Err(e);
+                }
+                // It was stored and queued as unindexed; it will be processed again
+                // once the key is unlocked.
+                tracing::debug!(
+                    "Giftwrap {} queued until the key is unlocked",
+                    event.id.as_hex_string()
+                );
+                return Ok(());
+            }
+        };
         rumor_event = rumor.into_event_with_bad_signature();
         rumor_event.id = event.id; // Lie so it's handled with the giftwrap's id
         event = &rumor_event;
@@ -210,7 +273,9 @@ pub async fn process_new_event(
         for tag in event.tags.iter() {
             if let Ok((_, Some(uurl), _optmarker)) = tag.parse_event() {
                 if let Ok(url) = RelayUrl::try_from_unchecked_url(&uurl) {
-                    GLOBALS.storage.write_relay_if_missing(&url, None)?;
+                    if !is_relay_blocked(&url) {
+                        GLOBALS.storage.write_relay_if_missing(&url, None)?;
+                    }
                 }
             }
 
@@ -220,6 +285,9 @@ pub async fn process_new_event(
 
                 if let Some(uncheckedurl) = maybeurl {
                     if let Ok(url) = RelayUrl::try_from_unchecked_url(&uncheckedurl) {
+                        if is_relay_blocked(&url) {
+                            continue;
+                        }
                         GLOBALS.storage.write_relay_if_missing(&url, None)?;
 
                         // upsert person_relay.last_suggested
@@ -235,6 +303,66 @@ pub async fn process_new_event(
         }
     }
 
+    // Let subscribers know
+    if event.kind.is_feed_displayable() {
+        crate::bus::send(BusMessage::NewEventInFeed(event.id));
+    }
+    if event.kind == EventKind::EncryptedDirectMessage || event.kind == EventKind::DmChat {
+        if let Some(my_pubkey) = GLOBALS.identity.public_key() {
+            if event.pubkey != my_pubkey && crate::dm_policy::is_dm_sender_accepted(&event.pubkey) {
+                if let Some(channel) = DmChannel::from_event(event, Some(my_pubkey)) {
+                    // An autoreply that fails must not keep the DM from being received
+                    if let Err(e) = crate::dm_autoreply::maybe_autoreply(event, &channel) {
+                        tracing::warn!("DM autoreply: {}", e);
+                    }
+                    crate::bus::send(BusMessage::DmReceived {
+                        channel,
+                        id: event.id,
+                    });
+                }
+            }
+        }
+    }
+    if let Some(tier) = crate::alerts::should_alert_now(event) {
+        crate::bus::send(BusMessage::Alert { id: event.id, tier });
+    }
+    GLOBALS.decks.count_new_event(event);
+    crate::activity::note_event(event);
+
+    // Remember which reactions we use, for reaction pickers
+    if event.kind == EventKind::Reaction && Some(event.pubkey) == GLOBALS.identity.public_key() {
+        crate::reactions::record_own_reaction(event)?;
+    }
+
+    // Remember when we last interacted with the people our event is for
+    if let Some(my_pubkey) = GLOBALS.identity.public_key() {
+        if event.pubkey == my_pubkey
+            && matches!(
+                event.kind,
+                EventKind::TextNote
+                    | EventKind::Reaction
+                    | EventKind::Repost
+                    | EventKind::GenericRepost
+                    | EventKind::EncryptedDirectMessage
+            )
+        {
+            let people: Vec<PublicKey> = event
+                .people()
+                .iter()
+                .map(|(pk, _, _)| *pk)
+                .filter(|pk| *pk != my_pubkey)
+                .collect();
+            if !people.is_empty() {
+                GLOBALS.people.note_interaction(&people, event.created_at)?;
+            }
+        }
+    }
+
+    // Score the interaction between us and them (once per event)
+    if !duplicate {
+        crate::relationship_strength::note_event(event)?;
+    }
+
     // Save event relationships (whether from a relay or not)
     let invalid_ids = process_relationships_of_event(event, None)?;
 
@@ -261,10 +389,10 @@ pub async fn process_new_event(
                 let (_personlist, _metadata) =
                     update_or_allocate_person_list_from_event(event, pubkey)?;
             } else {
-                process_somebody_elses_contact_list(event).await?;
+                process_somebody_elses_contact_list(event, followed_us_before).await?;
             }
         } else {
-            process_somebody_elses_contact_list(event).await?;
+            process_somebody_elses_contact_list(event, followed_us_before).await?;
         }
     } else if event.kind == EventKind::MuteList || event.kind == EventKind::FollowSets {
         // Only our own
@@ -275,6 +403,25 @@ pub async fn process_new_event(
                     update_or_allocate_person_list_from_event(event, pubkey)?;
             }
         }
+    } else if event.kind == EventKind::BlockedRelaysList {
+        // Only our own
+        if let Some(pubkey) = GLOBALS.identity.public_key() {
+            crate::blocked_relays::process_blocked_relays_event(event, pubkey)?;
+        }
+    } else if event.kind == EventKind::SearchRelaysList {
+        // Only our own
+        if let Some(pubkey) = GLOBALS.identity.public_key() {
+            if event.pubkey == pubkey {
+                process_search_relays_list(event)?;
+            }
+        }
+    } else if event.kind == EventKind::DmRelayList {
+        // Only our own. Those of others are looked up when we DM them.
+        if let Some(pubkey) = GLOBALS.identity.public_key() {
+            if event.pubkey == pubkey {
+                process_dm_relays_list(event)?;
+            }
+        }
     } else if event.kind == EventKind::RelayList {
         GLOBALS.storage.process_relay_list(event, None)?;
 
@@ -287,6 +434,8 @@ pub async fn process_new_event(
         let _ = GLOBALS
             .to_overlord
             .send(ToOverlordMessage::RefreshScoresAndPickRelays);
+    } else if event.kind == EventKind::from(crate::relay_monitor::RELAY_DISCOVERY_KIND) {
+        crate::relay_monitor::process_relay_discovery_event(event)?;
     } else if event.kind == EventKind::Repost {
         // If it has a json encoded inner event
         if let Ok(inner_event) = serde_json::from_str::<Event>(&event.content) {
@@ -337,6 +486,25 @@ pub async fn process_new_event(
         }
     } else if event.kind == EventKind::NostrConnect {
         crate::nip46::handle_command(event, seen_on.clone())?
+    } else if event.kind == EventKind::WalletResponse {
+        crate::nwc::handle_response(event)?
+    } else if event.kind == EventKind::LiveEvent {
+        crate::live::process_live_event(event)?
+    } else if crate::groups::group_record_kinds().contains(&event.kind) {
+        crate::groups::process_group_record(event, seen_on.as_ref())?
+    } else if event.kind == EventKind::CommunityDefinition {
+        crate::communities::process_community_definition(event)?
+    } else if event.kind == EventKind::CommunityPostApproval {
+        // Approvals carry the post they approve, which we may not have otherwise
+        if let Ok(post) = serde_json::from_str::<Event>(&event.content) {
+            if post.verify(None).is_ok() {
+                process_new_event(&post, None, None, verify, false).await?;
+            }
+        }
+    } else if u32::from(event.kind) == crate::dvm::JOB_FEEDBACK_KIND
+        || crate::dvm::is_job_result_kind(u32::from(event.kind))
+    {
+        crate::dvm::process_job_event(event)?
     }
 
     if event.kind.is_feed_displayable() {
@@ -361,6 +529,7 @@ pub async fn process_new_event(
                             .filter_map(|unchecked| {
                                 RelayUrl::try_from_unchecked_url(unchecked).ok()
                             })
+                            .filter(|url| !is_relay_blocked(url))
                             .collect();
                         let _ = GLOBALS
                             .to_overlord
@@ -392,6 +561,9 @@ pub async fn process_new_event(
                     // Make sure we have their relays
                     for relay in prof.relays {
                         if let Ok(rurl) = RelayUrl::try_from_unchecked_url(&relay) {
+                            if is_relay_blocked(&rurl) {
+                                continue;
+                            }
                             GLOBALS.storage.modify_person_relay(
                                 prof.pubkey,
                                 &rurl,
@@ -416,8 +588,10 @@ pub async fn process_new_event(
                 }
                 NostrBech32::Relay(relay) => {
                     if let Ok(rurl) = RelayUrl::try_from_unchecked_url(&relay) {
-                        // make sure we have the relay
-                        GLOBALS.storage.write_relay_if_missing(&rurl, None)?;
+                        // make sure we have the relay (unless it is blocked)
+                        if !is_relay_blocked(&rurl) {
+                            GLOBALS.storage.write_relay_if_missing(&rurl, None)?;
+                        }
                     }
                 }
             }
@@ -436,7 +610,34 @@ pub async fn process_new_event(
     Ok(())
 }
 
-async fn process_somebody_elses_contact_list(event: &Event) -> Result<(), Error> {
+// Have the remote signer unwrap this giftwrap in the background. Once it does, the
+// rumor is kept and indexed and the giftwrap is processed again, from the kept rumor.
+fn unwrap_remotely(event: Event) {
+    tokio::task::spawn(async move {
+        let result = async {
+            let rumor = GLOBALS.identity.unwrap_giftwrap(&event).await?;
+            GLOBALS.storage.write_rumor(event.id, &rumor, None)?;
+            // Storage does not wait on a remote signer, so it was stored unindexed
+            GLOBALS.storage.index_unindexed_giftwrap(event.id)?;
+            process_new_event(&event, None, None, false, true).await
+        }
+        .await;
+        if let Err(e) = result {
+            tracing::warn!("Giftwrap {}: {}", event.id.as_hex_string(), e);
+        }
+    });
+}
+
+async fn process_somebody_elses_contact_list(
+    event: &Event,
+    followed_us_before: Option<bool>,
+) -> Result<(), Error> {
+    // They may have just followed us. A failure here must not stop the
+    // relay list processing below.
+    if let Err(e) = crate::follow_back::on_contact_list(event, followed_us_before) {
+        tracing::error!("Follow-back: {}", e);
+    }
+
     // We don't keep their contacts or show to the user yet.
     // We only process the contents for (non-standard) relay list information.
 
@@ -488,7 +689,7 @@ pub(crate) fn process_relationships_of_event<'a>(
 
     let mut f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
         // RepliesTo (or Annotation)
-        match event.replies_to() {
+        match crate::nip10::replies_to(event) {
             Some(EventReference::Id { id, .. }) => {
                 if event.is_annotation() {
                     GLOBALS.storage.write_relationship_by_id(
@@ -862,6 +1063,9 @@ pub(crate) fn process_relationships_of_event<'a>(
 
             invalidate.push(zapdata.id);
         }
+        if let Some(zap) = crate::zaps::validate_zap_receipt(event) {
+            GLOBALS.storage.write_zap_receipt(&zap, Some(txn))?;
+        }
 
         // JobResult
         if event.kind.is_job_result() {
@@ -884,7 +1088,7 @@ pub(crate) fn process_relationships_of_event<'a>(
         Some(txn) => f(txn)?,
         None => {
             let mut txn = GLOBALS.storage.get_write_txn()?;
-            f(&mut txn)?;
+            f(&mut *txn)?;
             txn.commit()?;
         }
     };
@@ -911,7 +1115,7 @@ fn update_or_allocate_person_list_from_event(
             metadata.event_private_len = None;
         } else if GLOBALS.identity.is_unlocked() {
             let mut private_len: Option<usize> = None;
-            if let Ok(bytes) = GLOBALS.identity.decrypt(&pubkey, &event.content) {
+            if let Ok(bytes) = GLOBALS.identity.decrypt_locally(&pubkey, &event.content) {
                 if let Ok(vectags) = serde_json::from_str::<Vec<Tag>>(&bytes) {
                     private_len = Some(vectags.iter().filter(|t| t.tagname() == "p").count());
                 }
@@ -947,3 +1151,40 @@ fn update_or_allocate_person_list_from_event(
 
     Ok((list, metadata))
 }
+
+// Our own search relays list (kind 10007) sets the SEARCH usage bit on exactly
+// the listed relays
+fn process_search_relays_list(event: &Event) -> Result<(), Error> {
+    let mut urls: Vec<RelayUrl> = Vec::new();
+    for tag in event.tags.iter() {
+        if tag.tagname() == "relay" {
+            if let Ok(url) = RelayUrl::try_from_str(tag.value()) {
+                GLOBALS.storage.write_relay_if_missing(&url, None)?;
+                urls.push(url);
+            }
+        }
+    }
+
+    GLOBALS.storage.modify_all_relays(
+        |relay| relay.adjust_usage_bit(Relay::SEARCH, urls.contains(&relay.url)),
+        None,
+    )?;
+
+    Ok(())
+}
+
+// Our own DM relays list (kind 10050) sets the DM usage bit on exactly the listed
+// relays
+fn process_dm_relays_list(event: &Event) -> Result<(), Error> {
+    let urls = crate::nip17::relays_of_list(event);
+    for url in urls.iter() {
+        GLOBALS.storage.write_relay_if_missing(url, None)?;
+    }
+
+    GLOBALS.storage.modify_all_relays(
+        |relay| relay.adjust_usage_bit(Relay::DM, urls.contains(&relay.url)),
+        None,
+    )?;
+
+    Ok(())
+}
@@ -15,6 +15,9 @@ lazy_static! {
 /// Storage paths
 #[derive(Clone, Debug, PartialEq)]
 pub struct Profile {
+    /// The name of the profile, None for the default profile
+    pub name: Option<String>,
+
     /// The base directory for all gossip data
     pub base_dir: PathBuf,
 
@@ -29,7 +32,9 @@ pub struct Profile {
 }
 
 impl Profile {
-    fn new() -> Result<Profile, Error> {
+    // The base directory for all gossip data, holding the default profile and the
+    // directories of named profiles
+    fn base_dir() -> Result<PathBuf, Error> {
         if cfg!(feature = "appimage") {
             // Because AppImage only changes $HOME (and not $XDG_DATA_HOME), we unset
             // $XDG_DATA_HOME and let it use the changed $HOME on linux to find the
@@ -60,25 +65,40 @@ impl Profile {
             }
         };
 
+        Ok(base_dir)
+    }
+
+    fn new() -> Result<Profile, Error> {
+        // optional profile name, if specified the the user data is stored in a subdirectory
+        Profile::open(env::var("GOSSIP_PROFILE").ok().as_deref())
+    }
+
+    // The profile with this name, or the default profile. Its directories are
+    // created if missing.
+    fn open(name: Option<&str>) -> Result<Profile, Error> {
+        let base_dir = Profile::base_dir()?;
+
         let cache_dir = {
             let mut cache_dir = base_dir.clone();
             cache_dir.push("cache");
             cache_dir
         };
 
-        // optional profile name, if specified the the user data is stored in a subdirectory
-        let profile_dir = match env::var("GOSSIP_PROFILE") {
-            Ok(profile) => {
-                if "cache".eq_ignore_ascii_case(profile.as_str()) {
-                    return Err(Error::from("Profile name 'cache' is reserved."));
+        let profile_dir = match name {
+            Some(profile) => {
+                if "cache".eq_ignore_ascii_case(profile) || "lmdb".eq_ignore_ascii_case(profile) {
+                    return Err(Error::from(format!(
+                        "Profile name '{}' is reserved.",
+                        profile
+                    )));
                 }
 
                 // Check that it doesn't corrupt the expected path
                 let mut dir = base_dir.clone();
-                dir.push(&profile);
+                dir.push(profile);
                 match dir.file_name() {
                     Some(filename) => {
-                        if filename != OsStr::new(&profile) {
+                        if filename != OsStr::new(profile) {
                             return Err(Error::from(format!(
                                 "Profile is not a simple filename: {}",
                                 profile
@@ -92,7 +112,7 @@ impl Profile {
 
                 dir
             }
-            Err(_) => base_dir.clone(),
+            None => base_dir.clone(),
         };
 
         let lmdb_dir = {
@@ -114,6 +134,7 @@ impl Profile {
         fs::create_dir_all(&lmdb_dir)?;
 
         Ok(Profile {
+            name: name.map(|n| n.to_owned()),
             base_dir,
             profile_dir,
             cache_dir,
@@ -121,6 +142,39 @@ impl Profile {
         })
     }
 
+    /// The names of the named profiles that exist. The default profile (in the base
+    /// directory itself) is not among them.
+    pub fn list() -> Result<Vec<String>, Error> {
+        let base_dir = Profile::base_dir()?;
+        let mut names: Vec<String> = Vec::new();
+        for entry in fs::read_dir(&base_dir)? {
+            let entry = entry?;
+            let name = match entry.file_name().into_string() {
+                Ok(name) => name,
+                Err(_) => continue,
+            };
+            if name == "cache" || name == "lmdb" {
+                continue;
+            }
+            if entry.path().join("lmdb").is_dir() {
+                names.push(name);
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// Make the profile with this name (or the default profile with None) the
+    /// current one, creating it if it does not exist. This only changes where
+    /// [current](Profile::current) points; see
+    /// [switch_profile](crate::Overlord::switch_profile) to move a running gossip
+    /// over to it.
+    pub(crate) fn switch_to(name: Option<&str>) -> Result<Profile, Error> {
+        let profile = Profile::open(name)?;
+        *CURRENT.write().unwrap() = Some(profile.clone());
+        Ok(profile)
+    }
+
     pub fn current() -> Result<Profile, Error> {
         {
             // create a new scope to drop the read lock before we try to create a new profile if it doesn't exist
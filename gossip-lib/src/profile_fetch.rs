@@ -0,0 +1,47 @@
+use crate::globals::GLOBALS;
+use nostr_types::EventKind;
+
+/// What viewing someone's profile requests from relays, per the `profile_fetch_*`
+/// settings. What is not requested is still shown if we already have it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProfileFetch {
+    /// Their metadata (kind 0)
+    pub metadata: bool,
+
+    /// Their relay list (kind 10002)
+    pub relay_list: bool,
+
+    /// Their notes, as the person feed does
+    pub notes: bool,
+
+    /// At most this many of their notes (0 for all of the person feed chunk)
+    pub notes_limit: usize,
+
+    /// Reactions, zaps, replies and deletions of the notes in view
+    pub augments: bool,
+}
+
+impl ProfileFetch {
+    /// The current settings
+    pub fn current() -> ProfileFetch {
+        ProfileFetch {
+            metadata: GLOBALS.storage.read_setting_profile_fetch_metadata(),
+            relay_list: GLOBALS.storage.read_setting_profile_fetch_relay_list(),
+            notes: GLOBALS.storage.read_setting_profile_fetch_notes(),
+            notes_limit: GLOBALS.storage.read_setting_profile_fetch_notes_limit() as usize,
+            augments: GLOBALS.storage.read_setting_profile_fetch_augments(),
+        }
+    }
+
+    /// The replaceable event kinds requested when a profile is viewed
+    pub fn kinds(&self) -> Vec<EventKind> {
+        let mut kinds: Vec<EventKind> = Vec::new();
+        if self.metadata {
+            kinds.push(EventKind::Metadata);
+        }
+        if self.relay_list {
+            kinds.push(EventKind::RelayList);
+        }
+        kinds
+    }
+}
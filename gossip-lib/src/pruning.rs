@@ -0,0 +1,265 @@
+use crate::comms::ToOverlordMessage;
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use nostr_types::{EventKind, Id, PublicKey, Tag, Unixtime};
+use std::collections::{BTreeMap, HashSet};
+use std::time::Duration;
+use tokio::task;
+use tokio::time::Instant;
+
+// How long after startup policies are first applied
+const FIRST_PRUNE_SECS: u64 = 600;
+
+// How often policies are applied after that
+const PRUNE_INTERVAL_SECS: u64 = 6 * 60 * 60;
+
+const DAY_SECS: i64 = 86400;
+
+// How long we keep what refers to an event we don't have, in case it arrives
+const MISSING_EVENT_DAYS: i64 = 30;
+
+/// Which events automatic pruning deletes, from the settings. Direct messages, our
+/// own events, and events we have bookmarked or starred are never deleted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrunePolicy {
+    /// Events older than this many days are deleted (the `prune_period_days` setting)
+    pub max_age_days: u64,
+
+    /// Once events take up more than this many bytes, the oldest are deleted, a
+    /// day at a time, until they don't
+    pub max_events_bytes: Option<u64>,
+
+    /// Events of these kinds are deleted after this many days instead
+    pub kind_days: Vec<(EventKind, u64)>,
+}
+
+impl PrunePolicy {
+    pub fn from_settings() -> PrunePolicy {
+        let max_mb = GLOBALS.storage.read_setting_prune_max_events_mb();
+        PrunePolicy {
+            max_age_days: GLOBALS.storage.read_setting_prune_period_days(),
+            max_events_bytes: if max_mb == 0 {
+                None
+            } else {
+                Some(max_mb * 1_000_000)
+            },
+            kind_days: parse_kind_days(&GLOBALS.storage.read_setting_prune_kind_days()),
+        }
+    }
+
+    /// How old an event of this kind may get, in seconds
+    pub fn max_age_secs(&self, kind: EventKind) -> i64 {
+        let days = self
+            .kind_days
+            .iter()
+            .find(|(k, _)| *k == kind)
+            .map(|(_, days)| *days)
+            .unwrap_or(self.max_age_days);
+        days as i64 * DAY_SECS
+    }
+}
+
+/// Parse "kind:days" pairs separated by spaces or commas (e.g. "7:14 6:30"),
+/// skipping any that don't parse
+pub fn parse_kind_days(s: &str) -> Vec<(EventKind, u64)> {
+    s.split(|c: char| c.is_whitespace() || c == ',')
+        .filter_map(|pair| {
+            let (kind, days) = pair.split_once(':')?;
+            let kind: u32 = kind.trim().parse().ok()?;
+            let days: u64 = days.trim().parse().ok()?;
+            Some((kind.into(), days))
+        })
+        .collect()
+}
+
+fn is_dm_kind(kind: EventKind) -> bool {
+    matches!(
+        kind,
+        EventKind::EncryptedDirectMessage | EventKind::GiftWrap | EventKind::DmChat
+    )
+}
+
+// Events we have bookmarked (publicly or privately, by id or by address) or starred.
+// None if we can't tell, because our private bookmarks can't be decrypted while
+// the identity is locked.
+fn kept_ids() -> Result<Option<HashSet<Id>>, Error> {
+    let mut kept: HashSet<Id> = GLOBALS
+        .storage
+        .read_all_stars()?
+        .iter()
+        .map(|(id, _, _)| *id)
+        .collect();
+
+    if let Some(pubkey) = GLOBALS.identity.public_key() {
+        if let Some(list) =
+            GLOBALS
+                .storage
+                .get_replaceable_event(EventKind::BookmarkList, pubkey, "")?
+        {
+            let mut tags: Vec<Tag> = list.tags.clone();
+            if !list.content.is_empty() {
+                if !GLOBALS.identity.is_unlocked() {
+                    return Ok(None);
+                }
+                let bytes = GLOBALS.identity.decrypt_locally(&pubkey, &list.content)?;
+                tags.extend(serde_json::from_str::<Vec<Tag>>(&bytes)?);
+            }
+
+            for tag in tags.iter() {
+                if let Ok((id, _, _)) = tag.parse_event() {
+                    kept.insert(id);
+                } else if let Ok((ea, _)) = tag.parse_address() {
+                    if let Some(event) = GLOBALS
+                        .storage
+                        .get_replaceable_event(ea.kind, ea.author, &ea.d)?
+                    {
+                        kept.insert(event.id);
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(Some(kept))
+}
+
+/// Delete the events that the pruning policies pick, along with everything stored
+/// about them. Returns how many were deleted.
+pub fn prune_by_policy() -> Result<usize, Error> {
+    let policy = PrunePolicy::from_settings();
+    let me = GLOBALS.identity.public_key();
+    let kept = match kept_ids()? {
+        Some(kept) => kept,
+        None => {
+            tracing::info!("Not pruning while private bookmarks are locked");
+            return Ok(0);
+        }
+    };
+    let now = Unixtime::now().unwrap();
+
+    let exempt = |id: Id, kind: EventKind, pubkey: PublicKey| -> bool {
+        is_dm_kind(kind) || Some(pubkey) == me || kept.contains(&id)
+    };
+
+    // Pick those too old for their kind, and tally how much the rest take up each day
+    let mut ids: HashSet<Id> = HashSet::new();
+    let mut remaining: u64 = 0;
+    let mut prunable_by_day: BTreeMap<i64, u64> = BTreeMap::new();
+    GLOBALS
+        .storage
+        .for_each_event_header(|id, kind, pubkey, created_at, len| {
+            if exempt(id, kind, pubkey) {
+                remaining += len as u64;
+            } else if created_at.0 < now.0 - policy.max_age_secs(kind) {
+                ids.insert(id);
+            } else {
+                remaining += len as u64;
+                *prunable_by_day
+                    .entry(created_at.0.div_euclid(DAY_SECS))
+                    .or_default() += len as u64;
+            }
+        })?;
+
+    // If that leaves too much, also pick the oldest days of what's left
+    if let Some(max) = policy.max_events_bytes {
+        let mut cutoff: Option<i64> = None;
+        for (day, bytes) in prunable_by_day.iter() {
+            if remaining <= max {
+                break;
+            }
+            remaining = remaining.saturating_sub(*bytes);
+            cutoff = Some((day + 1) * DAY_SECS);
+        }
+        if let Some(cutoff) = cutoff {
+            GLOBALS
+                .storage
+                .for_each_event_header(|id, kind, pubkey, created_at, _len| {
+                    if created_at.0 < cutoff && !exempt(id, kind, pubkey) {
+                        ids.insert(id);
+                    }
+                })?;
+        }
+    }
+
+    if ids.is_empty() {
+        return Ok(0);
+    }
+
+    GLOBALS.storage.prune_events(&ids)?;
+
+    Ok(ids.len())
+}
+
+/// How far back an event we hear about but don't have may still arrive. What is
+/// kept about it (relationships, where it was seen) goes after that.
+pub fn missing_since() -> Unixtime {
+    Unixtime(Unixtime::now().unwrap().0 - MISSING_EVENT_DAYS * DAY_SECS)
+}
+
+/// Remove what the auxiliary tables hold about events we don't have (see
+/// [collect_garbage](crate::Storage::collect_garbage)), logging what went
+pub fn collect_garbage() {
+    match GLOBALS.storage.collect_garbage(missing_since()) {
+        Ok(report) => tracing::info!(
+            "Garbage collection removed {} relationships, {} index entries and {} seen-on records",
+            report.relationships,
+            report.index_entries,
+            report.seen_on
+        ),
+        Err(e) => tracing::error!("Garbage collection failed: {}", e),
+    }
+}
+
+pub(crate) fn start() {
+    tracing::info!("Pruner startup");
+
+    task::spawn(async {
+        let mut read_runstate = GLOBALS.read_runstate.clone();
+        read_runstate.mark_unchanged();
+        if read_runstate.borrow().going_offline() {
+            return;
+        }
+
+        let sleep = tokio::time::sleep(Duration::from_secs(FIRST_PRUNE_SECS));
+        tokio::pin!(sleep);
+
+        loop {
+            tokio::select! {
+                _ = &mut sleep => {
+                    sleep.as_mut().reset(Instant::now() + Duration::from_secs(PRUNE_INTERVAL_SECS));
+                },
+                _ = read_runstate.wait_for(|runstate| runstate.going_offline()) => break,
+            }
+
+            if GLOBALS.storage.read_setting_auto_prune() {
+                let _ = GLOBALS.to_overlord.send(ToOverlordMessage::PruneByPolicy);
+            } else {
+                let _ = GLOBALS.to_overlord.send(ToOverlordMessage::CollectGarbage);
+            }
+        }
+
+        tracing::info!("Pruner shutdown");
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_kind_days() {
+        assert_eq!(
+            parse_kind_days("7:14 6:30,1:365"),
+            vec![
+                (EventKind::Reaction, 14),
+                (EventKind::Repost, 30),
+                (EventKind::TextNote, 365)
+            ]
+        );
+        assert_eq!(
+            parse_kind_days("7:x 6 :3 9:2"),
+            vec![(EventKind::from(9u32), 2)]
+        );
+        assert!(parse_kind_days("").is_empty());
+    }
+}
@@ -0,0 +1,147 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use nostr_types::{Event, EventKind, Tag, Unixtime};
+use std::collections::HashMap;
+
+// A use this many days ago counts half as much as one today
+const HALF_LIFE_DAYS: f64 = 14.0;
+
+/// A reaction offered by a reaction picker
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReactionChoice {
+    /// The reaction event content: an emoji, "+", or ":shortcode:" for a custom emoji
+    pub content: String,
+
+    /// The image of a custom emoji (NIP-30)
+    pub image_url: Option<String>,
+
+    /// How many times we have reacted with this
+    pub count: u64,
+
+    /// When we last reacted with this
+    pub last_used: Option<Unixtime>,
+}
+
+impl ReactionChoice {
+    /// The 'emoji' tag a reaction with this custom emoji must carry
+    pub fn emoji_tag(&self) -> Option<Tag> {
+        let url = self.image_url.as_ref()?;
+        let shortcode = self.content.trim_matches(':');
+        Some(Tag::new(&["emoji", shortcode, url]))
+    }
+}
+
+// Process.rs calls this for each new reaction event of ours, whichever client made it
+pub(crate) fn record_own_reaction(event: &Event) -> Result<(), Error> {
+    let content = match event.content.trim() {
+        "" => "+",
+        c => c,
+    };
+    GLOBALS
+        .storage
+        .record_reaction_usage(content, event.created_at, None)
+}
+
+/// Our reactions ranked by how often and how recently we used them, most likely
+/// first, followed by the unused custom emoji from our emoji list (kind 10030 and
+/// the emoji sets it references). At most `limit` choices are returned.
+pub fn ranked_reactions(limit: usize) -> Result<Vec<ReactionChoice>, Error> {
+    let custom = custom_emoji()?;
+    let now = Unixtime::now().unwrap().0;
+
+    let mut used: Vec<(f64, ReactionChoice)> = GLOBALS
+        .storage
+        .read_all_reaction_usage()?
+        .drain(..)
+        .map(|(content, count, last_used)| {
+            let age_days = (now - last_used.0).max(0) as f64 / 86400.0;
+            let score = count as f64 * 0.5_f64.powf(age_days / HALF_LIFE_DAYS);
+            let image_url = shortcode(&content).and_then(|code| custom.get(code).cloned());
+            (
+                score,
+                ReactionChoice {
+                    content,
+                    image_url,
+                    count,
+                    last_used: Some(last_used),
+                },
+            )
+        })
+        .collect();
+    used.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let mut output: Vec<ReactionChoice> = used.drain(..).map(|(_, choice)| choice).collect();
+
+    let mut unused: Vec<(&String, &String)> = custom
+        .iter()
+        .filter(|(code, _)| {
+            !output
+                .iter()
+                .any(|c| shortcode(&c.content) == Some(code.as_str()))
+        })
+        .collect();
+    unused.sort();
+    for (code, url) in unused {
+        output.push(ReactionChoice {
+            content: format!(":{}:", code),
+            image_url: Some(url.to_owned()),
+            count: 0,
+            last_used: None,
+        });
+    }
+
+    output.truncate(limit);
+    Ok(output)
+}
+
+/// Our custom emoji (shortcode -> image url), from our emoji list and the emoji
+/// sets it references, as far as we have them
+pub fn custom_emoji() -> Result<HashMap<String, String>, Error> {
+    let mut output: HashMap<String, String> = HashMap::new();
+
+    let my_pubkey = match GLOBALS.identity.public_key() {
+        Some(pk) => pk,
+        None => return Ok(output),
+    };
+
+    let list =
+        match GLOBALS
+            .storage
+            .get_replaceable_event(EventKind::UserEmojiList, my_pubkey, "")?
+        {
+            Some(event) => event,
+            None => return Ok(output),
+        };
+
+    let mut lists: Vec<Event> = vec![list.clone()];
+    for tag in list.tags.iter() {
+        if let Ok((ea, _marker)) = tag.parse_address() {
+            if let Some(set) = GLOBALS
+                .storage
+                .get_replaceable_event(ea.kind, ea.author, &ea.d)?
+            {
+                lists.push(set);
+            }
+        }
+    }
+
+    for event in lists.iter() {
+        for tag in event.tags.iter() {
+            if tag.tagname() == "emoji" && !tag.get_index(1).is_empty() {
+                output
+                    .entry(tag.get_index(1).to_owned())
+                    .or_insert_with(|| tag.get_index(2).to_owned());
+            }
+        }
+    }
+
+    Ok(output)
+}
+
+fn shortcode(content: &str) -> Option<&str> {
+    if content.len() > 2 && content.starts_with(':') && content.ends_with(':') {
+        Some(&content[1..content.len() - 1])
+    } else {
+        None
+    }
+}
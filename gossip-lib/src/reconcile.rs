@@ -0,0 +1,140 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::pending::PendingItem;
+use nostr_types::{Event, EventKind, Id, Unixtime};
+use serde::{Deserialize, Serialize};
+
+// What we last published is kept in the app data store under this namespace and key
+const PUBLISHED_NAMESPACE: &str = crate::storage::LIB_NAMESPACE;
+const PUBLISHED_KEY: &str = "published_replaceables";
+
+/// Our replaceable events that are checked against our relays at startup
+pub const RECONCILED_KINDS: &[EventKind] = &[
+    EventKind::Metadata,
+    EventKind::ContactList,
+    EventKind::MuteList,
+    EventKind::RelayList,
+    EventKind::BookmarkList,
+    EventKind::BlockedRelaysList,
+    EventKind::SearchRelaysList,
+    EventKind::DmRelayList,
+    EventKind::UserEmojiList,
+    EventKind::FollowSets,
+];
+
+// The last version of a replaceable event that we signed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Published {
+    kind: u32,
+    d: String,
+    id: Id,
+    created_at: i64,
+}
+
+fn load() -> Result<Vec<Published>, Error> {
+    Ok(GLOBALS
+        .storage
+        .app_data(PUBLISHED_NAMESPACE)?
+        .get::<Vec<Published>>(PUBLISHED_KEY)?
+        .unwrap_or_default())
+}
+
+fn save(published: &Vec<Published>) -> Result<(), Error> {
+    GLOBALS
+        .storage
+        .app_data(PUBLISHED_NAMESPACE)?
+        .put(PUBLISHED_KEY, published)
+}
+
+fn d_of(event: &Event) -> String {
+    event.parameter().unwrap_or_default()
+}
+
+fn set_baseline(kind: u32, d: &str, id: Id, created_at: Unixtime) -> Result<(), Error> {
+    let mut published = load()?;
+    published.retain(|p| !(p.kind == kind && p.d == d));
+    published.push(Published {
+        kind,
+        d: d.to_owned(),
+        id,
+        created_at: created_at.0,
+    });
+    save(&published)
+}
+
+// GossipIdentity calls this for every event it signs. Remember our replaceable
+// events so a newer version from elsewhere can be told apart from our own.
+pub(crate) fn record_signed(event: &Event) {
+    if !event.kind.is_replaceable() {
+        return;
+    }
+    if Some(event.pubkey) != GLOBALS.identity.public_key() {
+        return;
+    }
+
+    let kind = u32::from(event.kind);
+    let d = d_of(event);
+    if let Err(e) = set_baseline(kind, &d, event.id, event.created_at) {
+        tracing::error!("Could not record a published replaceable event: {}", e);
+    }
+
+    // Publishing again settles any conflict
+    GLOBALS
+        .pending
+        .remove(&PendingItem::ReplaceableConflict { kind, d });
+}
+
+// Process.rs calls this when a replaceable event of ours from a relay replaced the
+// local one. If it is newer than what we last published, another client edited it.
+pub(crate) fn check_remote_version(event: &Event) -> Result<(), Error> {
+    if Some(event.pubkey) != GLOBALS.identity.public_key() {
+        return Ok(());
+    }
+
+    let kind = u32::from(event.kind);
+    let d = d_of(event);
+    let published = load()?;
+    let ours = match published.iter().find(|p| p.kind == kind && p.d == d) {
+        Some(ours) => ours,
+        None => return Ok(()), // we never published it, so there is nothing to protect
+    };
+
+    if ours.id != event.id && event.created_at.0 > ours.created_at {
+        tracing::info!(
+            "Our {} was changed by another client",
+            GLOBALS.kinds.describe_number(kind).name
+        );
+        GLOBALS
+            .pending
+            .insert(PendingItem::ReplaceableConflict { kind, d });
+    }
+
+    Ok(())
+}
+
+/// When we last published this replaceable event (kind, and 'd' tag value or "")
+pub fn last_published(kind: EventKind, d: &str) -> Result<Option<(Id, Unixtime)>, Error> {
+    let kind = u32::from(kind);
+    Ok(load()?
+        .iter()
+        .find(|p| p.kind == kind && p.d == d)
+        .map(|p| (p.id, Unixtime(p.created_at))))
+}
+
+/// Accept the version another client published as the one to compare with from now
+/// on, and drop the pending conflict
+pub fn acknowledge_conflict(kind: u32, d: &str) -> Result<(), Error> {
+    if let Some(pubkey) = GLOBALS.identity.public_key() {
+        if let Some(event) = GLOBALS
+            .storage
+            .get_replaceable_event(kind.into(), pubkey, d)?
+        {
+            set_baseline(kind, d, event.id, event.created_at)?;
+        }
+    }
+    GLOBALS.pending.remove(&PendingItem::ReplaceableConflict {
+        kind,
+        d: d.to_owned(),
+    });
+    Ok(())
+}
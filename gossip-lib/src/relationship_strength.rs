@@ -0,0 +1,183 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::people::PersonList;
+use nostr_types::{Event, EventKind, PublicKey, Unixtime};
+
+// What each interaction adds to the score, either way
+const REPLY_WEIGHT: f64 = 3.0;
+const MENTION_WEIGHT: f64 = 2.0;
+const DM_WEIGHT: f64 = 2.0;
+const ZAP_WEIGHT: f64 = 3.0;
+const REACTION_WEIGHT: f64 = 1.0;
+const REPOST_WEIGHT: f64 = 1.0;
+
+// An interaction counts half as much after this long
+const HALF_LIFE_SECS: f64 = 30.0 * 24.0 * 3600.0;
+
+// A score decayed from one time to a later one
+fn decayed(score: f64, from: Unixtime, to: Unixtime) -> f64 {
+    let elapsed = (to.0 - from.0).max(0) as f64;
+    score * 0.5_f64.powf(elapsed / HALF_LIFE_SECS)
+}
+
+// A score of `weight` at `at` added to the stored one, as of the later of the two
+fn add(old: Option<(f64, Unixtime)>, weight: f64, at: Unixtime) -> (f64, Unixtime) {
+    match old {
+        None => (weight, at),
+        Some((score, as_of)) => {
+            let now = as_of.max(at);
+            (decayed(score, as_of, now) + decayed(weight, at, now), now)
+        }
+    }
+}
+
+/// How strong our relationship with this person is: the interactions between us in
+/// both directions (replies, mentions, DMs, zaps, reactions and reposts), each
+/// counting half as much every 30 days. 0.0 if we never interacted, or mute them.
+pub fn strength(pubkey: PublicKey) -> f64 {
+    if is_muted(&pubkey) {
+        return 0.0;
+    }
+    match GLOBALS.storage.read_interaction_score(pubkey) {
+        Ok(Some((score, as_of))) => decayed(score, as_of, Unixtime::now().unwrap()),
+        _ => 0.0,
+    }
+}
+
+/// The people we have the strongest relationships with, strongest first. People we
+/// mute are left out.
+pub fn strongest(limit: usize) -> Result<Vec<(PublicKey, f64)>, Error> {
+    let now = Unixtime::now().unwrap();
+    let mut scores: Vec<(PublicKey, f64)> = GLOBALS
+        .storage
+        .read_all_interaction_scores()?
+        .drain(..)
+        .filter(|(pubkey, _, _)| !is_muted(pubkey))
+        .map(|(pubkey, score, as_of)| (pubkey, decayed(score, as_of, now)))
+        .collect();
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scores.truncate(limit);
+    Ok(scores)
+}
+
+/// People to suggest adding to `list`: those we have the strongest relationships
+/// with who are not on it yet, strongest first
+pub fn recommended(list: PersonList, limit: usize) -> Result<Vec<(PublicKey, f64)>, Error> {
+    Ok(strongest(usize::MAX)?
+        .drain(..)
+        .filter(|(pubkey, _)| !GLOBALS.people.is_person_in_list(pubkey, list))
+        .take(limit)
+        .collect())
+}
+
+// People we mute gain no strength, including those the spam filter muted
+fn is_muted(pubkey: &PublicKey) -> bool {
+    GLOBALS.people.is_person_in_list(pubkey, PersonList::Muted)
+}
+
+// What an event is worth, and who it is between, if it is an interaction
+fn weigh(event: &Event, my_pubkey: PublicKey) -> Option<(f64, Vec<PublicKey>)> {
+    if event.kind == EventKind::Zap {
+        let (sender, recipient) = zap_parties(event)?;
+        return if sender == my_pubkey && recipient != my_pubkey {
+            Some((ZAP_WEIGHT, vec![recipient]))
+        } else if recipient == my_pubkey && sender != my_pubkey {
+            Some((ZAP_WEIGHT, vec![sender]))
+        } else {
+            None
+        };
+    }
+
+    let weight = match event.kind {
+        EventKind::TextNote => {
+            if crate::nip10::replies_to(event).is_some() {
+                REPLY_WEIGHT
+            } else {
+                MENTION_WEIGHT
+            }
+        }
+        EventKind::EncryptedDirectMessage | EventKind::DmChat => DM_WEIGHT,
+        EventKind::Reaction => REACTION_WEIGHT,
+        EventKind::Repost | EventKind::GenericRepost => REPOST_WEIGHT,
+        _ => return None,
+    };
+
+    let tagged: Vec<PublicKey> = event.people().iter().map(|(pk, _, _)| *pk).collect();
+    if event.pubkey == my_pubkey {
+        let others: Vec<PublicKey> = tagged.into_iter().filter(|pk| *pk != my_pubkey).collect();
+        if others.is_empty() {
+            None
+        } else {
+            Some((weight, others))
+        }
+    } else if tagged.contains(&my_pubkey) {
+        Some((weight, vec![event.pubkey]))
+    } else {
+        None
+    }
+}
+
+// Who sent and who got the zap of a zap receipt, if it is valid. If we know who
+// signs the recipient's receipts, it must be signed by them.
+fn zap_parties(event: &Event) -> Option<(PublicKey, PublicKey)> {
+    let zap = crate::zaps::validate_zap_receipt(event)?;
+    let recipient = event.people().first().map(|(pk, _, _)| *pk)?;
+    if let Ok(Some(signer)) = crate::zaps::zap_signer(recipient) {
+        if signer != zap.signer {
+            return None;
+        }
+    }
+    Some((zap.zapper, recipient))
+}
+
+// Process calls this for each new event
+pub(crate) fn note_event(event: &Event) -> Result<(), Error> {
+    let my_pubkey = match GLOBALS.identity.public_key() {
+        Some(pk) => pk,
+        None => return Ok(()),
+    };
+    let (weight, mut people) = match weigh(event, my_pubkey) {
+        Some(found) => found,
+        None => return Ok(()),
+    };
+    people.retain(|pk| !is_muted(pk));
+    if people.is_empty() {
+        return Ok(());
+    }
+
+    let mut txn = GLOBALS.storage.get_write_txn()?;
+    for pubkey in people {
+        GLOBALS.storage.modify_interaction_score(
+            pubkey,
+            |old| add(old, weight, event.created_at),
+            Some(&mut *txn),
+        )?;
+    }
+    txn.commit()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decayed_add() {
+        let day = 24 * 3600;
+        let start = Unixtime(1_700_000_000);
+        let later = Unixtime(start.0 + 30 * day);
+
+        // A month later an interaction counts half
+        assert!((decayed(4.0, start, later) - 2.0).abs() < 1e-9);
+
+        // An older interaction decays to the stored time
+        let (score, as_of) = add(Some((1.0, later)), 4.0, start);
+        assert_eq!(as_of, later);
+        assert!((score - 3.0).abs() < 1e-9);
+
+        // A newer one decays the stored score to its own time
+        let (score, as_of) = add(Some((4.0, start)), 1.0, later);
+        assert_eq!(as_of, later);
+        assert!((score - 3.0).abs() < 1e-9);
+    }
+}
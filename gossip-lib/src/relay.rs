@@ -1,2 +1,8 @@
 /// Relay type, aliased to the latest version
-pub type Relay = crate::storage::types::Relay2;
+pub type Relay = crate::storage::types::Relay6;
+
+/// Relay capability report type, aliased to the latest version
+pub type RelayCapabilities = crate::storage::types::RelayCapabilities1;
+
+/// Relay monitor report type, aliased to the latest version
+pub type RelayMonitorReport = crate::storage::types::RelayMonitorReport1;
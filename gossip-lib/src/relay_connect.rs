@@ -0,0 +1,62 @@
+use crate::blocked_relays::is_relay_blocked;
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use crate::relay::Relay;
+use crate::USER_AGENT;
+use base64::Engine;
+use http::Uri;
+use nostr_types::RelayUrl;
+use std::time::Duration;
+
+pub(crate) type RelayWebSocket =
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+/// If we may open a connection of our own to this relay: we are not offline, and it
+/// passes the same rank, blocking and approval checks that minions are held to.
+/// Unlike a minion we never ask, so a relay awaiting approval is refused.
+pub(crate) fn may_connect(relay: &Relay) -> bool {
+    if GLOBALS.storage.read_setting_offline() || relay.rank == 0 || is_relay_blocked(&relay.url) {
+        return false;
+    }
+    if GLOBALS
+        .storage
+        .read_setting_relay_connection_requires_approval()
+    {
+        relay.allow_connect == Some(true)
+    } else {
+        relay.allow_connect != Some(false)
+    }
+}
+
+/// Open a bare websocket connection to a relay, outside of any minion. Nothing is
+/// subscribed and the connection is not tracked.
+pub(crate) async fn connect(url: &RelayUrl, timeout: Duration) -> Result<RelayWebSocket, Error> {
+    let uri: Uri = url.as_str().parse::<Uri>()?;
+    let host = match uri.host() {
+        Some(host) => host.to_owned(),
+        None => return Err(ErrorKind::InvalidUrl(url.as_str().to_owned()).into()),
+    };
+
+    let key: [u8; 16] = rand::random();
+    let req = http::request::Request::builder().method("GET");
+    let req = if GLOBALS.storage.read_setting_set_user_agent() {
+        req.header("User-Agent", USER_AGENT)
+    } else {
+        req
+    };
+    let req = req
+        .header("Host", host)
+        .header("Connection", "Upgrade")
+        .header("Upgrade", "websocket")
+        .header("Sec-WebSocket-Version", "13")
+        .header(
+            "Sec-WebSocket-Key",
+            base64::engine::general_purpose::STANDARD.encode(key),
+        )
+        .uri(uri)
+        .body(())?;
+
+    let (websocket, _response) =
+        tokio::time::timeout(timeout, tokio_tungstenite::connect_async(req)).await??;
+    Ok(websocket)
+}
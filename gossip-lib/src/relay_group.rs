@@ -0,0 +1,72 @@
+use crate::blocked_relays::is_relay_blocked;
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use nostr_types::RelayUrl;
+
+/// A named set of relays that stand in for each other, e.g. "my paid relays". The
+/// first member is the primary and the rest are its backups, in order.
+///
+/// Jobs (subscriptions and posts) for a member that is down go to the next member
+/// that is up instead of waiting for it. They stay there; a member that comes back
+/// gets new jobs again as they are made.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelayGroup {
+    pub name: String,
+    pub members: Vec<RelayUrl>,
+}
+
+/// Save a relay group. With no members, the group is removed.
+pub fn set_relay_group(name: &str, members: Vec<RelayUrl>) -> Result<(), Error> {
+    let name = name.trim();
+    if members.is_empty() {
+        return GLOBALS.storage.delete_relay_group(name, None);
+    }
+    let mut unique: Vec<RelayUrl> = Vec::new();
+    for url in members {
+        if !unique.contains(&url) {
+            unique.push(url);
+        }
+    }
+    GLOBALS.storage.write_relay_group(
+        &RelayGroup {
+            name: name.to_owned(),
+            members: unique,
+        },
+        None,
+    )
+}
+
+/// If we can't use this relay right now: it is waiting out a penalty after failing,
+/// or it is one we don't connect to
+pub fn is_relay_down(url: &RelayUrl) -> bool {
+    if GLOBALS.penalty_box_relays.contains_key(url) || is_relay_blocked(url) {
+        return true;
+    }
+    matches!(GLOBALS.storage.read_relay(url, None), Ok(Some(relay)) if relay.rank == 0)
+}
+
+/// The member to hand this relay's jobs to: the first one after it in its group
+/// (wrapping around) that is up. None if it is in no group or no other member is up.
+pub fn stand_in_for(url: &RelayUrl) -> Option<RelayUrl> {
+    let groups = match GLOBALS.storage.read_all_relay_groups() {
+        Ok(groups) => groups,
+        Err(e) => {
+            tracing::error!("{}", e);
+            return None;
+        }
+    };
+    for group in groups {
+        let pos = match group.members.iter().position(|m| m == url) {
+            Some(pos) => pos,
+            None => continue,
+        };
+        let count = group.members.len();
+        for offset in 1..count {
+            let member = &group.members[(pos + offset) % count];
+            if !is_relay_down(member) {
+                return Some(member.clone());
+            }
+        }
+    }
+    None
+}
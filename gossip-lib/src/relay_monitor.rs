@@ -0,0 +1,85 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::relay::RelayMonitorReport;
+use nostr_types::{Event, PublicKey, RelayUrl};
+
+/// The kind of the report a relay monitor publishes about each relay it finds up
+/// (NIP-66). It is addressable, by the relay url.
+pub const RELAY_DISCOVERY_KIND: u32 = 30166;
+
+/// The relay monitors whose reports we take in, from the `relay_monitors` setting
+pub fn monitors() -> Vec<PublicKey> {
+    GLOBALS
+        .storage
+        .read_setting_relay_monitors()
+        .iter()
+        .filter_map(|hex| PublicKey::try_from_hex_string(hex, true).ok())
+        .collect()
+}
+
+/// Take in the reports of this relay monitor from now on
+pub fn add_monitor(pubkey: PublicKey) -> Result<(), Error> {
+    let mut monitors = GLOBALS.storage.read_setting_relay_monitors();
+    let hex = pubkey.as_hex_string();
+    if !monitors.contains(&hex) {
+        monitors.push(hex);
+    }
+    GLOBALS
+        .storage
+        .write_setting_relay_monitors(&monitors, None)
+}
+
+/// Stop trusting this relay monitor, and forget what it reported
+pub fn remove_monitor(pubkey: PublicKey) -> Result<(), Error> {
+    let mut monitors = GLOBALS.storage.read_setting_relay_monitors();
+    monitors.retain(|hex| *hex != pubkey.as_hex_string());
+    GLOBALS
+        .storage
+        .write_setting_relay_monitors(&monitors, None)?;
+
+    GLOBALS.storage.modify_all_relays(
+        |relay| relay.monitor_reports.retain(|r| r.monitor != pubkey),
+        None,
+    )
+}
+
+/// Keep what a relay monitor we trust reports about a relay on its relay record.
+/// Reports on relays we have no record of are passed over, as monitors report on
+/// thousands of them.
+pub(crate) fn process_relay_discovery_event(event: &Event) -> Result<(), Error> {
+    if !monitors().contains(&event.pubkey) {
+        return Ok(());
+    }
+
+    let url = match event
+        .tags
+        .iter()
+        .find(|t| t.tagname() == "d")
+        .and_then(|t| RelayUrl::try_from_str(t.value()).ok())
+    {
+        Some(url) => url,
+        None => return Ok(()),
+    };
+    if GLOBALS.storage.read_relay(&url, None)?.is_none() {
+        return Ok(());
+    }
+
+    let rtt = |name: &str| -> Option<u64> {
+        event
+            .tags
+            .iter()
+            .find(|t| t.tagname() == name)
+            .and_then(|t| t.value().parse::<u64>().ok())
+    };
+    let report = RelayMonitorReport {
+        monitor: event.pubkey,
+        seen_at: event.created_at.0.max(0) as u64,
+        rtt_open_ms: rtt("rtt-open"),
+        rtt_read_ms: rtt("rtt-read"),
+        rtt_write_ms: rtt("rtt-write"),
+    };
+
+    GLOBALS
+        .storage
+        .modify_relay(&url, |relay| relay.add_monitor_report(report.clone()), None)
+}
@@ -1,5 +1,7 @@
+use crate::blocked_relays::is_relay_blocked;
 use crate::error::Error;
 use crate::globals::GLOBALS;
+use crate::latency::{latency_bonus, LATENCY_TIE_BREAK_SCALE};
 use async_trait::async_trait;
 use gossip_relay_picker::RelayPickerHooks;
 use nostr_types::{PublicKey, RelayUrl, RelayUsage};
@@ -14,7 +16,7 @@ impl RelayPickerHooks for Hooks {
 
     /// Returns all relays available to be connected to
     fn get_all_relays(&self) -> Vec<RelayUrl> {
-        match GLOBALS.storage.filter_relays(|_| true) {
+        match GLOBALS.storage.filter_relays(|r| !is_relay_blocked(&r.url)) {
             Err(_) => vec![],
             Ok(vec) => vec.iter().map(|elem| elem.url.to_owned()).collect(),
         }
@@ -26,7 +28,16 @@ impl RelayPickerHooks for Hooks {
         pubkey: PublicKey,
         usage: RelayUsage,
     ) -> Result<Vec<(RelayUrl, u64)>, Error> {
-        GLOBALS.storage.get_best_relays(pubkey, usage)
+        let mut relays = GLOBALS.storage.get_best_relays(pubkey, usage)?;
+        relays.retain(|(url, _)| !is_relay_blocked(url));
+
+        // Dormant people get just their best relay, leaving the connection budget
+        // to people who post
+        if usage == RelayUsage::Outbox && crate::activity::is_dormant(pubkey) {
+            relays.truncate(1);
+        }
+
+        Ok(relays)
     }
 
     /// Is the relay currently connected?
@@ -48,20 +59,27 @@ impl RelayPickerHooks for Hooks {
     /// Returns the public keys of all the people followed
     // this API name has become difficult..
     fn get_followed_pubkeys(&self) -> Vec<PublicKey> {
-        // ..We actually want all the people subscribed, which is a bigger list
-        GLOBALS.people.get_subscribed_pubkeys()
+        // ..We actually want all the people subscribed live, which is a bigger list
+        GLOBALS.people.get_live_subscribed_pubkeys()
     }
 
     /// Adjusts the score for a given relay, perhaps based on relay-specific metrics
     fn adjust_score(&self, url: RelayUrl, score: u64) -> u64 {
+        if is_relay_blocked(&url) {
+            return 0;
+        }
         match GLOBALS.storage.read_relay(&url, None) {
             Err(_) => 0,
             Ok(Some(relay)) => {
-                let success_rate = relay.success_rate();
+                let success_rate = relay.effective_success_rate();
                 let rank = (relay.rank as f32 * (1.3 * success_rate)) as u64;
-                score * rank
+                if GLOBALS.storage.read_setting_relay_prefer_low_latency() && score * rank > 0 {
+                    score * rank * LATENCY_TIE_BREAK_SCALE + latency_bonus(&relay)
+                } else {
+                    score * rank * LATENCY_TIE_BREAK_SCALE
+                }
             }
-            Ok(None) => score,
+            Ok(None) => score * LATENCY_TIE_BREAK_SCALE,
         }
     }
 }
@@ -0,0 +1,160 @@
+use crate::blocked_relays::is_relay_blocked;
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use futures_util::sink::SinkExt;
+use futures_util::stream::StreamExt;
+use nostr_types::{ClientMessage, Event, Filter, RelayUrl, SubscriptionId, Unixtime};
+use std::time::Duration;
+use tungstenite::protocol::Message as WsMessage;
+
+/// No query returns more events than this, whatever cap is asked for
+pub const MAX_RAW_QUERY_RESULTS: usize = 5000;
+
+// Give up connecting after this long, or when the relay goes quiet this long
+const RAW_QUERY_TIMEOUT_SECS: u64 = 30;
+
+/// What a raw relay query has returned so far. Published in `GLOBALS.raw_query`
+/// as results arrive.
+#[derive(Debug, Clone)]
+pub struct RawQueryResults {
+    pub relay: RelayUrl,
+
+    pub filter: Filter,
+
+    /// If the returned events are also processed like events from any subscription
+    pub ingest: bool,
+
+    pub started: Unixtime,
+
+    /// None while the query is still running
+    pub finished: Option<Unixtime>,
+
+    /// The events, as the relay sent them (JSON), in arrival order
+    pub events: Vec<String>,
+
+    /// Everything else the relay sent (NOTICE, CLOSED, AUTH, ...), as JSON
+    pub messages: Vec<String>,
+
+    /// If we stopped because the cap was reached
+    pub capped: bool,
+
+    /// Why the query failed, if it did
+    pub error: Option<String>,
+}
+
+/// Send a filter to one relay over a connection of its own and collect what comes
+/// back, until EOSE, CLOSED, `cap` events (at most [MAX_RAW_QUERY_RESULTS]) or the
+/// relay going quiet. Nothing is ingested into storage or feeds unless `ingest` is
+/// set. Progress is published in `GLOBALS.raw_query`.
+pub async fn raw_query(
+    relay: RelayUrl,
+    mut filter: Filter,
+    ingest: bool,
+    cap: usize,
+) -> Result<(), Error> {
+    if is_relay_blocked(&relay) {
+        return Err(ErrorKind::General(format!("{} is blocked", relay)).into());
+    }
+
+    let cap = cap.clamp(1, MAX_RAW_QUERY_RESULTS);
+    filter.limit = Some(filter.limit.map(|l| l.min(cap)).unwrap_or(cap));
+
+    *GLOBALS.raw_query.write() = Some(RawQueryResults {
+        relay: relay.clone(),
+        filter: filter.clone(),
+        ingest,
+        started: Unixtime::now().unwrap(),
+        finished: None,
+        events: vec![],
+        messages: vec![],
+        capped: false,
+        error: None,
+    });
+
+    let result = run(&relay, filter, ingest, cap).await;
+
+    if let Some(results) = GLOBALS.raw_query.write().as_mut() {
+        results.finished = Some(Unixtime::now().unwrap());
+        if let Err(e) = &result {
+            results.error = Some(format!("{}", e));
+        }
+    }
+    result
+}
+
+async fn run(relay: &RelayUrl, filter: Filter, ingest: bool, cap: usize) -> Result<(), Error> {
+    let timeout = Duration::from_secs(RAW_QUERY_TIMEOUT_SECS);
+    let mut websocket = crate::relay_connect::connect(relay, timeout).await?;
+
+    let subid = SubscriptionId("raw_query".to_owned());
+    let wire = serde_json::to_string(&ClientMessage::Req(subid.clone(), vec![filter]))?;
+    websocket.send(WsMessage::Text(wire)).await?;
+
+    let mut count: usize = 0;
+    loop {
+        if GLOBALS.read_runstate.borrow().going_offline() {
+            break;
+        }
+
+        let message = match tokio::time::timeout(timeout, websocket.next()).await {
+            Err(_) => break, // quiet
+            Ok(None) => break,
+            Ok(Some(message)) => message?,
+        };
+        let text = match message {
+            WsMessage::Text(text) => text,
+            WsMessage::Close(_) => break,
+            _ => continue,
+        };
+
+        // Keep what the relay sent rather than our re-serialization of it
+        let value: serde_json::Value = serde_json::from_str(&text)?;
+        let label = value.get(0).and_then(|v| v.as_str()).unwrap_or("");
+        match label {
+            "EVENT" => {
+                let raw = value.get(2).cloned().unwrap_or(serde_json::Value::Null);
+                if ingest {
+                    match serde_json::from_value::<Event>(raw.clone()) {
+                        Ok(event) => {
+                            crate::process::process_new_event(
+                                &event,
+                                Some(relay.clone()),
+                                None,
+                                true,
+                                false,
+                            )
+                            .await?
+                        }
+                        Err(e) => tracing::warn!("Raw query event did not parse: {}", e),
+                    }
+                }
+                if let Some(results) = GLOBALS.raw_query.write().as_mut() {
+                    results.events.push(raw.to_string());
+                }
+                count += 1;
+                if count >= cap {
+                    if let Some(results) = GLOBALS.raw_query.write().as_mut() {
+                        results.capped = true;
+                    }
+                    break;
+                }
+            }
+            "EOSE" => break,
+            _ => {
+                if let Some(results) = GLOBALS.raw_query.write().as_mut() {
+                    results.messages.push(text.clone());
+                }
+                if label == "CLOSED" {
+                    break;
+                }
+            }
+        }
+    }
+
+    if let Ok(wire) = serde_json::to_string(&ClientMessage::Close(subid)) {
+        let _ = websocket.send(WsMessage::Text(wire)).await;
+    }
+    let _ = websocket.close(None).await;
+
+    Ok(())
+}
@@ -0,0 +1,335 @@
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use crate::relay::{Relay, RelayCapabilities};
+use crate::relay_connect::RelayWebSocket;
+use futures_util::sink::SinkExt;
+use futures_util::stream::StreamExt;
+use http::uri::{Parts, Scheme};
+use http::Uri;
+use nostr_types::{
+    ClientMessage, EventKind, Filter, PreEvent, PrivateKey, RelayInformationDocument, RelayUrl,
+    SubscriptionId, Tag, Unixtime,
+};
+use serde_json::Value;
+use std::time::{Duration, Instant};
+use tungstenite::protocol::Message as WsMessage;
+
+// Give up on any one step after this long
+const STEP_TIMEOUT_SECS: u64 = 10;
+
+// An application-specific data kind (NIP-78), which no feed shows
+const TEST_EVENT_KIND: u32 = 30078;
+
+// The test event expires after this long (NIP-40)
+const TEST_EVENT_LIFETIME_SECS: i64 = 600;
+
+/// Probe what a relay can do: connect, fetch its NIP-11 document, time a REQ
+/// until EOSE, write a disposable event, answer an AUTH challenge, and try a NIP-50
+/// search. Each step is recorded as it goes, so a relay failing one step still gets
+/// a report of the others.
+///
+/// The event is written with a throwaway key, not our identity. The AUTH step is
+/// only tried if our identity is unlocked (and we don't refuse to authenticate to
+/// this relay). Nothing is tried while offline, or with a relay we may not connect to.
+pub async fn test_relay(url: &RelayUrl) -> Result<RelayCapabilities, Error> {
+    let relay = match GLOBALS.storage.read_relay(url, None)? {
+        Some(relay) => relay,
+        None => Relay::new(url.clone()),
+    };
+    if GLOBALS.storage.read_setting_offline() {
+        return Err(ErrorKind::General("We are offline".to_owned()).into());
+    }
+    if !crate::relay_connect::may_connect(&relay) {
+        return Err(ErrorKind::General(format!(
+            "{} is blocked or not approved for connection",
+            url
+        ))
+        .into());
+    }
+
+    let mut report = RelayCapabilities {
+        tested_at: Unixtime::now().unwrap().0 as u64,
+        ..Default::default()
+    };
+
+    report.nip11 = fetch_nip11(url).await.is_ok();
+
+    let timeout = Duration::from_secs(STEP_TIMEOUT_SECS);
+    let start = Instant::now();
+    let websocket = match crate::relay_connect::connect(url, timeout).await {
+        Ok(websocket) => websocket,
+        Err(e) => {
+            report.error = Some(format!("{}", e));
+            return Ok(report);
+        }
+    };
+    report.connect_ms = Some(start.elapsed().as_millis() as u64);
+
+    let mut probe = Probe {
+        url: url.clone(),
+        websocket,
+        challenge: None,
+    };
+    if let Err(e) = probe.run(&mut report).await {
+        report.error = Some(format!("{}", e));
+    }
+    let _ = probe.websocket.close(None).await;
+
+    Ok(report)
+}
+
+async fn fetch_nip11(url: &RelayUrl) -> Result<RelayInformationDocument, Error> {
+    let uri: Uri = url.as_str().parse::<Uri>()?;
+    let mut parts: Parts = uri.into_parts();
+    parts.scheme = match parts.scheme.as_ref().map(|s| s.as_str()) {
+        Some("ws") => Some(Scheme::HTTP),
+        _ => Some(Scheme::HTTPS),
+    };
+    let uri = Uri::from_parts(parts)?;
+
+    let text = reqwest::Client::builder()
+        .timeout(Duration::from_secs(STEP_TIMEOUT_SECS))
+        .redirect(reqwest::redirect::Policy::none())
+        .build()?
+        .get(format!("{}", uri))
+        .header("Accept", "application/nostr+json")
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    Ok(serde_json::from_str(&text)?)
+}
+
+// A connection to the relay under test
+struct Probe {
+    url: RelayUrl,
+    websocket: RelayWebSocket,
+
+    // The last AUTH challenge the relay sent
+    challenge: Option<String>,
+}
+
+impl Probe {
+    async fn run(&mut self, report: &mut RelayCapabilities) -> Result<(), Error> {
+        // REQ round trip. Ask for nothing, we only care about the answer.
+        let mut filter = Filter::new();
+        filter.add_event_kind(EventKind::Metadata);
+        filter.limit = Some(0);
+        let start = Instant::now();
+        self.send(&ClientMessage::Req(Self::subid("req"), vec![filter]))
+            .await?;
+        if let Some(reply) = self.await_sub("req").await? {
+            if reply.label == "EOSE" {
+                report.req_ms = Some(start.elapsed().as_millis() as u64);
+            }
+            if reply.auth_required() {
+                report.auth_required = true;
+            }
+        }
+        self.close_sub("req").await;
+
+        // AUTH, if the relay asked for it by now
+        report.auth_challenged = self.challenge.is_some();
+        let unlocked = GLOBALS.identity.is_unlocked_for_auth(&self.url);
+        let allowed = GLOBALS
+            .storage
+            .read_relay(&self.url, None)?
+            .map(|r| r.allow_auth != Some(false))
+            .unwrap_or(true);
+        if let Some(challenge) = self.challenge.clone() {
+            if unlocked && allowed {
+                report.auth_accepted = self.authenticate(&challenge).await?;
+            }
+        }
+
+        // A disposable write
+        let (accepted, message) = self.write_test_event().await?;
+        if !accepted && message.starts_with("auth-required:") {
+            report.auth_required = true;
+        }
+        report.write_accepted = Some(accepted);
+        report.write_message = Some(message);
+        report.auth_challenged |= self.challenge.is_some();
+
+        // NIP-50 search for something nothing matches. Relays without search either
+        // refuse the filter or ignore the search and return events.
+        let mut filter = Filter::new();
+        filter.add_event_kind(EventKind::TextNote);
+        filter.limit = Some(1);
+        let mut filter = serde_json::to_value(filter)?;
+        let nonsense = format!("gossip-relay-test-{:x}", rand::random::<u64>());
+        filter["search"] = Value::String(nonsense);
+        let wire = serde_json::to_string(&serde_json::json!(["REQ", "search", filter]))?;
+        self.websocket.send(WsMessage::Text(wire)).await?;
+        let mut found = false;
+        report.search = loop {
+            match self.await_sub("search").await? {
+                None => break None,
+                Some(reply) if reply.label == "EVENT" => found = true,
+                Some(reply) if reply.label == "EOSE" => break Some(!found),
+                Some(reply) => {
+                    if reply.auth_required() {
+                        report.auth_required = true;
+                        break None;
+                    }
+                    break Some(false);
+                }
+            }
+        };
+        self.close_sub("search").await;
+
+        Ok(())
+    }
+
+    fn subid(name: &str) -> SubscriptionId {
+        SubscriptionId(name.to_owned())
+    }
+
+    async fn send(&mut self, message: &ClientMessage) -> Result<(), Error> {
+        let wire = serde_json::to_string(message)?;
+        self.websocket.send(WsMessage::Text(wire)).await?;
+        Ok(())
+    }
+
+    async fn close_sub(&mut self, name: &str) {
+        let _ = self.send(&ClientMessage::Close(Self::subid(name))).await;
+    }
+
+    // The next message about a subscription (EVENT, EOSE or CLOSED), or None if the
+    // relay goes quiet or away. AUTH challenges are noted on the way.
+    async fn await_sub(&mut self, name: &str) -> Result<Option<Reply>, Error> {
+        self.await_reply(|label, value| {
+            matches!(label, "EVENT" | "EOSE" | "CLOSED")
+                && value.get(1).and_then(|v| v.as_str()) == Some(name)
+        })
+        .await
+    }
+
+    // The OK for this event id, or None if the relay goes quiet or away
+    async fn await_ok(&mut self, id: &str) -> Result<Option<Reply>, Error> {
+        self.await_reply(|label, value| {
+            label == "OK" && value.get(1).and_then(|v| v.as_str()) == Some(id)
+        })
+        .await
+    }
+
+    async fn await_reply<F>(&mut self, wanted: F) -> Result<Option<Reply>, Error>
+    where
+        F: Fn(&str, &Value) -> bool,
+    {
+        let timeout = Duration::from_secs(STEP_TIMEOUT_SECS);
+        loop {
+            let message = match tokio::time::timeout(timeout, self.websocket.next()).await {
+                Err(_) => return Ok(None),
+                Ok(None) => return Ok(None),
+                Ok(Some(message)) => message?,
+            };
+            let text = match message {
+                WsMessage::Text(text) => text,
+                WsMessage::Close(_) => return Ok(None),
+                _ => continue,
+            };
+            let value: Value = match serde_json::from_str(&text) {
+                Ok(value) => value,
+                Err(_) => continue,
+            };
+            let label = value.get(0).and_then(|v| v.as_str()).unwrap_or("");
+            if label == "AUTH" {
+                if let Some(challenge) = value.get(1).and_then(|v| v.as_str()) {
+                    self.challenge = Some(challenge.to_owned());
+                }
+                continue;
+            }
+            if wanted(label, &value) {
+                return Ok(Some(Reply {
+                    label: label.to_owned(),
+                    value,
+                }));
+            }
+        }
+    }
+
+    // Answer the challenge. None if the relay doesn't say how it went.
+    async fn authenticate(&mut self, challenge: &str) -> Result<Option<bool>, Error> {
+        let pubkey = match GLOBALS
+            .identity
+            .relay_auth_public_key(&self.url)
+            .or_else(|| GLOBALS.identity.public_key())
+        {
+            Some(pk) => pk,
+            None => return Ok(None),
+        };
+        let pre_event = PreEvent {
+            pubkey,
+            created_at: Unixtime::now().unwrap(),
+            kind: EventKind::Auth,
+            tags: vec![
+                Tag::new(&["relay", self.url.as_str()]),
+                Tag::new(&["challenge", challenge]),
+            ],
+            content: "".to_string(),
+        };
+        let event = GLOBALS
+            .identity
+            .sign_auth_event(&self.url, pre_event)
+            .await?;
+        let id = event.id.as_hex_string();
+        self.send(&ClientMessage::Auth(Box::new(event))).await?;
+        Ok(self.await_ok(&id).await?.map(|reply| reply.ok()))
+    }
+
+    // Post an expiring event of a kind no feed shows, signed by a throwaway key, and
+    // say if it was accepted and what the relay said about it
+    async fn write_test_event(&mut self) -> Result<(bool, String), Error> {
+        let key = PrivateKey::generate();
+        let now = Unixtime::now().unwrap();
+        let pre_event = PreEvent {
+            pubkey: key.public_key(),
+            created_at: now,
+            kind: EventKind::from(TEST_EVENT_KIND),
+            tags: vec![
+                Tag::new_identifier("gossip-relay-test".to_owned()),
+                Tag::new(&[
+                    "expiration",
+                    &format!("{}", now.0 + TEST_EVENT_LIFETIME_SECS),
+                ]),
+            ],
+            content: "".to_string(),
+        };
+        let event = crate::nwc::sign_with_key(&key, pre_event)?;
+        let id = event.id.as_hex_string();
+        self.send(&ClientMessage::Event(Box::new(event))).await?;
+        Ok(match self.await_ok(&id).await? {
+            Some(reply) => (reply.ok(), reply.message()),
+            None => (false, "No answer".to_owned()),
+        })
+    }
+}
+
+// A message from the relay, as JSON
+struct Reply {
+    label: String,
+    value: Value,
+}
+
+impl Reply {
+    // If an OK says the relay accepted
+    fn ok(&self) -> bool {
+        self.value.get(2).and_then(|v| v.as_bool()).unwrap_or(false)
+    }
+
+    // The message of an OK or CLOSED
+    fn message(&self) -> String {
+        let at = if self.label == "OK" { 3 } else { 2 };
+        self.value
+            .get(at)
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_owned()
+    }
+
+    fn auth_required(&self) -> bool {
+        self.label == "CLOSED" && self.message().starts_with("auth-required:")
+    }
+}
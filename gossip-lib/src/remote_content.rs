@@ -0,0 +1,206 @@
+use crate::dm_policy::{is_follow_of_follow, is_follow_of_follow_quick};
+use crate::globals::GLOBALS;
+use crate::people::PersonList;
+use dashmap::DashMap;
+use nostr_types::{PublicKey, Url};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+// How long the trust tier of an author of remote content is remembered
+const TIER_CACHE_SECS: u64 = 60;
+
+lazy_static! {
+    static ref AUTHOR_TIERS: DashMap<PublicKey, (Instant, TrustTier)> = DashMap::new();
+}
+
+/// How far someone is from us in our web of trust, nearest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TrustTier {
+    /// Us, and people we follow
+    Followed,
+
+    /// People followed by people we follow
+    SecondDegree,
+
+    /// Everyone else
+    Stranger,
+}
+
+impl TrustTier {
+    pub fn all() -> &'static [TrustTier] {
+        &[
+            TrustTier::Followed,
+            TrustTier::SecondDegree,
+            TrustTier::Stranger,
+        ]
+    }
+
+    /// The value stored in settings that take a tier, such as `notify_min_trust`
+    pub fn as_setting(&self) -> &'static str {
+        match self {
+            TrustTier::Followed => "followed",
+            TrustTier::SecondDegree => "second_degree",
+            TrustTier::Stranger => "everyone",
+        }
+    }
+
+    pub fn from_setting(s: &str) -> TrustTier {
+        match s {
+            "followed" => TrustTier::Followed,
+            "second_degree" => TrustTier::SecondDegree,
+            _ => TrustTier::Stranger,
+        }
+    }
+
+    /// The tier this person is in
+    pub fn of(pubkey: &PublicKey) -> TrustTier {
+        if Self::is_followed(pubkey) {
+            TrustTier::Followed
+        } else if is_follow_of_follow(pubkey) {
+            TrustTier::SecondDegree
+        } else {
+            TrustTier::Stranger
+        }
+    }
+
+    // The tier this person is in, remembered for a while and never computing the
+    // follows-of-follows on this thread. Until they are known, people who might be
+    // in the second degree count as strangers (and this is not remembered).
+    fn of_cached(pubkey: &PublicKey) -> TrustTier {
+        if let Some(entry) = AUTHOR_TIERS.get(pubkey) {
+            let (when, tier) = *entry;
+            if when.elapsed() < Duration::from_secs(TIER_CACHE_SECS) {
+                return tier;
+            }
+        }
+
+        let tier = if Self::is_followed(pubkey) {
+            TrustTier::Followed
+        } else {
+            match is_follow_of_follow_quick(pubkey) {
+                Some(true) => TrustTier::SecondDegree,
+                Some(false) => TrustTier::Stranger,
+                None => return TrustTier::Stranger,
+            }
+        };
+        AUTHOR_TIERS.insert(*pubkey, (Instant::now(), tier));
+        tier
+    }
+
+    fn is_followed(pubkey: &PublicKey) -> bool {
+        GLOBALS.identity.public_key().as_ref() == Some(pubkey)
+            || GLOBALS
+                .people
+                .is_person_in_list(pubkey, PersonList::Followed)
+    }
+}
+
+impl fmt::Display for TrustTier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrustTier::Followed => write!(f, "People I follow"),
+            TrustTier::SecondDegree => write!(f, "People they follow"),
+            TrustTier::Stranger => write!(f, "Everyone else"),
+        }
+    }
+}
+
+/// Whether remote content (media, link previews) that someone posts is fetched.
+/// Fetching it tells its host our IP address and that we looked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemoteContent {
+    /// Fetched and shown as soon as it is displayed
+    Auto,
+
+    /// Fetched once the user clicks on it
+    ClickToLoad,
+
+    /// Never fetched; it can still be opened in a browser
+    Never,
+}
+
+impl RemoteContent {
+    pub fn all() -> &'static [RemoteContent] {
+        &[
+            RemoteContent::Auto,
+            RemoteContent::ClickToLoad,
+            RemoteContent::Never,
+        ]
+    }
+
+    /// The value stored in the `remote_content_*` settings
+    pub fn as_setting(&self) -> &'static str {
+        match self {
+            RemoteContent::Auto => "auto",
+            RemoteContent::ClickToLoad => "click",
+            RemoteContent::Never => "never",
+        }
+    }
+
+    pub fn from_setting(s: &str) -> RemoteContent {
+        match s {
+            "auto" => RemoteContent::Auto,
+            "never" => RemoteContent::Never,
+            _ => RemoteContent::ClickToLoad,
+        }
+    }
+
+    /// The setting for a tier
+    pub fn for_tier(tier: TrustTier) -> RemoteContent {
+        RemoteContent::from_setting(&match tier {
+            TrustTier::Followed => GLOBALS.storage.read_setting_remote_content_followed(),
+            TrustTier::SecondDegree => GLOBALS.storage.read_setting_remote_content_second_degree(),
+            TrustTier::Stranger => GLOBALS.storage.read_setting_remote_content_strangers(),
+        })
+    }
+
+    /// Whether content shown in this state is fetched, given whether the user has
+    /// clicked on it
+    pub fn may_load(&self, clicked: bool) -> bool {
+        match self {
+            RemoteContent::Auto => true,
+            RemoteContent::ClickToLoad => clicked,
+            RemoteContent::Never => false,
+        }
+    }
+}
+
+impl fmt::Display for RemoteContent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RemoteContent::Auto => write!(f, "Load automatically"),
+            RemoteContent::ClickToLoad => write!(f, "Click to load"),
+            RemoteContent::Never => write!(f, "Never load"),
+        }
+    }
+}
+
+/// Whether the remote content someone posts is fetched. Turning off the
+/// `show_media` setting makes everything at most click-to-load, and DMs are always
+/// click-to-load, as the URL might have been made just to learn our IP address.
+///
+/// The author's tier is remembered for a while, so this is cheap enough to call
+/// every frame.
+pub fn remote_content_policy(author: &PublicKey, direct_message: bool) -> RemoteContent {
+    let policy = RemoteContent::for_tier(TrustTier::of_cached(author));
+    if policy == RemoteContent::Auto
+        && (direct_message || !GLOBALS.storage.read_setting_show_media())
+    {
+        RemoteContent::ClickToLoad
+    } else {
+        policy
+    }
+}
+
+/// Whether remote content at this URL, shown under this policy, may be fetched given
+/// whether the user has clicked on it. The answer is passed on to the fetcher, which
+/// then won't fetch it for anybody else either until it is allowed.
+pub fn may_fetch(url: &Url, policy: RemoteContent, clicked: bool) -> bool {
+    let allowed = policy.may_load(clicked);
+    if allowed {
+        GLOBALS.fetcher.release(url);
+    } else {
+        GLOBALS.fetcher.withhold(url);
+    }
+    allowed
+}
@@ -0,0 +1,388 @@
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use nostr_types::{Id, Unixtime};
+use regex::Regex;
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+use tokio::task;
+use tokio::time::Instant;
+
+/// RssEntry type, aliased to the latest version
+pub type RssEntry = crate::storage::types::RssEntry1;
+
+// Don't poll more often than this, whatever the setting says
+const MIN_POLL_INTERVAL_MINS: u64 = 5;
+
+lazy_static! {
+    static ref ITEM_RE: Regex = Regex::new(r"(?s)<item\b[^>]*>(.*?)</item>").unwrap();
+    static ref ENTRY_RE: Regex = Regex::new(r"(?s)<entry\b[^>]*>(.*?)</entry>").unwrap();
+    static ref ATOM_LINK_RE: Regex = Regex::new(r#"<link\b([^>]*)/?>"#).unwrap();
+    static ref HREF_RE: Regex = Regex::new(r#"href\s*=\s*["']([^"']*)["']"#).unwrap();
+    static ref HTML_TAG_RE: Regex = Regex::new(r"(?s)<[^>]*>").unwrap();
+}
+
+/// Add an RSS or Atom feed URL to poll
+pub fn add_feed(url: &str) -> Result<(), Error> {
+    let url = url.trim().to_owned();
+    if url::Url::parse(&url).is_err() {
+        return Err(ErrorKind::General(format!("Invalid feed URL: {}", url)).into());
+    }
+    let mut feeds = GLOBALS.storage.read_setting_rss_feeds();
+    if !feeds.contains(&url) {
+        feeds.push(url);
+        GLOBALS.storage.write_setting_rss_feeds(&feeds, None)?;
+    }
+    Ok(())
+}
+
+/// Stop polling a feed, and remove the entries we got from it
+pub fn remove_feed(url: &str) -> Result<(), Error> {
+    let mut feeds = GLOBALS.storage.read_setting_rss_feeds();
+    feeds.retain(|f| f != url);
+    GLOBALS.storage.write_setting_rss_feeds(&feeds, None)?;
+    GLOBALS.storage.delete_rss_entries_of_feed(url, None)?;
+    Ok(())
+}
+
+/// All RSS entries, newest first
+pub fn rss_feed() -> Result<Vec<RssEntry>, Error> {
+    let mut entries = GLOBALS.storage.read_all_rss_entries()?;
+    entries.sort_by(|a, b| b.published.cmp(&a.published).then(b.id.cmp(&a.id)));
+    Ok(entries)
+}
+
+/// Poll every configured feed now, saving new entries. Returns how many were new.
+pub async fn poll_feeds() -> Result<usize, Error> {
+    if !GLOBALS.storage.read_setting_rss_enabled() || GLOBALS.storage.read_setting_offline() {
+        return Ok(0);
+    }
+
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::new(30, 0))
+        .gzip(true)
+        .brotli(true)
+        .deflate(true)
+        .build()?;
+
+    let mut count: usize = 0;
+    for feed_url in GLOBALS.storage.read_setting_rss_feeds().iter() {
+        let body = match client.get(feed_url).send().await {
+            Ok(response) => match response.text().await {
+                Ok(body) => body,
+                Err(e) => {
+                    tracing::warn!("RSS feed {}: {}", feed_url, e);
+                    continue;
+                }
+            },
+            Err(e) => {
+                tracing::warn!("RSS feed {}: {}", feed_url, e);
+                continue;
+            }
+        };
+
+        for entry in parse_feed(feed_url, &body) {
+            if !GLOBALS.storage.has_rss_entry(entry.id)? {
+                GLOBALS.storage.write_rss_entry(&entry, None)?;
+                count += 1;
+            }
+        }
+    }
+
+    if count > 0 {
+        tracing::info!("RSS: {} new entries", count);
+    }
+
+    Ok(count)
+}
+
+pub(crate) fn start() {
+    tracing::info!("RSS poller startup");
+
+    task::spawn(async {
+        let mut read_runstate = GLOBALS.read_runstate.clone();
+        read_runstate.mark_unchanged();
+        if read_runstate.borrow().going_offline() {
+            return;
+        }
+
+        // Poll shortly after startup
+        let sleep = tokio::time::sleep(Duration::from_secs(30));
+        tokio::pin!(sleep);
+
+        loop {
+            tokio::select! {
+                _ = &mut sleep => {
+                    let mins = GLOBALS
+                        .storage
+                        .read_setting_rss_poll_interval_mins()
+                        .max(MIN_POLL_INTERVAL_MINS);
+                    sleep.as_mut().reset(Instant::now() + Duration::from_secs(mins * 60));
+                },
+                _ = read_runstate.wait_for(|runstate| runstate.going_offline()) => break,
+            }
+
+            if let Err(e) = poll_feeds().await {
+                tracing::error!("{}", e);
+            }
+        }
+
+        tracing::info!("RSS poller shutdown");
+    });
+}
+
+/// Parse an RSS 2.0 or Atom document into entries
+fn parse_feed(feed_url: &str, body: &str) -> Vec<RssEntry> {
+    let atom = !body.contains("<item") && body.contains("<entry");
+    let (blocks_re, first_block) = if atom {
+        (&*ENTRY_RE, body.find("<entry"))
+    } else {
+        (&*ITEM_RE, body.find("<item"))
+    };
+
+    let header = &body[..first_block.unwrap_or(body.len())];
+    let feed_title = element_text(header, "title").unwrap_or_else(|| feed_url.to_owned());
+
+    let now = Unixtime::now().unwrap();
+    let mut output: Vec<RssEntry> = Vec::new();
+
+    for captures in blocks_re.captures_iter(body) {
+        let block = &captures[1];
+
+        let title = element_text(block, "title").unwrap_or_default();
+        let link = if atom {
+            atom_link(block)
+        } else {
+            element_text(block, "link")
+        }
+        .unwrap_or_default();
+        let guid =
+            element_text(block, if atom { "id" } else { "guid" }).unwrap_or_else(|| link.clone());
+        if guid.is_empty() && title.is_empty() {
+            continue;
+        }
+
+        let summary = ["description", "summary", "content"]
+            .iter()
+            .find_map(|name| element_text(block, name))
+            .map(|s| strip_html(&s))
+            .unwrap_or_default();
+
+        let published = ["pubDate", "published", "updated", "dc:date"]
+            .iter()
+            .find_map(|name| element_text(block, name))
+            .and_then(|s| parse_date(&s))
+            .map(Unixtime)
+            .unwrap_or(now);
+
+        output.push(RssEntry {
+            id: entry_id(feed_url, if guid.is_empty() { &title } else { &guid }),
+            feed_url: feed_url.to_owned(),
+            feed_title: feed_title.clone(),
+            title,
+            link,
+            summary,
+            published,
+        });
+    }
+
+    output
+}
+
+// Deterministic id so the same entry polled twice is only stored once
+fn entry_id(feed_url: &str, guid: &str) -> Id {
+    let mut hasher = Sha256::new();
+    hasher.update(feed_url.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(guid.as_bytes());
+    Id(hasher.finalize().into())
+}
+
+// The text content of the first element with this name
+fn element_text(block: &str, name: &str) -> Option<String> {
+    let open = format!("<{}", name);
+    let close = format!("</{}>", name);
+    let mut search_from = 0;
+    while let Some(pos) = block[search_from..].find(&open) {
+        let start = search_from + pos;
+        let after_name = start + open.len();
+        search_from = after_name;
+
+        // Make sure this is the whole element name (e.g. <link> not <linkset>)
+        match block[after_name..].chars().next() {
+            Some('>') | Some(' ') | Some('\t') | Some('\n') | Some('\r') | Some('/') => (),
+            _ => continue,
+        }
+
+        let tag_end = after_name + block[after_name..].find('>')?;
+        if block[..tag_end].ends_with('/') {
+            return None; // self-closing, no text
+        }
+        let content_start = tag_end + 1;
+        let content_end = content_start + block[content_start..].find(&close)?;
+        let text = decode_text(&block[content_start..content_end]);
+        return if text.is_empty() { None } else { Some(text) };
+    }
+    None
+}
+
+// Atom links are in href attributes; prefer rel="alternate" (or no rel)
+fn atom_link(block: &str) -> Option<String> {
+    let mut fallback: Option<String> = None;
+    for captures in ATOM_LINK_RE.captures_iter(block) {
+        let attrs = &captures[1];
+        let href = match HREF_RE.captures(attrs) {
+            Some(c) => decode_entities(&c[1]),
+            None => continue,
+        };
+        if !attrs.contains("rel=") || attrs.contains("alternate") {
+            return Some(href);
+        }
+        if fallback.is_none() {
+            fallback = Some(href);
+        }
+    }
+    fallback
+}
+
+fn decode_text(raw: &str) -> String {
+    let raw = raw.trim();
+    if let Some(inner) = raw
+        .strip_prefix("<![CDATA[")
+        .and_then(|s| s.strip_suffix("]]>"))
+    {
+        inner.trim().to_owned()
+    } else {
+        decode_entities(raw)
+    }
+}
+
+fn decode_entities(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+fn strip_html(s: &str) -> String {
+    let text = HTML_TAG_RE.replace_all(s, " ");
+    let text = decode_entities(&text);
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+// Parse RFC 3339 (Atom) or RFC 2822 (RSS) dates into unix seconds
+fn parse_date(s: &str) -> Option<i64> {
+    let s = s.trim();
+    if s.len() >= 19 && s.as_bytes()[4] == b'-' {
+        // 2006-01-02T15:04:05[.sss](Z|+hh:mm)
+        let year: i64 = s.get(0..4)?.parse().ok()?;
+        let month: i64 = s.get(5..7)?.parse().ok()?;
+        let day: i64 = s.get(8..10)?.parse().ok()?;
+        let hour: i64 = s.get(11..13)?.parse().ok()?;
+        let min: i64 = s.get(14..16)?.parse().ok()?;
+        let sec: i64 = s.get(17..19)?.parse().ok()?;
+        let rest = s[19..].trim_start_matches(|c: char| c == '.' || c.is_ascii_digit());
+        let offset = parse_offset(rest)?;
+        Some(civil_to_unix(year, month, day) + hour * 3600 + min * 60 + sec - offset)
+    } else {
+        // Mon, 02 Jan 2006 15:04:05 +0000
+        let s = match s.find(',') {
+            Some(i) => &s[i + 1..],
+            None => s,
+        };
+        let parts: Vec<&str> = s.split_whitespace().collect();
+        if parts.len() < 4 {
+            return None;
+        }
+        let day: i64 = parts[0].parse().ok()?;
+        let month: i64 = [
+            "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+        ]
+        .iter()
+        .position(|m| parts[1].to_lowercase().starts_with(m))? as i64
+            + 1;
+        let mut year: i64 = parts[2].parse().ok()?;
+        if year < 100 {
+            year += 2000;
+        }
+        let hms: Vec<i64> = parts[3]
+            .split(':')
+            .map(|p| p.parse().unwrap_or(0))
+            .collect();
+        let (hour, min, sec) = (
+            *hms.first()?,
+            *hms.get(1).unwrap_or(&0),
+            *hms.get(2).unwrap_or(&0),
+        );
+        let offset = parse_offset(parts.get(4).unwrap_or(&"Z"))?;
+        Some(civil_to_unix(year, month, day) + hour * 3600 + min * 60 + sec - offset)
+    }
+}
+
+// Timezone offset in seconds east of UTC
+fn parse_offset(s: &str) -> Option<i64> {
+    let s = s.trim();
+    match s {
+        "" | "Z" | "z" | "GMT" | "UT" | "UTC" => return Some(0),
+        _ => (),
+    }
+    let sign = match s.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return Some(0), // named timezones we don't know, treat as UTC
+    };
+    let digits: String = s[1..].chars().filter(|c| c.is_ascii_digit()).collect();
+    if digits.len() != 4 {
+        return None;
+    }
+    let hours: i64 = digits[0..2].parse().ok()?;
+    let mins: i64 = digits[2..4].parse().ok()?;
+    Some(sign * (hours * 3600 + mins * 60))
+}
+
+// Days since the unix epoch of a civil date, times seconds per day
+fn civil_to_unix(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    (era * 146097 + doe - 719468) * 86400
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(parse_date("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(parse_date("2024-02-29T12:30:00+01:00"), Some(1709206200));
+        assert_eq!(
+            parse_date("Thu, 29 Feb 2024 11:30:00 GMT"),
+            Some(1709206200)
+        );
+        assert_eq!(
+            parse_date("Thu, 29 Feb 2024 06:30:00 -0500"),
+            Some(1709206200)
+        );
+        assert_eq!(parse_date("not a date"), None);
+    }
+
+    #[test]
+    fn test_element_text() {
+        let block =
+            "<title><![CDATA[Hello & goodbye]]></title><link>https://a.b/?x=1&amp;y=2</link>";
+        assert_eq!(
+            element_text(block, "title"),
+            Some("Hello & goodbye".to_owned())
+        );
+        assert_eq!(
+            element_text(block, "link"),
+            Some("https://a.b/?x=1&y=2".to_owned())
+        );
+        assert_eq!(element_text(block, "guid"), None);
+    }
+}
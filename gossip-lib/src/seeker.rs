@@ -59,6 +59,11 @@ impl Seeker {
         }
     }
 
+    /// Stop seeking everything, as when switching profiles
+    pub(crate) fn clear(&self) {
+        self.events.clear();
+    }
+
     fn storage_get_relays(author: PublicKey) -> Result<Vec<RelayUrl>, Error> {
         Ok(GLOBALS
             .storage
@@ -220,7 +225,7 @@ impl Seeker {
                     marker: None,
                 };
                 while let Some(event) = GLOBALS.storage.read_event_reference(&eref)? {
-                    if let Some(parent_eref) = event.replies_to() {
+                    if let Some(parent_eref) = crate::nip10::replies_to(&event) {
                         eref = parent_eref;
                         continue;
                     } else {
@@ -0,0 +1,201 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::misc::Private;
+use crate::people::{PersonList, PersonListMetadata, SmartList};
+use nostr_types::{EventKind, Filter, PublicKey, PublicKeyHex, Unixtime};
+use parking_lot::Mutex;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+// How far back interactions and follows count
+const WINDOW_DAYS: u64 = 30;
+
+// How many people the most-interacted list holds
+const MOST_INTERACTED_LEN: usize = 50;
+
+// How often the smart lists are regenerated
+const REGENERATE_SECS: i64 = 60 * 60;
+
+lazy_static! {
+    // When the smart lists were last regenerated (in memory, so they are regenerated
+    // at startup)
+    static ref LAST_REGENERATED: Mutex<Option<Unixtime>> = Mutex::new(None);
+}
+
+/// When the smart lists were last regenerated
+pub fn last_regenerated() -> Option<Unixtime> {
+    *LAST_REGENERATED.lock()
+}
+
+// The maintenance scheduler calls this every pass
+pub(crate) fn regenerate_if_due() -> Result<(), Error> {
+    let now = Unixtime::now().unwrap();
+    if let Some(last) = last_regenerated() {
+        if now.0 - last.0 < REGENERATE_SECS {
+            return Ok(());
+        }
+    }
+    *LAST_REGENERATED.lock() = Some(now);
+    regenerate()
+}
+
+/// Recompute the members of the smart lists from local data. With the `smart_lists`
+/// setting off, the smart lists are removed instead.
+pub fn regenerate() -> Result<(), Error> {
+    let enabled = GLOBALS.storage.read_setting_smart_lists();
+    let my_pubkey = GLOBALS.identity.public_key();
+
+    for smart in SmartList::all() {
+        let existing = GLOBALS.storage.find_person_list_by_dtag(smart.dtag())?;
+        if let Some((_, metadata)) = &existing {
+            if metadata.smart != Some(smart) {
+                tracing::warn!(
+                    "A list with d-tag {} is in the way of the {} smart list",
+                    smart.dtag(),
+                    smart.as_string()
+                );
+                continue;
+            }
+        }
+
+        let my_pubkey = match (enabled, my_pubkey) {
+            (true, Some(pk)) => pk,
+            _ => {
+                if let Some((list, _)) = existing {
+                    GLOBALS.storage.deallocate_person_list(list, None)?;
+                }
+                continue;
+            }
+        };
+
+        let list = match existing {
+            Some((list, _)) => list,
+            None => {
+                let metadata = PersonListMetadata {
+                    dtag: smart.dtag().to_owned(),
+                    title: smart.as_string(),
+                    private: Private(true),
+                    smart: Some(smart),
+                    ..Default::default()
+                };
+                GLOBALS.storage.allocate_person_list(&metadata, None)?
+            }
+        };
+
+        let members = match smart {
+            SmartList::MostInteracted => most_interacted(my_pubkey)?,
+            SmartList::RecentlyFollowed => recently_followed()?,
+            SmartList::Mutuals => mutuals(my_pubkey)?,
+        };
+        set_members(list, members)?;
+    }
+
+    Ok(())
+}
+
+// Bring the list to exactly these members, leaving alone those already there
+fn set_members(list: PersonList, members: HashSet<PublicKey>) -> Result<(), Error> {
+    let current: HashSet<PublicKey> = GLOBALS
+        .storage
+        .get_people_in_list(list)?
+        .drain(..)
+        .map(|(pk, _)| pk)
+        .collect();
+    if current == members {
+        return Ok(());
+    }
+
+    let mut txn = GLOBALS.storage.get_write_txn()?;
+    for pubkey in current.difference(&members) {
+        GLOBALS
+            .storage
+            .remove_person_from_list(pubkey, list, Some(&mut *txn))?;
+    }
+    for pubkey in members.difference(&current) {
+        GLOBALS
+            .storage
+            .add_person_to_list(pubkey, list, Private(true), Some(&mut *txn))?;
+    }
+    txn.commit()?;
+    Ok(())
+}
+
+fn window_start() -> Unixtime {
+    Unixtime::now().unwrap() - Duration::from_secs(WINDOW_DAYS * 24 * 3600)
+}
+
+// The people we tagged in our events, and who tagged us in theirs, most often
+fn most_interacted(my_pubkey: PublicKey) -> Result<HashSet<PublicKey>, Error> {
+    let since = window_start();
+    let mut counts: HashMap<PublicKey, usize> = HashMap::new();
+
+    let mut filter = Filter::new();
+    filter.add_author(&my_pubkey.into());
+    filter.kinds = vec![
+        EventKind::TextNote,
+        EventKind::Reaction,
+        EventKind::Repost,
+        EventKind::GenericRepost,
+        EventKind::EncryptedDirectMessage,
+    ];
+    filter.since = Some(since);
+    for event in GLOBALS.storage.find_events_by_filter(&filter, |_| true)? {
+        for (pubkey, _, _) in event.people() {
+            if pubkey != my_pubkey {
+                *counts.entry(pubkey).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let my_hex: PublicKeyHex = my_pubkey.into();
+    for event in GLOBALS.storage.find_tagged_events(
+        "p",
+        Some(my_hex.as_str()),
+        |e| e.created_at >= since && e.pubkey != my_pubkey,
+        false,
+    )? {
+        *counts.entry(event.pubkey).or_insert(0) += 1;
+    }
+
+    let muted: HashSet<PublicKey> = GLOBALS
+        .storage
+        .get_people_in_list(PersonList::Muted)?
+        .drain(..)
+        .map(|(pk, _)| pk)
+        .collect();
+
+    let mut ranked: Vec<(PublicKey, usize)> = counts.drain().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1));
+    Ok(ranked
+        .drain(..)
+        .filter(|(pubkey, _)| !muted.contains(pubkey))
+        .take(MOST_INTERACTED_LEN)
+        .map(|(pubkey, _)| pubkey)
+        .collect())
+}
+
+fn recently_followed() -> Result<HashSet<PublicKey>, Error> {
+    Ok(GLOBALS
+        .people
+        .followed_since(window_start())?
+        .drain(..)
+        .map(|p| p.pubkey)
+        .collect())
+}
+
+// The people we follow whose latest contact list has us in it
+fn mutuals(my_pubkey: PublicKey) -> Result<HashSet<PublicKey>, Error> {
+    let mut set: HashSet<PublicKey> = HashSet::new();
+    for (followed, _) in GLOBALS.storage.get_people_in_list(PersonList::Followed)? {
+        if let Some(event) =
+            GLOBALS
+                .storage
+                .get_replaceable_event(EventKind::ContactList, followed, "")?
+        {
+            if event.people().iter().any(|(pk, _, _)| *pk == my_pubkey) {
+                set.insert(followed);
+            }
+        }
+    }
+    Ok(set)
+}
@@ -0,0 +1,93 @@
+use crate::bus::BusMessage;
+use crate::error::{Error, ErrorKind};
+use crate::storage::Storage;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// A frontend's own key-value store, kept inside the gossip database.
+///
+/// Each frontend picks a namespace (e.g. "gossip-egui") so that several frontends can
+/// share a database without stepping on each other. Values are stored as JSON. Every
+/// change is announced on the bus as [BusMessage::AppDataChanged].
+///
+/// Get one with [Storage::app_data].
+pub struct AppData<'a> {
+    storage: &'a Storage,
+    namespace: String,
+}
+
+impl<'a> AppData<'a> {
+    pub(crate) fn new(storage: &'a Storage, namespace: &str) -> Result<AppData<'a>, Error> {
+        if namespace.is_empty() || namespace.contains('\0') {
+            return Err(
+                ErrorKind::General(format!("Invalid app data namespace: {:?}", namespace)).into(),
+            );
+        }
+        Ok(AppData {
+            storage,
+            namespace: namespace.to_owned(),
+        })
+    }
+
+    /// The namespace of this store
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    /// Get a value, or None if it was never set
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, Error> {
+        match self.storage.read_app_data1(&self.namespace, key)? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Set a value
+    pub fn put<T: Serialize>(&self, key: &str, value: &T) -> Result<(), Error> {
+        let bytes = serde_json::to_vec(value)?;
+        self.storage
+            .write_app_data1(&self.namespace, key, &bytes, None)?;
+        self.changed(key);
+        Ok(())
+    }
+
+    /// Change a value in one transaction, so that changes made to it at the same time
+    /// elsewhere are not lost. `f` gets the default if it was never set, and what it
+    /// returns is returned.
+    pub fn modify<T, R>(&self, key: &str, f: impl FnOnce(&mut T) -> R) -> Result<R, Error>
+    where
+        T: Serialize + DeserializeOwned + Default,
+    {
+        let result = self
+            .storage
+            .modify_app_data1(&self.namespace, key, |bytes| {
+                let mut value: T = match bytes {
+                    Some(bytes) => serde_json::from_slice(&bytes)?,
+                    None => T::default(),
+                };
+                let result = f(&mut value);
+                Ok((serde_json::to_vec(&value)?, result))
+            })?;
+        self.changed(key);
+        Ok(result)
+    }
+
+    /// Remove a value
+    pub fn remove(&self, key: &str) -> Result<(), Error> {
+        self.storage.delete_app_data1(&self.namespace, key, None)?;
+        self.changed(key);
+        Ok(())
+    }
+
+    /// All keys that have values in this namespace
+    pub fn keys(&self) -> Result<Vec<String>, Error> {
+        self.storage.app_data_keys1(&self.namespace)
+    }
+
+    fn changed(&self, key: &str) {
+        crate::bus::send(BusMessage::AppDataChanged {
+            namespace: self.namespace.clone(),
+            key: key.to_owned(),
+        });
+    }
+}
@@ -0,0 +1,118 @@
+use crate::error::Error;
+use crate::storage::{RawDatabase, Storage};
+use heed::RwTxn;
+
+// (Namespace, Key) -> Value
+//   key: namespace bytes, 0, key bytes
+//   val: the value bytes, as written by the frontend, through seal (except in
+//        LIB_NAMESPACE, where they are as they are)
+
+/// The namespace gossip-lib keeps its own state in (decks, the kind registry, ...).
+/// It is never sealed, as it is needed while the identity is locked.
+pub(crate) const LIB_NAMESPACE: &str = "gossip-lib";
+
+// Is this record (by its key) sealed when encrypt_at_rest is on?
+pub(super) fn app_data1_is_private(key: &[u8]) -> bool {
+    !(key.starts_with(LIB_NAMESPACE.as_bytes()) && key.get(LIB_NAMESPACE.len()) == Some(&0))
+}
+
+fn app_data1_key(namespace: &str, key: &str) -> Vec<u8> {
+    let mut k: Vec<u8> = Vec::with_capacity(namespace.len() + 1 + key.len());
+    k.extend(namespace.as_bytes());
+    k.push(0);
+    k.extend(key.as_bytes());
+    k
+}
+
+impl Storage {
+    pub(super) fn db_app_data1(&self) -> Result<RawDatabase, Error> {
+        self.open_database("app_data1")
+    }
+
+    pub(crate) fn read_app_data1(
+        &self,
+        namespace: &str,
+        key: &str,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let txn = self.env.read_txn()?;
+        let k = app_data1_key(namespace, key);
+        match self.db_app_data1()?.get(&txn, &k)? {
+            Some(v) if namespace == LIB_NAMESPACE => Ok(Some(v.to_owned())),
+            Some(v) => Ok(Some(self.unseal(v)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub(crate) fn write_app_data1<'a>(
+        &'a self,
+        namespace: &str,
+        key: &str,
+        value: &[u8],
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let k = app_data1_key(namespace, key);
+        let value = if namespace == LIB_NAMESPACE {
+            value.to_owned()
+        } else {
+            self.seal(value.to_owned())?
+        };
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            self.db_app_data1()?.put(txn, &k, &value)?;
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    // Read, change and write back a value in one transaction. `f` gets the current
+    // value (None if it was never set) and returns the new one.
+    pub(crate) fn modify_app_data1<R>(
+        &self,
+        namespace: &str,
+        key: &str,
+        f: impl FnOnce(Option<Vec<u8>>) -> Result<(Vec<u8>, R), Error>,
+    ) -> Result<R, Error> {
+        let k = app_data1_key(namespace, key);
+        let mut txn = self.write_txn()?;
+        let old = match self.db_app_data1()?.get(&txn, &k)? {
+            Some(v) if namespace == LIB_NAMESPACE => Some(v.to_owned()),
+            Some(v) => Some(self.unseal(v)?),
+            None => None,
+        };
+        let (value, result) = f(old)?;
+        let value = if namespace == LIB_NAMESPACE {
+            value
+        } else {
+            self.seal(value)?
+        };
+        self.db_app_data1()?.put(&mut *txn, &k, &value)?;
+        txn.commit()?;
+        Ok(result)
+    }
+
+    pub(crate) fn delete_app_data1<'a>(
+        &'a self,
+        namespace: &str,
+        key: &str,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let k = app_data1_key(namespace, key);
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            let _ = self.db_app_data1()?.delete(txn, &k)?;
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    pub(crate) fn app_data_keys1(&self, namespace: &str) -> Result<Vec<String>, Error> {
+        let prefix = app_data1_key(namespace, "");
+        Ok(self
+            .prefix_records(self.db_app_data1()?, &prefix, |_, _| true)?
+            .iter()
+            .map(|(key, _val)| String::from_utf8_lossy(&key[prefix.len()..]).into_owned())
+            .collect())
+    }
+}
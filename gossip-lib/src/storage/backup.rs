@@ -0,0 +1,198 @@
+use super::{NoIdentity, Storage};
+use crate::error::{Error, ErrorKind};
+use heed::CompactionOption;
+use nostr_types::Unixtime;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+// A backup archive:
+//   magic            8 bytes  "GOSSIPBK"
+//   version          u32 (big endian)
+//   migration level  u32 (big endian)
+//   created at       i64 (big endian)
+//   the database     the rest, a compacted copy of data.mdb
+const BACKUP_MAGIC: &[u8; 8] = b"GOSSIPBK";
+const BACKUP_VERSION: u32 = 1;
+const HEADER_LEN: usize = 24;
+
+/// What a backup archive holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackupInfo {
+    /// The migration level of the database in it
+    pub migration_level: u32,
+
+    /// When the backup was taken
+    pub created_at: Unixtime,
+
+    /// The size of the database in it, in bytes
+    pub size: u64,
+}
+
+impl BackupInfo {
+    fn to_header(self) -> [u8; HEADER_LEN] {
+        let mut header = [0; HEADER_LEN];
+        header[0..8].copy_from_slice(BACKUP_MAGIC);
+        header[8..12].copy_from_slice(&BACKUP_VERSION.to_be_bytes());
+        header[12..16].copy_from_slice(&self.migration_level.to_be_bytes());
+        header[16..24].copy_from_slice(&self.created_at.0.to_be_bytes());
+        header
+    }
+
+    fn from_header(header: &[u8; HEADER_LEN], size: u64) -> Result<BackupInfo, Error> {
+        if &header[0..8] != BACKUP_MAGIC {
+            return Err(ErrorKind::General("This is not a gossip backup".to_owned()).into());
+        }
+        let version = u32::from_be_bytes(header[8..12].try_into().unwrap());
+        if version > BACKUP_VERSION {
+            return Err(ErrorKind::General(format!(
+                "This backup is version {}, which needs a newer gossip",
+                version
+            ))
+            .into());
+        }
+        Ok(BackupInfo {
+            migration_level: u32::from_be_bytes(header[12..16].try_into().unwrap()),
+            created_at: Unixtime(i64::from_be_bytes(header[16..24].try_into().unwrap())),
+            size,
+        })
+    }
+}
+
+// A file next to `path` to build into before moving it in place
+fn partial_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_owned();
+    name.push(".partial");
+    path.with_file_name(name)
+}
+
+impl Storage {
+    /// Snapshot the whole database into a backup archive at `path`, which must not
+    /// exist yet. LMDB copies under a read transaction, so the snapshot is consistent
+    /// while the client keeps running and writing. It is compacted on the way.
+    pub fn backup(&self, path: &Path) -> Result<BackupInfo, Error> {
+        if path.exists() {
+            return Err(ErrorKind::General(format!("{} already exists", path.display())).into());
+        }
+
+        // Migrations only run at startup, so this is the level of what gets copied
+        let migration_level = self.read_migration_level()?.unwrap_or(0);
+
+        let partial = partial_path(path);
+        let _ = fs::remove_file(&partial);
+        let result = (|| -> Result<BackupInfo, Error> {
+            self.env.copy_to_file(&partial, CompactionOption::Enabled)?;
+            let size = fs::metadata(&partial)?.len();
+            let info = BackupInfo {
+                migration_level,
+                created_at: Unixtime::now().unwrap(),
+                size,
+            };
+
+            let mut archive = File::options().write(true).create_new(true).open(path)?;
+            archive.write_all(&info.to_header())?;
+            std::io::copy(&mut File::open(&partial)?, &mut archive)?;
+            archive.sync_all()?;
+            Ok(info)
+        })();
+        let _ = fs::remove_file(&partial);
+
+        if result.is_err() {
+            let _ = fs::remove_file(path);
+        }
+        result
+    }
+
+    /// Read what a backup archive holds, without restoring it
+    pub fn read_backup_info(path: &Path) -> Result<BackupInfo, Error> {
+        let mut file = File::open(path)?;
+        let len = file.metadata()?.len();
+        if len < HEADER_LEN as u64 {
+            return Err(ErrorKind::General("This is not a gossip backup".to_owned()).into());
+        }
+        let mut header = [0; HEADER_LEN];
+        file.read_exact(&mut header)?;
+        BackupInfo::from_header(&header, len - HEADER_LEN as u64)
+    }
+
+    /// Restore a backup archive into the LMDB directory `lmdb_dir`, which must not
+    /// hold a database yet (a fresh profile). This must be done before gossip opens
+    /// that directory; it is migrated as usual when it is next opened.
+    pub fn restore(path: &Path, lmdb_dir: &Path) -> Result<BackupInfo, Error> {
+        let info = Storage::read_backup_info(path)?;
+        if info.migration_level > Storage::MAX_MIGRATION_LEVEL {
+            return Err(ErrorKind::General(format!(
+                "This backup is at migration level {}, which needs a newer gossip",
+                info.migration_level
+            ))
+            .into());
+        }
+
+        let target = lmdb_dir.join("data.mdb");
+        if fs::metadata(&target).map(|m| m.len() > 0).unwrap_or(false) {
+            return Err(ErrorKind::General(format!(
+                "{} already holds a database, restore onto a fresh profile",
+                lmdb_dir.display()
+            ))
+            .into());
+        }
+        fs::create_dir_all(lmdb_dir)?;
+
+        let partial = partial_path(&target);
+        let result = (|| -> Result<(), Error> {
+            let mut archive = File::open(path)?;
+            let mut header = [0; HEADER_LEN];
+            archive.read_exact(&mut header)?;
+            let mut file = File::create(&partial)?;
+            std::io::copy(&mut archive, &mut file)?;
+            file.sync_all()?;
+            fs::rename(&partial, &target)?;
+
+            // Make sure it opens and is what the archive said
+            let storage = Storage::open(lmdb_dir, Box::new(NoIdentity))?;
+            if storage.read_migration_level()? != Some(info.migration_level) {
+                return Err(ErrorKind::General(
+                    "The restored database does not match its backup".to_owned(),
+                )
+                .into());
+            }
+            Ok(())
+        })();
+
+        if result.is_err() {
+            let _ = fs::remove_file(&partial);
+            let _ = fs::remove_file(&target);
+            let _ = fs::remove_file(lmdb_dir.join("lock.mdb"));
+        }
+        result.map(|_| info)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::storage::test_dir::{open_in, TestDir};
+    use nostr_types::Id;
+
+    #[test]
+    fn test_backup_restore() {
+        let base = TestDir::new();
+        let (from, to) = (base.subdir("from"), base.path().join("to"));
+
+        let storage = open_in(&from);
+        storage.write_migration_level(7, None).unwrap();
+        let id = Id([9; 32]);
+        storage.write_star1(id, 1000, false, None).unwrap();
+
+        let archive = base.path().join("backup");
+        let info = storage.backup(&archive).unwrap();
+        assert_eq!(info.migration_level, 7);
+        assert_eq!(Storage::read_backup_info(&archive).unwrap(), info);
+        assert!(storage.backup(&archive).is_err());
+
+        Storage::restore(&archive, &to).unwrap();
+        let restored = open_in(&to);
+        assert_eq!(restored.read_star1(id).unwrap(), Some((1000, false)));
+        assert!(Storage::restore(&archive, &to).is_err());
+    }
+}
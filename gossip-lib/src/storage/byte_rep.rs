@@ -0,0 +1,147 @@
+use crate::error::{Error, ErrorKind};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use speedy::{Readable, Writable};
+
+// Self-describing records start with this. Speedy has no header, and as the length
+// prefix a speedy record could start with it would be over a gigabyte.
+const MAGIC: &[u8; 4] = b"\xffGSD";
+
+// The encoding after the magic. Only JSON so far; a more compact one (e.g. CBOR)
+// can be added under another tag without touching records already written.
+const ENCODING_JSON: u8 = 1;
+
+/// How a record is turned into bytes for the database and back.
+pub trait ByteRep: Sized {
+    fn to_bytes(&self) -> Result<Vec<u8>, Error>;
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error>;
+}
+
+/// A record type stored in a self-describing encoding, keyed by field name.
+///
+/// Adding a field (with `#[serde(default)]`) needs no new version of the type and no
+/// migration: records written before it read with the default, and records written
+/// after it still read in older code, which ignores fields it doesn't know. Only a
+/// change in the meaning of an existing field needs a new version.
+///
+/// Implementing this (it has no methods) implements [ByteRep].
+pub trait SelfDescribing: Serialize + DeserializeOwned {}
+
+impl<T: SelfDescribing> ByteRep for T {
+    fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut bytes: Vec<u8> = Vec::with_capacity(64);
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(ENCODING_JSON);
+        serde_json::to_writer(&mut bytes, self)?;
+        Ok(bytes)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if !is_self_describing(bytes) {
+            return Err(ErrorKind::General("Record is not self-describing".to_owned()).into());
+        }
+        match bytes[MAGIC.len()] {
+            ENCODING_JSON => Ok(serde_json::from_slice(&bytes[MAGIC.len() + 1..])?),
+            tag => Err(ErrorKind::General(format!("Unknown record encoding {}", tag)).into()),
+        }
+    }
+}
+
+/// If these bytes are a self-describing record (rather than a speedy one)
+pub fn is_self_describing(bytes: &[u8]) -> bool {
+    bytes.len() > MAGIC.len() && bytes.starts_with(MAGIC)
+}
+
+/// Read a record of a table moving from a speedy type to a self-describing one.
+///
+/// Records still in the speedy `Legacy` form are read as that and converted, so a
+/// table can switch encodings without a migration: every record written after the
+/// switch is self-describing, and the rest convert as they are read.
+pub fn from_bytes_or_legacy<'a, T, Legacy>(bytes: &'a [u8]) -> Result<T, Error>
+where
+    T: SelfDescribing + From<Legacy>,
+    Legacy: Readable<'a, speedy::LittleEndian>,
+{
+    if is_self_describing(bytes) {
+        T::from_bytes(bytes)
+    } else {
+        Ok(Legacy::read_from_buffer(bytes)?.into())
+    }
+}
+
+/// Write a speedy record (for tables that have not moved to a self-describing type)
+pub fn speedy_to_bytes<T: Writable<speedy::LittleEndian>>(record: &T) -> Result<Vec<u8>, Error> {
+    Ok(record.write_to_vec()?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Clone, PartialEq, Readable, Writable)]
+    struct RecordV1 {
+        name: String,
+        count: u32,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Record {
+        name: String,
+        count: u32,
+        #[serde(default)]
+        note: Option<String>,
+    }
+
+    impl SelfDescribing for Record {}
+
+    impl From<RecordV1> for Record {
+        fn from(v1: RecordV1) -> Record {
+            Record {
+                name: v1.name,
+                count: v1.count,
+                note: None,
+            }
+        }
+    }
+
+    // The same record before the note field was added
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct OlderRecord {
+        name: String,
+        count: u32,
+    }
+
+    impl SelfDescribing for OlderRecord {}
+
+    #[test]
+    fn test_self_describing_round_trip() {
+        let record = Record {
+            name: "hello".to_owned(),
+            count: 3,
+            note: Some("note".to_owned()),
+        };
+        let bytes = record.to_bytes().unwrap();
+        assert!(is_self_describing(&bytes));
+        assert_eq!(Record::from_bytes(&bytes).unwrap(), record);
+
+        // Older code reads newer records, and newer code reads older records
+        let older = OlderRecord::from_bytes(&bytes).unwrap();
+        assert_eq!(older.count, 3);
+        let newer = Record::from_bytes(&older.to_bytes().unwrap()).unwrap();
+        assert_eq!(newer.note, None);
+    }
+
+    #[test]
+    fn test_legacy_fallback() {
+        let v1 = RecordV1 {
+            name: "legacy".to_owned(),
+            count: 7,
+        };
+        let bytes = speedy_to_bytes(&v1).unwrap();
+        assert!(!is_self_describing(&bytes));
+        let record: Record = from_bytes_or_legacy::<Record, RecordV1>(&bytes).unwrap();
+        assert_eq!(record, Record::from(v1));
+    }
+}
@@ -0,0 +1,89 @@
+use crate::error::Error;
+use crate::storage::types::Community1;
+use crate::storage::{CorruptRecords, RawDatabase, Storage};
+use heed::RwTxn;
+use nostr_types::PublicKey;
+use speedy::{Readable, Writable};
+
+// Author:D -> Community1
+//   key: key!(author.as_bytes() ++ d.as_bytes())
+//   val: community.write_to_vec() | Community1::read_from_buffer(val)
+
+fn communities1_key(author: &PublicKey, d: &str) -> Vec<u8> {
+    let mut key: Vec<u8> = author.as_bytes().to_vec();
+    key.extend(d.as_bytes());
+    key!(key.as_slice()).to_owned()
+}
+
+impl Storage {
+    pub(super) fn db_communities1(&self) -> Result<RawDatabase, Error> {
+        self.open_database("communities1")
+    }
+
+    pub(crate) fn read_community1(
+        &self,
+        author: &PublicKey,
+        d: &str,
+    ) -> Result<Option<Community1>, Error> {
+        let key = communities1_key(author, d);
+        let db = self.db_communities1()?;
+        let mut corrupt = CorruptRecords::new("communities1");
+        let txn = self.env.read_txn()?;
+        let output = match db.get(&txn, &key)? {
+            Some(val) => corrupt.check(&key, val, Community1::read_from_buffer(val)),
+            None => None,
+        };
+        drop(txn);
+        self.quarantine_corrupt(db, corrupt, None)?;
+        Ok(output)
+    }
+
+    pub(crate) fn modify_community1<'a, M>(
+        &'a self,
+        author: &PublicKey,
+        d: &str,
+        mut modify: M,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error>
+    where
+        M: FnMut(&mut Community1),
+    {
+        let key = communities1_key(author, d);
+
+        let mut f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            // A record that does not decode is quarantined, and is as if absent
+            let mut corrupt = CorruptRecords::new("communities1");
+            let existing = match self.db_communities1()?.get(txn, &key)? {
+                Some(val) => corrupt.check(&key, val, Community1::read_from_buffer(val)),
+                None => None,
+            };
+            self.quarantine_corrupt(self.db_communities1()?, corrupt, Some(&mut *txn))?;
+            let mut community = match existing {
+                Some(community) => community,
+                None => Community1::new(author.to_owned(), d.to_owned()),
+            };
+            modify(&mut community);
+            let bytes = community.write_to_vec()?;
+            self.db_communities1()?.put(txn, &key, &bytes)?;
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    pub(crate) fn read_all_communities1(&self) -> Result<Vec<Community1>, Error> {
+        let db = self.db_communities1()?;
+        let mut corrupt = CorruptRecords::new("communities1");
+        let txn = self.env.read_txn()?;
+        let mut output: Vec<Community1> = Vec::new();
+        for result in db.iter(&txn)? {
+            let (key, val) = result?;
+            if let Some(community) = corrupt.check(key, val, Community1::read_from_buffer(val)) {
+                output.push(community);
+            }
+        }
+        drop(txn);
+        self.quarantine_corrupt(db, corrupt, None)?;
+        Ok(output)
+    }
+}
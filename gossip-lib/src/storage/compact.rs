@@ -0,0 +1,173 @@
+use super::{Storage, StorageContext, StorageHandle};
+use crate::error::{Error, ErrorKind};
+use heed::CompactionOption;
+use nostr_types::Unixtime;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+/// How long compaction waits for the writes in progress to finish before giving up
+pub(crate) const DRAIN_WRITERS_SECS: u64 = 10;
+
+/// What compacting the database did
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompactionReport {
+    /// The size of the database file before, in bytes
+    pub before: u64,
+
+    /// The size of the database file after, in bytes
+    pub after: u64,
+}
+
+// A directory next to `dir`, named after it
+fn sibling(dir: &Path, suffix: &str) -> PathBuf {
+    let mut name = dir.file_name().unwrap_or_default().to_owned();
+    name.push(".");
+    name.push(suffix);
+    dir.with_file_name(name)
+}
+
+impl Storage {
+    // Copy the database into a fresh compacted file, open it, and move it into the
+    // place of this one. The caller must hold the write gate from before this starts
+    // until the returned storage is swapped in, or writes are lost.
+    //
+    // The fresh copy is opened from a directory of its own, and then the directories
+    // are renamed under both open environments: LMDB must not open the same
+    // directory twice in one process, and this one stays open.
+    fn compact_copy(
+        &self,
+        context: Box<dyn StorageContext>,
+    ) -> Result<(Storage, CompactionReport), Error> {
+        let dir = self.env.path().to_owned();
+        let before = fs::metadata(dir.join("data.mdb"))?.len();
+
+        // Unique, since every environment stays registered under the path it was
+        // opened at
+        let fresh_dir = sibling(&dir, &format!("compact-{}", Unixtime::now().unwrap().0));
+        let old_dir = sibling(&dir, "old");
+        let _ = fs::remove_dir_all(&fresh_dir);
+        let _ = fs::remove_dir_all(&old_dir);
+        fs::create_dir_all(&fresh_dir)?;
+
+        let result = (|| -> Result<Storage, Error> {
+            let file = self
+                .env
+                .copy_to_file(fresh_dir.join("data.mdb"), CompactionOption::Enabled)?;
+            file.sync_all()?;
+
+            // Make sure it opens and is what we copied
+            let storage = Storage::open(&fresh_dir, context)?;
+            if storage.read_migration_level()? != self.read_migration_level()? {
+                return Err(ErrorKind::General(
+                    "The compacted database does not match the original".to_owned(),
+                )
+                .into());
+            }
+            Ok(storage)
+        })();
+        let storage = match result {
+            Ok(storage) => storage,
+            Err(e) => {
+                let _ = fs::remove_dir_all(&fresh_dir);
+                return Err(e);
+            }
+        };
+
+        // Swap the directories. This fails on systems that don't rename open files
+        // (Windows), in which case nothing has changed.
+        if let Err(e) = fs::rename(&dir, &old_dir) {
+            drop(storage);
+            let _ = fs::remove_dir_all(&fresh_dir);
+            return Err(e.into());
+        }
+        if let Err(e) = fs::rename(&fresh_dir, &dir) {
+            let _ = fs::rename(&old_dir, &dir);
+            drop(storage);
+            let _ = fs::remove_dir_all(&fresh_dir);
+            return Err(e.into());
+        }
+
+        // This storage keeps the old file mapped, so its space only comes back once
+        // gossip exits
+        let _ = fs::remove_dir_all(&old_dir);
+
+        let after = fs::metadata(dir.join("data.mdb"))?.len();
+        Ok((storage, CompactionReport { before, after }))
+    }
+}
+
+impl StorageHandle {
+    /// Shrink the database file of the current storage down to what it holds, which
+    /// LMDB never does by itself (pruning only frees pages for reuse). The database
+    /// is copied into a fresh compacted file that is swapped in.
+    ///
+    /// Writes in progress are drained first (for up to `drain`, or the compaction is
+    /// abandoned), and new ones are held off until the swap is done. Writes that
+    /// were held off against the old storage then fail rather than being lost with
+    /// it, so quiet the client down first.
+    pub(crate) fn compact(
+        &self,
+        context: Box<dyn StorageContext>,
+        drain: Duration,
+    ) -> Result<CompactionReport, Error> {
+        let current: &Storage = self;
+        current.flush_event_batch()?;
+
+        let gate = match current.write_gate.try_write_for(drain) {
+            Some(gate) => gate,
+            None => {
+                return Err(ErrorKind::General(
+                    "Writes did not finish in time, so the database was not compacted".to_owned(),
+                )
+                .into())
+            }
+        };
+        let (storage, report) = current.compact_copy(context)?;
+        current.retired.store(true, Ordering::Release);
+        let _ = self.swap(storage);
+        drop(gate);
+
+        self.init()?;
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::storage::test_dir::{open_in, TestDir};
+    use crate::storage::NoIdentity;
+    use nostr_types::Id;
+
+    #[test]
+    fn test_compact() {
+        let base = TestDir::new();
+        let dir = base.subdir("lmdb");
+
+        let handle = StorageHandle::new(open_in(&dir));
+        handle.write_migration_level(7, None).unwrap();
+        let id = Id([9; 32]);
+        handle.write_star1(id, 1000, false, None).unwrap();
+
+        let drain = Duration::from_secs(DRAIN_WRITERS_SECS);
+        let report = handle.compact(Box::new(NoIdentity), drain).unwrap();
+        assert!(report.after <= report.before);
+        assert_eq!(handle.read_migration_level().unwrap(), Some(7));
+        assert_eq!(handle.read_star1(id).unwrap(), Some((1000, false)));
+        assert!(dir.join("data.mdb").exists());
+        assert!(!sibling(&dir, "old").exists());
+
+        // A write in progress that does not finish in time stops the compaction
+        let txn = handle.get_write_txn().unwrap();
+        let result = std::thread::scope(|s| {
+            s.spawn(|| handle.compact(Box::new(NoIdentity), Duration::from_millis(50)))
+                .join()
+                .unwrap()
+        });
+        assert!(result.is_err());
+        drop(txn);
+        assert_eq!(handle.read_star1(id).unwrap(), Some((1000, false)));
+    }
+}
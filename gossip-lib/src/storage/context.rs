@@ -0,0 +1,130 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use nostr_types::{Event, EventV2, PublicKey, Rumor, RumorV2};
+
+/// What a [Storage](crate::Storage) needs from outside of itself: the identity
+/// whose private events it indexes, and somewhere to report follow changes.
+///
+/// The app's storage uses [GlobalContext] which defers to GLOBALS. A storage opened
+/// with [Storage::open](crate::Storage::open) can be given any other context, so
+/// several (each with its own identity, or none) can live in one process.
+pub trait StorageContext: Send + Sync {
+    /// The public key of the identity, if there is one
+    fn public_key(&self) -> Option<PublicKey>;
+
+    /// If the private key of the identity is available
+    fn is_unlocked(&self) -> bool;
+
+    fn unwrap_giftwrap(&self, event: &Event) -> Result<Rumor, Error>;
+
+    /// If giftwraps can be unwrapped without waiting on anybody. Storage only
+    /// unwraps inside a write transaction if so; otherwise giftwraps are stored
+    /// unindexed and indexed once unwrapped outside of it.
+    fn unwraps_locally(&self) -> bool {
+        true
+    }
+
+    fn unwrap_giftwrap2(&self, event: &EventV2) -> Result<RumorV2, Error>;
+
+    fn decrypt_event_contents(&self, event: &Event) -> Result<String, Error>;
+
+    /// The key private records are encrypted at rest with, while the identity is
+    /// unlocked
+    fn storage_key(&self) -> Option<[u8; 32]> {
+        None
+    }
+
+    /// A person was followed or unfollowed
+    fn follow_state_changed(&self, _pubkey: &PublicKey, _followed: bool) {}
+
+    /// Follows changed in bulk
+    fn follow_states_invalidated(&self) {}
+}
+
+/// The context of the app's own storage: the identity and people in GLOBALS
+#[derive(Debug, Default)]
+pub struct GlobalContext;
+
+impl StorageContext for GlobalContext {
+    fn public_key(&self) -> Option<PublicKey> {
+        GLOBALS.identity.public_key()
+    }
+
+    fn is_unlocked(&self) -> bool {
+        GLOBALS.identity.is_unlocked()
+    }
+
+    fn unwrap_giftwrap(&self, event: &Event) -> Result<Rumor, Error> {
+        GLOBALS.identity.unwrap_giftwrap_locally(event)
+    }
+
+    fn unwraps_locally(&self) -> bool {
+        // A remote signer is a round trip over relays
+        !GLOBALS.identity.is_remote()
+    }
+
+    fn unwrap_giftwrap2(&self, event: &EventV2) -> Result<RumorV2, Error> {
+        GLOBALS.identity.unwrap_giftwrap2(event)
+    }
+
+    fn decrypt_event_contents(&self, event: &Event) -> Result<String, Error> {
+        GLOBALS.identity.decrypt_event_contents_locally(event)
+    }
+
+    fn storage_key(&self) -> Option<[u8; 32]> {
+        GLOBALS.identity.storage_key()
+    }
+
+    fn follow_state_changed(&self, pubkey: &PublicKey, followed: bool) {
+        GLOBALS.people.update_follow_state(pubkey, followed);
+    }
+
+    fn follow_states_invalidated(&self) {
+        GLOBALS.people.invalidate_follow_states();
+    }
+}
+
+/// A context without an identity. Giftwraps stay unindexed and DMs cannot be read.
+#[derive(Debug, Default)]
+pub struct NoIdentity;
+
+impl StorageContext for NoIdentity {
+    fn public_key(&self) -> Option<PublicKey> {
+        None
+    }
+
+    fn is_unlocked(&self) -> bool {
+        false
+    }
+
+    fn unwrap_giftwrap(&self, _event: &Event) -> Result<Rumor, Error> {
+        Err(crate::error::ErrorKind::NoPrivateKey.into())
+    }
+
+    fn unwrap_giftwrap2(&self, _event: &EventV2) -> Result<RumorV2, Error> {
+        Err(crate::error::ErrorKind::NoPrivateKey.into())
+    }
+
+    fn decrypt_event_contents(&self, _event: &Event) -> Result<String, Error> {
+        Err(crate::error::ErrorKind::NoPrivateKey.into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::storage::test_dir::TestDir;
+    use nostr_types::Id;
+
+    #[test]
+    fn test_isolated_storages() {
+        let (dir_a, dir_b) = (TestDir::new(), TestDir::new());
+        let (a, b) = (dir_a.open(), dir_b.open());
+
+        let id = Id([7; 32]);
+        a.write_star1(id, 1000, false, None).unwrap();
+        assert_eq!(a.read_star1(id).unwrap(), Some((1000, false)));
+        assert_eq!(b.read_star1(id).unwrap(), None);
+        assert!(b.context().public_key().is_none());
+    }
+}
@@ -0,0 +1,214 @@
+use super::expiry::Expiry;
+use crate::error::{Error, ErrorKind};
+use crate::storage::{RawDatabase, Storage};
+use heed::RwTxn;
+use nostr_types::Unixtime;
+
+// (Table, Key) -> CorruptRecord
+//   key: key!(table.as_bytes() ++ [0] ++ key)
+//   val: quarantined_at.to_be_bytes() ++ error_len.to_be_bytes() ++ error.as_bytes()
+//          ++ key_len.to_be_bytes() ++ key ++ value        (i64, u32, u32)
+//
+// The whole original key is in the value as the key here may be truncated.
+//
+// Records expire CORRUPT_RECORD_DAYS after they were quarantined.
+
+// How long a corrupt record is kept for inspection
+const CORRUPT_RECORD_DAYS: i64 = 90;
+
+pub(super) const CORRUPT_RECORDS1_EXPIRY: Expiry =
+    Expiry::new("corrupt_records1", Storage::db_corrupt_records1, expires_at);
+
+fn expires_at(_key: &[u8], val: &[u8]) -> Option<Unixtime> {
+    let quarantined_at = i64::from_be_bytes(val.get(..8)?.try_into().ok()?);
+    Some(Unixtime(quarantined_at + CORRUPT_RECORD_DAYS * 86400))
+}
+
+/// A record that could not be decoded, moved out of its table
+#[derive(Debug, Clone)]
+pub struct CorruptRecord {
+    /// The database it was in
+    pub table: String,
+
+    pub key: Vec<u8>,
+    pub value: Vec<u8>,
+
+    /// Why it could not be decoded
+    pub error: String,
+
+    pub quarantined_at: Unixtime,
+}
+
+/// Records that failed to decode while iterating a table. Iteration carries on
+/// past them; afterwards [Storage::quarantine_corrupt] moves them out of the table.
+pub(crate) struct CorruptRecords {
+    table: &'static str,
+    records: Vec<(Vec<u8>, Vec<u8>, String)>,
+}
+
+impl CorruptRecords {
+    pub(crate) fn new(table: &'static str) -> CorruptRecords {
+        CorruptRecords {
+            table,
+            records: Vec::new(),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// The decoded record, or None (remembering the record) if it failed to decode
+    pub(crate) fn check<T, E: std::fmt::Display>(
+        &mut self,
+        key: &[u8],
+        val: &[u8],
+        decoded: Result<T, E>,
+    ) -> Option<T> {
+        match decoded {
+            Ok(t) => Some(t),
+            Err(e) => {
+                self.push(key, val, format!("{}", e));
+                None
+            }
+        }
+    }
+
+    /// Remember a record that decodes but is bad for another reason
+    pub(crate) fn push(&mut self, key: &[u8], val: &[u8], error: String) {
+        self.records.push((key.to_owned(), val.to_owned(), error));
+    }
+}
+
+fn encode(key: &[u8], value: &[u8], error: &str, quarantined_at: Unixtime) -> Vec<u8> {
+    let mut bytes: Vec<u8> = Vec::with_capacity(16 + error.len() + key.len() + value.len());
+    bytes.extend(quarantined_at.0.to_be_bytes());
+    bytes.extend((error.len() as u32).to_be_bytes());
+    bytes.extend(error.as_bytes());
+    bytes.extend((key.len() as u32).to_be_bytes());
+    bytes.extend(key);
+    bytes.extend(value);
+    bytes
+}
+
+fn decode(table: &str, bytes: &[u8]) -> Result<CorruptRecord, Error> {
+    let short = || ErrorKind::General("Corrupt record entry is too short".to_owned());
+    if bytes.len() < 12 {
+        return Err(short().into());
+    }
+    let quarantined_at = Unixtime(i64::from_be_bytes(bytes[..8].try_into()?));
+    let error_len = u32::from_be_bytes(bytes[8..12].try_into()?) as usize;
+    let rest = &bytes[12..];
+    if rest.len() < error_len + 4 {
+        return Err(short().into());
+    }
+    let error = String::from_utf8_lossy(&rest[..error_len]).into_owned();
+    let rest = &rest[error_len..];
+    let key_len = u32::from_be_bytes(rest[..4].try_into()?) as usize;
+    let rest = &rest[4..];
+    if rest.len() < key_len {
+        return Err(short().into());
+    }
+    Ok(CorruptRecord {
+        table: table.to_owned(),
+        key: rest[..key_len].to_owned(),
+        value: rest[key_len..].to_owned(),
+        error,
+        quarantined_at,
+    })
+}
+
+fn quarantine_key(table: &str, key: &[u8]) -> Vec<u8> {
+    let mut qkey: Vec<u8> = Vec::with_capacity(table.len() + 1 + key.len());
+    qkey.extend(table.as_bytes());
+    qkey.push(0);
+    qkey.extend(key);
+    qkey
+}
+
+impl Storage {
+    pub(super) fn db_corrupt_records1(&self) -> Result<RawDatabase, Error> {
+        self.open_database("corrupt_records1")
+    }
+
+    pub(crate) fn get_corrupt_records1_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_corrupt_records1()?.len(&txn)?)
+    }
+
+    pub(crate) fn quarantine_corrupt1<'a>(
+        &'a self,
+        db: RawDatabase,
+        corrupt: CorruptRecords,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        if corrupt.records.is_empty() {
+            return Ok(());
+        }
+
+        let now = Unixtime::now().unwrap();
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            for (key, value, error) in corrupt.records.iter() {
+                tracing::error!(
+                    "Quarantined a corrupt record in {} ({} bytes): {}",
+                    corrupt.table,
+                    value.len(),
+                    error
+                );
+                let qkey = quarantine_key(corrupt.table, key);
+                self.db_corrupt_records1()?.put(
+                    txn,
+                    key!(&qkey[..]),
+                    &encode(key, value, error, now),
+                )?;
+                let _ = db.delete(txn, key)?;
+            }
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    pub(crate) fn read_all_corrupt_records1(&self) -> Result<Vec<CorruptRecord>, Error> {
+        let txn = self.env.read_txn()?;
+        let mut output: Vec<CorruptRecord> = Vec::new();
+        for result in self.db_corrupt_records1()?.iter(&txn)? {
+            let (key, val) = result?;
+            let table_len = key.iter().position(|b| *b == 0).unwrap_or(key.len());
+            let table = String::from_utf8_lossy(&key[..table_len]);
+            output.push(decode(&table, val)?);
+        }
+        Ok(output)
+    }
+
+    pub(crate) fn purge_corrupt_record1<'a>(
+        &'a self,
+        table: &str,
+        key: &[u8],
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let qkey = quarantine_key(table, key);
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            let _ = self.db_corrupt_records1()?.delete(txn, key!(&qkey[..]))?;
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    pub(crate) fn purge_all_corrupt_records1<'a>(
+        &'a self,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            self.db_corrupt_records1()?.clear(txn)?;
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+}
@@ -0,0 +1,62 @@
+use super::Storage;
+use crate::error::Error;
+use std::sync::atomic::Ordering;
+
+/// How soon the changes of a write transaction must reach the disk once committed.
+///
+/// LMDB itself syncs nothing as it commits (the environment is opened with
+/// NO_SYNC); a commit is synced (data and meta pages, in one force_sync) right after
+/// it if its class calls for that, and otherwise by the next sync. Every commit is
+/// atomic, and the database stays consistent through a crash either way. What
+/// differs is whether a system crash or power loss can undo a commit that was not
+/// yet synced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteClass {
+    /// Keys and other data that cannot be fetched again: synced as they commit
+    Critical,
+
+    /// Most writes: synced as they commit, unless the `relaxed_durability` setting
+    /// is on
+    Normal,
+
+    /// Events and what we saw them on, which relays can give us again: not synced
+    /// as they commit, but by the next sync (after a Critical or Normal commit, or
+    /// the periodic one)
+    Bulk,
+}
+
+impl Storage {
+    fn syncs_on_commit(&self, class: WriteClass) -> bool {
+        match class {
+            WriteClass::Critical => true,
+            WriteClass::Normal => !self.read_setting_relaxed_durability(),
+            WriteClass::Bulk => false,
+        }
+    }
+
+    // Called after a write transaction of this class commits
+    pub(super) fn committed(&self, class: WriteClass) -> Result<(), Error> {
+        if self.syncs_on_commit(class) {
+            self.sync()
+        } else {
+            self.unsynced.store(true, Ordering::Relaxed);
+            Ok(())
+        }
+    }
+
+    /// If there are commits that have not been synced to disk yet
+    pub fn has_unsynced_commits(&self) -> bool {
+        self.unsynced.load(Ordering::Relaxed)
+    }
+
+    /// Sync the data to disk if any commits have not been synced yet. The
+    /// maintenance scheduler does this every minute. Returns whether it synced.
+    pub fn sync_if_needed(&self) -> Result<bool, Error> {
+        if self.has_unsynced_commits() {
+            self.sync()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+}
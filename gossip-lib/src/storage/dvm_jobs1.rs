@@ -0,0 +1,108 @@
+use crate::error::Error;
+use crate::storage::types::DvmJob1;
+use crate::storage::{CorruptRecords, RawDatabase, Storage};
+use heed::RwTxn;
+use nostr_types::Id;
+use speedy::{Readable, Writable};
+
+// Id -> DvmJob1
+//   key: id.as_slice()
+//   val: job.write_to_vec() | DvmJob1::read_from_buffer(val)
+
+impl Storage {
+    pub(super) fn db_dvm_jobs1(&self) -> Result<RawDatabase, Error> {
+        self.open_database("dvm_jobs1")
+    }
+
+    pub(crate) fn write_dvm_job1<'a>(
+        &'a self,
+        job: &DvmJob1,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let bytes = job.write_to_vec()?;
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            self.db_dvm_jobs1()?
+                .put(txn, job.request_id.as_slice(), &bytes)?;
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    pub(crate) fn read_dvm_job1(&self, request_id: Id) -> Result<Option<DvmJob1>, Error> {
+        let db = self.db_dvm_jobs1()?;
+        let mut corrupt = CorruptRecords::new("dvm_jobs1");
+        let txn = self.env.read_txn()?;
+        let output = match db.get(&txn, request_id.as_slice())? {
+            Some(val) => corrupt.check(request_id.as_slice(), val, DvmJob1::read_from_buffer(val)),
+            None => None,
+        };
+        drop(txn);
+        self.quarantine_corrupt(db, corrupt, None)?;
+        Ok(output)
+    }
+
+    // Does nothing if there is no such job: we only track jobs we asked for
+    pub(crate) fn modify_dvm_job1<'a, M>(
+        &'a self,
+        request_id: Id,
+        mut modify: M,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error>
+    where
+        M: FnMut(&mut DvmJob1),
+    {
+        let mut f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            // A record that does not decode is quarantined, and is as if absent
+            let mut corrupt = CorruptRecords::new("dvm_jobs1");
+            let existing = match self.db_dvm_jobs1()?.get(txn, request_id.as_slice())? {
+                Some(val) => {
+                    corrupt.check(request_id.as_slice(), val, DvmJob1::read_from_buffer(val))
+                }
+                None => None,
+            };
+            self.quarantine_corrupt(self.db_dvm_jobs1()?, corrupt, Some(&mut *txn))?;
+            let mut job = match existing {
+                Some(job) => job,
+                None => return Ok(()),
+            };
+            modify(&mut job);
+            let bytes = job.write_to_vec()?;
+            self.db_dvm_jobs1()?
+                .put(txn, request_id.as_slice(), &bytes)?;
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    pub(crate) fn delete_dvm_job1<'a>(
+        &'a self,
+        request_id: Id,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            let _ = self.db_dvm_jobs1()?.delete(txn, request_id.as_slice())?;
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    pub(crate) fn read_all_dvm_jobs1(&self) -> Result<Vec<DvmJob1>, Error> {
+        let db = self.db_dvm_jobs1()?;
+        let mut corrupt = CorruptRecords::new("dvm_jobs1");
+        let txn = self.env.read_txn()?;
+        let mut output: Vec<DvmJob1> = Vec::new();
+        for result in db.iter(&txn)? {
+            let (key, val) = result?;
+            if let Some(job) = corrupt.check(key, val, DvmJob1::read_from_buffer(val)) {
+                output.push(job);
+            }
+        }
+        drop(txn);
+        self.quarantine_corrupt(db, corrupt, None)?;
+        Ok(output)
+    }
+}
@@ -0,0 +1,224 @@
+use super::{RawDatabase, Storage};
+use crate::error::{Error, ErrorKind};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use std::sync::atomic::Ordering;
+
+// Every record that goes through seal starts with a byte saying what follows: the
+// record's usual bytes (speedy, JSON or raw) in the clear, or a random nonce and the
+// XChaCha20-Poly1305 ciphertext of them. So clear bytes can never pass for sealed
+// ones, whatever they start with.
+const CLEAR: u8 = 0;
+const SEALED_V1: u8 = 1;
+const NONCE_LEN: usize = 24;
+
+// Before the format byte (migration 50), sealed records started with this and clear
+// records had no prefix at all
+pub(super) const LEGACY_SEALED_MAGIC: &[u8; 4] = b"\xffGSE";
+
+// Records resealed per transaction
+const RESEAL_BATCH_SIZE: usize = 500;
+
+/// If these bytes are a sealed (encrypted at rest) record
+pub fn is_sealed(bytes: &[u8]) -> bool {
+    bytes.len() >= 1 + NONCE_LEN && bytes[0] == SEALED_V1
+}
+
+// The bytes to write for a record kept in the clear
+pub(super) fn in_the_clear(bytes: &[u8]) -> Vec<u8> {
+    let mut framed: Vec<u8> = Vec::with_capacity(1 + bytes.len());
+    framed.push(CLEAR);
+    framed.extend_from_slice(bytes);
+    framed
+}
+
+// Which records of a sealed table (by key) hold private data
+type IsPrivate = fn(&[u8]) -> bool;
+
+fn all_private(_key: &[u8]) -> bool {
+    true
+}
+
+fn seal_with(key: &[u8; 32], bytes: &[u8]) -> Result<Vec<u8>, Error> {
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(key));
+    let nonce: [u8; NONCE_LEN] = rand::random();
+    let ciphertext = cipher
+        .encrypt(XNonce::from_slice(&nonce), bytes)
+        .map_err(|_| ErrorKind::General("Could not encrypt a record".to_owned()))?;
+    let mut sealed: Vec<u8> = Vec::with_capacity(1 + NONCE_LEN + ciphertext.len());
+    sealed.push(SEALED_V1);
+    sealed.extend_from_slice(&nonce);
+    sealed.extend(ciphertext);
+    Ok(sealed)
+}
+
+fn unseal_with(key: &[u8; 32], bytes: &[u8]) -> Result<Vec<u8>, Error> {
+    if !is_sealed(bytes) {
+        return Err(ErrorKind::General("The record is not sealed".to_owned()).into());
+    }
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(key));
+    let nonce = &bytes[1..1 + NONCE_LEN];
+    let plaintext = cipher
+        .decrypt(XNonce::from_slice(nonce), &bytes[1 + NONCE_LEN..])
+        .map_err(|_| ErrorKind::General("Could not decrypt a record".to_owned()))?;
+    Ok(plaintext)
+}
+
+// The usual bytes of a record kept in the clear
+fn open_clear(bytes: &[u8]) -> Result<Vec<u8>, Error> {
+    match bytes.first() {
+        Some(&CLEAR) => Ok(bytes[1..].to_owned()),
+        _ => Err(ErrorKind::General("The record is not in the clear".to_owned()).into()),
+    }
+}
+
+impl Storage {
+    fn storage_key(&self) -> Result<[u8; 32], Error> {
+        match self.context.storage_key() {
+            Some(key) => Ok(key),
+            None => Err(ErrorKind::General(
+                "Private data is encrypted, unlock your identity to use it".to_owned(),
+            )
+            .into()),
+        }
+    }
+
+    /// The bytes to write for a record holding private data: sealed when
+    /// `encrypt_at_rest` is on, in the clear otherwise. While the identity is locked
+    /// it is written in the clear too, and resealed once the identity is unlocked.
+    pub(crate) fn seal(&self, bytes: Vec<u8>) -> Result<Vec<u8>, Error> {
+        if !self.read_setting_encrypt_at_rest() {
+            return Ok(in_the_clear(&bytes));
+        }
+        match self.context.storage_key() {
+            Some(key) => seal_with(&key, &bytes),
+            None => {
+                self.seal_deferred.store(true, Ordering::SeqCst);
+                Ok(in_the_clear(&bytes))
+            }
+        }
+    }
+
+    /// The bytes to write for a record holding a secret that can spend money (the
+    /// wallet key): sealed whatever `encrypt_at_rest` says, so it needs the identity
+    /// unlocked.
+    pub(crate) fn seal_secret(&self, bytes: Vec<u8>) -> Result<Vec<u8>, Error> {
+        seal_with(&self.storage_key()?, &bytes)
+    }
+
+    /// The usual bytes of a record written by [seal](Storage::seal). Records in the
+    /// clear pass through, so a table can hold both while it is being resealed.
+    pub(crate) fn unseal(&self, bytes: &[u8]) -> Result<Vec<u8>, Error> {
+        match bytes.first() {
+            Some(&SEALED_V1) => unseal_with(&self.storage_key()?, bytes),
+            _ => open_clear(bytes),
+        }
+    }
+
+    // Once no write transaction is open, flag the records written in the clear
+    // while locked for resealing. The flag can't be written inside of the
+    // transaction that wrote them, as seal doesn't have it.
+    pub(super) fn seal_deferred_pending(&self) {
+        if self.write_txns_open.load(Ordering::SeqCst) > 0 {
+            return;
+        }
+        if self.seal_deferred.swap(false, Ordering::SeqCst) {
+            if let Err(e) = self.set_flag_reseal_needed(true, None) {
+                tracing::error!("{}", e);
+            }
+        }
+    }
+
+    // The tables whose records go through seal and unseal, with which of their
+    // records (by key) hold private data
+    fn sealed_dbs(&self) -> Result<Vec<(RawDatabase, IsPrivate)>, Error> {
+        Ok(vec![
+            (
+                self.db_app_data()?,
+                super::app_data1::app_data1_is_private as IsPrivate,
+            ),
+            (self.db_mute_log()?, all_private),
+            (self.db_rumors()?, all_private),
+        ])
+    }
+
+    /// Rewrite the records of every table holding private data to match the
+    /// `encrypt_at_rest` setting: sealing those in the clear when it is on, and
+    /// unsealing those sealed when it is off. This is how an existing database is
+    /// encrypted (or decrypted) in place. The identity must be unlocked. Returns how
+    /// many records were rewritten.
+    pub fn reseal_private_records(&self) -> Result<usize, Error> {
+        let key = self.storage_key()?;
+        let sealing = self.read_setting_encrypt_at_rest();
+
+        let mut count = 0;
+        for (db, is_private) in self.sealed_dbs()? {
+            // Gather which records need rewriting first, as writes can't happen
+            // while iterating
+            let mut pending: Vec<Vec<u8>> = Vec::new();
+            {
+                let txn = self.env.read_txn()?;
+                for result in db.iter(&txn)? {
+                    let (key_bytes, val) = result?;
+                    if is_private(key_bytes) && is_sealed(val) != sealing {
+                        pending.push(key_bytes.to_owned());
+                    }
+                }
+            }
+
+            // Each record is read again in the transaction that rewrites it, so a
+            // write made to it meanwhile is not undone
+            for batch in pending.chunks(RESEAL_BATCH_SIZE) {
+                let mut txn = self.write_txn()?;
+                for key_bytes in batch {
+                    let val = match db.get(&txn, key_bytes)? {
+                        Some(val) if is_sealed(val) != sealing => val.to_owned(),
+                        _ => continue,
+                    };
+                    let rewritten = if sealing {
+                        seal_with(&key, &open_clear(&val)?)?
+                    } else {
+                        in_the_clear(&unseal_with(&key, &val)?)
+                    };
+                    db.put(&mut *txn, key_bytes, &rewritten)?;
+                    count += 1;
+                }
+                txn.commit()?;
+            }
+        }
+
+        self.set_flag_reseal_needed(false, None)?;
+
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_seal_round_trip() {
+        let key = [3; 32];
+        let record = b"draft: meet at noon".to_vec();
+        let sealed = seal_with(&key, &record).unwrap();
+        assert!(is_sealed(&sealed));
+        assert!(!is_sealed(&in_the_clear(&record)));
+        assert_eq!(unseal_with(&key, &sealed).unwrap(), record);
+        assert_eq!(open_clear(&in_the_clear(&record)).unwrap(), record);
+
+        // Clear bytes that look like a sealed record are still clear
+        let lookalike = sealed.clone();
+        assert!(!is_sealed(&in_the_clear(&lookalike)));
+        assert_eq!(open_clear(&in_the_clear(&lookalike)).unwrap(), lookalike);
+
+        // A different key, or a changed byte, does not open it
+        assert!(unseal_with(&[4; 32], &sealed).is_err());
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(unseal_with(&key, &tampered).is_err());
+
+        // Each seal uses its own nonce
+        assert_ne!(seal_with(&key, &record).unwrap(), sealed);
+    }
+}
@@ -2,41 +2,14 @@ use crate::error::{Error, ErrorKind};
 use crate::storage::{EmptyDatabase, Storage};
 use heed::types::{Bytes, Unit};
 use nostr_types::{EventKind, Id, PublicKey, Unixtime};
-use std::sync::Mutex;
 
 // Author:Kind:Created(reversed):Id -> ()
 
-static EVENT_AKCI_INDEX_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
-static mut EVENT_AKCI_INDEX_DB: Option<EmptyDatabase> = None;
-
 impl Storage {
     pub(super) fn db_event_akci_index(&self) -> Result<EmptyDatabase, Error> {
-        unsafe {
-            if let Some(db) = EVENT_AKCI_INDEX_DB {
-                Ok(db)
-            } else {
-                // Lock.  This drops when anything returns.
-                let _lock = EVENT_AKCI_INDEX_DB_CREATE_LOCK.lock();
-
-                // In case of a race, check again
-                if let Some(db) = EVENT_AKCI_INDEX_DB {
-                    return Ok(db);
-                }
-
-                // Create it. We know that nobody else is doing this and that
-                // it cannot happen twice.
-                let mut txn = self.env.write_txn()?;
-                let db = self
-                    .env
-                    .database_options()
-                    .types::<Bytes, Unit>()
-                    .name("event_akci_index")
-                    .create(&mut txn)?;
-                txn.commit()?;
-                EVENT_AKCI_INDEX_DB = Some(db);
-                Ok(db)
-            }
-        }
+        Ok(self
+            .open_database("event_akci_index")?
+            .remap_types::<Bytes, Unit>())
     }
 }
 
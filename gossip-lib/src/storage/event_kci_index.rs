@@ -2,13 +2,9 @@ use crate::error::{Error, ErrorKind};
 use crate::storage::{EmptyDatabase, Storage};
 use heed::types::{Bytes, Unit};
 use nostr_types::{EventKind, Id, Unixtime};
-use std::sync::Mutex;
 
 // Kind:Created(reversed):Id -> ()
 
-static EVENT_KCI_INDEX_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
-static mut EVENT_KCI_INDEX_DB: Option<EmptyDatabase> = None;
-
 pub(super) const INDEXED_KINDS: [EventKind; 5] = [
     EventKind::Metadata,
     EventKind::ContactList,
@@ -19,32 +15,9 @@ pub(super) const INDEXED_KINDS: [EventKind; 5] = [
 
 impl Storage {
     pub(super) fn db_event_kci_index(&self) -> Result<EmptyDatabase, Error> {
-        unsafe {
-            if let Some(db) = EVENT_KCI_INDEX_DB {
-                Ok(db)
-            } else {
-                // Lock.  This drops when anything returns.
-                let _lock = EVENT_KCI_INDEX_DB_CREATE_LOCK.lock();
-
-                // In case of a race, check again
-                if let Some(db) = EVENT_KCI_INDEX_DB {
-                    return Ok(db);
-                }
-
-                // Create it. We know that nobody else is doing this and that
-                // it cannot happen twice.
-                let mut txn = self.env.write_txn()?;
-                let db = self
-                    .env
-                    .database_options()
-                    .types::<Bytes, Unit>()
-                    .name("event_kci_index")
-                    .create(&mut txn)?;
-                txn.commit()?;
-                EVENT_KCI_INDEX_DB = Some(db);
-                Ok(db)
-            }
-        }
+        Ok(self
+            .open_database("event_kci_index")?
+            .remap_types::<Bytes, Unit>())
     }
 }
 
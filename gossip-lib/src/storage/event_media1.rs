@@ -0,0 +1,12 @@
+use crate::error::Error;
+use crate::storage::{RawDatabase, Storage};
+
+// Id -> EventMedia (before alt text)
+//
+// No longer written (see event_media2). Cleared by migration 41.
+
+impl Storage {
+    pub(super) fn db_event_media1(&self) -> Result<RawDatabase, Error> {
+        self.open_database("event_media1")
+    }
+}
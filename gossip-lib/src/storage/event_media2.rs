@@ -0,0 +1,56 @@
+use crate::error::Error;
+use crate::event_media::EventMedia;
+use crate::storage::{CorruptRecords, RawDatabase, Storage};
+use heed::RwTxn;
+use nostr_types::Id;
+use speedy::{Readable, Writable};
+
+// Id -> EventMedia
+//   key: id.as_slice()
+//   val: media.write_to_vec() | EventMedia::read_from_buffer(val)
+//
+// Only events with media or links have a record.
+
+impl Storage {
+    pub(super) fn db_event_media2(&self) -> Result<RawDatabase, Error> {
+        self.open_database("event_media2")
+    }
+
+    pub(crate) fn get_event_media2_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_event_media2()?.len(&txn)?)
+    }
+
+    pub(crate) fn write_event_media2<'a>(
+        &'a self,
+        id: Id,
+        media: &EventMedia,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let bytes = media.write_to_vec()?;
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            if media.is_empty() {
+                let _ = self.db_event_media2()?.delete(txn, id.as_slice())?;
+            } else {
+                self.db_event_media2()?.put(txn, id.as_slice(), &bytes)?;
+            }
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    pub(crate) fn read_event_media2(&self, id: Id) -> Result<Option<EventMedia>, Error> {
+        let db = self.db_event_media2()?;
+        let mut corrupt = CorruptRecords::new("event_media2");
+        let txn = self.env.read_txn()?;
+        let output = match db.get(&txn, id.as_slice())? {
+            Some(val) => corrupt.check(id.as_slice(), val, EventMedia::read_from_buffer(val)),
+            None => None,
+        };
+        drop(txn);
+        self.quarantine_corrupt(db, corrupt, None)?;
+        Ok(output)
+    }
+}
@@ -0,0 +1,166 @@
+use crate::error::Error;
+use crate::event_media::{EventMedia, MediaGalleryItem};
+use crate::storage::{EmptyDatabase, Storage};
+use heed::types::{Bytes, Unit};
+use heed::RwTxn;
+use nostr_types::{Event, Id, PublicKey, Unixtime};
+use speedy::Readable;
+use std::ops::Bound;
+
+// Author:Created(reversed):Id -> ()
+// Hashtag:0:Created(reversed):Id -> ()
+//
+// Only events with media are indexed. Hashtags are lowercased and truncated so the
+// key fits. The media itself is in event_media.
+
+const MAX_HASHTAG_LEN: usize = 256;
+
+fn hashtag_prefix(hashtag: &str) -> Vec<u8> {
+    let hashtag = hashtag.to_lowercase();
+    let mut bytes = hashtag.as_bytes();
+    if bytes.len() > MAX_HASHTAG_LEN {
+        bytes = &bytes[..MAX_HASHTAG_LEN];
+    }
+    let mut prefix: Vec<u8> = Vec::with_capacity(bytes.len() + 1);
+    prefix.extend(bytes);
+    prefix.push(0);
+    prefix
+}
+
+fn make_key(prefix: &[u8], created_at: Unixtime, id: Id) -> Vec<u8> {
+    let mut key: Vec<u8> = Vec::with_capacity(prefix.len() + 8 + 32);
+    key.extend(prefix);
+    key.extend((u64::MAX - created_at.0 as u64).to_be_bytes().as_slice());
+    key.extend(id.0.as_slice());
+    key
+}
+
+impl Storage {
+    pub(super) fn db_event_media_by_author1(&self) -> Result<EmptyDatabase, Error> {
+        Ok(self
+            .open_database("event_media_by_author1")?
+            .remap_types::<Bytes, Unit>())
+    }
+
+    pub(super) fn db_event_media_by_hashtag1(&self) -> Result<EmptyDatabase, Error> {
+        Ok(self
+            .open_database("event_media_by_hashtag1")?
+            .remap_types::<Bytes, Unit>())
+    }
+
+    pub(crate) fn write_event_media_index1<'a>(
+        &'a self,
+        event: &Event,
+        media: &EventMedia,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        if media.attachments.is_empty() {
+            return Ok(());
+        }
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            let key = make_key(event.pubkey.as_slice(), event.created_at, event.id);
+            self.db_event_media_by_author1()?.put(txn, &key, &())?;
+            for hashtag in event.hashtags() {
+                if hashtag.is_empty() {
+                    continue;
+                }
+                let key = make_key(&hashtag_prefix(&hashtag), event.created_at, event.id);
+                self.db_event_media_by_hashtag1()?.put(txn, &key, &())?;
+            }
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    pub(crate) fn delete_event_media_index1<'a>(
+        &'a self,
+        event: &Event,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            let key = make_key(event.pubkey.as_slice(), event.created_at, event.id);
+            let _ = self.db_event_media_by_author1()?.delete(txn, &key)?;
+            for hashtag in event.hashtags() {
+                if hashtag.is_empty() {
+                    continue;
+                }
+                let key = make_key(&hashtag_prefix(&hashtag), event.created_at, event.id);
+                let _ = self.db_event_media_by_hashtag1()?.delete(txn, &key)?;
+            }
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    pub(crate) fn get_author_media1(
+        &self,
+        author: PublicKey,
+        until: Option<Unixtime>,
+        limit: usize,
+    ) -> Result<Vec<MediaGalleryItem>, Error> {
+        self.media_gallery1(
+            self.db_event_media_by_author1()?,
+            author.as_slice(),
+            until,
+            limit,
+        )
+    }
+
+    pub(crate) fn get_hashtag_media1(
+        &self,
+        hashtag: &str,
+        until: Option<Unixtime>,
+        limit: usize,
+    ) -> Result<Vec<MediaGalleryItem>, Error> {
+        self.media_gallery1(
+            self.db_event_media_by_hashtag1()?,
+            &hashtag_prefix(hashtag),
+            until,
+            limit,
+        )
+    }
+
+    fn media_gallery1(
+        &self,
+        db: EmptyDatabase,
+        prefix: &[u8],
+        until: Option<Unixtime>,
+        limit: usize,
+    ) -> Result<Vec<MediaGalleryItem>, Error> {
+        let txn = self.env.read_txn()?;
+        let start = make_key(prefix, until.unwrap_or(Unixtime(i64::MAX)), Id([0; 32]));
+        let mut output: Vec<MediaGalleryItem> = Vec::new();
+        let range = (Bound::Included(start.as_slice()), Bound::Unbounded);
+        for result in db.range(&txn, &range)? {
+            let (key, _) = result?;
+            if !key.starts_with(prefix) || key.len() != prefix.len() + 8 + 32 {
+                break;
+            }
+            let rest = &key[prefix.len()..];
+            let created_at =
+                Unixtime((u64::MAX - u64::from_be_bytes(rest[..8].try_into()?)) as i64);
+            let id = Id(rest[8..].try_into()?);
+
+            // Entries of deleted events linger until indexes are rebuilt
+            let media = match self.db_event_media()?.get(&txn, id.as_slice())? {
+                Some(bytes) => EventMedia::read_from_buffer(bytes)?,
+                None => continue,
+            };
+            for attachment in media.attachments {
+                output.push(MediaGalleryItem {
+                    id,
+                    created_at,
+                    attachment,
+                });
+            }
+            if output.len() >= limit {
+                output.truncate(limit);
+                break;
+            }
+        }
+        Ok(output)
+    }
+}
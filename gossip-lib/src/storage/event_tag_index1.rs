@@ -1,48 +1,21 @@
 use crate::error::Error;
 use crate::storage::{RawDatabase, Storage};
-use heed::{types::Bytes, DatabaseFlags, RwTxn};
+use heed::{DatabaseFlags, RwTxn};
 use nostr_types::{EventV2, EventV3, PublicKeyHex, TagV3};
-use std::sync::Mutex;
 
 // NOTE: "innerp" is a fake tag. We store events that reference a person internally under it.
-pub(super) const INDEXED_TAGS: [&str; 4] = ["a", "d", "p", "delegation"];
+pub(super) const INDEXED_TAGS: [&str; 5] = ["a", "d", "h", "p", "delegation"];
 
 // TagKey:QUOTE:TagValue -> Id
 // (dup keys, so multiple Ids per key)
 //   val: id.as_slice() | Id(val[0..32].try_into()?)
 
-static EVENT_TAG_INDEX1_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
-static mut EVENT_TAG_INDEX1_DB: Option<RawDatabase> = None;
-
 impl Storage {
     pub(super) fn db_event_tag_index1(&self) -> Result<RawDatabase, Error> {
-        unsafe {
-            if let Some(db) = EVENT_TAG_INDEX1_DB {
-                Ok(db)
-            } else {
-                // Lock.  This drops when anything returns.
-                let _lock = EVENT_TAG_INDEX1_DB_CREATE_LOCK.lock();
-
-                // In case of a race, check again
-                if let Some(db) = EVENT_TAG_INDEX1_DB {
-                    return Ok(db);
-                }
-
-                // Create it. We know that nobody else is doing this and that
-                // it cannot happen twice.
-                let mut txn = self.env.write_txn()?;
-                let db = self
-                    .env
-                    .database_options()
-                    .types::<Bytes, Bytes>()
-                    .flags(DatabaseFlags::DUP_SORT | DatabaseFlags::DUP_FIXED)
-                    .name("event_tag_index")
-                    .create(&mut txn)?;
-                txn.commit()?;
-                EVENT_TAG_INDEX1_DB = Some(db);
-                Ok(db)
-            }
-        }
+        self.open_database_with_flags(
+            "event_tag_index",
+            DatabaseFlags::DUP_SORT | DatabaseFlags::DUP_FIXED,
+        )
     }
 
     pub fn write_event2_tag_index1<'a>(
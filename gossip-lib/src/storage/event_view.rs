@@ -0,0 +1,86 @@
+use super::{RawDatabase, Storage};
+use crate::error::Error;
+use heed::RoTxn;
+use nostr_types::{Event, EventKind, Id, PublicKey, Unixtime};
+use speedy::Readable;
+
+/// An event as it is stored, borrowed from a read transaction. The fixed fields
+/// at the front of the record are read in place; the content and tags are only
+/// decoded by [decode](EventView::decode). This is much cheaper when most records
+/// are passed over on those fields.
+#[derive(Debug, Clone, Copy)]
+pub struct EventView<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> EventView<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> EventView<'a> {
+        EventView { bytes }
+    }
+
+    /// The id, if the record is well formed
+    pub fn id(&self) -> Option<Id> {
+        Event::get_id_from_speedy_bytes(self.bytes)
+    }
+
+    /// The kind, if the record is well formed
+    pub fn kind(&self) -> Option<EventKind> {
+        Event::get_kind_from_speedy_bytes(self.bytes)
+    }
+
+    /// The author, if the record is well formed
+    pub fn pubkey(&self) -> Option<PublicKey> {
+        Event::get_pubkey_from_speedy_bytes(self.bytes)
+    }
+
+    /// When it was created, if the record is well formed
+    pub fn created_at(&self) -> Option<Unixtime> {
+        Event::get_created_at_from_speedy_bytes(self.bytes)
+    }
+
+    /// The size of the stored record
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// If the stored record is empty (it never is for a stored event)
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Decode the whole event
+    pub fn decode(&self) -> Result<Event, Error> {
+        Ok(Event::read_from_buffer(self.bytes)?)
+    }
+}
+
+impl Storage {
+    /// The bytes of a record, borrowed from the transaction rather than copied
+    /// and decoded
+    pub(crate) fn read_record_lazy<'t>(
+        &self,
+        db: RawDatabase,
+        txn: &'t RoTxn,
+        key: &[u8],
+    ) -> Result<Option<&'t [u8]>, Error> {
+        Ok(db.get(txn, key)?)
+    }
+
+    /// Look at an event without decoding all of it. Returns None if we don't have
+    /// it, otherwise what `f` makes of its [EventView].
+    pub fn with_event_view<R, F>(&self, id: Id, f: F) -> Result<Option<R>, Error>
+    where
+        F: FnOnce(EventView<'_>) -> R,
+    {
+        if let Some(event) = self.read_queued_event(id) {
+            // Not written yet, so there are no stored bytes to borrow
+            let bytes = speedy::Writable::write_to_vec(&event)?;
+            return Ok(Some(f(EventView::new(&bytes))));
+        }
+
+        let txn = self.env.read_txn()?;
+        Ok(self
+            .read_record_lazy(self.db_events()?, &txn, id.as_slice())?
+            .map(|bytes| f(EventView::new(bytes))))
+    }
+}
@@ -1,47 +1,17 @@
 use crate::error::Error;
-use crate::storage::{RawDatabase, Storage};
-use heed::types::Bytes;
+use crate::event_media::EventMedia;
+use crate::storage::{RawDatabase, Storage, WriteClass};
 use heed::RwTxn;
 use nostr_types::{EventV3, Id};
 use speedy::{Readable, Writable};
-use std::sync::Mutex;
 
 // Id -> Event
 //   key: id.as_slice() | Id(val[0..32].try_into()?)
 //   val: event.write_to_vec() | Event::read_from_buffer(val)
 
-static EVENTS3_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
-static mut EVENTS3_DB: Option<RawDatabase> = None;
-
 impl Storage {
     pub(super) fn db_events3(&self) -> Result<RawDatabase, Error> {
-        unsafe {
-            if let Some(db) = EVENTS3_DB {
-                Ok(db)
-            } else {
-                // Lock.  This drops when anything returns.
-                let _lock = EVENTS3_DB_CREATE_LOCK.lock();
-
-                // In case of a race, check again
-                if let Some(db) = EVENTS3_DB {
-                    return Ok(db);
-                }
-
-                // Create it. We know that nobody else is doing this and that
-                // it cannot happen twice.
-                let mut txn = self.env.write_txn()?;
-                let db = self
-                    .env
-                    .database_options()
-                    .types::<Bytes, Bytes>()
-                    // no .flags needed
-                    .name("events3")
-                    .create(&mut txn)?;
-                txn.commit()?;
-                EVENTS3_DB = Some(db);
-                Ok(db)
-            }
-        }
+        self.open_database("events3")
     }
 
     pub(crate) fn write_event3<'a>(
@@ -54,6 +24,7 @@ impl Storage {
 
         let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
             self.db_events3()?.put(txn, event.id.as_slice(), &bytes)?;
+            self.meter_write("events3", bytes.len());
 
             // If giftwrap:
             //   Use the id and kind of the giftwrap,
@@ -85,17 +56,32 @@ impl Storage {
                 } // upstream bug
                 self.add_hashtag(&hashtag, event.id, Some(txn))?;
             }
+            let media = EventMedia::of_event(event);
+            self.write_event_media2(event.id, &media, Some(txn))?;
+            self.write_event_media_index1(event, &media, Some(txn))?;
             Ok(())
         };
 
-        write_transact!(self, rw_txn, f)
+        // Our own events may exist nowhere else yet, but relays can give us the rest
+        // again
+        let class = if self.read_setting_public_key() == Some(event.pubkey) {
+            WriteClass::Critical
+        } else {
+            WriteClass::Bulk
+        };
+
+        write_transact_as!(self, rw_txn, f, class)
     }
 
     pub(crate) fn read_event3(&self, id: Id) -> Result<Option<EventV3>, Error> {
         let txn = self.env.read_txn()?;
         match self.db_events3()?.get(&txn, id.as_slice())? {
             None => Ok(None),
-            Some(bytes) => Ok(Some(EventV3::read_from_buffer(bytes)?)),
+            Some(bytes) => {
+                Ok(Some(self.metered("events3", bytes, |b| {
+                    EventV3::read_from_buffer(b)
+                })?))
+            }
         }
     }
 
@@ -0,0 +1,103 @@
+use super::{RawDatabase, Storage};
+use crate::error::Error;
+use nostr_types::Unixtime;
+
+/// A table whose records expire, by a function giving when a record (key and
+/// value) expires, if it does. Tables opt in by being listed in `EXPIRING`, and
+/// [Storage::sweep_expired] then removes their records once they have expired.
+///
+/// Readers should still treat expired records as absent, as they linger until the
+/// next sweep.
+pub(crate) struct Expiry {
+    /// The name of its database, for logging
+    name: &'static str,
+
+    /// Its database
+    db: fn(&Storage) -> Result<RawDatabase, Error>,
+
+    /// When a record expires, None if it does not
+    expires_at: fn(&[u8], &[u8]) -> Option<Unixtime>,
+}
+
+impl Expiry {
+    pub(crate) const fn new(
+        name: &'static str,
+        db: fn(&Storage) -> Result<RawDatabase, Error>,
+        expires_at: fn(&[u8], &[u8]) -> Option<Unixtime>,
+    ) -> Expiry {
+        Expiry {
+            name,
+            db,
+            expires_at,
+        }
+    }
+}
+
+// The tables whose records expire
+const EXPIRING: &[Expiry] = &[
+    super::corrupt_records1::CORRUPT_RECORDS1_EXPIRY,
+    super::live_activities1::LIVE_ACTIVITIES1_EXPIRY,
+    super::nip46_permissions1::NIP46_PERMISSIONS1_EXPIRY,
+];
+
+impl Storage {
+    /// Remove the records that have expired from every table whose records expire.
+    /// This runs periodically; it returns how many were removed.
+    pub fn sweep_expired(&self) -> Result<usize, Error> {
+        let now = Unixtime::now().unwrap();
+        let mut count: usize = 0;
+        for expiry in EXPIRING {
+            let expires_at = expiry.expires_at;
+            let removed = self.delete_dangling((expiry.db)(self)?, |_txn, key, val| {
+                Ok(expires_at(key, val).map(|t| t <= now).unwrap_or(false))
+            })?;
+            if removed > 0 {
+                tracing::info!("Removed {} expired records from {}", removed, expiry.name);
+            }
+            count += removed;
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::nip46::Approval;
+    use crate::storage::test_dir::TestDir;
+    use nostr_types::{PrivateKey, Unixtime};
+
+    #[test]
+    fn test_sweep_expired() {
+        let dir = TestDir::new();
+        let storage = dir.open();
+
+        let peer = PrivateKey::generate().public_key();
+        let now = Unixtime::now().unwrap().0;
+        let approvals = [
+            ("ping", Approval::Until(Unixtime(now - 60))),
+            ("sign_event:1", Approval::Until(Unixtime(now + 3600))),
+            ("get_public_key", Approval::Always),
+        ];
+        for (permission, approval) in approvals.iter() {
+            storage
+                .write_nip46_permission1(peer, permission, *approval, None)
+                .unwrap();
+        }
+
+        // Only the approval that ran out goes
+        assert_eq!(storage.sweep_expired().unwrap(), 1);
+        assert!(storage
+            .read_nip46_permission1(peer, "ping")
+            .unwrap()
+            .is_none());
+        assert!(storage
+            .read_nip46_permission1(peer, "sign_event:1")
+            .unwrap()
+            .is_some());
+        assert!(storage
+            .read_nip46_permission1(peer, "get_public_key")
+            .unwrap()
+            .is_some());
+        assert_eq!(storage.sweep_expired().unwrap(), 0);
+    }
+}
@@ -0,0 +1,268 @@
+use super::relationships_by_addr2::relationships_by_addr2_from_value;
+use super::{RawDatabase, Storage, WriteClass};
+use crate::error::Error;
+use crate::relationship::RelationshipById;
+use heed::types::Bytes;
+use heed::RoTxn;
+use nostr_types::{Event, Id, Unixtime};
+use speedy::Readable;
+
+/// What a garbage collection pass removed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GarbageReport {
+    /// Relationships from events no longer stored, or to events that never arrived
+    pub relationships: usize,
+
+    /// Index entries (hashtags, tags, authors, kinds, media, viewed) of events no
+    /// longer stored
+    pub index_entries: usize,
+
+    /// Records of where events no longer stored were seen
+    pub seen_on: usize,
+}
+
+// Whether an entry of a table is garbage
+pub(super) type Dangling = dyn Fn(&RoTxn, &[u8], &[u8]) -> Result<bool, Error>;
+
+// Id at this offset of a key or value, if it is long enough
+pub(super) fn id_at(bytes: &[u8], offset: usize) -> Option<Id> {
+    Some(Id(bytes.get(offset..offset + 32)?.try_into().ok()?))
+}
+
+// Where the event id is in an entry of a table indexing events
+#[derive(Debug, Clone, Copy)]
+pub(super) enum IdAt {
+    Value,
+    KeyEnd,
+    Key,
+}
+
+impl IdAt {
+    pub(super) fn id(&self, key: &[u8], val: &[u8]) -> Option<Id> {
+        match self {
+            IdAt::Value => id_at(val, 0),
+            IdAt::KeyEnd => key.len().checked_sub(32).and_then(|o| id_at(key, o)),
+            IdAt::Key => id_at(key, 0),
+        }
+    }
+}
+
+impl Storage {
+    // The tables indexing events (by database name), whose entries are no use
+    // without their event
+    pub(super) fn event_indexes(&self) -> Result<Vec<(&'static str, RawDatabase, IdAt)>, Error> {
+        Ok(vec![
+            ("hashtags", self.db_hashtags()?, IdAt::Value),
+            ("event_tag_index", self.db_event_tag_index()?, IdAt::Value),
+            (
+                "event_akci_index",
+                self.db_event_akci_index()?,
+                IdAt::KeyEnd,
+            ),
+            ("event_kci_index", self.db_event_kci_index()?, IdAt::KeyEnd),
+            ("event_media2", self.db_event_media()?, IdAt::Key),
+            (
+                "event_media_by_author1",
+                self.db_event_media_by_author()?
+                    .remap_types::<Bytes, Bytes>(),
+                IdAt::KeyEnd,
+            ),
+            (
+                "event_media_by_hashtag1",
+                self.db_event_media_by_hashtag()?
+                    .remap_types::<Bytes, Bytes>(),
+                IdAt::KeyEnd,
+            ),
+            ("event_viewed", self.db_event_viewed()?, IdAt::Key),
+            ("rumors1", self.db_rumors()?, IdAt::Key),
+            ("zap_receipts1", self.db_zap_receipts()?, IdAt::KeyEnd),
+        ])
+    }
+
+    /// Remove entries of the auxiliary tables that point at events we don't have,
+    /// keeping them from growing faster than the events table does.
+    ///
+    /// Everything about an event that is no longer stored goes, except that
+    /// relationships to an event, and where it was seen, are kept until they are
+    /// older than `missing_since` (it may yet arrive). Deletions are always kept, so
+    /// that they still apply when it does.
+    pub fn collect_garbage(&self, missing_since: Unixtime) -> Result<GarbageReport, Error> {
+        let mut report = GarbageReport::default();
+        for (table, db, dangling) in self.garbage_checks(missing_since)? {
+            let count = self.delete_dangling(db, dangling)?;
+            match table {
+                "relationships_by_id2" | "relationships_by_addr2" => report.relationships += count,
+                "event_seen_on_relay" => report.seen_on += count,
+                _ => report.index_entries += count,
+            }
+        }
+        Ok(report)
+    }
+
+    // The auxiliary tables that point at events (by database name), each with what
+    // makes one of its entries garbage (see collect_garbage)
+    pub(super) fn garbage_checks(
+        &self,
+        missing_since: Unixtime,
+    ) -> Result<Vec<(&'static str, RawDatabase, Box<Dangling>)>, Error> {
+        let events = self.db_events()?;
+        let stored = move |txn: &RoTxn, id: Option<Id>| -> Result<bool, Error> {
+            match id {
+                Some(id) => Ok(events.get(txn, id.as_slice())?.is_some()),
+                None => Ok(false),
+            }
+        };
+        let created_before = move |txn: &RoTxn, id: Id, when: Unixtime| -> Result<bool, Error> {
+            Ok(match events.get(txn, id.as_slice())? {
+                Some(bytes) => Event::get_created_at_from_speedy_bytes(bytes)
+                    .map(|created_at| created_at < when)
+                    .unwrap_or(false),
+                None => true,
+            })
+        };
+
+        let mut checks: Vec<(&'static str, RawDatabase, Box<Dangling>)> = Vec::new();
+
+        // The SECOND id relates to the FIRST one
+        checks.push((
+            "relationships_by_id2",
+            self.db_relationships_by_id()?,
+            Box::new(move |txn: &RoTxn, key: &[u8], val: &[u8]| {
+                let related = match id_at(key, 32) {
+                    Some(id) => id,
+                    None => return Ok(true),
+                };
+                if !stored(txn, Some(related))? {
+                    return Ok(true);
+                }
+                if stored(txn, id_at(key, 0))? {
+                    return Ok(false);
+                }
+                if let Ok(RelationshipById::Deletes { .. }) =
+                    RelationshipById::read_from_buffer(val)
+                {
+                    return Ok(false);
+                }
+                created_before(txn, related, missing_since)
+            }),
+        ));
+        checks.push((
+            "relationships_by_addr2",
+            self.db_relationships_by_addr()?,
+            Box::new(move |txn: &RoTxn, _key: &[u8], val: &[u8]| {
+                match relationships_by_addr2_from_value(val) {
+                    Ok((_, related)) => Ok(!stored(txn, Some(related))?),
+                    Err(_) => Ok(true),
+                }
+            }),
+        ));
+
+        for (table, db, at) in self.event_indexes()? {
+            checks.push((
+                table,
+                db,
+                Box::new(move |txn: &RoTxn, key: &[u8], val: &[u8]| {
+                    Ok(!stored(txn, at.id(key, val))?)
+                }),
+            ));
+        }
+
+        checks.push((
+            "event_seen_on_relay",
+            self.db_event_seen_on_relay()?,
+            Box::new(move |txn: &RoTxn, key: &[u8], val: &[u8]| {
+                if stored(txn, id_at(key, 0))? {
+                    return Ok(false);
+                }
+                let when = val
+                    .get(..8)
+                    .map(|b| i64::from_be_bytes(b.try_into().unwrap()))
+                    .unwrap_or(0);
+                Ok(when < missing_since.0)
+            }),
+        ));
+
+        Ok(checks)
+    }
+
+    // The records of a table that `dangling` picks, scanned in a read transaction so
+    // as not to hold off writers meanwhile
+    pub(super) fn find_dangling<F>(
+        &self,
+        db: RawDatabase,
+        dangling: &F,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Error>
+    where
+        F: Fn(&RoTxn, &[u8], &[u8]) -> Result<bool, Error>,
+    {
+        let txn = self.env.read_txn()?;
+        let mut picks: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
+        for result in db.iter(&txn)? {
+            let (key, val) = result?;
+            if dangling(&txn, key, val)? {
+                picks.push((key.to_owned(), val.to_owned()));
+            }
+        }
+        Ok(picks)
+    }
+
+    // Delete the records of a table that `dangling` picks. The picks are checked
+    // again when they are deleted in case their event arrived in between.
+    pub(super) fn delete_dangling<F>(&self, db: RawDatabase, dangling: F) -> Result<usize, Error>
+    where
+        F: Fn(&RoTxn, &[u8], &[u8]) -> Result<bool, Error>,
+    {
+        let picks = self.find_dangling(db, &dangling)?;
+
+        if picks.is_empty() {
+            return Ok(0);
+        }
+
+        let mut txn = self.get_write_txn_as(WriteClass::Bulk)?;
+        let mut count: usize = 0;
+        for (key, val) in picks.iter() {
+            // (Without duplicate keys, LMDB ignores the value)
+            if dangling(&txn, key, val)? && db.delete_one_duplicate(&mut txn, key, val)? {
+                count += 1;
+            }
+        }
+        txn.commit()?;
+
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::storage::test_dir::TestDir;
+
+    #[test]
+    fn test_collect_garbage() {
+        let dir = TestDir::new();
+        let storage = dir.open();
+
+        let (target, related) = (Id([1; 32]), Id([2; 32]));
+        storage
+            .write_relationship_by_id(target, related, RelationshipById::RepliesTo, None)
+            .unwrap();
+        storage
+            .write_relationship_by_id(
+                target,
+                Id([3; 32]),
+                RelationshipById::Deletes {
+                    by: nostr_types::PrivateKey::generate().public_key(),
+                    reason: String::new(),
+                },
+                None,
+            )
+            .unwrap();
+        storage.mark_event_viewed(related, None).unwrap();
+
+        // None of these events are stored, so it all goes
+        let report = storage.collect_garbage(Unixtime(0)).unwrap();
+        assert_eq!(report.relationships, 2);
+        assert_eq!(report.index_entries, 1);
+        assert!(storage.find_relationships_by_id(target).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,104 @@
+use crate::error::Error;
+use crate::storage::types::Group1;
+use crate::storage::{CorruptRecords, RawDatabase, Storage};
+use heed::RwTxn;
+use nostr_types::RelayUrl;
+use speedy::{Readable, Writable};
+
+// Relay:Id -> Group1
+//   key: key!(relay.as_str() ++ b' ' ++ id)
+//   val: group.write_to_vec() | Group1::read_from_buffer(val)
+//
+// (Relay URLs never contain a space)
+
+fn groups1_key(relay: &RelayUrl, id: &str) -> Vec<u8> {
+    let mut key: Vec<u8> = relay.as_str().as_bytes().to_vec();
+    key.push(b' ');
+    key.extend(id.as_bytes());
+    key!(key.as_slice()).to_owned()
+}
+
+impl Storage {
+    pub(super) fn db_groups1(&self) -> Result<RawDatabase, Error> {
+        self.open_database("groups1")
+    }
+
+    pub(crate) fn write_group1<'a>(
+        &'a self,
+        group: &Group1,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let key = groups1_key(&group.relay, &group.id);
+        let bytes = group.write_to_vec()?;
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            self.db_groups1()?.put(txn, &key, &bytes)?;
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    pub(crate) fn read_group1(&self, relay: &RelayUrl, id: &str) -> Result<Option<Group1>, Error> {
+        let key = groups1_key(relay, id);
+        let db = self.db_groups1()?;
+        let mut corrupt = CorruptRecords::new("groups1");
+        let txn = self.env.read_txn()?;
+        let output = match db.get(&txn, &key)? {
+            Some(val) => corrupt.check(&key, val, Group1::read_from_buffer(val)),
+            None => None,
+        };
+        drop(txn);
+        self.quarantine_corrupt(db, corrupt, None)?;
+        Ok(output)
+    }
+
+    pub(crate) fn modify_group1<'a, M>(
+        &'a self,
+        relay: &RelayUrl,
+        id: &str,
+        mut modify: M,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error>
+    where
+        M: FnMut(&mut Group1),
+    {
+        let key = groups1_key(relay, id);
+
+        let mut f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            // A record that does not decode is quarantined, and is as if absent
+            let mut corrupt = CorruptRecords::new("groups1");
+            let existing = match self.db_groups1()?.get(txn, &key)? {
+                Some(val) => corrupt.check(&key, val, Group1::read_from_buffer(val)),
+                None => None,
+            };
+            self.quarantine_corrupt(self.db_groups1()?, corrupt, Some(&mut *txn))?;
+            let mut group = match existing {
+                Some(group) => group,
+                None => Group1::new(relay.to_owned(), id.to_owned()),
+            };
+            modify(&mut group);
+            let bytes = group.write_to_vec()?;
+            self.db_groups1()?.put(txn, &key, &bytes)?;
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    pub(crate) fn read_all_groups1(&self) -> Result<Vec<Group1>, Error> {
+        let db = self.db_groups1()?;
+        let mut corrupt = CorruptRecords::new("groups1");
+        let txn = self.env.read_txn()?;
+        let mut output: Vec<Group1> = Vec::new();
+        for result in db.iter(&txn)? {
+            let (key, val) = result?;
+            if let Some(group) = corrupt.check(key, val, Group1::read_from_buffer(val)) {
+                output.push(group);
+            }
+        }
+        drop(txn);
+        self.quarantine_corrupt(db, corrupt, None)?;
+        Ok(output)
+    }
+}
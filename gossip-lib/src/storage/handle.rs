@@ -0,0 +1,107 @@
+use super::Storage;
+use crate::error::{Error, ErrorKind};
+use std::ops::Deref;
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::time::Duration;
+
+/// The storage of the current profile. It derefs to that [Storage], so
+/// `GLOBALS.storage` is used just like one.
+///
+/// Switching profiles [swaps](StorageHandle::swap) in the storage of the other
+/// profile. The one swapped out is never freed: other threads may still be holding a
+/// reference into it, so it stays open (and unused) until gossip exits.
+pub struct StorageHandle {
+    current: AtomicPtr<Storage>,
+}
+
+impl StorageHandle {
+    pub(crate) fn new(storage: Storage) -> StorageHandle {
+        StorageHandle {
+            current: AtomicPtr::new(Box::into_raw(Box::new(storage))),
+        }
+    }
+
+    /// Make `storage` the one in use, returning the one it replaced
+    pub(crate) fn swap(&self, storage: Storage) -> &'static Storage {
+        let old = self
+            .current
+            .swap(Box::into_raw(Box::new(storage)), Ordering::AcqRel);
+
+        // SAFETY: it came from Box::into_raw and storages are never freed
+        unsafe { &*old }
+    }
+
+    /// Retire the current storage and make `storage` the one in use. Writes in
+    /// progress are drained first (for up to `drain`, or nothing is replaced), and
+    /// writes that start against the retired storage fail rather than being lost
+    /// with it.
+    pub(crate) fn replace(&self, storage: Storage, drain: Duration) -> Result<(), Error> {
+        let current: &Storage = self;
+        current.flush_event_batch()?;
+
+        let gate = match current.write_gate.try_write_for(drain) {
+            Some(gate) => gate,
+            None => {
+                return Err(ErrorKind::General(
+                    "Writes did not finish in time, so the database was not replaced".to_owned(),
+                )
+                .into())
+            }
+        };
+        current.retired.store(true, Ordering::Release);
+        let _ = self.swap(storage);
+        drop(gate);
+
+        self.init()
+    }
+}
+
+impl Deref for StorageHandle {
+    type Target = Storage;
+
+    fn deref(&self) -> &Storage {
+        // SAFETY: it came from Box::into_raw and storages are never freed
+        unsafe { &*self.current.load(Ordering::Acquire) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::storage::test_dir::{open_in, TestDir};
+    use nostr_types::Id;
+
+    #[test]
+    fn test_replace() {
+        let base = TestDir::new();
+        let (dir_a, dir_b, dir_c) = (base.subdir("a"), base.subdir("b"), base.subdir("c"));
+
+        let handle = StorageHandle::new(open_in(&dir_a));
+        let id = Id([3; 32]);
+        handle.write_star1(id, 1000, false, None).unwrap();
+        let old: &Storage = &handle;
+
+        // A write in progress that does not finish in time stops the replacement
+        let txn = handle.get_write_txn().unwrap();
+        let result = std::thread::scope(|s| {
+            s.spawn(|| {
+                let storage = open_in(&dir_c);
+                handle.replace(storage, Duration::from_millis(50))
+            })
+            .join()
+            .unwrap()
+        });
+        assert!(result.is_err());
+        drop(txn);
+        assert_eq!(handle.read_star1(id).unwrap(), Some((1000, false)));
+
+        let storage = open_in(&dir_b);
+        handle.replace(storage, Duration::from_secs(1)).unwrap();
+        assert_eq!(handle.read_star1(id).unwrap(), None);
+
+        // The retired storage refuses writes instead of silently taking them
+        assert!(old.write_star1(id, 2000, false, None).is_err());
+        handle.write_star1(id, 2000, false, None).unwrap();
+        assert_eq!(handle.read_star1(id).unwrap(), Some((2000, false)));
+    }
+}
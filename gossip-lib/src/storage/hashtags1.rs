@@ -1,46 +1,19 @@
 use crate::error::{Error, ErrorKind};
 use crate::storage::{RawDatabase, Storage};
-use heed::{types::Bytes, DatabaseFlags, RwTxn};
+use heed::{DatabaseFlags, RwTxn};
 use nostr_types::Id;
-use std::sync::Mutex;
 
 // Hashtag -> Id
 // (dup keys, so multiple Ids per hashtag)
 //   key: key!(hashtag.as_bytes())
 //   val: id.as_slice() | Id(val[0..32].try_into()?)
 
-static HASHTAGS1_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
-static mut HASHTAGS1_DB: Option<RawDatabase> = None;
-
 impl Storage {
     pub(super) fn db_hashtags1(&self) -> Result<RawDatabase, Error> {
-        unsafe {
-            if let Some(db) = HASHTAGS1_DB {
-                Ok(db)
-            } else {
-                // Lock.  This drops when anything returns.
-                let _lock = HASHTAGS1_DB_CREATE_LOCK.lock();
-
-                // In case of a race, check again
-                if let Some(db) = HASHTAGS1_DB {
-                    return Ok(db);
-                }
-
-                // Create it. We know that nobody else is doing this and that
-                // it cannot happen twice.
-                let mut txn = self.env.write_txn()?;
-                let db = self
-                    .env
-                    .database_options()
-                    .types::<Bytes, Bytes>()
-                    .flags(DatabaseFlags::DUP_SORT | DatabaseFlags::DUP_FIXED)
-                    .name("hashtags")
-                    .create(&mut txn)?;
-                txn.commit()?;
-                HASHTAGS1_DB = Some(db);
-                Ok(db)
-            }
-        }
+        self.open_database_with_flags(
+            "hashtags",
+            DatabaseFlags::DUP_SORT | DatabaseFlags::DUP_FIXED,
+        )
     }
 
     pub(crate) fn add_hashtag1<'a>(
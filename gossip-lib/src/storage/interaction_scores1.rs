@@ -0,0 +1,89 @@
+use crate::error::{Error, ErrorKind};
+use crate::storage::{CorruptRecords, RawDatabase, Storage};
+use heed::RwTxn;
+use nostr_types::PublicKey;
+
+// PublicKey -> (score, as of)
+//   key: pubkey.as_slice()
+//   val: score.to_be_bytes() ++ as_of.to_be_bytes()   (f64, i64)
+
+impl Storage {
+    pub(super) fn db_interaction_scores1(&self) -> Result<RawDatabase, Error> {
+        self.open_database("interaction_scores1")
+    }
+
+    pub(crate) fn modify_interaction_score1<'a, M>(
+        &'a self,
+        pubkey: PublicKey,
+        modify: M,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error>
+    where
+        M: Fn(Option<(f64, i64)>) -> (f64, i64),
+    {
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            let db = self.db_interaction_scores1()?;
+            // A corrupt record is started over
+            let old = match db.get(txn, pubkey.as_slice())?.map(decode) {
+                Some(Ok(score)) => Some(score),
+                Some(Err(e)) => {
+                    tracing::warn!("interaction_scores1 record replaced: {}", e);
+                    None
+                }
+                None => None,
+            };
+            let (score, as_of) = modify(old);
+            let mut val: Vec<u8> = Vec::with_capacity(16);
+            val.extend(score.to_be_bytes());
+            val.extend(as_of.to_be_bytes());
+            db.put(txn, pubkey.as_slice(), &val)?;
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    pub(crate) fn read_interaction_score1(
+        &self,
+        pubkey: PublicKey,
+    ) -> Result<Option<(f64, i64)>, Error> {
+        let txn = self.env.read_txn()?;
+        let db = self.db_interaction_scores1()?;
+        let bytes = match db.get(&txn, pubkey.as_slice())? {
+            Some(bytes) => bytes,
+            None => return Ok(None),
+        };
+        let mut corrupt = CorruptRecords::new("interaction_scores1");
+        let score = corrupt.check(pubkey.as_slice(), bytes, decode(bytes));
+        drop(txn);
+        self.quarantine_corrupt(db, corrupt, None)?;
+        Ok(score)
+    }
+
+    pub(crate) fn read_all_interaction_scores1(&self) -> Result<Vec<(PublicKey, f64, i64)>, Error> {
+        let txn = self.env.read_txn()?;
+        let mut output: Vec<(PublicKey, f64, i64)> = Vec::new();
+        let mut corrupt = CorruptRecords::new("interaction_scores1");
+        for result in self.db_interaction_scores1()?.iter(&txn)? {
+            let (key, val) = result?;
+            let decoded = PublicKey::from_bytes(key, true)
+                .map_err(Error::from)
+                .and_then(|pubkey| decode(val).map(|(score, as_of)| (pubkey, score, as_of)));
+            if let Some(score) = corrupt.check(key, val, decoded) {
+                output.push(score);
+            }
+        }
+        drop(txn);
+        self.quarantine_corrupt(self.db_interaction_scores1()?, corrupt, None)?;
+        Ok(output)
+    }
+}
+
+fn decode(bytes: &[u8]) -> Result<(f64, i64), Error> {
+    if bytes.len() < 16 {
+        return Err(ErrorKind::General("interaction score record is too short".to_owned()).into());
+    }
+    let score = f64::from_be_bytes(bytes[..8].try_into()?);
+    let as_of = i64::from_be_bytes(bytes[8..16].try_into()?);
+    Ok((score, as_of))
+}
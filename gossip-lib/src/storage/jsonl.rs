@@ -0,0 +1,241 @@
+use super::{CorruptRecords, Storage};
+use crate::error::Error;
+use nostr_types::{Event, Filter, Id, PublicKey, PublicKeyHex};
+use speedy::Readable;
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, Write};
+
+// Events written per transaction on import
+const IMPORT_BATCH_SIZE: usize = 1000;
+
+/// How an import of JSONL events went
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JsonlImport {
+    /// Events stored
+    pub imported: usize,
+
+    /// Events we already had (or that were in the input more than once)
+    pub duplicates: usize,
+
+    /// Lines that were not a validly signed event
+    pub invalid: usize,
+
+    /// Replaceable events that a newer one (stored, or in the input) replaces
+    pub superseded: usize,
+}
+
+// What a replaceable event replaces others by: kind, author and (for parameterized
+// replaceable events) parameter
+type ReplaceableKey = (u32, PublicKey, Option<String>);
+
+fn replaceable_key(event: &Event) -> Option<ReplaceableKey> {
+    if !event.kind.is_replaceable() {
+        return None;
+    }
+    let parameter = if event.kind.is_parameterized_replaceable() {
+        event.parameter()
+    } else {
+        None
+    };
+    Some((u32::from(event.kind), event.pubkey, parameter))
+}
+
+// Drop the replaceable events of a batch that a newer one in the same batch replaces,
+// as replace_event can't see the events written before it in the same transaction.
+// Returns how many were dropped.
+fn drop_superseded(batch: &mut Vec<Event>) -> usize {
+    let mut newest: HashMap<ReplaceableKey, &Event> = HashMap::new();
+    for event in batch.iter() {
+        if let Some(key) = replaceable_key(event) {
+            let entry = newest.entry(key).or_insert(event);
+            if event.created_at > entry.created_at {
+                *entry = event;
+            }
+        }
+    }
+    let keep: HashSet<Id> = newest.values().map(|event| event.id).collect();
+
+    let before = batch.len();
+    batch.retain(|event| !event.kind.is_replaceable() || keep.contains(&event.id));
+    before - batch.len()
+}
+
+impl Storage {
+    /// Write the stored events the filter matches to `writer`, one JSON event per line
+    /// (the format strfry and other relays import). Events are streamed out of a read
+    /// transaction as they are found, not collected first, so they come in storage
+    /// order and a `limit` on the filter just stops the export after that many.
+    /// Returns how many were written.
+    pub fn export_events_jsonl<W: Write>(
+        &self,
+        mut writer: W,
+        filter: &Filter,
+    ) -> Result<usize, Error> {
+        let txn = self.env.read_txn()?;
+        let mut corrupt = CorruptRecords::new("events3");
+        let limit = filter.limit.unwrap_or(usize::MAX);
+
+        let mut count = 0;
+        for result in self.db_events()?.iter(&txn)? {
+            if count >= limit {
+                break;
+            }
+            let (key, bytes) = result?;
+
+            // Screen on what can be read without decoding the whole event
+            if !filter.kinds.is_empty() {
+                match Event::get_kind_from_speedy_bytes(bytes) {
+                    Some(kind) if filter.kinds.contains(&kind) => (),
+                    _ => continue,
+                }
+            }
+            if !filter.authors.is_empty() {
+                match Event::get_pubkey_from_speedy_bytes(bytes) {
+                    Some(pk) if filter.authors.contains(&PublicKeyHex::from(pk)) => (),
+                    _ => continue,
+                }
+            }
+
+            let event = match corrupt.check(
+                key,
+                bytes,
+                self.metered("events3", bytes, |b| Event::read_from_buffer(b)),
+            ) {
+                Some(event) => event,
+                None => continue,
+            };
+            if !filter.event_matches(&event) {
+                continue;
+            }
+
+            serde_json::to_writer(&mut writer, &event)?;
+            writer.write_all(b"\n")?;
+            count += 1;
+        }
+        writer.flush()?;
+
+        drop(txn);
+        self.quarantine_corrupt(self.db_events()?, corrupt, None)?;
+
+        Ok(count)
+    }
+
+    /// Store the events read from `reader`, one JSON event per line. Blank lines are
+    /// skipped, events that do not verify are counted as invalid and events we already
+    /// have are counted as duplicates. A replaceable event is only stored if it is the
+    /// newest we have of it, replacing older ones. Relationships of the new events are
+    /// worked out when the relationships are next rebuilt (flagged here, done at
+    /// startup).
+    pub fn import_events_jsonl<R: BufRead>(&self, reader: R) -> Result<JsonlImport, Error> {
+        let mut import = JsonlImport::default();
+
+        // Ids of the batch being gathered. Those of earlier batches are stored, and
+        // has_event catches them.
+        let mut seen: HashSet<Id> = HashSet::new();
+        let mut batch: Vec<Event> = Vec::new();
+
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let event: Event = match serde_json::from_str(line) {
+                Ok(event) => event,
+                Err(_) => {
+                    import.invalid += 1;
+                    continue;
+                }
+            };
+            if event.verify(None).is_err() {
+                import.invalid += 1;
+                continue;
+            }
+            if !seen.insert(event.id) || self.has_event(event.id)? {
+                import.duplicates += 1;
+                continue;
+            }
+            batch.push(event);
+            if batch.len() >= IMPORT_BATCH_SIZE {
+                self.write_event_batch(&mut batch, &mut import)?;
+                seen.clear();
+            }
+        }
+        self.write_event_batch(&mut batch, &mut import)?;
+
+        Ok(import)
+    }
+
+    fn write_event_batch(
+        &self,
+        batch: &mut Vec<Event>,
+        import: &mut JsonlImport,
+    ) -> Result<(), Error> {
+        import.superseded += drop_superseded(batch);
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let mut txn = self.write_txn()?;
+        for event in batch.drain(..) {
+            if event.kind.is_replaceable() {
+                if self.replace_event(&event, Some(&mut *txn))? {
+                    import.imported += 1;
+                } else {
+                    import.superseded += 1;
+                }
+            } else {
+                self.write_event(&event, Some(&mut *txn))?;
+                import.imported += 1;
+            }
+        }
+        self.set_flag_rebuild_relationships_needed(true, Some(&mut *txn))?;
+        txn.commit()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nostr_types::{EventKind, PreEvent, PrivateKey, Unixtime};
+
+    fn make_event(key: &PrivateKey, kind: EventKind, created_at: i64) -> Event {
+        let pre_event = PreEvent {
+            pubkey: key.public_key(),
+            created_at: Unixtime(created_at),
+            kind,
+            tags: vec![],
+            content: String::new(),
+        };
+        let id = pre_event.hash().unwrap();
+        Event {
+            id,
+            pubkey: pre_event.pubkey,
+            created_at: pre_event.created_at,
+            kind: pre_event.kind,
+            sig: key.sign_id(id).unwrap(),
+            content: pre_event.content,
+            tags: pre_event.tags,
+        }
+    }
+
+    #[test]
+    fn test_drop_superseded() {
+        let key = PrivateKey::generate();
+        let other = PrivateKey::generate();
+        let newer = make_event(&key, EventKind::Metadata, 200);
+        let older = make_event(&key, EventKind::Metadata, 100);
+        let others = make_event(&other, EventKind::Metadata, 50);
+        let note = make_event(&key, EventKind::TextNote, 10);
+
+        // Whichever comes first, only the newest of each replaceable event stays
+        let mut batch = vec![newer.clone(), note.clone(), older.clone(), others.clone()];
+        assert_eq!(drop_superseded(&mut batch), 1);
+        let ids: Vec<Id> = batch.iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![newer.id, note.id, others.id]);
+
+        let mut batch = vec![older, newer.clone()];
+        assert_eq!(drop_superseded(&mut batch), 1);
+        assert_eq!(batch[0].id, newer.id);
+    }
+}
@@ -0,0 +1,104 @@
+use super::expiry::Expiry;
+use crate::error::Error;
+use crate::storage::types::LiveActivity1;
+use crate::storage::{CorruptRecords, RawDatabase, Storage};
+use heed::RwTxn;
+use nostr_types::{PublicKey, Unixtime};
+use speedy::{Readable, Writable};
+
+// Host:D -> LiveActivity1
+//   key: key!(host.as_bytes() ++ d.as_bytes())
+//   val: live_activity.write_to_vec() | LiveActivity1::read_from_buffer(val)
+//
+// Activities expire LIVE_ACTIVITY_DAYS after their last update, start or end,
+// whichever is latest.
+
+// How long an activity is kept after it was last heard of or scheduled
+const LIVE_ACTIVITY_DAYS: i64 = 30;
+
+pub(super) const LIVE_ACTIVITIES1_EXPIRY: Expiry =
+    Expiry::new("live_activities1", Storage::db_live_activities1, expires_at);
+
+fn expires_at(_key: &[u8], val: &[u8]) -> Option<Unixtime> {
+    let activity = LiveActivity1::read_from_buffer(val).ok()?;
+    let latest = [Some(activity.updated_at), activity.starts, activity.ends]
+        .into_iter()
+        .flatten()
+        .max()?;
+    Some(Unixtime(latest.0 + LIVE_ACTIVITY_DAYS * 86400))
+}
+
+fn live_activities1_key(host: &PublicKey, d: &str) -> Vec<u8> {
+    let mut key: Vec<u8> = host.as_bytes().to_vec();
+    key.extend(d.as_bytes());
+    key!(key.as_slice()).to_owned()
+}
+
+impl Storage {
+    pub(super) fn db_live_activities1(&self) -> Result<RawDatabase, Error> {
+        self.open_database("live_activities1")
+    }
+
+    /// Write unless we already have a later announcement of the same activity
+    pub(crate) fn write_live_activity1<'a>(
+        &'a self,
+        activity: &LiveActivity1,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let key = live_activities1_key(&activity.host, &activity.d);
+        let bytes = activity.write_to_vec()?;
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            // A record that does not decode is quarantined, and is as if absent
+            let mut corrupt = CorruptRecords::new("live_activities1");
+            let existing = match self.db_live_activities1()?.get(txn, &key)? {
+                Some(val) => corrupt.check(&key, val, LiveActivity1::read_from_buffer(val)),
+                None => None,
+            };
+            self.quarantine_corrupt(self.db_live_activities1()?, corrupt, Some(&mut *txn))?;
+            if let Some(existing) = existing {
+                if existing.updated_at > activity.updated_at {
+                    return Ok(());
+                }
+            }
+            self.db_live_activities1()?.put(txn, &key, &bytes)?;
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    pub(crate) fn read_live_activity1(
+        &self,
+        host: &PublicKey,
+        d: &str,
+    ) -> Result<Option<LiveActivity1>, Error> {
+        let key = live_activities1_key(host, d);
+        let db = self.db_live_activities1()?;
+        let mut corrupt = CorruptRecords::new("live_activities1");
+        let txn = self.env.read_txn()?;
+        let output = match db.get(&txn, &key)? {
+            Some(val) => corrupt.check(&key, val, LiveActivity1::read_from_buffer(val)),
+            None => None,
+        };
+        drop(txn);
+        self.quarantine_corrupt(db, corrupt, None)?;
+        Ok(output)
+    }
+
+    pub(crate) fn read_all_live_activities1(&self) -> Result<Vec<LiveActivity1>, Error> {
+        let db = self.db_live_activities1()?;
+        let mut corrupt = CorruptRecords::new("live_activities1");
+        let txn = self.env.read_txn()?;
+        let mut output: Vec<LiveActivity1> = Vec::new();
+        for result in db.iter(&txn)? {
+            let (key, val) = result?;
+            if let Some(activity) = corrupt.check(key, val, LiveActivity1::read_from_buffer(val)) {
+                output.push(activity);
+            }
+        }
+        drop(txn);
+        self.quarantine_corrupt(db, corrupt, None)?;
+        Ok(output)
+    }
+}
@@ -0,0 +1,130 @@
+use crate::error::Error;
+use crate::storage::{EmptyDatabase, RawDatabase, Storage};
+use heed::types::{Bytes, Unit};
+use heed::RwTxn;
+use sha2::Digest;
+
+// UrlKey -> (ContentHash, Url)
+//   key: sha256(url)   (also the name of the url's file in the fetcher cache)
+//   val: content_hash ++ url.as_bytes()
+//
+// ContentHash:UrlKey -> ()
+//   key: content_hash ++ sha256(url)
+//
+// The content hash is the sha256 of the fetched bytes, which are kept once in the
+// fetcher's blob directory no matter how many urls served them.
+
+// The key of a url in the media hash tables
+fn media_url_key(url: &str) -> [u8; 32] {
+    let mut hasher = sha2::Sha256::new();
+    hasher.update(url.as_bytes());
+    hasher.finalize().into()
+}
+
+impl Storage {
+    pub(super) fn db_media_hashes1(&self) -> Result<RawDatabase, Error> {
+        self.open_database("media_hashes1")
+    }
+
+    pub(super) fn db_media_hash_urls1(&self) -> Result<EmptyDatabase, Error> {
+        Ok(self
+            .open_database("media_hash_urls1")?
+            .remap_types::<Bytes, Unit>())
+    }
+
+    pub(crate) fn write_media_hash1<'a>(
+        &'a self,
+        url: &str,
+        content_hash: [u8; 32],
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let url_key = media_url_key(url);
+        let mut val: Vec<u8> = Vec::with_capacity(32 + url.len());
+        val.extend(content_hash.as_slice());
+        val.extend(url.as_bytes());
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            // Drop the reference from content this url served before
+            let old_hash: Option<Vec<u8>> = self
+                .db_media_hashes1()?
+                .get(txn, url_key.as_slice())?
+                .filter(|old| old.len() >= 32 && old[..32] != content_hash)
+                .map(|old| old[..32].to_owned());
+            if let Some(mut old_key) = old_hash {
+                old_key.extend(url_key.as_slice());
+                let _ = self.db_media_hash_urls1()?.delete(txn, &old_key);
+            }
+
+            self.db_media_hashes1()?
+                .put(txn, url_key.as_slice(), &val)?;
+            let mut key: Vec<u8> = content_hash.to_vec();
+            key.extend(url_key.as_slice());
+            self.db_media_hash_urls1()?.put(txn, &key, &())?;
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    pub(crate) fn read_media_hash1(&self, url: &str) -> Result<Option<[u8; 32]>, Error> {
+        let url_key = media_url_key(url);
+        let txn = self.env.read_txn()?;
+        Ok(
+            match self.db_media_hashes1()?.get(&txn, url_key.as_slice())? {
+                Some(val) if val.len() >= 32 => Some(val[..32].try_into().unwrap()),
+                _ => None,
+            },
+        )
+    }
+
+    pub(crate) fn read_media_hash_urls1(
+        &self,
+        content_hash: [u8; 32],
+    ) -> Result<Vec<String>, Error> {
+        let txn = self.env.read_txn()?;
+        let mut output: Vec<String> = Vec::new();
+        for result in self
+            .db_media_hash_urls1()?
+            .prefix_iter(&txn, content_hash.as_slice())?
+        {
+            let (key, _) = result?;
+            let url_key = &key[32..];
+            if let Some(val) = self.db_media_hashes1()?.get(&txn, url_key)? {
+                if val.len() > 32 {
+                    output.push(String::from_utf8_lossy(&val[32..]).into_owned());
+                }
+            }
+        }
+        Ok(output)
+    }
+
+    pub(crate) fn media_hash_in_use1(&self, content_hash: [u8; 32]) -> Result<bool, Error> {
+        let txn = self.env.read_txn()?;
+        let mut iter = self
+            .db_media_hash_urls1()?
+            .prefix_iter(&txn, content_hash.as_slice())?;
+        Ok(iter.next().is_some())
+    }
+
+    pub(crate) fn delete_media_hash1<'a>(
+        &'a self,
+        url_key: [u8; 32],
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            let content_hash: Option<Vec<u8>> = self
+                .db_media_hashes1()?
+                .get(txn, url_key.as_slice())?
+                .filter(|val| val.len() >= 32)
+                .map(|val| val[..32].to_owned());
+            if let Some(mut key) = content_hash {
+                key.extend(url_key.as_slice());
+                let _ = self.db_media_hash_urls1()?.delete(txn, &key);
+            }
+            let _ = self.db_media_hashes1()?.delete(txn, url_key.as_slice());
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+}
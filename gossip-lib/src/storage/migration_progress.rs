@@ -0,0 +1,209 @@
+use crate::error::Error;
+use crate::storage::{RawDatabase, Storage};
+use heed::RwTxn;
+use std::ops::Bound;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+// How many records a chunked migration handles in each write transaction
+const MIGRATION_CHUNK: u64 = 5000;
+
+/// Where a long storage migration is at. Watch it with
+/// [Storage::migration_progress].
+#[derive(Debug, Clone)]
+pub struct MigrationProgress {
+    /// What is being done
+    pub step: String,
+
+    /// How many records (or migration levels) are done
+    pub processed: u64,
+
+    /// How many there are in all
+    pub total: u64,
+
+    started: Instant,
+
+    // Where this run started from, if it resumed an interrupted one
+    resumed_at: u64,
+}
+
+impl MigrationProgress {
+    /// How much is done, from 0.0 to 1.0
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        (self.processed as f32 / self.total as f32).min(1.0)
+    }
+
+    /// How much longer it should take, at the pace of this run so far
+    pub fn eta(&self) -> Option<Duration> {
+        let done = self.processed.saturating_sub(self.resumed_at);
+        if done == 0 {
+            return None;
+        }
+        let remaining = self.total.saturating_sub(self.processed);
+        let per_record = self.started.elapsed().as_secs_f64() / done as f64;
+        Some(Duration::from_secs_f64(per_record * remaining as f64))
+    }
+}
+
+impl std::fmt::Display for MigrationProgress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} of {} ({:.0}%)",
+            self.step,
+            self.processed,
+            self.total,
+            self.fraction() * 100.0
+        )?;
+        if let Some(eta) = self.eta() {
+            write!(f, ", about {}s left", eta.as_secs())?;
+        }
+        Ok(())
+    }
+}
+
+lazy_static! {
+    static ref PROGRESS: watch::Sender<Option<MigrationProgress>> = watch::channel(None).0;
+}
+
+impl Storage {
+    /// The progress of long storage migrations, as they happen. It is `None` when
+    /// none is running.
+    pub fn migration_progress(&self) -> watch::Receiver<Option<MigrationProgress>> {
+        PROGRESS.subscribe()
+    }
+
+    pub(super) fn start_migration_step(&self, step: &str, processed: u64, total: u64) {
+        tracing::info!("{step}...");
+        PROGRESS.send_replace(Some(MigrationProgress {
+            step: step.to_owned(),
+            processed,
+            total,
+            started: Instant::now(),
+            resumed_at: processed,
+        }));
+    }
+
+    pub(super) fn report_migration_progress(&self, processed: u64) {
+        PROGRESS.send_modify(|progress| {
+            if let Some(progress) = progress {
+                progress.processed = processed;
+            }
+        });
+    }
+
+    pub(super) fn end_migration_step(&self) {
+        PROGRESS.send_replace(None);
+    }
+
+    /// If a chunked migration was interrupted, how far it got
+    pub(super) fn migration_cursor(
+        &self,
+        cursor_key: &[u8],
+    ) -> Result<Option<(u64, Vec<u8>)>, Error> {
+        let txn = self.env.read_txn()?;
+        match self.general.get(&txn, cursor_key)? {
+            Some(bytes) if bytes.len() >= 8 => {
+                let processed = u64::from_be_bytes(bytes[..8].try_into()?);
+                Ok(Some((processed, bytes[8..].to_owned())))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Run `f` on every record (key and value) of `db`, in write transactions of a
+    /// few thousand records each so progress is reported as it goes. How far it got
+    /// is saved under `cursor_key` with each transaction, and a run that was
+    /// interrupted resumes after the last record it committed. The progress of a
+    /// step this runs within (a migration level) is reported again afterwards.
+    pub(super) fn migrate_in_chunks<'a, F>(
+        &'a self,
+        db: RawDatabase,
+        cursor_key: &[u8],
+        step: &str,
+        mut f: F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(&[u8], &[u8], &mut RwTxn<'a>) -> Result<(), Error>,
+    {
+        let outer = PROGRESS.borrow().clone();
+
+        let total = {
+            let txn = self.env.read_txn()?;
+            db.len(&txn)?
+        };
+        let (mut processed, mut cursor) = match self.migration_cursor(cursor_key)? {
+            Some((processed, key)) => (processed, Some(key)),
+            None => (0, None),
+        };
+        self.start_migration_step(step, processed, total);
+
+        loop {
+            let loop_txn = self.env.read_txn()?;
+            let mut txn = self.write_txn()?;
+            let start = match &cursor {
+                Some(key) => Bound::Excluded(key.as_slice()),
+                None => Bound::Unbounded,
+            };
+            let mut count: u64 = 0;
+            let mut last: Option<Vec<u8>> = None;
+            for result in db.range(&loop_txn, &(start, Bound::Unbounded))? {
+                let (key, val) = result?;
+                f(key, val, &mut *txn)?;
+                count += 1;
+                last = Some(key.to_owned());
+                if count >= MIGRATION_CHUNK {
+                    break;
+                }
+            }
+
+            match last {
+                Some(key) => {
+                    processed += count;
+                    let mut bytes: Vec<u8> = processed.to_be_bytes().to_vec();
+                    bytes.extend(key.as_slice());
+                    self.general.put(&mut *txn, cursor_key, &bytes)?;
+                    txn.commit()?;
+                    self.report_migration_progress(processed);
+                    cursor = Some(key);
+                }
+                None => {
+                    self.general.delete(&mut *txn, cursor_key)?;
+                    txn.commit()?;
+                    break;
+                }
+            }
+        }
+
+        PROGRESS.send_replace(outer);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_migration_progress_eta() {
+        let mut progress = MigrationProgress {
+            step: "Testing".to_owned(),
+            processed: 40,
+            total: 100,
+            started: Instant::now() - Duration::from_secs(10),
+            resumed_at: 20,
+        };
+
+        // 20 records took 10 seconds, so the other 60 take 30
+        let eta = progress.eta().unwrap().as_secs_f64();
+        assert!((eta - 30.0).abs() < 0.5);
+        assert!((progress.fraction() - 0.4).abs() < 1e-6);
+
+        // Nothing done in this run yet
+        progress.processed = 20;
+        assert!(progress.eta().is_none());
+    }
+}
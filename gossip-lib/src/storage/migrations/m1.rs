@@ -11,30 +11,24 @@ impl Storage {
         Ok(())
     }
 
-    pub(super) fn m1_migrate<'a>(&'a self, prefix: &str, txn: &mut RwTxn<'a>) -> Result<(), Error> {
-        let read_txn = self.env.read_txn()?;
-        let total = self.db_events1()?.len(&read_txn)?;
-
-        // Info message
-        tracing::info!("{prefix}: Computing and storing event relationships for {total} events...");
-
-        // Migrate
-        let mut count = 0;
-        let event_txn = self.env.read_txn()?;
-        for result in self.db_events1()?.iter(&event_txn)? {
-            let pair = result?;
-            let event = EventV1::read_from_buffer(pair.1)?;
-            self.m1_process_relationships_of_event(&event, txn)?;
-            count += 1;
-            for checkpoint in &[10, 20, 30, 40, 50, 60, 70, 80, 90] {
-                if count == checkpoint * total / 100 {
-                    tracing::info!("{}% done", checkpoint);
-                }
-            }
-        }
-
-        tracing::info!("syncing...");
+    pub(super) fn m1_migrate_chunks(&self, prefix: &str) -> Result<(), Error> {
+        self.migrate_in_chunks(
+            self.db_events1()?,
+            &Self::migration_cursor_key(1),
+            &format!("{prefix}: Computing and storing event relationships"),
+            |_key, val, txn| {
+                let event = EventV1::read_from_buffer(val)?;
+                self.m1_process_relationships_of_event(&event, txn)
+            },
+        )
+    }
 
+    pub(super) fn m1_migrate<'a>(
+        &'a self,
+        _prefix: &str,
+        _txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        // Done in m1_migrate_chunks
         Ok(())
     }
 
@@ -13,28 +13,24 @@ impl Storage {
         Ok(())
     }
 
+    pub(super) fn m11_migrate_chunks(&self, prefix: &str) -> Result<(), Error> {
+        self.migrate_in_chunks(
+            self.db_events1()?,
+            &Self::migration_cursor_key(11),
+            &format!("{prefix}: populating event tag index"),
+            |_key, val, txn| {
+                let event = EventV1::read_from_buffer(val)?;
+                self.m11_write_event_tag_index1_event1(&event, txn)
+            },
+        )
+    }
+
     pub(super) fn m11_migrate<'a>(
         &'a self,
-        prefix: &str,
-        txn: &mut RwTxn<'a>,
+        _prefix: &str,
+        _txn: &mut RwTxn<'a>,
     ) -> Result<(), Error> {
-        // Info message
-        tracing::info!("{prefix}: populating event tag index...");
-
-        // Migrate
-        self.m11_populate_event_tag_index(txn)?;
-
-        Ok(())
-    }
-
-    fn m11_populate_event_tag_index<'a>(&'a self, txn: &mut RwTxn<'a>) -> Result<(), Error> {
-        let loop_txn = self.env.read_txn()?;
-        for result in self.db_events1()?.iter(&loop_txn)? {
-            let (_key, val) = result?;
-            let event = EventV1::read_from_buffer(val)?;
-            self.m11_write_event_tag_index1_event1(&event, txn)?;
-        }
-
+        // Done in m11_migrate_chunks
         Ok(())
     }
 
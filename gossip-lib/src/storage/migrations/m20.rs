@@ -115,7 +115,7 @@ impl Storage {
                 metadata.event_private_len = {
                     let mut private_len: Option<usize> = None;
                     if !matches!(list, PersonList1::Followed) && GLOBALS.identity.is_unlocked() {
-                        if let Ok(bytes) = GLOBALS.identity.decrypt(&pk, &event.content) {
+                        if let Ok(bytes) = GLOBALS.identity.decrypt_locally(&pk, &event.content) {
                             if let Ok(vectags) = serde_json::from_str::<Vec<TagV2>>(&bytes) {
                                 private_len = Some(vectags.len());
                             }
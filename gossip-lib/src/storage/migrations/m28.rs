@@ -9,31 +9,29 @@ impl Storage {
         Ok(())
     }
 
+    pub(super) fn m28_migrate_chunks(&self, prefix: &str) -> Result<(), Error> {
+        self.migrate_in_chunks(
+            self.db_people2()?,
+            &Self::migration_cursor_key(28),
+            &format!("{prefix}: fixing empty petnames"),
+            |key, val, txn| {
+                let mut person: Person2 = serde_json::from_slice(val)?;
+                if person.petname == Some("".to_string()) {
+                    person.petname = None;
+                    let bytes = serde_json::to_vec(&person)?;
+                    self.db_people2()?.put(txn, key, &bytes)?;
+                }
+                Ok(())
+            },
+        )
+    }
+
     pub(super) fn m28_migrate<'a>(
         &'a self,
-        prefix: &str,
-        txn: &mut RwTxn<'a>,
+        _prefix: &str,
+        _txn: &mut RwTxn<'a>,
     ) -> Result<(), Error> {
-        // Info message
-        tracing::info!("{prefix}: fixing empty petnames...");
-
-        // Migrate
-        self.m28_fix_empty_petnames(txn)?;
-
-        Ok(())
-    }
-
-    fn m28_fix_empty_petnames<'a>(&'a self, txn: &mut RwTxn<'a>) -> Result<(), Error> {
-        let loop_txn = self.env.read_txn()?;
-        for result in self.db_people2()?.iter(&loop_txn)? {
-            let (key, val) = result?;
-            let mut person: Person2 = serde_json::from_slice(val)?;
-            if person.petname == Some("".to_string()) {
-                person.petname = None;
-                let bytes = serde_json::to_vec(&person)?;
-                self.db_people2()?.put(txn, key, &bytes)?;
-            }
-        }
+        // Done in m28_migrate_chunks
         Ok(())
     }
 }
@@ -9,34 +9,31 @@ impl Storage {
         Ok(())
     }
 
+    pub(super) fn m29_migrate_chunks(&self, prefix: &str) -> Result<(), Error> {
+        self.migrate_in_chunks(
+            self.db_events()?,
+            &Self::migration_cursor_key(29),
+            &format!("{prefix}: Building new event indexes"),
+            |_key, bytes, txn| {
+                let event = Event::read_from_buffer(bytes)?;
+                self.write_event_akci_index(
+                    event.pubkey,
+                    event.kind,
+                    event.created_at,
+                    event.id,
+                    Some(txn),
+                )?;
+                self.write_event_kci_index(event.kind, event.created_at, event.id, Some(txn))
+            },
+        )
+    }
+
     pub(super) fn m29_migrate<'a>(
         &'a self,
-        prefix: &str,
-        txn: &mut RwTxn<'a>,
+        _prefix: &str,
+        _txn: &mut RwTxn<'a>,
     ) -> Result<(), Error> {
-        // Info message
-        tracing::info!("{prefix}: Building new event indexes...");
-
-        // Migrate
-        self.m29_build_new_event_indexes(txn)?;
-
-        Ok(())
-    }
-
-    fn m29_build_new_event_indexes<'a>(&'a self, txn: &mut RwTxn<'a>) -> Result<(), Error> {
-        let loop_txn = self.env.read_txn()?;
-        for result in self.db_events()?.iter(&loop_txn)? {
-            let (_, bytes) = result?;
-            let event = Event::read_from_buffer(bytes)?;
-            self.write_event_akci_index(
-                event.pubkey,
-                event.kind,
-                event.created_at,
-                event.id,
-                Some(txn),
-            )?;
-            self.write_event_kci_index(event.kind, event.created_at, event.id, Some(txn))?;
-        }
+        // Done in m29_migrate_chunks
         Ok(())
     }
 }
@@ -0,0 +1,51 @@
+use crate::error::Error;
+use crate::storage::types::{ListAutoPublish1, PersonListMetadata4};
+use crate::storage::Storage;
+use heed::RwTxn;
+
+impl Storage {
+    pub(super) fn m37_trigger(&self) -> Result<(), Error> {
+        let _ = self.db_person_lists_metadata3()?;
+        let _ = self.db_person_lists_metadata4()?;
+        Ok(())
+    }
+
+    pub(super) fn m37_migrate<'a>(
+        &'a self,
+        prefix: &str,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        // Info message
+        tracing::info!("{prefix}: migrating person list metadata (auto-publish)...");
+
+        // Migrate
+        self.m37_migrate_person_list_metadata(txn)?;
+
+        Ok(())
+    }
+
+    fn m37_migrate_person_list_metadata<'a>(&'a self, txn: &mut RwTxn<'a>) -> Result<(), Error> {
+        let mut old = self.get_all_person_list_metadata3()?;
+        for (list, metadata3) in old.drain(..) {
+            let metadata4 = PersonListMetadata4 {
+                dtag: metadata3.dtag,
+                title: metadata3.title,
+                last_edit_time: metadata3.last_edit_time,
+                event_created_at: metadata3.event_created_at,
+                event_public_len: metadata3.event_public_len,
+                event_private_len: metadata3.event_private_len,
+                favorite: metadata3.favorite,
+                order: metadata3.order,
+                private: metadata3.private,
+                len: metadata3.len,
+                auto_publish: ListAutoPublish1::Manual,
+            };
+            self.set_person_list_metadata4(list, &metadata4, Some(txn))?;
+        }
+
+        // Clear the old database
+        self.db_person_lists_metadata3()?.clear(txn)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+use crate::error::Error;
+use crate::storage::types::{ListRefresh1, PersonListMetadata5};
+use crate::storage::Storage;
+use heed::RwTxn;
+
+impl Storage {
+    pub(super) fn m38_trigger(&self) -> Result<(), Error> {
+        let _ = self.db_person_lists_metadata4()?;
+        let _ = self.db_person_lists_metadata5()?;
+        Ok(())
+    }
+
+    pub(super) fn m38_migrate<'a>(
+        &'a self,
+        prefix: &str,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        // Info message
+        tracing::info!("{prefix}: migrating person list metadata (refresh cadence)...");
+
+        // Migrate
+        self.m38_migrate_person_list_metadata(txn)?;
+
+        Ok(())
+    }
+
+    fn m38_migrate_person_list_metadata<'a>(&'a self, txn: &mut RwTxn<'a>) -> Result<(), Error> {
+        let mut old = self.get_all_person_list_metadata4()?;
+        for (list, metadata4) in old.drain(..) {
+            let metadata5 = PersonListMetadata5 {
+                dtag: metadata4.dtag,
+                title: metadata4.title,
+                last_edit_time: metadata4.last_edit_time,
+                event_created_at: metadata4.event_created_at,
+                event_public_len: metadata4.event_public_len,
+                event_private_len: metadata4.event_private_len,
+                favorite: metadata4.favorite,
+                order: metadata4.order,
+                private: metadata4.private,
+                len: metadata4.len,
+                auto_publish: metadata4.auto_publish,
+                refresh: ListRefresh1::Live,
+            };
+            self.set_person_list_metadata5(list, &metadata5, Some(txn))?;
+        }
+
+        // Clear the old database
+        self.db_person_lists_metadata4()?.clear(txn)?;
+
+        Ok(())
+    }
+}
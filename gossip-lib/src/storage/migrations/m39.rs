@@ -0,0 +1,29 @@
+use crate::error::Error;
+use crate::storage::Storage;
+use heed::RwTxn;
+
+impl Storage {
+    pub(super) fn m39_trigger(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    pub(super) fn m39_migrate<'a>(
+        &'a self,
+        prefix: &str,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        // Info message
+        tracing::info!("{prefix}: flagging need to rebuild indexes (event media)...");
+
+        // Migrate
+        self.m39_rebuild_indexes(txn)?;
+
+        Ok(())
+    }
+
+    fn m39_rebuild_indexes<'a>(&'a self, txn: &mut RwTxn<'a>) -> Result<(), Error> {
+        self.set_flag_rebuild_indexes_needed(true, Some(txn))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+use crate::error::Error;
+use crate::storage::Storage;
+use heed::RwTxn;
+
+impl Storage {
+    pub(super) fn m41_trigger(&self) -> Result<(), Error> {
+        let _ = self.db_event_media1()?;
+        let _ = self.db_event_media2()?;
+        Ok(())
+    }
+
+    pub(super) fn m41_migrate<'a>(
+        &'a self,
+        prefix: &str,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        // Info message
+        tracing::info!("{prefix}: moving event media to a table with alt text...");
+
+        // Migrate
+        self.m41_clear_event_media1(txn)?;
+        self.m41_rebuild_indexes(txn)?;
+
+        Ok(())
+    }
+
+    fn m41_clear_event_media1<'a>(&'a self, txn: &mut RwTxn<'a>) -> Result<(), Error> {
+        // Its records are rebuilt into event_media2
+        self.db_event_media1()?.clear(txn)?;
+
+        Ok(())
+    }
+
+    fn m41_rebuild_indexes<'a>(&'a self, txn: &mut RwTxn<'a>) -> Result<(), Error> {
+        self.set_flag_rebuild_indexes_needed(true, Some(txn))?;
+
+        Ok(())
+    }
+}
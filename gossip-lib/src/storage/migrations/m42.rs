@@ -0,0 +1,47 @@
+use crate::error::Error;
+use crate::storage::types::{Person2, Person3};
+use crate::storage::{CorruptRecords, Storage};
+use heed::RwTxn;
+
+impl Storage {
+    pub(super) fn m42_trigger(&self) -> Result<(), Error> {
+        let _ = self.db_people2()?;
+        let _ = self.db_people3()?;
+        Ok(())
+    }
+
+    pub(super) fn m42_migrate_chunks(&self, prefix: &str) -> Result<(), Error> {
+        // We don't know when existing follows started, so followed_since
+        // is only set for follows from here on
+        self.migrate_in_chunks(
+            self.db_people2()?,
+            &Self::migration_cursor_key(42),
+            &format!("{prefix}: Migrating Person records"),
+            |key, val, txn| {
+                let mut corrupt = CorruptRecords::new("people2");
+                match corrupt.check(key, val, serde_json::from_slice::<Person2>(val)) {
+                    Some(person2) => {
+                        let person3: Person3 = person2.into();
+                        self.write_person3(&person3, Some(txn))
+                    }
+                    // Keep the record we couldn't read, rather than clearing it
+                    // away with the rest
+                    None => self.quarantine_corrupt(self.db_people2()?, corrupt, Some(txn)),
+                }
+            },
+        )
+    }
+
+    pub(super) fn m42_migrate<'a>(
+        &'a self,
+        _prefix: &str,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        // The records were migrated by m42_migrate_chunks
+
+        // Clear the old database
+        self.db_people2()?.clear(txn)?;
+
+        Ok(())
+    }
+}
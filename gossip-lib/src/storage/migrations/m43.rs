@@ -0,0 +1,30 @@
+use crate::error::Error;
+use crate::storage::Storage;
+use heed::RwTxn;
+
+impl Storage {
+    pub(super) fn m43_trigger(&self) -> Result<(), Error> {
+        let _ = self.db_people3()?;
+        Ok(())
+    }
+
+    pub(super) fn m43_migrate<'a>(
+        &'a self,
+        prefix: &str,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        // Info message
+        tracing::info!("{prefix}: building the person nip05 index...");
+
+        // Migrate
+        self.m43_rebuild_indexes(txn)?;
+
+        Ok(())
+    }
+
+    fn m43_rebuild_indexes<'a>(&'a self, txn: &mut RwTxn<'a>) -> Result<(), Error> {
+        self.set_flag_rebuild_indexes_needed(true, Some(txn))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+use crate::error::Error;
+use crate::storage::types::Relay5;
+use crate::storage::Storage;
+use heed::RwTxn;
+
+impl Storage {
+    pub(super) fn m44_trigger(&self) -> Result<(), Error> {
+        let _ = self.db_relays4()?;
+        let _ = self.db_relays5()?;
+        Ok(())
+    }
+
+    pub(super) fn m44_migrate<'a>(
+        &'a self,
+        prefix: &str,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        // Info message
+        tracing::info!("{prefix}: Migrating Relay records...");
+
+        // Migrate
+        self.m44_migrate_relay_records(txn)?;
+
+        Ok(())
+    }
+
+    fn m44_migrate_relay_records<'a>(&'a self, txn: &mut RwTxn<'a>) -> Result<(), Error> {
+        let mut old = self.filter_relays4(|_| true)?;
+        for relay4 in old.drain(..) {
+            let usage_bits = relay4.get_usage_bits();
+            let relay5 = Relay5 {
+                url: relay4.url,
+                success_count: relay4.success_count,
+                failure_count: relay4.failure_count,
+                last_connected_at: relay4.last_connected_at,
+                last_general_eose_at: relay4.last_general_eose_at,
+                rank: relay4.rank,
+                hidden: relay4.hidden,
+                usage_bits,
+                nip11: relay4.nip11,
+                last_attempt_nip11: relay4.last_attempt_nip11,
+                allow_connect: relay4.allow_connect,
+                allow_auth: relay4.allow_auth,
+                latency_samples: relay4.latency_samples,
+                latency_p50_ms: relay4.latency_p50_ms,
+                latency_p90_ms: relay4.latency_p90_ms,
+                last_latency_probe_at: relay4.last_latency_probe_at,
+                post_pow: relay4.post_pow,
+                post_client_tag: relay4.post_client_tag,
+                post_max_content_length: relay4.post_max_content_length,
+                post_auto_split: relay4.post_auto_split,
+                capabilities: None,
+            };
+            self.write_relay5(&relay5, Some(txn))?;
+        }
+
+        // Clear the old database
+        self.db_relays4()?.clear(txn)?;
+
+        Ok(())
+    }
+}
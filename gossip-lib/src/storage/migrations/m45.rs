@@ -0,0 +1,63 @@
+use crate::error::Error;
+use crate::storage::types::Relay6;
+use crate::storage::Storage;
+use heed::RwTxn;
+
+impl Storage {
+    pub(super) fn m45_trigger(&self) -> Result<(), Error> {
+        let _ = self.db_relays5()?;
+        let _ = self.db_relays6()?;
+        Ok(())
+    }
+
+    pub(super) fn m45_migrate<'a>(
+        &'a self,
+        prefix: &str,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        // Info message
+        tracing::info!("{prefix}: Migrating Relay records...");
+
+        // Migrate
+        self.m45_migrate_relay_records(txn)?;
+
+        Ok(())
+    }
+
+    fn m45_migrate_relay_records<'a>(&'a self, txn: &mut RwTxn<'a>) -> Result<(), Error> {
+        let mut old = self.filter_relays5(|_| true)?;
+        for relay5 in old.drain(..) {
+            let usage_bits = relay5.get_usage_bits();
+            let relay6 = Relay6 {
+                url: relay5.url,
+                success_count: relay5.success_count,
+                failure_count: relay5.failure_count,
+                last_connected_at: relay5.last_connected_at,
+                last_general_eose_at: relay5.last_general_eose_at,
+                rank: relay5.rank,
+                hidden: relay5.hidden,
+                usage_bits,
+                nip11: relay5.nip11,
+                last_attempt_nip11: relay5.last_attempt_nip11,
+                allow_connect: relay5.allow_connect,
+                allow_auth: relay5.allow_auth,
+                latency_samples: relay5.latency_samples,
+                latency_p50_ms: relay5.latency_p50_ms,
+                latency_p90_ms: relay5.latency_p90_ms,
+                last_latency_probe_at: relay5.last_latency_probe_at,
+                post_pow: relay5.post_pow,
+                post_client_tag: relay5.post_client_tag,
+                post_max_content_length: relay5.post_max_content_length,
+                post_auto_split: relay5.post_auto_split,
+                capabilities: relay5.capabilities,
+                monitor_reports: Vec::new(),
+            };
+            self.write_relay6(&relay6, Some(txn))?;
+        }
+
+        // Clear the old database
+        self.db_relays5()?.clear(txn)?;
+
+        Ok(())
+    }
+}
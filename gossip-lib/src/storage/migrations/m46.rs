@@ -0,0 +1,53 @@
+use crate::error::Error;
+use crate::storage::types::PersonListMetadata6;
+use crate::storage::Storage;
+use heed::RwTxn;
+
+impl Storage {
+    pub(super) fn m46_trigger(&self) -> Result<(), Error> {
+        let _ = self.db_person_lists_metadata5()?;
+        let _ = self.db_person_lists_metadata6()?;
+        Ok(())
+    }
+
+    pub(super) fn m46_migrate<'a>(
+        &'a self,
+        prefix: &str,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        // Info message
+        tracing::info!("{prefix}: migrating person list metadata (smart lists)...");
+
+        // Migrate
+        self.m46_migrate_person_list_metadata(txn)?;
+
+        Ok(())
+    }
+
+    fn m46_migrate_person_list_metadata<'a>(&'a self, txn: &mut RwTxn<'a>) -> Result<(), Error> {
+        let mut old = self.get_all_person_list_metadata5()?;
+        for (list, metadata5) in old.drain(..) {
+            let metadata6 = PersonListMetadata6 {
+                dtag: metadata5.dtag,
+                title: metadata5.title,
+                last_edit_time: metadata5.last_edit_time,
+                event_created_at: metadata5.event_created_at,
+                event_public_len: metadata5.event_public_len,
+                event_private_len: metadata5.event_private_len,
+                favorite: metadata5.favorite,
+                order: metadata5.order,
+                private: metadata5.private,
+                len: metadata5.len,
+                auto_publish: metadata5.auto_publish,
+                refresh: metadata5.refresh,
+                smart: None,
+            };
+            self.set_person_list_metadata6(list, &metadata6, Some(txn))?;
+        }
+
+        // Clear the old database
+        self.db_person_lists_metadata5()?.clear(txn)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+use crate::error::Error;
+use crate::nip46::{Approval, Nip46Server};
+use crate::storage::Storage;
+use heed::RwTxn;
+use speedy::Readable;
+
+impl Storage {
+    pub(super) fn m47_trigger(&self) -> Result<(), Error> {
+        let _ = self.db_nip46servers2()?;
+        let _ = self.db_nip46_permissions1()?;
+        Ok(())
+    }
+
+    pub(super) fn m47_migrate<'a>(
+        &'a self,
+        prefix: &str,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        // Info message
+        tracing::info!("{prefix}: migrating NIP-46 approvals into permissions...");
+
+        // Migrate
+        self.m47_migrate_nip46_approvals(txn)?;
+
+        Ok(())
+    }
+
+    fn m47_migrate_nip46_approvals<'a>(&'a self, txn: &mut RwTxn<'a>) -> Result<(), Error> {
+        let mut servers: Vec<Nip46Server> = Vec::new();
+        for result in self.db_nip46servers2()?.iter(txn)? {
+            let (_, val) = result?;
+            if let Ok(server) = Nip46Server::read_from_buffer(val) {
+                servers.push(server);
+            }
+        }
+
+        for server in servers.iter() {
+            let decided = [
+                ("sign_event", server.sign_approval),
+                ("nip04_encrypt", server.encrypt_approval),
+                ("nip44_encrypt", server.encrypt_approval),
+                ("nip04_decrypt", server.decrypt_approval),
+                ("nip44_decrypt", server.decrypt_approval),
+            ];
+            for (permission, approval) in decided {
+                // Once and Ask are asked again
+                if matches!(
+                    approval,
+                    Approval::Always | Approval::Until(_) | Approval::None
+                ) {
+                    self.write_nip46_permission1(
+                        server.peer_pubkey,
+                        permission,
+                        approval,
+                        Some(&mut *txn),
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
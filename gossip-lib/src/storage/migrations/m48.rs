@@ -0,0 +1,30 @@
+use crate::error::Error;
+use crate::storage::Storage;
+use heed::RwTxn;
+
+impl Storage {
+    pub(super) fn m48_trigger(&self) -> Result<(), Error> {
+        let _ = self.db_zap_receipts1()?;
+        Ok(())
+    }
+
+    pub(super) fn m48_migrate<'a>(
+        &'a self,
+        prefix: &str,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        // Info message
+        tracing::info!("{prefix}: flagging need to rebuild relationships (zap receipts)...");
+
+        // Migrate
+        self.m48_index_zap_receipts(txn)?;
+
+        Ok(())
+    }
+
+    fn m48_index_zap_receipts<'a>(&'a self, txn: &mut RwTxn<'a>) -> Result<(), Error> {
+        // Rebuilding relationships validates and indexes the zap receipts we have
+        self.set_flag_rebuild_relationships_needed(true, Some(txn))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,30 @@
+use crate::error::Error;
+use crate::storage::Storage;
+use heed::RwTxn;
+
+impl Storage {
+    pub(super) fn m49_trigger(&self) -> Result<(), Error> {
+        let _ = self.db_event_tag_index1()?;
+        Ok(())
+    }
+
+    pub(super) fn m49_migrate<'a>(
+        &'a self,
+        prefix: &str,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        // Info message
+        tracing::info!("{prefix}: flagging need to rebuild indexes (group 'h' tags)...");
+
+        // Migrate
+        self.m49_index_h_tags(txn)?;
+
+        Ok(())
+    }
+
+    fn m49_index_h_tags<'a>(&'a self, txn: &mut RwTxn<'a>) -> Result<(), Error> {
+        // The tag index now covers 'h' tags, which place events in groups (NIP-29)
+        self.set_flag_rebuild_indexes_needed(true, Some(txn))?;
+        Ok(())
+    }
+}
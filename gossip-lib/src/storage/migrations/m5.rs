@@ -1,7 +1,7 @@
 use crate::error::Error;
 use crate::storage::Storage;
 use heed::RwTxn;
-use nostr_types::{EventV1, Id, Signature};
+use nostr_types::{EventV1, Signature};
 use speedy::Readable;
 
 impl Storage {
@@ -10,31 +10,27 @@ impl Storage {
         Ok(())
     }
 
-    pub(super) fn m5_migrate<'a>(&'a self, prefix: &str, txn: &mut RwTxn<'a>) -> Result<(), Error> {
-        // Info message
-        tracing::info!("{prefix}: deleting decrypted rumors...");
-
-        // Migrate
-        self.m5_delete_rumors(txn)?;
-
-        Ok(())
+    pub(super) fn m5_migrate_chunks(&self, prefix: &str) -> Result<(), Error> {
+        self.migrate_in_chunks(
+            self.db_events1()?,
+            &Self::migration_cursor_key(5),
+            &format!("{prefix}: deleting decrypted rumors"),
+            |_key, val, txn| {
+                let event = EventV1::read_from_buffer(val)?;
+                if event.sig == Signature::zeroes() {
+                    self.db_events1()?.delete(txn, event.id.as_slice())?;
+                }
+                Ok(())
+            },
+        )
     }
 
-    fn m5_delete_rumors<'a>(&'a self, txn: &mut RwTxn<'a>) -> Result<(), Error> {
-        let mut ids: Vec<Id> = Vec::new();
-        let iter = self.db_events1()?.iter(txn)?;
-        for result in iter {
-            let (_key, val) = result?;
-            let event = EventV1::read_from_buffer(val)?;
-            if event.sig == Signature::zeroes() {
-                ids.push(event.id);
-            }
-        }
-
-        for id in ids {
-            self.db_events1()?.delete(txn, id.as_slice())?;
-        }
-
+    pub(super) fn m5_migrate<'a>(
+        &'a self,
+        _prefix: &str,
+        _txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        // Done in m5_migrate_chunks
         Ok(())
     }
 }
@@ -0,0 +1,70 @@
+use crate::error::Error;
+use crate::storage::encryption::{in_the_clear, LEGACY_SEALED_MAGIC};
+use crate::storage::{RawDatabase, Storage};
+use heed::RwTxn;
+
+// Sealed records, before they had a format byte, were this long at least
+const LEGACY_SEALED_MIN_LEN: usize = 4 + 24;
+
+impl Storage {
+    pub(super) fn m50_trigger(&self) -> Result<(), Error> {
+        let _ = self.db_app_data1()?;
+        let _ = self.db_mute_log1()?;
+        let _ = self.db_rumors1()?;
+        Ok(())
+    }
+
+    pub(super) fn m50_migrate<'a>(
+        &'a self,
+        prefix: &str,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        // Info message
+        tracing::info!("{prefix}: giving sealable records a format byte...");
+
+        // Migrate
+        self.m50_frame_sealable_records(txn)?;
+
+        Ok(())
+    }
+
+    fn m50_frame_sealable_records<'a>(&'a self, txn: &mut RwTxn<'a>) -> Result<(), Error> {
+        let tables: Vec<(RawDatabase, fn(&[u8]) -> bool)> = vec![
+            (
+                self.db_app_data1()?,
+                crate::storage::app_data1::app_data1_is_private,
+            ),
+            (self.db_mute_log1()?, |_| true),
+            (self.db_rumors1()?, |_| true),
+            (self.general, |key| key == b"nwc_connection"),
+        ];
+
+        for (db, sealable) in tables {
+            let mut records: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
+            for result in db.iter(txn)? {
+                let (key, val) = result?;
+                if sealable(key) {
+                    records.push((key.to_owned(), m50_framed(val)));
+                }
+            }
+            for (key, val) in records {
+                db.put(txn, &key, &val)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// The legacy magic becomes the format byte of a sealed record, and everything else
+// is in the clear
+fn m50_framed(val: &[u8]) -> Vec<u8> {
+    if val.len() >= LEGACY_SEALED_MIN_LEN && val.starts_with(LEGACY_SEALED_MAGIC) {
+        let mut framed: Vec<u8> = Vec::with_capacity(val.len() - 3);
+        framed.push(1); // SEALED_V1
+        framed.extend_from_slice(&val[LEGACY_SEALED_MAGIC.len()..]);
+        framed
+    } else {
+        in_the_clear(val)
+    }
+}
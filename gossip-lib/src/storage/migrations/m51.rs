@@ -0,0 +1,41 @@
+use crate::error::Error;
+use crate::storage::{CorruptRecords, Storage};
+use heed::RwTxn;
+
+impl Storage {
+    pub(super) fn m51_trigger(&self) -> Result<(), Error> {
+        let _ = self.db_quarantine1()?;
+        let _ = self.db_events3()?;
+        let _ = self.db_corrupt_records1()?;
+        Ok(())
+    }
+
+    pub(super) fn m51_migrate<'a>(
+        &'a self,
+        prefix: &str,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        // Info message
+        tracing::info!("{prefix}: moving quarantined events into the corrupt records...");
+
+        // Migrate
+        self.m51_move_quarantined_events(txn)?;
+
+        Ok(())
+    }
+
+    fn m51_move_quarantined_events<'a>(&'a self, txn: &mut RwTxn<'a>) -> Result<(), Error> {
+        let events = self.db_events3()?;
+        let mut corrupt = CorruptRecords::new("events3");
+        for result in self.db_quarantine1()?.iter(txn)? {
+            let (key, reason) = result?;
+            if let Some(val) = events.get(txn, key)? {
+                corrupt.push(key, val, String::from_utf8_lossy(reason).into_owned());
+            }
+        }
+        self.quarantine_corrupt(events, corrupt, Some(txn))?;
+        self.db_quarantine1()?.clear(txn)?;
+
+        Ok(())
+    }
+}
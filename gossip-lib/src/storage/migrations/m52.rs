@@ -0,0 +1,33 @@
+use crate::error::Error;
+use crate::storage::Storage;
+use heed::RwTxn;
+
+impl Storage {
+    pub(super) fn m52_trigger(&self) -> Result<(), Error> {
+        let _ = self.db_zap_receipts1()?;
+        let _ = self.db_zap_signers1()?;
+        Ok(())
+    }
+
+    pub(super) fn m52_migrate<'a>(
+        &'a self,
+        prefix: &str,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        // Info message
+        tracing::info!("{prefix}: reindexing zap receipts with their signers...");
+
+        // Migrate
+        self.m52_reindex_zap_receipts(txn)?;
+
+        Ok(())
+    }
+
+    fn m52_reindex_zap_receipts<'a>(&'a self, txn: &mut RwTxn<'a>) -> Result<(), Error> {
+        // The records don't say who signed the receipt, so they can't be verified.
+        // Rebuilding relationships indexes them again, with the signer.
+        self.db_zap_receipts1()?.clear(txn)?;
+        self.set_flag_rebuild_relationships_needed(true, Some(txn))?;
+        Ok(())
+    }
+}
@@ -10,61 +10,58 @@ impl Storage {
         Ok(())
     }
 
-    pub(super) fn m8_migrate<'a>(&'a self, prefix: &str, txn: &mut RwTxn<'a>) -> Result<(), Error> {
-        // Info message
-        tracing::info!("{prefix}: populating missing last_fetched data...");
-
-        // Migrate
-        self.m8_populate_last_fetched(txn)?;
-
-        Ok(())
-    }
-
-    fn m8_populate_last_fetched<'a>(&'a self, txn: &mut RwTxn<'a>) -> Result<(), Error> {
-        let total = self.get_event_seen_on_relay1_len()?;
-        let mut count = 0;
-
+    pub(super) fn m8_migrate_chunks(&self, prefix: &str) -> Result<(), Error> {
         // Since we failed to properly collect person_relay.last_fetched, we will
         // use seen_on data to reconstruct it
-        let loop_txn = self.env.read_txn()?;
-
-        for result in self.db_event_seen_on_relay1()?.iter(&loop_txn)? {
-            let (key, val) = result?;
+        self.migrate_in_chunks(
+            self.db_event_seen_on_relay1()?,
+            &Self::migration_cursor_key(8),
+            &format!("{prefix}: populating missing last_fetched data"),
+            |key, val, txn| self.m8_populate_last_fetched(key, val, txn),
+        )
+    }
 
-            // Extract out the data
-            let id = Id(key[..32].try_into().unwrap());
-            let url = match RelayUrl::try_from_str(std::str::from_utf8(&key[32..])?) {
-                Ok(url) => url,
-                Err(_) => continue, // skip if relay url is bad. We will prune these in the future.
-            };
+    pub(super) fn m8_migrate<'a>(
+        &'a self,
+        _prefix: &str,
+        _txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        // Done in m8_migrate_chunks
+        Ok(())
+    }
 
-            let time = u64::from_be_bytes(val[..8].try_into()?);
+    fn m8_populate_last_fetched<'a>(
+        &'a self,
+        key: &[u8],
+        val: &[u8],
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        // Extract out the data
+        let id = Id(key[..32].try_into().unwrap());
+        let url = match RelayUrl::try_from_str(std::str::from_utf8(&key[32..])?) {
+            Ok(url) => url,
+            Err(_) => return Ok(()), // skip if relay url is bad. We will prune these in the future.
+        };
 
-            // Read event to get the person
-            if let Some(event) = self.read_event(id)? {
-                // Read (or create) person_relay
-                let (mut pr, update) = match self.read_person_relay1(event.pubkey, &url)? {
-                    Some(pr) => match pr.last_fetched {
-                        Some(lf) => (pr, lf < time),
-                        None => (pr, true),
-                    },
-                    None => {
-                        let pr = PersonRelay1::new(event.pubkey, url.clone());
-                        (pr, true)
-                    }
-                };
+        let time = u64::from_be_bytes(val[..8].try_into()?);
 
-                if update {
-                    pr.last_fetched = Some(time);
-                    self.write_person_relay1(&pr, Some(txn))?;
+        // Read event to get the person
+        if let Some(event) = self.read_event(id)? {
+            // Read (or create) person_relay
+            let (mut pr, update) = match self.read_person_relay1(event.pubkey, &url)? {
+                Some(pr) => match pr.last_fetched {
+                    Some(lf) => (pr, lf < time),
+                    None => (pr, true),
+                },
+                None => {
+                    let pr = PersonRelay1::new(event.pubkey, url.clone());
+                    (pr, true)
                 }
-            }
+            };
 
-            count += 1;
-            for checkpoint in &[10, 20, 30, 40, 50, 60, 70, 80, 90] {
-                if count == checkpoint * total / 100 {
-                    tracing::info!("{}% done", checkpoint);
-                }
+            if update {
+                pr.last_fetched = Some(time);
+                self.write_person_relay1(&pr, Some(txn))?;
             }
         }
 
@@ -14,37 +14,42 @@ impl Storage {
         Ok(())
     }
 
-    pub(super) fn m9_migrate<'a>(&'a self, prefix: &str, txn: &mut RwTxn<'a>) -> Result<(), Error> {
-        // Info message
-        tracing::info!("{prefix}: rebuilding event indices...");
+    pub(super) fn m9_migrate_chunks(&self, prefix: &str) -> Result<(), Error> {
+        let cursor_key = Self::migration_cursor_key(9);
 
-        // Migrate
-        self.m9_rebuild_event_indices(txn)?;
+        // Erase all indices first, unless resuming
+        if self.migration_cursor(&cursor_key)?.is_none() {
+            let mut txn = self.write_txn()?;
+            self.db_event_ek_pk_index1()?.clear(&mut txn)?;
+            self.db_event_ek_c_index1()?.clear(&mut txn)?;
+            self.db_hashtags1()?.clear(&mut txn)?;
+            txn.commit()?;
+        }
 
-        Ok(())
+        self.migrate_in_chunks(
+            self.db_events1()?,
+            &cursor_key,
+            &format!("{prefix}: rebuilding event indices"),
+            |_key, val, txn| {
+                let event = EventV1::read_from_buffer(val)?;
+                self.m9_write_event_indices(&event, txn)?;
+                for hashtag in event.hashtags() {
+                    if hashtag.is_empty() {
+                        continue;
+                    } // upstream bug
+                    self.add_hashtag1(&hashtag, event.id, Some(txn))?;
+                }
+                Ok(())
+            },
+        )
     }
 
-    /// Rebuild all the event indices. This is generally internal, but might be used
-    /// to fix a broken database.
-    pub fn m9_rebuild_event_indices<'a>(&'a self, txn: &mut RwTxn<'a>) -> Result<(), Error> {
-        // Erase all indices first
-        self.db_event_ek_pk_index1()?.clear(txn)?;
-        self.db_event_ek_c_index1()?.clear(txn)?;
-        self.db_hashtags1()?.clear(txn)?;
-
-        let loop_txn = self.env.read_txn()?;
-        for result in self.db_events1()?.iter(&loop_txn)? {
-            let (_key, val) = result?;
-            let event = EventV1::read_from_buffer(val)?;
-            self.m9_write_event_indices(&event, txn)?;
-            for hashtag in event.hashtags() {
-                if hashtag.is_empty() {
-                    continue;
-                } // upstream bug
-                self.add_hashtag1(&hashtag, event.id, Some(txn))?;
-            }
-        }
-
+    pub(super) fn m9_migrate<'a>(
+        &'a self,
+        _prefix: &str,
+        _txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        // Done in m9_migrate_chunks
         Ok(())
     }
 
@@ -28,8 +28,26 @@ mod m31;
 mod m32;
 mod m33;
 mod m34;
+mod m35;
+mod m36;
+mod m37;
+mod m38;
+mod m39;
 mod m4;
+mod m40;
+mod m41;
+mod m42;
+mod m43;
+mod m44;
+mod m45;
+mod m46;
+mod m47;
+mod m48;
+mod m49;
 mod m5;
+mod m50;
+mod m51;
+mod m52;
 mod m6;
 mod m7;
 mod m8;
@@ -40,7 +58,7 @@ use crate::error::{Error, ErrorKind};
 use heed::RwTxn;
 
 impl Storage {
-    const MAX_MIGRATION_LEVEL: u32 = 34;
+    pub(super) const MAX_MIGRATION_LEVEL: u32 = 52;
 
     /// Initialize the database from empty
     pub(super) fn init_from_empty(&self) -> Result<(), Error> {
@@ -55,18 +73,22 @@ impl Storage {
             20,  // Initializes person list metadata
             21,  // Migrates person list metadata
             22,  // Migrates person list metadata again
+            37,  // Migrates person list metadata (auto-publish)
+            38,  // Migrates person list metadata (refresh cadence)
+            46,  // Migrates person list metadata (smart lists)
         ];
 
         for level in necessary.iter() {
             self.trigger(*level)?;
-            let mut txn = self.env.write_txn()?;
-            self.migrate_inner(*level, &mut txn)?;
-            self.write_migration_level(*level, Some(&mut txn))?;
+            self.migrate_chunks(*level)?;
+            let mut txn = self.write_txn()?;
+            self.migrate_inner(*level, &mut *txn)?;
+            self.write_migration_level(*level, Some(&mut *txn))?;
             txn.commit()?;
         }
 
-        let mut txn = self.env.write_txn()?;
-        self.write_migration_level(Self::MAX_MIGRATION_LEVEL, Some(&mut txn))?;
+        let mut txn = self.write_txn()?;
+        self.write_migration_level(Self::MAX_MIGRATION_LEVEL, Some(&mut *txn))?;
         txn.commit()?;
 
         Ok(())
@@ -81,14 +103,30 @@ impl Storage {
             .into());
         }
 
+        // Each level commits on its own, so an interrupted upgrade resumes at the
+        // level after the last one committed. Levels that go through a large table
+        // also commit as they go, and resume within the level.
+        let first = level;
+        if level < Self::MAX_MIGRATION_LEVEL {
+            self.start_migration_step(
+                "Upgrading the database",
+                0,
+                (Self::MAX_MIGRATION_LEVEL - first) as u64,
+            );
+        }
         while level < Self::MAX_MIGRATION_LEVEL {
             level += 1;
             self.trigger(level)?;
-            let mut txn = self.env.write_txn()?;
-            self.migrate_inner(level, &mut txn)?;
-            self.write_migration_level(level, Some(&mut txn))?;
+            self.migrate_chunks(level)?;
+            // Counted as an open write transaction, so that corrupt records found
+            // while migrating are quarantined after it ends, not inside of it
+            let mut txn = self.write_txn()?;
+            self.migrate_inner(level, &mut *txn)?;
+            self.write_migration_level(level, Some(&mut *txn))?;
             txn.commit()?;
+            self.report_migration_progress((level - first) as u64);
         }
+        self.end_migration_step();
 
         Ok(())
     }
@@ -129,12 +167,59 @@ impl Storage {
             32 => self.m32_trigger()?,
             33 => self.m33_trigger()?,
             34 => self.m34_trigger()?,
+            35 => self.m35_trigger()?,
+            36 => self.m36_trigger()?,
+            37 => self.m37_trigger()?,
+            38 => self.m38_trigger()?,
+            39 => self.m39_trigger()?,
+            40 => self.m40_trigger()?,
+            41 => self.m41_trigger()?,
+            42 => self.m42_trigger()?,
+            43 => self.m43_trigger()?,
+            44 => self.m44_trigger()?,
+            45 => self.m45_trigger()?,
+            46 => self.m46_trigger()?,
+            47 => self.m47_trigger()?,
+            48 => self.m48_trigger()?,
+            49 => self.m49_trigger()?,
+            50 => self.m50_trigger()?,
+            51 => self.m51_trigger()?,
+            52 => self.m52_trigger()?,
             _ => panic!("Unreachable migration level"),
         }
 
         Ok(())
     }
 
+    // Levels that go through every record of a large table do so here, before the
+    // level's own transaction, in chunks (see migrate_in_chunks). What is left of
+    // the level (like clearing the table it moved records out of) is done in
+    // migrate_inner. Small tables are still gone through in one transaction.
+    fn migrate_chunks(&self, level: u32) -> Result<(), Error> {
+        let prefix = format!("LMDB Migration {}", level);
+        match level {
+            1 => self.m1_migrate_chunks(&prefix)?,
+            5 => self.m5_migrate_chunks(&prefix)?,
+            8 => self.m8_migrate_chunks(&prefix)?,
+            9 => self.m9_migrate_chunks(&prefix)?,
+            11 => self.m11_migrate_chunks(&prefix)?,
+            16 => self.m16_migrate_chunks(&prefix)?,
+            25 => self.m25_migrate_chunks(&prefix)?,
+            28 => self.m28_migrate_chunks(&prefix)?,
+            29 => self.m29_migrate_chunks(&prefix)?,
+            34 => self.m34_migrate_chunks(&prefix)?,
+            42 => self.m42_migrate_chunks(&prefix)?,
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    // Where migrate_in_chunks keeps how far a level got
+    fn migration_cursor_key(level: u32) -> Vec<u8> {
+        format!("migration{}_cursor", level).into_bytes()
+    }
+
     fn migrate_inner<'a>(&'a self, level: u32, txn: &mut RwTxn<'a>) -> Result<(), Error> {
         let prefix = format!("LMDB Migration {}", level);
         match level {
@@ -172,6 +257,24 @@ impl Storage {
             32 => self.m32_migrate(&prefix, txn)?,
             33 => self.m33_migrate(&prefix, txn)?,
             34 => self.m34_migrate(&prefix, txn)?,
+            35 => self.m35_migrate(&prefix, txn)?,
+            36 => self.m36_migrate(&prefix, txn)?,
+            37 => self.m37_migrate(&prefix, txn)?,
+            38 => self.m38_migrate(&prefix, txn)?,
+            39 => self.m39_migrate(&prefix, txn)?,
+            40 => self.m40_migrate(&prefix, txn)?,
+            41 => self.m41_migrate(&prefix, txn)?,
+            42 => self.m42_migrate(&prefix, txn)?,
+            43 => self.m43_migrate(&prefix, txn)?,
+            44 => self.m44_migrate(&prefix, txn)?,
+            45 => self.m45_migrate(&prefix, txn)?,
+            46 => self.m46_migrate(&prefix, txn)?,
+            47 => self.m47_migrate(&prefix, txn)?,
+            48 => self.m48_migrate(&prefix, txn)?,
+            49 => self.m49_migrate(&prefix, txn)?,
+            50 => self.m50_migrate(&prefix, txn)?,
+            51 => self.m51_migrate(&prefix, txn)?,
+            52 => self.m52_migrate(&prefix, txn)?,
             _ => panic!("Unreachable migration level"),
         };
 
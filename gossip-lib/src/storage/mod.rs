@@ -15,8 +15,23 @@ macro_rules! write_transact {
         match $opttxn {
             Some(txn) => $f(txn),
             None => {
-                let mut txn = $storage.env.write_txn()?;
-                let result = $f(&mut txn);
+                let mut txn = $storage.write_txn_at(file!(), line!())?;
+                let result = $f(&mut *txn);
+                txn.commit()?;
+                result
+            }
+        }
+    };
+}
+
+macro_rules! write_transact_as {
+    ($storage:ident, $opttxn:ident, $f:ident, $class:expr) => {
+        match $opttxn {
+            Some(txn) => $f(txn),
+            None => {
+                let mut txn = $storage.write_txn_at(file!(), line!())?;
+                txn.set_class($class);
+                let result = $f(&mut *txn);
                 txn.commit()?;
                 result
             }
@@ -41,66 +56,157 @@ mod migrations;
 // type implementations
 pub mod types;
 
+// record encodings
+pub mod byte_rep;
+
 // database implementations
+mod app_data;
+pub use app_data::AppData;
+mod context;
+pub use context::{GlobalContext, NoIdentity, StorageContext};
+mod txn_timing;
+pub use txn_timing::{SlowTxn, TimedTxn, TxnSiteStats, SLOW_TXN_MILLIS};
+mod app_data1;
+pub(crate) use app_data1::LIB_NAMESPACE;
+mod backup;
+pub use backup::BackupInfo;
+mod communities1;
+mod compact;
+pub use compact::CompactionReport;
+pub(crate) use compact::DRAIN_WRITERS_SECS;
+mod corrupt_records1;
+mod durability;
+pub use durability::WriteClass;
+mod encryption;
+mod expiry;
+mod gc;
+pub use gc::GarbageReport;
+mod handle;
+pub use corrupt_records1::CorruptRecord;
+pub(crate) use corrupt_records1::CorruptRecords;
+pub use handle::StorageHandle;
+mod migration_progress;
+pub use migration_progress::MigrationProgress;
+mod paging;
+use paging::take_records;
+pub use paging::{PageCursor, RecordPage, ScanOrder};
+mod secondary_index;
+pub(crate) use secondary_index::SecondaryIndex;
+mod stats;
+pub use stats::TableStats;
+#[cfg(test)]
+pub(crate) mod test_dir;
+mod verify;
+pub use verify::{TableCheck, VerifyReport};
+
+mod dvm_jobs1;
 mod event_akci_index;
 use event_akci_index::AkciKey;
 mod event_kci_index;
+mod event_media1;
+mod event_media2;
+mod event_media_index1;
 use event_kci_index::KciKey;
 
 mod event_ek_c_index1;
 mod event_ek_pk_index1;
 mod event_seen_on_relay1;
 mod event_tag_index1;
+mod event_view;
+pub use event_view::EventView;
 mod event_viewed1;
 mod events1;
 mod events2;
 mod events3;
+mod groups1;
 mod hashtags1;
+mod interaction_scores1;
+mod jsonl;
+pub use jsonl::JsonlImport;
+mod live_activities1;
+mod media_hashes1;
+mod mute_log1;
+mod nip46_permissions1;
 mod nip46servers1;
 mod nip46servers2;
 mod people1;
 mod people2;
+mod people3;
 mod person_lists1;
 mod person_lists2;
 mod person_lists_metadata1;
 mod person_lists_metadata2;
 mod person_lists_metadata3;
+mod person_lists_metadata4;
+mod person_lists_metadata5;
+mod person_lists_metadata6;
 mod person_relays1;
 mod person_relays2;
+mod quarantine1;
+mod reaction_usage1;
+mod read_cache;
+pub use read_cache::ReadCacheStats;
 mod relationships1;
 mod relationships_by_addr1;
 mod relationships_by_addr2;
 mod relationships_by_id1;
 mod relationships_by_id2;
+mod relay_auth_keys1;
+mod relay_groups1;
 mod relays1;
 mod relays2;
+mod relays3;
+mod relays4;
+mod relays5;
+mod relays6;
 mod reprel1;
+mod rss_entries1;
+mod rumors1;
+mod stars1;
+mod table_metrics;
+pub use table_metrics::TableMetrics;
 mod unindexed_giftwraps1;
 mod versioned;
+mod write_batch;
+pub use write_batch::BATCH_MAX_MILLIS;
+mod zap_receipts1;
+mod zap_signers1;
 
+use crate::bunker::BunkerConnection;
+use crate::communities::Community;
 use crate::dm_channel::{DmChannel, DmChannelData};
+use crate::dvm::DvmJob;
 use crate::error::{Error, ErrorKind};
-use crate::globals::GLOBALS;
+use crate::event_media::{EventMedia, MediaGalleryItem};
+use crate::groups::Group;
+use crate::live::LiveActivity;
 use crate::misc::Private;
-use crate::nip46::{Nip46Server, Nip46UnconnectedServer};
-use crate::people::{Person, PersonList, PersonListMetadata};
+use crate::nip46::{Approval, Nip46Server, Nip46UnconnectedServer};
+use crate::nwc::NwcConnection;
+use crate::people::{MuteLogEntry, Person, PersonList, PersonListMetadata};
 use crate::person_relay::PersonRelay;
 use crate::profile::Profile;
 use crate::relationship::{RelationshipByAddr, RelationshipById};
 use crate::relay::Relay;
+use crate::relay_group::RelayGroup;
+use crate::rss::RssEntry;
+use crate::zaps::ZapReceipt;
 use heed::types::{Bytes, Unit};
-use heed::{Database, Env, EnvFlags, EnvOpenOptions, RoTxn, RwTxn};
+use heed::{Database, DatabaseFlags, Env, EnvFlags, EnvOpenOptions, RoTxn, RwTxn};
 use nostr_types::{
-    EncryptedPrivateKey, Event, EventAddr, EventKind, EventReference, Filter, Id, MilliSatoshi,
-    PublicKey, PublicKeyHex, RelayList, RelayUrl, RelayUsage, Unixtime,
+    EncryptedPrivateKey, Event, EventAddr, EventKind, EventReference, Filter, Id, PublicKey,
+    PublicKeyHex, RelayList, RelayUrl, RelayUsage, Rumor, Unixtime,
 };
 use paste::paste;
 use speedy::{Readable, Writable};
 use std::collections::{BTreeSet, HashMap, HashSet};
 use std::ops::Bound;
+use std::path::Path;
+use std::sync::atomic::Ordering;
 
 use self::event_kci_index::INDEXED_KINDS;
 use self::event_tag_index1::INDEXED_TAGS;
+use self::read_cache::Written;
 
 // Macro to define read-and-write into "general" database, largely for settings
 // The type must implemented Speedy Readable and Writable
@@ -199,16 +305,79 @@ pub struct Storage {
 
     // General database (settings, local_settings)
     general: RawDatabase,
+
+    // Handles of the named databases, opened on first use
+    dbs: parking_lot::RwLock<HashMap<&'static str, RawDatabase>>,
+
+    // The identity and app this storage serves
+    context: Box<dyn StorageContext>,
+
+    // How long write transactions are held
+    txn_timings: parking_lot::Mutex<txn_timing::TxnTimings>,
+
+    // How many write transactions are open right now
+    write_txns_open: std::sync::atomic::AtomicUsize,
+
+    // Every write transaction holds this for reading, so that compaction can drain
+    // the writers and hold new ones off until it has swapped this storage out
+    write_gate: parking_lot::RwLock<()>,
+
+    // Set once this storage has been swapped out; writes to it would be lost
+    retired: std::sync::atomic::AtomicBool,
+
+    // Set when a record to be sealed was written in the clear, as the identity was
+    // locked
+    seal_deferred: std::sync::atomic::AtomicBool,
+
+    // Corrupt records found while a write transaction was open, to be quarantined
+    // once none are
+    corrupt_pending: parking_lot::Mutex<Vec<(RawDatabase, CorruptRecords)>>,
+
+    // Follow state changes made in the open write transaction, told to the context
+    // once it commits
+    follow_changes: parking_lot::Mutex<Vec<FollowChange>>,
+
+    // Reads and writes of each table
+    table_meter: table_metrics::TableMeter,
+
+    // If commits have been made that are not yet synced to disk
+    unsynced: std::sync::atomic::AtomicBool,
+
+    // Events from relays waiting to be written together
+    event_batch: parking_lot::Mutex<write_batch::EventBatch>,
+
+    // Person and relay records read lately (off until init)
+    person_cache: read_cache::PersonCache,
+    relay_cache: read_cache::RelayCache,
+}
+
+// A change to who we follow, made in a write transaction
+enum FollowChange {
+    Changed(PublicKey, bool),
+    Invalidated,
 }
 
 impl Storage {
     pub(crate) fn new() -> Result<Storage, Error> {
+        let dir = Profile::current()?.lmdb_dir;
+        Storage::open(&dir, Box::new(GlobalContext))
+    }
+
+    /// Open the LMDB at `dir` (which must exist) with a context of its own. The app
+    /// has one storage in `GLOBALS.storage`; this is for tests and for library users
+    /// hosting more than one. Run [init](Storage::init) on it before use.
+    pub fn open(dir: &Path, context: Box<dyn StorageContext>) -> Result<Storage, Error> {
         let mut builder = EnvOpenOptions::new();
         unsafe {
-            builder.flags(EnvFlags::NO_TLS);
+            // With NO_SYNC, LMDB does not sync anything to disk as a transaction
+            // commits. We sync (force_sync) after each commit whose WriteClass needs
+            // it, and periodically for the rest. Commits not yet synced may be undone
+            // by a system crash or power loss, but the database stays consistent
+            // (as long as the filesystem keeps the order of writes).
+            builder.flags(EnvFlags::NO_TLS | EnvFlags::NO_SYNC);
         }
         // builder.max_readers(126); // this is the default
-        builder.max_dbs(32);
+        builder.max_dbs(64);
 
         // This has to be big enough for all the data.
         // Note that it is the size of the map in VIRTUAL address space,
@@ -219,9 +388,8 @@ impl Storage {
         //       after the database has been launched.
         builder.map_size(1048576 * 1024 * 24); // 24 GB
 
-        let dir = Profile::current()?.lmdb_dir;
         let env = unsafe {
-            match builder.open(&dir) {
+            match builder.open(dir) {
                 Ok(env) => env,
                 Err(e) => {
                     tracing::error!("Unable to open LMDB at {}", dir.display());
@@ -239,53 +407,312 @@ impl Storage {
 
         txn.commit()?;
 
-        Ok(Storage { env, general })
+        Ok(Storage {
+            env,
+            general,
+            dbs: parking_lot::RwLock::new(HashMap::new()),
+            context,
+            txn_timings: parking_lot::Mutex::new(Default::default()),
+            write_txns_open: std::sync::atomic::AtomicUsize::new(0),
+            write_gate: parking_lot::RwLock::new(()),
+            retired: std::sync::atomic::AtomicBool::new(false),
+            seal_deferred: std::sync::atomic::AtomicBool::new(false),
+            corrupt_pending: parking_lot::Mutex::new(Vec::new()),
+            follow_changes: parking_lot::Mutex::new(Vec::new()),
+            table_meter: Default::default(),
+            unsynced: std::sync::atomic::AtomicBool::new(false),
+            event_batch: parking_lot::Mutex::new(Default::default()),
+            person_cache: read_cache::ReadCache::new("people", 0),
+            relay_cache: read_cache::ReadCache::new("relays", 0),
+        })
+    }
+
+    /// The context this storage was opened with
+    pub fn context(&self) -> &dyn StorageContext {
+        &*self.context
+    }
+
+    // Open a named database, creating it if it does not exist yet. Handles are kept
+    // per storage rather than in statics so that storages do not share them.
+    pub(super) fn open_database(&self, name: &'static str) -> Result<RawDatabase, Error> {
+        self.open_database_with_flags(name, DatabaseFlags::empty())
+    }
+
+    pub(super) fn open_database_with_flags(
+        &self,
+        name: &'static str,
+        flags: DatabaseFlags,
+    ) -> Result<RawDatabase, Error> {
+        if let Some(db) = self.dbs.read().get(name) {
+            return Ok(*db);
+        }
+
+        // Hold the write lock while creating, so that it cannot happen twice
+        let mut dbs = self.dbs.write();
+        if let Some(db) = dbs.get(name) {
+            return Ok(*db);
+        }
+
+        let mut txn = self.env.write_txn()?;
+        let mut options = self.env.database_options().types::<Bytes, Bytes>();
+        if !flags.is_empty() {
+            options.flags(flags);
+        }
+        let db = options.name(name).create(&mut txn)?;
+        txn.commit()?;
+        self.unsynced.store(true, Ordering::Relaxed);
+        dbs.insert(name, db);
+        Ok(db)
+    }
+
+    /// Delete the record under this key from a table. Returns false if there was none.
+    pub(crate) fn delete_record<'a>(
+        &'a self,
+        db: RawDatabase,
+        key: &[u8],
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<bool, Error> {
+        let f = |txn: &mut RwTxn<'a>| -> Result<bool, Error> { Ok(db.delete(txn, key)?) };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    /// The records of a table (key and value) with keys in the range that the
+    /// predicate picks, in key order. Only the range is read.
+    pub(crate) fn range_records<F>(
+        &self,
+        db: RawDatabase,
+        start: Bound<&[u8]>,
+        end: Bound<&[u8]>,
+        predicate: F,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Error>
+    where
+        F: Fn(&[u8], &[u8]) -> bool,
+    {
+        let txn = self.env.read_txn()?;
+        let mut output: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
+        for result in db.range(&txn, &(start, end))? {
+            let (key, val) = result?;
+            if predicate(key, val) {
+                output.push((key.to_owned(), val.to_owned()));
+            }
+        }
+        Ok(output)
+    }
+
+    /// The records of a table (key and value) with keys starting with the prefix that
+    /// the predicate picks, in key order. Only those keys are read.
+    pub(crate) fn prefix_records<F>(
+        &self,
+        db: RawDatabase,
+        prefix: &[u8],
+        predicate: F,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Error>
+    where
+        F: Fn(&[u8], &[u8]) -> bool,
+    {
+        let txn = self.env.read_txn()?;
+        let mut output: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
+        for result in db.prefix_iter(&txn, prefix)? {
+            let (key, val) = result?;
+            if predicate(key, val) {
+                output.push((key.to_owned(), val.to_owned()));
+            }
+        }
+        Ok(output)
+    }
+
+    /// The first `limit` records of a table (key and value) with keys starting with
+    /// the prefix (an empty one for all of them) that the predicate picks, going
+    /// through the keys in the given order. It stops reading once it has them.
+    pub(crate) fn filter_records_limited<F>(
+        &self,
+        db: RawDatabase,
+        prefix: &[u8],
+        predicate: F,
+        limit: usize,
+        order: ScanOrder,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Error>
+    where
+        F: Fn(&[u8], &[u8]) -> bool,
+    {
+        let txn = self.env.read_txn()?;
+        match (order, prefix.is_empty()) {
+            (ScanOrder::Forward, true) => take_records(db.iter(&txn)?, predicate, limit),
+            (ScanOrder::Reverse, true) => take_records(db.rev_iter(&txn)?, predicate, limit),
+            (ScanOrder::Forward, false) => {
+                take_records(db.prefix_iter(&txn, prefix)?, predicate, limit)
+            }
+            (ScanOrder::Reverse, false) => {
+                take_records(db.rev_prefix_iter(&txn, prefix)?, predicate, limit)
+            }
+        }
+    }
+
+    /// A page of up to `limit` records of a table (key and value), in key order,
+    /// starting after the cursor (or at the first record). Only the page is read.
+    /// Tables with duplicate keys can't be paged this way.
+    pub(crate) fn page_records(
+        &self,
+        db: RawDatabase,
+        cursor: Option<&PageCursor>,
+        limit: usize,
+    ) -> Result<RecordPage<(Vec<u8>, Vec<u8>)>, Error> {
+        let start = match cursor {
+            Some(cursor) => Bound::Excluded(&cursor.0[..]),
+            None => Bound::Unbounded,
+        };
+        let txn = self.env.read_txn()?;
+        let mut items: Vec<(Vec<u8>, Vec<u8>)> = Vec::with_capacity(limit);
+        let mut more = false;
+        for result in db.range(&txn, &(start, Bound::Unbounded))? {
+            let (key, val) = result?;
+            if items.len() == limit {
+                more = true;
+                break;
+            }
+            items.push((key.to_owned(), val.to_owned()));
+        }
+        let next = match items.last() {
+            Some((key, _)) if more => Some(PageCursor(key.clone())),
+            _ => None,
+        };
+        Ok(RecordPage { items, next })
+    }
+
+    /// Delete every record of a table (given its key and value) that the predicate
+    /// picks, including single duplicates of tables with duplicate keys. Returns how
+    /// many were deleted.
+    pub(crate) fn filter_delete<'a, F>(
+        &'a self,
+        db: RawDatabase,
+        predicate: F,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<usize, Error>
+    where
+        F: Fn(&[u8], &[u8]) -> bool,
+    {
+        let f = |txn: &mut RwTxn<'a>| -> Result<usize, Error> {
+            // We can't delete while iterating
+            let mut deletions: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
+            for result in db.iter(txn)? {
+                let (key, val) = result?;
+                if predicate(key, val) {
+                    deletions.push((key.to_owned(), val.to_owned()));
+                }
+            }
+            // (Without duplicate keys, LMDB ignores the value)
+            for (key, val) in deletions.iter() {
+                db.delete_one_duplicate(txn, key, val)?;
+            }
+            Ok(deletions.len())
+        };
+
+        write_transact!(self, rw_txn, f)
     }
 
     /// Run this after GLOBALS lazy static initialisation, so functions within storage can
-    /// access GLOBALS without hanging.
+    /// access GLOBALS (through the [GlobalContext]) without hanging.
     pub fn init(&self) -> Result<(), Error> {
-        // We have to trigger all of the current-version databases into existence
-        // because otherwise there will be MVCC visibility problems later having
-        // different transactions in parallel
-        //
-        // old-version databases will be handled by their migration code and only
-        // triggered into existence if their migration is necessary.
+        self.open_tables()?;
+
+        // Do migrations
+        match self.read_migration_level()? {
+            Some(level) => self.migrate(level)?,
+            None => self.init_from_empty()?,
+        }
+
+        self.configure_read_caches();
+
+        Ok(())
+    }
+
+    // We have to trigger all of the current-version databases into existence
+    // because otherwise there will be MVCC visibility problems later having
+    // different transactions in parallel
+    //
+    // old-version databases will be handled by their migration code and only
+    // triggered into existence if their migration is necessary.
+    pub(super) fn open_tables(&self) -> Result<(), Error> {
+        let _ = self.db_app_data()?;
         let _ = self.db_event_akci_index()?;
         let _ = self.db_event_kci_index()?;
         let _ = self.db_event_tag_index()?;
         let _ = self.db_events()?;
         let _ = self.db_event_seen_on_relay()?;
         let _ = self.db_event_viewed()?;
+        let _ = self.db_event_media()?;
+        let _ = self.db_event_media_by_author()?;
+        let _ = self.db_event_media_by_hashtag()?;
         let _ = self.db_hashtags()?;
+        let _ = self.db_mute_log()?;
         let _ = self.db_nip46servers()?;
+        let _ = self.db_nip46_permissions()?;
         let _ = self.db_people()?;
+        self.open_people3_indexes()?;
         let _ = self.db_person_relays()?;
         let _ = self.db_relationships_by_id()?;
         let _ = self.db_relationships_by_addr()?;
         let _ = self.db_relays()?;
         let _ = self.db_unindexed_giftwraps()?;
+        let _ = self.db_rumors()?;
         let _ = self.db_person_lists()?;
         let _ = self.db_person_lists_metadata()?;
-
-        // Do migrations
-        match self.read_migration_level()? {
-            Some(level) => self.migrate(level)?,
-            None => self.init_from_empty()?,
-        }
-
+        let _ = self.db_rss_entries()?;
+        let _ = self.db_corrupt_records()?;
+        let _ = self.db_reaction_usage()?;
+        let _ = self.db_interaction_scores()?;
+        let _ = self.db_media_hashes()?;
+        let _ = self.db_media_hash_urls()?;
+        let _ = self.db_stars()?;
+        let _ = self.db_relay_auth_keys()?;
+        let _ = self.db_relay_groups()?;
+        let _ = self.db_zap_receipts()?;
+        let _ = self.db_zap_signers()?;
+        let _ = self.db_live_activities()?;
+        let _ = self.db_groups()?;
+        let _ = self.db_communities()?;
+        let _ = self.db_dvm_jobs()?;
         Ok(())
     }
 
     /// Get a write transaction. With it, you can do multiple writes before you commit it.
-    /// Bundling multiple writes together is more efficient.
-    pub fn get_write_txn(&self) -> Result<RwTxn<'_>, Error> {
-        Ok(self.env.write_txn()?)
+    /// Bundling multiple writes together is more efficient. It blocks all other writers
+    /// until committed or dropped, so how long it is held is measured against the
+    /// caller (see [txn_report](Storage::txn_report)).
+    #[track_caller]
+    pub fn get_write_txn(&self) -> Result<TimedTxn<'_>, Error> {
+        self.write_txn()
+    }
+
+    #[track_caller]
+    pub(crate) fn write_txn(&self) -> Result<TimedTxn<'_>, Error> {
+        let caller = std::panic::Location::caller();
+        self.write_txn_at(caller.file(), caller.line())
+    }
+
+    pub(crate) fn write_txn_at(
+        &self,
+        file: &'static str,
+        line: u32,
+    ) -> Result<TimedTxn<'_>, Error> {
+        TimedTxn::new(self, file, line)
+    }
+
+    /// Get a write transaction of a [WriteClass] other than `Normal`, which decides
+    /// whether its commit is synced to disk right away
+    #[track_caller]
+    pub fn get_write_txn_as(&self, class: WriteClass) -> Result<TimedTxn<'_>, Error> {
+        let mut txn = self.write_txn()?;
+        txn.set_class(class);
+        Ok(txn)
     }
 
-    /// Sync the data to disk. This happens periodically, but sometimes it's useful to force
-    /// it.
+    /// Sync the data to disk, including every commit not yet synced (see
+    /// [WriteClass]). This happens after critical writes and periodically, but
+    /// sometimes it's useful to force it.
     pub fn sync(&self) -> Result<(), Error> {
+        self.unsynced.store(false, Ordering::Relaxed);
         self.env.force_sync()?;
         Ok(())
     }
@@ -312,19 +739,44 @@ impl Storage {
         self.db_event_viewed1()
     }
 
+    #[inline]
+    pub(crate) fn db_event_media(&self) -> Result<RawDatabase, Error> {
+        self.db_event_media2()
+    }
+
+    #[inline]
+    pub(crate) fn db_event_media_by_author(&self) -> Result<EmptyDatabase, Error> {
+        self.db_event_media_by_author1()
+    }
+
+    #[inline]
+    pub(crate) fn db_event_media_by_hashtag(&self) -> Result<EmptyDatabase, Error> {
+        self.db_event_media_by_hashtag1()
+    }
+
     #[inline]
     pub(crate) fn db_hashtags(&self) -> Result<RawDatabase, Error> {
         self.db_hashtags1()
     }
 
+    #[inline]
+    pub(crate) fn db_mute_log(&self) -> Result<RawDatabase, Error> {
+        self.db_mute_log1()
+    }
+
     #[inline]
     pub(crate) fn db_nip46servers(&self) -> Result<RawDatabase, Error> {
         self.db_nip46servers2()
     }
 
+    #[inline]
+    pub(crate) fn db_nip46_permissions(&self) -> Result<RawDatabase, Error> {
+        self.db_nip46_permissions1()
+    }
+
     #[inline]
     pub(crate) fn db_people(&self) -> Result<RawDatabase, Error> {
-        self.db_people2()
+        self.db_people3()
     }
 
     #[inline]
@@ -344,7 +796,7 @@ impl Storage {
 
     #[inline]
     pub(crate) fn db_relays(&self) -> Result<RawDatabase, Error> {
-        self.db_relays2()
+        self.db_relays6()
     }
 
     #[inline]
@@ -352,6 +804,11 @@ impl Storage {
         self.db_unindexed_giftwraps1()
     }
 
+    #[inline]
+    pub(crate) fn db_rumors(&self) -> Result<RawDatabase, Error> {
+        self.db_rumors1()
+    }
+
     #[inline]
     pub(crate) fn db_person_lists(&self) -> Result<RawDatabase, Error> {
         self.db_person_lists2()
@@ -359,7 +816,87 @@ impl Storage {
 
     #[inline]
     pub(crate) fn db_person_lists_metadata(&self) -> Result<RawDatabase, Error> {
-        self.db_person_lists_metadata3()
+        self.db_person_lists_metadata6()
+    }
+
+    #[inline]
+    pub(crate) fn db_rss_entries(&self) -> Result<RawDatabase, Error> {
+        self.db_rss_entries1()
+    }
+
+    #[inline]
+    pub(crate) fn db_app_data(&self) -> Result<RawDatabase, Error> {
+        self.db_app_data1()
+    }
+
+    #[inline]
+    pub(crate) fn db_corrupt_records(&self) -> Result<RawDatabase, Error> {
+        self.db_corrupt_records1()
+    }
+
+    #[inline]
+    pub(crate) fn db_reaction_usage(&self) -> Result<RawDatabase, Error> {
+        self.db_reaction_usage1()
+    }
+
+    #[inline]
+    pub(crate) fn db_interaction_scores(&self) -> Result<RawDatabase, Error> {
+        self.db_interaction_scores1()
+    }
+
+    #[inline]
+    pub(crate) fn db_media_hashes(&self) -> Result<RawDatabase, Error> {
+        self.db_media_hashes1()
+    }
+
+    #[inline]
+    pub(crate) fn db_media_hash_urls(&self) -> Result<EmptyDatabase, Error> {
+        self.db_media_hash_urls1()
+    }
+
+    #[inline]
+    pub(crate) fn db_stars(&self) -> Result<RawDatabase, Error> {
+        self.db_stars1()
+    }
+
+    #[inline]
+    pub(crate) fn db_relay_auth_keys(&self) -> Result<RawDatabase, Error> {
+        self.db_relay_auth_keys1()
+    }
+
+    #[inline]
+    pub(crate) fn db_relay_groups(&self) -> Result<RawDatabase, Error> {
+        self.db_relay_groups1()
+    }
+
+    #[inline]
+    pub(crate) fn db_zap_receipts(&self) -> Result<RawDatabase, Error> {
+        self.db_zap_receipts1()
+    }
+
+    #[inline]
+    pub(crate) fn db_zap_signers(&self) -> Result<RawDatabase, Error> {
+        self.db_zap_signers1()
+    }
+
+    #[inline]
+    pub(crate) fn db_live_activities(&self) -> Result<RawDatabase, Error> {
+        self.db_live_activities1()
+    }
+
+    #[inline]
+    pub(crate) fn db_groups(&self) -> Result<RawDatabase, Error> {
+        self.db_groups1()
+    }
+
+    #[inline]
+    pub(crate) fn db_communities(&self) -> Result<RawDatabase, Error> {
+        self.db_communities1()
+    }
+
+    #[inline]
+    pub(crate) fn db_dvm_jobs(&self) -> Result<RawDatabase, Error> {
+        self.db_dvm_jobs1()
     }
 
     // Database length functions ---------------------------------
@@ -382,22 +919,148 @@ impl Storage {
         self.get_event_viewed1_len()
     }
 
+    /// The number of records in the event_media table
+    #[inline]
+    pub fn get_event_media_len(&self) -> Result<u64, Error> {
+        self.get_event_media2_len()
+    }
+
+    /// The number of records in the event_media_by_author table
+    pub fn get_event_media_by_author_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_event_media_by_author()?.len(&txn)?)
+    }
+
+    /// The number of records in the event_media_by_hashtag table
+    pub fn get_event_media_by_hashtag_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_event_media_by_hashtag()?.len(&txn)?)
+    }
+
     /// The number of records in the hashtags table
     pub fn get_hashtags_len(&self) -> Result<u64, Error> {
         let txn = self.env.read_txn()?;
         Ok(self.db_hashtags()?.len(&txn)?)
     }
 
+    /// The number of records in the mute_log table
+    pub fn get_mute_log_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_mute_log()?.len(&txn)?)
+    }
+
     /// The number of records in the nip46servers table
     pub fn get_nip46servers_len(&self) -> Result<u64, Error> {
         let txn = self.env.read_txn()?;
         Ok(self.db_nip46servers()?.len(&txn)?)
     }
 
+    /// The number of records in the nip46_permissions table
+    pub fn get_nip46_permissions_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_nip46_permissions()?.len(&txn)?)
+    }
+
+    /// The number of records in the app_data table
+    pub fn get_app_data_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_app_data()?.len(&txn)?)
+    }
+
+    /// The number of records in the corrupt_records table
+    #[inline]
+    pub fn get_corrupt_records_len(&self) -> Result<u64, Error> {
+        self.get_corrupt_records1_len()
+    }
+
+    /// The number of records in the reaction_usage table
+    pub fn get_reaction_usage_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_reaction_usage()?.len(&txn)?)
+    }
+
+    /// The number of records in the interaction_scores table
+    pub fn get_interaction_scores_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_interaction_scores()?.len(&txn)?)
+    }
+
+    /// The number of records in the media_hashes table
+    pub fn get_media_hashes_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_media_hashes()?.len(&txn)?)
+    }
+
+    /// The number of records in the stars table
+    pub fn get_stars_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_stars()?.len(&txn)?)
+    }
+
+    /// The number of records in the relay_auth_keys table
+    pub fn get_relay_auth_keys_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_relay_auth_keys()?.len(&txn)?)
+    }
+
+    /// The number of records in the relay_groups table
+    pub fn get_relay_groups_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_relay_groups()?.len(&txn)?)
+    }
+
+    /// The number of records in the zap_receipts table
+    pub fn get_zap_receipts_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_zap_receipts()?.len(&txn)?)
+    }
+
+    /// The number of records in the live_activities table
+    pub fn get_live_activities_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_live_activities()?.len(&txn)?)
+    }
+
+    /// The number of records in the groups table
+    pub fn get_groups_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_groups()?.len(&txn)?)
+    }
+
+    /// The number of records in the communities table
+    pub fn get_communities_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_communities()?.len(&txn)?)
+    }
+
+    /// The number of records in the dvm_jobs table
+    pub fn get_dvm_jobs_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_dvm_jobs()?.len(&txn)?)
+    }
+
+    /// The number of records in the rss_entries table
+    pub fn get_rss_entries_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_rss_entries()?.len(&txn)?)
+    }
+
+    /// The number of GiftWraps waiting for our key to be unlocked
+    #[inline]
+    pub fn get_unindexed_giftwraps_len(&self) -> Result<u64, Error> {
+        self.get_unindexed_giftwraps1_len()
+    }
+
+    /// The number of GiftWraps whose rumor we keep unwrapped
+    #[inline]
+    pub fn get_rumors_len(&self) -> Result<u64, Error> {
+        self.get_rumors1_len()
+    }
+
     /// The number of records in the relays table
     #[inline]
     pub fn get_relays_len(&self) -> Result<u64, Error> {
-        self.get_relays2_len()
+        self.get_relays6_len()
     }
 
     /// The number of records in the event table
@@ -441,7 +1104,7 @@ impl Storage {
     /// The number of records in the people table
     #[inline]
     pub fn get_people_len(&self) -> Result<u64, Error> {
-        self.get_people2_len()
+        self.get_people3_len()
     }
 
     /// The number of records in the person_relays table
@@ -461,6 +1124,8 @@ impl Storage {
     /// Remove all events (and related data) with a created_at before `from`
     /// and all related indexes.
     pub fn prune(&self, from: Unixtime) -> Result<usize, Error> {
+        self.event_read_barrier()?;
+
         // Extract the Ids to delete.
         let txn = self.env.read_txn()?;
         let mut ids: HashSet<Id> = HashSet::new();
@@ -480,14 +1145,42 @@ impl Storage {
         }
         drop(txn);
 
-        let mut txn = self.env.write_txn()?;
+        self.prune_events(&ids)?;
 
-        // Delete from event_seen_on_relay
-        let mut deletions: Vec<Vec<u8>> = Vec::new();
-        for id in &ids {
-            let start_key: &[u8] = id.as_slice();
-            for result in self
-                .db_event_seen_on_relay()?
+        Ok(ids.len())
+    }
+
+    /// Visit the id, kind, author, created_at and stored size of every event, without
+    /// decoding the rest of them
+    pub(crate) fn for_each_event_header<F>(&self, mut f: F) -> Result<(), Error>
+    where
+        F: FnMut(Id, EventKind, PublicKey, Unixtime, usize),
+    {
+        self.event_read_barrier()?;
+        let txn = self.env.read_txn()?;
+        for result in self.db_events()?.iter(&txn)? {
+            let (_key, val) = result?;
+            let view = EventView::new(val);
+            if let (Some(id), Some(kind), Some(pubkey), Some(created_at)) =
+                (view.id(), view.kind(), view.pubkey(), view.created_at())
+            {
+                f(id, kind, pubkey, created_at, view.len());
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove these events, everything stored about them, and their index entries
+    pub fn prune_events(&self, ids: &HashSet<Id>) -> Result<(), Error> {
+        self.event_read_barrier()?;
+        let mut txn = self.write_txn()?;
+
+        // Delete from event_seen_on_relay
+        let mut deletions: Vec<Vec<u8>> = Vec::new();
+        for id in ids {
+            let start_key: &[u8] = id.as_slice();
+            for result in self
+                .db_event_seen_on_relay()?
                 .prefix_iter(&txn, start_key)?
             {
                 let (_key, val) = result?;
@@ -503,56 +1196,92 @@ impl Storage {
         }
 
         // Delete from event_viewed
-        for id in &ids {
-            let _ = self.db_event_viewed()?.delete(&mut txn, id.as_slice());
+        let mut count: usize = 0;
+        for id in ids {
+            if self.delete_record(self.db_event_viewed()?, id.as_slice(), Some(&mut *txn))? {
+                count += 1;
+            }
         }
-        tracing::info!("PRUNE: deleted {} records from event_viewed", ids.len());
+        tracing::info!("PRUNE: deleted {} records from event_viewed", count);
 
-        // Delete from hashtags
-        // (unfortunately since Ids are the values, we have to scan the whole thing)
-        let mut deletions: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
-        for result in self.db_hashtags()?.iter(&txn)? {
-            let (key, val) = result?;
-            let id = Id(val[0..32].try_into()?);
-            if ids.contains(&id) {
-                deletions.push((key.to_owned(), val.to_owned()));
+        // Delete from event_media
+        let mut count: usize = 0;
+        for id in ids {
+            if self.delete_record(self.db_event_media()?, id.as_slice(), Some(&mut *txn))? {
+                count += 1;
             }
         }
-        tracing::info!("PRUNE: deleting {} records from hashtags", deletions.len());
-        for deletion in deletions.drain(..) {
-            self.db_hashtags()?
-                .delete_one_duplicate(&mut txn, &deletion.0, &deletion.1)?;
-        }
+        tracing::info!("PRUNE: deleted {} records from event_media", count);
+
+        // Id at this offset of a key or value, if it is long enough
+        let id_at = |bytes: &[u8], offset: usize| -> Option<Id> {
+            Some(Id(bytes.get(offset..offset + 32)?.try_into().ok()?))
+        };
+        let pruned = |id: Option<Id>| id.map(|id| ids.contains(&id)).unwrap_or(false);
+
+        // Delete from hashtags
+        // (unfortunately since Ids are the values, we have to scan the whole thing)
+        let count = self.filter_delete(
+            self.db_hashtags()?,
+            |_key, val| pruned(id_at(val, 0)),
+            Some(&mut *txn),
+        )?;
+        tracing::info!("PRUNE: deleted {} records from hashtags", count);
 
         // Delete from relationships
         // (unfortunately because of the 2nd Id in the tag, we have to scan the whole thing)
-        let mut deletions: Vec<Vec<u8>> = Vec::new();
-        for result in self.db_relationships_by_id()?.iter(&txn)? {
-            let (key, _val) = result?;
-            let id = Id(key[0..32].try_into()?);
-            if ids.contains(&id) {
-                deletions.push(key.to_owned());
-                continue;
-            }
-            let id2 = Id(key[32..64].try_into()?);
-            if ids.contains(&id2) {
-                deletions.push(key.to_owned());
-            }
-        }
-        tracing::info!("PRUNE: deleting {} relationships", deletions.len());
-        for deletion in deletions.drain(..) {
-            self.db_relationships_by_id()?.delete(&mut txn, &deletion)?;
+        let count = self.filter_delete(
+            self.db_relationships_by_id()?,
+            |key, _val| pruned(id_at(key, 0)) || pruned(id_at(key, 32)),
+            Some(&mut *txn),
+        )?;
+        tracing::info!("PRUNE: deleted {} relationships", count);
+
+        // Delete from the author, kind and media gallery indexes, whose keys we can
+        // rebuild from the events while we still have them
+        for id in ids {
+            let event = match self.db_events()?.get(&txn, id.as_slice())? {
+                Some(bytes) => match Event::read_from_buffer(bytes) {
+                    Ok(event) => event,
+                    Err(_) => continue,
+                },
+                None => continue,
+            };
+            let rumor = self.switch_to_rumor(&event, &mut txn)?;
+            let inner = rumor.as_ref().unwrap_or(&event);
+            let akci = AkciKey::from_parts(inner.pubkey, event.kind, inner.created_at, event.id);
+            let _ = self
+                .db_event_akci_index()?
+                .delete(&mut txn, akci.as_slice())?;
+            let kci = KciKey::from_parts(event.kind, inner.created_at, event.id);
+            let _ = self
+                .db_event_kci_index()?
+                .delete(&mut txn, kci.as_slice())?;
+            self.delete_event_media_index1(&event, Some(&mut *txn))?;
         }
+        tracing::info!("PRUNE: deleted author, kind and media gallery index entries");
+
+        // Delete from the tag index
+        // (since Ids are the values, we have to scan the whole thing)
+        let count = self.filter_delete(
+            self.db_event_tag_index()?,
+            |_key, val| pruned(id_at(val, 0)),
+            Some(&mut *txn),
+        )?;
+        tracing::info!("PRUNE: deleted {} records from event_tag_index", count);
 
         // delete from events
-        for id in &ids {
-            let _ = self.db_events()?.delete(&mut txn, id.as_slice());
+        let mut count: usize = 0;
+        for id in ids {
+            if self.delete_record(self.db_events()?, id.as_slice(), Some(&mut *txn))? {
+                count += 1;
+            }
         }
-        tracing::info!("PRUNE: deleted {} records from events", ids.len());
+        tracing::info!("PRUNE: deleted {} records from events", count);
 
         txn.commit()?;
 
-        Ok(ids.len())
+        Ok(())
     }
 
     // General key-value functions --------------------------------------------------
@@ -593,7 +1322,7 @@ impl Storage {
             Ok(())
         };
 
-        write_transact!(self, rw_txn, f)
+        write_transact_as!(self, rw_txn, f, WriteClass::Critical)
     }
 
     /// Read the user's encrypted private key
@@ -653,6 +1382,90 @@ impl Storage {
         write_transact!(self, rw_txn, f)
     }
 
+    /// Write the remote signer (NIP-46 bunker) we use
+    pub fn write_bunker_connection<'a>(
+        &'a self,
+        conn: &BunkerConnection,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let bytes = conn.write_to_vec()?;
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            self.general.put(txn, b"bunker_connection", &bytes)?;
+            Ok(())
+        };
+
+        write_transact_as!(self, rw_txn, f, WriteClass::Critical)
+    }
+
+    /// Read the remote signer (NIP-46 bunker) we use
+    pub fn read_bunker_connection(&self) -> Result<Option<BunkerConnection>, Error> {
+        let txn = self.env.read_txn()?;
+        match self.general.get(&txn, b"bunker_connection")? {
+            None => Ok(None),
+            Some(bytes) => Ok(Some(BunkerConnection::read_from_buffer(bytes)?)),
+        }
+    }
+
+    /// Delete the remote signer (NIP-46 bunker) we use
+    pub fn delete_bunker_connection<'a>(
+        &'a self,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            self.general.delete(txn, b"bunker_connection")?;
+            Ok(())
+        };
+
+        write_transact_as!(self, rw_txn, f, WriteClass::Critical)
+    }
+
+    /// Write the wallet (NIP-47) we pay zaps with. It holds a key that can spend
+    /// from the wallet, so if `sealed` it is sealed, and the identity must be
+    /// unlocked. Otherwise it is written in the clear.
+    pub fn write_nwc_connection<'a>(
+        &'a self,
+        conn: &NwcConnection,
+        sealed: bool,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let bytes = if sealed {
+            self.seal_secret(conn.write_to_vec()?)?
+        } else {
+            encryption::in_the_clear(&conn.write_to_vec()?)
+        };
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            self.general.put(txn, b"nwc_connection", &bytes)?;
+            Ok(())
+        };
+
+        write_transact_as!(self, rw_txn, f, WriteClass::Critical)
+    }
+
+    /// Read the wallet (NIP-47) we pay zaps with. The identity must be unlocked if
+    /// it was sealed.
+    pub fn read_nwc_connection(&self) -> Result<Option<NwcConnection>, Error> {
+        let txn = self.env.read_txn()?;
+        match self.general.get(&txn, b"nwc_connection")? {
+            None => Ok(None),
+            Some(bytes) => Ok(Some(NwcConnection::read_from_buffer(&self.unseal(bytes)?)?)),
+        }
+    }
+
+    /// Delete the wallet (NIP-47) we pay zaps with
+    pub fn delete_nwc_connection<'a>(
+        &'a self,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            self.general.delete(txn, b"nwc_connection")?;
+            Ok(())
+        };
+
+        write_transact_as!(self, rw_txn, f, WriteClass::Critical)
+    }
+
     // Flags ------------------------------------------------------------
 
     def_flag!(following_only, b"following_only", false);
@@ -668,6 +1481,7 @@ impl Storage {
         b"reprocess_relay_lists_needed",
         true
     );
+    def_flag!(reseal_needed, b"reseal_needed", false);
 
     // Settings ----------------------------------------------------------
 
@@ -724,6 +1538,24 @@ impl Storage {
     def_setting!(reactions, b"reactions", bool, true);
     def_setting!(enable_zap_receipts, b"enable_zap_receipts", bool, true);
     def_setting!(show_media, b"show_media", bool, true);
+    def_setting!(
+        remote_content_followed,
+        b"remote_content_followed",
+        String,
+        "auto".to_owned()
+    );
+    def_setting!(
+        remote_content_second_degree,
+        b"remote_content_second_degree",
+        String,
+        "click".to_owned()
+    );
+    def_setting!(
+        remote_content_strangers,
+        b"remote_content_strangers",
+        String,
+        "never".to_owned()
+    );
     def_setting!(
         approve_content_warning,
         b"approve_content_warning",
@@ -899,15 +1731,366 @@ impl Storage {
         false
     );
 
+    def_setting!(feed_deduplicate, b"feed_deduplicate", bool, false);
+    def_setting!(privacy_mode, b"privacy_mode", bool, false);
+    def_setting!(
+        privacy_randomize_client_tag,
+        b"privacy_randomize_client_tag",
+        bool,
+        false
+    );
+    def_setting!(
+        privacy_time_jitter_secs,
+        b"privacy_time_jitter_secs",
+        u64,
+        120
+    );
+    def_setting!(blocked_relays, b"blocked_relays", Vec<String>, Vec::new());
+    def_setting!(
+        blocked_relay_patterns,
+        b"blocked_relay_patterns",
+        Vec<String>,
+        Vec::new()
+    );
+    def_setting!(feed_show_bridged, b"feed_show_bridged", bool, true);
+    def_setting!(rss_enabled, b"rss_enabled", bool, false);
+    def_setting!(rss_poll_interval_mins, b"rss_poll_interval_mins", u64, 60);
+    def_setting!(rss_feeds, b"rss_feeds", Vec<String>, Vec::new());
+    def_setting!(live_activities, b"live_activities", bool, true);
+    def_setting!(relay_latency_probing, b"relay_latency_probing", bool, true);
+    def_setting!(
+        relay_latency_probe_interval_mins,
+        b"relay_latency_probe_interval_mins",
+        u64,
+        60
+    );
+    def_setting!(
+        relay_prefer_low_latency,
+        b"relay_prefer_low_latency",
+        bool,
+        true
+    );
+    def_setting!(relay_monitors, b"relay_monitors", Vec<String>, Vec::new());
+    def_setting!(person_cache_capacity, b"person_cache_capacity", u64, 4096);
+    def_setting!(relay_cache_capacity, b"relay_cache_capacity", u64, 1024);
+    def_setting!(dm_autoreply, b"dm_autoreply", bool, false);
+    def_setting!(
+        dm_autoreply_message,
+        b"dm_autoreply_message",
+        String,
+        "Hi {name}, I'm away and will read your message when I'm back.".to_owned()
+    );
+    def_setting!(
+        dm_autoreply_interval_hours,
+        b"dm_autoreply_interval_hours",
+        u64,
+        24
+    );
+    def_setting!(
+        dm_autoreply_allowlist,
+        b"dm_autoreply_allowlist",
+        String,
+        "".to_owned()
+    );
+    def_setting!(
+        dm_autoreply_denylist,
+        b"dm_autoreply_denylist",
+        String,
+        "".to_owned()
+    );
+    def_setting!(
+        dm_autoreply_sent,
+        b"dm_autoreply_sent",
+        Vec<String>,
+        Vec::new()
+    );
+    def_setting!(alert_dm_from_follow, b"alert_dm_from_follow", bool, true);
+    def_setting!(alert_dm, b"alert_dm", bool, true);
+    def_setting!(alert_mention, b"alert_mention", bool, true);
+    def_setting!(alert_reaction, b"alert_reaction", bool, false);
+    def_setting!(alert_quiet_hours, b"alert_quiet_hours", bool, false);
+    def_setting!(alert_quiet_start_hour, b"alert_quiet_start_hour", u8, 22);
+    def_setting!(alert_quiet_end_hour, b"alert_quiet_end_hour", u8, 7);
+    def_setting!(
+        alert_quiet_hours_allow_follow_dms,
+        b"alert_quiet_hours_allow_follow_dms",
+        bool,
+        true
+    );
+    def_setting!(
+        notify_only_my_threads,
+        b"notify_only_my_threads",
+        bool,
+        false
+    );
+    def_setting!(
+        notify_min_trust,
+        b"notify_min_trust",
+        String,
+        "everyone".to_owned()
+    );
+    def_setting!(
+        notify_identities,
+        b"notify_identities",
+        Vec<String>,
+        Vec::new()
+    );
+    def_setting!(
+        profile_refresh_max_per_minute,
+        b"profile_refresh_max_per_minute",
+        u32,
+        60
+    );
+    def_setting!(
+        split_long_notes_threshold,
+        b"split_long_notes_threshold",
+        u64,
+        2000
+    );
+    def_setting!(split_long_notes, b"split_long_notes", bool, false);
+    def_setting!(
+        legacy_thread_heuristics,
+        b"legacy_thread_heuristics",
+        bool,
+        true
+    );
+    def_setting!(write_redundancy_target, b"write_redundancy_target", u8, 0);
+    def_setting!(
+        fallback_write_relays,
+        b"fallback_write_relays",
+        String,
+        String::new()
+    );
+    def_setting!(write_relay_rotation, b"write_relay_rotation", bool, false);
+    def_setting!(smart_lists, b"smart_lists", bool, true);
+    def_setting!(encrypt_with_nip44, b"encrypt_with_nip44", bool, true);
+    def_setting!(
+        profile_fetch_metadata,
+        b"profile_fetch_metadata",
+        bool,
+        true
+    );
+    def_setting!(
+        profile_fetch_relay_list,
+        b"profile_fetch_relay_list",
+        bool,
+        true
+    );
+    def_setting!(profile_fetch_notes, b"profile_fetch_notes", bool, true);
+    def_setting!(
+        profile_fetch_notes_limit,
+        b"profile_fetch_notes_limit",
+        u64,
+        0
+    );
+    def_setting!(
+        profile_fetch_augments,
+        b"profile_fetch_augments",
+        bool,
+        true
+    );
+    def_setting!(log_filter, b"log_filter", String, "".to_string());
+    def_setting!(alt_text_reminder, b"alt_text_reminder", bool, true);
+    def_setting!(alt_text_required, b"alt_text_required", bool, false);
+    def_setting!(
+        history_sweep_relays,
+        b"history_sweep_relays",
+        String,
+        "wss://relay.damus.io wss://nos.lol wss://relay.nostr.band wss://relay.primal.net"
+            .to_owned()
+    );
+    def_setting!(
+        duplicate_post_window_hours,
+        b"duplicate_post_window_hours",
+        u64,
+        24
+    );
+    def_setting!(dormant_after_days, b"dormant_after_days", u64, 60);
+    def_setting!(encrypt_at_rest, b"encrypt_at_rest", bool, false);
+    def_setting!(auto_prune, b"auto_prune", bool, false);
+    def_setting!(prune_max_events_mb, b"prune_max_events_mb", u64, 0);
+    def_setting!(prune_kind_days, b"prune_kind_days", String, String::new());
+    def_setting!(relaxed_durability, b"relaxed_durability", bool, false);
     // -------------------------------------------------------------------
 
+    // The keys of all of the settings above, so they can be carried between
+    // installs (see identity_bundle). Add new settings here too.
+    const SETTING_KEYS: &[&str] = &[
+        "public_key",
+        "log_n",
+        "login_at_startup",
+        "offline",
+        "load_avatars",
+        "load_media",
+        "check_nip05",
+        "wgpu_renderer",
+        "automatically_fetch_metadata",
+        "relay_connection_requires_approval",
+        "relay_auth_requires_approval",
+        "num_relays_per_person",
+        "max_relays",
+        "feed_chunk",
+        "replies_chunk",
+        "person_feed_chunk",
+        "overlap",
+        "reposts",
+        "show_long_form",
+        "show_mentions",
+        "direct_messages",
+        "future_allowance_secs",
+        "hide_mutes_entirely",
+        "reactions",
+        "enable_zap_receipts",
+        "show_media",
+        "remote_content_followed",
+        "remote_content_second_degree",
+        "remote_content_strangers",
+        "approve_content_warning",
+        "show_deleted_events",
+        "pow",
+        "set_client_tag",
+        "set_user_agent",
+        "delegatee_tag",
+        "max_fps",
+        "recompute_feed_periodically",
+        "feed_recompute_interval_ms",
+        "feed_thread_scroll_to_main_event",
+        "theme_variant",
+        "dark_mode",
+        "follow_os_dark_mode",
+        "override_dpi",
+        "highlight_unread_events",
+        "feed_newest_at_bottom",
+        "posting_area_at_top",
+        "status_bar",
+        "image_resize_algorithm",
+        "inertial_scrolling",
+        "mouse_acceleration",
+        "relay_list_becomes_stale_minutes",
+        "metadata_becomes_stale_minutes",
+        "nip05_becomes_stale_if_valid_hours",
+        "nip05_becomes_stale_if_invalid_minutes",
+        "avatar_becomes_stale_hours",
+        "media_becomes_stale_hours",
+        "max_websocket_message_size_kb",
+        "max_websocket_frame_size_kb",
+        "websocket_accept_unmasked_frames",
+        "websocket_connect_timeout_sec",
+        "websocket_ping_frequency_sec",
+        "fetcher_metadata_looptime_ms",
+        "fetcher_looptime_ms",
+        "fetcher_connect_timeout_sec",
+        "fetcher_timeout_sec",
+        "fetcher_max_requests_per_host",
+        "fetcher_host_exclusion_on_low_error_secs",
+        "fetcher_host_exclusion_on_med_error_secs",
+        "fetcher_host_exclusion_on_high_error_secs",
+        "nip11_lines_to_output_on_error",
+        "prune_period_days",
+        "cache_prune_period_days",
+        "avoid_spam_on_unsafe_relays",
+        "feed_deduplicate",
+        "privacy_mode",
+        "privacy_randomize_client_tag",
+        "privacy_time_jitter_secs",
+        "blocked_relays",
+        "blocked_relay_patterns",
+        "feed_show_bridged",
+        "rss_enabled",
+        "rss_poll_interval_mins",
+        "rss_feeds",
+        "live_activities",
+        "relay_latency_probing",
+        "relay_latency_probe_interval_mins",
+        "relay_prefer_low_latency",
+        "relay_monitors",
+        "person_cache_capacity",
+        "relay_cache_capacity",
+        "dm_autoreply",
+        "dm_autoreply_message",
+        "dm_autoreply_interval_hours",
+        "dm_autoreply_allowlist",
+        "dm_autoreply_denylist",
+        "alert_dm_from_follow",
+        "alert_dm",
+        "alert_mention",
+        "alert_reaction",
+        "alert_quiet_hours",
+        "alert_quiet_start_hour",
+        "alert_quiet_end_hour",
+        "alert_quiet_hours_allow_follow_dms",
+        "notify_only_my_threads",
+        "notify_min_trust",
+        "notify_identities",
+        "profile_refresh_max_per_minute",
+        "split_long_notes_threshold",
+        "split_long_notes",
+        "legacy_thread_heuristics",
+        "write_redundancy_target",
+        "fallback_write_relays",
+        "write_relay_rotation",
+        "smart_lists",
+        "encrypt_with_nip44",
+        "profile_fetch_metadata",
+        "profile_fetch_relay_list",
+        "profile_fetch_notes",
+        "profile_fetch_notes_limit",
+        "profile_fetch_augments",
+        "log_filter",
+        "alt_text_reminder",
+        "alt_text_required",
+        "history_sweep_relays",
+        "duplicate_post_window_hours",
+        "dormant_after_days",
+        "encrypt_at_rest",
+        "auto_prune",
+        "prune_max_events_mb",
+        "prune_kind_days",
+        "relaxed_durability",
+    ];
+
+    /// The stored bytes of every setting that has been set, by key
+    pub(crate) fn read_raw_settings(&self) -> Result<Vec<(String, Vec<u8>)>, Error> {
+        let txn = self.env.read_txn()?;
+        let mut output: Vec<(String, Vec<u8>)> = Vec::new();
+        for key in Self::SETTING_KEYS {
+            if let Some(bytes) = self.general.get(&txn, key.as_bytes())? {
+                output.push((key.to_string(), bytes.to_owned()));
+            }
+        }
+        Ok(output)
+    }
+
+    /// If this version has a setting of this key
+    pub(crate) fn is_setting_key(key: &str) -> bool {
+        Self::SETTING_KEYS.contains(&key)
+    }
+
+    /// Write the stored bytes of a setting. Unknown keys are an error. A value that
+    /// does not decode reads as the setting's default.
+    pub(crate) fn write_raw_setting<'a>(
+        &'a self,
+        key: &str,
+        bytes: &[u8],
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        if !Self::is_setting_key(key) {
+            return Err(ErrorKind::General(format!("Unknown setting {}", key)).into());
+        }
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            Ok(self.general.put(txn, key.as_bytes(), bytes)?)
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
     /// Get personlist metadata
     #[inline]
     pub fn get_person_list_metadata(
         &self,
         list: PersonList,
     ) -> Result<Option<PersonListMetadata>, Error> {
-        self.get_person_list_metadata3(list)
+        self.get_person_list_metadata6(list)
     }
 
     /// Set personlist metadata
@@ -918,7 +2101,7 @@ impl Storage {
         metadata: &PersonListMetadata,
         rw_txn: Option<&mut RwTxn<'a>>,
     ) -> Result<(), Error> {
-        self.set_person_list_metadata3(list, metadata, rw_txn)
+        self.set_person_list_metadata6(list, metadata, rw_txn)
     }
 
     /// Get all person lists with their metadata
@@ -926,7 +2109,7 @@ impl Storage {
     pub fn get_all_person_list_metadata(
         &self,
     ) -> Result<Vec<(PersonList, PersonListMetadata)>, Error> {
-        self.get_all_person_list_metadata3()
+        self.get_all_person_list_metadata6()
     }
 
     /// Find a person list by "d" tag
@@ -935,7 +2118,7 @@ impl Storage {
         &self,
         dtag: &str,
     ) -> Result<Option<(PersonList, PersonListMetadata)>, Error> {
-        self.find_person_list_by_dtag3(dtag)
+        self.find_person_list_by_dtag6(dtag)
     }
 
     /// Allocate a new person list
@@ -945,7 +2128,7 @@ impl Storage {
         metadata: &PersonListMetadata,
         rw_txn: Option<&mut RwTxn<'a>>,
     ) -> Result<PersonList, Error> {
-        self.allocate_person_list3(metadata, rw_txn)
+        self.allocate_person_list6(metadata, rw_txn)
     }
 
     /// Deallocate an empty person list
@@ -955,7 +2138,7 @@ impl Storage {
         list: PersonList,
         rw_txn: Option<&mut RwTxn<'a>>,
     ) -> Result<(), Error> {
-        self.deallocate_person_list3(list, rw_txn)
+        self.deallocate_person_list6(list, rw_txn)
     }
 
     pub fn rename_person_list<'a>(
@@ -1023,9 +2206,41 @@ impl Storage {
     #[inline]
     #[allow(dead_code)]
     pub fn get_event_ids_with_hashtag(&self, hashtag: &String) -> Result<Vec<Id>, Error> {
+        self.event_read_barrier()?;
         self.get_event_ids_with_hashtag1(hashtag)
     }
 
+    /// Get the media and links of an event (None if it has neither, or we don't have it)
+    #[inline]
+    pub fn read_event_media(&self, id: Id) -> Result<Option<EventMedia>, Error> {
+        self.event_read_barrier()?;
+        self.read_event_media2(id)
+    }
+
+    /// Get the media of an author's events, newest first, from `until` back.
+    /// This reads no events, so it is cheap enough for media grids.
+    #[inline]
+    pub fn get_author_media(
+        &self,
+        author: PublicKey,
+        until: Option<Unixtime>,
+        limit: usize,
+    ) -> Result<Vec<MediaGalleryItem>, Error> {
+        self.event_read_barrier()?;
+        self.get_author_media1(author, until, limit)
+    }
+
+    /// Get the media of events with a hashtag, newest first, from `until` back.
+    #[inline]
+    pub fn get_hashtag_media(
+        &self,
+        hashtag: &str,
+        until: Option<Unixtime>,
+        limit: usize,
+    ) -> Result<Vec<MediaGalleryItem>, Error> {
+        self.get_hashtag_media1(hashtag, until, limit)
+    }
+
     /// Write a relay record.
     ///
     /// NOTE: this overwrites. You may wish to read first, or you might prefer
@@ -1036,7 +2251,8 @@ impl Storage {
         relay: &Relay,
         rw_txn: Option<&mut RwTxn<'a>>,
     ) -> Result<(), Error> {
-        self.write_relay2(relay, rw_txn)
+        self.cache_written(Written::Relay(relay.url.clone()));
+        self.write_relay6(relay, rw_txn)
     }
 
     /// Delete a relay record
@@ -1047,7 +2263,8 @@ impl Storage {
         url: &RelayUrl,
         rw_txn: Option<&mut RwTxn<'a>>,
     ) -> Result<(), Error> {
-        self.delete_relay2(url, rw_txn)
+        self.cache_written(Written::Relay(url.clone()));
+        self.delete_relay6(url, rw_txn)
     }
 
     /// Write a new relay record only if it is missing
@@ -1079,7 +2296,8 @@ impl Storage {
     where
         M: FnMut(&mut Relay),
     {
-        self.modify_relay2(url, modify, rw_txn)
+        self.cache_written(Written::Relay(url.clone()));
+        self.modify_relay6(url, modify, rw_txn)
     }
 
     //// Modify all relay records
@@ -1092,17 +2310,27 @@ impl Storage {
     where
         M: FnMut(&mut Relay),
     {
-        self.modify_all_relays2(modify, rw_txn)
+        self.cache_written(Written::AllRelays);
+        self.modify_all_relays6(modify, rw_txn)
     }
 
-    /// Read a relay record
-    #[inline]
+    /// Read a relay record. Reads outside of a transaction go through a cache.
     pub fn read_relay<'a>(
         &'a self,
         url: &RelayUrl,
         txn: Option<&RoTxn<'a>>,
     ) -> Result<Option<Relay>, Error> {
-        self.read_relay2(url, txn)
+        if txn.is_some() {
+            return self.read_relay6(url, txn);
+        }
+        let generation = self.relay_cache.generation();
+        if let Some(relay) = self.relay_cache.get(url) {
+            return Ok(relay);
+        }
+        let relay = self.read_relay6(url, None)?;
+        self.relay_cache
+            .insert(generation, url.clone(), relay.clone());
+        Ok(relay)
     }
 
     /// Read or create relay
@@ -1132,7 +2360,7 @@ impl Storage {
     where
         F: Fn(&Relay) -> bool,
     {
-        self.filter_relays2(f)
+        self.filter_relays6(f)
     }
 
     /// Load effective relay list
@@ -1246,8 +2474,8 @@ impl Storage {
                 f(txn)?;
             }
             None => {
-                let mut txn = self.env.write_txn()?;
-                f(&mut txn)?;
+                let mut txn = self.write_txn()?;
+                f(&mut *txn)?;
                 txn.commit()?;
             }
         };
@@ -1305,12 +2533,64 @@ impl Storage {
     /// Read an event
     #[inline]
     pub fn read_event(&self, id: Id) -> Result<Option<Event>, Error> {
+        if let Some(event) = self.read_queued_event(id) {
+            return Ok(Some(event));
+        }
         self.read_event3(id)
     }
 
+    /// A page of up to `limit` events (in id order), starting after the cursor or at
+    /// the first. Records that fail to decode are quarantined.
+    pub fn page_events(
+        &self,
+        cursor: Option<&PageCursor>,
+        limit: usize,
+    ) -> Result<RecordPage<Event>, Error> {
+        self.event_read_barrier()?;
+        let db = self.db_events()?;
+        let mut corrupt = CorruptRecords::new("events3");
+        let page = self
+            .page_records(db, cursor, limit)?
+            .decode(&mut corrupt, |val| Event::read_from_buffer(val));
+        self.quarantine_corrupt(db, corrupt, None)?;
+        Ok(page)
+    }
+
+    /// The latest `limit` events of this kind by this author, newest first. Only
+    /// those are read from the index, however many the author has.
+    pub fn latest_events_by_author(
+        &self,
+        author: PublicKey,
+        kind: EventKind,
+        limit: usize,
+    ) -> Result<Vec<Event>, Error> {
+        self.event_read_barrier()?;
+        let mut prefix: Vec<u8> = author.as_slice().to_owned();
+        prefix.extend(u32::from(kind).to_be_bytes());
+        // Keys sort newest first within the author and kind
+        let keys = self.filter_records_limited(
+            self.db_event_akci_index()?,
+            &prefix,
+            |_, _| true,
+            limit,
+            ScanOrder::Forward,
+        )?;
+        let mut events: Vec<Event> = Vec::with_capacity(keys.len());
+        for (key, _) in keys {
+            let (_, _, _, id) = AkciKey::from_bytes(&key)?.into_parts()?;
+            if let Some(event) = self.read_event(id)? {
+                events.push(event);
+            }
+        }
+        Ok(events)
+    }
+
     /// If we have th event
     #[inline]
     pub fn has_event(&self, id: Id) -> Result<bool, Error> {
+        if self.is_event_queued(id) {
+            return Ok(true);
+        }
         self.has_event3(id)
     }
 
@@ -1324,6 +2604,8 @@ impl Storage {
 
     /// Delete the event
     pub fn delete_event<'a>(&'a self, id: Id, rw_txn: Option<&mut RwTxn<'a>>) -> Result<(), Error> {
+        self.event_read_barrier()?;
+
         let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
             // Delete from the events table
             self.delete_event3(id, Some(txn))?;
@@ -1350,6 +2632,9 @@ impl Storage {
             // Delete from event_viewed
             self.db_event_viewed()?.delete(txn, id.as_slice())?;
 
+            // Delete from event_media
+            self.db_event_media()?.delete(txn, id.as_slice())?;
+
             // DO NOT delete from relationships. The related event still applies in case
             // this event comes back, ESPECIALLY deletion relationships!
 
@@ -1361,6 +2646,7 @@ impl Storage {
             //
             // These include
             //   db_event_hashtags()
+            //   db_event_media_by_author(), db_event_media_by_hashtag()
             //   db_relationships(), where the ID is the 2nd half of the key
             //   db_reprel()
             //   db_event_akci_index()
@@ -1459,7 +2745,26 @@ impl Storage {
     where
         F: Fn(&Event) -> bool,
     {
+        self.find_events_by_filter_prescreened(filter, |_| true, screen)
+    }
+
+    /// Like [find_events_by_filter](Storage::find_events_by_filter), but events are
+    /// first passed to `prescreen` as an [EventView], and only those it keeps are
+    /// decoded for the filter and `screen`. Use it to pass over most events on their
+    /// id, kind, author or time without the cost of decoding them.
+    pub fn find_events_by_filter_prescreened<P, F>(
+        &self,
+        filter: &Filter,
+        prescreen: P,
+        screen: F,
+    ) -> Result<Vec<Event>, Error>
+    where
+        P: Fn(&EventView<'_>) -> bool,
+        F: Fn(&Event) -> bool,
+    {
+        self.event_read_barrier()?;
         let txn = self.env.read_txn()?;
+        let mut corrupt = CorruptRecords::new("events3");
 
         // We insert into a BTreeSet to keep them time-ordered
         let mut output: BTreeSet<Event> = BTreeSet::new();
@@ -1477,7 +2782,17 @@ impl Storage {
                     break;
                 }
                 if let Some(bytes) = self.db_events()?.get(&txn, id.as_slice())? {
-                    let event = Event::read_from_buffer(bytes)?;
+                    if !prescreen(&EventView::new(bytes)) {
+                        continue;
+                    }
+                    let event = match corrupt.check(
+                        id.as_slice(),
+                        bytes,
+                        self.metered("events3", bytes, |b| Event::read_from_buffer(b)),
+                    ) {
+                        Some(event) => event,
+                        None => continue,
+                    };
                     if filter.event_matches(&event) && screen(&event) {
                         output.insert(event);
                     }
@@ -1507,14 +2822,24 @@ impl Storage {
                         let key = AkciKey::from_bytes(keybytes)?;
                         let (_, _, created_at, id) = key.into_parts()?;
                         if let Some(bytes) = self.db_events()?.get(&txn, id.as_slice())? {
-                            let event = Event::read_from_buffer(bytes)?;
-
                             // If we have gone beyond since, we can stop early
                             // (We have to check because `since` might change in this loop)
                             if created_at < since {
                                 break 'per_event;
                             }
 
+                            if !prescreen(&EventView::new(bytes)) {
+                                continue 'per_event;
+                            }
+                            let event = match corrupt.check(
+                                id.as_slice(),
+                                bytes,
+                                self.metered("events3", bytes, |b| Event::read_from_buffer(b)),
+                            ) {
+                                Some(event) => event,
+                                None => continue 'per_event,
+                            };
+
                             // check against the rest of the filter
                             if filter.event_matches(&event) && screen(&event) {
                                 output.insert(event);
@@ -1563,14 +2888,24 @@ impl Storage {
                     let key = KciKey::from_bytes(keybytes)?;
                     let (_, created_at, id) = key.into_parts()?;
                     if let Some(bytes) = self.db_events()?.get(&txn, id.as_slice())? {
-                        let event = Event::read_from_buffer(bytes)?;
-
                         // If we have gone beyond since, we can stop early
                         // (We have to check because `since` might change in this loop)
                         if created_at < since {
                             break 'per_event;
                         }
 
+                        if !prescreen(&EventView::new(bytes)) {
+                            continue 'per_event;
+                        }
+                        let event = match corrupt.check(
+                            id.as_slice(),
+                            bytes,
+                            self.metered("events3", bytes, |b| Event::read_from_buffer(b)),
+                        ) {
+                            Some(event) => event,
+                            None => continue 'per_event,
+                        };
+
                         // check against the rest of the filter
                         if filter.event_matches(&event) && screen(&event) {
                             output.insert(event);
@@ -1592,10 +2927,17 @@ impl Storage {
             tracing::warn!("KINDS SCRAPE OF STORAGE");
             let iter = self.db_events()?.iter(&txn)?;
             for result in iter {
-                let (_key, bytes) = result?;
+                let (key, bytes) = result?;
                 if let Some(kind) = Event::get_kind_from_speedy_bytes(bytes) {
-                    if filter.kinds.contains(&kind) {
-                        let event = Event::read_from_buffer(bytes)?;
+                    if filter.kinds.contains(&kind) && prescreen(&EventView::new(bytes)) {
+                        let event = match corrupt.check(
+                            key,
+                            bytes,
+                            self.metered("events3", bytes, |b| Event::read_from_buffer(b)),
+                        ) {
+                            Some(event) => event,
+                            None => continue,
+                        };
                         if filter.event_matches(&event) && screen(&event) {
                             output.insert(event);
                             // We can't stop at a limit because our data is unsorted
@@ -1608,11 +2950,18 @@ impl Storage {
             tracing::warn!("AUTHOR SCRAPE OF STORAGE");
             let iter = self.db_events()?.iter(&txn)?;
             for result in iter {
-                let (_key, bytes) = result?;
+                let (key, bytes) = result?;
                 if let Some(author) = Event::get_pubkey_from_speedy_bytes(bytes) {
                     let pkh: PublicKeyHex = author.into();
-                    if filter.authors.contains(&pkh) {
-                        let event = Event::read_from_buffer(bytes)?;
+                    if filter.authors.contains(&pkh) && prescreen(&EventView::new(bytes)) {
+                        let event = match corrupt.check(
+                            key,
+                            bytes,
+                            self.metered("events3", bytes, |b| Event::read_from_buffer(b)),
+                        ) {
+                            Some(event) => event,
+                            None => continue,
+                        };
                         if filter.event_matches(&event) && screen(&event) {
                             output.insert(event);
                         }
@@ -1624,14 +2973,27 @@ impl Storage {
             tracing::warn!("FULL SCRAPE OF STORAGE");
             let iter = self.db_events()?.iter(&txn)?;
             for result in iter {
-                let (_key, bytes) = result?;
-                let event = Event::read_from_buffer(bytes)?;
-                if filter.event_matches(&event) && screen(&event) {
+                let (key, bytes) = result?;
+                if !prescreen(&EventView::new(bytes)) {
+                    continue;
+                }
+                let event = match corrupt.check(
+                    key,
+                    bytes,
+                    self.metered("events3", bytes, |b| Event::read_from_buffer(b)),
+                ) {
+                    Some(event) => event,
+                    None => continue,
+                };
+                if filter.event_matches(&event) && screen(&event) {
                     output.insert(event);
                 }
             }
         }
 
+        drop(txn);
+        self.quarantine_corrupt(self.db_events()?, corrupt, None)?;
+
         Ok(output
             .iter()
             .rev()
@@ -1643,6 +3005,8 @@ impl Storage {
     /// Search all events for the text, case insensitive. Both content and tags
     /// are searched.
     pub fn search_events(&self, text: &str) -> Result<Vec<Event>, Error> {
+        self.event_read_barrier()?;
+
         let event_kinds = crate::feed::feed_displayable_event_kinds(true);
 
         let needle = regex::escape(text.to_lowercase().as_str());
@@ -1764,6 +3128,8 @@ impl Storage {
             return Err(ErrorKind::TagNotIndexed(tagname.to_owned()).into());
         }
 
+        self.event_read_barrier()?;
+
         let mut ids: HashSet<Id> = HashSet::new();
         let txn = self.env.read_txn()?;
 
@@ -1801,18 +3167,28 @@ impl Storage {
         Ok(events)
     }
 
+    /// Index GiftWraps that arrived while we could not decrypt them.
+    /// Returns the ids of the GiftWraps that were indexed.
     #[inline]
-    pub(crate) fn index_unindexed_giftwraps(&self) -> Result<(), Error> {
+    pub(crate) fn index_unindexed_giftwraps(&self) -> Result<Vec<Id>, Error> {
         self.index_unindexed_giftwraps1()
     }
 
+    /// Index a GiftWrap that was stored unindexed, because it could not be
+    /// unwrapped inside the write transaction (a remote signer)
+    #[inline]
+    pub(crate) fn index_unindexed_giftwrap(&self, id: Id) -> Result<(), Error> {
+        self.index_giftwraps1(vec![id])?;
+        Ok(())
+    }
+
     pub(crate) fn get_highest_local_parent_event_id(&self, id: Id) -> Result<Option<Id>, Error> {
         let event = match self.read_event(id)? {
             Some(event) => event,
             None => return Ok(None),
         };
 
-        match event.replies_to() {
+        match crate::nip10::replies_to(&event) {
             Some(EventReference::Id { id: parent_id, .. }) => {
                 self.get_highest_local_parent_event_id(parent_id)
             }
@@ -1871,15 +3247,20 @@ impl Storage {
         self.find_relationships_by_addr2(addr)
     }
 
-    /// Get replies to the given event
+    /// Get replies to the given event. A reply that refers to it both by id and by
+    /// address is listed once.
     pub fn get_replies(&self, event: &Event) -> Result<Vec<Id>, Error> {
         let mut output = self.get_non_replaceable_replies(event.id)?;
-        output.extend(self.get_replaceable_replies(&EventAddr {
+        for id in self.get_replaceable_replies(&EventAddr {
             d: event.parameter().unwrap_or("".to_string()),
             relays: vec![],
             kind: event.kind,
             author: event.pubkey,
-        })?);
+        })? {
+            if !output.contains(&id) {
+                output.push(id);
+            }
+        }
         Ok(output)
     }
 
@@ -1935,7 +3316,7 @@ impl Storage {
                     '+'
                 };
                 phase1.insert(by, symbol);
-                if Some(by) == GLOBALS.identity.public_key() {
+                if Some(by) == self.context.public_key() {
                     self_already_reacted = true;
                 }
             }
@@ -1955,15 +3336,57 @@ impl Storage {
         Ok((v, self_already_reacted))
     }
 
-    /// Get the zap total of a given event
-    pub fn get_zap_total(&self, id: Id) -> Result<MilliSatoshi, Error> {
-        let mut total = MilliSatoshi(0);
-        for (_, rel) in self.find_relationships_by_id(id)? {
-            if let RelationshipById::Zaps { by: _, amount } = rel {
-                total = total + amount;
-            }
-        }
-        Ok(total)
+    /// Record a validated zap receipt (see [crate::zaps::validate_zap_receipt])
+    pub fn write_zap_receipt<'a>(
+        &'a self,
+        zap: &ZapReceipt,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.write_zap_receipt1(
+            zap.zapped,
+            zap.receipt,
+            zap.zapper,
+            zap.amount,
+            zap.signer,
+            rw_txn,
+        )
+    }
+
+    /// The validated zap receipts of an event, whoever signed them. See
+    /// [crate::zaps::verified_zap_receipts] for those that count.
+    pub fn read_zap_receipts(&self, zapped: Id) -> Result<Vec<ZapReceipt>, Error> {
+        Ok(self
+            .read_zap_receipts1(zapped)?
+            .drain(..)
+            .map(|(receipt, zapper, amount, signer)| ZapReceipt {
+                zapped,
+                receipt,
+                zapper,
+                amount,
+                signer,
+            })
+            .collect())
+    }
+
+    /// Remember the key that signs zap receipts for `recipient` (None if they have
+    /// no lightning service that does zaps), looked up at `looked_up_at`
+    pub(crate) fn write_zap_signer<'a>(
+        &'a self,
+        recipient: PublicKey,
+        signer: Option<PublicKey>,
+        looked_up_at: Unixtime,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.write_zap_signer1(recipient, signer, looked_up_at, rw_txn)
+    }
+
+    /// When we looked up the key that signs zap receipts for `recipient`, and what
+    /// it was
+    pub(crate) fn read_zap_signer(
+        &self,
+        recipient: PublicKey,
+    ) -> Result<Option<(Unixtime, Option<PublicKey>)>, Error> {
+        self.read_zap_signer1(recipient)
     }
 
     /// Get whether an event was deleted, and if so the optional reason
@@ -2018,7 +3441,8 @@ impl Storage {
         person: &Person,
         rw_txn: Option<&mut RwTxn<'a>>,
     ) -> Result<(), Error> {
-        self.write_person2(person, rw_txn)
+        self.cache_written(Written::Person(person.pubkey));
+        self.write_person3(person, rw_txn)
     }
 
     /// Has a person record
@@ -2028,17 +3452,26 @@ impl Storage {
         pubkey: &PublicKey,
         txn: Option<&RoTxn<'a>>,
     ) -> Result<bool, Error> {
-        self.has_person2(pubkey, txn)
+        self.has_person3(pubkey, txn)
     }
 
-    /// Read a person record
-    #[inline]
+    /// Read a person record. Reads outside of a transaction go through a cache.
     pub fn read_person<'a>(
         &'a self,
         pubkey: &PublicKey,
         txn: Option<&RoTxn<'a>>,
     ) -> Result<Option<Person>, Error> {
-        self.read_person2(pubkey, txn)
+        if txn.is_some() {
+            return self.read_person3(pubkey, txn);
+        }
+        let generation = self.person_cache.generation();
+        if let Some(person) = self.person_cache.get(pubkey) {
+            return Ok(person);
+        }
+        let person = self.read_person3(pubkey, None)?;
+        self.person_cache
+            .insert(generation, *pubkey, person.clone());
+        Ok(person)
     }
 
     /// Read a person record, create if missing
@@ -2088,7 +3521,29 @@ impl Storage {
     where
         F: Fn(&Person) -> bool,
     {
-        self.filter_people2(f)
+        self.filter_people3(f)
+    }
+
+    /// People whose nip05 is this (case-insensitively), whether or not it checked out
+    #[inline]
+    pub fn find_people_by_nip05(&self, nip05: &str) -> Result<Vec<Person>, Error> {
+        self.find_people3_by_nip05(nip05)
+    }
+
+    /// A page of up to `limit` person records (in public key order), starting after
+    /// the cursor or at the first. Records that fail to decode are quarantined.
+    pub fn page_people(
+        &self,
+        cursor: Option<&PageCursor>,
+        limit: usize,
+    ) -> Result<RecordPage<Person>, Error> {
+        let db = self.db_people()?;
+        let mut corrupt = CorruptRecords::new("people3");
+        let page = self
+            .page_records(db, cursor, limit)?
+            .decode(&mut corrupt, people3::decode_person3);
+        self.quarantine_corrupt(db, corrupt, None)?;
+        Ok(page)
     }
 
     /// Modify a person record
@@ -2102,7 +3557,8 @@ impl Storage {
     where
         M: FnMut(&mut Person),
     {
-        self.modify_person2(pubkey, modify, rw_txn)
+        self.cache_written(Written::Person(pubkey));
+        self.modify_person3(pubkey, modify, rw_txn)
     }
 
     //// Modify all person records
@@ -2115,7 +3571,8 @@ impl Storage {
     where
         M: FnMut(&mut Person),
     {
-        self.modify_all_people2(modify, rw_txn)
+        self.cache_written(Written::AllPeople);
+        self.modify_all_people3(modify, rw_txn)
     }
 
     /// Read a PersonRelay record
@@ -2218,7 +3675,8 @@ impl Storage {
             let relay = self.read_or_create_relay(&ranked_relay.0, None)?;
             ranked_relay.1 = (ranked_relay.1 as f32
                 * (relay.rank as f32 / 3.0)
-                * (0.75 + 0.25 * relay.success_rate())) as u64;
+                * (0.75 + 0.25 * relay.effective_success_rate()))
+                as u64;
         }
 
         // Resort
@@ -2275,7 +3733,7 @@ impl Storage {
 
     /// Get all the DM channels with associated data
     pub fn dm_channels(&self) -> Result<Vec<DmChannelData>, Error> {
-        let my_pubkey = match GLOBALS.identity.public_key() {
+        let my_pubkey = match self.context.public_key() {
             Some(pk) => pk,
             None => return Ok(Vec::new()),
         };
@@ -2305,6 +3763,7 @@ impl Storage {
             };
             if event.kind == EventKind::EncryptedDirectMessage {
                 let time = event.created_at;
+                let sent = (event.pubkey == my_pubkey) as usize;
                 let dmchannel = match DmChannel::from_event(event, Some(my_pubkey)) {
                     Some(dmc) => dmc,
                     None => continue,
@@ -2313,29 +3772,29 @@ impl Storage {
                     if time > dmcdata.latest_message_created_at {
                         dmcdata.latest_message_created_at = time;
                         dmcdata.latest_message_content =
-                            GLOBALS.identity.decrypt_event_contents(event).ok();
+                            self.context.decrypt_event_contents(event).ok();
                     }
                     dmcdata.message_count += 1;
                     dmcdata.unread_message_count += unread;
+                    dmcdata.sent_message_count += sent;
                 } else {
                     map.insert(
                         dmchannel.clone(),
                         DmChannelData {
                             dm_channel: dmchannel,
                             latest_message_created_at: time,
-                            latest_message_content: GLOBALS
-                                .identity
-                                .decrypt_event_contents(event)
-                                .ok(),
+                            latest_message_content: self.context.decrypt_event_contents(event).ok(),
                             message_count: 1,
                             unread_message_count: unread,
+                            sent_message_count: sent,
                         },
                     );
                 }
             } else if event.kind == EventKind::GiftWrap {
-                if let Ok(rumor) = GLOBALS.identity.unwrap_giftwrap(event) {
+                if let Ok(rumor) = self.giftwrap_rumor(event) {
                     let rumor_event = rumor.into_event_with_bad_signature();
                     let time = rumor_event.created_at;
+                    let sent = (rumor_event.pubkey == my_pubkey) as usize;
                     let dmchannel = match DmChannel::from_event(&rumor_event, Some(my_pubkey)) {
                         Some(dmc) => dmc,
                         None => continue,
@@ -2347,6 +3806,7 @@ impl Storage {
                         }
                         dmcdata.message_count += 1;
                         dmcdata.unread_message_count += unread;
+                        dmcdata.sent_message_count += sent;
                     } else {
                         map.insert(
                             dmchannel.clone(),
@@ -2356,6 +3816,7 @@ impl Storage {
                                 latest_message_content: Some(rumor_event.content.clone()),
                                 message_count: 1,
                                 unread_message_count: unread,
+                                sent_message_count: sent,
                             },
                         );
                     }
@@ -2372,9 +3833,32 @@ impl Storage {
         Ok(output)
     }
 
+    /// Keep the rumor of a giftwrap, so it need not be unwrapped again
+    pub(crate) fn write_rumor<'a>(
+        &'a self,
+        wrap_id: Id,
+        rumor: &Rumor,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.write_rumor1(wrap_id, rumor, rw_txn)
+    }
+
+    /// The rumor of a giftwrap, if we kept it
+    pub fn read_rumor(&self, wrap_id: Id) -> Result<Option<Rumor>, Error> {
+        self.read_rumor1(wrap_id)
+    }
+
+    /// The rumor of a giftwrap: the one we kept, or else unwrapped now
+    pub fn giftwrap_rumor(&self, event: &Event) -> Result<Rumor, Error> {
+        if let Ok(Some(rumor)) = self.read_rumor(event.id) {
+            return Ok(rumor);
+        }
+        self.context.unwrap_giftwrap(event)
+    }
+
     /// Get DM events (by id) in a channel
     pub fn dm_events(&self, channel: &DmChannel) -> Result<Vec<Id>, Error> {
-        let my_pubkey = match GLOBALS.identity.public_key() {
+        let my_pubkey = match self.context.public_key() {
             Some(pk) => pk,
             None => return Ok(Vec::new()),
         };
@@ -2395,7 +3879,7 @@ impl Storage {
             .drain(..)
             .map(|e| {
                 if e.kind == EventKind::GiftWrap {
-                    if let Ok(rumor) = GLOBALS.identity.unwrap_giftwrap(&e) {
+                    if let Ok(rumor) = self.giftwrap_rumor(&e) {
                         (rumor.created_at, e)
                     } else {
                         (e.created_at, e)
@@ -2411,21 +3895,30 @@ impl Storage {
         Ok(sortable.iter().map(|(_, e)| e.id).collect())
     }
 
-    /// Rebuild all the event indices.
-    pub fn rebuild_event_indices<'a>(
-        &'a self,
-        rw_txn: Option<&mut RwTxn<'a>>,
-    ) -> Result<(), Error> {
-        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
-            // Erase all indices first
-            self.db_event_akci_index()?.clear(txn)?;
-            self.db_event_kci_index()?.clear(txn)?;
-            self.db_event_tag_index()?.clear(txn)?;
-            self.db_hashtags()?.clear(txn)?;
+    /// Rebuild all the event indices. This commits as it goes (see
+    /// [migration_progress](Storage::migration_progress)) and resumes where it was
+    /// if it was interrupted.
+    pub fn rebuild_event_indices(&self) -> Result<(), Error> {
+        const CURSOR: &[u8] = b"rebuild_indexes_cursor";
+
+        // Erase all indices first, unless resuming
+        if self.migration_cursor(CURSOR)?.is_none() {
+            let mut txn = self.write_txn()?;
+            self.db_event_akci_index()?.clear(&mut txn)?;
+            self.db_event_kci_index()?.clear(&mut txn)?;
+            self.db_event_tag_index()?.clear(&mut txn)?;
+            self.db_hashtags()?.clear(&mut txn)?;
+            self.db_event_media()?.clear(&mut txn)?;
+            self.db_event_media_by_author()?.clear(&mut txn)?;
+            self.db_event_media_by_hashtag()?.clear(&mut txn)?;
+            txn.commit()?;
+        }
 
-            let loop_txn = self.env.read_txn()?;
-            for result in self.db_events()?.iter(&loop_txn)? {
-                let (_key, val) = result?;
+        self.migrate_in_chunks(
+            self.db_events()?,
+            CURSOR,
+            "Rebuilding event indices",
+            |_key, val, txn| {
                 let event = Event::read_from_buffer(val)?;
 
                 // If giftwrap:
@@ -2456,12 +3949,18 @@ impl Storage {
                     } // upstream bug
                     self.add_hashtag(&hashtag, event.id, Some(txn))?;
                 }
-            }
-            self.set_flag_rebuild_indexes_needed(false, Some(txn))?;
-            Ok(())
-        };
+                let media = EventMedia::of_event(&event);
+                self.write_event_media2(event.id, &media, Some(txn))?;
+                self.write_event_media_index1(&event, &media, Some(txn))?;
+                Ok(())
+            },
+        )?;
 
-        write_transact!(self, rw_txn, f)
+        let mut txn = self.write_txn()?;
+        self.rebuild_people3_indexes(&mut txn)?;
+        self.set_flag_rebuild_indexes_needed(false, Some(&mut txn))?;
+        txn.commit()?;
+        Ok(())
     }
 
     pub fn rebuild_event_tags_index<'a>(
@@ -2485,7 +3984,7 @@ impl Storage {
     }
 
     pub fn reprocess_relay_lists(&self) -> Result<(), Error> {
-        let mut txn = self.env.write_txn()?;
+        let mut txn = self.write_txn()?;
 
         // Clear relay_list_created_at fields in person records so that
         // it will rebuild
@@ -2493,14 +3992,14 @@ impl Storage {
             |person| {
                 person.relay_list_created_at = None;
             },
-            Some(&mut txn),
+            Some(&mut *txn),
         )?;
 
         // Commit this change, otherwise read_person (which takes no transaction)
         // will give stale data when it is called within process_relay_list()
         txn.commit()?;
 
-        let mut txn = self.env.write_txn()?;
+        let mut txn = self.write_txn()?;
 
         // Load all RelayLists
         let mut filter = Filter::new();
@@ -2509,11 +4008,11 @@ impl Storage {
 
         // Process all RelayLists
         for event in relay_lists.iter() {
-            self.process_relay_list(event, Some(&mut txn))?;
+            self.process_relay_list(event, Some(&mut *txn))?;
         }
 
         // Turn off the flag
-        self.set_flag_reprocess_relay_lists_needed(false, Some(&mut txn))?;
+        self.set_flag_reprocess_relay_lists_needed(false, Some(&mut *txn))?;
 
         txn.commit()?;
 
@@ -2535,7 +4034,57 @@ impl Storage {
         lists: HashMap<PersonList, Private>,
         rw_txn: Option<&mut RwTxn<'a>>,
     ) -> Result<(), Error> {
-        self.write_person_lists2(pubkey, lists, rw_txn)
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            let followed = lists.contains_key(&PersonList::Followed);
+            self.update_person_list_times(
+                pubkey,
+                followed,
+                lists.contains_key(&PersonList::Muted),
+                Some(txn),
+            )?;
+            self.write_person_lists2(pubkey, lists, Some(txn))?;
+            self.follow_changes
+                .lock()
+                .push(FollowChange::Changed(*pubkey, followed));
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    // Keep followed_since and muted_until in step with the Followed and Muted lists
+    fn update_person_list_times<'a>(
+        &'a self,
+        pubkey: &PublicKey,
+        followed: bool,
+        muted: bool,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let now = Unixtime::now().unwrap().0;
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            let person = self
+                .read_person(pubkey, Some(&**txn))?
+                .unwrap_or_else(|| Person::new(pubkey.to_owned()));
+            let followed_since = match (followed, person.followed_since) {
+                (true, Some(since)) => Some(since),
+                (true, None) => Some(now),
+                (false, _) => None,
+            };
+            let muted_until = if muted { person.muted_until } else { None };
+            if followed_since != person.followed_since || muted_until != person.muted_until {
+                self.modify_person(
+                    *pubkey,
+                    |p| {
+                        p.followed_since = followed_since;
+                        p.muted_until = muted_until;
+                    },
+                    Some(txn),
+                )?;
+            }
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
     }
 
     /// Get people in a person list
@@ -2546,8 +4095,8 @@ impl Storage {
         if let Some(mut metadata) = self.get_person_list_metadata(list)? {
             if metadata.len != people.len() {
                 metadata.len = people.len();
-                let mut txn = self.env.write_txn()?;
-                self.set_person_list_metadata(list, &metadata, Some(&mut txn))?;
+                let mut txn = self.write_txn()?;
+                self.set_person_list_metadata(list, &metadata, Some(&mut *txn))?;
                 txn.commit()?;
             }
         }
@@ -2572,6 +4121,17 @@ impl Storage {
         rw_txn: Option<&mut RwTxn<'a>>,
     ) -> Result<(), Error> {
         let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            if list == PersonList::Followed || list == PersonList::Muted {
+                for (pubkey, _) in self.get_people_in_list2(list)? {
+                    let lists = self.read_person_lists(&pubkey)?;
+                    self.update_person_list_times(
+                        &pubkey,
+                        list != PersonList::Followed && lists.contains_key(&PersonList::Followed),
+                        list != PersonList::Muted && lists.contains_key(&PersonList::Muted),
+                        Some(txn),
+                    )?;
+                }
+            }
             self.clear_person_list2(list, Some(txn))?;
             let now = Unixtime::now().unwrap();
             if let Some(mut metadata) = self.get_person_list_metadata(list)? {
@@ -2579,6 +4139,9 @@ impl Storage {
                 metadata.len = 0;
                 self.set_person_list_metadata(list, &metadata, Some(txn))?;
             }
+            if list == PersonList::Followed {
+                self.follow_changes.lock().push(FollowChange::Invalidated);
+            }
             Ok(())
         };
 
@@ -2668,24 +4231,21 @@ impl Storage {
         write_transact!(self, rw_txn, f)
     }
 
-    /// Rebuild relationships
-    pub fn rebuild_relationships<'a>(
-        &'a self,
-        rw_txn: Option<&mut RwTxn<'a>>,
-    ) -> Result<(), Error> {
-        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
-            // Iterate through all events
-            let loop_txn = self.env.read_txn()?;
-            for result in self.db_events()?.iter(&loop_txn)? {
-                let (_key, val) = result?;
+    /// Rebuild relationships. Like [rebuild_event_indices](Storage::rebuild_event_indices)
+    /// this commits as it goes and resumes if interrupted.
+    pub fn rebuild_relationships(&self) -> Result<(), Error> {
+        // Iterate through all events
+        self.migrate_in_chunks(
+            self.db_events()?,
+            b"rebuild_relationships_cursor",
+            "Rebuilding relationships",
+            |_key, val, txn| {
                 let event = Event::read_from_buffer(val)?;
-                crate::process::process_relationships_of_event(&event, Some(txn))?;
-            }
-            self.set_flag_rebuild_relationships_needed(false, Some(txn))?;
-            Ok(())
-        };
-
-        write_transact!(self, rw_txn, f)
+                crate::process::process_relationships_of_event(&event, Some(txn))
+            },
+        )?;
+        self.set_flag_rebuild_relationships_needed(false, None)?;
+        Ok(())
     }
 
     pub fn write_nip46server<'a>(
@@ -2711,4 +4271,523 @@ impl Storage {
     ) -> Result<(), Error> {
         self.delete_nip46server2(pubkey, rw_txn)
     }
+
+    /// Write what a NIP-46 client (by its key) may do without asking. A permission
+    /// is a NIP-46 method, or `sign_event:<kind>` for signing one kind.
+    pub fn write_nip46_permission<'a>(
+        &'a self,
+        peer: PublicKey,
+        permission: &str,
+        approval: Approval,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.write_nip46_permission1(peer, permission, approval, rw_txn)
+    }
+
+    /// Read what a NIP-46 client may do for one permission, if it was decided
+    pub fn read_nip46_permission(
+        &self,
+        peer: PublicKey,
+        permission: &str,
+    ) -> Result<Option<Approval>, Error> {
+        self.read_nip46_permission1(peer, permission)
+    }
+
+    /// Read every decided permission of a NIP-46 client
+    pub fn read_nip46_permissions(
+        &self,
+        peer: PublicKey,
+    ) -> Result<Vec<(String, Approval)>, Error> {
+        self.read_nip46_permissions1(peer)
+    }
+
+    /// Forget one permission of a NIP-46 client (it is asked again)
+    pub fn delete_nip46_permission<'a>(
+        &'a self,
+        peer: PublicKey,
+        permission: &str,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.delete_nip46_permission1(peer, permission, rw_txn)
+    }
+
+    /// Forget every permission of a NIP-46 client
+    pub fn delete_nip46_permissions<'a>(
+        &'a self,
+        peer: PublicKey,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.delete_nip46_permissions1(peer, rw_txn)
+    }
+
+    /// Write an RSS entry (local-only, never published)
+    pub fn write_rss_entry<'a>(
+        &'a self,
+        entry: &RssEntry,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.write_rss_entry1(entry, rw_txn)
+    }
+
+    /// Do we already have this RSS entry?
+    pub fn has_rss_entry(&self, id: Id) -> Result<bool, Error> {
+        self.has_rss_entry1(id)
+    }
+
+    /// Read all RSS entries (unsorted)
+    pub fn read_all_rss_entries(&self) -> Result<Vec<RssEntry>, Error> {
+        self.read_all_rss_entries1()
+    }
+
+    /// Delete all RSS entries that came from the given feed
+    pub fn delete_rss_entries_of_feed<'a>(
+        &'a self,
+        feed_url: &str,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.delete_rss_entries_of_feed1(feed_url, rw_txn)
+    }
+
+    /// Save a live activity, unless we have a later announcement of it
+    pub fn write_live_activity<'a>(
+        &'a self,
+        activity: &LiveActivity,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.write_live_activity1(activity, rw_txn)
+    }
+
+    /// A live activity, by its host and 'd' tag
+    pub fn read_live_activity(
+        &self,
+        host: &PublicKey,
+        d: &str,
+    ) -> Result<Option<LiveActivity>, Error> {
+        self.read_live_activity1(host, d)
+    }
+
+    /// All the live activities we know of, in any status
+    pub fn read_all_live_activities(&self) -> Result<Vec<LiveActivity>, Error> {
+        self.read_all_live_activities1()
+    }
+
+    /// Save a relay-based group (NIP-29)
+    pub fn write_group<'a>(
+        &'a self,
+        group: &Group,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.write_group1(group, rw_txn)
+    }
+
+    /// A relay-based group (NIP-29), by its relay and id
+    pub fn read_group(&self, relay: &RelayUrl, id: &str) -> Result<Option<Group>, Error> {
+        self.read_group1(relay, id)
+    }
+
+    /// Modify a relay-based group (NIP-29), starting from a new one if we have none
+    pub fn modify_group<'a, M>(
+        &'a self,
+        relay: &RelayUrl,
+        id: &str,
+        modify: M,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error>
+    where
+        M: FnMut(&mut Group),
+    {
+        self.modify_group1(relay, id, modify, rw_txn)
+    }
+
+    /// All the relay-based groups (NIP-29) we know of, joined or not
+    pub fn read_all_groups(&self) -> Result<Vec<Group>, Error> {
+        self.read_all_groups1()
+    }
+
+    /// A moderated community (NIP-72), by its author and 'd' tag
+    pub fn read_community(&self, author: &PublicKey, d: &str) -> Result<Option<Community>, Error> {
+        self.read_community1(author, d)
+    }
+
+    /// Modify a moderated community (NIP-72), starting from a new one if we have none
+    pub fn modify_community<'a, M>(
+        &'a self,
+        author: &PublicKey,
+        d: &str,
+        modify: M,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error>
+    where
+        M: FnMut(&mut Community),
+    {
+        self.modify_community1(author, d, modify, rw_txn)
+    }
+
+    /// All the moderated communities (NIP-72) we know of, joined or not
+    pub fn read_all_communities(&self) -> Result<Vec<Community>, Error> {
+        self.read_all_communities1()
+    }
+
+    /// Save a job we asked data vending machines (NIP-90) to do
+    pub fn write_dvm_job<'a>(
+        &'a self,
+        job: &DvmJob,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.write_dvm_job1(job, rw_txn)
+    }
+
+    /// A job we asked data vending machines (NIP-90) to do, by its request id
+    pub fn read_dvm_job(&self, request_id: Id) -> Result<Option<DvmJob>, Error> {
+        self.read_dvm_job1(request_id)
+    }
+
+    /// Modify a job we asked data vending machines (NIP-90) to do, if we have it
+    pub fn modify_dvm_job<'a, M>(
+        &'a self,
+        request_id: Id,
+        modify: M,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error>
+    where
+        M: FnMut(&mut DvmJob),
+    {
+        self.modify_dvm_job1(request_id, modify, rw_txn)
+    }
+
+    /// Forget a job we asked data vending machines (NIP-90) to do
+    pub fn delete_dvm_job<'a>(
+        &'a self,
+        request_id: Id,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.delete_dvm_job1(request_id, rw_txn)
+    }
+
+    /// All the jobs we asked data vending machines (NIP-90) to do
+    pub fn read_all_dvm_jobs(&self) -> Result<Vec<DvmJob>, Error> {
+        self.read_all_dvm_jobs1()
+    }
+
+    /// Quarantine an event: its record is moved out of the events table into the
+    /// corrupt records, where it is kept for inspection. Nothing reads it from there,
+    /// so it is gone from every feed, thread and DM.
+    pub fn quarantine_event(&self, id: Id, reason: &str) -> Result<(), Error> {
+        let db = self.db_events()?;
+        let mut corrupt = CorruptRecords::new("events3");
+        {
+            let txn = self.env.read_txn()?;
+            match db.get(&txn, id.as_slice())? {
+                Some(val) => corrupt.push(id.as_slice(), val, reason.to_owned()),
+                None => return Ok(()),
+            }
+        }
+        self.quarantine_corrupt(db, corrupt, None)
+    }
+
+    // Move records that failed to decode out of the database they were found in
+    // (`db`), into the corrupt records table. Without a transaction, if a write
+    // transaction is open (maybe by our own caller, which a new one would deadlock
+    // against) this waits until it is done.
+    pub(crate) fn quarantine_corrupt<'a>(
+        &'a self,
+        db: RawDatabase,
+        corrupt: CorruptRecords,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        if rw_txn.is_none()
+            && !corrupt.is_empty()
+            && self.write_txns_open.load(Ordering::SeqCst) > 0
+        {
+            self.corrupt_pending.lock().push((db, corrupt));
+            return Ok(());
+        }
+        self.quarantine_corrupt1(db, corrupt, rw_txn)
+    }
+
+    // Tell the context of the follow state changes of a write transaction once it
+    // has committed. Those of one that did not commit are forgotten.
+    pub(super) fn follow_changes_ended(&self, committed: bool) {
+        let changes: Vec<FollowChange> = std::mem::take(&mut *self.follow_changes.lock());
+        if !committed {
+            return;
+        }
+        for change in changes {
+            match change {
+                FollowChange::Changed(pubkey, followed) => {
+                    self.context.follow_state_changed(&pubkey, followed)
+                }
+                FollowChange::Invalidated => self.context.follow_states_invalidated(),
+            }
+        }
+    }
+
+    // Quarantine what was put off by quarantine_corrupt. Called as write
+    // transactions end.
+    pub(super) fn quarantine_corrupt_pending(&self) {
+        if self.write_txns_open.load(Ordering::SeqCst) > 0 {
+            return;
+        }
+        let pending: Vec<(RawDatabase, CorruptRecords)> =
+            std::mem::take(&mut *self.corrupt_pending.lock());
+        for (db, corrupt) in pending {
+            if let Err(e) = self.quarantine_corrupt1(db, corrupt, None) {
+                tracing::error!("{}", e);
+            }
+        }
+    }
+
+    /// Records that could not be decoded and were moved out of their tables
+    pub fn read_all_corrupt_records(&self) -> Result<Vec<CorruptRecord>, Error> {
+        self.read_all_corrupt_records1()
+    }
+
+    /// Delete a corrupt record for good
+    pub fn purge_corrupt_record<'a>(
+        &'a self,
+        table: &str,
+        key: &[u8],
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.purge_corrupt_record1(table, key, rw_txn)
+    }
+
+    /// Delete all corrupt records for good
+    pub fn purge_all_corrupt_records<'a>(
+        &'a self,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.purge_all_corrupt_records1(rw_txn)
+    }
+
+    /// Count one use of this reaction content (by us) at the given time
+    pub fn record_reaction_usage<'a>(
+        &'a self,
+        content: &str,
+        used_at: Unixtime,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.record_reaction_usage1(content, used_at.0, rw_txn)
+    }
+
+    /// Every reaction content we have used, with how often and when we last used it
+    pub fn read_all_reaction_usage(&self) -> Result<Vec<(String, u64, Unixtime)>, Error> {
+        Ok(self
+            .read_all_reaction_usage1()?
+            .drain(..)
+            .map(|(content, count, last_used)| (content, count, Unixtime(last_used)))
+            .collect())
+    }
+
+    /// Change the interaction score of a person. `modify` gets the stored score and
+    /// the time it is as of (if any), and returns the new ones.
+    pub fn modify_interaction_score<'a, M>(
+        &'a self,
+        pubkey: PublicKey,
+        modify: M,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error>
+    where
+        M: Fn(Option<(f64, Unixtime)>) -> (f64, Unixtime),
+    {
+        self.modify_interaction_score1(
+            pubkey,
+            |old| {
+                let (score, as_of) = modify(old.map(|(score, as_of)| (score, Unixtime(as_of))));
+                (score, as_of.0)
+            },
+            rw_txn,
+        )
+    }
+
+    /// The stored interaction score of a person, and the time it is as of
+    pub fn read_interaction_score(
+        &self,
+        pubkey: PublicKey,
+    ) -> Result<Option<(f64, Unixtime)>, Error> {
+        Ok(self
+            .read_interaction_score1(pubkey)?
+            .map(|(score, as_of)| (score, Unixtime(as_of))))
+    }
+
+    /// Every stored interaction score, with the time it is as of
+    pub fn read_all_interaction_scores(&self) -> Result<Vec<(PublicKey, f64, Unixtime)>, Error> {
+        Ok(self
+            .read_all_interaction_scores1()?
+            .drain(..)
+            .map(|(pubkey, score, as_of)| (pubkey, score, Unixtime(as_of)))
+            .collect())
+    }
+
+    /// Record that a url served content with this sha256 hash
+    pub fn write_media_hash<'a>(
+        &'a self,
+        url: &str,
+        content_hash: [u8; 32],
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.write_media_hash1(url, content_hash, rw_txn)
+    }
+
+    /// The sha256 hash of the content a url served, if we fetched it
+    pub fn read_media_hash(&self, url: &str) -> Result<Option<[u8; 32]>, Error> {
+        self.read_media_hash1(url)
+    }
+
+    /// Every url known to have served content with this sha256 hash
+    pub fn read_media_hash_urls(&self, content_hash: [u8; 32]) -> Result<Vec<String>, Error> {
+        self.read_media_hash_urls1(content_hash)
+    }
+
+    /// Whether any url still refers to content with this sha256 hash
+    pub fn media_hash_in_use(&self, content_hash: [u8; 32]) -> Result<bool, Error> {
+        self.media_hash_in_use1(content_hash)
+    }
+
+    /// Forget the content hash of a url, given the url's key (the sha256 of the url,
+    /// which also names its file in the fetcher cache)
+    pub fn delete_media_hash<'a>(
+        &'a self,
+        url_key: [u8; 32],
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.delete_media_hash1(url_key, rw_txn)
+    }
+
+    /// Star an event locally (nothing is published), or change whether it is pinned.
+    /// An event that is already starred keeps its original star time.
+    pub fn star_event<'a>(
+        &'a self,
+        id: Id,
+        pinned: bool,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let starred_at = match self.read_star1(id)? {
+            Some((starred_at, _)) => starred_at,
+            None => Unixtime::now().unwrap().0,
+        };
+        self.write_star1(id, starred_at, pinned, rw_txn)
+    }
+
+    /// Remove the local star (and pin) from an event
+    pub fn unstar_event<'a>(&'a self, id: Id, rw_txn: Option<&mut RwTxn<'a>>) -> Result<(), Error> {
+        self.delete_star1(id, rw_txn)
+    }
+
+    /// When the event was starred, and whether it is pinned, if it is starred
+    pub fn read_star(&self, id: Id) -> Result<Option<(Unixtime, bool)>, Error> {
+        Ok(self
+            .read_star1(id)?
+            .map(|(starred_at, pinned)| (Unixtime(starred_at), pinned)))
+    }
+
+    /// Save the key to authenticate to this relay with (instead of our identity)
+    pub(crate) fn write_relay_auth_key<'a>(
+        &'a self,
+        url: &RelayUrl,
+        pubkey: PublicKey,
+        epk: &EncryptedPrivateKey,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.write_relay_auth_key1(url, pubkey, epk, rw_txn)
+    }
+
+    /// Go back to authenticating to this relay with our identity
+    pub(crate) fn delete_relay_auth_key<'a>(
+        &'a self,
+        url: &RelayUrl,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.delete_relay_auth_key1(url, rw_txn)
+    }
+
+    /// Every relay with its own authentication key, with that key (encrypted)
+    pub(crate) fn read_all_relay_auth_keys(
+        &self,
+    ) -> Result<Vec<(RelayUrl, PublicKey, EncryptedPrivateKey)>, Error> {
+        self.read_all_relay_auth_keys1()
+    }
+
+    /// Save a relay group, replacing any of the same name
+    pub fn write_relay_group<'a>(
+        &'a self,
+        group: &RelayGroup,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.write_relay_group1(group, rw_txn)
+    }
+
+    /// Remove a relay group (its relays are not affected)
+    pub fn delete_relay_group<'a>(
+        &'a self,
+        name: &str,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.delete_relay_group1(name, rw_txn)
+    }
+
+    /// All relay groups, by name
+    pub fn read_all_relay_groups(&self) -> Result<Vec<RelayGroup>, Error> {
+        self.read_all_relay_groups1()
+    }
+
+    /// Record a change to whether someone is muted
+    pub(crate) fn write_mute_log_entry<'a>(
+        &'a self,
+        pubkey: &PublicKey,
+        entry: &MuteLogEntry,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.write_mute_log_entry1(pubkey, entry, rw_txn)
+    }
+
+    /// Every change to whether someone is muted, oldest first
+    pub fn read_mute_log(&self, pubkey: &PublicKey) -> Result<Vec<MuteLogEntry>, Error> {
+        self.read_mute_log1(pubkey)
+    }
+
+    /// All starred events, pinned ones first, then most recently starred first
+    pub fn read_all_stars(&self) -> Result<Vec<(Id, Unixtime, bool)>, Error> {
+        let mut stars = self.read_all_stars1()?;
+        stars.sort_by(|a, b| b.2.cmp(&a.2).then(b.1.cmp(&a.1)));
+        Ok(stars
+            .drain(..)
+            .map(|(id, starred_at, pinned)| (id, Unixtime(starred_at), pinned))
+            .collect())
+    }
+
+    /// Read up to `limit` events in id order, starting just after `after` (or from
+    /// the beginning). Events that fail to decode are returned as errors in place, so
+    /// that a caller walking the whole table can report them and carry on.
+    pub fn read_events_chunk(
+        &self,
+        after: Option<Id>,
+        limit: usize,
+    ) -> Result<Vec<(Id, Result<Event, Error>)>, Error> {
+        self.event_read_barrier()?;
+        let txn = self.env.read_txn()?;
+        let iter = match &after {
+            Some(id) => {
+                let range = (Bound::Excluded(id.as_slice()), Bound::Unbounded);
+                self.db_events()?.range(&txn, &range)?
+            }
+            None => {
+                let range: (Bound<&[u8]>, Bound<&[u8]>) = (Bound::Unbounded, Bound::Unbounded);
+                self.db_events()?.range(&txn, &range)?
+            }
+        };
+        let mut output: Vec<(Id, Result<Event, Error>)> = Vec::new();
+        for result in iter.take(limit) {
+            let (key, val) = result?;
+            let id = Id(key[..32].try_into()?);
+            output.push((id, Event::read_from_buffer(val).map_err(|e| e.into())));
+        }
+        Ok(output)
+    }
+
+    /// Get the key-value store of a frontend, so it can persist its own state
+    /// (window layouts, column configurations, ...) in the gossip database.
+    pub fn app_data(&self, namespace: &str) -> Result<AppData<'_>, Error> {
+        AppData::new(self, namespace)
+    }
 }
@@ -0,0 +1,62 @@
+use crate::error::Error;
+use crate::storage::types::MuteLogEntry1;
+use crate::storage::{CorruptRecords, RawDatabase, Storage};
+use heed::RwTxn;
+use nostr_types::PublicKey;
+
+// (PublicKey, at, seq) -> MuteLogEntry
+//   key: pubkey.as_bytes() ++ at.to_be_bytes() ++ seq.to_be_bytes()
+//   val: serde_json::to_vec(entry) (sealed if encrypt_at_rest)
+//
+// seq tells apart the entries of the same second. Keys written before it was
+// added end at `at`, and sort before those of the same second that have it.
+
+fn mute_log1_prefix(pubkey: &PublicKey, at: i64) -> Vec<u8> {
+    let mut key: Vec<u8> = pubkey.to_bytes();
+    key.extend(at.to_be_bytes());
+    key
+}
+
+impl Storage {
+    pub(super) fn db_mute_log1(&self) -> Result<RawDatabase, Error> {
+        self.open_database("mute_log1")
+    }
+
+    pub(crate) fn write_mute_log_entry1<'a>(
+        &'a self,
+        pubkey: &PublicKey,
+        entry: &MuteLogEntry1,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let prefix = mute_log1_prefix(pubkey, entry.at);
+        let bytes = self.seal(serde_json::to_vec(entry)?)?;
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            let db = self.db_mute_log1()?;
+            let seq = db.prefix_iter(txn, &prefix)?.count() as u32;
+            let mut key = prefix.clone();
+            key.extend(seq.to_be_bytes());
+            db.put(txn, &key, &bytes)?;
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    /// Oldest first
+    pub(crate) fn read_mute_log1(&self, pubkey: &PublicKey) -> Result<Vec<MuteLogEntry1>, Error> {
+        let db = self.db_mute_log1()?;
+        let mut corrupt = CorruptRecords::new("mute_log1");
+        let mut output: Vec<MuteLogEntry1> = Vec::new();
+        // (Records that can't be unsealed are not corrupt, the identity may be locked)
+        for (key, val) in self.prefix_records(db, pubkey.as_bytes(), |_, _| true)? {
+            if let Some(entry) =
+                corrupt.check(&key, &val, serde_json::from_slice(&self.unseal(&val)?))
+            {
+                output.push(entry);
+            }
+        }
+        self.quarantine_corrupt(db, corrupt, None)?;
+        Ok(output)
+    }
+}
@@ -0,0 +1,132 @@
+use super::expiry::Expiry;
+use crate::error::Error;
+use crate::nip46::Approval;
+use crate::storage::{CorruptRecords, RawDatabase, Storage};
+use heed::RwTxn;
+use nostr_types::{PublicKey, Unixtime};
+use speedy::{Readable, Writable};
+
+// (PublicKey, permission) -> Approval
+//   key: pubkey.as_slice() ++ permission.as_bytes()
+//   val: approval.write_to_vec() | Approval::read_from_buffer(val)
+//
+// A permission is a NIP-46 method, or `sign_event:<kind>` for signing one kind
+//
+// Approvals until a time expire then.
+
+pub(super) const NIP46_PERMISSIONS1_EXPIRY: Expiry = Expiry::new(
+    "nip46_permissions1",
+    Storage::db_nip46_permissions1,
+    expires_at,
+);
+
+fn expires_at(_key: &[u8], val: &[u8]) -> Option<Unixtime> {
+    match Approval::read_from_buffer(val).ok()? {
+        Approval::Until(time) => Some(time),
+        _ => None,
+    }
+}
+
+impl Storage {
+    pub(super) fn db_nip46_permissions1(&self) -> Result<RawDatabase, Error> {
+        self.open_database("nip46_permissions1")
+    }
+
+    pub(crate) fn write_nip46_permission1<'a>(
+        &'a self,
+        peer: PublicKey,
+        permission: &str,
+        approval: Approval,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let mut full_key: Vec<u8> = peer.as_slice().to_owned();
+        full_key.extend(permission.as_bytes());
+        let key = key!(full_key.as_slice());
+        let bytes = approval.write_to_vec()?;
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            self.db_nip46_permissions1()?.put(txn, key, &bytes)?;
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    pub(crate) fn read_nip46_permission1(
+        &self,
+        peer: PublicKey,
+        permission: &str,
+    ) -> Result<Option<Approval>, Error> {
+        let mut full_key: Vec<u8> = peer.as_slice().to_owned();
+        full_key.extend(permission.as_bytes());
+        let key = key!(full_key.as_slice());
+        let db = self.db_nip46_permissions1()?;
+        let mut corrupt = CorruptRecords::new("nip46_permissions1");
+        let txn = self.env.read_txn()?;
+        let output = match db.get(&txn, key)? {
+            Some(val) => corrupt.check(key, val, Approval::read_from_buffer(val)),
+            None => None,
+        };
+        drop(txn);
+        self.quarantine_corrupt(db, corrupt, None)?;
+        Ok(output)
+    }
+
+    pub(crate) fn read_nip46_permissions1(
+        &self,
+        peer: PublicKey,
+    ) -> Result<Vec<(String, Approval)>, Error> {
+        let db = self.db_nip46_permissions1()?;
+        let mut corrupt = CorruptRecords::new("nip46_permissions1");
+        let txn = self.env.read_txn()?;
+        let mut output: Vec<(String, Approval)> = Vec::new();
+        for result in db.prefix_iter(&txn, peer.as_slice())? {
+            let (key, val) = result?;
+            let permission = String::from_utf8_lossy(&key[peer.as_slice().len()..]).into_owned();
+            if let Some(approval) = corrupt.check(key, val, Approval::read_from_buffer(val)) {
+                output.push((permission, approval));
+            }
+        }
+        drop(txn);
+        self.quarantine_corrupt(db, corrupt, None)?;
+        Ok(output)
+    }
+
+    pub(crate) fn delete_nip46_permission1<'a>(
+        &'a self,
+        peer: PublicKey,
+        permission: &str,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let mut full_key: Vec<u8> = peer.as_slice().to_owned();
+        full_key.extend(permission.as_bytes());
+        let key = key!(full_key.as_slice());
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            let _ = self.db_nip46_permissions1()?.delete(txn, key);
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    pub(crate) fn delete_nip46_permissions1<'a>(
+        &'a self,
+        peer: PublicKey,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            let db = self.db_nip46_permissions1()?;
+            let keys: Vec<Vec<u8>> = db
+                .prefix_iter(txn, peer.as_slice())?
+                .map(|result| result.map(|(key, _)| key.to_owned()))
+                .collect::<Result<_, _>>()?;
+            for key in keys {
+                let _ = db.delete(txn, &key);
+            }
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+}
@@ -0,0 +1,172 @@
+use super::CorruptRecords;
+use crate::error::Error;
+use std::fmt::Display;
+
+/// Where a page of records ended, to carry on from with the next page.
+///
+/// It is the key of the last record on the page, so records written or deleted
+/// between pages don't make the next page skip or repeat any others.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PageCursor(pub(crate) Vec<u8>);
+
+/// A page of records, in key order
+#[derive(Debug, Clone)]
+pub struct RecordPage<T> {
+    pub items: Vec<T>,
+
+    /// Where the next page starts, or None if this page is the last
+    pub next: Option<PageCursor>,
+}
+
+impl RecordPage<(Vec<u8>, Vec<u8>)> {
+    // The page with its values decoded. Records that fail to decode are left out,
+    // and remembered in `corrupt` to be quarantined.
+    pub(crate) fn decode<T, E, F>(self, corrupt: &mut CorruptRecords, decode: F) -> RecordPage<T>
+    where
+        F: Fn(&[u8]) -> Result<T, E>,
+        E: Display,
+    {
+        RecordPage {
+            items: self
+                .items
+                .iter()
+                .filter_map(|(key, val)| corrupt.check(key, val, decode(val)))
+                .collect(),
+            next: self.next,
+        }
+    }
+}
+
+/// Which way to go through the keys of a table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScanOrder {
+    /// Lowest key first
+    Forward,
+
+    /// Highest key first
+    Reverse,
+}
+
+// The first `limit` records from the iterator that the predicate picks
+pub(super) fn take_records<'t, I, F>(
+    iter: I,
+    predicate: F,
+    limit: usize,
+) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Error>
+where
+    I: Iterator<Item = heed::Result<(&'t [u8], &'t [u8])>>,
+    F: Fn(&[u8], &[u8]) -> bool,
+{
+    let mut output: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
+    if limit == 0 {
+        return Ok(output);
+    }
+    for result in iter {
+        let (key, val) = result?;
+        if predicate(key, val) {
+            output.push((key.to_owned(), val.to_owned()));
+            if output.len() == limit {
+                break;
+            }
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::storage::test_dir::TestDir;
+    use nostr_types::{Event, EventKind, PreEvent, PrivateKey, Unixtime};
+
+    #[test]
+    fn test_filter_records_limited() {
+        let dir = TestDir::new();
+        let storage = dir.open();
+
+        let db = storage
+            .open_database("test_filter_records_limited")
+            .unwrap();
+        let mut txn = storage.env.write_txn().unwrap();
+        for n in 0u8..10 {
+            db.put(&mut txn, &[n / 5, n], &[n]).unwrap();
+        }
+        txn.commit().unwrap();
+
+        let even = |_: &[u8], val: &[u8]| val[0] % 2 == 0;
+        let found = storage
+            .filter_records_limited(db, &[], even, 2, ScanOrder::Forward)
+            .unwrap();
+        assert_eq!(found, vec![(vec![0, 0], vec![0]), (vec![0, 2], vec![2])]);
+        let found = storage
+            .filter_records_limited(db, &[], even, 2, ScanOrder::Reverse)
+            .unwrap();
+        assert_eq!(found, vec![(vec![1, 8], vec![8]), (vec![1, 6], vec![6])]);
+
+        let found = storage
+            .filter_records_limited(db, &[0], even, 5, ScanOrder::Reverse)
+            .unwrap();
+        assert_eq!(found.len(), 3);
+        assert_eq!(found[0].1, vec![4]);
+    }
+
+    fn make_event(key: &PrivateKey, kind: EventKind, created_at: i64) -> Event {
+        let pre_event = PreEvent {
+            pubkey: key.public_key(),
+            created_at: Unixtime(created_at),
+            kind,
+            tags: vec![],
+            content: format!("{}", created_at),
+        };
+        let id = pre_event.hash().unwrap();
+        Event {
+            id,
+            pubkey: pre_event.pubkey,
+            created_at: pre_event.created_at,
+            kind: pre_event.kind,
+            sig: key.sign_id(id).unwrap(),
+            content: pre_event.content,
+            tags: pre_event.tags,
+        }
+    }
+
+    #[test]
+    fn test_latest_events_by_author() {
+        let dir = TestDir::new();
+        let storage = dir.open();
+
+        // Tables cannot be created while a write transaction is open
+        let _ = storage.db_events().unwrap();
+        let _ = storage.db_event_akci_index().unwrap();
+        let _ = storage.db_event_kci_index().unwrap();
+        let _ = storage.db_event_tag_index().unwrap();
+        let _ = storage.db_hashtags().unwrap();
+        let _ = storage.db_event_media().unwrap();
+
+        let key = PrivateKey::generate();
+        let other = PrivateKey::generate();
+        let events = vec![
+            make_event(&key, EventKind::TextNote, 100),
+            make_event(&key, EventKind::TextNote, 300),
+            make_event(&key, EventKind::TextNote, 200),
+            make_event(&key, EventKind::Repost, 400),
+            make_event(&other, EventKind::TextNote, 500),
+        ];
+        for event in events.iter() {
+            storage.write_event(event, None).unwrap();
+        }
+
+        // Newest first, only of that author and kind
+        let latest = storage
+            .latest_events_by_author(key.public_key(), EventKind::TextNote, 2)
+            .unwrap();
+        let times: Vec<i64> = latest.iter().map(|e| e.created_at.0).collect();
+        assert_eq!(times, vec![300, 200]);
+
+        let latest = storage
+            .latest_events_by_author(key.public_key(), EventKind::Repost, 5)
+            .unwrap();
+        assert_eq!(latest.len(), 1);
+        assert_eq!(latest[0].id, events[3].id);
+    }
+}
@@ -1,48 +1,17 @@
 use crate::error::Error;
 use crate::storage::types::Person1;
 use crate::storage::{RawDatabase, Storage};
-use heed::types::Bytes;
 use heed::RwTxn;
 use nostr_types::PublicKey;
-use std::sync::Mutex;
 
 // PublicKey -> Person
 //   key: pubkey.as_bytes()
 //   val: serde_json::to_vec(person) | serde_json::from_slice(bytes)
 
-static PEOPLE1_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
-static mut PEOPLE1_DB: Option<RawDatabase> = None;
-
 impl Storage {
     #[allow(dead_code)]
     pub(super) fn db_people1(&self) -> Result<RawDatabase, Error> {
-        unsafe {
-            if let Some(db) = PEOPLE1_DB {
-                Ok(db)
-            } else {
-                // Lock.  This drops when anything returns.
-                let _lock = PEOPLE1_DB_CREATE_LOCK.lock();
-
-                // In case of a race, check again
-                if let Some(db) = PEOPLE1_DB {
-                    return Ok(db);
-                }
-
-                // Create it. We know that nobody else is doing this and that
-                // it cannot happen twice.
-                let mut txn = self.env.write_txn()?;
-                let db = self
-                    .env
-                    .database_options()
-                    .types::<Bytes, Bytes>()
-                    // no .flags needed
-                    .name("people")
-                    .create(&mut txn)?;
-                txn.commit()?;
-                PEOPLE1_DB = Some(db);
-                Ok(db)
-            }
-        }
+        self.open_database("people")
     }
 
     #[allow(dead_code)]
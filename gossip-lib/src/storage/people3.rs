@@ -0,0 +1,225 @@
+use crate::error::Error;
+use crate::storage::byte_rep::{is_self_describing, ByteRep};
+use crate::storage::types::Person3;
+use crate::storage::{CorruptRecords, RawDatabase, SecondaryIndex, Storage};
+use heed::{RoTxn, RwTxn};
+use nostr_types::PublicKey;
+
+// PublicKey -> Person
+//   key: pubkey.as_bytes()
+//   val: person.to_bytes() | decode_person3(bytes)
+//
+// Records written before people3 moved to the self-describing encoding are
+// plain JSON, and are read as that until they are next written.
+
+// People by their nip05 (lowercased), valid or not
+pub(super) const PERSON_NIP05_INDEX: SecondaryIndex<Person3> =
+    SecondaryIndex::new("person_nip05_index1", |person| match person.nip05() {
+        Some(nip05) if !nip05.is_empty() => vec![nip05.to_lowercase().into_bytes()],
+        _ => vec![],
+    });
+
+// The indexes kept up to date on every write
+const PEOPLE3_INDEXES: &[&SecondaryIndex<Person3>] = &[&PERSON_NIP05_INDEX];
+
+pub(super) fn decode_person3(bytes: &[u8]) -> Result<Person3, Error> {
+    if is_self_describing(bytes) {
+        Person3::from_bytes(bytes)
+    } else {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+}
+
+impl Storage {
+    pub(super) fn db_people3(&self) -> Result<RawDatabase, Error> {
+        self.open_database("people3")
+    }
+
+    pub(crate) fn get_people3_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_people3()?.len(&txn)?)
+    }
+
+    pub(crate) fn write_person3<'a>(
+        &'a self,
+        person: &Person3,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let key: Vec<u8> = person.pubkey.to_bytes();
+        let bytes = person.to_bytes()?;
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            let old: Option<Person3> = match self.db_people3()?.get(txn, &key)? {
+                Some(bytes) => decode_person3(bytes).ok(),
+                None => None,
+            };
+            self.db_people3()?.put(txn, &key, &bytes)?;
+            self.meter_write("people3", bytes.len());
+            self.update_people3_indexes(&key, old.as_ref(), Some(person), txn)?;
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    pub(crate) fn has_person3<'a>(
+        &'a self,
+        pubkey: &PublicKey,
+        txn: Option<&RoTxn<'a>>,
+    ) -> Result<bool, Error> {
+        let f = |txn: &RoTxn<'a>| -> Result<bool, Error> {
+            let key: Vec<u8> = pubkey.to_bytes();
+            Ok(self.db_people3()?.get(txn, &key)?.is_some())
+        };
+
+        read_transact!(self, txn, f)
+    }
+
+    pub(crate) fn read_person3<'a>(
+        &'a self,
+        pubkey: &PublicKey,
+        txn: Option<&RoTxn<'a>>,
+    ) -> Result<Option<Person3>, Error> {
+        let f = |txn: &RoTxn<'a>| -> Result<Option<Person3>, Error> {
+            let key: Vec<u8> = pubkey.to_bytes();
+            Ok(match self.db_people3()?.get(txn, &key)? {
+                Some(bytes) => Some(self.metered("people3", bytes, decode_person3)?),
+                None => None,
+            })
+        };
+
+        read_transact!(self, txn, f)
+    }
+
+    pub(crate) fn filter_people3<F>(&self, f: F) -> Result<Vec<Person3>, Error>
+    where
+        F: Fn(&Person3) -> bool,
+    {
+        let txn = self.env.read_txn()?;
+        let iter = self.db_people3()?.iter(&txn)?;
+        let mut output: Vec<Person3> = Vec::new();
+        let mut corrupt = CorruptRecords::new("people3");
+        for result in iter {
+            let (key, val) = result?;
+            let person: Person3 =
+                match corrupt.check(key, val, self.metered("people3", val, decode_person3)) {
+                    Some(person) => person,
+                    None => continue,
+                };
+            if f(&person) {
+                output.push(person);
+            }
+        }
+        drop(txn);
+        self.quarantine_corrupt(self.db_people3()?, corrupt, None)?;
+        Ok(output)
+    }
+
+    pub(crate) fn modify_person3<'a, M>(
+        &'a self,
+        pubkey: PublicKey,
+        mut modify: M,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error>
+    where
+        M: FnMut(&mut Person3),
+    {
+        let key = key!(pubkey.as_bytes());
+
+        let mut f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            let bytes = self.db_people3()?.get(txn, key)?;
+            let old: Option<Person3> = match bytes {
+                Some(bytes) => Some(self.metered("people3", bytes, decode_person3)?),
+                None => None,
+            };
+            let mut person = old
+                .clone()
+                .unwrap_or_else(|| Person3::new(pubkey.to_owned()));
+            modify(&mut person);
+            let bytes = person.to_bytes()?;
+            self.db_people3()?.put(txn, key, &bytes)?;
+            self.meter_write("people3", bytes.len());
+            self.update_people3_indexes(key, old.as_ref(), Some(&person), txn)?;
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    pub(crate) fn modify_all_people3<'a, M>(
+        &'a self,
+        mut modify: M,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error>
+    where
+        M: FnMut(&mut Person3),
+    {
+        let mut f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            // The indexes can't be written while iterating
+            let mut changed: Vec<(Vec<u8>, Person3, Person3)> = Vec::new();
+            let mut iter = self.db_people3()?.iter_mut(txn)?;
+            while let Some(result) = iter.next() {
+                let (key, val) = result?;
+                let old: Person3 = decode_person3(val)?;
+                let mut person = old.clone();
+                modify(&mut person);
+                let bytes = person.to_bytes()?;
+                let unchanged = bytes == val;
+                // to deal with the unsafety of put_current
+                let key = key.to_owned();
+                unsafe {
+                    iter.put_current(&key, &bytes)?;
+                }
+                if !unchanged {
+                    changed.push((key, old, person));
+                }
+            }
+            drop(iter);
+            for (key, old, person) in changed.iter() {
+                self.update_people3_indexes(key, Some(old), Some(person), txn)?;
+            }
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    pub(super) fn open_people3_indexes(&self) -> Result<(), Error> {
+        for index in PEOPLE3_INDEXES {
+            let _ = index.db(self)?;
+        }
+        Ok(())
+    }
+
+    fn update_people3_indexes<'a>(
+        &'a self,
+        key: &[u8],
+        old: Option<&Person3>,
+        new: Option<&Person3>,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        for index in PEOPLE3_INDEXES {
+            index.update(self, key, old, new, txn)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn rebuild_people3_indexes<'a>(&'a self, txn: &mut RwTxn<'a>) -> Result<(), Error> {
+        for index in PEOPLE3_INDEXES {
+            index.rebuild(self, self.db_people3()?, decode_person3, txn)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn find_people3_by_nip05(&self, nip05: &str) -> Result<Vec<Person3>, Error> {
+        let mut output: Vec<Person3> = Vec::new();
+        let keys = PERSON_NIP05_INDEX.lookup(self, nip05.to_lowercase().as_bytes())?;
+        for key in keys {
+            let pubkey = PublicKey::from_bytes(&key, false)?;
+            if let Some(person) = self.read_person3(&pubkey, None)? {
+                output.push(person);
+            }
+        }
+        Ok(output)
+    }
+}
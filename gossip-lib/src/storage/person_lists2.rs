@@ -1,50 +1,19 @@
 use super::types::PersonList1;
 use crate::error::Error;
 use crate::misc::Private;
-use crate::storage::{RawDatabase, Storage};
-use heed::types::Bytes;
+use crate::storage::{CorruptRecords, RawDatabase, Storage};
 use heed::RwTxn;
 use nostr_types::PublicKey;
 use speedy::{Readable, Writable};
 use std::collections::{BTreeMap, HashMap};
 use std::hash::{Hash, Hasher};
-use std::sync::Mutex;
 
 // Pubkey -> HashMap<PersonList1, Private> // Private reads and writes as a bool
 //   key: pubkey.as_bytes()
 
-static PERSON_LISTS2_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
-static mut PERSON_LISTS2_DB: Option<RawDatabase> = None;
-
 impl Storage {
     pub(super) fn db_person_lists2(&self) -> Result<RawDatabase, Error> {
-        unsafe {
-            if let Some(db) = PERSON_LISTS2_DB {
-                Ok(db)
-            } else {
-                // Lock.  This drops when anything returns.
-                let _lock = PERSON_LISTS2_DB_CREATE_LOCK.lock();
-
-                // In case of a race, check again
-                if let Some(db) = PERSON_LISTS2_DB {
-                    return Ok(db);
-                }
-
-                // Create it. We know that nobody else is doing this and that
-                // it cannot happen twice.
-                let mut txn = self.env.write_txn()?;
-                let db = self
-                    .env
-                    .database_options()
-                    .types::<Bytes, Bytes>()
-                    // no .flags needed
-                    .name("person_lists_2")
-                    .create(&mut txn)?;
-                txn.commit()?;
-                PERSON_LISTS2_DB = Some(db);
-                Ok(db)
-            }
-        }
+        self.open_database("person_lists_2")
     }
 
     pub(crate) fn read_person_lists2(
@@ -52,11 +21,20 @@ impl Storage {
         pubkey: &PublicKey,
     ) -> Result<HashMap<PersonList1, Private>, Error> {
         let key: Vec<u8> = pubkey.to_bytes();
+        let db = self.db_person_lists2()?;
+        let mut corrupt = CorruptRecords::new("person_lists2");
         let txn = self.env.read_txn()?;
-        Ok(match self.db_person_lists2()?.get(&txn, &key)? {
-            None => HashMap::new(),
-            Some(bytes) => HashMap::<PersonList1, Private>::read_from_buffer(bytes)?,
-        })
+        let output = match db.get(&txn, &key)? {
+            None => None,
+            Some(val) => corrupt.check(
+                &key,
+                val,
+                HashMap::<PersonList1, Private>::read_from_buffer(val),
+            ),
+        };
+        drop(txn);
+        self.quarantine_corrupt(db, corrupt, None)?;
+        Ok(output.unwrap_or_default())
     }
 
     pub(crate) fn write_person_lists2<'a>(
@@ -77,16 +55,30 @@ impl Storage {
     }
 
     pub(crate) fn get_people_in_all_followed_lists2(&self) -> Result<Vec<PublicKey>, Error> {
+        let db = self.db_person_lists2()?;
+        let mut corrupt = CorruptRecords::new("person_lists2");
         let txn = self.env.read_txn()?;
         let mut pubkeys: Vec<PublicKey> = Vec::new();
-        for result in self.db_person_lists2()?.iter(&txn)? {
+        for result in db.iter(&txn)? {
             let (key, val) = result?;
-            let pubkey = PublicKey::from_bytes(key, true)?;
-            let map = HashMap::<PersonList1, Private>::read_from_buffer(val)?;
+            let decoded = PublicKey::from_bytes(key, true)
+                .map_err(Error::from)
+                .and_then(|pubkey| {
+                    Ok((
+                        pubkey,
+                        HashMap::<PersonList1, Private>::read_from_buffer(val)?,
+                    ))
+                });
+            let (pubkey, map) = match corrupt.check(key, val, decoded) {
+                Some(decoded) => decoded,
+                None => continue,
+            };
             if map.keys().any(|list| list.subscribe()) {
                 pubkeys.push(pubkey);
             }
         }
+        drop(txn);
+        self.quarantine_corrupt(db, corrupt, None)?;
         Ok(pubkeys)
     }
 
@@ -94,16 +86,30 @@ impl Storage {
         &self,
         list: PersonList1,
     ) -> Result<Vec<(PublicKey, Private)>, Error> {
+        let db = self.db_person_lists2()?;
+        let mut corrupt = CorruptRecords::new("person_lists2");
         let txn = self.env.read_txn()?;
         let mut output: Vec<(PublicKey, Private)> = Vec::new();
-        for result in self.db_person_lists2()?.iter(&txn)? {
+        for result in db.iter(&txn)? {
             let (key, val) = result?;
-            let pubkey = PublicKey::from_bytes(key, true)?;
-            let map = HashMap::<PersonList1, Private>::read_from_buffer(val)?;
+            let decoded = PublicKey::from_bytes(key, true)
+                .map_err(Error::from)
+                .and_then(|pubkey| {
+                    Ok((
+                        pubkey,
+                        HashMap::<PersonList1, Private>::read_from_buffer(val)?,
+                    ))
+                });
+            let (pubkey, map) = match corrupt.check(key, val, decoded) {
+                Some(decoded) => decoded,
+                None => continue,
+            };
             if let Some(actual_private) = map.get(&list) {
                 output.push((pubkey, *actual_private));
             }
         }
+        drop(txn);
+        self.quarantine_corrupt(db, corrupt, None)?;
         Ok(output)
     }
 
@@ -1,45 +1,14 @@
 use super::types::{PersonList1, PersonListMetadata2};
 use crate::error::Error;
 use crate::storage::{RawDatabase, Storage};
-use heed::types::Bytes;
 use heed::RwTxn;
 use speedy::{Readable, Writable};
-use std::sync::Mutex;
 
 // PersonList1 -> PersonListMetadata2 // bool is if private or not
 
-static PERSON_LISTS_METADATA2_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
-static mut PERSON_LISTS_METADATA2_DB: Option<RawDatabase> = None;
-
 impl Storage {
     pub(super) fn db_person_lists_metadata2(&self) -> Result<RawDatabase, Error> {
-        unsafe {
-            if let Some(db) = PERSON_LISTS_METADATA2_DB {
-                Ok(db)
-            } else {
-                // Lock.  This drops when anything returns.
-                let _lock = PERSON_LISTS_METADATA2_DB_CREATE_LOCK.lock();
-
-                // In case of a race, check again
-                if let Some(db) = PERSON_LISTS_METADATA2_DB {
-                    return Ok(db);
-                }
-
-                // Create it. We know that nobody else is doing this and that
-                // it cannot happen twice.
-                let mut txn = self.env.write_txn()?;
-                let db = self
-                    .env
-                    .database_options()
-                    .types::<Bytes, Bytes>()
-                    // no .flags needed
-                    .name("person_lists_metadata2")
-                    .create(&mut txn)?;
-                txn.commit()?;
-                PERSON_LISTS_METADATA2_DB = Some(db);
-                Ok(db)
-            }
-        }
+        self.open_database("person_lists_metadata2")
     }
 
     pub(crate) fn set_person_list_metadata2<'a>(
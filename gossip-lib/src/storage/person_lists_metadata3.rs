@@ -2,47 +2,17 @@ use super::types::{PersonList1, PersonListMetadata3};
 use crate::error::{Error, ErrorKind};
 use crate::misc::Private;
 use crate::storage::{RawDatabase, Storage};
-use heed::types::Bytes;
 use heed::RwTxn;
 use speedy::{Readable, Writable};
-use std::sync::Mutex;
 
 // PersonList1 -> PersonListMetadata3 // bool is if private or not
 
-static PERSON_LISTS_METADATA3_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
-static mut PERSON_LISTS_METADATA3_DB: Option<RawDatabase> = None;
-
 impl Storage {
     pub(super) fn db_person_lists_metadata3(&self) -> Result<RawDatabase, Error> {
-        unsafe {
-            if let Some(db) = PERSON_LISTS_METADATA3_DB {
-                Ok(db)
-            } else {
-                // Lock.  This drops when anything returns.
-                let _lock = PERSON_LISTS_METADATA3_DB_CREATE_LOCK.lock();
-
-                // In case of a race, check again
-                if let Some(db) = PERSON_LISTS_METADATA3_DB {
-                    return Ok(db);
-                }
-
-                // Create it. We know that nobody else is doing this and that
-                // it cannot happen twice.
-                let mut txn = self.env.write_txn()?;
-                let db = self
-                    .env
-                    .database_options()
-                    .types::<Bytes, Bytes>()
-                    // no .flags needed
-                    .name("person_lists_metadata3")
-                    .create(&mut txn)?;
-                txn.commit()?;
-                PERSON_LISTS_METADATA3_DB = Some(db);
-                Ok(db)
-            }
-        }
+        self.open_database("person_lists_metadata3")
     }
 
+    #[allow(dead_code)]
     pub(crate) fn get_person_list_metadata3(
         &self,
         list: PersonList1,
@@ -116,6 +86,7 @@ impl Storage {
         Ok(output)
     }
 
+    #[allow(dead_code)]
     pub(crate) fn find_person_list_by_dtag3(
         &self,
         dtag: &str,
@@ -138,6 +109,7 @@ impl Storage {
         Ok(None)
     }
 
+    #[allow(dead_code)]
     pub(crate) fn allocate_person_list3<'a>(
         &'a self,
         metadata: &PersonListMetadata3,
@@ -184,6 +156,7 @@ impl Storage {
     }
 
     /// Deallocate this PersonList1
+    #[allow(dead_code)]
     pub(crate) fn deallocate_person_list3<'a>(
         &'a self,
         list: PersonList1,
@@ -0,0 +1,182 @@
+use super::types::{PersonList1, PersonListMetadata4};
+use crate::error::{Error, ErrorKind};
+use crate::misc::Private;
+use crate::storage::{RawDatabase, Storage};
+use heed::RwTxn;
+use speedy::{Readable, Writable};
+
+// PersonList1 -> PersonListMetadata4
+
+impl Storage {
+    pub(super) fn db_person_lists_metadata4(&self) -> Result<RawDatabase, Error> {
+        self.open_database("person_lists_metadata4")
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn get_person_list_metadata4(
+        &self,
+        list: PersonList1,
+    ) -> Result<Option<PersonListMetadata4>, Error> {
+        let key: Vec<u8> = list.write_to_vec()?;
+        let txn = self.env.read_txn()?;
+        Ok(match self.db_person_lists_metadata4()?.get(&txn, &key)? {
+            None => None,
+            Some(bytes) => {
+                let mut plm = PersonListMetadata4::read_from_buffer(bytes)?;
+
+                // Force followed list to be public
+                if list == PersonList1::Followed {
+                    plm.private = Private(false);
+                }
+
+                Some(plm)
+            }
+        })
+    }
+
+    pub(crate) fn set_person_list_metadata4<'a>(
+        &'a self,
+        list: PersonList1,
+        metadata: &PersonListMetadata4,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let key: Vec<u8> = list.write_to_vec()?;
+
+        // Do not allow overwriting dtag or title of well defined lists:
+        let bytes: Vec<u8> = if list == PersonList1::Muted {
+            let mut md = metadata.to_owned();
+            md.dtag = "muted".to_owned();
+            md.title = "Muted".to_owned();
+            md.write_to_vec()?
+        } else if list == PersonList1::Followed {
+            let mut md = metadata.to_owned();
+            md.dtag = "followed".to_owned();
+            md.title = "Followed".to_owned();
+            md.private = Private(false);
+            md.write_to_vec()?
+        } else {
+            metadata.write_to_vec()?
+        };
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            self.db_person_lists_metadata4()?.put(txn, &key, &bytes)?;
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    pub(crate) fn get_all_person_list_metadata4(
+        &self,
+    ) -> Result<Vec<(PersonList1, PersonListMetadata4)>, Error> {
+        let txn = self.env.read_txn()?;
+        let mut output: Vec<(PersonList1, PersonListMetadata4)> = Vec::new();
+        for result in self.db_person_lists_metadata4()?.iter(&txn)? {
+            let (key, val) = result?;
+            let list = PersonList1::read_from_buffer(key)?;
+            let mut metadata = PersonListMetadata4::read_from_buffer(val)?;
+
+            // Force followed list to be public
+            if list == PersonList1::Followed {
+                metadata.private = Private(false);
+            }
+
+            output.push((list, metadata));
+        }
+        Ok(output)
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn find_person_list_by_dtag4(
+        &self,
+        dtag: &str,
+    ) -> Result<Option<(PersonList1, PersonListMetadata4)>, Error> {
+        let txn = self.env.read_txn()?;
+        for result in self.db_person_lists_metadata4()?.iter(&txn)? {
+            let (key, val) = result?;
+            let list = PersonList1::read_from_buffer(key)?;
+            let mut metadata = PersonListMetadata4::read_from_buffer(val)?;
+
+            // Force followed list to be public
+            if list == PersonList1::Followed {
+                metadata.private = Private(false);
+            }
+
+            if metadata.dtag == dtag {
+                return Ok(Some((list, metadata)));
+            }
+        }
+        Ok(None)
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn allocate_person_list4<'a>(
+        &'a self,
+        metadata: &PersonListMetadata4,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<PersonList1, Error> {
+        // Do not allocate for well-known names
+        if &metadata.title == "Followed"
+            || &metadata.title == "Muted"
+            || &metadata.dtag == "followed"
+            || &metadata.dtag == "muted"
+        {
+            return Err(ErrorKind::ListIsWellKnown.into());
+        }
+
+        // Check if it exists first (by dtag match)
+        if let Some((found_list, _)) = self.find_person_list_by_dtag4(&metadata.dtag)? {
+            return Err(ErrorKind::ListAlreadyExists(found_list).into());
+        }
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<PersonList1, Error> {
+            let mut slot: u8 = 0;
+
+            for i in 2..=255 {
+                let key: Vec<u8> = PersonList1::Custom(i).write_to_vec()?;
+                if self.db_person_lists_metadata4()?.get(txn, &key)?.is_none() {
+                    slot = i;
+                    break;
+                }
+            }
+
+            if slot < 2 {
+                return Err(ErrorKind::ListAllocationFailed.into());
+            }
+
+            let list = PersonList1::Custom(slot);
+            let key: Vec<u8> = list.write_to_vec()?;
+            let val: Vec<u8> = metadata.write_to_vec()?;
+            self.db_person_lists_metadata4()?.put(txn, &key, &val)?;
+
+            Ok(list)
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    /// Deallocate this PersonList1
+    #[allow(dead_code)]
+    pub(crate) fn deallocate_person_list4<'a>(
+        &'a self,
+        list: PersonList1,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        if u8::from(list) < 2 {
+            return Err(ErrorKind::ListIsWellKnown.into());
+        }
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            self.clear_person_list(list, Some(txn))?;
+
+            // note: we dont have to delete the list of people because those
+            //       lists are keyed by pubkey, and we already checked that
+            //       this list is not referenced.
+            let key: Vec<u8> = list.write_to_vec()?;
+            self.db_person_lists_metadata4()?.delete(txn, &key)?;
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+}
@@ -1,48 +1,17 @@
 use crate::error::Error;
 use crate::storage::types::PersonRelay2;
-use crate::storage::{RawDatabase, Storage, MAX_LMDB_KEY};
-use heed::types::Bytes;
+use crate::storage::{CorruptRecords, RawDatabase, Storage, MAX_LMDB_KEY};
 use heed::RwTxn;
 use nostr_types::{PublicKey, RelayUrl};
 use speedy::{Readable, Writable};
-use std::sync::Mutex;
 
 // PublicKey:Url -> PersonRelay2
 //   key: key!(pubkey.as_bytes + url.as_str().as_bytes)
 //   val: person_relay.write_to_vec) | PersonRelay::read_from_buffer(bytes)
 
-static PERSON_RELAYS2_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
-static mut PERSON_RELAYS2_DB: Option<RawDatabase> = None;
-
 impl Storage {
     pub(super) fn db_person_relays2(&self) -> Result<RawDatabase, Error> {
-        unsafe {
-            if let Some(db) = PERSON_RELAYS2_DB {
-                Ok(db)
-            } else {
-                // Lock.  This drops when anything returns.
-                let _lock = PERSON_RELAYS2_DB_CREATE_LOCK.lock();
-
-                // In case of a race, check again
-                if let Some(db) = PERSON_RELAYS2_DB {
-                    return Ok(db);
-                }
-
-                // Create it. We know that nobody else is doing this and that
-                // it cannot happen twice.
-                let mut txn = self.env.write_txn()?;
-                let db = self
-                    .env
-                    .database_options()
-                    .types::<Bytes, Bytes>()
-                    // no .flags needed
-                    .name("person_relays2")
-                    .create(&mut txn)?;
-                txn.commit()?;
-                PERSON_RELAYS2_DB = Some(db);
-                Ok(db)
-            }
-        }
+        self.open_database("person_relays2")
     }
 
     pub(crate) fn get_person_relays2_len(&self) -> Result<u64, Error> {
@@ -63,6 +32,7 @@ impl Storage {
 
         let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
             self.db_person_relays2()?.put(txn, &key, &bytes)?;
+            self.meter_write("person_relays2", bytes.len());
             Ok(())
         };
 
@@ -79,7 +49,9 @@ impl Storage {
         key.truncate(MAX_LMDB_KEY);
         let txn = self.env.read_txn()?;
         Ok(match self.db_person_relays2()?.get(&txn, &key)? {
-            Some(bytes) => Some(PersonRelay2::read_from_buffer(bytes)?),
+            Some(bytes) => Some(self.metered("person_relays2", bytes, |b| {
+                PersonRelay2::read_from_buffer(b)
+            })?),
             None => None,
         })
     }
@@ -89,26 +61,41 @@ impl Storage {
         let txn = self.env.read_txn()?;
         let iter = self.db_person_relays2()?.prefix_iter(&txn, &start_key)?;
         let mut output: Vec<PersonRelay2> = Vec::new();
+        let mut corrupt = CorruptRecords::new("person_relays2");
         for result in iter {
-            let (_key, val) = result?;
-            let person_relay = PersonRelay2::read_from_buffer(val)?;
-            output.push(person_relay);
+            let (key, val) = result?;
+            if let Some(person_relay) = corrupt.check(
+                key,
+                val,
+                self.metered("person_relays2", val, |b| PersonRelay2::read_from_buffer(b)),
+            ) {
+                output.push(person_relay);
+            }
         }
+        drop(txn);
+        self.quarantine_corrupt(self.db_person_relays2()?, corrupt, None)?;
         Ok(output)
     }
 
     pub(crate) fn have_persons_relays2(&self, pubkey: PublicKey) -> Result<bool, Error> {
         let start_key = pubkey.to_bytes();
+        let db = self.db_person_relays2()?;
+        let mut corrupt = CorruptRecords::new("person_relays2");
         let txn = self.env.read_txn()?;
-        let iter = self.db_person_relays2()?.prefix_iter(&txn, &start_key)?;
-        for result in iter {
-            let (_key, val) = result?;
-            let person_relay = PersonRelay2::read_from_buffer(val)?;
-            if person_relay.write || person_relay.read {
-                return Ok(true);
+        let mut found = false;
+        for result in db.prefix_iter(&txn, &start_key)? {
+            let (key, val) = result?;
+            if let Some(person_relay) = corrupt.check(key, val, PersonRelay2::read_from_buffer(val))
+            {
+                if person_relay.write || person_relay.read {
+                    found = true;
+                    break;
+                }
             }
         }
-        Ok(false)
+        drop(txn);
+        self.quarantine_corrupt(db, corrupt, None)?;
+        Ok(found)
     }
 
     pub(crate) fn delete_person_relays2<'a, F>(
@@ -162,12 +149,15 @@ impl Storage {
         let mut f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
             let bytes = self.db_person_relays2()?.get(txn, &key)?;
             let mut person_relay = match bytes {
-                Some(bytes) => PersonRelay2::read_from_buffer(bytes)?,
+                Some(bytes) => self.metered("person_relays2", bytes, |b| {
+                    PersonRelay2::read_from_buffer(b)
+                })?,
                 None => PersonRelay2::new(pubkey, url.to_owned()),
             };
             modify(&mut person_relay);
             let bytes = person_relay.write_to_vec()?;
             self.db_person_relays2()?.put(txn, &key, &bytes)?;
+            self.meter_write("person_relays2", bytes.len());
             Ok(())
         };
 
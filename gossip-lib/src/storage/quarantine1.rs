@@ -0,0 +1,15 @@
+use crate::error::Error;
+use crate::storage::{RawDatabase, Storage};
+
+// Id -> Reason
+//   key: id.as_slice()
+//   val: reason.as_bytes() | String::from_utf8_lossy(val)
+//
+// No longer written (quarantined events go to corrupt_records1). Cleared by
+// migration 51.
+
+impl Storage {
+    pub(super) fn db_quarantine1(&self) -> Result<RawDatabase, Error> {
+        self.open_database("quarantine1")
+    }
+}
@@ -0,0 +1,69 @@
+use crate::error::{Error, ErrorKind};
+use crate::storage::{CorruptRecords, RawDatabase, Storage};
+use heed::RwTxn;
+
+// Reaction content -> (count, last used)
+//   key: content.as_bytes()
+//   val: count.to_be_bytes() ++ last_used.to_be_bytes()   (u64, i64)
+
+impl Storage {
+    pub(super) fn db_reaction_usage1(&self) -> Result<RawDatabase, Error> {
+        self.open_database("reaction_usage1")
+    }
+
+    pub(crate) fn record_reaction_usage1<'a>(
+        &'a self,
+        content: &str,
+        used_at: i64,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let key = key!(content.as_bytes());
+        if key.is_empty() {
+            return Ok(());
+        }
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            let db = self.db_reaction_usage1()?;
+            // A corrupt record is started over
+            let (count, last_used) = match db.get(txn, key)?.map(decode) {
+                Some(Ok(usage)) => usage,
+                Some(Err(e)) => {
+                    tracing::warn!("reaction_usage1 record replaced: {}", e);
+                    (0, 0)
+                }
+                None => (0, 0),
+            };
+            let mut val: Vec<u8> = Vec::with_capacity(16);
+            val.extend((count + 1).to_be_bytes());
+            val.extend(last_used.max(used_at).to_be_bytes());
+            db.put(txn, key, &val)?;
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    pub(crate) fn read_all_reaction_usage1(&self) -> Result<Vec<(String, u64, i64)>, Error> {
+        let txn = self.env.read_txn()?;
+        let mut output: Vec<(String, u64, i64)> = Vec::new();
+        let mut corrupt = CorruptRecords::new("reaction_usage1");
+        for result in self.db_reaction_usage1()?.iter(&txn)? {
+            let (key, val) = result?;
+            if let Some((count, last_used)) = corrupt.check(key, val, decode(val)) {
+                output.push((String::from_utf8_lossy(key).into_owned(), count, last_used));
+            }
+        }
+        drop(txn);
+        self.quarantine_corrupt(self.db_reaction_usage1()?, corrupt, None)?;
+        Ok(output)
+    }
+}
+
+fn decode(bytes: &[u8]) -> Result<(u64, i64), Error> {
+    if bytes.len() < 16 {
+        return Err(ErrorKind::General("reaction usage record is too short".to_owned()).into());
+    }
+    let count = u64::from_be_bytes(bytes[..8].try_into()?);
+    let last_used = i64::from_be_bytes(bytes[8..16].try_into()?);
+    Ok((count, last_used))
+}
@@ -0,0 +1,252 @@
+use super::Storage;
+use crate::people::Person;
+use crate::relay::Relay;
+use nostr_types::{PublicKey, RelayUrl};
+use parking_lot::Mutex;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+/// How a read cache has done since startup
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReadCacheStats {
+    /// The table it caches records of
+    pub table: &'static str,
+
+    pub entries: usize,
+
+    /// How many records it keeps at most (0 if it is off)
+    pub capacity: usize,
+
+    pub hits: u64,
+
+    pub misses: u64,
+}
+
+impl ReadCacheStats {
+    /// The fraction of reads served from the cache
+    pub fn hit_rate(&self) -> f32 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            return 0.0;
+        }
+        self.hits as f32 / lookups as f32
+    }
+}
+
+// The records of one table read lately (including that there was none), least
+// recently used ones going first when it is full.
+//
+// Writes invalidate a record right away, and again once their transaction ends.
+// A read that started before that may have found the old record, so it is only
+// kept if the generation did not change meanwhile.
+pub(super) struct ReadCache<K, V> {
+    table: &'static str,
+    inner: Mutex<Lru<K, V>>,
+}
+
+struct Lru<K, V> {
+    capacity: usize,
+
+    // The record, and when it was last used
+    records: HashMap<K, (V, u64)>,
+
+    // Keys by when they were last used
+    uses: BTreeMap<u64, K>,
+
+    tick: u64,
+
+    // Bumped whenever a write transaction that wrote records ends
+    generation: u64,
+
+    hits: u64,
+    misses: u64,
+}
+
+impl<K: Clone + Eq + Hash, V: Clone> ReadCache<K, V> {
+    pub(super) fn new(table: &'static str, capacity: usize) -> ReadCache<K, V> {
+        ReadCache {
+            table,
+            inner: Mutex::new(Lru {
+                capacity,
+                records: HashMap::new(),
+                uses: BTreeMap::new(),
+                tick: 0,
+                generation: 0,
+                hits: 0,
+                misses: 0,
+            }),
+        }
+    }
+
+    pub(super) fn set_capacity(&self, capacity: usize) {
+        let mut lru = self.inner.lock();
+        lru.capacity = capacity;
+        lru.evict();
+    }
+
+    pub(super) fn generation(&self) -> u64 {
+        self.inner.lock().generation
+    }
+
+    pub(super) fn get(&self, key: &K) -> Option<V> {
+        let mut lru = self.inner.lock();
+        lru.tick += 1;
+        let tick = lru.tick;
+        let (value, last_used) = match lru.records.get_mut(key) {
+            Some((value, last_used)) => (value.clone(), std::mem::replace(last_used, tick)),
+            None => {
+                lru.misses += 1;
+                return None;
+            }
+        };
+        lru.uses.remove(&last_used);
+        lru.uses.insert(tick, key.clone());
+        lru.hits += 1;
+        Some(value)
+    }
+
+    // Keep a record read from the table, unless a write ended since `generation`
+    pub(super) fn insert(&self, generation: u64, key: K, value: V) {
+        let mut lru = self.inner.lock();
+        if lru.capacity == 0 || lru.generation != generation {
+            return;
+        }
+        lru.tick += 1;
+        let tick = lru.tick;
+        if let Some((_, last_used)) = lru.records.insert(key.clone(), (value, tick)) {
+            lru.uses.remove(&last_used);
+        }
+        lru.uses.insert(tick, key);
+        lru.evict();
+    }
+
+    pub(super) fn invalidate(&self, key: &K) {
+        let mut lru = self.inner.lock();
+        if let Some((_, last_used)) = lru.records.remove(key) {
+            lru.uses.remove(&last_used);
+        }
+    }
+
+    pub(super) fn clear(&self) {
+        let mut lru = self.inner.lock();
+        lru.records.clear();
+        lru.uses.clear();
+    }
+
+    fn bump_generation(&self) {
+        self.inner.lock().generation += 1;
+    }
+
+    pub(super) fn stats(&self) -> ReadCacheStats {
+        let lru = self.inner.lock();
+        ReadCacheStats {
+            table: self.table,
+            entries: lru.records.len(),
+            capacity: lru.capacity,
+            hits: lru.hits,
+            misses: lru.misses,
+        }
+    }
+}
+
+impl<K: Eq + Hash, V> Lru<K, V> {
+    fn evict(&mut self) {
+        while self.records.len() > self.capacity {
+            match self.uses.pop_first() {
+                Some((_, key)) => {
+                    self.records.remove(&key);
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+// A record written in the write transaction of this thread
+pub(super) enum Written {
+    Person(PublicKey),
+    AllPeople,
+    Relay(RelayUrl),
+    AllRelays,
+}
+
+thread_local! {
+    // What the write transaction this thread holds has written, to be invalidated
+    // again when it ends (a thread holds one at a time)
+    static WRITTEN: RefCell<Vec<Written>> = const { RefCell::new(Vec::new()) };
+}
+
+pub(super) type PersonCache = ReadCache<PublicKey, Option<Person>>;
+pub(super) type RelayCache = ReadCache<RelayUrl, Option<Relay>>;
+
+impl Storage {
+    // Invalidate what a write is writing, now and when its transaction ends
+    pub(super) fn cache_written(&self, written: Written) {
+        self.invalidate_cached(&written);
+        WRITTEN.with(|w| w.borrow_mut().push(written));
+    }
+
+    fn invalidate_cached(&self, written: &Written) {
+        match written {
+            Written::Person(pubkey) => self.person_cache.invalidate(pubkey),
+            Written::AllPeople => self.person_cache.clear(),
+            Written::Relay(url) => self.relay_cache.invalidate(url),
+            Written::AllRelays => self.relay_cache.clear(),
+        }
+    }
+
+    // Run when a write transaction of this thread ends, committed or not
+    pub(super) fn write_txn_ended(&self) {
+        let written = WRITTEN.with(|w| std::mem::take(&mut *w.borrow_mut()));
+        if written.is_empty() {
+            return;
+        }
+        self.person_cache.bump_generation();
+        self.relay_cache.bump_generation();
+        for w in written.iter() {
+            self.invalidate_cached(w);
+        }
+    }
+
+    // Take the capacities from the settings
+    pub(super) fn configure_read_caches(&self) {
+        self.person_cache
+            .set_capacity(self.read_setting_person_cache_capacity() as usize);
+        self.relay_cache
+            .set_capacity(self.read_setting_relay_cache_capacity() as usize);
+    }
+
+    /// How the caches in front of the person and relay tables are doing
+    pub fn read_cache_stats(&self) -> Vec<ReadCacheStats> {
+        vec![self.person_cache.stats(), self.relay_cache.stats()]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_read_cache() {
+        let cache: ReadCache<u32, Option<u32>> = ReadCache::new("test", 2);
+        let generation = cache.generation();
+        cache.insert(generation, 1, Some(10));
+        cache.insert(generation, 2, None);
+        assert_eq!(cache.get(&1), Some(Some(10)));
+
+        // 2 is the least recently used
+        cache.insert(generation, 3, Some(30));
+        assert_eq!(cache.get(&2), None);
+        assert_eq!(cache.get(&3), Some(Some(30)));
+
+        // A read from before a write ended is not kept
+        cache.bump_generation();
+        cache.insert(generation, 4, Some(40));
+        assert_eq!(cache.get(&4), None);
+
+        cache.invalidate(&1);
+        assert_eq!(cache.get(&1), None);
+        assert_eq!(cache.stats().entries, 1);
+    }
+}
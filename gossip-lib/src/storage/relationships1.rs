@@ -1,11 +1,9 @@
 use crate::error::Error;
 use crate::storage::types::Relationship1;
 use crate::storage::{RawDatabase, Storage};
-use heed::types::Bytes;
 use heed::RwTxn;
 use nostr_types::Id;
 use speedy::Writable;
-use std::sync::Mutex;
 
 // Id:Id -> Relationship1
 //   key: id.as_slice(), id.as_slice() | Id(val[32..64].try_into()?)
@@ -17,38 +15,9 @@ use std::sync::Mutex;
 //     id2 deletes id1
 //     id2 is a zap receipt on id1
 
-static RELATIONSHIPS1_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
-static mut RELATIONSHIPS1_DB: Option<RawDatabase> = None;
-
 impl Storage {
     pub(super) fn db_relationships1(&self) -> Result<RawDatabase, Error> {
-        unsafe {
-            if let Some(db) = RELATIONSHIPS1_DB {
-                Ok(db)
-            } else {
-                // Lock.  This drops when anything returns.
-                let _lock = RELATIONSHIPS1_DB_CREATE_LOCK.lock();
-
-                // In case of a race, check again
-                if let Some(db) = RELATIONSHIPS1_DB {
-                    return Ok(db);
-                }
-
-                // Create it. We know that nobody else is doing this and that
-                // it cannot happen twice.
-                let mut txn = self.env.write_txn()?;
-                let db = self
-                    .env
-                    .database_options()
-                    .types::<Bytes, Bytes>()
-                    // no .flags needed?
-                    .name("relationships")
-                    .create(&mut txn)?;
-                txn.commit()?;
-                RELATIONSHIPS1_DB = Some(db);
-                Ok(db)
-            }
-        }
+        self.open_database("relationships")
     }
 
     pub(crate) fn write_relationship1<'a>(
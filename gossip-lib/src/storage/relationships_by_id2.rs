@@ -1,11 +1,9 @@
 use crate::error::Error;
 use crate::storage::types::RelationshipById2;
-use crate::storage::{RawDatabase, Storage};
-use heed::types::Bytes;
+use crate::storage::{CorruptRecords, RawDatabase, Storage};
 use heed::RwTxn;
 use nostr_types::Id;
 use speedy::{Readable, Writable};
-use std::sync::Mutex;
 
 // Id:Id -> RelationshipById2
 //   key: id.as_slice(), id.as_slice() | Id(val[32..64].try_into()?)
@@ -17,38 +15,9 @@ use std::sync::Mutex;
 //     id2 deletes id1
 //     id2 is a zap receipt on id1
 
-static RELATIONSHIPS_BY_ID2_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
-static mut RELATIONSHIPS_BY_ID2_DB: Option<RawDatabase> = None;
-
 impl Storage {
     pub(super) fn db_relationships_by_id2(&self) -> Result<RawDatabase, Error> {
-        unsafe {
-            if let Some(db) = RELATIONSHIPS_BY_ID2_DB {
-                Ok(db)
-            } else {
-                // Lock.  This drops when anything returns.
-                let _lock = RELATIONSHIPS_BY_ID2_DB_CREATE_LOCK.lock();
-
-                // In case of a race, check again
-                if let Some(db) = RELATIONSHIPS_BY_ID2_DB {
-                    return Ok(db);
-                }
-
-                // Create it. We know that nobody else is doing this and that
-                // it cannot happen twice.
-                let mut txn = self.env.write_txn()?;
-                let db = self
-                    .env
-                    .database_options()
-                    .types::<Bytes, Bytes>()
-                    // no .flags needed?
-                    .name("relationships_by_id2")
-                    .create(&mut txn)?;
-                txn.commit()?;
-                RELATIONSHIPS_BY_ID2_DB = Some(db);
-                Ok(db)
-            }
-        }
+        self.open_database("relationships_by_id2")
     }
 
     pub(crate) fn write_relationship_by_id2<'a>(
@@ -75,17 +44,27 @@ impl Storage {
         id: Id,
     ) -> Result<Vec<(Id, RelationshipById2)>, Error> {
         let start_key = id.as_slice();
+        let db = self.db_relationships_by_id2()?;
+        let mut corrupt = CorruptRecords::new("relationships_by_id2");
         let txn = self.env.read_txn()?;
-        let iter = self
-            .db_relationships_by_id2()?
-            .prefix_iter(&txn, start_key)?;
         let mut output: Vec<(Id, RelationshipById2)> = Vec::new();
-        for result in iter {
+        for result in db.prefix_iter(&txn, start_key)? {
             let (key, val) = result?;
-            let id2 = Id(key[32..64].try_into().unwrap());
-            let relationship_by_id = RelationshipById2::read_from_buffer(val)?;
-            output.push((id2, relationship_by_id));
+            let id2 = match key.get(32..64) {
+                Some(bytes) => Id(bytes.try_into().unwrap()),
+                None => {
+                    corrupt.push(key, val, "relationship key is too short".to_owned());
+                    continue;
+                }
+            };
+            if let Some(relationship_by_id) =
+                corrupt.check(key, val, RelationshipById2::read_from_buffer(val))
+            {
+                output.push((id2, relationship_by_id));
+            }
         }
+        drop(txn);
+        self.quarantine_corrupt(db, corrupt, None)?;
         Ok(output)
     }
 }
@@ -0,0 +1,81 @@
+use crate::error::{Error, ErrorKind};
+use crate::storage::{CorruptRecords, RawDatabase, Storage, WriteClass};
+use heed::RwTxn;
+use nostr_types::{EncryptedPrivateKey, PublicKey, RelayUrl};
+
+// RelayUrl -> (auth pubkey, encrypted private key)
+//   key: key!(url.as_str().as_bytes())
+//   val: pubkey.as_bytes() ++ epk.0.as_bytes()
+
+impl Storage {
+    pub(super) fn db_relay_auth_keys1(&self) -> Result<RawDatabase, Error> {
+        self.open_database("relay_auth_keys1")
+    }
+
+    pub(crate) fn write_relay_auth_key1<'a>(
+        &'a self,
+        url: &RelayUrl,
+        pubkey: PublicKey,
+        epk: &EncryptedPrivateKey,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let key = key!(url.as_str().as_bytes());
+        if key.is_empty() {
+            return Err(ErrorKind::Empty("relay url".to_owned()).into());
+        }
+        let mut val: Vec<u8> = pubkey.as_bytes().to_vec();
+        val.extend(epk.0.as_bytes());
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            self.db_relay_auth_keys1()?.put(txn, key, &val)?;
+            Ok(())
+        };
+
+        write_transact_as!(self, rw_txn, f, WriteClass::Critical)
+    }
+
+    pub(crate) fn delete_relay_auth_key1<'a>(
+        &'a self,
+        url: &RelayUrl,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let key = key!(url.as_str().as_bytes());
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            let _ = self.db_relay_auth_keys1()?.delete(txn, key)?;
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    pub(crate) fn read_all_relay_auth_keys1(
+        &self,
+    ) -> Result<Vec<(RelayUrl, PublicKey, EncryptedPrivateKey)>, Error> {
+        let txn = self.env.read_txn()?;
+        let mut output: Vec<(RelayUrl, PublicKey, EncryptedPrivateKey)> = Vec::new();
+        let mut corrupt = CorruptRecords::new("relay_auth_keys1");
+        for result in self.db_relay_auth_keys1()?.iter(&txn)? {
+            let (key, val) = result?;
+            if let Some(record) = corrupt.check(key, val, decode_relay_auth_key1(key, val)) {
+                output.push(record);
+            }
+        }
+        drop(txn);
+        self.quarantine_corrupt(self.db_relay_auth_keys1()?, corrupt, None)?;
+        Ok(output)
+    }
+}
+
+fn decode_relay_auth_key1(
+    key: &[u8],
+    val: &[u8],
+) -> Result<(RelayUrl, PublicKey, EncryptedPrivateKey), Error> {
+    if val.len() < 32 {
+        return Err(ErrorKind::General("relay auth key record is too short".to_owned()).into());
+    }
+    let url = RelayUrl::try_from_str(&String::from_utf8_lossy(key))?;
+    let pubkey = PublicKey::from_bytes(&val[..32], true)?;
+    let epk = EncryptedPrivateKey(String::from_utf8_lossy(&val[32..]).into_owned());
+    Ok((url, pubkey, epk))
+}
@@ -0,0 +1,69 @@
+use crate::error::Error;
+use crate::relay_group::RelayGroup;
+use crate::storage::{CorruptRecords, RawDatabase, Storage};
+use heed::RwTxn;
+use nostr_types::RelayUrl;
+
+// Name -> Members (primary first)
+//   key: name.as_bytes()
+//   val: serde_json::to_vec(&member_urls) | serde_json::from_slice(val)
+
+impl Storage {
+    pub(super) fn db_relay_groups1(&self) -> Result<RawDatabase, Error> {
+        self.open_database("relay_groups1")
+    }
+
+    pub(crate) fn write_relay_group1<'a>(
+        &'a self,
+        group: &RelayGroup,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let members: Vec<&str> = group.members.iter().map(|u| u.as_str()).collect();
+        let val = serde_json::to_vec(&members)?;
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            self.db_relay_groups1()?
+                .put(txn, group.name.as_bytes(), &val)?;
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    pub(crate) fn delete_relay_group1<'a>(
+        &'a self,
+        name: &str,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            let _ = self.db_relay_groups1()?.delete(txn, name.as_bytes())?;
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    pub(crate) fn read_all_relay_groups1(&self) -> Result<Vec<RelayGroup>, Error> {
+        let db = self.db_relay_groups1()?;
+        let mut corrupt = CorruptRecords::new("relay_groups1");
+        let txn = self.env.read_txn()?;
+        let mut output: Vec<RelayGroup> = Vec::new();
+        for result in db.iter(&txn)? {
+            let (key, val) = result?;
+            let members: Vec<String> = match corrupt.check(key, val, serde_json::from_slice(val)) {
+                Some(members) => members,
+                None => continue,
+            };
+            output.push(RelayGroup {
+                name: String::from_utf8_lossy(key).into_owned(),
+                members: members
+                    .iter()
+                    .filter_map(|u| RelayUrl::try_from_str(u).ok())
+                    .collect(),
+            });
+        }
+        drop(txn);
+        self.quarantine_corrupt(db, corrupt, None)?;
+        Ok(output)
+    }
+}
@@ -1,48 +1,17 @@
 use crate::error::{Error, ErrorKind};
 use crate::storage::types::Relay1;
 use crate::storage::{RawDatabase, Storage};
-use heed::types::Bytes;
 use heed::RwTxn;
 use nostr_types::RelayUrl;
-use std::sync::Mutex;
 
 // Url -> Relay
 //   key: key!(url.0.as_bytes())
 //   val: serde_json::to_vec(relay) | serde_json::from_slice(bytes)
 
-static RELAYS1_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
-static mut RELAYS1_DB: Option<RawDatabase> = None;
-
 impl Storage {
     #[allow(dead_code)]
     pub(super) fn db_relays1(&self) -> Result<RawDatabase, Error> {
-        unsafe {
-            if let Some(db) = RELAYS1_DB {
-                Ok(db)
-            } else {
-                // Lock.  This drops when anything returns.
-                let _lock = RELAYS1_DB_CREATE_LOCK.lock();
-
-                // In case of a race, check again
-                if let Some(db) = RELAYS1_DB {
-                    return Ok(db);
-                }
-
-                // Create it. We know that nobody else is doing this and that
-                // it cannot happen twice.
-                let mut txn = self.env.write_txn()?;
-                let db = self
-                    .env
-                    .database_options()
-                    .types::<Bytes, Bytes>()
-                    // no .flags needed
-                    .name("relays")
-                    .create(&mut txn)?;
-                txn.commit()?;
-                RELAYS1_DB = Some(db);
-                Ok(db)
-            }
-        }
+        self.open_database("relays")
     }
 
     #[allow(dead_code)]
@@ -1,49 +1,19 @@
 use crate::error::{Error, ErrorKind};
 use crate::storage::types::Relay2;
 use crate::storage::{RawDatabase, Storage};
-use heed::types::Bytes;
 use heed::{RoTxn, RwTxn};
 use nostr_types::RelayUrl;
-use std::sync::Mutex;
 
 // Url -> Relay
 //   key: key!(url.0.as_bytes())
 //   val: serde_json::to_vec(relay) | serde_json::from_slice(bytes)
 
-static RELAYS2_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
-static mut RELAYS2_DB: Option<RawDatabase> = None;
-
 impl Storage {
     pub(super) fn db_relays2(&self) -> Result<RawDatabase, Error> {
-        unsafe {
-            if let Some(db) = RELAYS2_DB {
-                Ok(db)
-            } else {
-                // Lock.  This drops when anything returns.
-                let _lock = RELAYS2_DB_CREATE_LOCK.lock();
-
-                // In case of a race, check again
-                if let Some(db) = RELAYS2_DB {
-                    return Ok(db);
-                }
-
-                // Create it. We know that nobody else is doing this and that
-                // it cannot happen twice.
-                let mut txn = self.env.write_txn()?;
-                let db = self
-                    .env
-                    .database_options()
-                    .types::<Bytes, Bytes>()
-                    // no .flags needed
-                    .name("relays2")
-                    .create(&mut txn)?;
-                txn.commit()?;
-                RELAYS2_DB = Some(db);
-                Ok(db)
-            }
-        }
+        self.open_database("relays2")
     }
 
+    #[allow(dead_code)]
     pub(crate) fn get_relays2_len(&self) -> Result<u64, Error> {
         let txn = self.env.read_txn()?;
         Ok(self.db_relays2()?.len(&txn)?)
@@ -72,6 +42,7 @@ impl Storage {
         write_transact!(self, rw_txn, f)
     }
 
+    #[allow(dead_code)]
     pub(crate) fn delete_relay2<'a>(
         &'a self,
         url: &RelayUrl,
@@ -97,6 +68,7 @@ impl Storage {
         write_transact!(self, rw_txn, f)
     }
 
+    #[allow(dead_code)]
     pub(crate) fn modify_relay2<'a, M>(
         &'a self,
         url: &RelayUrl,
@@ -126,6 +98,7 @@ impl Storage {
         write_transact!(self, rw_txn, f)
     }
 
+    #[allow(dead_code)]
     pub(crate) fn modify_all_relays2<'a, M>(
         &'a self,
         mut modify: M,
@@ -153,6 +126,7 @@ impl Storage {
         write_transact!(self, rw_txn, f)
     }
 
+    #[allow(dead_code)]
     pub(crate) fn read_relay2<'a>(
         &'a self,
         url: &RelayUrl,
@@ -175,6 +149,7 @@ impl Storage {
         read_transact!(self, txn, f)
     }
 
+    #[allow(dead_code)]
     pub(crate) fn filter_relays2<F>(&self, f: F) -> Result<Vec<Relay2>, Error>
     where
         F: Fn(&Relay2) -> bool,
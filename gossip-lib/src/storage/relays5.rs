@@ -0,0 +1,184 @@
+use crate::error::{Error, ErrorKind};
+use crate::storage::types::Relay5;
+use crate::storage::{CorruptRecords, RawDatabase, Storage};
+use heed::{RoTxn, RwTxn};
+use nostr_types::RelayUrl;
+
+// Url -> Relay
+//   key: key!(url.0.as_bytes())
+//   val: serde_json::to_vec(relay) | serde_json::from_slice(bytes)
+
+impl Storage {
+    pub(super) fn db_relays5(&self) -> Result<RawDatabase, Error> {
+        self.open_database("relays5")
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn get_relays5_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_relays5()?.len(&txn)?)
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn write_relay5<'a>(
+        &'a self,
+        relay: &Relay5,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        // Note that we use serde instead of speedy because the complexity of the
+        // serde_json::Value type makes it difficult. Any other serde serialization
+        // should work though: Consider bincode.
+        let key = key!(relay.url.as_str().as_bytes());
+        if key.is_empty() {
+            return Err(ErrorKind::Empty("relay url".to_owned()).into());
+        }
+        let bytes = serde_json::to_vec(relay)?;
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            self.db_relays5()?.put(txn, key, &bytes)?;
+            self.meter_write("relays5", bytes.len());
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn delete_relay5<'a>(
+        &'a self,
+        url: &RelayUrl,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        // Note that we use serde instead of speedy because the complexity of the
+        // serde_json::Value type makes it difficult. Any other serde serialization
+        // should work though: Consider bincode.
+        let key = key!(url.as_str().as_bytes());
+        if key.is_empty() {
+            return Err(ErrorKind::Empty("relay url".to_owned()).into());
+        }
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            // Delete any PersonRelay with this url
+            self.delete_person_relays(|f| f.url == *url, Some(txn))?;
+
+            // Delete the relay
+            self.db_relays5()?.delete(txn, key)?;
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn modify_relay5<'a, M>(
+        &'a self,
+        url: &RelayUrl,
+        mut modify: M,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error>
+    where
+        M: FnMut(&mut Relay5),
+    {
+        let key = key!(url.as_str().as_bytes());
+        if key.is_empty() {
+            return Err(ErrorKind::Empty("relay url".to_owned()).into());
+        }
+
+        let mut f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            let bytes = self.db_relays5()?.get(txn, key)?;
+            let mut relay = match bytes {
+                Some(bytes) => self.metered("relays5", bytes, |b| serde_json::from_slice(b))?,
+                None => Relay5::new(url.to_owned()),
+            };
+            modify(&mut relay);
+            let bytes = serde_json::to_vec(&relay)?;
+            self.db_relays5()?.put(txn, key, &bytes)?;
+            self.meter_write("relays5", bytes.len());
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn modify_all_relays5<'a, M>(
+        &'a self,
+        mut modify: M,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error>
+    where
+        M: FnMut(&mut Relay5),
+    {
+        let mut f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            let mut iter = self.db_relays5()?.iter_mut(txn)?;
+            while let Some(result) = iter.next() {
+                let (key, val) = result?;
+                let mut dbrelay: Relay5 = serde_json::from_slice(val)?;
+                modify(&mut dbrelay);
+                let bytes = serde_json::to_vec(&dbrelay)?;
+                // to deal with the unsafety of put_current
+                let key = key.to_owned();
+                unsafe {
+                    iter.put_current(&key, &bytes)?;
+                }
+            }
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn read_relay5<'a>(
+        &'a self,
+        url: &RelayUrl,
+        txn: Option<&RoTxn<'a>>,
+    ) -> Result<Option<Relay5>, Error> {
+        let f = |txn: &RoTxn<'a>| -> Result<Option<Relay5>, Error> {
+            // Note that we use serde instead of speedy because the complexity of the
+            // serde_json::Value type makes it difficult. Any other serde serialization
+            // should work though: Consider bincode.
+            let key = key!(url.as_str().as_bytes());
+            if key.is_empty() {
+                return Err(ErrorKind::Empty("relay url".to_owned()).into());
+            }
+            match self.db_relays5()?.get(txn, key)? {
+                Some(bytes) => {
+                    Ok(Some(self.metered("relays5", bytes, |b| {
+                        serde_json::from_slice(b)
+                    })?))
+                }
+                None => Ok(None),
+            }
+        };
+
+        read_transact!(self, txn, f)
+    }
+
+    pub(crate) fn filter_relays5<F>(&self, f: F) -> Result<Vec<Relay5>, Error>
+    where
+        F: Fn(&Relay5) -> bool,
+    {
+        let txn = self.env.read_txn()?;
+        let mut output: Vec<Relay5> = Vec::new();
+        let mut corrupt = CorruptRecords::new("relays5");
+        let iter = self.db_relays5()?.iter(&txn)?;
+        for result in iter {
+            let (key, val) = result?;
+            let relay: Relay5 = match corrupt.check(
+                key,
+                val,
+                self.metered("relays5", val, |b| serde_json::from_slice(b)),
+            ) {
+                Some(relay) => relay,
+                None => continue,
+            };
+            if f(&relay) {
+                output.push(relay);
+            }
+        }
+        drop(txn);
+        self.quarantine_corrupt(self.db_relays5()?, corrupt, None)?;
+        Ok(output)
+    }
+}
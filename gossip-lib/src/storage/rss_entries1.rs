@@ -0,0 +1,76 @@
+use crate::error::Error;
+use crate::storage::types::RssEntry1;
+use crate::storage::{CorruptRecords, RawDatabase, Storage};
+use heed::RwTxn;
+use nostr_types::Id;
+use speedy::{Readable, Writable};
+
+// Id -> RssEntry1
+//   key: id.as_slice()
+//   val: rss_entry.write_to_vec() | RssEntry1::read_from_buffer(val)
+
+impl Storage {
+    pub(super) fn db_rss_entries1(&self) -> Result<RawDatabase, Error> {
+        self.open_database("rss_entries1")
+    }
+
+    pub(crate) fn write_rss_entry1<'a>(
+        &'a self,
+        entry: &RssEntry1,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let key = entry.id.as_slice();
+        let bytes = entry.write_to_vec()?;
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            self.db_rss_entries1()?.put(txn, key, &bytes)?;
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    pub(crate) fn has_rss_entry1(&self, id: Id) -> Result<bool, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_rss_entries1()?.get(&txn, id.as_slice())?.is_some())
+    }
+
+    pub(crate) fn read_all_rss_entries1(&self) -> Result<Vec<RssEntry1>, Error> {
+        let db = self.db_rss_entries1()?;
+        let mut corrupt = CorruptRecords::new("rss_entries1");
+        let txn = self.env.read_txn()?;
+        let mut output: Vec<RssEntry1> = Vec::new();
+        for result in db.iter(&txn)? {
+            let (key, val) = result?;
+            if let Some(entry) = corrupt.check(key, val, RssEntry1::read_from_buffer(val)) {
+                output.push(entry);
+            }
+        }
+        drop(txn);
+        self.quarantine_corrupt(db, corrupt, None)?;
+        Ok(output)
+    }
+
+    pub(crate) fn delete_rss_entries_of_feed1<'a>(
+        &'a self,
+        feed_url: &str,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            let mut ids: Vec<Id> = Vec::new();
+            for result in self.db_rss_entries1()?.iter(txn)? {
+                let (_key, val) = result?;
+                let entry = RssEntry1::read_from_buffer(val)?;
+                if entry.feed_url == feed_url {
+                    ids.push(entry.id);
+                }
+            }
+            for id in ids {
+                let _ = self.db_rss_entries1()?.delete(txn, id.as_slice());
+            }
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+}
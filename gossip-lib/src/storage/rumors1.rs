@@ -0,0 +1,48 @@
+use crate::error::Error;
+use crate::storage::{RawDatabase, Storage};
+use heed::{RoTxn, RwTxn};
+use nostr_types::{Id, Rumor};
+
+// Giftwrap Id -> Rumor
+//   key: id.as_slice()
+//   val: serde_json::to_vec(rumor) (sealed if encrypt_at_rest)
+
+impl Storage {
+    pub(super) fn db_rumors1(&self) -> Result<RawDatabase, Error> {
+        self.open_database("rumors1")
+    }
+
+    pub(crate) fn write_rumor1<'a>(
+        &'a self,
+        wrap_id: Id,
+        rumor: &Rumor,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let bytes = self.seal(serde_json::to_vec(rumor)?)?;
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            self.db_rumors1()?.put(txn, wrap_id.as_slice(), &bytes)?;
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    pub(crate) fn read_rumor1(&self, wrap_id: Id) -> Result<Option<Rumor>, Error> {
+        let txn = self.env.read_txn()?;
+        self.read_rumor1_in(wrap_id, &txn)
+    }
+
+    // Within a transaction we already have open, which may be a write transaction
+    pub(crate) fn read_rumor1_in(&self, wrap_id: Id, txn: &RoTxn) -> Result<Option<Rumor>, Error> {
+        match self.db_rumors1()?.get(txn, wrap_id.as_slice())? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&self.unseal(bytes)?)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub(crate) fn get_rumors1_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_rumors1()?.len(&txn)?)
+    }
+}
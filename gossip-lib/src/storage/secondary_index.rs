@@ -0,0 +1,105 @@
+use crate::error::Error;
+use crate::storage::{RawDatabase, Storage};
+use heed::{DatabaseFlags, RwTxn};
+
+// IndexKey -> PrimaryKey
+// (dup keys, so multiple records per index key)
+//   key: key!(index_key)
+//   val: primary_key
+//
+// Primary keys must fit in a duplicate value (511 bytes), which ids and pubkeys do.
+
+/// A derived table that finds the records of a primary table by something other
+/// than their key, e.g. people by nip05. Each record has any number of index keys,
+/// computed from the record.
+///
+/// The primary table keeps it up to date by calling [SecondaryIndex::update] on
+/// every write (with the record as it was before, if any), and it can be rebuilt
+/// from the primary table at any time.
+pub(crate) struct SecondaryIndex<T> {
+    /// The name of its database
+    name: &'static str,
+
+    /// The index keys of a record
+    index_keys: fn(&T) -> Vec<Vec<u8>>,
+}
+
+impl<T> SecondaryIndex<T> {
+    pub(crate) const fn new(
+        name: &'static str,
+        index_keys: fn(&T) -> Vec<Vec<u8>>,
+    ) -> SecondaryIndex<T> {
+        SecondaryIndex { name, index_keys }
+    }
+
+    /// Open (or create) its database. Storage::init opens every index, as creating
+    /// a database while another write transaction is open would deadlock.
+    pub(crate) fn db(&self, storage: &Storage) -> Result<RawDatabase, Error> {
+        storage.open_database_with_flags(self.name, DatabaseFlags::DUP_SORT)
+    }
+
+    /// Bring the index up to date with a write of the record under `primary_key`.
+    /// `old` is the record it replaced and `new` the record written (None for a
+    /// delete).
+    pub(crate) fn update<'a>(
+        &self,
+        storage: &'a Storage,
+        primary_key: &[u8],
+        old: Option<&T>,
+        new: Option<&T>,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        let old_keys = old.map(self.index_keys).unwrap_or_default();
+        let new_keys = new.map(self.index_keys).unwrap_or_default();
+        let db = self.db(storage)?;
+        for key in old_keys.iter().filter(|k| !new_keys.contains(k)) {
+            db.delete_one_duplicate(txn, key!(&key[..]), primary_key)?;
+        }
+        for key in new_keys.iter().filter(|k| !old_keys.contains(k)) {
+            if key.is_empty() {
+                continue;
+            }
+            db.put(txn, key!(&key[..]), primary_key)?;
+        }
+        Ok(())
+    }
+
+    /// The primary keys of the records with this index key
+    pub(crate) fn lookup(
+        &self,
+        storage: &Storage,
+        index_key: &[u8],
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        let txn = storage.env.read_txn()?;
+        let iter = match self.db(storage)?.get_duplicates(&txn, key!(index_key))? {
+            Some(iter) => iter,
+            None => return Ok(vec![]),
+        };
+        let mut output: Vec<Vec<u8>> = Vec::new();
+        for result in iter {
+            let (_key, val) = result?;
+            output.push(val.to_owned());
+        }
+        Ok(output)
+    }
+
+    /// Rebuild the index from every record of the primary table. Records that fail
+    /// to decode are left out.
+    pub(crate) fn rebuild<'a>(
+        &self,
+        storage: &'a Storage,
+        primary: RawDatabase,
+        decode: fn(&[u8]) -> Result<T, Error>,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        self.db(storage)?.clear(txn)?;
+        let loop_txn = storage.env.read_txn()?;
+        for result in primary.iter(&loop_txn)? {
+            let (key, val) = result?;
+            if let Ok(record) = decode(val) {
+                self.update(storage, key, None, Some(&record), txn)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,91 @@
+use crate::error::{Error, ErrorKind};
+use crate::storage::{CorruptRecords, RawDatabase, Storage};
+use heed::RwTxn;
+use nostr_types::Id;
+
+// Id -> (starred_at, pinned)
+//   key: id.as_slice()
+//   val: starred_at.to_be_bytes() ++ [pinned as u8]   (i64, u8)
+
+impl Storage {
+    pub(super) fn db_stars1(&self) -> Result<RawDatabase, Error> {
+        self.open_database("stars1")
+    }
+
+    pub(crate) fn write_star1<'a>(
+        &'a self,
+        id: Id,
+        starred_at: i64,
+        pinned: bool,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let mut val: Vec<u8> = Vec::with_capacity(9);
+        val.extend(starred_at.to_be_bytes());
+        val.push(pinned as u8);
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            self.db_stars1()?.put(txn, id.as_slice(), &val)?;
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    pub(crate) fn delete_star1<'a>(
+        &'a self,
+        id: Id,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            let _ = self.db_stars1()?.delete(txn, id.as_slice())?;
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    pub(crate) fn read_star1(&self, id: Id) -> Result<Option<(i64, bool)>, Error> {
+        let db = self.db_stars1()?;
+        let mut corrupt = CorruptRecords::new("stars1");
+        let txn = self.env.read_txn()?;
+        let output = match db.get(&txn, id.as_slice())? {
+            Some(val) => corrupt.check(id.as_slice(), val, decode(val)),
+            None => None,
+        };
+        drop(txn);
+        self.quarantine_corrupt(db, corrupt, None)?;
+        Ok(output)
+    }
+
+    pub(crate) fn read_all_stars1(&self) -> Result<Vec<(Id, i64, bool)>, Error> {
+        let txn = self.env.read_txn()?;
+        let mut output: Vec<(Id, i64, bool)> = Vec::new();
+        let mut corrupt = CorruptRecords::new("stars1");
+        for result in self.db_stars1()?.iter(&txn)? {
+            let (key, val) = result?;
+            let decoded = decode_id(key).and_then(|id| decode(val).map(|(at, p)| (id, at, p)));
+            if let Some(star) = corrupt.check(key, val, decoded) {
+                output.push(star);
+            }
+        }
+        drop(txn);
+        self.quarantine_corrupt(self.db_stars1()?, corrupt, None)?;
+        Ok(output)
+    }
+}
+
+fn decode_id(key: &[u8]) -> Result<Id, Error> {
+    if key.len() != 32 {
+        return Err(ErrorKind::General("star key is not an id".to_owned()).into());
+    }
+    Ok(Id(key.try_into()?))
+}
+
+fn decode(bytes: &[u8]) -> Result<(i64, bool), Error> {
+    if bytes.len() < 8 {
+        return Err(ErrorKind::General("star record is too short".to_owned()).into());
+    }
+    let starred_at = i64::from_be_bytes(bytes[..8].try_into()?);
+    let pinned = bytes.get(8).copied().unwrap_or(0) != 0;
+    Ok((starred_at, pinned))
+}
@@ -0,0 +1,70 @@
+use super::Storage;
+use crate::error::Error;
+use nostr_types::Unixtime;
+
+/// The size and shape of one table on disk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableStats {
+    /// The table's database name (e.g. "events3")
+    pub name: &'static str,
+
+    /// Records in the table
+    pub records: u64,
+
+    /// Bytes the table's pages take up on disk
+    pub bytes: u64,
+
+    /// Depth of the table's B-tree
+    pub depth: u32,
+
+    /// Internal (non-leaf) pages
+    pub branch_pages: u64,
+
+    /// Leaf pages
+    pub leaf_pages: u64,
+
+    /// Pages holding values too large to fit in a leaf page
+    pub overflow_pages: u64,
+
+    /// When a record was last written, if that has been counted since startup
+    /// (see [table_metrics](Storage::table_metrics))
+    pub last_write: Option<Unixtime>,
+}
+
+impl Storage {
+    /// Record counts, sizes and page counts of every open table (which includes every
+    /// current table, and old tables still holding data to migrate), largest first.
+    /// This is what a "database health" view shows.
+    pub fn stats(&self) -> Result<Vec<TableStats>, Error> {
+        let mut tables: Vec<_> = self
+            .dbs
+            .read()
+            .iter()
+            .map(|(name, db)| (*name, *db))
+            .collect();
+        tables.push(("general", self.general));
+
+        let metrics = self.table_metrics();
+        let txn = self.env.read_txn()?;
+        let mut output: Vec<TableStats> = Vec::with_capacity(tables.len());
+        for (name, db) in tables {
+            let stat = db.stat(&txn)?;
+            let pages = stat.branch_pages + stat.leaf_pages + stat.overflow_pages;
+            output.push(TableStats {
+                name,
+                records: stat.entries as u64,
+                bytes: pages as u64 * stat.page_size as u64,
+                depth: stat.depth,
+                branch_pages: stat.branch_pages as u64,
+                leaf_pages: stat.leaf_pages as u64,
+                overflow_pages: stat.overflow_pages as u64,
+                last_write: metrics
+                    .iter()
+                    .find(|(table, _)| *table == name)
+                    .and_then(|(_, m)| m.last_write),
+            });
+        }
+        output.sort_by(|a, b| b.bytes.cmp(&a.bytes).then(a.name.cmp(b.name)));
+        Ok(output)
+    }
+}
@@ -0,0 +1,111 @@
+use super::Storage;
+use dashmap::DashMap;
+use nostr_types::Unixtime;
+use std::time::{Duration, Instant};
+
+/// Counters of how one table has been used since startup (or the last reset)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TableMetrics {
+    /// Records read (and decoded)
+    pub reads: u64,
+
+    /// Records written
+    pub writes: u64,
+
+    pub bytes_read: u64,
+
+    pub bytes_written: u64,
+
+    /// Total time spent decoding the records read
+    pub decode_time: Duration,
+
+    /// When a record was last written
+    pub last_write: Option<Unixtime>,
+}
+
+impl TableMetrics {
+    /// The average time to decode one record
+    pub fn mean_decode_time(&self) -> Duration {
+        if self.reads == 0 {
+            return Duration::ZERO;
+        }
+        let nanos = self.decode_time.as_nanos() / self.reads as u128;
+        Duration::from_nanos(nanos as u64)
+    }
+}
+
+// Counters per table name
+#[derive(Debug, Default)]
+pub(crate) struct TableMeter {
+    tables: DashMap<&'static str, TableMetrics>,
+}
+
+impl Storage {
+    /// Decode a record read from `table`, counting the read, its size and how long
+    /// the decode took
+    #[inline]
+    pub(crate) fn metered<T>(
+        &self,
+        table: &'static str,
+        bytes: &[u8],
+        decode: impl FnOnce(&[u8]) -> T,
+    ) -> T {
+        let start = Instant::now();
+        let record = decode(bytes);
+        let elapsed = start.elapsed();
+        let mut metrics = self.table_meter.tables.entry(table).or_default();
+        metrics.reads += 1;
+        metrics.bytes_read += bytes.len() as u64;
+        metrics.decode_time += elapsed;
+        record
+    }
+
+    /// Count a record of `len` bytes written to `table`
+    #[inline]
+    pub(crate) fn meter_write(&self, table: &'static str, len: usize) {
+        let mut metrics = self.table_meter.tables.entry(table).or_default();
+        metrics.writes += 1;
+        metrics.bytes_written += len as u64;
+        metrics.last_write = Unixtime::now().ok();
+    }
+
+    /// Read and write counters of each table that has been used, by table name.
+    /// The records of the main tables (events, people, relays and person relays) are
+    /// counted as they are read, written and decoded, so a slowdown can be traced
+    /// to the table behind it.
+    pub fn table_metrics(&self) -> Vec<(&'static str, TableMetrics)> {
+        let mut output: Vec<(&'static str, TableMetrics)> = self
+            .table_meter
+            .tables
+            .iter()
+            .map(|entry| (*entry.key(), *entry.value()))
+            .collect();
+        output.sort_by_key(|(name, _)| *name);
+        output
+    }
+
+    /// Start counting again from zero
+    pub fn reset_table_metrics(&self) {
+        self.table_meter.tables.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_mean_decode_time() {
+        let mut metrics = TableMetrics::default();
+        assert_eq!(metrics.mean_decode_time(), Duration::ZERO);
+
+        metrics.reads = 4;
+        metrics.decode_time = Duration::from_micros(10);
+        assert_eq!(metrics.mean_decode_time(), Duration::from_nanos(2500));
+
+        // More reads than fit in a u32
+        metrics.reads = u32::MAX as u64 + 1;
+        metrics.decode_time = Duration::from_secs(u32::MAX as u64 + 1);
+        assert_eq!(metrics.mean_decode_time(), Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,43 @@
+use super::{NoIdentity, Storage};
+use std::path::{Path, PathBuf};
+
+// A scratch directory for a test's database, removed when it goes out of scope
+// (also when the test panics)
+pub(crate) struct TestDir {
+    path: PathBuf,
+}
+
+impl TestDir {
+    pub(crate) fn new() -> TestDir {
+        let path = std::env::temp_dir().join(format!("gossip-test-{}", rand::random::<u64>()));
+        std::fs::create_dir_all(&path).unwrap();
+        TestDir { path }
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // A subdirectory, created (path() itself is the default database directory)
+    pub(crate) fn subdir(&self, name: &str) -> PathBuf {
+        let path = self.path.join(name);
+        std::fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    // Storage opened in this directory
+    pub(crate) fn open(&self) -> Storage {
+        open_in(&self.path)
+    }
+}
+
+// Storage opened in the given (existing) directory
+pub(crate) fn open_in(dir: &Path) -> Storage {
+    Storage::open(dir, Box::new(NoIdentity)).unwrap()
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
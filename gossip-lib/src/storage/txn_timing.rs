@@ -0,0 +1,222 @@
+use crate::error::{Error, ErrorKind};
+use crate::storage::{Storage, WriteClass};
+use heed::RwTxn;
+use nostr_types::Unixtime;
+use parking_lot::RwLockReadGuard;
+use std::cell::Cell;
+use std::collections::{HashMap, VecDeque};
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+/// Write transactions held open longer than this are logged and kept in the report.
+/// LMDB has only one writer at a time, so every other writer waits this long.
+pub const SLOW_TXN_MILLIS: u64 = 250;
+
+// How many slow transactions are remembered
+const MAX_SLOW_TXNS: usize = 100;
+
+thread_local! {
+    // How many write transactions this thread holds open
+    static WRITING: Cell<usize> = const { Cell::new(0) };
+}
+
+// If this thread holds a write transaction, so that starting another would wait
+// on itself forever
+pub(super) fn writing_on_this_thread() -> bool {
+    WRITING.with(|w| w.get() > 0)
+}
+
+/// A write transaction that was held open too long
+#[derive(Debug, Clone)]
+pub struct SlowTxn {
+    /// Where the transaction was started
+    pub file: &'static str,
+    pub line: u32,
+
+    /// How long it was held open
+    pub held: Duration,
+
+    /// When it ended
+    pub ended: Unixtime,
+
+    /// False if it was aborted (dropped without commit)
+    pub committed: bool,
+}
+
+/// How long write transactions started at one place in the code are held
+#[derive(Debug, Clone)]
+pub struct TxnSiteStats {
+    pub file: &'static str,
+    pub line: u32,
+    pub count: u64,
+    pub total: Duration,
+    pub max: Duration,
+
+    /// How many were over [SLOW_TXN_MILLIS]
+    pub slow: u64,
+}
+
+impl TxnSiteStats {
+    pub fn mean(&self) -> Duration {
+        if self.count == 0 {
+            Duration::ZERO
+        } else {
+            Duration::from_nanos((self.total.as_nanos() / self.count as u128) as u64)
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub(super) struct TxnTimings {
+    sites: HashMap<(&'static str, u32), TxnSiteStats>,
+    slow: VecDeque<SlowTxn>,
+}
+
+/// A write transaction that measures how long it is held open. It dereferences to
+/// the underlying [RwTxn]; pass `&mut *txn` where a `&mut RwTxn` is wanted.
+pub struct TimedTxn<'a> {
+    // None once committed
+    txn: Option<RwTxn<'a>>,
+    storage: &'a Storage,
+    file: &'static str,
+    line: u32,
+    started: Instant,
+    class: WriteClass,
+
+    // Holds compaction off until this ends
+    _gate: RwLockReadGuard<'a, ()>,
+}
+
+impl<'a> TimedTxn<'a> {
+    pub(super) fn new(storage: &'a Storage, file: &'static str, line: u32) -> Result<Self, Error> {
+        // Recursive, as a transaction may start another as it ends (see finish)
+        let gate = storage.write_gate.read_recursive();
+        if storage.retired.load(Ordering::Acquire) {
+            return Err(ErrorKind::General(
+                "The database was swapped out while this write waited".to_owned(),
+            )
+            .into());
+        }
+        let txn = storage.env.write_txn()?;
+        storage.write_txns_open.fetch_add(1, Ordering::SeqCst);
+        WRITING.with(|w| w.set(w.get() + 1));
+        Ok(TimedTxn {
+            txn: Some(txn),
+            storage,
+            file,
+            line,
+            started: Instant::now(),
+            class: WriteClass::Normal,
+            _gate: gate,
+        })
+    }
+
+    /// Set how soon the commit must reach the disk (`Normal` unless set)
+    pub fn set_class(&mut self, class: WriteClass) {
+        self.class = class;
+    }
+
+    /// Commit the transaction, syncing it to disk if its class calls for that
+    pub fn commit(mut self) -> Result<(), Error> {
+        let result = match self.txn.take() {
+            Some(txn) => txn.commit(),
+            None => Ok(()),
+        };
+        self.finish(result.is_ok());
+        result?;
+        self.storage.committed(self.class)
+    }
+
+    fn finish(&self, committed: bool) {
+        let held = self.started.elapsed();
+        self.storage.write_txns_open.fetch_sub(1, Ordering::SeqCst);
+        WRITING.with(|w| w.set(w.get().saturating_sub(1)));
+        let slow = held >= Duration::from_millis(SLOW_TXN_MILLIS);
+        if slow {
+            tracing::warn!(
+                "Write transaction from {}:{} was held for {}ms",
+                self.file,
+                self.line,
+                held.as_millis()
+            );
+        }
+
+        let mut timings = self.storage.txn_timings.lock();
+
+        let stats = timings
+            .sites
+            .entry((self.file, self.line))
+            .or_insert_with(|| TxnSiteStats {
+                file: self.file,
+                line: self.line,
+                count: 0,
+                total: Duration::ZERO,
+                max: Duration::ZERO,
+                slow: 0,
+            });
+        stats.count += 1;
+        stats.total += held;
+        stats.max = stats.max.max(held);
+
+        if slow {
+            stats.slow += 1;
+            if timings.slow.len() >= MAX_SLOW_TXNS {
+                timings.slow.pop_front();
+            }
+            timings.slow.push_back(SlowTxn {
+                file: self.file,
+                line: self.line,
+                held,
+                ended: Unixtime::now().unwrap(),
+                committed,
+            });
+        }
+        drop(timings);
+
+        self.storage.write_txn_ended();
+        self.storage.follow_changes_ended(committed);
+        self.storage.quarantine_corrupt_pending();
+        self.storage.seal_deferred_pending();
+    }
+}
+
+impl<'a> Deref for TimedTxn<'a> {
+    type Target = RwTxn<'a>;
+
+    fn deref(&self) -> &RwTxn<'a> {
+        // Only empty after commit, which consumes self
+        self.txn.as_ref().unwrap()
+    }
+}
+
+impl<'a> DerefMut for TimedTxn<'a> {
+    fn deref_mut(&mut self) -> &mut RwTxn<'a> {
+        self.txn.as_mut().unwrap()
+    }
+}
+
+impl Drop for TimedTxn<'_> {
+    fn drop(&mut self) {
+        // Dropped without commit: heed aborts it
+        if let Some(txn) = self.txn.take() {
+            drop(txn);
+            self.finish(false);
+        }
+    }
+}
+
+impl Storage {
+    /// Write transaction timings by the place they were started, longest held first
+    pub fn txn_report(&self) -> Vec<TxnSiteStats> {
+        let mut output: Vec<TxnSiteStats> =
+            self.txn_timings.lock().sites.values().cloned().collect();
+        output.sort_by(|a, b| b.max.cmp(&a.max));
+        output
+    }
+
+    /// The most recent write transactions held over [SLOW_TXN_MILLIS], newest first
+    pub fn slow_txns(&self) -> Vec<SlowTxn> {
+        self.txn_timings.lock().slow.iter().rev().cloned().collect()
+    }
+}
@@ -0,0 +1,75 @@
+use nostr_types::{EventAddr, EventKind, Id, PublicKey, RelayUrl, Unixtime};
+use speedy::{Readable, Writable};
+
+/// A moderated community (NIP-72), as last defined by its owner in a kind 34550
+/// event
+#[derive(Debug, Clone, PartialEq, Eq, Readable, Writable)]
+pub struct Community1 {
+    /// Who defined it
+    pub author: PublicKey,
+
+    /// The 'd' tag of the definition
+    pub d: String,
+
+    /// The latest definition, if we have one
+    pub event_id: Option<Id>,
+
+    /// When the latest definition was made
+    pub updated_at: Unixtime,
+
+    pub name: String,
+    pub description: String,
+    pub image: String,
+    pub rules: String,
+
+    /// Who may approve posts (besides the author)
+    pub moderators: Vec<PublicKey>,
+
+    /// Where its posts and approvals are
+    pub relays: Vec<RelayUrl>,
+
+    /// We want to see it among our communities
+    pub joined: bool,
+}
+
+impl Community1 {
+    pub fn new(author: PublicKey, d: String) -> Community1 {
+        Community1 {
+            author,
+            d,
+            event_id: None,
+            updated_at: Unixtime(0),
+            name: String::new(),
+            description: String::new(),
+            image: String::new(),
+            rules: String::new(),
+            moderators: Vec::new(),
+            relays: Vec::new(),
+            joined: false,
+        }
+    }
+
+    /// The address of its definition, which posts and approvals refer to
+    pub fn addr(&self) -> EventAddr {
+        EventAddr {
+            d: self.d.clone(),
+            relays: self.relays.iter().map(|r| r.to_unchecked_url()).collect(),
+            kind: EventKind::CommunityDefinition,
+            author: self.author,
+        }
+    }
+
+    /// The name, or the 'd' tag if it has none
+    pub fn display_name(&self) -> &str {
+        if self.name.is_empty() {
+            &self.d
+        } else {
+            &self.name
+        }
+    }
+
+    /// The author and the moderators may approve posts
+    pub fn is_moderator(&self, pubkey: &PublicKey) -> bool {
+        self.author == *pubkey || self.moderators.contains(pubkey)
+    }
+}
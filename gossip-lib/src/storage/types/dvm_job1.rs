@@ -0,0 +1,97 @@
+use nostr_types::{Id, PublicKey, RelayUrl, Unixtime};
+use speedy::{Readable, Writable};
+
+/// Where a job we asked a data vending machine (NIP-90) to do stands, as last
+/// reported by a service provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Readable, Writable)]
+pub enum DvmJobStatus1 {
+    /// Asked, and nobody has answered yet
+    Requested,
+
+    /// A service provider is working on it
+    Processing,
+
+    /// A service provider wants to be paid before (or after) it gives the result
+    PaymentRequired,
+
+    /// A service provider gave up on it
+    Error,
+
+    /// A service provider gave part of the result
+    Partial,
+
+    /// A service provider gave the result
+    Success,
+}
+
+/// A job we asked data vending machines (NIP-90) to do, with what they said back
+#[derive(Debug, Clone, PartialEq, Eq, Readable, Writable)]
+pub struct DvmJob1 {
+    /// The job request event
+    pub request_id: Id,
+
+    /// The kind of the job request (5000-5999)
+    pub kind: u32,
+
+    /// What we gave it to work on
+    pub input: String,
+
+    /// When we asked
+    pub created_at: Unixtime,
+
+    /// The service provider doing the job: the one we asked, if we asked only one,
+    /// or else the first to ask to be paid or to give the result. Others are ignored.
+    pub provider: Option<PublicKey>,
+
+    /// The relays we asked service providers to answer on
+    pub relays: Vec<RelayUrl>,
+
+    pub status: DvmJobStatus1,
+
+    /// What came with the latest status (an error message, a note on progress)
+    pub status_info: String,
+
+    /// Who sent the latest status, and when
+    pub status_from: Option<PublicKey>,
+    pub status_at: Unixtime,
+
+    /// What a service provider asked to be paid, and the invoice to pay it with if
+    /// it sent one
+    pub amount_msat: Option<u64>,
+    pub bolt11: Option<String>,
+
+    /// The service provider that asked to be paid. Only it may change what to pay.
+    pub invoice_from: Option<PublicKey>,
+
+    /// We paid the invoice
+    pub paid: bool,
+
+    /// The job result event, who sent it, and its content
+    pub result_id: Option<Id>,
+    pub result_from: Option<PublicKey>,
+    pub result: String,
+}
+
+impl DvmJob1 {
+    pub fn new(request_id: Id, kind: u32, created_at: Unixtime) -> DvmJob1 {
+        DvmJob1 {
+            request_id,
+            kind,
+            input: String::new(),
+            created_at,
+            provider: None,
+            relays: Vec::new(),
+            status: DvmJobStatus1::Requested,
+            status_info: String::new(),
+            status_from: None,
+            status_at: created_at,
+            amount_msat: None,
+            bolt11: None,
+            invoice_from: None,
+            paid: false,
+            result_id: None,
+            result_from: None,
+            result: String::new(),
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use nostr_types::{PublicKey, RelayUrl, Unixtime};
+use speedy::{Readable, Writable};
+
+/// A relay-based group (NIP-29). Groups live on one relay, which keeps their
+/// metadata, admins and members, and are identified by their id on that relay.
+#[derive(Debug, Clone, PartialEq, Eq, Readable, Writable)]
+pub struct Group1 {
+    /// The relay the group lives on
+    pub relay: RelayUrl,
+
+    /// The group id on that relay
+    pub id: String,
+
+    pub name: String,
+    pub about: String,
+    pub picture: String,
+
+    /// Anybody can read it (otherwise only members can)
+    pub public: bool,
+
+    /// Anybody can join it (otherwise joining needs approval)
+    pub open: bool,
+
+    /// Admins, and their roles
+    pub admins: Vec<(PublicKey, Vec<String>)>,
+
+    pub members: Vec<PublicKey>,
+
+    /// When the relay last published the metadata, admins and members
+    /// (kinds 39000, 39001 and 39002)
+    pub metadata_at: Unixtime,
+    pub admins_at: Unixtime,
+    pub members_at: Unixtime,
+
+    /// We asked to join (and have not asked to leave)
+    pub joined: bool,
+}
+
+impl Group1 {
+    pub fn new(relay: RelayUrl, id: String) -> Group1 {
+        Group1 {
+            relay,
+            id,
+            name: String::new(),
+            about: String::new(),
+            picture: String::new(),
+            public: true,
+            open: true,
+            admins: Vec::new(),
+            members: Vec::new(),
+            metadata_at: Unixtime(0),
+            admins_at: Unixtime(0),
+            members_at: Unixtime(0),
+            joined: false,
+        }
+    }
+
+    /// The name, or the id if it has none
+    pub fn display_name(&self) -> &str {
+        if self.name.is_empty() {
+            &self.id
+        } else {
+            &self.name
+        }
+    }
+}
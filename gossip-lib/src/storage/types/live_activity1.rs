@@ -0,0 +1,59 @@
+use nostr_types::{EventAddr, EventKind, Id, PublicKey, RelayUrl, Unixtime};
+use speedy::{Readable, Writable};
+
+/// The status of a live activity (NIP-53)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Readable, Writable)]
+pub enum LiveStatus1 {
+    Planned,
+    Live,
+    Ended,
+}
+
+/// A live activity (NIP-53), such as a live stream, as last announced by its host
+/// in a kind 30311 event
+#[derive(Debug, Clone, PartialEq, Eq, Readable, Writable)]
+pub struct LiveActivity1 {
+    /// Who announced it
+    pub host: PublicKey,
+
+    /// The 'd' tag of the announcement
+    pub d: String,
+
+    /// The latest announcement
+    pub event_id: Id,
+
+    /// When the latest announcement was made
+    pub updated_at: Unixtime,
+
+    pub title: String,
+    pub summary: String,
+    pub image: String,
+
+    /// Where to watch it
+    pub streaming: String,
+
+    /// Where to watch it later, once it has ended
+    pub recording: String,
+
+    pub status: LiveStatus1,
+    pub starts: Option<Unixtime>,
+    pub ends: Option<Unixtime>,
+
+    /// People taking part, and their role (Host, Speaker, ...) if given
+    pub participants: Vec<(PublicKey, String)>,
+
+    /// Where its chat (kind 1311) is posted
+    pub relays: Vec<RelayUrl>,
+}
+
+impl LiveActivity1 {
+    /// The address of its announcement, which live chat messages refer to
+    pub fn addr(&self) -> EventAddr {
+        EventAddr {
+            d: self.d.clone(),
+            relays: self.relays.iter().map(|r| r.to_unchecked_url()).collect(),
+            kind: EventKind::LiveEvent,
+            author: self.host,
+        }
+    }
+}
@@ -1,9 +1,27 @@
+mod community1;
+pub use community1::Community1;
+
+mod dvm_job1;
+pub use dvm_job1::{DvmJob1, DvmJobStatus1};
+
+mod group1;
+pub use group1::Group1;
+
+mod live_activity1;
+pub use live_activity1::{LiveActivity1, LiveStatus1};
+
+mod mute_log_entry1;
+pub use mute_log_entry1::MuteLogEntry1;
+
 mod person1;
 pub(crate) use person1::Person1;
 
 mod person2;
 pub use person2::Person2;
 
+mod person3;
+pub use person3::Person3;
+
 mod person_list1;
 pub use person_list1::PersonList1;
 
@@ -16,6 +34,15 @@ pub use person_list_metadata2::PersonListMetadata2;
 mod person_list_metadata3;
 pub use person_list_metadata3::PersonListMetadata3;
 
+mod person_list_metadata4;
+pub use person_list_metadata4::{ListAutoPublish1, PersonListMetadata4};
+
+mod person_list_metadata5;
+pub use person_list_metadata5::{ListRefresh1, PersonListMetadata5};
+
+mod person_list_metadata6;
+pub use person_list_metadata6::{PersonListMetadata6, SmartList1};
+
 mod person_relay1;
 pub use person_relay1::PersonRelay1;
 
@@ -43,6 +70,27 @@ pub use relay1::Relay1;
 mod relay2;
 pub use relay2::Relay2;
 
+mod relay3;
+pub use relay3::Relay3;
+
+mod relay4;
+pub use relay4::Relay4;
+
+mod relay5;
+pub use relay5::Relay5;
+
+mod relay6;
+pub use relay6::Relay6;
+
+mod relay_capabilities1;
+pub use relay_capabilities1::RelayCapabilities1;
+
+mod relay_monitor_report1;
+pub use relay_monitor_report1::RelayMonitorReport1;
+
+mod rss_entry1;
+pub use rss_entry1::RssEntry1;
+
 mod settings1;
 pub(crate) use settings1::Settings1;
 
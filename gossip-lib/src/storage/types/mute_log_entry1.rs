@@ -0,0 +1,21 @@
+use nostr_types::Id;
+use serde::{Deserialize, Serialize};
+
+/// A change to whether someone is muted, with why. These are local-only and are
+/// never published, and are sealed when encrypt_at_rest is on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MuteLogEntry1 {
+    /// When it happened
+    pub at: i64,
+
+    /// If they were muted (false if they were unmuted)
+    pub muted: bool,
+
+    /// Why, in the user's words
+    #[serde(default)]
+    pub reason: Option<String>,
+
+    /// The event that prompted it
+    #[serde(default)]
+    pub event: Option<Id>,
+}
@@ -0,0 +1,197 @@
+use super::Person2;
+use crate::globals::GLOBALS;
+use crate::people::PersonList;
+use crate::storage::byte_rep::SelfDescribing;
+use nostr_types::{Metadata, PublicKey};
+use serde::{Deserialize, Serialize};
+
+/// A person record
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Person3 {
+    /// Public key
+    pub pubkey: PublicKey,
+
+    /// Petname
+    pub petname: Option<String>,
+
+    /// Metadata
+    pub metadata: Option<Metadata>,
+
+    /// When the metadata was created
+    pub metadata_created_at: Option<i64>,
+
+    /// When the metadata was last received (to determine if we need to check
+    /// for an update)
+    pub metadata_last_received: i64,
+
+    /// If nip05 checked out to be valid
+    pub nip05_valid: bool,
+
+    /// When the nip05 was last checked (to determine if we need to check again)
+    pub nip05_last_checked: Option<u64>,
+
+    /// When their relay list was created (to determine if we need to check
+    /// for an update, and if a list is newer than what we've had before)
+    pub relay_list_created_at: Option<i64>,
+
+    /// When their relay list was last sought (to determine if we need to
+    /// check for an update)
+    #[serde(rename = "relay_list_last_received")]
+    pub relay_list_last_sought: i64,
+
+    /// When we started following them (None if we don't)
+    #[serde(default)]
+    pub followed_since: Option<i64>,
+
+    /// When their mute expires (None if it doesn't, or if they are not muted)
+    #[serde(default)]
+    pub muted_until: Option<i64>,
+
+    /// When we last replied to, reacted to, reposted or messaged them
+    #[serde(default)]
+    pub last_interacted: Option<i64>,
+}
+
+impl Person3 {
+    pub fn new(pubkey: PublicKey) -> Person3 {
+        Person3 {
+            pubkey,
+            petname: None,
+            metadata: None,
+            metadata_created_at: None,
+            metadata_last_received: 0,
+            nip05_valid: false,
+            nip05_last_checked: None,
+            relay_list_created_at: None,
+            relay_list_last_sought: 0,
+            followed_since: None,
+            muted_until: None,
+            last_interacted: None,
+        }
+    }
+
+    pub fn best_name(&self) -> String {
+        if let Some(pn) = &self.petname {
+            return pn.to_owned();
+        }
+        if let Some(md) = &self.metadata {
+            if let Some(n) = &md.name {
+                if !n.is_empty() {
+                    return n.to_owned();
+                }
+            }
+            if let Some(serde_json::Value::String(s)) = md.other.get("display_name") {
+                if !s.is_empty() {
+                    return s.to_owned();
+                }
+            }
+        }
+        crate::names::pubkey_short(&self.pubkey)
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        if let Some(md) = &self.metadata {
+            md.name.as_deref()
+        } else {
+            None
+        }
+    }
+
+    pub fn about(&self) -> Option<&str> {
+        if let Some(md) = &self.metadata {
+            md.about.as_deref()
+        } else {
+            None
+        }
+    }
+
+    pub fn picture(&self) -> Option<&str> {
+        if let Some(md) = &self.metadata {
+            md.picture.as_deref()
+        } else {
+            None
+        }
+    }
+
+    pub fn display_name(&self) -> Option<&str> {
+        if let Some(md) = &self.metadata {
+            if md.other.contains_key("display_name") {
+                if let Some(serde_json::Value::String(s)) = md.other.get("display_name") {
+                    if !s.is_empty() {
+                        return Some(s);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    pub fn nip05(&self) -> Option<&str> {
+        if let Some(md) = &self.metadata {
+            md.nip05.as_deref()
+        } else {
+            None
+        }
+    }
+
+    pub fn is_in_list(&self, list: PersonList) -> bool {
+        GLOBALS
+            .storage
+            .is_person_in_list(&self.pubkey, list)
+            .unwrap_or(false)
+    }
+
+    /// If their mute has an expiry that has passed
+    pub fn mute_expired(&self, now: i64) -> bool {
+        matches!(self.muted_until, Some(until) if until <= now)
+    }
+
+    pub fn is_subscribed_to(&self) -> bool {
+        GLOBALS
+            .storage
+            .is_person_subscribed_to(&self.pubkey)
+            .unwrap_or(false)
+    }
+}
+
+// Stored self-describing, so a field can be added (with a serde default) without a
+// Person4 and a migration to it
+impl SelfDescribing for Person3 {}
+
+impl From<Person2> for Person3 {
+    fn from(person2: Person2) -> Person3 {
+        Person3 {
+            pubkey: person2.pubkey,
+            petname: person2.petname,
+            metadata: person2.metadata,
+            metadata_created_at: person2.metadata_created_at,
+            metadata_last_received: person2.metadata_last_received,
+            nip05_valid: person2.nip05_valid,
+            nip05_last_checked: person2.nip05_last_checked,
+            relay_list_created_at: person2.relay_list_created_at,
+            relay_list_last_sought: person2.relay_list_last_sought,
+            followed_since: None,
+            muted_until: None,
+            last_interacted: None,
+        }
+    }
+}
+
+impl PartialEq for Person3 {
+    fn eq(&self, other: &Self) -> bool {
+        self.pubkey.eq(&other.pubkey)
+    }
+}
+impl Eq for Person3 {}
+impl PartialOrd for Person3 {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Person3 {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.best_name()
+            .to_lowercase()
+            .cmp(&other.best_name().to_lowercase())
+    }
+}
@@ -0,0 +1,68 @@
+use crate::misc::Private;
+use nostr_types::Unixtime;
+use speedy::{Readable, Writable};
+
+/// When a person list is published after it is edited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Readable, Writable)]
+pub enum ListAutoPublish1 {
+    /// Only when the user publishes it
+    #[default]
+    Manual,
+
+    /// Shortly after each edit (edits in quick succession are published together)
+    OnEdit,
+
+    /// At most once a day, with all the edits since
+    Daily,
+}
+
+impl ListAutoPublish1 {
+    pub fn all() -> &'static [ListAutoPublish1] {
+        &[
+            ListAutoPublish1::Manual,
+            ListAutoPublish1::OnEdit,
+            ListAutoPublish1::Daily,
+        ]
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ListAutoPublish1::Manual => "Manually",
+            ListAutoPublish1::OnEdit => "On edit",
+            ListAutoPublish1::Daily => "Daily",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Readable, Writable)]
+pub struct PersonListMetadata4 {
+    pub dtag: String,
+    pub title: String,
+    pub last_edit_time: Unixtime,
+    pub event_created_at: Unixtime,
+    pub event_public_len: usize,
+    pub event_private_len: Option<usize>,
+    pub favorite: bool,
+    pub order: usize,
+    pub private: Private,
+    pub len: usize,
+    pub auto_publish: ListAutoPublish1,
+}
+
+impl Default for PersonListMetadata4 {
+    fn default() -> PersonListMetadata4 {
+        PersonListMetadata4 {
+            dtag: "".to_owned(),
+            title: "".to_owned(),
+            last_edit_time: Unixtime::now().unwrap(),
+            event_created_at: Unixtime(0),
+            event_public_len: 0,
+            event_private_len: None,
+            favorite: false,
+            order: 0,
+            private: Private(false),
+            len: 0,
+            auto_publish: ListAutoPublish1::Manual,
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use super::ListAutoPublish1;
+use crate::misc::Private;
+use nostr_types::Unixtime;
+use speedy::{Readable, Writable};
+
+/// How often the content of the people in a person list is fetched
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Readable, Writable)]
+pub enum ListRefresh1 {
+    /// Live subscriptions on their relays
+    #[default]
+    Live,
+
+    /// A one-off fetch every this many minutes
+    EveryMinutes(u16),
+
+    /// Only when the user asks (or opens the list's feed)
+    Manual,
+}
+
+impl ListRefresh1 {
+    pub fn as_string(&self) -> String {
+        match self {
+            ListRefresh1::Live => "Live".to_owned(),
+            ListRefresh1::EveryMinutes(m) => format!("Every {} minutes", m),
+            ListRefresh1::Manual => "Manually".to_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Readable, Writable)]
+pub struct PersonListMetadata5 {
+    pub dtag: String,
+    pub title: String,
+    pub last_edit_time: Unixtime,
+    pub event_created_at: Unixtime,
+    pub event_public_len: usize,
+    pub event_private_len: Option<usize>,
+    pub favorite: bool,
+    pub order: usize,
+    pub private: Private,
+    pub len: usize,
+    pub auto_publish: ListAutoPublish1,
+    pub refresh: ListRefresh1,
+}
+
+impl Default for PersonListMetadata5 {
+    fn default() -> PersonListMetadata5 {
+        PersonListMetadata5 {
+            dtag: "".to_owned(),
+            title: "".to_owned(),
+            last_edit_time: Unixtime::now().unwrap(),
+            event_created_at: Unixtime(0),
+            event_public_len: 0,
+            event_private_len: None,
+            favorite: false,
+            order: 0,
+            private: Private(false),
+            len: 0,
+            auto_publish: ListAutoPublish1::Manual,
+            refresh: ListRefresh1::Live,
+        }
+    }
+}
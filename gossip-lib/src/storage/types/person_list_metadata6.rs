@@ -0,0 +1,91 @@
+use super::{ListAutoPublish1, ListRefresh1};
+use crate::misc::Private;
+use nostr_types::Unixtime;
+use speedy::{Readable, Writable};
+
+/// What a smart list is computed from. Smart lists are kept up to date by gossip
+/// from local data and are never published.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Readable, Writable)]
+pub enum SmartList1 {
+    /// The people we interacted with most in the last 30 days
+    MostInteracted,
+
+    /// The people we followed in the last 30 days
+    RecentlyFollowed,
+
+    /// The people we follow who follow us back
+    Mutuals,
+}
+
+impl SmartList1 {
+    pub fn all() -> [SmartList1; 3] {
+        [
+            SmartList1::MostInteracted,
+            SmartList1::RecentlyFollowed,
+            SmartList1::Mutuals,
+        ]
+    }
+
+    pub fn dtag(&self) -> &'static str {
+        match self {
+            SmartList1::MostInteracted => "gossip-smart-most-interacted",
+            SmartList1::RecentlyFollowed => "gossip-smart-recently-followed",
+            SmartList1::Mutuals => "gossip-smart-mutuals",
+        }
+    }
+
+    pub fn as_string(&self) -> String {
+        match self {
+            SmartList1::MostInteracted => "People I Talk To".to_owned(),
+            SmartList1::RecentlyFollowed => "Recently Followed".to_owned(),
+            SmartList1::Mutuals => "Mutuals".to_owned(),
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            SmartList1::MostInteracted => {
+                "The people you replied to, mentioned or heard from most in the last 30 days"
+            }
+            SmartList1::RecentlyFollowed => "The people you followed in the last 30 days",
+            SmartList1::Mutuals => "The people you follow who follow you back",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Readable, Writable)]
+pub struct PersonListMetadata6 {
+    pub dtag: String,
+    pub title: String,
+    pub last_edit_time: Unixtime,
+    pub event_created_at: Unixtime,
+    pub event_public_len: usize,
+    pub event_private_len: Option<usize>,
+    pub favorite: bool,
+    pub order: usize,
+    pub private: Private,
+    pub len: usize,
+    pub auto_publish: ListAutoPublish1,
+    pub refresh: ListRefresh1,
+    pub smart: Option<SmartList1>,
+}
+
+impl Default for PersonListMetadata6 {
+    fn default() -> PersonListMetadata6 {
+        PersonListMetadata6 {
+            dtag: "".to_owned(),
+            title: "".to_owned(),
+            last_edit_time: Unixtime::now().unwrap(),
+            event_created_at: Unixtime(0),
+            event_public_len: 0,
+            event_private_len: None,
+            favorite: false,
+            order: 0,
+            private: Private(false),
+            len: 0,
+            auto_publish: ListAutoPublish1::Manual,
+            refresh: ListRefresh1::Live,
+            smart: None,
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+
+// THIS IS HISTORICAL FOR MIGRATIONS AND THE STRUCTURES SHOULD NOT BE EDITED
+
+/// What a capability test of a relay found (see
+/// [test_relay](crate::Overlord::test_relay))
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RelayCapabilities1 {
+    /// When the test ran (in unixtime seconds)
+    pub tested_at: u64,
+
+    /// How long it took to connect, in milliseconds
+    /// None: we could not connect
+    pub connect_ms: Option<u64>,
+
+    /// Why the test stopped early, if it did
+    pub error: Option<String>,
+
+    /// If the relay serves a NIP-11 information document
+    pub nip11: bool,
+
+    /// Round-trip time of a REQ until EOSE, in milliseconds
+    /// None: it was not answered
+    pub req_ms: Option<u64>,
+
+    /// If a test event (of a kind no feed shows, which expires) was accepted
+    /// None: not tried, as we could not sign it
+    pub write_accepted: Option<bool>,
+
+    /// What the relay said about the test event
+    pub write_message: Option<String>,
+
+    /// If the relay sent an AUTH challenge
+    pub auth_challenged: bool,
+
+    /// If the relay refused something until we authenticate
+    pub auth_required: bool,
+
+    /// If our authentication was accepted
+    /// None: not tried
+    pub auth_accepted: Option<bool>,
+
+    /// If the relay answers NIP-50 searches
+    /// None: the search was not answered at all
+    pub search: Option<bool>,
+}
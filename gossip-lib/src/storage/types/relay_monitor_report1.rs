@@ -0,0 +1,23 @@
+use nostr_types::PublicKey;
+use serde::{Deserialize, Serialize};
+
+// THIS IS HISTORICAL FOR MIGRATIONS AND THE STRUCTURES SHOULD NOT BE EDITED
+
+/// What a relay monitor (NIP-66) last reported about a relay
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RelayMonitorReport1 {
+    /// The monitor that reported it
+    pub monitor: PublicKey,
+
+    /// When the monitor last found the relay up (in unixtime seconds)
+    pub seen_at: u64,
+
+    /// How long opening a connection took, in milliseconds
+    pub rtt_open_ms: Option<u64>,
+
+    /// How long a read took, in milliseconds
+    pub rtt_read_ms: Option<u64>,
+
+    /// How long a write took, in milliseconds
+    pub rtt_write_ms: Option<u64>,
+}
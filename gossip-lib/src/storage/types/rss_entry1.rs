@@ -0,0 +1,16 @@
+use nostr_types::{Id, Unixtime};
+use speedy::{Readable, Writable};
+
+/// An entry from an RSS or Atom feed. These are local-only and are never published.
+#[derive(Debug, Clone, PartialEq, Eq, Readable, Writable)]
+pub struct RssEntry1 {
+    /// Deterministic id: the sha256 of the feed URL and the entry's guid (or link)
+    pub id: Id,
+
+    pub feed_url: String,
+    pub feed_title: String,
+    pub title: String,
+    pub link: String,
+    pub summary: String,
+    pub published: Unixtime,
+}
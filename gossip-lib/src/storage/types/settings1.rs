@@ -100,8 +100,8 @@ impl Storage {
         match rw_txn {
             Some(txn) => f(txn)?,
             None => {
-                let mut txn = self.env.write_txn()?;
-                f(&mut txn)?;
+                let mut txn = self.write_txn()?;
+                f(&mut *txn)?;
                 txn.commit()?;
             }
         };
@@ -49,8 +49,8 @@ impl Storage {
                 f(txn)?;
             }
             None => {
-                let mut txn = self.env.write_txn()?;
-                f(&mut txn)?;
+                let mut txn = self.write_txn()?;
+                f(&mut *txn)?;
                 txn.commit()?;
             }
         };
@@ -1,50 +1,23 @@
 use crate::error::{Error, ErrorKind};
-use crate::globals::GLOBALS;
 use crate::storage::{RawDatabase, Storage};
-use heed::types::Bytes;
-use nostr_types::Id;
-use std::sync::Mutex;
+use nostr_types::{Event, Id};
 
 // Id -> ()
 //   key: id.as_slice()
 //   val: vec![]
 
-static UNINDEXED_GIFTWRAPS1_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
-static mut UNINDEXED_GIFTWRAPS1_DB: Option<RawDatabase> = None;
-
 impl Storage {
     pub(super) fn db_unindexed_giftwraps1(&self) -> Result<RawDatabase, Error> {
-        unsafe {
-            if let Some(db) = UNINDEXED_GIFTWRAPS1_DB {
-                Ok(db)
-            } else {
-                // Lock.  This drops when anything returns.
-                let _lock = UNINDEXED_GIFTWRAPS1_DB_CREATE_LOCK.lock();
-
-                // In case of a race, check again
-                if let Some(db) = UNINDEXED_GIFTWRAPS1_DB {
-                    return Ok(db);
-                }
+        self.open_database("unindexed_giftwraps")
+    }
 
-                // Create it. We know that nobody else is doing this and that
-                // it cannot happen twice.
-                let mut txn = self.env.write_txn()?;
-                let db = self
-                    .env
-                    .database_options()
-                    .types::<Bytes, Bytes>()
-                    // no .flags needed
-                    .name("unindexed_giftwraps")
-                    .create(&mut txn)?;
-                txn.commit()?;
-                UNINDEXED_GIFTWRAPS1_DB = Some(db);
-                Ok(db)
-            }
-        }
+    pub(crate) fn get_unindexed_giftwraps1_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_unindexed_giftwraps1()?.len(&txn)?)
     }
 
-    pub(crate) fn index_unindexed_giftwraps1(&self) -> Result<(), Error> {
-        if !GLOBALS.identity.is_unlocked() {
+    pub(crate) fn index_unindexed_giftwraps1(&self) -> Result<Vec<Id>, Error> {
+        if !self.context.is_unlocked() {
             return Err(ErrorKind::NoPrivateKey.into());
         }
 
@@ -57,26 +30,55 @@ impl Storage {
             let id = Id(a);
             ids.push(id);
         }
+        drop(txn);
 
-        let mut txn = self.env.write_txn()?;
+        self.index_giftwraps1(ids)
+    }
+
+    // Index these unindexed giftwraps. They are unwrapped (and their rumors kept)
+    // before the write transaction is opened, as unwrapping may wait on a remote
+    // signer. Those that cannot be unwrapped stay unindexed.
+    pub(crate) fn index_giftwraps1(&self, ids: Vec<Id>) -> Result<Vec<Id>, Error> {
+        let mut unwrapped: Vec<(Event, Event)> = Vec::new();
         for id in ids {
-            if let Some(event) = self.read_event(id)? {
-                self.write_event_akci_index(
-                    event.pubkey,
-                    event.kind,
-                    event.created_at,
-                    event.id,
-                    Some(&mut txn),
-                )?;
-                self.write_event_kci_index(event.kind, event.created_at, event.id, Some(&mut txn))?;
-                self.write_event_tag_index(&event, Some(&mut txn))?;
-            }
-            self.db_unindexed_giftwraps1()?
-                .delete(&mut txn, id.as_slice())?;
+            let event = match self.read_event(id)? {
+                Some(event) => event,
+                None => {
+                    let mut txn = self.write_txn()?;
+                    self.db_unindexed_giftwraps1()?
+                        .delete(&mut txn, id.as_slice())?;
+                    txn.commit()?;
+                    continue;
+                }
+            };
+            let rumor = match self.giftwrap_rumor(&event) {
+                Ok(rumor) => rumor,
+                Err(e) => {
+                    tracing::debug!("Giftwrap {} stays unindexed: {}", id.as_hex_string(), e);
+                    continue;
+                }
+            };
+            self.write_rumor(id, &rumor, None)?;
+            unwrapped.push((event, rumor.into_event_with_bad_signature()));
         }
 
+        let mut txn = self.write_txn()?;
+        for (event, rumor) in unwrapped.iter() {
+            self.write_event_akci_index(
+                rumor.pubkey,
+                event.kind,
+                rumor.created_at,
+                event.id,
+                Some(&mut *txn),
+            )?;
+            self.write_event_kci_index(event.kind, rumor.created_at, event.id, Some(&mut *txn))?;
+            // finds the rumor we just kept
+            self.write_event_tag_index(event, Some(&mut *txn))?;
+            self.db_unindexed_giftwraps1()?
+                .delete(&mut txn, event.id.as_slice())?;
+        }
         txn.commit()?;
 
-        Ok(())
+        Ok(unwrapped.iter().map(|(event, _)| event.id).collect())
     }
 }
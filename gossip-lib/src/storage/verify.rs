@@ -0,0 +1,284 @@
+use super::relationships_by_addr2::relationships_by_addr2_from_value;
+use super::{CorruptRecords, RawDatabase, Storage};
+use crate::error::{Error, ErrorKind};
+use crate::event_media::EventMedia;
+use crate::misc::Private;
+use crate::nip46::{Approval, Nip46Server};
+use crate::people::{PersonList, PersonListMetadata};
+use crate::person_relay::PersonRelay;
+use crate::relationship::RelationshipById;
+use crate::relay::Relay;
+use crate::rss::RssEntry;
+use nostr_types::Event;
+use speedy::Readable;
+use std::collections::HashMap;
+use std::fmt;
+
+/// What verifying one table found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableCheck {
+    /// The table's database name (e.g. "events3")
+    pub table: &'static str,
+
+    /// Records in the table
+    pub records: u64,
+
+    /// Records that could not be decoded
+    pub corrupt: usize,
+
+    /// Entries about events that are not stored, which garbage collection would
+    /// remove (see [collect_garbage](Storage::collect_garbage))
+    pub orphans: usize,
+}
+
+/// What [verify](Storage::verify) found, table by table
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    pub tables: Vec<TableCheck>,
+
+    /// Whether the corrupt records were quarantined and the orphans deleted
+    pub repaired: bool,
+}
+
+impl VerifyReport {
+    /// Corrupt records and orphans over all tables
+    pub fn problems(&self) -> usize {
+        self.tables.iter().map(|t| t.corrupt + t.orphans).sum()
+    }
+
+    fn table(&mut self, table: &'static str) -> &mut TableCheck {
+        if let Some(i) = self.tables.iter().position(|t| t.table == table) {
+            return &mut self.tables[i];
+        }
+        self.tables.push(TableCheck {
+            table,
+            records: 0,
+            corrupt: 0,
+            orphans: 0,
+        });
+        self.tables.last_mut().unwrap()
+    }
+}
+
+impl fmt::Display for VerifyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for t in self.tables.iter() {
+            write!(f, "{}: {} records", t.table, t.records)?;
+            if t.corrupt > 0 {
+                write!(f, ", {} corrupt", t.corrupt)?;
+            }
+            if t.orphans > 0 {
+                write!(f, ", {} orphaned", t.orphans)?;
+            }
+            writeln!(f)?;
+        }
+        match (self.problems(), self.repaired) {
+            (0, _) => write!(f, "No problems found"),
+            (n, true) => write!(f, "{} problems found and repaired", n),
+            (n, false) => write!(f, "{} problems found", n),
+        }
+    }
+}
+
+// How the records of a table are decoded, for the tables that hold more than
+// plain bytes or numbers
+type Decoder = fn(&[u8], &[u8]) -> Result<(), Error>;
+
+fn decoder(table: &str) -> Option<Decoder> {
+    let decode: Decoder = match table {
+        "events3" => |key, val| {
+            let event = Event::read_from_buffer(val)?;
+            if event.id.as_slice() != key {
+                return Err(ErrorKind::General("Event stored under another id".to_owned()).into());
+            }
+            Ok(())
+        },
+        "event_media2" => |_, val| {
+            EventMedia::read_from_buffer(val)?;
+            Ok(())
+        },
+        "nip46server2" => |_, val| {
+            Nip46Server::read_from_buffer(val)?;
+            Ok(())
+        },
+        "nip46_permissions1" => |_, val| {
+            Approval::read_from_buffer(val)?;
+            Ok(())
+        },
+        "people3" => |_, val| {
+            super::people3::decode_person3(val)?;
+            Ok(())
+        },
+        "person_relays2" => |_, val| {
+            PersonRelay::read_from_buffer(val)?;
+            Ok(())
+        },
+        "relays6" => |_, val| {
+            serde_json::from_slice::<Relay>(val)?;
+            Ok(())
+        },
+        "relationships_by_id2" => |_, val| {
+            RelationshipById::read_from_buffer(val)?;
+            Ok(())
+        },
+        "relationships_by_addr2" => |_, val| {
+            relationships_by_addr2_from_value(val)?;
+            Ok(())
+        },
+        "person_lists_2" => |_, val| {
+            HashMap::<PersonList, Private>::read_from_buffer(val)?;
+            Ok(())
+        },
+        "person_lists_metadata6" => |key, val| {
+            PersonList::read_from_buffer(key)?;
+            PersonListMetadata::read_from_buffer(val)?;
+            Ok(())
+        },
+        "rss_entries1" => |_, val| {
+            RssEntry::read_from_buffer(val)?;
+            Ok(())
+        },
+        "relay_groups1" => |_, val| {
+            serde_json::from_slice::<Vec<String>>(val)?;
+            Ok(())
+        },
+        _ => return None,
+    };
+    Some(decode)
+}
+
+impl Storage {
+    /// Check the database: walk every table, decode every record of the tables that
+    /// hold encoded records, and cross-check the tables about events against the
+    /// events table, with the same exemptions as garbage collection.
+    ///
+    /// With `repair`, records that cannot be decoded are quarantined (see
+    /// [read_all_corrupt_records](Storage::read_all_corrupt_records)) and orphaned
+    /// entries are deleted. This scans everything, so it takes a while on a large
+    /// database.
+    pub fn verify(&self, repair: bool) -> Result<VerifyReport, Error> {
+        let mut report = VerifyReport {
+            tables: Vec::new(),
+            repaired: repair,
+        };
+
+        // Not only the tables used so far
+        self.open_tables()?;
+
+        let mut tables: Vec<(&'static str, RawDatabase)> = self
+            .dbs
+            .read()
+            .iter()
+            .map(|(name, db)| (*name, *db))
+            .collect();
+        tables.push(("general", self.general));
+        tables.sort_by(|a, b| a.0.cmp(b.0));
+
+        for (table, db) in tables {
+            let mut corrupt = CorruptRecords::new(table);
+            let mut records: u64 = 0;
+            {
+                let txn = self.env.read_txn()?;
+                let decode = decoder(table);
+                for result in db.iter(&txn)? {
+                    let (key, val) = result?;
+                    records += 1;
+                    if let Some(decode) = decode {
+                        let _ = corrupt.check(key, val, decode(key, val));
+                    }
+                }
+            }
+            let check = report.table(table);
+            check.records = records;
+            check.corrupt = corrupt.len();
+            if repair {
+                self.quarantine_corrupt(db, corrupt, None)?;
+            }
+        }
+
+        // Orphans are looked for after corrupt events are out of the way, so that
+        // their index entries go with them
+        let orphan_checks = self.garbage_checks(crate::pruning::missing_since())?;
+
+        for (table, db, dangling) in orphan_checks {
+            let orphans = if repair {
+                self.delete_dangling(db, dangling)?
+            } else {
+                self.find_dangling(db, &dangling)?.len()
+            };
+            report.table(table).orphans += orphans;
+        }
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::relationship::RelationshipById;
+    use crate::storage::test_dir::TestDir;
+    use nostr_types::{EventKind, Id, PreEvent, PrivateKey, Unixtime};
+
+    #[test]
+    fn test_verify() {
+        let dir = TestDir::new();
+        let storage = dir.open();
+        storage.open_tables().unwrap();
+
+        // An undecodable relay, and a viewed mark for an event we don't have
+        let relays = storage.db_relays().unwrap();
+        let mut txn = storage.get_write_txn().unwrap();
+        relays
+            .put(&mut txn, b"wss://bad.example/", b"{ not json")
+            .unwrap();
+        txn.commit().unwrap();
+        storage.mark_event_viewed(Id([5; 32]), None).unwrap();
+
+        // A deletion of an event we don't have (yet) is kept, as garbage collection
+        // keeps it
+        let key = PrivateKey::generate();
+        let pre_event = PreEvent {
+            pubkey: key.public_key(),
+            created_at: Unixtime::now().unwrap(),
+            kind: EventKind::EventDeletion,
+            tags: vec![],
+            content: "".to_owned(),
+        };
+        let id = pre_event.hash().unwrap();
+        let deletion = Event {
+            id,
+            pubkey: pre_event.pubkey,
+            created_at: pre_event.created_at,
+            kind: pre_event.kind,
+            sig: key.sign_id(id).unwrap(),
+            content: pre_event.content,
+            tags: pre_event.tags,
+        };
+        storage.write_event(&deletion, None).unwrap();
+        storage
+            .write_relationship_by_id(
+                Id([6; 32]),
+                deletion.id,
+                RelationshipById::Deletes {
+                    by: deletion.pubkey,
+                    reason: String::new(),
+                },
+                None,
+            )
+            .unwrap();
+
+        let report = storage.verify(false).unwrap();
+        assert_eq!(report.problems(), 2);
+        assert!(!report.repaired);
+
+        let report = storage.verify(true).unwrap();
+        assert_eq!(report.problems(), 2);
+        assert_eq!(storage.verify(false).unwrap().problems(), 0);
+        assert_eq!(storage.read_all_corrupt_records().unwrap().len(), 1);
+        assert_eq!(
+            storage.find_relationships_by_id(Id([6; 32])).unwrap().len(),
+            1
+        );
+    }
+}
@@ -1,5 +1,4 @@
 use crate::error::Error;
-use crate::globals::GLOBALS;
 use crate::storage::Storage;
 use heed::RwTxn;
 use nostr_types::{EventKind, EventV2, EventV3};
@@ -11,7 +10,7 @@ impl Storage {
         txn: &mut RwTxn<'a>,
     ) -> Result<Option<EventV2>, Error> {
         if event.kind == EventKind::GiftWrap {
-            match GLOBALS.identity.unwrap_giftwrap2(event) {
+            match self.context.unwrap_giftwrap2(event) {
                 Ok(rumor) => {
                     let mut rumor_event = rumor.into_event_with_bad_signature();
                     rumor_event.id = event.id; // lie, so it indexes it under the giftwrap
@@ -37,7 +36,17 @@ impl Storage {
         txn: &mut RwTxn<'a>,
     ) -> Result<Option<EventV3>, Error> {
         if event.kind == EventKind::GiftWrap {
-            match GLOBALS.identity.unwrap_giftwrap(event) {
+            // Unwrapped before, and kept
+            let kept = self.read_rumor1_in(event.id, txn).ok().flatten();
+            let unwrapped = match kept {
+                Some(rumor) => Ok(rumor),
+                // Never wait on a remote signer while holding the write transaction
+                None if !self.context.unwraps_locally() => {
+                    Err(crate::error::ErrorKind::NoPrivateKey.into())
+                }
+                None => self.context.unwrap_giftwrap(event),
+            };
+            match unwrapped {
                 Ok(rumor) => {
                     let mut rumor_event = rumor.into_event_with_bad_signature();
                     rumor_event.id = event.id; // lie, so it indexes it under the giftwrap
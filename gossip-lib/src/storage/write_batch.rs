@@ -0,0 +1,215 @@
+use super::txn_timing::writing_on_this_thread;
+use super::{Storage, WriteClass};
+use crate::error::Error;
+use heed::RwTxn;
+use nostr_types::{Event, Id};
+use std::time::{Duration, Instant};
+
+// How many events are queued before they are written
+const BATCH_MAX_EVENTS: usize = 256;
+
+/// How long an event may wait in the queue before it is written
+pub const BATCH_MAX_MILLIS: u64 = 250;
+
+// Events waiting to be written together
+#[derive(Debug, Default)]
+pub(super) struct EventBatch {
+    events: Vec<Event>,
+
+    // When the oldest of them was queued
+    since: Option<Instant>,
+}
+
+impl EventBatch {
+    fn get(&self, id: Id) -> Option<&Event> {
+        self.events.iter().find(|e| e.id == id)
+    }
+
+    fn is_due(&self) -> bool {
+        self.events.len() >= BATCH_MAX_EVENTS
+            || self
+                .since
+                .map(|since| since.elapsed() >= Duration::from_millis(BATCH_MAX_MILLIS))
+                .unwrap_or(false)
+    }
+}
+
+// The batch lock is only held briefly and never while taking LMDB's writer lock,
+// so threads holding a write transaction can still look into the queue.
+impl Storage {
+    /// Queue an event received from a relay to be written together with others, in
+    /// one transaction, within [BATCH_MAX_MILLIS]. This saves a commit per event
+    /// while a flood of them comes in. Our own events are written right away.
+    ///
+    /// Until then [read_event](Storage::read_event) and
+    /// [has_event](Storage::has_event) find it in the queue, and the queue is
+    /// written out before any other read of events.
+    pub fn queue_event(&self, event: &Event) -> Result<(), Error> {
+        if self.read_setting_public_key() == Some(event.pubkey) {
+            return self.write_event(event, None);
+        }
+
+        let due = {
+            let mut batch = self.event_batch.lock();
+            if batch.get(event.id).is_none() {
+                batch.events.push(event.clone());
+                batch.since.get_or_insert_with(Instant::now);
+            }
+            batch.is_due()
+        };
+
+        if due && !writing_on_this_thread() {
+            self.flush_event_batch()?;
+        }
+        Ok(())
+    }
+
+    /// Write out the queued events in one transaction. If that fails they are
+    /// written one at a time, and any that cannot be written are dropped, so one bad
+    /// event does not keep the rest (and every read of events) waiting forever.
+    pub fn flush_event_batch(&self) -> Result<(), Error> {
+        self.flush_event_batch_with(|event, txn| self.write_event(event, Some(txn)))
+    }
+
+    // Write out the queued events, each with `write`
+    fn flush_event_batch_with<'a, W>(&'a self, write: W) -> Result<(), Error>
+    where
+        W: Fn(&Event, &mut RwTxn<'a>) -> Result<(), Error>,
+    {
+        if self.event_batch.lock().events.is_empty() {
+            return Ok(());
+        }
+
+        // They stay queued (and found there) until they are committed. Only the
+        // thread holding the writer lock takes them off, so they are still first.
+        let mut txn = self.get_write_txn_as(WriteClass::Bulk)?;
+        let events = self.event_batch.lock().events.clone();
+        let written = events.iter().try_for_each(|event| write(event, &mut txn));
+        match written {
+            Ok(()) => txn.commit()?,
+            Err(e) => {
+                drop(txn);
+                tracing::warn!(
+                    "Writing {} queued events together failed ({}), writing them one at a time",
+                    events.len(),
+                    e
+                );
+                for event in events.iter() {
+                    let mut txn = self.get_write_txn_as(WriteClass::Bulk)?;
+                    match write(event, &mut txn) {
+                        Ok(()) => txn.commit()?,
+                        Err(e) => tracing::error!(
+                            "Dropping queued event {} that cannot be written: {}",
+                            event.id.as_hex_string(),
+                            e
+                        ),
+                    }
+                }
+            }
+        }
+
+        let mut batch = self.event_batch.lock();
+        batch.events.drain(..events.len());
+        batch.since = if batch.events.is_empty() {
+            None
+        } else {
+            Some(Instant::now())
+        };
+        Ok(())
+    }
+
+    /// Write out the queued events if the oldest has waited [BATCH_MAX_MILLIS]
+    pub fn flush_event_batch_if_due(&self) -> Result<(), Error> {
+        if self.event_batch.lock().is_due() {
+            self.flush_event_batch()
+        } else {
+            Ok(())
+        }
+    }
+
+    // Before reading events other than by id, write out the queue so that they are
+    // found. Not while this thread holds a write transaction though, as we could not
+    // take the writer lock; such reads may miss what is queued.
+    pub(super) fn event_read_barrier(&self) -> Result<(), Error> {
+        if writing_on_this_thread() {
+            return Ok(());
+        }
+        self.flush_event_batch()
+    }
+
+    pub(super) fn read_queued_event(&self, id: Id) -> Option<Event> {
+        self.event_batch.lock().get(id).cloned()
+    }
+
+    pub(super) fn is_event_queued(&self, id: Id) -> bool {
+        self.event_batch.lock().get(id).is_some()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::storage::test_dir::TestDir;
+    use nostr_types::{EventKind, PreEvent, PrivateKey, Unixtime};
+
+    fn make_event(key: &PrivateKey, content: &str) -> Event {
+        let pre_event = PreEvent {
+            pubkey: key.public_key(),
+            created_at: Unixtime::now().unwrap(),
+            kind: EventKind::TextNote,
+            tags: vec![],
+            content: content.to_owned(),
+        };
+        let id = pre_event.hash().unwrap();
+        Event {
+            id,
+            pubkey: pre_event.pubkey,
+            created_at: pre_event.created_at,
+            kind: pre_event.kind,
+            sig: key.sign_id(id).unwrap(),
+            content: pre_event.content,
+            tags: pre_event.tags,
+        }
+    }
+
+    #[test]
+    fn test_unwritable_event_is_dropped() {
+        let dir = TestDir::new();
+        let storage = dir.open();
+
+        // Tables cannot be created while a write transaction is open
+        let _ = storage.db_events().unwrap();
+        let _ = storage.db_event_akci_index().unwrap();
+        let _ = storage.db_event_kci_index().unwrap();
+        let _ = storage.db_event_tag_index().unwrap();
+        let _ = storage.db_hashtags().unwrap();
+        let _ = storage.db_event_media().unwrap();
+
+        let key = PrivateKey::generate();
+        let good = make_event(&key, "good");
+        let bad = make_event(&key, "bad");
+        let later = make_event(&key, "later");
+
+        storage.queue_event(&good).unwrap();
+        storage.queue_event(&bad).unwrap();
+        storage
+            .flush_event_batch_with(|event, txn| {
+                if event.id == bad.id {
+                    return Err(ErrorKind::General("unwritable".to_owned()).into());
+                }
+                storage.write_event(event, Some(txn))
+            })
+            .unwrap();
+
+        // The good one was written, the bad one dropped, and the queue is empty
+        assert!(storage.event_batch.lock().events.is_empty());
+        assert!(storage.read_event3(good.id).unwrap().is_some());
+        assert!(storage.read_event3(bad.id).unwrap().is_none());
+
+        // Later events are not held up
+        storage.queue_event(&later).unwrap();
+        storage.event_read_barrier().unwrap();
+        assert!(storage.read_event3(later.id).unwrap().is_some());
+    }
+}
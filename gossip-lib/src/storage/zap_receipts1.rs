@@ -0,0 +1,63 @@
+use crate::error::Error;
+use crate::storage::{RawDatabase, Storage};
+use heed::RwTxn;
+use nostr_types::{Id, MilliSatoshi, PublicKey};
+
+// ZappedId:ReceiptId -> (Zapper, Amount, Signer)
+//   key: zapped_id.as_slice() ++ receipt_id.as_slice()
+//   val: zapper.as_bytes() ++ msats.to_be_bytes() ++ signer.as_bytes()
+//
+// Only receipts that passed validation (see crate::zaps) are kept. The zapper is
+// the author of the embedded zap request, the signer that of the receipt. Records
+// from before the signer was kept (40 bytes) were cleared by migration 52.
+
+impl Storage {
+    pub(super) fn db_zap_receipts1(&self) -> Result<RawDatabase, Error> {
+        self.open_database("zap_receipts1")
+    }
+
+    pub(crate) fn write_zap_receipt1<'a>(
+        &'a self,
+        zapped: Id,
+        receipt: Id,
+        zapper: PublicKey,
+        amount: MilliSatoshi,
+        signer: PublicKey,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let mut key: Vec<u8> = Vec::with_capacity(64);
+        key.extend(zapped.as_slice());
+        key.extend(receipt.as_slice());
+        let mut val: Vec<u8> = Vec::with_capacity(72);
+        val.extend(zapper.as_bytes());
+        val.extend(amount.0.to_be_bytes());
+        val.extend(signer.as_bytes());
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            self.db_zap_receipts1()?.put(txn, &key, &val)?;
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    pub(crate) fn read_zap_receipts1(
+        &self,
+        zapped: Id,
+    ) -> Result<Vec<(Id, PublicKey, MilliSatoshi, PublicKey)>, Error> {
+        let mut output: Vec<(Id, PublicKey, MilliSatoshi, PublicKey)> = Vec::new();
+        for (key, val) in
+            self.prefix_records(self.db_zap_receipts1()?, zapped.as_slice(), |_, _| true)?
+        {
+            if key.len() < 64 || val.len() < 72 {
+                continue;
+            }
+            let receipt = Id(key[32..64].try_into()?);
+            let zapper = PublicKey::from_bytes(&val[..32], true)?;
+            let msats = u64::from_be_bytes(val[32..40].try_into()?);
+            let signer = PublicKey::from_bytes(&val[40..72], true)?;
+            output.push((receipt, zapper, MilliSatoshi(msats), signer));
+        }
+        Ok(output)
+    }
+}
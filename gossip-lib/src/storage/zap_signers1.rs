@@ -0,0 +1,68 @@
+use crate::error::{Error, ErrorKind};
+use crate::storage::{CorruptRecords, RawDatabase, Storage};
+use heed::RwTxn;
+use nostr_types::{PublicKey, Unixtime};
+
+// Recipient -> (LookedUpAt, Signer)
+//   key: recipient.as_bytes()
+//   val: looked_up_at.to_be_bytes() ++ [signer.as_bytes()]
+//
+// The key the lightning service of a zap recipient signs their zap receipts with
+// (the `nostrPubkey` of its LNURL pay endpoint), and when we looked it up. The
+// signer is missing if they have no such service, or it does not do zaps.
+
+impl Storage {
+    pub(super) fn db_zap_signers1(&self) -> Result<RawDatabase, Error> {
+        self.open_database("zap_signers1")
+    }
+
+    pub(crate) fn write_zap_signer1<'a>(
+        &'a self,
+        recipient: PublicKey,
+        signer: Option<PublicKey>,
+        looked_up_at: Unixtime,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let mut val: Vec<u8> = Vec::with_capacity(40);
+        val.extend(looked_up_at.0.to_be_bytes());
+        if let Some(signer) = signer {
+            val.extend(signer.as_bytes());
+        }
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            self.db_zap_signers1()?
+                .put(txn, recipient.as_bytes(), &val)?;
+            Ok(())
+        };
+
+        write_transact!(self, rw_txn, f)
+    }
+
+    pub(crate) fn read_zap_signer1(
+        &self,
+        recipient: PublicKey,
+    ) -> Result<Option<(Unixtime, Option<PublicKey>)>, Error> {
+        let db = self.db_zap_signers1()?;
+        let mut corrupt = CorruptRecords::new("zap_signers1");
+        let txn = self.env.read_txn()?;
+        let output = match db.get(&txn, recipient.as_bytes())? {
+            Some(val) => corrupt.check(recipient.as_bytes(), val, decode(val)),
+            None => None,
+        };
+        drop(txn);
+        self.quarantine_corrupt(db, corrupt, None)?;
+        Ok(output)
+    }
+}
+
+fn decode(bytes: &[u8]) -> Result<(Unixtime, Option<PublicKey>), Error> {
+    if bytes.len() != 8 && bytes.len() != 40 {
+        return Err(ErrorKind::General("zap signer record has the wrong length".to_owned()).into());
+    }
+    let looked_up_at = Unixtime(i64::from_be_bytes(bytes[..8].try_into()?));
+    let signer = match bytes.get(8..40) {
+        Some(signer) => Some(PublicKey::from_bytes(signer, true)?),
+        None => None,
+    };
+    Ok((looked_up_at, signer))
+}
@@ -1,5 +1,6 @@
+use crate::globals::GLOBALS;
 use crate::relay::Relay;
-use nostr_types::{EventAddr, Id, PublicKey, Tag, UncheckedUrl};
+use nostr_types::{EventAddr, Id, PublicKey, RelayUrl, RelayUsage, Tag, UncheckedUrl};
 
 pub async fn add_pubkey_to_tags(existing_tags: &mut Vec<Tag>, added: PublicKey) -> usize {
     let newtag = Tag::new_pubkey(added, None, None);
@@ -100,6 +101,183 @@ pub fn add_subject_to_tags_if_missing(existing_tags: &mut Vec<Tag>, subject: Str
     }
 }
 
+/// Validate and repair the relay hints in 'e', 'q' and 'p' tags of an outgoing event.
+///
+/// Hints that don't parse, that point to private relays (localhost, LAN, .onion), or
+/// that point to relays we know to be dead are replaced with a better hint from where
+/// we have seen the event (or from the person's outbox relays), or are removed if no
+/// good replacement exists. The other fields of the tag (marker, pubkey, petname)
+/// are kept.
+pub async fn repair_relay_hints(tags: &mut [Tag]) {
+    for tag in tags.iter_mut() {
+        if let Ok((id, hint, _marker)) = tag.parse_event() {
+            if hint.as_ref().map(bad_hint).unwrap_or(true) {
+                *tag = with_hint(tag, best_event_hint(id).await);
+            }
+        } else if let Ok((id, hint)) = tag.parse_quote() {
+            if hint.as_ref().map(bad_hint).unwrap_or(true) {
+                *tag = with_hint(tag, best_event_hint(id).await);
+            }
+        } else if let Ok((pubkey, hint, _petname)) = tag.parse_pubkey() {
+            if let Some(ref h) = hint {
+                if bad_hint(h) {
+                    *tag = with_hint(tag, best_pubkey_hint(pubkey));
+                }
+            }
+        }
+    }
+}
+
+// The tag with its relay hint (the third field) replaced and every other field kept.
+// Trailing empty fields are dropped.
+fn with_hint(tag: &Tag, hint: Option<UncheckedUrl>) -> Tag {
+    // More than any 'e', 'q' or 'p' tag has
+    const MAX_FIELDS: usize = 8;
+
+    let hint = hint.map(|h| h.0).unwrap_or_default();
+    let mut fields: Vec<&str> = (0..MAX_FIELDS)
+        .map(|i| {
+            if i == 2 {
+                hint.as_str()
+            } else {
+                tag.get_index(i)
+            }
+        })
+        .collect();
+    while fields.len() > 2 && fields.last() == Some(&"") {
+        fields.pop();
+    }
+    Tag::new(&fields)
+}
+
+/// If this relay is not reachable by other people
+pub fn is_private_relay(url: &RelayUrl) -> bool {
+    let host = match url::Url::parse(url.as_str()) {
+        Ok(u) => match u.host() {
+            Some(host) => host.to_owned(),
+            None => return true,
+        },
+        Err(_) => return true,
+    };
+
+    match host {
+        url::Host::Domain(domain) => {
+            let domain = domain.to_lowercase();
+            domain == "localhost"
+                || domain.ends_with(".localhost")
+                || domain.ends_with(".onion")
+                || domain.ends_with(".local")
+        }
+        url::Host::Ipv4(ip) => {
+            ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified()
+        }
+        url::Host::Ipv6(ip) => {
+            let first = ip.segments()[0];
+            ip.is_loopback()
+                || ip.is_unspecified()
+                || (first & 0xfe00) == 0xfc00 // unique local, fc00::/7
+                || (first & 0xffc0) == 0xfe80 // link-local, fe80::/10
+        }
+    }
+}
+
+/// If we should not publish this relay hint
+fn bad_hint(hint: &UncheckedUrl) -> bool {
+    match RelayUrl::try_from_unchecked_url(hint) {
+        Ok(url) => is_private_relay(&url) || is_dead_relay(&url),
+        Err(_) => true,
+    }
+}
+
+/// A relay we keep failing to connect to and have never connected to
+fn is_dead_relay(url: &RelayUrl) -> bool {
+    match GLOBALS.storage.read_relay(url, None) {
+        Ok(Some(relay)) => relay.success_count == 0 && relay.failure_count >= 3,
+        _ => false,
+    }
+}
+
+async fn best_event_hint(id: Id) -> Option<UncheckedUrl> {
+    if let Ok(seen_on) = GLOBALS.storage.get_event_seen_on_relay(id) {
+        for (url, _) in seen_on.iter() {
+            if !is_private_relay(url) && !is_dead_relay(url) {
+                return Some(url.to_unchecked_url());
+            }
+        }
+    }
+
+    match Relay::recommended_relay_for_reply(id).await {
+        Ok(Some(url)) if !is_private_relay(&url) && !is_dead_relay(&url) => {
+            Some(url.to_unchecked_url())
+        }
+        _ => None,
+    }
+}
+
+fn best_pubkey_hint(pubkey: PublicKey) -> Option<UncheckedUrl> {
+    let relays = GLOBALS
+        .storage
+        .get_best_relays(pubkey, RelayUsage::Outbox)
+        .ok()?;
+    relays
+        .iter()
+        .find(|(url, _)| !is_private_relay(url) && !is_dead_relay(url))
+        .map(|(url, _)| url.to_unchecked_url())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_is_private_relay() {
+        let private = |url: &str| is_private_relay(&RelayUrl::try_from_str(url).unwrap());
+        for url in [
+            "ws://localhost:8080",
+            "ws://127.0.0.1",
+            "ws://10.1.2.3",
+            "ws://172.20.0.1",
+            "ws://192.168.1.1",
+            "ws://169.254.10.10",
+            "ws://[::1]",
+            "ws://[fc00::1]",
+            "ws://[fd12:3456::1]",
+            "ws://[fe80::1]",
+            "ws://abcdefghijklmnop.onion",
+        ] {
+            assert!(private(url), "{}", url);
+        }
+        for url in [
+            "wss://relay.example.com",
+            "wss://172.32.0.1",
+            "wss://8.8.8.8",
+            "wss://[2001:db8::1]",
+        ] {
+            assert!(!private(url), "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_with_hint() {
+        let id = "0000000000000000000000000000000000000000000000000000000000000001";
+        let pubkey = "0000000000000000000000000000000000000000000000000000000000000002";
+        let hint = Some(UncheckedUrl("wss://relay.example.com/".to_owned()));
+
+        let tag = Tag::new(&["e", id, "ws://localhost/", "reply", pubkey]);
+        assert_eq!(
+            with_hint(&tag, hint.clone()),
+            Tag::new(&["e", id, "wss://relay.example.com/", "reply", pubkey])
+        );
+        assert_eq!(
+            with_hint(&tag, None),
+            Tag::new(&["e", id, "", "reply", pubkey])
+        );
+
+        let tag = Tag::new(&["p", pubkey, "ws://localhost/"]);
+        assert_eq!(with_hint(&tag, None), Tag::new(&["p", pubkey]));
+    }
+}
+
 //#[cfg(test)]
 // mod test {
 //     use super::*;
@@ -0,0 +1,142 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use nostr_types::{Event, EventReference, Id, Unixtime};
+use std::collections::HashSet;
+
+/// How a thread (conversation) should be presented
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThreadLayout {
+    /// The full reply tree from the highest local ancestor, depth first, with
+    /// siblings in chronological order
+    Tree,
+
+    /// Every note in the thread, oldest first, without nesting
+    Chronological,
+
+    /// The ancestors of the focused note, the focused note, and the reply tree
+    /// beneath the focused note. Sibling branches are left out.
+    FocusPath,
+}
+
+/// One note within a thread presentation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreadEntry {
+    pub id: Id,
+
+    /// Nesting depth for display (always 0 for `ThreadLayout::Chronological`)
+    pub depth: usize,
+
+    pub created_at: Unixtime,
+
+    /// If this is the note the thread was opened on
+    pub focused: bool,
+}
+
+/// Compute a presentation of the thread containing `focus` from local storage.
+///
+/// The result is an ordered list of entries to render top to bottom. Notes we do not
+/// have locally are not included (the Overlord fetches them as the thread is viewed).
+pub fn thread_view(focus: Id, layout: ThreadLayout) -> Result<Vec<ThreadEntry>, Error> {
+    let focus_event = match GLOBALS.storage.read_event(focus)? {
+        Some(e) => e,
+        None => return Ok(vec![]),
+    };
+
+    let mut output: Vec<ThreadEntry> = Vec::new();
+    let mut visited: HashSet<Id> = HashSet::new();
+
+    match layout {
+        ThreadLayout::Tree => {
+            let top = local_ancestors(&focus_event)?
+                .into_iter()
+                .next()
+                .unwrap_or(focus_event);
+            push_tree(&top, 0, focus, &mut output, &mut visited)?;
+        }
+        ThreadLayout::Chronological => {
+            let top = local_ancestors(&focus_event)?
+                .into_iter()
+                .next()
+                .unwrap_or(focus_event);
+            push_tree(&top, 0, focus, &mut output, &mut visited)?;
+            for entry in output.iter_mut() {
+                entry.depth = 0;
+            }
+            output.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+        }
+        ThreadLayout::FocusPath => {
+            let ancestors = local_ancestors(&focus_event)?;
+            let depth = ancestors.len();
+            for (i, ancestor) in ancestors.iter().enumerate() {
+                visited.insert(ancestor.id);
+                output.push(ThreadEntry {
+                    id: ancestor.id,
+                    depth: i,
+                    created_at: ancestor.created_at,
+                    focused: false,
+                });
+            }
+            push_tree(&focus_event, depth, focus, &mut output, &mut visited)?;
+        }
+    }
+
+    Ok(output)
+}
+
+/// Ancestors of the event that we have locally, highest first, not including the event
+fn local_ancestors(event: &Event) -> Result<Vec<Event>, Error> {
+    let mut ancestors: Vec<Event> = Vec::new();
+    let mut seen: HashSet<Id> = HashSet::new();
+    seen.insert(event.id);
+
+    let mut parent: Option<EventReference> = crate::nip10::replies_to(event);
+    while let Some(eref) = parent {
+        match GLOBALS.storage.read_event_reference(&eref)? {
+            Some(parent_event) => {
+                // guard against reply loops
+                if !seen.insert(parent_event.id) {
+                    break;
+                }
+                parent = crate::nip10::replies_to(&parent_event);
+                ancestors.push(parent_event);
+            }
+            None => break,
+        }
+    }
+
+    ancestors.reverse();
+    Ok(ancestors)
+}
+
+fn push_tree(
+    event: &Event,
+    depth: usize,
+    focus: Id,
+    output: &mut Vec<ThreadEntry>,
+    visited: &mut HashSet<Id>,
+) -> Result<(), Error> {
+    if !visited.insert(event.id) {
+        return Ok(());
+    }
+
+    output.push(ThreadEntry {
+        id: event.id,
+        depth,
+        created_at: event.created_at,
+        focused: event.id == focus,
+    });
+
+    let mut replies: Vec<Event> = Vec::new();
+    for id in GLOBALS.storage.get_replies(event)? {
+        if let Some(reply) = GLOBALS.storage.read_event(id)? {
+            replies.push(reply);
+        }
+    }
+    replies.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+
+    for reply in replies.iter() {
+        push_tree(reply, depth + 1, focus, output, visited)?;
+    }
+
+    Ok(())
+}
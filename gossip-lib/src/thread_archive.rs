@@ -0,0 +1,143 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::thread::{thread_view, ThreadLayout};
+use nostr_types::{Event, Id, RelayUrl, Unixtime};
+
+/// One event of an archived thread
+#[derive(Debug, Clone)]
+pub struct ArchivedEvent {
+    /// The event as signed
+    pub event: Event,
+
+    /// Reply depth within the thread (0 for the highest ancestor we have)
+    pub depth: usize,
+
+    /// If its id and signature check out
+    pub verified: bool,
+
+    /// The relays we saw it on, and when we first did
+    pub seen_on: Vec<(RelayUrl, Unixtime)>,
+}
+
+/// Every event we have of a thread, with where each came from, for keeping as a
+/// record of a conversation
+#[derive(Debug, Clone)]
+pub struct ThreadArchive {
+    /// The event the thread was archived from
+    pub focus: Id,
+
+    pub archived_at: Unixtime,
+
+    /// In reply tree order, depth first
+    pub events: Vec<ArchivedEvent>,
+}
+
+/// Capture the thread containing `focus`: all of its stored ancestors and
+/// descendants. Events we don't have are not fetched.
+pub fn archive_thread(focus: Id) -> Result<ThreadArchive, Error> {
+    let mut events: Vec<ArchivedEvent> = Vec::new();
+    for entry in thread_view(focus, ThreadLayout::Tree)? {
+        let event = match GLOBALS.storage.read_event(entry.id)? {
+            Some(event) => event,
+            None => continue,
+        };
+        let verified = event.verify(None).is_ok();
+        let mut seen_on = GLOBALS.storage.get_event_seen_on_relay(entry.id)?;
+        seen_on.sort_by(|a, b| a.1.cmp(&b.1));
+        events.push(ArchivedEvent {
+            event,
+            depth: entry.depth,
+            verified,
+            seen_on,
+        });
+    }
+
+    Ok(ThreadArchive {
+        focus,
+        archived_at: Unixtime::now().unwrap(),
+        events,
+    })
+}
+
+impl ThreadArchive {
+    /// The archive as JSON, with each event exactly as signed, so that its signature
+    /// can be checked by anyone
+    pub fn to_json(&self) -> Result<String, Error> {
+        let events: Vec<serde_json::Value> = self
+            .events
+            .iter()
+            .map(|archived| {
+                let seen_on: Vec<serde_json::Value> = archived
+                    .seen_on
+                    .iter()
+                    .map(|(url, when)| serde_json::json!({ "relay": url.as_str(), "at": when.0 }))
+                    .collect();
+                Ok(serde_json::json!({
+                    "event": serde_json::to_value(&archived.event)?,
+                    "depth": archived.depth,
+                    "verified": archived.verified,
+                    "seen_on": seen_on,
+                }))
+            })
+            .collect::<Result<_, Error>>()?;
+
+        Ok(serde_json::to_string_pretty(&serde_json::json!({
+            "focus": self.focus.as_hex_string(),
+            "archived_at": self.archived_at.0,
+            "events": events,
+        }))?)
+    }
+
+    /// A plain text transcript of the thread, indented by reply depth
+    pub fn transcript(&self) -> String {
+        let mut output = format!(
+            "Thread of {}\nArchived {}, {} events\n",
+            self.focus.as_bech32_string(),
+            format_time(self.archived_at),
+            self.events.len()
+        );
+
+        for archived in self.events.iter() {
+            let event = &archived.event;
+            let indent = "    ".repeat(archived.depth);
+            output.push('\n');
+            output.push_str(&format!(
+                "{}{} {} ({})\n",
+                indent,
+                format_time(event.created_at),
+                crate::names::best_name_from_pubkey_lookup(&event.pubkey),
+                event.pubkey.as_bech32_string()
+            ));
+            output.push_str(&format!(
+                "{}{}{}\n",
+                indent,
+                event.id.as_bech32_string(),
+                if archived.verified {
+                    ""
+                } else {
+                    " (SIGNATURE DOES NOT VERIFY)"
+                }
+            ));
+            for (url, when) in archived.seen_on.iter() {
+                output.push_str(&format!(
+                    "{}  seen on {} at {}\n",
+                    indent,
+                    url,
+                    format_time(*when)
+                ));
+            }
+            for line in event.content.lines() {
+                output.push_str(&format!("{}| {}\n", indent, line));
+            }
+        }
+
+        output
+    }
+}
+
+fn format_time(when: Unixtime) -> String {
+    match chrono::DateTime::from_timestamp(when.0, 0) {
+        Some(dt) => dt.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+        None => when.0.to_string(),
+    }
+}
@@ -0,0 +1,189 @@
+use crate::blocked_relays::is_relay_blocked;
+use crate::comms::ToOverlordMessage;
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::relay::Relay;
+use nostr_types::{Event, Id, RelayUrl, Unixtime};
+use std::time::Duration;
+
+// How long relays get to accept a post before we try alternates
+const CHECK_AFTER_SECS: u64 = 20;
+
+// Rounds of alternates tried before we give up
+const MAX_ROUNDS: u8 = 3;
+
+// Reports are kept for this many recent posts
+const MAX_REPORTS: usize = 50;
+
+/// How many distinct relays accepted one of our posts, against the
+/// `write_redundancy_target` setting
+#[derive(Debug, Clone)]
+pub struct RedundancyReport {
+    /// The post (its first event, if it was split)
+    pub id: Id,
+
+    pub target: usize,
+
+    /// Relays we sent it to, in order
+    pub tried: Vec<RelayUrl>,
+
+    /// Relays that accepted it
+    pub reached: Vec<RelayUrl>,
+
+    /// Rounds of alternate relays tried
+    pub rounds: u8,
+
+    /// None while we are still trying
+    pub finished: Option<Unixtime>,
+
+    // What goes to alternate relays
+    events: Vec<Event>,
+}
+
+/// Redundancy reports of recent posts, newest first
+pub fn reports() -> Vec<RedundancyReport> {
+    GLOBALS.write_redundancy.read().clone()
+}
+
+/// The redundancy report of this post, if it is recent
+pub fn report(id: Id) -> Option<RedundancyReport> {
+    GLOBALS
+        .write_redundancy
+        .read()
+        .iter()
+        .find(|r| r.id == id)
+        .cloned()
+}
+
+/// The relays of the `fallback_write_relays` setting, which are tried when our own
+/// write relays do not get a post to enough relays.
+pub fn fallback_relays() -> Vec<RelayUrl> {
+    GLOBALS
+        .storage
+        .read_setting_fallback_write_relays()
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|s| !s.is_empty())
+        .filter_map(|s| RelayUrl::try_from_str(s).ok())
+        .collect()
+}
+
+// Start tracking a post that was just sent to `tried`. `events` is what alternate
+// relays will get.
+pub(crate) fn track(events: Vec<Event>, tried: Vec<RelayUrl>) {
+    let target = GLOBALS.storage.read_setting_write_redundancy_target() as usize;
+    if target == 0 || events.is_empty() {
+        return;
+    }
+
+    let id = events[0].id;
+    let mut reports = GLOBALS.write_redundancy.write();
+    reports.insert(
+        0,
+        RedundancyReport {
+            id,
+            target,
+            tried,
+            reached: vec![],
+            rounds: 0,
+            finished: None,
+            events,
+        },
+    );
+    reports.truncate(MAX_REPORTS);
+    drop(reports);
+
+    schedule_check(id);
+}
+
+pub(crate) fn schedule_check(id: Id) {
+    std::mem::drop(tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(CHECK_AFTER_SECS)).await;
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::CheckWriteRedundancy(id));
+    }));
+}
+
+// Count the relays that accepted the post. If that is short of the target, pick
+// alternates (our other write relays, then the fallback pool) and return them with
+// the events to send. Returns None once the target is met or we have run out.
+pub(crate) fn next_relays(id: Id) -> Result<Option<(Vec<Event>, Vec<RelayUrl>)>, Error> {
+    let mut reports = GLOBALS.write_redundancy.write();
+    let report = match reports.iter_mut().find(|r| r.id == id) {
+        Some(report) => report,
+        None => return Ok(None),
+    };
+    if report.finished.is_some() {
+        return Ok(None);
+    }
+
+    let mut reached: Vec<RelayUrl> = Vec::new();
+    for (url, _) in GLOBALS
+        .storage
+        .get_event_seen_on_relay(report.id)?
+        .drain(..)
+    {
+        if !reached.contains(&url) {
+            reached.push(url);
+        }
+    }
+    report.reached = reached;
+
+    if report.reached.len() >= report.target || report.rounds >= MAX_ROUNDS {
+        finish(report);
+        return Ok(None);
+    }
+
+    let needed = report.target - report.reached.len();
+    let mut candidates: Vec<RelayUrl> = GLOBALS
+        .storage
+        .filter_relays(|r| r.has_usage_bits(Relay::WRITE) && r.rank != 0)?
+        .drain(..)
+        .map(|r| r.url)
+        .collect();
+    candidates.extend(fallback_relays());
+    let mut alternates: Vec<RelayUrl> = Vec::new();
+    for url in candidates {
+        if alternates.len() >= needed {
+            break;
+        }
+        if report.tried.contains(&url) || alternates.contains(&url) || is_relay_blocked(&url) {
+            continue;
+        }
+        alternates.push(url);
+    }
+
+    if alternates.is_empty() {
+        finish(report);
+        return Ok(None);
+    }
+
+    report.tried.extend(alternates.iter().cloned());
+    report.rounds += 1;
+    Ok(Some((report.events.clone(), alternates)))
+}
+
+fn finish(report: &mut RedundancyReport) {
+    report.finished = Some(Unixtime::now().unwrap());
+    let reached = report.reached.len();
+    let message = if reached >= report.target {
+        tracing::info!(
+            "Post {} reached {} relays",
+            report.id.as_hex_string(),
+            reached
+        );
+        format!("Your post reached {} relays", reached)
+    } else {
+        tracing::warn!(
+            "Post {} reached only {} of {} relays",
+            report.id.as_hex_string(),
+            reached,
+            report.target
+        );
+        format!(
+            "Your post reached only {} of {} relays",
+            reached, report.target
+        )
+    };
+    GLOBALS.status_queue.write().write(message);
+}
@@ -0,0 +1,104 @@
+use crate::globals::GLOBALS;
+use nostr_types::{EventKind, RelayUrl};
+use rand::seq::SliceRandom;
+use std::collections::HashMap;
+use std::sync::Once;
+
+// Regular events go to at least this many of our write relays
+const MIN_ROTATED_RELAYS: usize = 2;
+
+// The counts are kept in the app data store under this namespace and key, so the
+// rotation carries on where it was after a restart
+const NAMESPACE: &str = crate::storage::LIB_NAMESPACE;
+const COUNTS_KEY: &str = "write_rotation";
+
+static LOAD: Once = Once::new();
+
+// Read the saved counts into GLOBALS.write_rotation, the first time only
+fn load() {
+    LOAD.call_once(|| {
+        let saved = GLOBALS
+            .storage
+            .app_data(NAMESPACE)
+            .and_then(|data| data.get::<HashMap<String, u64>>(COUNTS_KEY));
+        match saved {
+            Ok(Some(counts)) => {
+                for (url, count) in counts {
+                    if let Ok(url) = RelayUrl::try_from_str(&url) {
+                        GLOBALS.write_rotation.insert(url, count);
+                    }
+                }
+            }
+            Ok(None) => (),
+            Err(e) => tracing::error!("Could not read the write relay rotation: {}", e),
+        }
+    });
+}
+
+fn save() {
+    let counts: HashMap<String, u64> = GLOBALS
+        .write_rotation
+        .iter()
+        .map(|entry| (entry.key().as_str().to_owned(), *entry.value()))
+        .collect();
+    if let Err(e) = GLOBALS
+        .storage
+        .app_data(NAMESPACE)
+        .and_then(|data| data.put(COUNTS_KEY, &counts))
+    {
+        tracing::error!("Could not save the write relay rotation: {}", e);
+    }
+}
+
+/// Which of our write relays get an event of this kind.
+///
+/// With the `write_relay_rotation` setting on, each regular event goes to only some
+/// of them, as many as the `write_redundancy_target` setting asks for (but at least
+/// two), so that no one write relay sees everything we post. Those that got the
+/// fewest so far are picked first, ties at random. Replaceable events always go to
+/// all of them, so each one has the latest.
+pub fn rotate(kind: EventKind, write_relays: Vec<RelayUrl>) -> Vec<RelayUrl> {
+    if !GLOBALS.storage.read_setting_write_relay_rotation() || kind.is_replaceable() {
+        return write_relays;
+    }
+
+    let count =
+        (GLOBALS.storage.read_setting_write_redundancy_target() as usize).max(MIN_ROTATED_RELAYS);
+    if write_relays.len() <= count {
+        return write_relays;
+    }
+
+    load();
+
+    // A relay new to the rotation starts level with the others, rather than getting
+    // every event until it catches up
+    let floor = write_relays
+        .iter()
+        .filter_map(|url| GLOBALS.write_rotation.get(url).map(|n| *n))
+        .min()
+        .unwrap_or(0);
+
+    let mut relays = write_relays;
+    relays.shuffle(&mut rand::thread_rng());
+    relays.sort_by_key(|url| GLOBALS.write_rotation.get(url).map(|n| *n).unwrap_or(floor));
+    relays.truncate(count);
+
+    for url in relays.iter() {
+        *GLOBALS.write_rotation.entry(url.clone()).or_insert(floor) += 1;
+    }
+    save();
+
+    relays
+}
+
+/// How many rotated events each write relay got
+pub fn rotation_counts() -> Vec<(RelayUrl, u64)> {
+    load();
+    let mut counts: Vec<(RelayUrl, u64)> = GLOBALS
+        .write_rotation
+        .iter()
+        .map(|entry| (entry.key().clone(), *entry.value()))
+        .collect();
+    counts.sort();
+    counts
+}
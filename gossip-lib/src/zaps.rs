@@ -0,0 +1,297 @@
+use crate::comms::ToOverlordMessage;
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use dashmap::DashMap;
+use nostr_types::{Event, EventKind, Id, MilliSatoshi, PublicKey, UncheckedUrl, Unixtime, Url};
+use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+// How long the key that signs someone's zap receipts is kept before it is looked
+// up again
+const ZAP_SIGNER_SECS: i64 = 60 * 60 * 24;
+
+// How long after starting to look up someone's zap signer we may try again, if that
+// lookup failed (or is still going)
+const ZAP_SIGNER_RETRY_SECS: u64 = 60 * 10;
+
+lazy_static! {
+    // Recipients whose zap signer we started looking up, and when
+    static ref SIGNER_LOOKUPS: DashMap<PublicKey, Instant> = DashMap::new();
+}
+
+/// A zap receipt (kind 9735) that passed [validate_zap_receipt]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZapReceipt {
+    /// The event that was zapped
+    pub zapped: Id,
+
+    /// The receipt event
+    pub receipt: Id,
+
+    /// Who zapped: the author of the zap request, not of the receipt (which is
+    /// their lightning service)
+    pub zapper: PublicKey,
+
+    /// The amount of the paid invoice
+    pub amount: MilliSatoshi,
+
+    /// Who signed the receipt. Only receipts signed by the recipient's lightning
+    /// service count, see [verified_zap_receipts]
+    pub signer: PublicKey,
+}
+
+/// Check a zap receipt. It must embed (in its description tag) a signed zap request
+/// for the same event and recipient, and if the request asked for an amount, the
+/// invoice paid must be for exactly that amount.
+///
+/// Anyone can sign a receipt, so this doesn't say the zap was paid. That takes the
+/// receipt's signer to be the recipient's lightning service, which
+/// [verified_zap_receipts] checks.
+pub fn validate_zap_receipt(event: &Event) -> Option<ZapReceipt> {
+    if event.kind != EventKind::Zap {
+        return None;
+    }
+    let zapdata = match event.zaps() {
+        Ok(Some(zapdata)) => zapdata,
+        _ => return None,
+    };
+    let description = event.tags.iter().find(|t| t.tagname() == "description")?;
+    let request: Event = serde_json::from_str(description.value()).ok()?;
+    if !zap_request_matches(&request, zapdata.id, zapdata.amount)
+        || recipient(&request) != recipient(event)
+    {
+        return None;
+    }
+    Some(ZapReceipt {
+        zapped: zapdata.id,
+        receipt: event.id,
+        zapper: request.pubkey,
+        amount: zapdata.amount,
+        signer: event.pubkey,
+    })
+}
+
+/// The zap receipts of `zapped` signed by the lightning service of `recipient` (the
+/// `nostrPubkey` of their LNURL pay endpoint). Until we have looked that key up,
+/// there are none.
+pub fn verified_zap_receipts(zapped: Id, recipient: PublicKey) -> Result<Vec<ZapReceipt>, Error> {
+    let signer = match zap_signer(recipient)? {
+        Some(signer) => signer,
+        None => return Ok(vec![]),
+    };
+    Ok(GLOBALS
+        .storage
+        .read_zap_receipts(zapped)?
+        .drain(..)
+        .filter(|zap| zap.signer == signer)
+        .collect())
+}
+
+/// The total of the verified zaps of `zapped`, an event by `recipient`
+pub fn zap_total(zapped: Id, recipient: PublicKey) -> Result<MilliSatoshi, Error> {
+    let mut total = MilliSatoshi(0);
+    for zap in verified_zap_receipts(zapped, recipient)? {
+        total = total + zap.amount;
+    }
+    Ok(total)
+}
+
+/// Who zapped `zapped`, an event by `recipient`, and how much in all, most first.
+/// Only verified zaps count.
+pub fn zappers(zapped: Id, recipient: PublicKey) -> Result<Vec<(PublicKey, MilliSatoshi)>, Error> {
+    let mut by_zapper: HashMap<PublicKey, MilliSatoshi> = HashMap::new();
+    for zap in verified_zap_receipts(zapped, recipient)? {
+        let sum = by_zapper.entry(zap.zapper).or_insert(MilliSatoshi(0));
+        *sum = *sum + zap.amount;
+    }
+    let mut output: Vec<(PublicKey, MilliSatoshi)> = by_zapper.drain().collect();
+    output.sort_by(|a, b| b.1 .0.cmp(&a.1 .0));
+    Ok(output)
+}
+
+/// The key that signs zap receipts for `recipient`, as far as we know. If we
+/// haven't looked it up lately, the overlord is asked to, and the UI is refreshed
+/// once it has.
+pub fn zap_signer(recipient: PublicKey) -> Result<Option<PublicKey>, Error> {
+    let (looked_up_at, signer) = GLOBALS
+        .storage
+        .read_zap_signer(recipient)?
+        .unwrap_or((Unixtime(0), None));
+
+    if Unixtime::now().0 - looked_up_at.0 > ZAP_SIGNER_SECS
+        && !GLOBALS.storage.read_setting_offline()
+    {
+        let retry = Duration::from_secs(ZAP_SIGNER_RETRY_SECS);
+        let started = match SIGNER_LOOKUPS.get(&recipient) {
+            Some(started) => started.elapsed() < retry,
+            None => false,
+        };
+        if !started {
+            SIGNER_LOOKUPS.insert(recipient, Instant::now());
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::FetchZapSigner(recipient));
+        }
+    }
+
+    Ok(signer)
+}
+
+/// Look up the key that signs zap receipts for `recipient` from their LNURL pay
+/// endpoint, and remember it. If they have none, or it does not do zaps, that is
+/// remembered too.
+pub(crate) async fn fetch_zap_signer(recipient: PublicKey) -> Result<(), Error> {
+    let lnurl = match GLOBALS.storage.read_person(&recipient, None)? {
+        Some(person) => person.metadata.as_ref().and_then(|m| m.lnurl()),
+        None => None,
+    };
+
+    let signer = match lnurl {
+        Some(lnurl) => {
+            let url = Url::try_from_unchecked_url(&UncheckedUrl(lnurl))?;
+            let client = reqwest::Client::builder()
+                .timeout(std::time::Duration::new(15, 0))
+                .gzip(true)
+                .brotli(true)
+                .deflate(true)
+                .build()?;
+            let text = client.get(url.as_str()).send().await?.text().await?;
+            nostr_pubkey_of(&text)
+        }
+        None => None,
+    };
+
+    remember_zap_signer(recipient, signer)
+}
+
+/// Remember the key that signs zap receipts for `recipient`, given the text of the
+/// LNURL pay request data of their lightning service
+pub(crate) fn remember_zap_signer_from(recipient: PublicKey, text: &str) -> Result<(), Error> {
+    remember_zap_signer(recipient, nostr_pubkey_of(text))
+}
+
+fn remember_zap_signer(recipient: PublicKey, signer: Option<PublicKey>) -> Result<(), Error> {
+    let previous = GLOBALS
+        .storage
+        .read_zap_signer(recipient)?
+        .and_then(|(_, signer)| signer);
+    GLOBALS
+        .storage
+        .write_zap_signer(recipient, signer, Unixtime::now(), None)?;
+    SIGNER_LOOKUPS.remove(&recipient);
+    if signer != previous {
+        GLOBALS.ui_invalidate_all.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
+
+// The `nostrPubkey` of LNURL pay request data, if it does zaps
+fn nostr_pubkey_of(text: &str) -> Option<PublicKey> {
+    let value: serde_json::Value = serde_json::from_str(text).ok()?;
+    if value.get("allowsNostr").and_then(|v| v.as_bool()) != Some(true) {
+        return None;
+    }
+    let hex = value.get("nostrPubkey")?.as_str()?;
+    PublicKey::try_from_hex_string(hex, true).ok()
+}
+
+// Who a zap request or receipt is for, from its first "p" tag
+fn recipient(event: &Event) -> Option<PublicKey> {
+    event
+        .tags
+        .iter()
+        .find(|t| t.tagname() == "p")
+        .and_then(|t| PublicKey::try_from_hex_string(t.value(), false).ok())
+}
+
+// Is this a signed zap request for `zapped`, asking for `amount` if it asks for any?
+fn zap_request_matches(request: &Event, zapped: Id, amount: MilliSatoshi) -> bool {
+    if request.kind != EventKind::ZapRequest || request.verify(None).is_err() {
+        return false;
+    }
+    let requested = request
+        .tags
+        .iter()
+        .find(|t| t.tagname() == "e")
+        .and_then(|t| Id::try_from_hex_string(t.value()).ok());
+    if requested != Some(zapped) {
+        return false;
+    }
+    match request.tags.iter().find(|t| t.tagname() == "amount") {
+        Some(tag) => tag.value().parse::<u64>().ok() == Some(amount.0),
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nostr_types::{PreEvent, PrivateKey, Tag, Unixtime};
+
+    fn zap_request(key: &PrivateKey, zapped: Id, msats: &str) -> Event {
+        let pre_event = PreEvent {
+            pubkey: key.public_key(),
+            created_at: Unixtime::now().unwrap(),
+            kind: EventKind::ZapRequest,
+            tags: vec![
+                Tag::new_event(zapped, None, None),
+                Tag::new(&["amount", msats]),
+            ],
+            content: "".to_owned(),
+        };
+        let id = pre_event.hash().unwrap();
+        let sig = key.sign_id(id).unwrap();
+        Event {
+            id,
+            pubkey: pre_event.pubkey,
+            created_at: pre_event.created_at,
+            kind: pre_event.kind,
+            sig,
+            content: pre_event.content,
+            tags: pre_event.tags,
+        }
+    }
+
+    #[test]
+    fn test_zap_request_matches() {
+        let key = PrivateKey::generate();
+        let zapped = Id([7; 32]);
+        let request = zap_request(&key, zapped, "21000");
+
+        assert!(zap_request_matches(&request, zapped, MilliSatoshi(21000)));
+        assert!(!zap_request_matches(&request, zapped, MilliSatoshi(1000)));
+        assert!(!zap_request_matches(
+            &request,
+            Id([8; 32]),
+            MilliSatoshi(21000)
+        ));
+
+        // A tampered request is not signed by its author
+        let mut tampered = request.clone();
+        tampered.content = "changed".to_owned();
+        assert!(!zap_request_matches(&tampered, zapped, MilliSatoshi(21000)));
+    }
+
+    #[test]
+    fn test_nostr_pubkey_of() {
+        let key = PrivateKey::generate().public_key();
+        let text = format!(
+            r#"{{"callback":"https://example.com/cb","allowsNostr":true,"nostrPubkey":"{}"}}"#,
+            key.as_hex_string()
+        );
+        assert_eq!(nostr_pubkey_of(&text), Some(key));
+
+        // Not doing zaps, or saying so with a bad key, is no signer
+        let text = format!(
+            r#"{{"allowsNostr":false,"nostrPubkey":"{}"}}"#,
+            key.as_hex_string()
+        );
+        assert_eq!(nostr_pubkey_of(&text), None);
+        assert_eq!(
+            nostr_pubkey_of(r#"{"allowsNostr":true,"nostrPubkey":"abc"}"#),
+            None
+        );
+        assert_eq!(nostr_pubkey_of("not json"), None);
+    }
+}